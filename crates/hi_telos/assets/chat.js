@@ -0,0 +1,126 @@
+(function() {
+  const ui = window.HiUi;
+  const STORAGE_KEY = 'hi-telos-chat-id';
+  const transcript = document.getElementById('transcript');
+  const form = document.getElementById('chat-form');
+  const chatLabel = document.getElementById('chat-id');
+  let chatId = window.localStorage.getItem(STORAGE_KEY) || '';
+  let busy = false;
+
+  function showChatId() {
+    chatLabel.textContent = chatId ? '(' + chatId + ')' : '';
+  }
+
+  function append(role, text) {
+    if (transcript.querySelector('em')) {
+      ui.clearChildren(transcript);
+    }
+    const entry = document.createElement('div');
+    entry.className = 'turn ' + role;
+    const who = document.createElement('strong');
+    who.textContent = role === 'user' ? '你' : role === 'step' ? '· 思考' : 'Telos';
+    const body = document.createElement('pre');
+    body.textContent = text;
+    entry.appendChild(who);
+    entry.appendChild(body);
+    transcript.appendChild(entry);
+    transcript.scrollTop = transcript.scrollHeight;
+    return body;
+  }
+
+  // EventSource only speaks GET, so the POST response is parsed by hand.
+  function readEvents(response, onEvent) {
+    const reader = response.body.getReader();
+    const decoder = new TextDecoder();
+    let buffer = '';
+    function pump() {
+      return reader.read().then(function(chunk) {
+        if (chunk.done) {
+          return;
+        }
+        buffer += decoder.decode(chunk.value, { stream: true });
+        let boundary = buffer.indexOf('\n\n');
+        while (boundary !== -1) {
+          const raw = buffer.slice(0, boundary);
+          buffer = buffer.slice(boundary + 2);
+          let name = 'message';
+          const data = [];
+          raw.split('\n').forEach(function(line) {
+            if (line.startsWith('event:')) {
+              name = line.slice(6).trim();
+            } else if (line.startsWith('data:')) {
+              data.push(line.slice(5).replace(/^ /, ''));
+            }
+          });
+          if (data.length > 0) {
+            onEvent(name, JSON.parse(data.join('\n')));
+          }
+          boundary = buffer.indexOf('\n\n');
+        }
+        return pump();
+      });
+    }
+    return pump();
+  }
+
+  function send(message) {
+    busy = true;
+    ui.updateStatus('思考中 …');
+    append('user', message);
+    fetch(ui.pageData('chatStream'), {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ message: message, chat_id: chatId || null }),
+    })
+      .then(function(response) {
+        if (!response.ok) {
+          throw new Error('HTTP ' + response.status);
+        }
+        return readEvents(response, function(name, payload) {
+          if (name === 'step') {
+            append('step', payload.thought + ' → ' + payload.action);
+          } else if (name === 'final') {
+            chatId = payload.chat_id;
+            window.localStorage.setItem(STORAGE_KEY, chatId);
+            showChatId();
+            append('assistant', payload.question || payload.final_answer);
+            ui.updateStatus('就绪');
+          } else if (name === 'error') {
+            append('assistant', '⚠ ' + payload.error);
+            ui.updateStatus('运行失败');
+          }
+        });
+      })
+      .catch(function(err) {
+        ui.updateStatus('发送失败：' + err.message);
+      })
+      .then(function() {
+        busy = false;
+      });
+  }
+
+  form.onsubmit = function(event) {
+    event.preventDefault();
+    const field = form.elements.message;
+    const message = field.value.trim();
+    if (!message || busy) {
+      return;
+    }
+    field.value = '';
+    send(message);
+  };
+  form.elements.message.addEventListener('keydown', function(event) {
+    if (event.key === 'Enter' && (event.ctrlKey || event.metaKey)) {
+      form.requestSubmit();
+    }
+  });
+  document.getElementById('new-chat').onclick = function() {
+    chatId = '';
+    window.localStorage.removeItem(STORAGE_KEY);
+    showChatId();
+    ui.clearChildren(transcript);
+    ui.updateStatus('就绪');
+  };
+
+  showChatId();
+})();
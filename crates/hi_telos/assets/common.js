@@ -0,0 +1,138 @@
+// Shared helpers for the /ui pages. Each page script reads its stream URLs
+// from data attributes on <main id="page">, so the templates own the wiring.
+(function() {
+  const status = document.getElementById('status');
+
+  function updateStatus(text) {
+    if (status) {
+      status.textContent = text;
+    }
+  }
+
+  function renderLines(id, lines, separator) {
+    const target = document.getElementById(id);
+    if (!target) {
+      return;
+    }
+    if (!lines || lines.length === 0) {
+      target.textContent = '—';
+      return;
+    }
+    target.textContent = lines.join(separator || '\n');
+  }
+
+  function clearChildren(node) {
+    while (node.firstChild) {
+      node.removeChild(node.firstChild);
+    }
+  }
+
+  function pageData(name) {
+    const page = document.getElementById('page');
+    return page ? page.dataset[name] : undefined;
+  }
+
+  // `?refresh=<secs>` on the page URL overrides the server's rate for
+  // this visit; it is passed on to the page's stream.
+  function refreshSecs() {
+    const requested = Number(new URLSearchParams(window.location.search).get('refresh'));
+    if (requested > 0) {
+      return requested;
+    }
+    const fallback = Number(pageData('refresh'));
+    return fallback > 0 ? fallback : null;
+  }
+
+  let streams = [];
+  let paused = false;
+
+  // Subscribe to a polled SSE payload, keeping the status line current.
+  // The pause button closes every subscription and resume reopens them.
+  function connect(url, onPayload) {
+    const stream = { url: url, onPayload: onPayload, source: null };
+    streams.push(stream);
+    if (!paused) {
+      open(stream);
+    }
+    return stream;
+  }
+
+  function open(stream) {
+    const secs = refreshSecs();
+    const url = secs
+      ? stream.url + (stream.url.indexOf('?') === -1 ? '?' : '&') + 'refresh=' + secs
+      : stream.url;
+    updateStatus('连接中 …');
+    const source = new EventSource(url);
+    source.onopen = function() {
+      updateStatus('已连接');
+    };
+    source.onerror = function() {
+      updateStatus('连接断开，等待重试 …');
+    };
+    source.onmessage = function(event) {
+      updateStatus('已连接');
+      try {
+        stream.onPayload(JSON.parse(event.data));
+      } catch (err) {
+        updateStatus('数据解析失败');
+      }
+    };
+    stream.source = source;
+  }
+
+  function setPaused(value) {
+    paused = value;
+    streams.forEach(function(stream) {
+      if (paused && stream.source) {
+        stream.source.close();
+        stream.source = null;
+      } else if (!paused && !stream.source) {
+        open(stream);
+      }
+    });
+    if (paused) {
+      updateStatus('已暂停');
+    }
+    const button = document.getElementById('stream-pause');
+    if (button) {
+      button.textContent = paused ? '继续' : '暂停';
+    }
+  }
+
+  function bindControls() {
+    const theme = document.getElementById('theme-toggle');
+    if (theme) {
+      theme.onclick = function() {
+        const next = document.documentElement.dataset.theme === 'light' ? 'dark' : 'light';
+        document.documentElement.dataset.theme = next;
+        try {
+          window.localStorage.setItem('hi-telos-theme', next);
+        } catch (err) {
+          // Not persisted; the toggle still applies to this page.
+        }
+      };
+    }
+    const pause = document.getElementById('stream-pause');
+    if (pause) {
+      pause.onclick = function() {
+        setPaused(!paused);
+      };
+    }
+    const info = document.getElementById('refresh-info');
+    const secs = refreshSecs();
+    if (info && secs) {
+      info.textContent = '每 ' + secs + ' 秒刷新';
+    }
+  }
+
+  bindControls();
+
+  window.HiUi = {
+    updateStatus: updateStatus,
+    renderLines: renderLines,
+    clearChildren: clearChildren,
+    pageData: pageData,
+    connect: connect,
+  };
+})();
@@ -0,0 +1,261 @@
+(function() {
+  const ui = window.HiUi;
+
+  // Actions offered per stage; each maps to an `/api/intents/:id/...` call.
+  const ACTIONS = {
+    inbox: ['edit', 'cancel'],
+    pending_approval: ['approve', 'reject', 'edit'],
+    queue: ['edit', 'cancel'],
+    deferred: ['approve', 'edit', 'cancel'],
+    failed: ['retry', 'edit', 'cancel'],
+    history: [],
+  };
+  const LABELS = {
+    approve: '批准',
+    reject: '拒绝',
+    retry: '重试',
+    cancel: '取消',
+    edit: '编辑',
+  };
+
+  // While an edit form is open its list is left alone, so the next payload
+  // does not wipe what the operator is typing.
+  let editing = null;
+  let latest = null;
+
+  function describe(intent) {
+    const parts = [
+      intent.created_at ? intent.created_at.replace('T', ' ').slice(0, 19) : '—',
+      intent.source,
+      Number(intent.telos_alignment || 0).toFixed(2),
+      intent.summary,
+    ];
+    if (intent.due_at) {
+      parts.push('due ' + intent.due_at.replace('T', ' ').slice(0, 16));
+    }
+    const metadata = intent.metadata || {};
+    if (metadata.cost_estimate) {
+      parts.push('cost ' + metadata.cost_estimate);
+    }
+    if (metadata.estimated_tokens) {
+      parts.push('≈' + metadata.estimated_tokens + ' tok');
+    }
+    if (intent.stage === 'pending_approval' && metadata.hold_reason) {
+      parts.push('held: ' + metadata.hold_reason);
+    }
+    return parts.join(' | ') + (intent.overdue ? ' ⏰' : '');
+  }
+
+  function request(method, url, body) {
+    const init = { method: method, headers: {} };
+    if (body !== undefined) {
+      init.headers['Content-Type'] = 'application/json';
+      init.body = JSON.stringify(body);
+    }
+    return fetch(url, init).then(function(response) {
+      if (response.status === 409) {
+        throw new Error('意图正在执行或已归档');
+      }
+      if (!response.ok) {
+        throw new Error('请求失败：HTTP ' + response.status);
+      }
+      return response.json();
+    });
+  }
+
+  function act(action, intent) {
+    const path = '/api/intents/' + encodeURIComponent(intent.id);
+    if (action === 'cancel' && !window.confirm('取消意图「' + intent.summary + '」？')) {
+      return;
+    }
+    request('POST', path + '/' + action, {})
+      .then(function(result) {
+        ui.updateStatus(LABELS[action] + '成功' + (result.beat_scheduled ? '，已触发心跳' : ''));
+      })
+      .catch(function(err) {
+        ui.updateStatus(LABELS[action] + '失败：' + err.message);
+      });
+  }
+
+  function field(form, label, input) {
+    const wrapper = document.createElement('label');
+    wrapper.textContent = label + ' ';
+    wrapper.appendChild(input);
+    form.appendChild(wrapper);
+    return input;
+  }
+
+  function openEditor(item, intent) {
+    editing = intent.id;
+    const form = document.createElement('form');
+    form.className = 'intent-edit';
+
+    const summary = document.createElement('input');
+    summary.value = intent.summary;
+    field(form, '摘要', summary);
+
+    const alignment = document.createElement('input');
+    alignment.type = 'number';
+    alignment.min = '0';
+    alignment.max = '1';
+    alignment.step = '0.05';
+    alignment.value = intent.telos_alignment;
+    field(form, '对齐度', alignment);
+
+    const due = document.createElement('input');
+    due.placeholder = 'RFC3339，留空不改';
+    due.value = intent.due_at || '';
+    field(form, '截止', due);
+
+    // Listings carry a preview of long bodies; load the whole one to edit.
+    const body = document.createElement('textarea');
+    let originalBody = intent.body || '';
+    body.value = originalBody;
+    field(form, '正文', body);
+    if (intent.body_truncated) {
+      body.disabled = true;
+      request('GET', '/api/intents/' + encodeURIComponent(intent.id))
+        .then(function(detail) {
+          originalBody = detail.body || '';
+          body.value = originalBody;
+          body.disabled = false;
+        })
+        .catch(function(err) {
+          ui.updateStatus('读取正文失败：' + err.message);
+        });
+    }
+
+    const save = document.createElement('button');
+    save.type = 'submit';
+    save.textContent = '保存';
+    form.appendChild(save);
+    const close = document.createElement('button');
+    close.type = 'button';
+    close.textContent = '放弃';
+    form.appendChild(close);
+
+    close.onclick = function() {
+      editing = null;
+      render(latest);
+    };
+    form.onsubmit = function(event) {
+      event.preventDefault();
+      const edit = {
+        summary: summary.value,
+        telos_alignment: Number(alignment.value),
+      };
+      if (due.value.trim()) {
+        edit.due_at = due.value.trim();
+      }
+      if (!body.disabled && body.value !== originalBody) {
+        edit.body = body.value;
+      }
+      request('PATCH', '/api/intents/' + encodeURIComponent(intent.id), edit)
+        .then(function() {
+          ui.updateStatus('已保存');
+          editing = null;
+        })
+        .catch(function(err) {
+          ui.updateStatus('保存失败：' + err.message);
+        });
+    };
+    item.appendChild(form);
+  }
+
+  function renderStage(stage, intents) {
+    const list = document.getElementById('stage-' + stage);
+    if (!list) {
+      return;
+    }
+    if (editing && list.querySelector('form.intent-edit')) {
+      return;
+    }
+    ui.clearChildren(list);
+    if (!intents || intents.length === 0) {
+      const empty = document.createElement('li');
+      empty.textContent = '—';
+      list.appendChild(empty);
+      return;
+    }
+    intents.forEach(function(intent) {
+      const item = document.createElement('li');
+      const text = document.createElement('span');
+      text.textContent = describe(intent);
+      item.appendChild(text);
+      if (intent.body) {
+        const preview = document.createElement('div');
+        preview.className = 'intent-body';
+        preview.textContent = intent.body;
+        item.appendChild(preview);
+      }
+      (ACTIONS[stage] || []).forEach(function(action) {
+        const button = document.createElement('button');
+        button.type = 'button';
+        button.textContent = LABELS[action];
+        button.onclick = function() {
+          if (action === 'edit') {
+            if (!editing) {
+              openEditor(item, intent);
+            }
+          } else {
+            act(action, intent);
+          }
+        };
+        item.appendChild(button);
+      });
+      list.appendChild(item);
+    });
+  }
+
+  function render(payload) {
+    if (!payload) {
+      return;
+    }
+    const stages = payload.stages || {};
+    Object.keys(ACTIONS).forEach(function(stage) {
+      renderStage(stage, stages[stage]);
+    });
+  }
+
+  function bindCreateForm() {
+    const form = document.getElementById('new-intent');
+    const result = document.getElementById('new-intent-result');
+    if (!form || !result) {
+      return;
+    }
+    form.onsubmit = function(event) {
+      event.preventDefault();
+      const data = new FormData(form);
+      const intent = {
+        summary: String(data.get('summary') || '').trim(),
+        body: String(data.get('body') || ''),
+        source: String(data.get('source') || '').trim() || 'user',
+        telos_alignment: Number(data.get('telos_alignment')),
+        priority: String(data.get('priority') || 'normal'),
+      };
+      const due = String(data.get('due_at') || '');
+      if (due) {
+        // datetime-local has no zone; read it as the browser's local time.
+        intent.due_at = new Date(due).toISOString();
+      }
+      request('POST', '/api/intents', intent)
+        .then(function(created) {
+          result.textContent = [
+            'id: ' + created.id,
+            'path: ' + created.path,
+            'beat_scheduled: ' + created.beat_scheduled,
+          ].join('\n');
+          form.reset();
+        })
+        .catch(function(err) {
+          result.textContent = '创建失败：' + err.message;
+        });
+    };
+  }
+
+  bindCreateForm();
+  ui.connect(ui.pageData('stream'), function(payload) {
+    latest = payload;
+    render(payload);
+  });
+})();
@@ -0,0 +1,176 @@
+(function() {
+  const ui = window.HiUi;
+
+  // Entries are paged from the logs API as the list scrolls into view; new
+  // calls arrive over the live stream while no `until` bound is set.
+  const PAGE_SIZE = 25;
+  const list = document.getElementById('logs');
+  const more = document.getElementById('logs-more');
+  const form = document.getElementById('log-filters');
+  let filters = {};
+  let cursor = null;
+  let exhausted = false;
+  let loading = false;
+  let generation = 0;
+  let live = null;
+
+  function truncate(text, max) {
+    const flat = (text || '').replace(/\n/g, ' ');
+    return flat.length > max ? flat.slice(0, max - 1) + '…' : flat;
+  }
+
+  function block(label, text) {
+    const fragment = document.createDocumentFragment();
+    const heading = document.createElement('h4');
+    heading.textContent = label;
+    const body = document.createElement('pre');
+    body.textContent = text || '';
+    fragment.appendChild(heading);
+    fragment.appendChild(body);
+    return fragment;
+  }
+
+  function renderEntry(entry) {
+    const item = document.createElement('li');
+    const details = document.createElement('details');
+    const summary = document.createElement('summary');
+    const time = new Date(entry.timestamp).toLocaleString([], { hour12: false });
+    const model = entry.model ? '/' + entry.model : '';
+    summary.textContent = time + ' [' + (entry.phase || '').toUpperCase() + '] '
+      + entry.provider + model + ' — ' + truncate(entry.response, 120);
+    details.appendChild(summary);
+
+    const run = document.createElement('button');
+    run.type = 'button';
+    run.textContent = 'run ' + entry.run_id;
+    run.title = '只看这次运行';
+    run.onclick = function() {
+      form.elements.run_id.value = entry.run_id;
+      applyFilters();
+    };
+    details.appendChild(run);
+    details.appendChild(block('Prompt', entry.prompt));
+    details.appendChild(block('Response', entry.response));
+    item.appendChild(details);
+    return item;
+  }
+
+  function query(extra) {
+    const params = new URLSearchParams();
+    Object.keys(filters).forEach(function(key) {
+      params.set(key, filters[key]);
+    });
+    Object.keys(extra || {}).forEach(function(key) {
+      params.set(key, extra[key]);
+    });
+    return params.toString();
+  }
+
+  function loadPage() {
+    if (loading || exhausted) {
+      return;
+    }
+    loading = true;
+    const requested = generation;
+    const extra = { limit: PAGE_SIZE };
+    if (cursor) {
+      extra.cursor = cursor;
+    }
+    more.textContent = 'Loading…';
+    fetch(ui.pageData('logsApi') + '?' + query(extra))
+      .then(function(response) {
+        if (!response.ok) {
+          throw new Error('HTTP ' + response.status);
+        }
+        return response.json();
+      })
+      .then(function(page) {
+        if (requested !== generation) {
+          return;
+        }
+        (page.entries || []).forEach(function(entry) {
+          list.appendChild(renderEntry(entry));
+        });
+        cursor = page.next_cursor || null;
+        exhausted = !cursor;
+        more.textContent = exhausted ? (list.children.length ? '— 没有更早的记录 —' : '—') : '';
+      })
+      .catch(function(err) {
+        more.textContent = '加载失败：' + err.message;
+      })
+      .then(function() {
+        if (requested === generation) {
+          loading = false;
+        }
+      });
+  }
+
+  function connectLive() {
+    if (live) {
+      live.close();
+      live = null;
+    }
+    if (filters.until) {
+      return;
+    }
+    const params = {};
+    ['level', 'model', 'run_id'].forEach(function(key) {
+      if (filters[key]) {
+        params[key] = filters[key];
+      }
+    });
+    live = new EventSource(ui.pageData('liveStream') + '?' + new URLSearchParams(params));
+    live.addEventListener('llm_log', function(event) {
+      try {
+        list.insertBefore(renderEntry(JSON.parse(event.data)), list.firstChild);
+      } catch (err) {
+        ui.updateStatus('数据解析失败');
+      }
+    });
+  }
+
+  function applyFilters() {
+    const data = new FormData(form);
+    filters = {};
+    ['level', 'model', 'run_id'].forEach(function(key) {
+      const value = String(data.get(key) || '').trim();
+      if (value) {
+        filters[key] = value;
+      }
+    });
+    ['since', 'until'].forEach(function(key) {
+      const value = String(data.get(key) || '');
+      if (value) {
+        // datetime-local has no zone; read it as the browser's local time.
+        filters[key] = new Date(value).toISOString();
+      }
+    });
+    generation += 1;
+    cursor = null;
+    exhausted = false;
+    loading = false;
+    ui.clearChildren(list);
+    loadPage();
+    connectLive();
+  }
+
+  form.onsubmit = function(event) {
+    event.preventDefault();
+    applyFilters();
+  };
+  form.onreset = function() {
+    window.setTimeout(applyFilters, 0);
+  };
+
+  new IntersectionObserver(function(entries) {
+    if (entries.some(function(entry) { return entry.isIntersecting; })) {
+      loadPage();
+    }
+  }).observe(more);
+
+  ui.connect(ui.pageData('stream'), function(payload) {
+    ui.renderLines('sp', payload.sp || [], '\n\n');
+    ui.renderLines('memory', payload.memory || [], '\n\n');
+  });
+  applyFilters();
+})();
@@ -0,0 +1,68 @@
+(function() {
+  const ui = window.HiUi;
+
+  function renderAcceptance(lines) {
+    const block = document.getElementById('acceptance');
+    if (!block) {
+      return;
+    }
+    if (!lines || lines.length === 0) {
+      block.textContent = '暂无数据';
+      return;
+    }
+    block.textContent = lines.join('\n');
+  }
+
+  function renderFiles(files) {
+    const list = document.getElementById('file-list');
+    if (!list) {
+      return;
+    }
+    ui.clearChildren(list);
+    if (!files || files.length === 0) {
+      const item = document.createElement('li');
+      item.textContent = '暂无 Markdown 文件';
+      list.appendChild(item);
+      return;
+    }
+
+    files.forEach(function(path) {
+      const item = document.createElement('li');
+      const button = document.createElement('button');
+      button.textContent = path;
+      button.type = 'button';
+      button.onclick = function() {
+        loadFile(path);
+      };
+      item.appendChild(button);
+      list.appendChild(item);
+    });
+  }
+
+  function loadFile(path) {
+    const viewer = document.getElementById('file-viewer');
+    if (!viewer) {
+      return;
+    }
+    viewer.innerHTML = '<em>载入中…</em>';
+    fetch('/api/md/file?path=' + encodeURIComponent(path) + '&render=true')
+      .then(function(response) {
+        if (!response.ok) {
+          throw new Error('HTTP ' + response.status);
+        }
+        return response.text();
+      })
+      .then(function(html) {
+        // Rendered server-side and sanitized.
+        viewer.innerHTML = html;
+      })
+      .catch(function(err) {
+        viewer.textContent = '读取失败：' + err;
+      });
+  }
+
+  ui.connect(ui.pageData('stream'), function(payload) {
+    renderFiles(payload.files || []);
+    renderAcceptance(payload.acceptance || []);
+  });
+})();
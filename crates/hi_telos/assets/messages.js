@@ -0,0 +1,7 @@
+(function() {
+  const ui = window.HiUi;
+  ui.connect(ui.pageData('stream'), function(payload) {
+    ui.renderLines('telegram-in', payload.telegram_in || []);
+    ui.renderLines('telegram-out', payload.telegram_out || []);
+  });
+})();
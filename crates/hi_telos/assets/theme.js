@@ -0,0 +1,12 @@
+// Loaded in <head> so a saved theme applies before the first paint. The
+// server's default from config/ui.yml is already on <html data-theme>.
+(function() {
+  try {
+    const saved = window.localStorage.getItem('hi-telos-theme');
+    if (saved === 'dark' || saved === 'light') {
+      document.documentElement.dataset.theme = saved;
+    }
+  } catch (err) {
+    // Storage may be unavailable (private mode); keep the server default.
+  }
+})();
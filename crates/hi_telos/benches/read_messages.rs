@@ -0,0 +1,106 @@
+//! `cargo bench --bench read_messages`: time `storage::read_messages` on a
+//! multi-MB day log against a full forward read of the same file, which is
+//! what it did before it read logs from the end.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{TimeZone, Utc};
+use hi_telos::storage::{self, MessageDirection, MessageLogEntry, MessageLogQuery};
+use uuid::Uuid;
+
+/// Entries in the day log; about 10 MB with [`TEXT_LEN`]-character texts.
+const ENTRIES: usize = 20_000;
+const TEXT_LEN: usize = 400;
+const ITERATIONS: u32 = 20;
+/// What `/ui` asks for every few seconds.
+const LIMIT: usize = 50;
+
+fn main() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let data_dir = temp.path();
+    let log = write_day_log(data_dir);
+    let size_mb = fs::metadata(&log).expect("log metadata").len() as f64 / 1e6;
+    println!("day log: {ENTRIES} entries, {size_mb:.1} MB; newest {LIMIT} entries");
+
+    let query = || MessageLogQuery {
+        source: Some("telegram".to_string()),
+        direction: Some(MessageDirection::Inbound),
+        limit: LIMIT,
+        ..MessageLogQuery::default()
+    };
+    let tail = time("read_messages (tail)", || {
+        storage::read_messages(data_dir, query())
+            .expect("read messages")
+            .len()
+    });
+    let full = time("full forward read", || full_read(&log, LIMIT).len());
+    println!(
+        "speedup: {:.1}x",
+        full.as_secs_f64() / tail.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+/// Mean time of [`ITERATIONS`] runs of `run`, after one warm-up run.
+fn time(name: &str, mut run: impl FnMut() -> usize) -> Duration {
+    assert_eq!(run(), LIMIT);
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(run());
+    }
+    let mean = started.elapsed() / ITERATIONS;
+    println!("{name:>24}: {mean:?} per read");
+    mean
+}
+
+/// Log one entry through the storage API so the file lands where
+/// `read_messages` looks, then append the rest directly.
+fn write_day_log(data_dir: &Path) -> PathBuf {
+    let day = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let entry = |index: usize| MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction: MessageDirection::Inbound,
+        source: "telegram".to_string(),
+        chat_id: "42".to_string(),
+        author: Some("bench".to_string()),
+        text: format!("{index:06} {}", "x".repeat(TEXT_LEN)),
+        timestamp: day + chrono::Duration::seconds(index as i64),
+        metadata: None,
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime");
+    runtime
+        .block_on(storage::append_message_entry(data_dir, &entry(0)))
+        .expect("append first entry");
+
+    let log = walkdir::WalkDir::new(data_dir.join("messages"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_type().is_file())
+        .expect("day log")
+        .into_path();
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(&log)
+        .expect("open day log");
+    for index in 1..ENTRIES {
+        let line = serde_json::to_string(&entry(index)).expect("serialize entry");
+        writeln!(file, "{line}").expect("append entry");
+    }
+    log
+}
+
+/// Parse every line, then keep the newest `limit`.
+fn full_read(path: &Path, limit: usize) -> Vec<MessageLogEntry> {
+    let file = fs::File::open(path).expect("open day log");
+    let mut entries: Vec<MessageLogEntry> = BufReader::new(file)
+        .lines()
+        .map(|line| serde_json::from_str(&line.expect("read line")).expect("parse entry"))
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+    entries.truncate(limit);
+    entries
+}
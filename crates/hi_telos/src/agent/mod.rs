@@ -1,27 +1,89 @@
-use std::{fmt::Write, sync::Arc};
+use std::{
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use anyhow::Context;
-use chrono::Utc;
-use serde::Deserialize;
+use anyhow::{Context, bail};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::{
-    config::{AgentConfig, AppConfig, LlmProviderConfig},
-    llm::{LlmClient, LlmLogEntry, LocalStubClient, OpenAiClient},
-    tasks::Intent,
+    clock::{self, SharedClock},
+    config::{
+        AgentConfig, AppConfig, DEFAULT_PERSONA_NAME, HistoryConfig, LlmProviderConfig, Persona,
+        default_openai_api_key_env,
+    },
+    llm::{
+        LlmClient, LlmIdentity, LlmLogEntry, LlmRecorder, LlmStatus, LocalStubClient, OpenAiClient,
+        ResponseCache, ResponseSchema, check_llm,
+    },
+    storage::SpPrecedent,
+    tasks::{Intent, LLM_MODEL_KEY, LLM_PROVIDER_KEY, PERSONA_KEY},
+    text,
+    tools::{ToolFile, ToolRegistry},
 };
+use tracing::warn;
+
+mod prompts;
+
+pub use prompts::{ActiveExperiment, PromptVersions, RunPrompts};
 
 #[derive(Debug, Clone)]
 pub struct AgentInput {
     pub intent: Intent,
     pub backlog_size: usize,
+    /// Earlier turns of the conversation, oldest first; included in the
+    /// prompts when non-empty.
+    pub conversation: Vec<ConversationTurn>,
+    /// Steps of a paused run to continue from; they count against
+    /// `max_react_steps`.
+    pub prior_steps: Vec<AgentStep>,
+    /// Similar intents answered before, from the SP index; included in the
+    /// prompts when non-empty.
+    pub precedents: Vec<SpPrecedent>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationTurn {
+    /// `user` or `assistant`.
+    pub role: String,
+    pub text: String,
+}
+
+/// Action that pauses the run until the user answers `question`.
+pub const ASK_USER_ACTION: &str = "ask_user";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentStep {
     pub thought: String,
     pub action: String,
     pub observation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub question: Option<String>,
+    /// Input for the tool named by `action`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    /// URLs the tool call read, cited in the final answer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+impl AgentStep {
+    /// The clarifying question when this step is an `ask_user` action,
+    /// falling back to the observation when `question` is missing.
+    pub fn user_question(&self) -> Option<&str> {
+        if self.action.trim() != ASK_USER_ACTION {
+            return None;
+        }
+        let question = self.question.as_deref().unwrap_or(&self.observation).trim();
+        (!question.is_empty()).then_some(question)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,114 +97,814 @@ pub struct AgentOutcome {
     pub final_answer: String,
 }
 
+impl AgentOutcome {
+    /// Sources read by tool calls across all steps, first use first.
+    pub fn sources(&self) -> Vec<String> {
+        collect_sources(&self.steps)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AgentRun {
+    pub run_id: Uuid,
     pub outcome: AgentOutcome,
     pub llm_logs: Vec<LlmLogEntry>,
+    /// Set when the run paused on `ask_user`; `outcome.final_answer` is empty
+    /// and `outcome.steps` ends with the asking step.
+    pub question: Option<String>,
+    /// Templates the prompts were built from.
+    pub prompts: RunPrompts,
+    /// Files the run's tool calls produced.
+    pub files: Vec<ToolFile>,
+}
+
+/// Error returned by [`AgentRuntime::run_react`], keeping the calls made
+/// before the failure so callers can persist them and link to the run. It
+/// displays as the underlying error.
+#[derive(Debug)]
+pub struct FailedRun {
+    pub run_id: Uuid,
+    pub llm_logs: Vec<LlmLogEntry>,
+    pub files: Vec<ToolFile>,
+    pub error: anyhow::Error,
 }
 
+impl std::fmt::Display for FailedRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for FailedRun {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Live LLM log entries kept for slow subscribers before they lag.
+const LLM_LOG_FEED_CAPACITY: usize = 256;
+
 pub struct AgentRuntime {
-    config: AgentConfig,
+    /// Swapped by config hot reload; each run reads it once at the start.
+    config: RwLock<AgentConfig>,
     llm: Arc<dyn LlmClient>,
+    /// Configured provider, reused for credentials when an intent pins a
+    /// different model.
+    llm_config: Option<LlmProviderConfig>,
+    /// Record-or-replay wrapper applied to pinned clients as well.
+    recorder: Option<LlmRecorder>,
+    /// Answers to identical prompts, reused until they expire.
+    cache: Option<ResponseCache>,
+    clock: SharedClock,
+    log_feed: broadcast::Sender<LlmLogEntry>,
+    tools: ToolRegistry,
+    /// Runs started, to alternate experiment variants.
+    runs_started: AtomicUsize,
+}
+
+/// A provider answer and how it was obtained.
+struct LlmReply {
+    raw: String,
+    latency: Duration,
+    cached: bool,
+}
+
+/// A run's id and prompt templates, stamped on each of its LLM log entries,
+/// and the files its tool calls produced so far.
+struct RunContext {
+    id: Uuid,
+    prompts: RunPrompts,
+    files: Mutex<Vec<ToolFile>>,
+}
+
+impl RunContext {
+    fn stamp(&self, entry: LlmLogEntry) -> LlmLogEntry {
+        entry.with_prompt(
+            &self.prompts.version,
+            self.prompts
+                .experiment
+                .as_ref()
+                .map(|(name, variant)| (name.as_str(), variant.as_str())),
+        )
+    }
 }
 
 impl AgentRuntime {
     pub fn new(config: AgentConfig, llm: Arc<dyn LlmClient>) -> Self {
-        Self { config, llm }
+        let (log_feed, _) = broadcast::channel(LLM_LOG_FEED_CAPACITY);
+        Self {
+            config: RwLock::new(config),
+            llm,
+            llm_config: None,
+            recorder: None,
+            cache: None,
+            clock: clock::system_clock(),
+            log_feed,
+            tools: ToolRegistry::default(),
+            runs_started: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn agent_config(&self) -> AgentConfig {
+        self.config.read().clone()
+    }
+
+    pub fn update_config(&self, config: AgentConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Stamp LLM logs with `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Answer prompts seen before from `cache` instead of the provider.
+    pub fn with_response_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Offer `tools` to the THINK phase.
+    pub fn with_tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Every LLM call as it completes, before the run ends and its logs are
+    /// persisted.
+    pub fn subscribe_llm_logs(&self) -> broadcast::Receiver<LlmLogEntry> {
+        self.log_feed.subscribe()
+    }
+
+    /// Prompt versions of `agent.prompts` and the active experiment.
+    pub fn prompt_versions(&self) -> PromptVersions {
+        PromptVersions::from_config(&self.agent_config())
+    }
+
+    /// Rough number of tokens a run of `intent` sends and receives: the
+    /// THINK prompt with `body` once per step of its persona, the FINAL
+    /// prompt, and a reply of [`ESTIMATED_REPLY_TOKENS`] per call that is
+    /// also carried in the history of every later prompt.
+    pub fn estimate_intent_tokens(&self, intent: &Intent, body: &str) -> u64 {
+        let config = self.agent_config();
+        let persona = persona_for(&config, intent);
+        let prompts = RunPrompts::for_run(&config, 0);
+        let steps = std::cmp::max(persona.max_react_steps, 1) as u64;
+        let shared = [intent.summary.as_str(), &persona.prompt, body].concat();
+        let think = estimate_tokens(
+            &[
+                shared.as_str(),
+                &prompts.think_instructions,
+                &format_tools(&self.tools),
+            ]
+            .concat(),
+        ) as u64;
+        let final_prompt =
+            estimate_tokens(&[shared.as_str(), &prompts.final_instructions].concat()) as u64;
+        let replies = (steps + 1) * ESTIMATED_REPLY_TOKENS;
+        let history = steps * (steps + 1) / 2 * ESTIMATED_REPLY_TOKENS;
+        steps * think + final_prompt + replies + history
+    }
+
+    /// Probe the configured provider: reachability, latency and whether it
+    /// serves the configured model.
+    pub async fn llm_status(&self) -> LlmStatus {
+        check_llm(self.llm.as_ref(), self.clock.now()).await
+    }
+
+    /// `llm`'s answer to `prompt`, from the response cache while it holds a
+    /// fresh one. Cache errors are logged and the provider is asked instead.
+    async fn ask(
+        &self,
+        llm: &dyn LlmClient,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> anyhow::Result<LlmReply> {
+        let started = Instant::now();
+        let identity = llm.identity();
+        if let Some(cache) = &self.cache {
+            match cache.get(&identity, prompt, self.clock.now()).await {
+                Ok(Some(raw)) => {
+                    return Ok(LlmReply {
+                        raw,
+                        latency: started.elapsed(),
+                        cached: true,
+                    });
+                }
+                Ok(None) => {}
+                Err(err) => warn!(error = ?err, "reading llm response cache failed"),
+            }
+        }
+        let raw = llm.chat_structured(prompt, schema).await?;
+        let latency = started.elapsed();
+        // Only JSON answers are kept, so a garbled one is asked again on retry.
+        if let Some(cache) = &self.cache
+            && serde_json::from_str::<serde_json::Value>(&raw).is_ok()
+            && let Err(err) = cache.put(&identity, prompt, &raw, self.clock.now()).await
+        {
+            warn!(error = ?err, "writing llm response cache failed");
+        }
+        Ok(LlmReply {
+            raw,
+            latency,
+            cached: false,
+        })
+    }
+
+    /// The log entry for a `phase` call answered by `reply`.
+    fn reply_entry(
+        &self,
+        run_id: Uuid,
+        phase: &str,
+        prompt: &str,
+        reply: &LlmReply,
+        identity: &LlmIdentity,
+    ) -> LlmLogEntry {
+        LlmLogEntry::new(
+            run_id,
+            self.clock.now(),
+            phase,
+            prompt,
+            &reply.raw,
+            identity,
+        )
+        .with_latency(reply.latency)
+        .with_cache_hit(reply.cached)
+    }
+
+    fn record_llm_call(&self, logs: &mut Vec<LlmLogEntry>, entry: LlmLogEntry) {
+        let _ = self.log_feed.send(entry.clone());
+        logs.push(entry);
     }
 
     pub fn from_app_config(config: &AppConfig) -> anyhow::Result<Self> {
-        let llm_client: Arc<dyn LlmClient> = match &config.llm {
-            LlmProviderConfig::LocalStub => Arc::new(LocalStubClient),
-            LlmProviderConfig::OpenAi {
-                model,
-                api_key_env,
-                base_url,
-                organization,
-            } => Arc::new(OpenAiClient::from_env(
-                api_key_env,
-                model,
-                base_url.clone(),
-                organization.clone(),
-            )?),
+        let recorder = LlmRecorder {
+            mode: config.llm_recording.mode,
+            dir: config.llm_recording_dir(),
+        };
+        let llm_client = recorder.wrap(config.llm.identity(), || {
+            Ok(match &config.llm {
+                LlmProviderConfig::LocalStub => Arc::new(LocalStubClient),
+                LlmProviderConfig::OpenAi { model, .. } => {
+                    Arc::new(openai_client(Some(&config.llm), model)?)
+                }
+            })
+        })?;
+
+        let mut runtime = Self::new(config.agent.clone(), llm_client);
+        runtime.llm_config = Some(config.llm.clone());
+        runtime.recorder = Some(recorder);
+        runtime.cache = config.llm_cache.as_ref().map(|cache| {
+            ResponseCache::new(config.llm_cache_dir(), Duration::from_secs(cache.ttl_secs))
+        });
+        runtime.tools = ToolRegistry::from_config(config)?;
+        Ok(runtime)
+    }
+
+    /// The client for `intent`: the configured one, unless the intent pins a
+    /// provider or model through [`LLM_PROVIDER_KEY`] / [`LLM_MODEL_KEY`] or
+    /// its persona names a model.
+    fn client_for(
+        &self,
+        intent: &Intent,
+        persona_model: Option<&str>,
+    ) -> anyhow::Result<Arc<dyn LlmClient>> {
+        let pinned = |key: &str| {
+            intent
+                .metadata
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let model = pinned(LLM_MODEL_KEY).or(persona_model);
+        self.client_with(pinned(LLM_PROVIDER_KEY), model)
+    }
+
+    /// The configured client, or one for `provider` / `model` when either
+    /// differs from it.
+    fn client_with(
+        &self,
+        provider: Option<&str>,
+        model: Option<&str>,
+    ) -> anyhow::Result<Arc<dyn LlmClient>> {
+        let provider = match provider {
+            Some(provider) => provider,
+            None if model.is_some() => self.llm.identity().provider,
+            None => return Ok(self.llm.clone()),
         };
 
-        Ok(Self::new(config.agent.clone(), llm_client))
+        let wrap = |identity: LlmIdentity,
+                    build: &dyn Fn() -> anyhow::Result<Arc<dyn LlmClient>>| {
+            match &self.recorder {
+                Some(recorder) => recorder.wrap(identity, build),
+                None => build(),
+            }
+        };
+        match provider {
+            "local_stub" => wrap(LocalStubClient.identity(), &|| {
+                Ok(Arc::new(LocalStubClient))
+            }),
+            "openai" | "open_ai" => {
+                let configured = self
+                    .llm_config
+                    .as_ref()
+                    .filter(|config| matches!(config, LlmProviderConfig::OpenAi { .. }));
+                let configured_model = match configured {
+                    Some(LlmProviderConfig::OpenAi { model, .. }) => Some(model.as_str()),
+                    _ => None,
+                };
+                let model = model
+                    .or(configured_model)
+                    .with_context(|| format!("intent pins openai without {LLM_MODEL_KEY}"))?;
+                wrap(LlmIdentity::new("openai", Some(model.to_string())), &|| {
+                    Ok(Arc::new(openai_client(configured, model)?))
+                })
+            }
+            other => bail!("intent pins unknown llm provider {other:?}"),
+        }
     }
 
     pub async fn run_react(&self, input: AgentInput) -> anyhow::Result<AgentRun> {
-        let mut steps = Vec::new();
+        self.run_react_observed(input, |_| {}).await
+    }
+
+    /// [`Self::run_react`], calling `on_step` as each THINK step is parsed so
+    /// callers can stream progress before the run ends. Errors are a
+    /// [`FailedRun`].
+    pub async fn run_react_observed(
+        &self,
+        input: AgentInput,
+        on_step: impl FnMut(&AgentStep) + Send,
+    ) -> anyhow::Result<AgentRun> {
+        let run = RunContext {
+            id: Uuid::new_v4(),
+            prompts: RunPrompts::for_run(
+                &self.agent_config(),
+                self.runs_started.fetch_add(1, Ordering::Relaxed),
+            ),
+            files: Mutex::new(Vec::new()),
+        };
         let mut llm_logs = Vec::new();
-        let run_id = Uuid::new_v4();
-        let identity = self.llm.identity();
+        self.react_loop(input, on_step, &run, &mut llm_logs)
+            .await
+            .map_err(|error| {
+                FailedRun {
+                    run_id: run.id,
+                    llm_logs,
+                    files: run.files.into_inner(),
+                    error,
+                }
+                .into()
+            })
+    }
 
-        let step_count = std::cmp::max(self.config.max_react_steps, 1);
-        for step_index in 0..step_count {
-            let history = format_history(&steps);
+    async fn react_loop(
+        &self,
+        input: AgentInput,
+        mut on_step: impl FnMut(&AgentStep) + Send,
+        run: &RunContext,
+        llm_logs: &mut Vec<LlmLogEntry>,
+    ) -> anyhow::Result<AgentRun> {
+        let mut steps = input.prior_steps.clone();
+        let persona = persona_for(&self.agent_config(), &input.intent);
+        let llm = self.client_for(&input.intent, persona.model.as_deref())?;
+        let identity = llm.identity();
+
+        let conversation = format_conversation(&input.conversation);
+        let precedents = format_precedents(&input.precedents);
+        let tools = format_tools(&self.tools);
+        let mut digest = HistoryDigest::new(self.agent_config().history);
+        let step_count = std::cmp::max(persona.max_react_steps, 1);
+        for step_index in steps.len()..step_count {
+            let history = self
+                .bounded_history(&*llm, &input.intent, &steps, &mut digest, run, llm_logs)
+                .await;
             let prompt = format!(
-                "# Phase: THINK\nIntent: {}\nBacklog: {}\nPersona: {}\nStep: {}\nHistory:\n{}\nRespond with JSON containing thought, action, observation.",
+                "# Phase: THINK\nIntent: {}\nBacklog: {}\nPersona: {}\nStep: {}\n{}{}{}History:\n{}\n{}{}",
                 input.intent.summary,
                 input.backlog_size,
-                self.config.persona,
+                persona.prompt,
                 step_index + 1,
+                precedents,
+                conversation,
+                tools,
                 history,
+                run.prompts.think_instructions,
+                if tools.is_empty() {
+                    ""
+                } else {
+                    " To use a tool, set action to its name and put its input in input; its result replaces your observation."
+                },
             );
 
-            let raw = self.llm.chat(&prompt).await?;
-            llm_logs.push(LlmLogEntry::new(
-                run_id,
-                Utc::now(),
-                "THINK",
-                &prompt,
-                &raw,
-                &identity,
-            ));
-            let step: AgentStep = serde_json::from_str(&raw)
+            let reply = self.ask(&*llm, &prompt, &step_schema()).await?;
+            self.record_llm_call(
+                llm_logs,
+                run.stamp(self.reply_entry(run.id, "THINK", &prompt, &reply, &identity)),
+            );
+            let raw = reply.raw;
+            let mut step: AgentStep = serde_json::from_str(&raw)
                 .with_context(|| format!("parsing agent step response: {raw}"))?;
+            self.call_tool(&mut step, run, &identity, llm_logs).await;
+            let question = step.user_question().map(str::to_string);
+            on_step(&step);
             steps.push(step);
+            if question.is_some() {
+                return Ok(AgentRun {
+                    run_id: run.id,
+                    outcome: AgentOutcome {
+                        steps,
+                        final_answer: String::new(),
+                    },
+                    llm_logs: std::mem::take(llm_logs),
+                    question,
+                    prompts: run.prompts.clone(),
+                    files: std::mem::take(&mut *run.files.lock()),
+                });
+            }
         }
 
-        let history = format_history(&steps);
+        let history = self
+            .bounded_history(&*llm, &input.intent, &steps, &mut digest, run, llm_logs)
+            .await;
+        let sources = format_sources(&collect_sources(&steps));
         let final_prompt = format!(
-            "# Phase: FINAL\nIntent: {}\nPersona: {}\nHistory:\n{}\nRespond with JSON containing final_answer.",
-            input.intent.summary, self.config.persona, history,
+            "# Phase: FINAL\nIntent: {}\nPersona: {}\n{}{}History:\n{}\n{}{}{}",
+            input.intent.summary,
+            persona.prompt,
+            precedents,
+            conversation,
+            history,
+            sources,
+            run.prompts.final_instructions,
+            if sources.is_empty() {
+                ""
+            } else {
+                " Cite the sources you relied on by URL."
+            },
         );
 
-        let final_raw = self.llm.chat(&final_prompt).await?;
-        llm_logs.push(LlmLogEntry::new(
-            run_id,
-            Utc::now(),
-            "FINAL",
-            &final_prompt,
-            &final_raw,
-            &identity,
-        ));
+        let reply = self.ask(&*llm, &final_prompt, &final_schema()).await?;
+        self.record_llm_call(
+            llm_logs,
+            run.stamp(self.reply_entry(run.id, "FINAL", &final_prompt, &reply, &identity)),
+        );
+        let final_raw = reply.raw;
         let final_payload = serde_json::from_str::<FinalAnswer>(&final_raw)
             .with_context(|| format!("parsing final answer: {final_raw}"))?;
 
         Ok(AgentRun {
+            run_id: run.id,
             outcome: AgentOutcome {
                 steps,
                 final_answer: final_payload.final_answer,
             },
-            llm_logs,
+            llm_logs: std::mem::take(llm_logs),
+            question: None,
+            prompts: run.prompts.clone(),
+            files: std::mem::take(&mut *run.files.lock()),
         })
     }
+
+    /// The history block for the next prompt: every step while it fits
+    /// `agent.history.max_tokens`, else a summary of the older steps plus
+    /// the latest `keep_recent_steps` verbatim. The summary is extended
+    /// only as more steps fall out of the recent window, each time logged
+    /// as a `HISTORY` call; when that call fails the older steps are just
+    /// left out.
+    async fn bounded_history(
+        &self,
+        llm: &dyn LlmClient,
+        intent: &Intent,
+        steps: &[AgentStep],
+        digest: &mut HistoryDigest,
+        run: &RunContext,
+        llm_logs: &mut Vec<LlmLogEntry>,
+    ) -> String {
+        let full = format_history(steps);
+        let config = &digest.config;
+        let split = steps.len().saturating_sub(config.keep_recent_steps);
+        if config.max_tokens == 0 || estimate_tokens(&full) <= config.max_tokens || split == 0 {
+            return full;
+        }
+
+        if split > digest.covered {
+            let mut document = String::new();
+            if !digest.summary.is_empty() {
+                let _ = writeln!(document, "Earlier summary: {}", digest.summary);
+            }
+            document.push_str(&format_steps(&steps[digest.covered..split], digest.covered));
+            let prompt = format!(
+                "# Phase: SUMMARY\nIntent: Summarize the earlier steps of '{}' in a few sentences, keeping facts, results and URLs\nDocument:\n{document}\nRespond with JSON containing final_answer.",
+                intent.summary,
+            );
+            let identity = llm.identity();
+            let summary = match self.ask(llm, &prompt, &final_schema()).await {
+                Ok(reply) => {
+                    self.record_llm_call(
+                        llm_logs,
+                        run.stamp(self.reply_entry(run.id, "HISTORY", &prompt, &reply, &identity)),
+                    );
+                    let raw = reply.raw;
+                    serde_json::from_str::<FinalAnswer>(&raw)
+                        .map(|payload| payload.final_answer.trim().to_string())
+                        .with_context(|| format!("parsing history summary: {raw}"))
+                }
+                Err(err) => Err(err),
+            };
+            match summary {
+                Ok(summary) => {
+                    digest.summary = summary;
+                    digest.covered = split;
+                }
+                Err(err) => {
+                    warn!(intent = %intent.id, error = ?err, "summarizing agent history failed")
+                }
+            }
+        }
+
+        let recent = format_steps(&steps[split..], split);
+        if digest.covered == split {
+            format!("Summary of steps 1-{split}: {}\n{recent}", digest.summary)
+        } else {
+            format!("({split} earlier steps omitted)\n{recent}")
+        }
+    }
+
+    /// Run the tool `step.action` names, if any, replacing the model's
+    /// imagined observation with the real one. Tool failures become the
+    /// observation so the model can react to them. Each call is logged with
+    /// the run as a `TOOL` entry holding the input and full result.
+    async fn call_tool(
+        &self,
+        step: &mut AgentStep,
+        run: &RunContext,
+        identity: &LlmIdentity,
+        llm_logs: &mut Vec<LlmLogEntry>,
+    ) {
+        let Some(tool) = self.tools.get(step.action.trim()) else {
+            return;
+        };
+        let input = step.input.clone().unwrap_or_default();
+        let transcript = match tool.call(&input).await {
+            Ok(output) => {
+                step.observation = output.observation;
+                step.sources = output.sources;
+                run.files.lock().extend(output.files);
+                output.transcript
+            }
+            Err(err) => {
+                warn!(tool = tool.name(), error = ?err, "agent tool call failed");
+                step.observation = format!("{} failed: {err:#}", tool.name());
+                step.sources.clear();
+                None
+            }
+        };
+        let mut entry = LlmLogEntry::new(
+            run.id,
+            self.clock.now(),
+            "TOOL",
+            format!("{} {}", tool.name(), input),
+            transcript.as_deref().unwrap_or(&step.observation),
+            identity,
+        );
+        entry.model = Some(tool.name().to_string());
+        self.record_llm_call(llm_logs, run.stamp(entry));
+    }
+
+    /// A short summary of `document` from the configured LLM as the default
+    /// persona, with the call's log entry. Long documents are cut to
+    /// [`SUMMARY_DOCUMENT_CHARS`].
+    pub async fn summarize_document(
+        &self,
+        title: &str,
+        document: &str,
+    ) -> anyhow::Result<(String, Vec<LlmLogEntry>)> {
+        let persona = self.agent_config().persona_for(None)?;
+        let llm = self.client_with(None, persona.model.as_deref())?;
+        let identity = llm.identity();
+        let document = text::truncate(document, SUMMARY_DOCUMENT_CHARS);
+        let prompt = format!(
+            "# Phase: SUMMARY\nIntent: Summarize '{title}' in two or three sentences\nPersona: {}\nDocument:\n{document}\nRespond with JSON containing final_answer.",
+            persona.prompt,
+        );
+
+        let reply = self.ask(&*llm, &prompt, &final_schema()).await?;
+        let mut llm_logs = Vec::new();
+        self.record_llm_call(
+            &mut llm_logs,
+            self.reply_entry(Uuid::new_v4(), "SUMMARY", &prompt, &reply, &identity),
+        );
+        let raw = reply.raw;
+        let payload = serde_json::from_str::<FinalAnswer>(&raw)
+            .with_context(|| format!("parsing summary: {raw}"))?;
+        Ok((payload.final_answer.trim().to_string(), llm_logs))
+    }
+}
+
+/// Longest document prefix [`AgentRuntime::summarize_document`] sends.
+const SUMMARY_DOCUMENT_CHARS: usize = 20_000;
+
+/// The persona `intent` asks for through [`PERSONA_KEY`], falling back to
+/// the configured default when the name is unknown so a typo does not fail
+/// the run.
+fn persona_for(config: &AgentConfig, intent: &Intent) -> Persona {
+    let requested = intent
+        .metadata
+        .get(PERSONA_KEY)
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty());
+    for candidate in [requested, None, Some(DEFAULT_PERSONA_NAME)] {
+        match config.persona_for(candidate) {
+            Ok(persona) => return persona,
+            Err(err) => warn!(intent = %intent.id, error = %err, "persona unavailable"),
+        }
+    }
+    unreachable!("the inline persona always resolves")
+}
+
+/// A THINK step, as [`AgentStep`] reads it.
+fn step_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "react_step",
+        description: "Record the next ReAct step",
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "thought": { "type": "string" },
+                "action": { "type": "string" },
+                "observation": { "type": "string" },
+                "question": { "type": "string", "description": "For action ask_user" },
+                "input": { "type": "string", "description": "For a tool action" },
+            },
+            "required": ["thought", "action", "observation"],
+        }),
+    }
+}
+
+/// A final answer, as [`FinalAnswer`] reads it.
+fn final_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "final_answer",
+        description: "Give the final answer",
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": { "final_answer": { "type": "string" } },
+            "required": ["final_answer"],
+        }),
+    }
+}
+
+/// An OpenAI client using the key resolved from `api_key_file` /
+/// `api_key_command` when there is one, else the one in `api_key_env`.
+/// An OpenAI client for `model`, set up from `config` (an `openai` provider
+/// section) or, without one, from the default key variable.
+fn openai_client(config: Option<&LlmProviderConfig>, model: &str) -> anyhow::Result<OpenAiClient> {
+    let Some(
+        config @ LlmProviderConfig::OpenAi {
+            api_key,
+            api_key_env,
+            base_url,
+            organization,
+            function_calling,
+            ..
+        },
+    ) = config
+    else {
+        return OpenAiClient::from_env(&default_openai_api_key_env(), model, None, None);
+    };
+    let client = match api_key {
+        Some(api_key) => OpenAiClient::new(
+            api_key.clone(),
+            model,
+            base_url.clone(),
+            organization.clone(),
+        )?,
+        None => OpenAiClient::from_env(api_key_env, model, base_url.clone(), organization.clone())?,
+    };
+    Ok(client
+        .with_function_calling(*function_calling)
+        .with_params(config.params_for(model)))
+}
+
+/// `Conversation:` block ending in a newline, or nothing for a fresh
+/// conversation so single-shot prompts stay unchanged.
+fn format_conversation(turns: &[ConversationTurn]) -> String {
+    if turns.is_empty() {
+        return String::new();
+    }
+
+    let mut block = String::from("Conversation:\n");
+    for turn in turns {
+        let _ = writeln!(&mut block, "- {}: {}", turn.role, turn.text.trim());
+    }
+    block
+}
+
+/// Prior answers to similar intents, as a block ending in a newline, or
+/// empty when there are none.
+fn format_precedents(precedents: &[SpPrecedent]) -> String {
+    if precedents.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("Similar intents you solved before:\n");
+    for precedent in precedents {
+        let _ = writeln!(
+            block,
+            "- {} ⇒ {} (used {}×)",
+            precedent.intent, precedent.final_answer, precedent.count
+        );
+    }
+    block
+}
+
+/// `Tools:` block ending in a newline, or nothing without tools so prompts
+/// (and recordings of them) stay unchanged.
+fn format_tools(tools: &ToolRegistry) -> String {
+    if tools.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("Tools:\n");
+    for tool in tools.iter() {
+        let _ = writeln!(block, "- {}: {}", tool.name(), tool.description());
+    }
+    block
+}
+
+fn collect_sources(steps: &[AgentStep]) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    for source in steps.iter().flat_map(|step| &step.sources) {
+        if !sources.contains(source) {
+            sources.push(source.clone());
+        }
+    }
+    sources
+}
+
+/// `Sources:` block ending in a newline, or nothing.
+fn format_sources(sources: &[String]) -> String {
+    if sources.is_empty() {
+        return String::new();
+    }
+    let mut block = String::from("Sources:\n");
+    for source in sources {
+        let _ = writeln!(block, "- {source}");
+    }
+    block
+}
+
+/// Older steps already folded into a summary during one run.
+struct HistoryDigest {
+    config: HistoryConfig,
+    /// Steps `0..covered` are in `summary`.
+    covered: usize,
+    summary: String,
+}
+
+impl HistoryDigest {
+    fn new(config: HistoryConfig) -> Self {
+        Self {
+            config,
+            covered: 0,
+            summary: String::new(),
+        }
+    }
+}
+
+/// Tokens [`AgentRuntime::estimate_intent_tokens`] expects per LLM reply.
+pub const ESTIMATED_REPLY_TOKENS: u64 = 150;
+
+/// Rough token count for prompt budgeting: about four characters a token.
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
 }
 
 fn format_history(steps: &[AgentStep]) -> String {
     if steps.is_empty() {
         return "(none)".to_string();
     }
+    format_steps(steps, 0)
+}
 
+/// One line per step, numbered from `first_index + 1`.
+fn format_steps(steps: &[AgentStep], first_index: usize) -> String {
     let mut history = String::new();
     for (idx, step) in steps.iter().enumerate() {
+        let idx = first_index + idx;
+        let action = match (step.user_question(), step.input.as_deref()) {
+            (Some(question), _) => format!("{} ({question})", step.action),
+            (None, Some(input)) if !input.trim().is_empty() => {
+                format!("{} ({})", step.action, input.trim())
+            }
+            _ => step.action.clone(),
+        };
         let _ = writeln!(
             &mut history,
             "{}. Thought: {} | Action: {} | Observation: {}",
             idx + 1,
             step.thought,
-            step.action,
+            action,
             step.observation
         );
     }
@@ -161,6 +923,8 @@ mod tests {
             summary: "Draft launch plan".to_string(),
             telos_alignment: 0.8,
             created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
             storage_path: None,
         }
     }
@@ -172,11 +936,17 @@ mod tests {
                 thought: "Consider constraints".to_string(),
                 action: "review_context".to_string(),
                 observation: "Remaining backlog count: 2".to_string(),
+                question: None,
+                input: None,
+                sources: Vec::new(),
             },
             AgentStep {
                 thought: "Outline deliverables".to_string(),
                 action: "summarize_intent".to_string(),
                 observation: "Remaining backlog count: 1".to_string(),
+                question: None,
+                input: None,
+                sources: Vec::new(),
             },
         ];
 
@@ -191,20 +961,275 @@ mod tests {
         assert_eq!(format_history(&[]), "(none)");
     }
 
+    #[test]
+    fn conversation_block_lists_turns() {
+        assert_eq!(format_conversation(&[]), "");
+        let turns = vec![
+            ConversationTurn {
+                role: "user".to_string(),
+                text: "Draft the launch plan".to_string(),
+            },
+            ConversationTurn {
+                role: "assistant".to_string(),
+                text: "Here is a draft.\n".to_string(),
+            },
+        ];
+        assert_eq!(
+            format_conversation(&turns),
+            "Conversation:\n- user: Draft the launch plan\n- assistant: Here is a draft.\n"
+        );
+    }
+
+    #[test]
+    fn precedent_block_lists_prior_answers() {
+        assert_eq!(format_precedents(&[]), "");
+        let precedents = vec![SpPrecedent {
+            id: "abc".to_string(),
+            intent: "Draft the weekly report".to_string(),
+            final_answer: "Sent it to the team".to_string(),
+            count: 3,
+            similarity: 0.8,
+        }];
+        assert_eq!(
+            format_precedents(&precedents),
+            "Similar intents you solved before:\n- Draft the weekly report ⇒ Sent it to the team (used 3×)\n"
+        );
+    }
+
+    /// Asks which region to use, then answers with whatever the user said.
+    struct AskingClient;
+
+    #[async_trait::async_trait]
+    impl LlmClient for AskingClient {
+        async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+            if prompt.contains("# Phase: THINK") {
+                return Ok(serde_json::json!({
+                    "thought": "Region is unclear",
+                    "action": ASK_USER_ACTION,
+                    "observation": "",
+                    "question": "Which region?",
+                })
+                .to_string());
+            }
+            let answered =
+                prompt.contains("ask_user (Which region?) | Observation: User answered: eu");
+            Ok(serde_json::json!({ "final_answer": format!("answered: {answered}") }).to_string())
+        }
+
+        fn identity(&self) -> crate::llm::LlmIdentity {
+            crate::llm::LlmIdentity::new("asking", None)
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_user_pauses_run_and_resumes_with_answer() {
+        let runtime = AgentRuntime::new(
+            AgentConfig {
+                max_react_steps: 1,
+                persona: "TelosOps".to_string(),
+                session: Default::default(),
+                personas: Default::default(),
+                default_persona: None,
+                sp_recall: Default::default(),
+                history: Default::default(),
+                prompts: Default::default(),
+                experiment: None,
+            },
+            Arc::new(AskingClient),
+        );
+        let input = AgentInput {
+            intent: sample_intent(),
+            backlog_size: 0,
+            conversation: Vec::new(),
+            prior_steps: Vec::new(),
+            precedents: Vec::new(),
+        };
+
+        let paused = runtime.run_react(input.clone()).await.unwrap();
+        assert_eq!(paused.question.as_deref(), Some("Which region?"));
+        assert!(paused.outcome.final_answer.is_empty());
+        assert_eq!(paused.outcome.steps.len(), 1);
+
+        let mut steps = paused.outcome.steps;
+        steps[0].observation = "User answered: eu".to_string();
+        let resumed = runtime
+            .run_react(AgentInput {
+                prior_steps: steps,
+                ..input
+            })
+            .await
+            .unwrap();
+        assert_eq!(resumed.question, None);
+        assert_eq!(resumed.outcome.final_answer, "answered: true");
+    }
+
+    /// Fetches a page on THINK, then reports what the FINAL prompt showed.
+    struct FetchingClient;
+
+    #[async_trait::async_trait]
+    impl LlmClient for FetchingClient {
+        async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+            if prompt.contains("# Phase: THINK") {
+                assert!(prompt.contains("Tools:\n- echo_page: returns the page\n"));
+                return Ok(serde_json::json!({
+                    "thought": "Read the article",
+                    "action": "echo_page",
+                    "input": "https://example.com/a",
+                    "observation": "imagined",
+                })
+                .to_string());
+            }
+            let fetched = prompt.contains(
+                "echo_page (https://example.com/a) | Observation: page at https://example.com/a",
+            );
+            let cited = prompt.contains("Sources:\n- https://example.com/a\n");
+            Ok(serde_json::json!({ "final_answer": format!("{fetched} {cited}") }).to_string())
+        }
+
+        fn identity(&self) -> crate::llm::LlmIdentity {
+            crate::llm::LlmIdentity::new("fetching", None)
+        }
+    }
+
+    struct EchoPage;
+
+    #[async_trait::async_trait]
+    impl crate::tools::Tool for EchoPage {
+        fn name(&self) -> &str {
+            "echo_page"
+        }
+
+        fn description(&self) -> &str {
+            "returns the page"
+        }
+
+        async fn call(&self, input: &str) -> anyhow::Result<crate::tools::ToolOutput> {
+            Ok(crate::tools::ToolOutput {
+                observation: format!("page at {input}"),
+                sources: vec![input.to_string()],
+                transcript: None,
+                files: Vec::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_actions_replace_observations_and_cite_sources() {
+        let mut tools = ToolRegistry::default();
+        tools.register(Arc::new(EchoPage));
+        let runtime = AgentRuntime::new(
+            AgentConfig {
+                max_react_steps: 1,
+                persona: "TelosOps".to_string(),
+                session: Default::default(),
+                personas: Default::default(),
+                default_persona: None,
+                sp_recall: Default::default(),
+                history: Default::default(),
+                prompts: Default::default(),
+                experiment: None,
+            },
+            Arc::new(FetchingClient),
+        )
+        .with_tools(tools);
+
+        let run = runtime
+            .run_react(AgentInput {
+                intent: sample_intent(),
+                backlog_size: 0,
+                conversation: Vec::new(),
+                prior_steps: Vec::new(),
+                precedents: Vec::new(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(run.outcome.final_answer, "true true");
+        assert_eq!(
+            run.outcome.steps[0].observation,
+            "page at https://example.com/a"
+        );
+        assert_eq!(run.outcome.sources(), vec!["https://example.com/a"]);
+        let tool_log = run
+            .llm_logs
+            .iter()
+            .find(|entry| entry.phase == "TOOL")
+            .unwrap();
+        assert_eq!(tool_log.prompt, "echo_page https://example.com/a");
+        assert_eq!(tool_log.model.as_deref(), Some("echo_page"));
+        assert_eq!(tool_log.latency_ms, None);
+    }
+
+    #[tokio::test]
+    async fn pinned_provider_overrides_configured_client() {
+        let runtime = AgentRuntime::new(
+            AgentConfig {
+                max_react_steps: 1,
+                persona: "TelosOps".to_string(),
+                session: Default::default(),
+                personas: Default::default(),
+                default_persona: None,
+                sp_recall: Default::default(),
+                history: Default::default(),
+                prompts: Default::default(),
+                experiment: None,
+            },
+            Arc::new(AskingClient),
+        );
+        let mut intent = sample_intent();
+        intent
+            .metadata
+            .insert(LLM_PROVIDER_KEY.to_string(), "local_stub".to_string());
+        let input = AgentInput {
+            intent,
+            backlog_size: 0,
+            conversation: Vec::new(),
+            prior_steps: Vec::new(),
+            precedents: Vec::new(),
+        };
+
+        let run = runtime.run_react(input.clone()).await.unwrap();
+        assert_eq!(run.question, None);
+        assert!(run.outcome.final_answer.contains("completed the plan"));
+        assert!(
+            run.llm_logs
+                .iter()
+                .all(|entry| entry.provider == "local_stub")
+        );
+
+        let mut unknown = input;
+        unknown
+            .intent
+            .metadata
+            .insert(LLM_PROVIDER_KEY.to_string(), "mystery".to_string());
+        let err = runtime.run_react(unknown).await.unwrap_err();
+        assert!(err.to_string().contains("mystery"));
+    }
+
     #[tokio::test]
     async fn react_runtime_yields_steps_and_final_answer() {
         let runtime = AgentRuntime::new(
             AgentConfig {
                 max_react_steps: 2,
                 persona: "TelosOps".to_string(),
+                session: Default::default(),
+                personas: Default::default(),
+                default_persona: None,
+                sp_recall: Default::default(),
+                history: Default::default(),
+                prompts: Default::default(),
+                experiment: None,
             },
             Arc::new(LocalStubClient),
         );
+        let mut feed = runtime.subscribe_llm_logs();
 
         let run = runtime
             .run_react(AgentInput {
                 intent: sample_intent(),
                 backlog_size: 3,
+                conversation: Vec::new(),
+                prior_steps: Vec::new(),
+                precedents: Vec::new(),
             })
             .await
             .expect("agent run should succeed");
@@ -223,5 +1248,205 @@ mod tests {
         );
         assert!(!run.llm_logs.is_empty());
         assert!(run.llm_logs.iter().any(|entry| entry.phase == "THINK"));
+
+        let mut streamed = Vec::new();
+        while let Ok(entry) = feed.try_recv() {
+            streamed.push(entry.phase);
+        }
+        let logged: Vec<_> = run
+            .llm_logs
+            .iter()
+            .map(|entry| entry.phase.clone())
+            .collect();
+        assert_eq!(streamed, logged);
+    }
+
+    /// [`LocalStubClient`], counting the calls that reach it.
+    #[derive(Default)]
+    struct CountingClient(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl LlmClient for CountingClient {
+        async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            LocalStubClient.chat(prompt).await
+        }
+
+        fn identity(&self) -> crate::llm::LlmIdentity {
+            LocalStubClient.identity()
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_prompts_are_answered_from_the_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let config: AgentConfig =
+            serde_yaml::from_str("max_react_steps: 2\npersona: TelosOps\n").unwrap();
+        let client = Arc::new(CountingClient::default());
+        let runtime = AgentRuntime::new(config, client.clone()).with_response_cache(
+            ResponseCache::new(temp.path().to_path_buf(), Duration::from_secs(60)),
+        );
+        let input = AgentInput {
+            intent: sample_intent(),
+            backlog_size: 3,
+            conversation: Vec::new(),
+            prior_steps: Vec::new(),
+            precedents: Vec::new(),
+        };
+
+        let first = runtime.run_react(input.clone()).await.unwrap();
+        let calls = client.0.load(Ordering::SeqCst);
+        assert_eq!(calls, 3);
+        assert!(first.llm_logs.iter().all(|entry| !entry.cache_hit));
+
+        let second = runtime.run_react(input).await.unwrap();
+        assert_eq!(client.0.load(Ordering::SeqCst), calls);
+        assert!(second.llm_logs.iter().all(|entry| entry.cache_hit));
+        assert_eq!(second.outcome.final_answer, first.outcome.final_answer);
+    }
+
+    #[tokio::test]
+    async fn long_histories_summarize_older_steps() {
+        let config: AgentConfig = serde_yaml::from_str(
+            "max_react_steps: 3
+persona: TelosOps
+history:
+  max_tokens: 1
+  keep_recent_steps: 1
+",
+        )
+        .unwrap();
+        let runtime = AgentRuntime::new(config, Arc::new(LocalStubClient));
+        let run = runtime
+            .run_react(AgentInput {
+                intent: sample_intent(),
+                backlog_size: 0,
+                conversation: Vec::new(),
+                prior_steps: Vec::new(),
+                precedents: Vec::new(),
+            })
+            .await
+            .unwrap();
+
+        let phases: Vec<_> = run
+            .llm_logs
+            .iter()
+            .map(|entry| entry.phase.as_str())
+            .collect();
+        assert_eq!(
+            phases,
+            ["THINK", "THINK", "HISTORY", "THINK", "HISTORY", "FINAL"]
+        );
+        let third = &run.llm_logs[3].prompt;
+        assert!(third.contains("History:\nSummary of steps 1-1: Summary of a 1-line document"));
+        assert!(third.contains("\n2. Thought:"));
+        assert!(!third.contains("1. Thought:"));
+        assert!(
+            run.llm_logs[4]
+                .prompt
+                .contains("Earlier summary: Summary of a 1-line document")
+        );
+        assert!(run.llm_logs[5].prompt.contains("Summary of steps 1-2:"));
+        assert_eq!(run.outcome.steps.len(), 3);
+
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("你好"), 1);
+    }
+
+    #[tokio::test]
+    async fn intents_pick_named_personas() {
+        let config: AgentConfig = serde_yaml::from_str(
+            "max_react_steps: 1
+persona: TelosOps
+default_persona: ops
+personas:
+  ops:
+    prompt: OpsDesk
+  researcher:
+    prompt: DeepDiver
+    max_react_steps: 3
+",
+        )
+        .unwrap();
+        let runtime = AgentRuntime::new(config, Arc::new(LocalStubClient));
+        let run_as = |persona: Option<&str>| {
+            let mut intent = sample_intent();
+            if let Some(persona) = persona {
+                intent
+                    .metadata
+                    .insert(PERSONA_KEY.to_string(), persona.to_string());
+            }
+            runtime.run_react(AgentInput {
+                intent,
+                backlog_size: 0,
+                conversation: Vec::new(),
+                prior_steps: Vec::new(),
+                precedents: Vec::new(),
+            })
+        };
+
+        let run = run_as(Some("researcher")).await.unwrap();
+        assert_eq!(run.outcome.steps.len(), 3);
+        assert!(run.outcome.final_answer.starts_with("DeepDiver completed"));
+
+        for fallback in [None, Some("writer")] {
+            let run = run_as(fallback).await.unwrap();
+            assert_eq!(run.outcome.steps.len(), 1);
+            assert!(run.outcome.final_answer.starts_with("OpsDesk completed"));
+        }
+
+        let run = run_as(Some(DEFAULT_PERSONA_NAME)).await.unwrap();
+        assert!(run.outcome.final_answer.starts_with("TelosOps completed"));
+    }
+
+    #[tokio::test]
+    async fn experiments_alternate_prompt_variants() {
+        let config: AgentConfig = serde_yaml::from_str(
+            "max_react_steps: 1
+persona: TelosOps
+experiment:
+  name: terse-final
+  variants:
+    control: {}
+    terse:
+      final_instructions: Respond with JSON containing final_answer, in one sentence.
+",
+        )
+        .unwrap();
+        let base = PromptVersions::from_config(&AgentConfig {
+            experiment: None,
+            ..config.clone()
+        });
+        let versions = PromptVersions::from_config(&config);
+        let active = versions.experiment.unwrap();
+        assert_eq!(active.variants["control"], base.prompt_version);
+        assert_ne!(active.variants["terse"], base.prompt_version);
+
+        let runtime = AgentRuntime::new(config, Arc::new(LocalStubClient));
+        let mut variants = Vec::new();
+        for _ in 0..3 {
+            let run = runtime
+                .run_react(AgentInput {
+                    intent: sample_intent(),
+                    backlog_size: 0,
+                    conversation: Vec::new(),
+                    prior_steps: Vec::new(),
+                    precedents: Vec::new(),
+                })
+                .await
+                .unwrap();
+            let (experiment, variant) = run.prompts.experiment.clone().unwrap();
+            assert_eq!(experiment, "terse-final");
+            assert_eq!(run.prompts.version, active.variants[&variant]);
+            let final_log = run.llm_logs.last().unwrap();
+            assert!(final_log.prompt.ends_with(&run.prompts.final_instructions));
+            for entry in &run.llm_logs {
+                assert_eq!(entry.prompt_version.as_ref(), Some(&run.prompts.version));
+                assert_eq!(entry.variant.as_ref(), Some(&variant));
+            }
+            variants.push(variant);
+        }
+        assert_eq!(variants, ["control", "terse", "control"]);
     }
 }
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::{AgentConfig, PromptTemplates};
+
+/// Bump when the fixed parts of the THINK or FINAL prompt change in code,
+/// so runs before and after the change get different versions.
+const PROMPT_SKELETON_REVISION: u32 = 1;
+
+const DEFAULT_THINK_INSTRUCTIONS: &str = "Respond with JSON containing thought, action, observation. To ask the user a clarifying question, use action \"ask_user\" and put the question in question.";
+const DEFAULT_FINAL_INSTRUCTIONS: &str = "Respond with JSON containing final_answer.";
+
+/// The prompt templates one run is built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunPrompts {
+    pub think_instructions: String,
+    pub final_instructions: String,
+    /// Short hash of the templates, stamped on the run and its LLM logs.
+    pub version: String,
+    /// `(experiment, variant)` when the run is part of an experiment.
+    pub experiment: Option<(String, String)>,
+}
+
+impl RunPrompts {
+    fn resolve(templates: &PromptTemplates) -> Self {
+        let think_instructions = templates
+            .think_instructions
+            .clone()
+            .unwrap_or_else(|| DEFAULT_THINK_INSTRUCTIONS.to_string());
+        let final_instructions = templates
+            .final_instructions
+            .clone()
+            .unwrap_or_else(|| DEFAULT_FINAL_INSTRUCTIONS.to_string());
+        let mut hasher = Sha256::new();
+        hasher.update(PROMPT_SKELETON_REVISION.to_le_bytes());
+        for part in [&think_instructions, &final_instructions] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+        let version = hex::encode(&hasher.finalize()[..6]);
+        Self {
+            think_instructions,
+            final_instructions,
+            version,
+            experiment: None,
+        }
+    }
+
+    /// The prompts for the `run`th run (counting from 0) under `config`:
+    /// `agent.prompts`, or the experiment's variants in turn.
+    pub fn for_run(config: &AgentConfig, run: usize) -> Self {
+        let Some(experiment) = config
+            .experiment
+            .as_ref()
+            .filter(|experiment| !experiment.variants.is_empty())
+        else {
+            return Self::resolve(&config.prompts);
+        };
+        let (variant, templates) = experiment
+            .variants
+            .iter()
+            .nth(run % experiment.variants.len())
+            .expect("index is within the variants");
+        Self {
+            experiment: Some((experiment.name.clone(), variant.clone())),
+            ..Self::resolve(&templates.or(&config.prompts))
+        }
+    }
+}
+
+/// Prompt versions in effect, for `/api/experiments`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptVersions {
+    /// Version of `agent.prompts`, used outside experiments.
+    pub prompt_version: String,
+    pub experiment: Option<ActiveExperiment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveExperiment {
+    pub name: String,
+    /// Prompt version of each variant.
+    pub variants: BTreeMap<String, String>,
+}
+
+impl PromptVersions {
+    pub fn from_config(config: &AgentConfig) -> Self {
+        let experiment = config
+            .experiment
+            .as_ref()
+            .filter(|experiment| !experiment.variants.is_empty())
+            .map(|experiment| ActiveExperiment {
+                name: experiment.name.clone(),
+                variants: experiment
+                    .variants
+                    .iter()
+                    .map(|(variant, templates)| {
+                        let prompts = RunPrompts::resolve(&templates.or(&config.prompts));
+                        (variant.clone(), prompts.version)
+                    })
+                    .collect(),
+            });
+        Self {
+            prompt_version: RunPrompts::resolve(&config.prompts).version,
+            experiment,
+        }
+    }
+}
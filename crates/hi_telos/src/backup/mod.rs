@@ -0,0 +1,416 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow, bail};
+use chrono::{DateTime, Utc};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::{export, storage};
+
+/// Snapshots live in the data dir but are never part of one.
+pub const BACKUPS_DIR: &str = "backups";
+
+/// First entry of every snapshot; restore refuses archives without it.
+const MANIFEST_NAME: &str = "hi_backup.json";
+const MANIFEST_FORMAT: u32 = 1;
+const STAGING_PREFIX: &str = ".restore-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub created_at: DateTime<Utc>,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub files: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Write `data/backups/hi-data-<timestamp>.tar.gz` with every file under the
+/// data dir except earlier snapshots and restore leftovers.
+pub fn create_snapshot(data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<BackupInfo> {
+    let backups_dir = data_dir.join(BACKUPS_DIR);
+    fs::create_dir_all(&backups_dir)
+        .with_context(|| format!("creating backups dir {:?}", backups_dir))?;
+
+    let files = snapshot_files(data_dir);
+    let manifest = BackupManifest {
+        format: MANIFEST_FORMAT,
+        created_at: now,
+        files: files.len(),
+    };
+
+    let file_name = format!("hi-data-{}.tar.gz", now.format("%Y%m%dT%H%M%S%.3fZ"));
+    let path = backups_dir.join(&file_name);
+    let output = File::create(&path).with_context(|| format!("creating backup {:?}", path))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(output, Compression::default()));
+
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(now.timestamp().max(0) as u64);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, MANIFEST_NAME, manifest_bytes.as_slice())
+        .context("writing backup manifest")?;
+
+    for relative in &files {
+        archive
+            .append_path_with_name(data_dir.join(relative), relative)
+            .with_context(|| format!("adding {:?} to backup", relative))?;
+    }
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("finishing backup {:?}", path))?;
+
+    let size_bytes = fs::metadata(&path)?.len();
+    Ok(BackupInfo {
+        file_name,
+        path,
+        size_bytes,
+        files: files.len(),
+        created_at: now,
+    })
+}
+
+/// Snapshots under `data/backups`, newest first.
+pub fn list_snapshots(data_dir: &Path) -> anyhow::Result<Vec<BackupInfo>> {
+    let backups_dir = data_dir.join(BACKUPS_DIR);
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&backups_dir)
+        .with_context(|| format!("reading backups dir {:?}", backups_dir))?
+    {
+        let path = entry?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !file_name.ends_with(".tar.gz") {
+            continue;
+        }
+        let manifest = read_manifest(&path)?;
+        snapshots.push(BackupInfo {
+            file_name: file_name.to_string(),
+            size_bytes: fs::metadata(&path)?.len(),
+            files: manifest.files,
+            created_at: manifest.created_at,
+            path,
+        });
+    }
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created_at));
+    Ok(snapshots)
+}
+
+/// Resolve a snapshot name from `data/backups`, rejecting anything that is
+/// not a plain file name.
+pub fn snapshot_path(data_dir: &Path, file_name: &str) -> anyhow::Result<PathBuf> {
+    let mut components = Path::new(file_name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if file_name.ends_with(".tar.gz") => {}
+        _ => bail!("invalid backup name {file_name:?}"),
+    }
+    let path = data_dir.join(BACKUPS_DIR).join(file_name);
+    if !path.exists() {
+        bail!("backup {file_name:?} not found");
+    }
+    Ok(path)
+}
+
+/// Replace the data dir with the snapshot in `archive`; see
+/// [`stage_snapshot`] and [`StagedRestore::apply`].
+pub fn restore_snapshot(data_dir: &Path, archive: &[u8]) -> anyhow::Result<BackupManifest> {
+    stage_snapshot(data_dir, archive)?.apply(data_dir)
+}
+
+/// A snapshot unpacked and checked in a staging dir inside the data dir,
+/// not yet swapped in. Dropping it removes the staging dir.
+pub struct StagedRestore {
+    staging: StagingDir,
+    pub manifest: BackupManifest,
+}
+
+/// Unpack `archive` next to the live data, so a bad snapshot is rejected
+/// before anything is replaced.
+pub fn stage_snapshot(data_dir: &Path, archive: impl Read) -> anyhow::Result<StagedRestore> {
+    let spec = ArchiveSpec {
+        kind: "backup",
+        manifest_name: MANIFEST_NAME,
+        staging_prefix: STAGING_PREFIX,
+    };
+    let (staging, manifest, files) = stage_archive(
+        data_dir,
+        archive,
+        &spec,
+        |manifest: &BackupManifest| {
+            if manifest.format != MANIFEST_FORMAT {
+                bail!("unsupported backup format {}", manifest.format);
+            }
+            Ok(())
+        },
+        |_, relative| {
+            Ok(!relative.starts_with(BACKUPS_DIR)
+                && relative != Path::new(storage::DATA_DIR_LOCK_FILE))
+        },
+    )?;
+    if files != manifest.files {
+        bail!(
+            "backup is incomplete: manifest lists {} files, archive has {files}",
+            manifest.files
+        );
+    }
+    Ok(StagedRestore { staging, manifest })
+}
+
+impl StagedRestore {
+    /// Swap the staged files in for everything in the data dir except
+    /// `data/backups` and the instance lock.
+    pub fn apply(self, data_dir: &Path) -> anyhow::Result<BackupManifest> {
+        let staging = self.staging.path();
+        for entry in fs::read_dir(data_dir).with_context(|| format!("reading {:?}", data_dir))? {
+            let entry = entry?;
+            let path = entry.path();
+            let kept = entry.file_name() == BACKUPS_DIR
+                || entry.file_name() == storage::DATA_DIR_LOCK_FILE
+                || path == staging;
+            if kept {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(&path).with_context(|| format!("removing {:?}", path))?;
+            } else {
+                fs::remove_file(&path).with_context(|| format!("removing {:?}", path))?;
+            }
+        }
+        for entry in fs::read_dir(staging).with_context(|| format!("reading {:?}", staging))? {
+            let entry = entry?;
+            let destination = data_dir.join(entry.file_name());
+            fs::rename(entry.path(), &destination)
+                .with_context(|| format!("moving restored {:?}", destination))?;
+        }
+        storage::ensure_data_layout(data_dir)?;
+        Ok(self.manifest)
+    }
+}
+
+/// A staging dir inside the data dir, removed when dropped.
+pub(crate) struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn create(data_dir: &Path, prefix: &str) -> anyhow::Result<Self> {
+        let staging = Self(data_dir.join(format!("{prefix}{}", Uuid::new_v4())));
+        fs::create_dir_all(staging.path())
+            .with_context(|| format!("creating {:?}", staging.path()))?;
+        Ok(staging)
+    }
+
+    /// A staging dir to stream an uploaded archive into before it is
+    /// staged; snapshots leave it out like any other restore leftover.
+    pub(crate) fn for_upload(data_dir: &Path) -> anyhow::Result<Self> {
+        Self::create(data_dir, STAGING_PREFIX)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// What kind of archive [`stage_archive`] is reading.
+pub(crate) struct ArchiveSpec {
+    /// Names the archive in errors.
+    pub kind: &'static str,
+    /// The JSON manifest that must be the first entry.
+    pub manifest_name: &'static str,
+    /// Prefix of the staging dir, which snapshots leave out.
+    pub staging_prefix: &'static str,
+}
+
+/// Unpack a `.tar.gz` into a fresh staging dir under `data_dir`, so a bad
+/// archive is rejected before anything live is touched. `check` validates
+/// the manifest; every later entry must be a regular file with a safe
+/// relative path, and `keep` decides whether it is unpacked or rejects it.
+/// Returns the staging dir, the manifest and the number of files unpacked.
+pub(crate) fn stage_archive<M: DeserializeOwned>(
+    data_dir: &Path,
+    archive: impl Read,
+    spec: &ArchiveSpec,
+    check: impl FnOnce(&M) -> anyhow::Result<()>,
+    mut keep: impl FnMut(&M, &Path) -> anyhow::Result<bool>,
+) -> anyhow::Result<(StagingDir, M, usize)> {
+    let staging = StagingDir::create(data_dir, spec.staging_prefix)?;
+    let kind = spec.kind;
+    let mut entries = tar::Archive::new(GzDecoder::new(archive));
+    let mut check = Some(check);
+    let mut manifest: Option<M> = None;
+    let mut files = 0;
+
+    for entry in entries
+        .entries()
+        .with_context(|| format!("reading {kind} archive"))?
+    {
+        let mut entry = entry.with_context(|| format!("reading {kind} entry"))?;
+        let raw = entry
+            .path()
+            .with_context(|| format!("{kind} entry path"))?
+            .to_string_lossy()
+            .to_string();
+
+        let Some(parsed) = &manifest else {
+            if raw != spec.manifest_name {
+                bail!("not a {kind} archive: {} missing", spec.manifest_name);
+            }
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            let parsed: M = serde_json::from_str(&content)
+                .with_context(|| format!("parsing {kind} manifest"))?;
+            if let Some(check) = check.take() {
+                check(&parsed)?;
+            }
+            manifest = Some(parsed);
+            continue;
+        };
+
+        if !entry.header().entry_type().is_file() {
+            bail!("unexpected entry type in {kind}: {raw}");
+        }
+        let relative = storage::sanitize_data_relative_path(&raw)
+            .with_context(|| format!("unsafe path in {kind}: {raw}"))?;
+        if !keep(parsed, &relative)? {
+            continue;
+        }
+        let destination = staging.path().join(&relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {:?}", parent))?;
+        }
+        entry
+            .unpack(&destination)
+            .with_context(|| format!("unpacking {raw}"))?;
+        files += 1;
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("empty {kind} archive"))?;
+    Ok((staging, manifest, files))
+}
+
+fn read_manifest(path: &Path) -> anyhow::Result<BackupManifest> {
+    let file = File::open(path).with_context(|| format!("opening backup {:?}", path))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut entries = archive.entries()?;
+    let mut first = entries
+        .next()
+        .ok_or_else(|| anyhow!("empty backup {:?}", path))??;
+    let mut content = String::new();
+    first.read_to_string(&mut content)?;
+    serde_json::from_str(&content).with_context(|| format!("parsing manifest of {:?}", path))
+}
+
+fn snapshot_files(data_dir: &Path) -> Vec<String> {
+    WalkDir::new(data_dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(entry.depth() == 1
+                && (name == BACKUPS_DIR
+                    || name == storage::DATA_DIR_LOCK_FILE
+                    || name.starts_with(STAGING_PREFIX)
+                    || name.starts_with(export::STAGING_PREFIX)))
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(data_dir).ok()?;
+            let parts: Vec<&str> = relative
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<_>>()?;
+            Some(parts.join("/"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn snapshot_round_trip_replaces_data_and_keeps_backups() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        fs::write(data_dir.join("intent/queue/a.md"), "queued").unwrap();
+        fs::write(data_dir.join("journals/today.md"), "journal").unwrap();
+
+        let snapshot = create_snapshot(data_dir, Utc::now()).unwrap();
+        assert_eq!(snapshot.files, 2);
+        assert_eq!(list_snapshots(data_dir).unwrap().len(), 1);
+
+        fs::remove_file(data_dir.join("intent/queue/a.md")).unwrap();
+        fs::write(data_dir.join("intent/history/b.md"), "later").unwrap();
+
+        let archive = fs::read(snapshot_path(data_dir, &snapshot.file_name).unwrap()).unwrap();
+        let manifest = restore_snapshot(data_dir, &archive).unwrap();
+        assert_eq!(manifest.files, 2);
+        assert_eq!(
+            fs::read_to_string(data_dir.join("intent/queue/a.md")).unwrap(),
+            "queued"
+        );
+        assert!(!data_dir.join("intent/history/b.md").exists());
+        assert!(data_dir.join("intent/history").is_dir());
+        assert!(snapshot.path.exists());
+
+        assert!(snapshot_path(data_dir, "../etc.tar.gz").is_err());
+    }
+
+    #[test]
+    fn restore_rejects_archives_without_manifest() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        fs::write(data_dir.join("journals/today.md"), "keep").unwrap();
+
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, "journals/today.md", "evil".as_bytes())
+            .unwrap();
+        let bytes = archive.into_inner().unwrap().finish().unwrap();
+
+        assert!(restore_snapshot(data_dir, &bytes).is_err());
+        assert_eq!(
+            fs::read_to_string(data_dir.join("journals/today.md")).unwrap(),
+            "keep"
+        );
+        assert!(fs::read_dir(data_dir).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(STAGING_PREFIX)
+        }));
+    }
+}
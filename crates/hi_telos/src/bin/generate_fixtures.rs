@@ -0,0 +1,56 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use hi_telos::fixtures::{self, FixtureSpec};
+
+const USAGE: &str = "usage: generate_fixtures [target-root] [--intents N] [--days M] [--messages-per-day K] [--seed S]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut spec = FixtureSpec::default();
+    let mut target = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") && target.is_none() {
+            target = Some(PathBuf::from(arg));
+            continue;
+        }
+        if arg == "--help" {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        let value = args
+            .next()
+            .with_context(|| format!("{arg} needs a value\n{USAGE}"))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .with_context(|| format!("{arg} expects a number, got {value:?}"))
+        };
+        match arg.as_str() {
+            "--intents" => spec.intents = number()? as usize,
+            "--days" => spec.days = number()?.try_into().context("--days is too large")?,
+            "--messages-per-day" => spec.messages_per_day = number()? as usize,
+            "--seed" => spec.seed = number()?,
+            _ => bail!("unexpected argument {arg:?}\n{USAGE}"),
+        }
+    }
+    let target = match target {
+        Some(path) => path,
+        None => env::current_dir().context("resolving current directory")?,
+    };
+
+    // A bare directory gets the core config so the server can start on it.
+    if !target.join("config").exists() {
+        fixtures::install_core_fixture(&target)?;
+    }
+    let report = fixtures::generate_fixture_data(&target.join("data"), &spec).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    println!(
+        "Generated fixture data under {:?}. Set HI_APP_ROOT to {:?} to browse it.",
+        target.join("data"),
+        target
+    );
+    Ok(())
+}
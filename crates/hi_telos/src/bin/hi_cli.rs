@@ -0,0 +1,548 @@
+//! Command-line companion for a running hi_telos server. Every command is a
+//! thin wrapper over one HTTP API call; `--json` prints the raw response.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use anyhow::{Context, Result, bail};
+use reqwest::{Client, Method, RequestBuilder};
+use serde_json::{Value, json};
+
+const USAGE: &str = "\
+usage: hi_cli [--url URL] [--json] <command>
+
+commands:
+  intent new <summary> [--body TEXT] [--source NAME] [--alignment 0..1]
+                       [--priority low|normal|high] [--due RFC3339]
+  intent list [--stage STAGE] [--overdue]
+  logs tail [--limit N] [--level PHASE] [--model NAME] [--follow]
+  memory search <text> [--level L1|L2] [--tag TAG] [--limit N]
+  beat
+
+The server address defaults to $HI_URL, then http://127.0.0.1:8080.";
+
+const DEFAULT_URL: &str = "http://127.0.0.1:8080";
+
+/// Options that never take a value.
+const SWITCHES: &[&str] = &["json", "overdue", "follow", "help"];
+
+#[derive(Debug, PartialEq)]
+struct Cli {
+    base_url: String,
+    json: bool,
+    command: Command,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    IntentNew {
+        summary: String,
+        body: Option<String>,
+        source: Option<String>,
+        alignment: Option<f32>,
+        priority: Option<String>,
+        due_at: Option<String>,
+    },
+    IntentList {
+        stage: Option<String>,
+        overdue: bool,
+    },
+    LogsTail {
+        limit: usize,
+        level: Option<String>,
+        model: Option<String>,
+        follow: bool,
+    },
+    MemorySearch {
+        text: String,
+        level: Option<String>,
+        tag: Option<String>,
+        limit: Option<usize>,
+    },
+    Beat,
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: HashSet<String>,
+}
+
+impl Args {
+    fn split(raw: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut args = Args::default();
+        let mut raw = raw.into_iter();
+        while let Some(arg) = raw.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                args.positional.push(arg);
+                continue;
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                args.options.insert(name.to_string(), value.to_string());
+            } else if SWITCHES.contains(&name) {
+                args.switches.insert(name.to_string());
+            } else {
+                let value = raw
+                    .next()
+                    .with_context(|| format!("--{name} needs a value"))?;
+                args.options.insert(name.to_string(), value);
+            }
+        }
+        Ok(args)
+    }
+
+    /// Reject options the command does not understand, so typos are not
+    /// silently ignored.
+    fn only(&self, allowed: &[&str]) -> Result<()> {
+        let unknown = self
+            .options
+            .keys()
+            .chain(self.switches.iter())
+            .find(|name| {
+                !allowed.contains(&name.as_str()) && !["url", "json"].contains(&name.as_str())
+            });
+        match unknown {
+            Some(name) => bail!("unknown option --{name}"),
+            None => Ok(()),
+        }
+    }
+
+    fn option(&self, name: &str) -> Option<String> {
+        self.options.get(name).cloned()
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.options
+            .get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("invalid value for --{name}: {value}"))
+            })
+            .transpose()
+    }
+
+    fn rest(&self, from: usize, what: &str) -> Result<String> {
+        let text = self.positional.get(from..).unwrap_or_default().join(" ");
+        if text.trim().is_empty() {
+            bail!("missing {what}");
+        }
+        Ok(text)
+    }
+}
+
+fn parse(raw: impl IntoIterator<Item = String>, env_url: Option<String>) -> Result<Cli> {
+    let args = Args::split(raw)?;
+    if args.switches.contains("help") || args.positional.is_empty() {
+        bail!("{USAGE}");
+    }
+
+    let words: Vec<&str> = args.positional.iter().map(String::as_str).collect();
+    let command = match words.as_slice() {
+        ["intent", "new", ..] => {
+            args.only(&["body", "source", "alignment", "priority", "due"])?;
+            Command::IntentNew {
+                summary: args.rest(2, "intent summary")?,
+                body: args.option("body"),
+                source: args.option("source"),
+                alignment: args.parsed("alignment")?,
+                priority: args.option("priority"),
+                due_at: args.option("due"),
+            }
+        }
+        ["intent", "list"] => {
+            args.only(&["stage", "overdue"])?;
+            Command::IntentList {
+                stage: args.option("stage"),
+                overdue: args.switches.contains("overdue"),
+            }
+        }
+        ["logs", "tail"] => {
+            args.only(&["limit", "level", "model", "follow"])?;
+            Command::LogsTail {
+                limit: args.parsed("limit")?.unwrap_or(20),
+                level: args.option("level"),
+                model: args.option("model"),
+                follow: args.switches.contains("follow"),
+            }
+        }
+        ["memory", "search", ..] => {
+            args.only(&["level", "tag", "limit"])?;
+            Command::MemorySearch {
+                text: args.rest(2, "search text")?,
+                level: args.option("level"),
+                tag: args.option("tag"),
+                limit: args.parsed("limit")?,
+            }
+        }
+        ["beat"] => {
+            args.only(&[])?;
+            Command::Beat
+        }
+        _ => bail!("unknown command: {}\n\n{USAGE}", words.join(" ")),
+    };
+
+    let base_url = args
+        .option("url")
+        .or(env_url)
+        .unwrap_or_else(|| DEFAULT_URL.to_string());
+    Ok(Cli {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        json: args.switches.contains("json"),
+        command,
+    })
+}
+
+struct Api {
+    client: Client,
+    base_url: String,
+}
+
+impl Api {
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.base_url, path))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("connecting to {}", self.base_url))?;
+        let status = response.status();
+        let url = response.url().path().to_string();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("{url} failed: HTTP {status} {}", body.trim());
+        }
+        response
+            .json()
+            .await
+            .with_context(|| format!("decoding response from {url}"))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = match parse(env::args().skip(1), env::var("HI_URL").ok()) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    let api = Api {
+        client: Client::new(),
+        base_url: cli.base_url.clone(),
+    };
+
+    match cli.command {
+        Command::IntentNew {
+            summary,
+            body,
+            source,
+            alignment,
+            priority,
+            due_at,
+        } => {
+            let mut payload = json!({ "summary": summary });
+            for (key, value) in [
+                ("body", body),
+                ("source", source),
+                ("priority", priority),
+                ("due_at", due_at),
+            ] {
+                if let Some(value) = value {
+                    payload[key] = json!(value);
+                }
+            }
+            if let Some(alignment) = alignment {
+                payload["telos_alignment"] = json!(alignment);
+            }
+            let created = api
+                .send(api.request(Method::POST, "/api/intents").json(&payload))
+                .await?;
+            if cli.json {
+                print_json(&created)?;
+            } else {
+                println!("created {}", text(&created["id"]));
+                println!("path: {}", text(&created["path"]));
+                println!("beat scheduled: {}", created["beat_scheduled"]);
+            }
+        }
+        Command::IntentList { stage, overdue } => {
+            let mut query = Vec::new();
+            if let Some(stage) = stage {
+                query.push(("stage", stage));
+            }
+            if overdue {
+                query.push(("overdue", "true".to_string()));
+            }
+            let listed = api
+                .send(api.request(Method::GET, "/api/intents").query(&query))
+                .await?;
+            if cli.json {
+                print_json(&listed)?;
+            } else {
+                let intents = listed["intents"].as_array().cloned().unwrap_or_default();
+                if intents.is_empty() {
+                    println!("no intents");
+                }
+                for intent in intents {
+                    println!(
+                        "{:<16} {} {:.2} {}{}",
+                        text(&intent["stage"]),
+                        text(&intent["id"]),
+                        intent["telos_alignment"].as_f64().unwrap_or_default(),
+                        text(&intent["summary"]),
+                        if intent["overdue"] == true {
+                            " (overdue)"
+                        } else {
+                            ""
+                        },
+                    );
+                }
+            }
+        }
+        Command::LogsTail {
+            limit,
+            level,
+            model,
+            follow,
+        } => {
+            let mut filters = Vec::new();
+            if let Some(level) = level {
+                filters.push(("level", level));
+            }
+            if let Some(model) = model {
+                filters.push(("model", model));
+            }
+
+            let mut query = filters.clone();
+            query.push(("limit", limit.to_string()));
+            let page = api
+                .send(api.request(Method::GET, "/api/logs/llm").query(&query))
+                .await?;
+            let mut entries = page["entries"].as_array().cloned().unwrap_or_default();
+            // The API pages newest first; a tail reads oldest to newest.
+            entries.reverse();
+            for entry in &entries {
+                print_log(entry, cli.json)?;
+            }
+
+            if follow {
+                follow_logs(&api, &filters, cli.json).await?;
+            }
+        }
+        Command::MemorySearch {
+            text: needle,
+            level,
+            tag,
+            limit,
+        } => {
+            let mut query = vec![("q", needle)];
+            if let Some(level) = level {
+                query.push(("level", level));
+            }
+            if let Some(tag) = tag {
+                query.push(("tag", tag));
+            }
+            if let Some(limit) = limit {
+                query.push(("limit", limit.to_string()));
+            }
+            let found = api
+                .send(api.request(Method::GET, "/api/memory").query(&query))
+                .await?;
+            if cli.json {
+                print_json(&found)?;
+            } else {
+                let entries = found["entries"].as_array().cloned().unwrap_or_default();
+                if entries.is_empty() {
+                    println!("no matching memories");
+                }
+                for entry in entries {
+                    let tags: Vec<&str> = entry["tags"]
+                        .as_array()
+                        .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+                        .unwrap_or_default();
+                    println!(
+                        "{} {} {}",
+                        text(&entry["created_at"]).get(..10).unwrap_or_default(),
+                        text(&entry["id"]),
+                        text(&entry["summary"]),
+                    );
+                    if !tags.is_empty() {
+                        println!("    tags: {}", tags.join(", "));
+                    }
+                }
+            }
+        }
+        Command::Beat => {
+            let result = api.send(api.request(Method::POST, "/api/beat")).await?;
+            if cli.json {
+                print_json(&result)?;
+            } else {
+                println!("beat scheduled");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print `llm_log` events from the live stream until the server hangs up.
+async fn follow_logs(api: &Api, filters: &[(&str, String)], json: bool) -> Result<()> {
+    let mut response = api
+        .request(Method::GET, "/api/logs/llm/stream")
+        .query(filters)
+        .send()
+        .await
+        .with_context(|| format!("connecting to {}", api.base_url))?
+        .error_for_status()
+        .context("opening log stream")?;
+
+    let mut buffer = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("reading log stream")? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+            let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+            let Some((event, data)) = parse_sse_frame(&String::from_utf8_lossy(&frame)) else {
+                continue;
+            };
+            match event.as_str() {
+                "llm_log" => {
+                    let entry: Value =
+                        serde_json::from_str(&data).context("decoding streamed log entry")?;
+                    print_log(&entry, json)?;
+                }
+                "lagged" => eprintln!("(skipped {data} entries)"),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `(event, data)` of one server-sent event; comment-only frames such as
+/// keep-alives yield `None`.
+fn parse_sse_frame(frame: &str) -> Option<(String, String)> {
+    let mut event = "message".to_string();
+    let mut data = Vec::new();
+    for line in frame.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    (!data.is_empty()).then(|| (event, data.join("\n")))
+}
+
+fn print_log(entry: &Value, json: bool) -> Result<()> {
+    if json {
+        // One entry per line, so `--follow --json` pipes as JSON lines.
+        println!("{}", serde_json::to_string(entry)?);
+        return Ok(());
+    }
+    let model = entry["model"].as_str().unwrap_or("-");
+    println!(
+        "{} [{}] {}/{} run={}",
+        text(&entry["timestamp"]),
+        text(&entry["phase"]),
+        text(&entry["provider"]),
+        model,
+        text(&entry["run_id"]),
+    );
+    println!("    {}", preview(text(&entry["response"]), 160));
+    Ok(())
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn text(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+/// First line of `value`, cut to at most `max` characters; `…` marks a cut
+/// or hidden lines.
+fn preview(value: &str, max: usize) -> String {
+    let line = value.lines().next().unwrap_or_default();
+    if value.lines().nth(1).is_some() {
+        hi_telos::text::truncate(&format!("{line}…"), max)
+    } else {
+        hi_telos::text::truncate(line, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_global_flags() {
+        let cli = parse(
+            args("--json intent new Draft the weekly report --priority high --alignment=0.8"),
+            None,
+        )
+        .expect("intent new");
+        assert!(cli.json);
+        assert_eq!(cli.base_url, DEFAULT_URL);
+        assert_eq!(
+            cli.command,
+            Command::IntentNew {
+                summary: "Draft the weekly report".to_string(),
+                body: None,
+                source: None,
+                alignment: Some(0.8),
+                priority: Some("high".to_string()),
+                due_at: None,
+            }
+        );
+
+        let cli = parse(
+            args("logs tail --follow --url http://hi.local:9000/"),
+            Some("http://ignored".to_string()),
+        )
+        .expect("logs tail");
+        assert_eq!(cli.base_url, "http://hi.local:9000");
+        assert_eq!(
+            cli.command,
+            Command::LogsTail {
+                limit: 20,
+                level: None,
+                model: None,
+                follow: true,
+            }
+        );
+
+        let cli = parse(args("beat"), Some("http://env:1".to_string())).expect("beat");
+        assert_eq!(cli.base_url, "http://env:1");
+        assert_eq!(cli.command, Command::Beat);
+    }
+
+    #[test]
+    fn rejects_bad_invocations() {
+        assert!(parse(args(""), None).is_err());
+        assert!(parse(args("intent new"), None).is_err());
+        assert!(parse(args("intent list --follow"), None).is_err());
+        assert!(parse(args("logs tail --limit many"), None).is_err());
+        assert!(parse(args("memory search roadmap --tag"), None).is_err());
+        assert!(parse(args("memory forget"), None).is_err());
+    }
+
+    #[test]
+    fn parses_sse_frames() {
+        assert_eq!(
+            parse_sse_frame("event: llm_log\ndata: {\"a\":1}\n\n"),
+            Some(("llm_log".to_string(), "{\"a\":1}".to_string()))
+        );
+        assert_eq!(parse_sse_frame(": keep-alive\n\n"), None);
+    }
+}
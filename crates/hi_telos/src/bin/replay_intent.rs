@@ -0,0 +1,80 @@
+use std::env;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use hi_telos::{
+    config::AppConfig,
+    storage::{self, IntentRecord},
+    tasks::{LLM_MODEL_KEY, LLM_PROVIDER_KEY},
+};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: replay_intent <intent-id | intent/history file> [--provider local_stub|openai] [--model NAME]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut target = None;
+    let mut pins = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let key = match arg.as_str() {
+            "--provider" => LLM_PROVIDER_KEY,
+            "--model" => LLM_MODEL_KEY,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ if target.is_none() && !arg.starts_with("--") => {
+                target = Some(arg);
+                continue;
+            }
+            _ => bail!("unexpected argument {arg:?}\n{USAGE}"),
+        };
+        let value = args
+            .next()
+            .with_context(|| format!("{arg} needs a value\n{USAGE}"))?;
+        pins.push((key, value));
+    }
+    let Some(target) = target else {
+        bail!("{USAGE}");
+    };
+
+    let config = AppConfig::load().context("loading config; is HI_APP_ROOT set?")?;
+    let record = find_archived(&config.data_dir, &target)?;
+    let pins: Vec<(&str, &str)> = pins
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect();
+    let replayed = storage::replay_intent(&config.data_dir, &record, &pins).await?;
+
+    println!(
+        "Replaying {} as {} at {:?}. It runs on the next beat; `hi_cli beat` triggers one now.",
+        record.intent.id, replayed.id, replayed.path
+    );
+    Ok(())
+}
+
+/// Resolve `target`, an intent id or a file path, to an intent in
+/// `intent/history`.
+fn find_archived(data_dir: &Path, target: &str) -> Result<IntentRecord> {
+    if let Ok(id) = target.parse::<Uuid>() {
+        return match storage::find_intent(data_dir, id)? {
+            Some(("history", record)) => Ok(record),
+            Some((stage, _)) => bail!("intent {id} is in {stage}, not history"),
+            None => bail!("no intent with id {id}"),
+        };
+    }
+
+    let wanted = Path::new(target)
+        .canonicalize()
+        .with_context(|| format!("resolving {target}"))?;
+    storage::scan_history(data_dir)?
+        .into_iter()
+        .find(|record| record.path.canonicalize().is_ok_and(|path| path == wanted))
+        .with_context(|| {
+            format!(
+                "{target} is not an intent in {:?}",
+                data_dir.join("intent/history")
+            )
+        })
+}
@@ -0,0 +1,434 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use reqwest::Client;
+use tracing::warn;
+
+use crate::{
+    config::{AppConfig, CalendarConfig, CalendarSourceConfig},
+    sources::{IngestMode, IngestSource},
+    state::AppContext,
+    storage::{self, IntentDraft},
+    text,
+};
+
+pub const CALENDAR_SOURCE: &str = "calendar";
+pub const CALENDAR_URL_KEY: &str = "calendar_url";
+pub const EVENT_UID_KEY: &str = "calendar_event_uid";
+pub const EVENT_START_KEY: &str = "calendar_event_start";
+
+const CALENDAR_STATE_NAMESPACE: &str = "calendar";
+const CALENDAR_SUMMARY_MAX_CHARS: usize = 80;
+
+/// Bound on fetching one calendar feed.
+const CALENDAR_TIMEOUT_SECS: u64 = 30;
+
+/// A `VEVENT` reduced to what a preparation intent needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub cancelled: bool,
+}
+
+/// Polls the iCalendar feeds in `config/calendar.yml` for upcoming events.
+#[derive(Debug)]
+pub struct CalendarSource {
+    client: Client,
+}
+
+impl Default for CalendarSource {
+    fn default() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(CALENDAR_TIMEOUT_SECS))
+            .build()
+            .expect("building calendar client");
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl IngestSource for CalendarSource {
+    fn name(&self) -> &'static str {
+        "calendar"
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        let calendar = config
+            .calendar
+            .as_ref()
+            .filter(|calendar| !calendar.calendars.is_empty())?;
+        Some(IngestMode::Poll {
+            interval_secs: calendar.poll_interval_minutes.max(1) * 60,
+        })
+    }
+
+    async fn poll(&self, ctx: &AppContext) -> anyhow::Result<usize> {
+        let config = ctx.config();
+        let Some(calendar) = &config.calendar else {
+            return Ok(0);
+        };
+        Ok(poll_calendars(&self.client, &config.data_dir, calendar, ctx.now()).await)
+    }
+}
+
+/// Poll every configured calendar once and create intents for events that
+/// start within their lead time. Returns the number of intents created.
+pub async fn poll_calendars(
+    client: &Client,
+    data_dir: &Path,
+    config: &CalendarConfig,
+    now: DateTime<Utc>,
+) -> usize {
+    let mut created = 0;
+    for calendar in &config.calendars {
+        match poll_calendar(client, data_dir, config, calendar, now).await {
+            Ok(count) => created += count,
+            Err(err) => warn!(url = %calendar.url, error = ?err, "failed to poll calendar"),
+        }
+    }
+    created
+}
+
+async fn poll_calendar(
+    client: &Client,
+    data_dir: &Path,
+    config: &CalendarConfig,
+    calendar: &CalendarSourceConfig,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let response = client
+        .get(&calendar.url)
+        .send()
+        .await
+        .with_context(|| format!("fetching calendar {}", calendar.url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("calendar returned status {}", response.status()));
+    }
+    let ics = response
+        .text()
+        .await
+        .with_context(|| format!("reading calendar body {}", calendar.url))?;
+    let (name, events) = parse_ical(&ics);
+    let name = calendar
+        .name
+        .clone()
+        .or(name)
+        .unwrap_or_else(|| calendar.url.clone());
+    let lead = chrono::Duration::minutes(
+        calendar
+            .lead_time_minutes
+            .unwrap_or(config.lead_time_minutes)
+            .max(0),
+    );
+
+    let mut seen = storage::load_seen_state(data_dir, CALENDAR_STATE_NAMESPACE)?;
+    let mut created = 0;
+    for event in events {
+        if event.cancelled || event.start <= now || event.start - lead > now {
+            continue;
+        }
+        // Keyed by UID plus start so each occurrence of a recurring series,
+        // or a rescheduled meeting, gets its own intent.
+        let key = format!("{}@{}", event.uid, event.start.to_rfc3339());
+        if seen.contains(&calendar.url, &key) {
+            continue;
+        }
+        let draft = event_intent(&calendar.url, &name, config.telos_alignment, &event);
+        storage::persist_intent(data_dir, &draft).await?;
+        seen.insert(&calendar.url, &key);
+        created += 1;
+    }
+    storage::save_seen_state(data_dir, CALENDAR_STATE_NAMESPACE, &seen)?;
+    Ok(created)
+}
+
+fn event_intent(
+    calendar_url: &str,
+    calendar_name: &str,
+    telos_alignment: f32,
+    event: &CalendarEvent,
+) -> IntentDraft {
+    let title = event.summary.trim();
+    let start = event.start.format("%Y-%m-%d %H:%M UTC");
+    let summary = text::truncate(
+        &format!(
+            "Prepare for {} at {}",
+            title,
+            event.start.format("%H:%M UTC")
+        ),
+        CALENDAR_SUMMARY_MAX_CHARS,
+    );
+
+    let body = format!(
+        "## Prepare for {title}\n\nCalendar: {calendar_name}\nStarts: {start}\nLocation: {location}\n\n{description}\n\nGather what is needed beforehand: agenda, open questions, related notes and follow-ups.",
+        location = event.location.as_deref().unwrap_or("(none)"),
+        description = event.description.as_deref().unwrap_or("(no description)"),
+    );
+
+    let metadata = BTreeMap::from([
+        (CALENDAR_URL_KEY.to_string(), calendar_url.to_string()),
+        (EVENT_UID_KEY.to_string(), event.uid.clone()),
+        (EVENT_START_KEY.to_string(), event.start.to_rfc3339()),
+    ]);
+
+    IntentDraft {
+        source: CALENDAR_SOURCE.to_string(),
+        summary,
+        telos_alignment,
+        body,
+        due_at: Some(event.start),
+        metadata,
+    }
+}
+
+/// Parse the `VEVENT`s of an iCalendar document along with its `X-WR-CALNAME`.
+/// Events without a UID or a parseable DTSTART are skipped. Times with a
+/// `TZID` or no zone at all are read in the server's local timezone; recurrence
+/// rules are not expanded.
+pub fn parse_ical(ics: &str) -> (Option<String>, Vec<CalendarEvent>) {
+    let mut name = None;
+    let mut events = Vec::new();
+    let mut current: Option<BTreeMap<String, (Vec<String>, String)>> = None;
+
+    for line in unfold_lines(ics) {
+        let Some((prop, params, value)) = split_property(&line) else {
+            continue;
+        };
+        match (prop.as_str(), value.as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(BTreeMap::new()),
+            ("END", "VEVENT") => {
+                if let Some(props) = current.take()
+                    && let Some(event) = build_event(&props)
+                {
+                    events.push(event);
+                }
+            }
+            ("X-WR-CALNAME", _) if current.is_none() => name = Some(unescape(&value)),
+            _ => {
+                if let Some(props) = current.as_mut() {
+                    props.entry(prop).or_insert((params, value));
+                }
+            }
+        }
+    }
+
+    (name, events)
+}
+
+fn build_event(props: &BTreeMap<String, (Vec<String>, String)>) -> Option<CalendarEvent> {
+    let uid = props
+        .get("UID")
+        .map(|(_, value)| value.trim().to_string())?;
+    let (params, raw_start) = props.get("DTSTART")?;
+    let start = parse_ical_datetime(params, raw_start)?;
+    let text = |key: &str| {
+        props
+            .get(key)
+            .map(|(_, value)| unescape(value))
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    Some(CalendarEvent {
+        uid,
+        summary: text("SUMMARY").unwrap_or_else(|| "(untitled event)".to_string()),
+        start,
+        location: text("LOCATION"),
+        description: text("DESCRIPTION"),
+        cancelled: props
+            .get("STATUS")
+            .is_some_and(|(_, status)| status.eq_ignore_ascii_case("CANCELLED")),
+    })
+}
+
+/// RFC 5545 folds long lines by starting continuation lines with a space or
+/// tab.
+fn unfold_lines(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in ics.lines() {
+        let raw = raw.trim_end_matches('\r');
+        if let Some(continuation) = raw.strip_prefix([' ', '\t'])
+            && let Some(last) = lines.last_mut()
+        {
+            last.push_str(continuation);
+        } else if !raw.is_empty() {
+            lines.push(raw.to_string());
+        }
+    }
+    lines
+}
+
+/// Split `NAME;PARAM=a;PARAM=b:value`, ignoring colons inside quoted params.
+fn split_property(line: &str) -> Option<(String, Vec<String>, String)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(idx, ch)| match ch {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(idx),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let prop = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts.map(|param| param.trim().to_string()).collect();
+    Some((prop, params, value.to_string()))
+}
+
+fn parse_ical_datetime(params: &[String], value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    let is_date = params
+        .iter()
+        .any(|param| param.eq_ignore_ascii_case("VALUE=DATE"))
+        || value.len() == 8;
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return local_to_utc(date.and_hms_opt(0, 0, 0)?);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(naive.and_utc());
+    }
+    local_to_utc(NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?)
+}
+
+fn local_to_utc(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use tempfile::TempDir;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+X-WR-CALNAME:Work\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup-1\r\n\
+DTSTART:20250115T140000Z\r\n\
+SUMMARY:Team sync\r\n\
+LOCATION:Room 4\\, 2nd floor\r\n\
+DESCRIPTION:Discuss the roadmap\\nand hiring\r\n\
+\x20 plans\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:later-1\r\n\
+DTSTART:20250115T180000Z\r\n\
+SUMMARY:Dinner\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:cancelled-1\r\n\
+DTSTART:20250115T143000Z\r\n\
+SUMMARY:Cancelled review\r\n\
+STATUS:CANCELLED\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn parses_events_with_folding_and_escapes() {
+        let (name, events) = parse_ical(ICS);
+        assert_eq!(name.as_deref(), Some("Work"));
+        assert_eq!(events.len(), 3);
+
+        let sync = &events[0];
+        assert_eq!(sync.uid, "standup-1");
+        assert_eq!(sync.summary, "Team sync");
+        assert_eq!(sync.location.as_deref(), Some("Room 4, 2nd floor"));
+        assert_eq!(
+            sync.description.as_deref(),
+            Some("Discuss the roadmap\nand hiring plans")
+        );
+        assert_eq!(
+            sync.start,
+            "2025-01-15T14:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(events[2].cancelled);
+
+        assert!(
+            split_property("DTSTART;TZID=\"A:B\":20250101T000000").is_some_and(
+                |(prop, params, value)| prop == "DTSTART"
+                    && params == ["TZID=\"A:B\""]
+                    && value == "20250101T000000"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn creates_intents_within_lead_time_once() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/work.ics");
+                then.status(200).body(ICS);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = CalendarConfig {
+            poll_interval_minutes: 15,
+            lead_time_minutes: 60,
+            telos_alignment: 0.9,
+            calendars: vec![CalendarSourceConfig {
+                url: server.url("/work.ics"),
+                name: None,
+                lead_time_minutes: None,
+            }],
+        };
+        let client = Client::new();
+
+        let too_early = "2025-01-15T12:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            poll_calendars(&client, data_dir, &config, too_early).await,
+            0
+        );
+
+        let now = "2025-01-15T13:15:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(poll_calendars(&client, data_dir, &config, now).await, 1);
+        let inbox = storage::scan_inbox(data_dir).unwrap();
+        assert_eq!(inbox.len(), 1);
+        let intent = &inbox[0].intent;
+        assert_eq!(intent.source, CALENDAR_SOURCE);
+        assert_eq!(intent.summary, "Prepare for Team sync at 14:00 UTC");
+        assert_eq!(
+            intent.due_at,
+            Some("2025-01-15T14:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(
+            intent.metadata.get(EVENT_UID_KEY).map(String::as_str),
+            Some("standup-1")
+        );
+
+        let later = "2025-01-15T13:45:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(poll_calendars(&client, data_dir, &config, later).await, 0);
+    }
+}
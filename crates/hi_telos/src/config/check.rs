@@ -0,0 +1,830 @@
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{orchestrator::JobSchedule, storage};
+
+use super::{
+    AcceptanceConfig, AgentConfig, BeatConfig, CalendarConfig, ConfigOverrides, DiskConfig,
+    EmailConfig, FeedsConfig, GithubConfig, JobsConfig, LlmCacheConfig, LlmProviderConfig,
+    LlmRecordingConfig, LlmRecordingMode, NotificationChannel, NotificationsConfig,
+    ObjectStorageConfig, OutboxConfig, RetentionConfig, SearchProvider, SourcesConfig,
+    TelegramConfig, TelegramMode, ToolsConfig, UiConfig, WebhooksConfig, WorkspacesConfig,
+    validate_workspace_name,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckLevel {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for CheckLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckLevel::Ok => "ok",
+            CheckLevel::Warning => "warning",
+            CheckLevel::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFinding {
+    pub level: CheckLevel,
+    /// Config file stem, e.g. `beat` for `config/beat.yml`.
+    pub section: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigCheckReport {
+    pub findings: Vec<ConfigFinding>,
+}
+
+impl ConfigCheckReport {
+    pub fn errors(&self) -> usize {
+        self.count(CheckLevel::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(CheckLevel::Warning)
+    }
+
+    fn count(&self, level: CheckLevel) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.level == level)
+            .count()
+    }
+}
+
+/// Load every file under `root/config` the way the server would (with the
+/// env overrides in `vars`) and check what deserializing alone cannot:
+/// value ranges, that referenced secret env vars are set, and Telegram
+/// settings the Bot API would reject. Nothing is written.
+pub fn check_config(root: &Path, vars: &BTreeMap<String, String>) -> ConfigCheckReport {
+    let mut checker = Checker {
+        dir: &root.join("config"),
+        root,
+        vars,
+        overrides: ConfigOverrides::default(),
+        report: ConfigCheckReport::default(),
+    };
+    match ConfigOverrides::from_vars(vars.clone()) {
+        Ok(overrides) => checker.overrides = overrides,
+        Err(err) => checker.error("env", format!("{err:#}")),
+    }
+    let unknown: Vec<String> = checker
+        .overrides
+        .unknown_sections()
+        .map(str::to_string)
+        .collect();
+    for section in unknown {
+        checker.warn("env", format!("overrides for unknown section {section:?}"));
+    }
+
+    let mut approval_sources = Vec::new();
+    let mut watchdog_channels = Vec::new();
+    let mut agent_config = None;
+    checker.section("beat", true, |c, beat: BeatConfig| {
+        approval_sources = beat.approval.sources.clone();
+        watchdog_channels = beat.watchdog.channels.clone();
+        c.positive("beat", "interval_minutes", beat.interval_minutes);
+        c.positive(
+            "beat",
+            "watchdog.missed_beats",
+            u64::from(beat.watchdog.missed_beats),
+        );
+        c.unit("beat", "intent_threshold", beat.intent_threshold);
+        if let Some(review) = &beat.weekly_review {
+            c.unit(
+                "beat",
+                "weekly_review.telos_alignment",
+                review.telos_alignment,
+            );
+        }
+        if beat.approval.max_cost_estimate.is_some_and(|max| max < 0.0) {
+            c.error("beat", "approval.max_cost_estimate must not be negative");
+        }
+        if beat.approval.max_estimated_tokens == Some(0)
+            || beat.approval.daily_estimated_tokens == Some(0)
+        {
+            c.warn(
+                "beat",
+                "a token ceiling of 0 holds every intent for approval",
+            );
+        }
+    });
+    checker.section("agent", true, |c, agent: AgentConfig| {
+        agent_config = Some(agent.clone());
+        c.positive("agent", "max_react_steps", agent.max_react_steps as u64);
+        if agent.persona.trim().is_empty() {
+            c.error("agent", "persona must not be empty");
+        }
+        c.positive("agent", "session.max_turns", agent.session.max_turns as u64);
+        if agent.session.window_minutes <= 0 {
+            c.error("agent", "session.window_minutes must be above 0");
+        }
+        if let Err(err) = agent.persona_for(None) {
+            c.error("agent", format!("default_persona: {err:#}"));
+        }
+        for (name, persona) in &agent.personas {
+            if persona.prompt.trim().is_empty() {
+                c.error("agent", format!("personas.{name}.prompt must not be empty"));
+            }
+            if persona.max_react_steps == Some(0) {
+                c.error(
+                    "agent",
+                    format!("personas.{name}.max_react_steps must be above 0"),
+                );
+            }
+        }
+        if let Some(experiment) = &agent.experiment {
+            if experiment.name.trim().is_empty() {
+                c.error("agent", "experiment.name must not be empty");
+            }
+            if experiment.variants.len() < 2 {
+                c.error("agent", "experiment.variants needs at least two variants");
+            }
+        }
+        c.unit(
+            "agent",
+            "sp_recall.min_similarity",
+            agent.sp_recall.min_similarity as f32,
+        );
+    });
+    checker.section("llm", true, |c, llm: LlmProviderConfig| {
+        let mut llm = llm;
+        if let Err(err) = llm.resolve_secrets(c.root) {
+            c.error("llm", format!("{err:#}"));
+        }
+        if let LlmProviderConfig::OpenAi {
+            model,
+            api_key_env,
+            api_key_file,
+            api_key_command,
+            base_url,
+            params,
+            models,
+            ..
+        } = &llm
+        {
+            if model.trim().is_empty() {
+                c.error("llm", "model must not be empty");
+            }
+            if api_key_file.is_none() && api_key_command.is_none() {
+                c.env("llm", "api_key_env", api_key_env);
+            }
+            if let Some(base_url) = base_url {
+                c.url("llm", "base_url", base_url);
+            }
+            let overrides = models
+                .iter()
+                .map(|(model, params)| (format!("models.{model}."), params));
+            for (prefix, params) in
+                std::iter::once((String::new(), params.as_ref())).chain(overrides)
+            {
+                if let Some(temperature) = params.temperature
+                    && !(0.0..=2.0).contains(&temperature)
+                {
+                    c.error(
+                        "llm",
+                        format!("{prefix}temperature must be between 0 and 2, got {temperature}"),
+                    );
+                }
+                if params.max_tokens == Some(0) {
+                    c.error("llm", format!("{prefix}max_tokens must be above 0"));
+                }
+            }
+        }
+    });
+    checker.section(
+        "llm_recording",
+        false,
+        |c, recording: LlmRecordingConfig| {
+            let dir = match &recording.dir {
+                Some(dir) => c.root.join(dir),
+                None => c.root.join("data/llm_recordings"),
+            };
+            if recording.mode == LlmRecordingMode::Replay && !dir.exists() {
+                c.warn(
+                    "llm_recording",
+                    format!("mode is replay but {dir:?} has no recordings yet"),
+                );
+            }
+        },
+    );
+    checker.section("llm_cache", false, |c, cache: LlmCacheConfig| {
+        c.positive("llm_cache", "ttl_secs", cache.ttl_secs);
+    });
+
+    let mut telegram_default_chat = None;
+    checker.section("telegram", false, |c, mut telegram: TelegramConfig| {
+        telegram_default_chat = Some(telegram.default_chat_id);
+        match telegram.resolve_secrets(c.root) {
+            Ok(()) => check_telegram(c, &telegram),
+            Err(err) => c.error("telegram", format!("{err:#}")),
+        }
+    });
+    checker.section("email", false, |c, email: EmailConfig| {
+        if let Some(imap) = &email.imap {
+            c.env("email", "imap.password_env", &imap.password_env);
+            c.positive("email", "imap.port", u64::from(imap.port));
+            c.positive("email", "imap.poll_interval_secs", imap.poll_interval_secs);
+            c.positive(
+                "email",
+                "imap.max_message_bytes",
+                imap.max_message_bytes as u64,
+            );
+            c.unit("email", "imap.telos_alignment", imap.telos_alignment);
+        }
+        if let Some(smtp) = &email.smtp {
+            if let Some(password_env) = &smtp.password_env {
+                c.env("email", "smtp.password_env", password_env);
+            }
+            if !smtp.from.contains('@') {
+                c.error(
+                    "email",
+                    format!("smtp.from {:?} is not an address", smtp.from),
+                );
+            }
+        }
+        if email.imap.is_none() && email.smtp.is_none() {
+            c.warn("email", "neither imap nor smtp is configured");
+        }
+    });
+    checker.section("github", false, |c, github: GithubConfig| {
+        c.env("github", "webhook_secret_env", &github.webhook_secret_env);
+        if let Some(token_env) = &github.token_env {
+            c.env("github", "token_env", token_env);
+        }
+        c.unit("github", "telos_alignment", github.telos_alignment);
+        c.url("github", "api_base", &github.api_base);
+    });
+    checker.section("feeds", false, |c, feeds: FeedsConfig| {
+        c.positive(
+            "feeds",
+            "poll_interval_minutes",
+            feeds.poll_interval_minutes,
+        );
+        c.unit("feeds", "telos_alignment", feeds.telos_alignment);
+        if feeds.feeds.is_empty() {
+            c.warn("feeds", "no feeds listed");
+        }
+        for feed in &feeds.feeds {
+            c.url("feeds", "feeds[].url", &feed.url);
+        }
+    });
+    checker.section("calendar", false, |c, calendar: CalendarConfig| {
+        c.positive(
+            "calendar",
+            "poll_interval_minutes",
+            calendar.poll_interval_minutes,
+        );
+        c.unit("calendar", "telos_alignment", calendar.telos_alignment);
+        for source in &calendar.calendars {
+            c.url("calendar", "calendars[].url", &source.url);
+        }
+    });
+    checker.section("webhooks", false, |c, webhooks: WebhooksConfig| {
+        if let Some(inbound) = &webhooks.inbound {
+            c.env("webhooks", "inbound.secret_env", &inbound.secret_env);
+            c.unit(
+                "webhooks",
+                "inbound.telos_alignment",
+                inbound.telos_alignment,
+            );
+        }
+        for outbound in &webhooks.outbound {
+            c.url("webhooks", "outbound[].url", &outbound.url);
+            if let Some(secret_env) = &outbound.secret_env {
+                c.env("webhooks", "outbound[].secret_env", secret_env);
+            }
+        }
+    });
+    checker.section(
+        "notifications",
+        false,
+        |c, notifications: NotificationsConfig| {
+            for rule in &notifications.rules {
+                let field = |name: &str| format!("rules[{}].{name}", rule.name);
+                if rule.channels.is_empty() {
+                    c.error("notifications", format!("{} is empty", field("channels")));
+                }
+                for (name, bound) in [
+                    ("min_alignment", rule.min_alignment),
+                    ("max_alignment", rule.max_alignment),
+                ] {
+                    if let Some(bound) = bound {
+                        c.unit("notifications", &field(name), bound);
+                    }
+                }
+                if let (Some(min), Some(max)) = (rule.min_alignment, rule.max_alignment)
+                    && min > max
+                {
+                    c.error(
+                        "notifications",
+                        format!("{} is above max_alignment", field("min_alignment")),
+                    );
+                }
+                check_channels(
+                    c,
+                    "notifications",
+                    &field("channels"),
+                    rule.channels.iter().map(|target| &target.channel),
+                    telegram_default_chat,
+                );
+            }
+        },
+    );
+    if !watchdog_channels.is_empty() {
+        check_channels(
+            &mut checker,
+            "beat",
+            "watchdog.channels",
+            &watchdog_channels,
+            telegram_default_chat,
+        );
+    }
+    checker.section("sources", false, |c, sources: SourcesConfig| {
+        for (source, policy) in &sources.policies {
+            if let Some(alignment) = policy.telos_alignment {
+                c.unit("sources", &format!("{source}.telos_alignment"), alignment);
+            }
+            if let (Some(persona), Some(agent)) = (&policy.persona, &agent_config)
+                && let Err(err) = agent.persona_for(Some(persona))
+            {
+                c.error("sources", format!("{source}.persona: {err:#}"));
+            }
+            let gated = approval_sources
+                .iter()
+                .any(|gated| gated.eq_ignore_ascii_case(source));
+            if policy.auto_approve && gated {
+                c.warn(
+                    "sources",
+                    format!("{source}.auto_approve overrides beat.approval.sources"),
+                );
+            }
+        }
+    });
+    checker.section("acceptance", false, |c, acceptance: AcceptanceConfig| {
+        for pattern in &acceptance.docs {
+            let matched = crate::server::resolve_plan_docs(c.root, std::slice::from_ref(pattern));
+            if matched.is_empty() {
+                c.warn(
+                    "acceptance",
+                    format!("docs entry {pattern:?} matches no file"),
+                );
+            }
+        }
+    });
+    checker.section("storage", false, |c, storage: ObjectStorageConfig| {
+        c.url("storage", "endpoint", &storage.endpoint);
+        c.env("storage", "access_key_env", &storage.access_key_env);
+        c.env("storage", "secret_key_env", &storage.secret_key_env);
+        c.positive("storage", "sync_interval_secs", storage.sync_interval_secs);
+    });
+    checker.section("memory", false, |_, _: serde_yaml::Value| {});
+    checker.section("outbox", false, |c, outbox: OutboxConfig| {
+        c.positive("outbox", "max_attempts", u64::from(outbox.max_attempts));
+        if outbox.retry_base_secs > outbox.retry_max_secs {
+            c.warn("outbox", "retry_base_secs is above retry_max_secs");
+        }
+    });
+    checker.section("retention", false, |c, retention: RetentionConfig| {
+        c.positive("retention", "interval_minutes", retention.interval_minutes);
+    });
+    checker.section("jobs", false, |c, jobs: JobsConfig| {
+        for (name, job) in &jobs.jobs {
+            if let Err(err) = JobSchedule::from_config(job) {
+                c.error("jobs", format!("{name}: {err:#}"));
+            }
+        }
+    });
+    checker.section("disk", false, |c, disk: DiskConfig| {
+        c.positive("disk", "check_interval_secs", disk.check_interval_secs);
+    });
+    checker.section("walk", false, |c, walk: storage::WalkPolicy| {
+        if walk.max_depth == Some(0) {
+            c.error("walk", "max_depth must be above 0");
+        }
+        for pattern in &walk.ignore {
+            if pattern.trim().is_empty() || pattern.trim_matches(['/', '*']).is_empty() {
+                c.error(
+                    "walk",
+                    format!("ignore pattern {pattern:?} matches everything"),
+                );
+            }
+        }
+    });
+    checker.section("tools", false, |c, tools: ToolsConfig| {
+        if let Some(fetch) = &tools.fetch_url {
+            if fetch
+                .allowed_domains
+                .iter()
+                .all(|domain| domain.trim().is_empty())
+            {
+                c.error(
+                    "tools",
+                    "fetch_url.allowed_domains is empty, so every fetch is refused",
+                );
+            }
+            c.positive("tools", "fetch_url.max_bytes", fetch.max_bytes as u64);
+            c.positive("tools", "fetch_url.timeout_secs", fetch.timeout_secs);
+        }
+        if let Some(command) = &tools.run_command {
+            if command
+                .allowed_commands
+                .iter()
+                .all(|name| name.trim().is_empty())
+            {
+                c.error(
+                    "tools",
+                    "run_command.allowed_commands is empty, so every command is refused",
+                );
+            }
+            let dir = c.root.join(&command.working_dir);
+            if !dir.is_dir() {
+                c.error(
+                    "tools",
+                    format!(
+                        "run_command.working_dir {} is not a directory",
+                        dir.display()
+                    ),
+                );
+            }
+            c.positive("tools", "run_command.timeout_secs", command.timeout_secs);
+        }
+        if let Some(search) = &tools.web_search {
+            let key_env = match &search.provider {
+                SearchProvider::Searxng { base_url } => {
+                    if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+                        c.error(
+                            "tools",
+                            format!("web_search.base_url {base_url:?} is not an http(s) URL"),
+                        );
+                    }
+                    None
+                }
+                SearchProvider::Brave { api_key_env, .. }
+                | SearchProvider::Bing { api_key_env, .. } => Some(api_key_env),
+            };
+            if let Some(key_env) = key_env
+                && std::env::var(key_env).is_err()
+            {
+                c.warn("tools", format!("web_search key env {key_env} is not set"));
+            }
+            c.positive("tools", "web_search.max_results", search.max_results as u64);
+            c.positive("tools", "web_search.timeout_secs", search.timeout_secs);
+        }
+        for (name, server) in &tools.mcp_servers {
+            if name.is_empty() || name.contains(['.', ' ']) {
+                c.error(
+                    "tools",
+                    format!("mcp_servers name {name:?} must be non-empty without dots or spaces"),
+                );
+            }
+            if server.command.is_empty() {
+                c.error("tools", format!("mcp_servers.{name}.command is empty"));
+            }
+            if let Some(dir) = &server.working_dir
+                && !c.root.join(dir).is_dir()
+            {
+                c.error(
+                    "tools",
+                    format!("mcp_servers.{name}.working_dir {dir:?} is not a directory"),
+                );
+            }
+            c.positive(
+                "tools",
+                &format!("mcp_servers.{name}.timeout_secs"),
+                server.timeout_secs,
+            );
+        }
+    });
+    checker.section("ui", false, |c, ui: UiConfig| {
+        let refresh = &ui.refresh_secs;
+        for (name, secs) in [
+            ("refresh_secs.messages", refresh.messages),
+            ("refresh_secs.intents", refresh.intents),
+            ("refresh_secs.markdown", refresh.markdown),
+            ("refresh_secs.logs", refresh.logs),
+        ] {
+            c.positive("ui", name, secs);
+        }
+    });
+    checker.section("workspaces", false, |c, workspaces: WorkspacesConfig| {
+        for (name, workspace) in &workspaces.workspaces {
+            if let Err(err) = validate_workspace_name(name) {
+                c.error("workspaces", format!("{err:#}"));
+            }
+            let root = c.root.join(&workspace.root);
+            if !root.join("config").is_dir() {
+                c.error(
+                    "workspaces",
+                    format!("{name}.root {root:?} has no config/ directory"),
+                );
+            } else if root.canonicalize().ok() == c.root.canonicalize().ok() {
+                c.error("workspaces", format!("{name}.root is the app root itself"));
+            }
+        }
+    });
+
+    checker.report
+}
+
+/// `telegram_default_chat` is `None` when `telegram.yml` is missing.
+fn check_channels<'a>(
+    c: &mut Checker<'_>,
+    section: &str,
+    field: &str,
+    channels: impl IntoIterator<Item = &'a NotificationChannel>,
+    telegram_default_chat: Option<Option<i64>>,
+) {
+    for channel in channels {
+        match channel {
+            NotificationChannel::Telegram { chat_id } => match telegram_default_chat {
+                None => c.error(
+                    section,
+                    format!("{field} uses telegram but telegram.yml is missing"),
+                ),
+                Some(None) if chat_id.is_none() => c.error(
+                    section,
+                    format!("{field} has no chat_id and telegram.yml has no default_chat_id"),
+                ),
+                Some(_) => {}
+            },
+            NotificationChannel::Slack { webhook_url_env } => {
+                c.env(
+                    section,
+                    &format!("{field}.webhook_url_env"),
+                    webhook_url_env,
+                );
+            }
+            NotificationChannel::Webhook(webhook) => {
+                c.url(section, &format!("{field}.url"), &webhook.url);
+                if let Some(secret_env) = &webhook.secret_env {
+                    c.env(section, &format!("{field}.secret_env"), secret_env);
+                }
+            }
+            NotificationChannel::Ntfy {
+                server,
+                topic,
+                token_env,
+            } => {
+                c.url(section, &format!("{field}.server"), server);
+                if topic.trim().is_empty() || topic.contains('/') {
+                    c.error(
+                        section,
+                        format!("{field}.topic must be a non-empty name without '/'"),
+                    );
+                }
+                if let Some(token_env) = token_env {
+                    c.env(section, &format!("{field}.token_env"), token_env);
+                }
+            }
+        }
+    }
+}
+
+fn check_telegram(c: &mut Checker<'_>, telegram: &TelegramConfig) {
+    // Bot tokens look like `123456789:AA...`.
+    let token_ok = telegram
+        .bot_token
+        .split_once(':')
+        .is_some_and(|(id, secret)| {
+            !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) && !secret.is_empty()
+        });
+    if !token_ok {
+        c.error("telegram", "bot_token is not of the form <bot id>:<secret>");
+    }
+    if let Some(secret) = &telegram.webhook_secret {
+        let valid = (1..=256).contains(&secret.len())
+            && secret
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+        if !valid {
+            c.error(
+                "telegram",
+                "webhook_secret must be 1-256 characters of A-Z, a-z, 0-9, _ and -",
+            );
+        }
+    }
+    match (&telegram.mode, &telegram.public_url) {
+        (TelegramMode::Webhook, None) => c.warn(
+            "telegram",
+            "mode is webhook but public_url is unset; register the webhook by hand",
+        ),
+        (_, Some(url)) if !url.starts_with("https://") => c.error(
+            "telegram",
+            "public_url must be https:// for Telegram webhooks",
+        ),
+        _ => {}
+    }
+    if telegram.mode == TelegramMode::Polling && telegram.poll_timeout_secs > 50 {
+        c.warn(
+            "telegram",
+            "poll_timeout_secs above 50 is capped by Telegram",
+        );
+    }
+    c.url("telegram", "api_base", &telegram.api_base);
+}
+
+struct Checker<'a> {
+    dir: &'a Path,
+    root: &'a Path,
+    vars: &'a BTreeMap<String, String>,
+    overrides: ConfigOverrides,
+    report: ConfigCheckReport,
+}
+
+impl Checker<'_> {
+    /// Load `section`, run `check` on it and mark it ok when nothing was
+    /// reported. Optional sections that are absent are skipped.
+    fn section<T: DeserializeOwned>(
+        &mut self,
+        section: &str,
+        required: bool,
+        check: impl FnOnce(&mut Self, T),
+    ) {
+        let loaded = match self.overrides.load_optional::<T>(self.dir, section) {
+            Ok(Some(value)) => value,
+            Ok(None) if required => {
+                return self.error(section, format!("{section}.yml is missing"));
+            }
+            Ok(None) => return,
+            Err(err) => return self.error(section, format!("{err:#}")),
+        };
+        let before = self.report.findings.len();
+        check(self, loaded);
+        if self.report.findings.len() == before {
+            self.push(CheckLevel::Ok, section, "loaded".to_string());
+        }
+    }
+
+    fn push(&mut self, level: CheckLevel, section: &str, message: String) {
+        self.report.findings.push(ConfigFinding {
+            level,
+            section: section.to_string(),
+            message,
+        });
+    }
+
+    fn error(&mut self, section: &str, message: impl Into<String>) {
+        self.push(CheckLevel::Error, section, message.into());
+    }
+
+    fn warn(&mut self, section: &str, message: impl Into<String>) {
+        self.push(CheckLevel::Warning, section, message.into());
+    }
+
+    fn positive(&mut self, section: &str, field: &str, value: u64) {
+        if value == 0 {
+            self.error(section, format!("{field} must be above 0"));
+        }
+    }
+
+    fn unit(&mut self, section: &str, field: &str, value: f32) {
+        if !(0.0..=1.0).contains(&value) {
+            self.error(
+                section,
+                format!("{field} must be between 0 and 1, got {value}"),
+            );
+        }
+    }
+
+    fn env(&mut self, section: &str, field: &str, name: &str) {
+        let set = self
+            .vars
+            .get(name)
+            .is_some_and(|value| !value.trim().is_empty());
+        if !set {
+            self.error(section, format!("{field}: env var {name} is not set"));
+        }
+    }
+
+    fn url(&mut self, section: &str, field: &str, url: &str) {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            self.error(section, format!("{field} {url:?} is not an http(s) URL"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn messages(report: &ConfigCheckReport, level: CheckLevel) -> Vec<String> {
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.level == level)
+            .map(|finding| format!("{}: {}", finding.section, finding.message))
+            .collect()
+    }
+
+    #[test]
+    fn reports_ranges_missing_secrets_and_telegram_problems() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        let vars = BTreeMap::new();
+
+        let clean = check_config(&root, &vars);
+        assert_eq!(clean.errors(), 0, "{:?}", clean.findings);
+        assert!(messages(&clean, CheckLevel::Ok).contains(&"beat: loaded".to_string()));
+
+        let config = root.join("config");
+        fs::write(
+            config.join("beat.yml"),
+            "interval_minutes: 0\nintent_threshold: 1.5\n",
+        )
+        .unwrap();
+        fs::write(
+            config.join("llm.yml"),
+            "provider: open_ai\nmodel: gpt-4o-mini\napi_key_env: TEST_CHECK_OPENAI_KEY\ntemperature: 2.5\nmodels:\n  gpt-4o:\n    max_tokens: 0\n",
+        )
+        .unwrap();
+        fs::write(
+            config.join("telegram.yml"),
+            "bot_token: not-a-token\nwebhook_secret: has spaces\npublic_url: http://example.com\n",
+        )
+        .unwrap();
+        fs::write(config.join("agent.yml"), "persona: [unclosed\n").unwrap();
+        fs::write(
+            config.join("jobs.yml"),
+            "jobs:\n  retention:\n    cron: \"0 25 * * *\"\n  digest:\n    cron: \"0 8 * * 1\"\n    interval_secs: 60\n",
+        )
+        .unwrap();
+        fs::write(
+            config.join("workspaces.yml"),
+            "workspaces:\n  Work:\n    root: missing\n",
+        )
+        .unwrap();
+
+        let report = check_config(&root, &vars);
+        let errors = messages(&report, CheckLevel::Error);
+        for expected in [
+            "beat: interval_minutes must be above 0",
+            "beat: intent_threshold must be between 0 and 1, got 1.5",
+            "llm: api_key_env: env var TEST_CHECK_OPENAI_KEY is not set",
+            "llm: temperature must be between 0 and 2, got 2.5",
+            "llm: models.gpt-4o.max_tokens must be above 0",
+            "telegram: bot_token is not of the form <bot id>:<secret>",
+            "telegram: public_url must be https:// for Telegram webhooks",
+            "workspaces: workspace name \"Work\" must be 1-64 of a-z, 0-9, - and _",
+            "jobs: digest: set either cron or interval_secs, not both",
+        ] {
+            assert!(errors.contains(&expected.to_string()), "{errors:#?}");
+        }
+        assert!(
+            errors
+                .iter()
+                .any(|error| error.starts_with("telegram: webhook_secret"))
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|error| error.starts_with("agent: parsing yaml"))
+        );
+        assert!(errors.iter().any(|error| error.contains("has no config/")));
+        assert!(
+            errors
+                .iter()
+                .any(|error| error.starts_with("jobs: retention: cron \"0 25 * * *\""))
+        );
+
+        fs::write(
+            config.join("llm.yml"),
+            "provider: open_ai\nmodel: gpt-4o-mini\napi_key_env: TEST_CHECK_OPENAI_KEY\ntemperature: 0\nmodels:\n  gpt-4o:\n    max_tokens: 512\n",
+        )
+        .unwrap();
+        // Env overrides are applied before checking, and satisfy env lookups.
+        let vars = BTreeMap::from([
+            ("HI_BEAT__INTERVAL_MINUTES".to_string(), "5".to_string()),
+            ("HI_BEAT__INTENT_THRESHOLD".to_string(), "0.5".to_string()),
+            ("TEST_CHECK_OPENAI_KEY".to_string(), "sk-test".to_string()),
+        ]);
+        let report = check_config(&root, &vars);
+        let errors = messages(&report, CheckLevel::Error);
+        assert!(
+            !errors.iter().any(|error| error.starts_with("beat:")),
+            "{errors:#?}"
+        );
+        assert!(
+            !errors.iter().any(|error| error.starts_with("llm:")),
+            "{errors:#?}"
+        );
+
+        let llm: LlmProviderConfig =
+            serde_yaml::from_str(&fs::read_to_string(config.join("llm.yml")).unwrap()).unwrap();
+        assert_eq!(llm.params_for("gpt-4o").temperature, Some(0.0));
+        assert_eq!(llm.params_for("gpt-4o").max_tokens, Some(512));
+        assert_eq!(llm.params_for("gpt-4o-mini").max_tokens, None);
+    }
+}
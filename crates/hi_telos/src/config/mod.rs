@@ -1,9 +1,30 @@
-use std::{env, path::PathBuf, time::Duration};
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use serde::Deserialize;
+use anyhow::Context;
+use chrono::{DateTime, Days, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
 use tracing_subscriber::{EnvFilter, fmt};
 
-use crate::storage;
+use crate::{
+    llm::LlmIdentity,
+    storage,
+    tasks::{Intent, IntentPriority},
+};
+
+mod check;
+mod overrides;
+mod reload;
+mod secrets;
+
+pub use check::{CheckLevel, ConfigCheckReport, ConfigFinding, check_config};
+pub use overrides::{CONFIG_OVERRIDES_ENV, CONFIG_SECTIONS, ConfigOverrides};
+pub use reload::{ConfigChange, ConfigReload, merge_reloadable, spawn_watcher};
+pub use secrets::resolve_secret;
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
@@ -13,7 +34,32 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub agent: AgentConfig,
     pub llm: LlmProviderConfig,
+    pub llm_recording: LlmRecordingConfig,
+    pub llm_cache: Option<LlmCacheConfig>,
     pub telegram: Option<TelegramConfig>,
+    pub email: Option<EmailConfig>,
+    pub github: Option<GithubConfig>,
+    pub feeds: Option<FeedsConfig>,
+    pub calendar: Option<CalendarConfig>,
+    pub webhooks: WebhooksConfig,
+    pub notifications: NotificationsConfig,
+    /// Retry policy for every channel the outbox sends to.
+    pub outbox: OutboxConfig,
+    pub sources: SourcesConfig,
+    pub acceptance: AcceptanceConfig,
+    pub memory: MemoryConfig,
+    pub retention: RetentionConfig,
+    pub disk: DiskConfig,
+    /// What the markdown tree listing skips, from `config/walk.yml`.
+    pub walk: storage::WalkPolicy,
+    pub jobs: JobsConfig,
+    pub tools: ToolsConfig,
+    pub storage: Option<ObjectStorageConfig>,
+    pub ui: UiConfig,
+    pub workspaces: WorkspacesConfig,
+    /// Name of the workspace this config belongs to; `None` for the main
+    /// one under `HI_APP_ROOT`.
+    pub workspace: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -21,6 +67,151 @@ pub struct BeatConfig {
     pub interval_minutes: u64,
     #[serde(default = "default_intent_threshold")]
     pub intent_threshold: f32,
+    #[serde(default)]
+    pub weekly_review: Option<WeeklyReviewConfig>,
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+}
+
+/// Intents that wait in `intent/pending_approval` for a human before they
+/// are queued, even when their alignment is above the threshold.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApprovalConfig {
+    /// Sources (e.g. `github`) whose intents always need approval.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Intents whose `cost_estimate` metadata is above this need approval.
+    #[serde(default)]
+    pub max_cost_estimate: Option<f64>,
+    /// Intents whose `estimated_tokens` are above this need approval.
+    #[serde(default)]
+    pub max_estimated_tokens: Option<u64>,
+    /// Intents that would take the estimated tokens of the intents queued
+    /// today (UTC) above this need approval.
+    #[serde(default)]
+    pub daily_estimated_tokens: Option<u64>,
+}
+
+impl ApprovalConfig {
+    /// The setting that holds `intent` for approval, if any, with
+    /// `queued_today` estimated tokens already queued today. The submitted
+    /// `cost_estimate` and the app's `estimated_tokens` are separate figures
+    /// with separate limits; either one breaking its limit holds the intent.
+    pub fn hold_reason(&self, intent: &Intent, queued_today: u64) -> Option<&'static str> {
+        let estimate = intent.estimated_tokens().unwrap_or(0);
+        if self
+            .sources
+            .iter()
+            .any(|gated| gated.eq_ignore_ascii_case(&intent.source))
+        {
+            Some("sources")
+        } else if self
+            .max_cost_estimate
+            .zip(intent.cost_estimate())
+            .is_some_and(|(max, cost)| cost > max)
+        {
+            Some("max_cost_estimate")
+        } else if self.max_estimated_tokens.is_some_and(|max| estimate > max) {
+            Some("max_estimated_tokens")
+        } else if self
+            .daily_estimated_tokens
+            .is_some_and(|daily| queued_today.saturating_add(estimate) > daily)
+        {
+            Some("daily_estimated_tokens")
+        } else {
+            None
+        }
+    }
+}
+
+/// Dead man's switch for the beat loop: once no beat has finished for
+/// `missed_beats` intervals, `/healthz` turns unhealthy and `channels` are
+/// alerted, and alerted again when beats resume.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchdogConfig {
+    #[serde(default = "default_watchdog_missed_beats")]
+    pub missed_beats: u32,
+    #[serde(default)]
+    pub channels: Vec<NotificationChannel>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            missed_beats: default_watchdog_missed_beats(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// Plan documents behind `/api/meta/acceptance`, from
+/// `config/acceptance.yml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcceptanceConfig {
+    /// Paths relative to the app root. `*` in the file name matches any
+    /// characters, e.g. `docs/acceptance/*.md`.
+    #[serde(default = "default_acceptance_docs")]
+    pub docs: Vec<String>,
+}
+
+impl Default for AcceptanceConfig {
+    fn default() -> Self {
+        Self {
+            docs: default_acceptance_docs(),
+        }
+    }
+}
+
+fn default_acceptance_docs() -> Vec<String> {
+    vec!["docs/work_acceptance_plan.md".to_string()]
+}
+
+/// Per-source defaults from `config/sources.yml`, keyed by intent source
+/// (`telegram`, `email`, `github`, `user`, ...), applied when the beat moves
+/// intents out of the inbox.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct SourcesConfig {
+    pub policies: BTreeMap<String, SourcePolicy>,
+}
+
+impl SourcesConfig {
+    /// The policy for `source`, matched case-insensitively.
+    pub fn policy_for(&self, source: &str) -> Option<&SourcePolicy> {
+        self.policies
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(source))
+            .map(|(_, policy)| policy)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SourcePolicy {
+    /// Replaces the alignment the channel assigned, unless a human already
+    /// approved the intent.
+    #[serde(default)]
+    pub telos_alignment: Option<f32>,
+    /// Used when the intent has no `priority` metadata.
+    #[serde(default)]
+    pub priority: Option<IntentPriority>,
+    /// Skip the `beat.approval` hold for this source. Low-alignment intents
+    /// are still deferred.
+    #[serde(default)]
+    pub auto_approve: bool,
+    /// Used when the intent has no `persona` metadata.
+    #[serde(default)]
+    pub persona: Option<String>,
+}
+
+/// Schedules a "weekly review" intent covering the previous ISO week.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeeklyReviewConfig {
+    #[serde(default = "default_weekly_review_day")]
+    pub weekday: Weekday,
+    #[serde(default = "default_weekly_review_alignment")]
+    pub telos_alignment: f32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -29,6 +220,155 @@ pub struct AgentConfig {
     pub max_react_steps: usize,
     #[serde(default = "default_agent_persona")]
     pub persona: String,
+    #[serde(default)]
+    pub session: SessionConfig,
+    /// Named personas intents can pick with `persona:` in their front
+    /// matter or the intents API.
+    #[serde(default)]
+    pub personas: BTreeMap<String, PersonaConfig>,
+    /// Entry of `personas` used when an intent names none; without it the
+    /// inline `persona` and `max_react_steps` apply.
+    #[serde(default)]
+    pub default_persona: Option<String>,
+    /// Similar, frequently used SP entries shown to the agent as precedents.
+    #[serde(default)]
+    pub sp_recall: storage::SpRecallPolicy,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    /// Closing instructions of the THINK and FINAL prompts.
+    #[serde(default)]
+    pub prompts: PromptTemplates,
+    /// A/B test of prompt variants, alternated run by run.
+    #[serde(default)]
+    pub experiment: Option<ExperimentConfig>,
+}
+
+/// Instructions ending the THINK and FINAL prompts, after the intent,
+/// context and history; unset ones keep the built-in text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct PromptTemplates {
+    #[serde(default)]
+    pub think_instructions: Option<String>,
+    #[serde(default)]
+    pub final_instructions: Option<String>,
+}
+
+impl PromptTemplates {
+    /// `self`, with unset instructions taken from `fallback`.
+    pub fn or(&self, fallback: &PromptTemplates) -> PromptTemplates {
+        PromptTemplates {
+            think_instructions: self
+                .think_instructions
+                .clone()
+                .or_else(|| fallback.think_instructions.clone()),
+            final_instructions: self
+                .final_instructions
+                .clone()
+                .or_else(|| fallback.final_instructions.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExperimentConfig {
+    /// Recorded on every LLM log entry of the experiment's runs.
+    pub name: String,
+    /// Prompt overrides per variant name, on top of `agent.prompts`; runs
+    /// take the variants in turn, in name order.
+    pub variants: BTreeMap<String, PromptTemplates>,
+}
+
+/// How the step history in THINK and FINAL prompts is kept within bounds
+/// on long runs.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryConfig {
+    /// Estimated tokens (about four characters each) the history may use
+    /// before older steps are summarized by the LLM; 0 keeps every step.
+    #[serde(default = "default_history_max_tokens")]
+    pub max_tokens: usize,
+    /// Latest steps always shown verbatim.
+    #[serde(default = "default_history_keep_recent_steps")]
+    pub keep_recent_steps: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: default_history_max_tokens(),
+            keep_recent_steps: default_history_keep_recent_steps(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PersonaConfig {
+    /// Written into the prompt as `Persona:`.
+    pub prompt: String,
+    /// Model used on the configured provider unless the intent pins one.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Overrides `agent.max_react_steps`.
+    #[serde(default)]
+    pub max_react_steps: Option<usize>,
+}
+
+/// The persona a run uses, after defaults are applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Persona {
+    /// Key in `agent.personas`, or [`DEFAULT_PERSONA_NAME`] for the inline one.
+    pub name: String,
+    pub prompt: String,
+    pub model: Option<String>,
+    pub max_react_steps: usize,
+}
+
+/// Name of the persona built from the inline `agent.persona`.
+pub const DEFAULT_PERSONA_NAME: &str = "default";
+
+impl AgentConfig {
+    /// `requested` (or the default persona when `None`), failing on names
+    /// missing from `personas`.
+    pub fn persona_for(&self, requested: Option<&str>) -> anyhow::Result<Persona> {
+        let name = requested.or(self.default_persona.as_deref());
+        let Some(name) = name.filter(|name| *name != DEFAULT_PERSONA_NAME) else {
+            return Ok(Persona {
+                name: DEFAULT_PERSONA_NAME.to_string(),
+                prompt: self.persona.clone(),
+                model: None,
+                max_react_steps: self.max_react_steps,
+            });
+        };
+        let persona = self.personas.get(name).with_context(|| {
+            let known: Vec<&str> = self.personas.keys().map(String::as_str).collect();
+            format!("unknown persona {name:?}; agent.yml defines {known:?}")
+        })?;
+        Ok(Persona {
+            name: name.to_string(),
+            prompt: persona.prompt.clone(),
+            model: persona.model.clone(),
+            max_react_steps: persona.max_react_steps.unwrap_or(self.max_react_steps),
+        })
+    }
+}
+
+/// How much of a chat's recent message log is replayed into the prompt as
+/// conversation context.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionConfig {
+    #[serde(default = "default_session_max_turns")]
+    pub max_turns: usize,
+    /// Messages older than this do not belong to the current session.
+    #[serde(default = "default_session_window_minutes")]
+    pub window_minutes: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            max_turns: default_session_max_turns(),
+            window_minutes: default_session_window_minutes(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -39,48 +379,962 @@ pub enum LlmProviderConfig {
         model: String,
         #[serde(default = "default_openai_api_key_env")]
         api_key_env: String,
+        /// Read the key from this file instead of `api_key_env`.
+        #[serde(default)]
+        api_key_file: Option<PathBuf>,
+        /// Read the key from this command's stdout instead of `api_key_env`.
+        #[serde(default)]
+        api_key_command: Option<Vec<String>>,
+        /// Resolved from `api_key_file` / `api_key_command` at load time.
+        #[serde(skip)]
+        api_key: Option<String>,
         #[serde(default)]
         base_url: Option<String>,
         #[serde(default)]
         organization: Option<String>,
+        /// Ask for THINK steps and final answers as function calls rather
+        /// than JSON in the message text. Turn off for OpenAI-compatible
+        /// servers that do not support `tools`.
+        #[serde(default = "default_openai_function_calling")]
+        function_calling: bool,
+        /// Request parameters for every model.
+        #[serde(flatten)]
+        params: Box<OpenAiParams>,
+        /// Per-model overrides of `params`, keyed by model name; they also
+        /// apply when an intent or persona picks that model.
+        #[serde(default)]
+        models: Box<BTreeMap<String, OpenAiParams>>,
+    },
+}
+
+/// Chat completion parameters; unset ones fall back to the provider-wide
+/// value, then to the built-in default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct OpenAiParams {
+    /// Defaults to 0.2.
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    /// Left to the provider when unset.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// For requests without function calling; defaults to `json_object`.
+    #[serde(default)]
+    pub response_format: Option<OpenAiResponseFormat>,
+}
+
+impl OpenAiParams {
+    /// `self`, with unset values taken from `fallback`.
+    pub fn or(&self, fallback: &OpenAiParams) -> OpenAiParams {
+        OpenAiParams {
+            temperature: self.temperature.or(fallback.temperature),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            system_prompt: self
+                .system_prompt
+                .clone()
+                .or_else(|| fallback.system_prompt.clone()),
+            response_format: self.response_format.or(fallback.response_format),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAiResponseFormat {
+    JsonObject,
+    /// No `response_format`, for servers that reject it.
+    Text,
+}
+
+impl LlmProviderConfig {
+    /// Request parameters for `model`: its entry in `models` over the
+    /// provider-wide ones. Empty for providers without parameters.
+    pub fn params_for(&self, model: &str) -> OpenAiParams {
+        match self {
+            LlmProviderConfig::LocalStub => OpenAiParams::default(),
+            LlmProviderConfig::OpenAi { params, models, .. } => match models.get(model) {
+                Some(overrides) => overrides.or(params),
+                None => OpenAiParams::clone(params),
+            },
+        }
+    }
+
+    /// Provider and model as they appear in LLM logs, without building a
+    /// client (and so without needing credentials).
+    pub fn identity(&self) -> LlmIdentity {
+        match self {
+            LlmProviderConfig::LocalStub => {
+                LlmIdentity::new("local_stub", Some("local_stub".to_string()))
+            }
+            LlmProviderConfig::OpenAi { model, .. } => {
+                LlmIdentity::new("openai", Some(model.clone()))
+            }
+        }
+    }
+}
+
+/// Record-and-replay of LLM calls, from `config/llm_recording.yml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LlmRecordingConfig {
+    #[serde(default)]
+    pub mode: LlmRecordingMode,
+    /// Where recordings live, relative to the app root. Defaults to
+    /// `data/llm_recordings`.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmRecordingMode {
+    /// Call the provider directly.
+    #[default]
+    Off,
+    /// Call the provider and save each response keyed by prompt hash.
+    Record,
+    /// Serve saved responses only; a prompt without a recording fails.
+    Replay,
+}
+
+/// On-disk cache of LLM responses keyed by prompt hash, from
+/// `config/llm_cache.yml`; caching is off without the file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlmCacheConfig {
+    /// How long a cached response is served before the provider is asked
+    /// again.
+    #[serde(default = "default_llm_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Where cached responses live, relative to the app root. Defaults to
+    /// `data/llm_cache`.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+fn default_llm_cache_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Schedules of background jobs, from `config/jobs.yml`, keyed by job name
+/// (e.g. `retention`). Jobs missing here keep their default schedule.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobsConfig {
+    #[serde(default)]
+    pub jobs: BTreeMap<String, JobConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobConfig {
+    #[serde(default = "default_job_enabled")]
+    pub enabled: bool,
+    /// Run at startup and every `interval_secs` after that.
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// Five-field cron expression in UTC, instead of `interval_secs`.
+    #[serde(default)]
+    pub cron: Option<String>,
+    /// Delay each run by a random amount up to this.
+    #[serde(default)]
+    pub jitter_secs: u64,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_job_enabled(),
+            interval_secs: None,
+            cron: None,
+            jitter_secs: 0,
+        }
+    }
+}
+
+fn default_job_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MemoryConfig {
+    #[serde(default)]
+    pub retention: Option<storage::MemoryRetentionPolicy>,
+}
+
+/// Pruning of logs, messages, journals and intent history, from
+/// `config/retention.yml`. Categories without a rule are kept forever.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default = "default_retention_interval_minutes")]
+    pub interval_minutes: u64,
+    #[serde(flatten)]
+    pub policy: storage::RetentionPolicy,
+}
+
+/// Free space the data dir's disk must keep, from `config/disk.yml`. Below
+/// `min_free_mb` LLM logs, webhook delivery logs and markdown / structured
+/// text history stop being written until space is freed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiskConfig {
+    /// `0` turns the guard off.
+    #[serde(default = "default_disk_min_free_mb")]
+    pub min_free_mb: u64,
+    #[serde(default = "default_disk_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl DiskConfig {
+    pub fn min_free_bytes(&self) -> u64 {
+        self.min_free_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// Tools the agent may call from a THINK step, from `config/tools.yml`.
+/// A tool left out is not offered.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ToolsConfig {
+    #[serde(default)]
+    pub fetch_url: Option<FetchUrlConfig>,
+    #[serde(default)]
+    pub run_command: Option<RunCommandConfig>,
+    #[serde(default)]
+    pub web_search: Option<WebSearchConfig>,
+    /// MCP servers started with the process; their tools are offered as
+    /// `<name>.<tool>`.
+    #[serde(default)]
+    pub mcp_servers: BTreeMap<String, McpServerConfig>,
+}
+
+/// `fetch_url`: HTTP GET of pages on `allowed_domains` (a domain also
+/// allows its subdomains), redirects included.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FetchUrlConfig {
+    pub allowed_domains: Vec<String>,
+    /// Bodies are cut off after this many bytes.
+    #[serde(default = "default_fetch_max_bytes")]
+    pub max_bytes: usize,
+    #[serde(default = "default_fetch_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// `run_command`: runs a program from `allowed_commands` (matched by the
+/// first word, no shell) in `working_dir` with a minimal environment.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunCommandConfig {
+    pub allowed_commands: Vec<String>,
+    /// Relative to the app root.
+    pub working_dir: PathBuf,
+    #[serde(default = "default_command_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Each of stdout and stderr is cut off after this many bytes in the
+    /// observation; the run's logs keep all of it.
+    #[serde(default = "default_command_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+/// `web_search`: a query against the configured search provider,
+/// answered with up to `max_results` titles, links and snippets.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebSearchConfig {
+    #[serde(flatten)]
+    pub provider: SearchProvider,
+    #[serde(default = "default_search_max_results")]
+    pub max_results: usize,
+    #[serde(default = "default_fetch_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum SearchProvider {
+    /// A SearxNG instance with the JSON format enabled.
+    Searxng { base_url: String },
+    Brave {
+        #[serde(default = "default_brave_api_key_env")]
+        api_key_env: String,
+        /// Defaults to the public Brave Search API.
+        #[serde(default)]
+        base_url: Option<String>,
+    },
+    Bing {
+        #[serde(default = "default_bing_api_key_env")]
+        api_key_env: String,
+        /// Defaults to the public Bing Web Search API.
+        #[serde(default)]
+        base_url: Option<String>,
     },
 }
 
+/// An MCP server spoken to over its stdin/stdout.
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpServerConfig {
+    /// Program and arguments, e.g. `[npx, -y, some-mcp-server]`.
+    pub command: Vec<String>,
+    /// Added to the process environment, which the server inherits.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Relative to the app root; defaults to it.
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// Per request, including the startup handshake.
+    #[serde(default = "default_mcp_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Extra workspaces served by the same process, from
+/// `config/workspaces.yml`. Each has its own `config/` and `data/` under
+/// `root` and its own orchestrator; its API lives under
+/// `/api/w/<name>/...`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkspacesConfig {
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, WorkspaceConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceConfig {
+    /// Relative to the app root.
+    pub root: PathBuf,
+}
+
+/// Workspace names become URL segments: 1-64 characters from `a-z`, `0-9`,
+/// `-` and `_`.
+pub fn validate_workspace_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_');
+    if !valid {
+        anyhow::bail!("workspace name {name:?} must be 1-64 of a-z, 0-9, - and _");
+    }
+    Ok(())
+}
+
+/// Defaults for the `/ui` pages, from `config/ui.yml`. A page URL may
+/// override its refresh rate with `?refresh=<secs>`, and the browser keeps
+/// its own theme choice once toggled.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub theme: UiTheme,
+    #[serde(default)]
+    pub refresh_secs: UiRefreshConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiTheme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl UiTheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UiTheme::Dark => "dark",
+            UiTheme::Light => "light",
+        }
+    }
+}
+
+/// How often each page's SSE stream re-sends its payload.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UiRefreshConfig {
+    #[serde(default = "default_ui_messages_refresh_secs")]
+    pub messages: u64,
+    #[serde(default = "default_ui_intents_refresh_secs")]
+    pub intents: u64,
+    #[serde(default = "default_ui_markdown_refresh_secs")]
+    pub markdown: u64,
+    #[serde(default = "default_ui_logs_refresh_secs")]
+    pub logs: u64,
+}
+
+impl Default for UiRefreshConfig {
+    fn default() -> Self {
+        Self {
+            messages: default_ui_messages_refresh_secs(),
+            intents: default_ui_intents_refresh_secs(),
+            markdown: default_ui_markdown_refresh_secs(),
+            logs: default_ui_logs_refresh_secs(),
+        }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            interval_minutes: default_retention_interval_minutes(),
+            policy: storage::RetentionPolicy::default(),
+        }
+    }
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            min_free_mb: default_disk_min_free_mb(),
+            check_interval_secs: default_disk_check_interval_secs(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub bind_addr: String,
+    /// `HI_PID_FILE`; see [`AppConfig::pid_file`] for the default.
+    pub pid_file: Option<PathBuf>,
+    /// `HI_ROLE`, defaulting to [`Role::All`]; `--role` overrides it.
+    pub role: Role,
+}
+
+/// What one process runs. Several `server` processes and one `worker` can
+/// share a data dir, so ingestion scales apart from agent processing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    /// HTTP API and the source pollers; beats are requested from the worker
+    /// through the data dir.
+    Server,
+    /// Beat orchestrator, outbound notifications and maintenance, without
+    /// the HTTP API.
+    Worker,
+    #[default]
+    All,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Server => "server",
+            Role::Worker => "worker",
+            Role::All => "all",
+        }
+    }
+
+    pub fn runs_server(&self) -> bool {
+        matches!(self, Role::Server | Role::All)
+    }
+
+    pub fn runs_worker(&self) -> bool {
+        matches!(self, Role::Worker | Role::All)
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "server" => Ok(Role::Server),
+            "worker" => Ok(Role::Worker),
+            "all" => Ok(Role::All),
+            other => anyhow::bail!("unknown role {other:?}; expected server, worker or all"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct TelegramConfig {
+    /// Inline token; leave empty and set `bot_token_file` or
+    /// `bot_token_command` to keep it out of the YAML.
+    #[serde(default)]
     pub bot_token: String,
     #[serde(default)]
+    pub bot_token_file: Option<PathBuf>,
+    /// Argv whose stdout is the token, e.g. `[pass, show, hi/telegram]`.
+    #[serde(default)]
+    pub bot_token_command: Option<Vec<String>>,
+    #[serde(default)]
     pub default_chat_id: Option<i64>,
     #[serde(default)]
     pub webhook_secret: Option<String>,
+    /// Public base URL of this server, used by
+    /// `POST /api/telegram/webhook/register`.
+    #[serde(default)]
+    pub public_url: Option<String>,
     #[serde(default = "default_telegram_api_base")]
     pub api_base: String,
+    #[serde(default)]
+    pub mode: TelegramMode,
+    /// `getUpdates` long-poll timeout in `mode: polling`.
+    #[serde(default = "default_telegram_poll_timeout_secs")]
+    pub poll_timeout_secs: u64,
+    /// Chats allowed to create intents. Together with `allowed_usernames`
+    /// this forms the allowlist; when both are empty everyone is allowed.
+    #[serde(default)]
+    pub allowed_chat_ids: Vec<i64>,
+    /// Sender usernames (without `@`, case-insensitive) allowed to create
+    /// intents from any chat.
+    #[serde(default)]
+    pub allowed_usernames: Vec<String>,
+    /// Sent back to rejected senders; when unset they are only logged.
+    #[serde(default)]
+    pub unauthorized_reply: Option<String>,
+    /// Non-urgent messages created inside this window wait in the outbox
+    /// until it ends.
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// Daily local-time window such as `start: "22:00"`, `end: "07:00"`; it may
+/// wrap past midnight, and `start == end` never matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    #[serde(deserialize_with = "deserialize_hh_mm")]
+    pub start: NaiveTime,
+    #[serde(deserialize_with = "deserialize_hh_mm")]
+    pub end: NaiveTime,
+    /// Offset of local time from UTC, e.g. `480` for UTC+8.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let time = self.local(now).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the window around `now` closes; `now` itself when outside it.
+    pub fn end_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.contains(now) {
+            return now;
+        }
+        let local = self.local(now);
+        let mut end = local.date().and_time(self.end);
+        if end <= local {
+            end = end + Days::new(1);
+        }
+        (end - self.offset()).and_utc()
+    }
+
+    fn offset(&self) -> chrono::Duration {
+        chrono::Duration::minutes(i64::from(self.utc_offset_minutes))
+    }
+
+    fn local(&self, now: DateTime<Utc>) -> chrono::NaiveDateTime {
+        now.naive_utc() + self.offset()
+    }
+}
+
+fn deserialize_hh_mm<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(raw.trim(), "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(raw.trim(), "%H:%M:%S"))
+        .map_err(serde::de::Error::custom)
+}
+
+/// Retry policy for queued outbound messages from `config/outbox.yml`:
+/// attempt `n` failing waits `retry_base_secs * 2^(n-1)` (capped at
+/// `retry_max_secs`) before the next one, and the message is marked failed
+/// after `max_attempts`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutboxConfig {
+    #[serde(default = "default_outbox_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_outbox_retry_base_secs")]
+    pub retry_base_secs: u64,
+    #[serde(default = "default_outbox_retry_max_secs")]
+    pub retry_max_secs: u64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_outbox_max_attempts(),
+            retry_base_secs: default_outbox_retry_base_secs(),
+            retry_max_secs: default_outbox_retry_max_secs(),
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Delay before the next attempt after `attempts` failed ones.
+    pub fn retry_delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(16);
+        let secs = self
+            .retry_base_secs
+            .saturating_mul(1 << exponent)
+            .min(self.retry_max_secs);
+        Duration::from_secs(secs)
+    }
+}
+
+impl TelegramConfig {
+    /// Whether a message from `chat_id` sent by `username` may create intents.
+    pub fn is_authorized(&self, chat_id: i64, username: Option<&str>) -> bool {
+        if self.allowed_chat_ids.is_empty() && self.allowed_usernames.is_empty() {
+            return true;
+        }
+        self.allowed_chat_ids.contains(&chat_id)
+            || username.is_some_and(|username| {
+                let username = username.trim_start_matches('@');
+                self.allowed_usernames.iter().any(|allowed| {
+                    allowed
+                        .trim_start_matches('@')
+                        .eq_ignore_ascii_case(username)
+                })
+            })
+    }
+}
+
+/// `webhook` relies on `POST /webhook/telegram` being reachable from
+/// Telegram; `polling` pulls updates with `getUpdates` instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TelegramMode {
+    #[default]
+    Webhook,
+    Polling,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhooksConfig {
+    #[serde(default)]
+    pub inbound: Option<InboundWebhookConfig>,
+    #[serde(default)]
+    pub outbound: Vec<OutboundWebhookConfig>,
+}
+
+/// Target for intent lifecycle notifications. An empty `events` list
+/// subscribes to every event kind. Deliveries go through the outbox, which
+/// backs off between attempts and gives up after `max_attempts`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundWebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub secret_env: Option<String>,
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+}
+
+/// Routing rules from `config/notifications.yml`. Every rule matching an
+/// event fires; an event matching no rule is not announced anywhere.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+}
+
+/// Empty filter lists match everything.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationRule {
+    pub name: String,
+    /// Event kinds such as `completed`, `failed`, `deferred`, `digest_ready`.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Intent sources such as `telegram` or `github`.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Matched against the comma-separated `tags` entry of the intent
+    /// metadata; any overlap counts.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub min_alignment: Option<f32>,
+    #[serde(default)]
+    pub max_alignment: Option<f32>,
+    /// Urgent notifications skip quiet hours.
+    #[serde(default)]
+    pub urgent: bool,
+    pub channels: Vec<NotificationTarget>,
+}
+
+/// A channel of a notification rule and its own quiet hours.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationTarget {
+    #[serde(flatten)]
+    pub channel: NotificationChannel,
+    /// Non-urgent notifications for this channel wait in the outbox while
+    /// the window is open. Telegram channels without one follow
+    /// `quiet_hours` from `telegram.yml`.
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// Falls back to `default_chat_id` from `telegram.yml`.
+    Telegram {
+        #[serde(default)]
+        chat_id: Option<i64>,
+    },
+    /// Slack incoming webhook; the URL is read from `webhook_url_env`.
+    Slack {
+        webhook_url_env: String,
+    },
+    Webhook(OutboundWebhookConfig),
+    /// Push to an ntfy topic (ntfy.sh or self-hosted); phones subscribed
+    /// to the topic in the ntfy app get the notification.
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        /// Anyone who knows a topic on a public server can read it; pick
+        /// something unguessable or use an access token.
+        topic: String,
+        /// Env var holding an access token for protected topics.
+        #[serde(default)]
+        token_env: Option<String>,
+    },
+}
+
+/// `POST /webhook/generic`: payloads must carry an HMAC-SHA256 of the raw
+/// body in `signature_header`, and the `*_path` fields are dot paths into the
+/// JSON (`alerts.0.labels.alertname`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct InboundWebhookConfig {
+    pub secret_env: String,
+    #[serde(default = "default_webhook_signature_header")]
+    pub signature_header: String,
+    pub summary_path: String,
+    #[serde(default)]
+    pub body_path: Option<String>,
+    #[serde(default)]
+    pub source_path: Option<String>,
+    #[serde(default = "default_webhook_source")]
+    pub default_source: String,
+    #[serde(default = "default_webhook_alignment")]
+    pub telos_alignment: f32,
+}
+
+/// `POST /webhook/github`: issue events are verified against the secret in
+/// `webhook_secret_env` (`X-Hub-Signature-256`). Empty `repos` / `labels`
+/// accept everything; `token_env` enables posting final answers back as issue
+/// comments.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubConfig {
+    pub webhook_secret_env: String,
+    #[serde(default)]
+    pub token_env: Option<String>,
+    #[serde(default)]
+    pub repos: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default = "default_webhook_alignment")]
+    pub telos_alignment: f32,
+    #[serde(default = "default_github_api_base")]
+    pub api_base: String,
+}
+
+/// RSS/Atom sources polled into low-alignment "review this article" intents.
+/// Keep `telos_alignment` at or above `beat.intent_threshold`, otherwise the
+/// intents are deferred instead of triaged.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedsConfig {
+    #[serde(default = "default_feed_poll_interval_minutes")]
+    pub poll_interval_minutes: u64,
+    #[serde(default = "default_feed_alignment")]
+    pub telos_alignment: f32,
+    /// Upper bound on intents created per feed and poll, newest first.
+    #[serde(default = "default_feed_max_new_entries")]
+    pub max_new_entries: usize,
+    #[serde(default)]
+    pub feeds: Vec<FeedSourceConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedSourceConfig {
+    pub url: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub telos_alignment: Option<f32>,
+}
+
+/// iCal URLs polled for upcoming events; each event becomes a "prepare for"
+/// intent `lead_time_minutes` before it starts.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarConfig {
+    #[serde(default = "default_calendar_poll_interval_minutes")]
+    pub poll_interval_minutes: u64,
+    #[serde(default = "default_calendar_lead_time_minutes")]
+    pub lead_time_minutes: i64,
+    #[serde(default = "default_calendar_alignment")]
+    pub telos_alignment: f32,
+    #[serde(default)]
+    pub calendars: Vec<CalendarSourceConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarSourceConfig {
+    pub url: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub lead_time_minutes: Option<i64>,
+}
+
+/// S3-compatible bucket (AWS S3, MinIO, R2 …) mirroring `data/`, from
+/// `config/storage.yml`. Objects are addressed path-style as
+/// `<endpoint>/<bucket>/<prefix><relative path>`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectStorageConfig {
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_object_storage_region")]
+    pub region: String,
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_env: String,
+    pub secret_key_env: String,
+    #[serde(default = "default_object_storage_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+    /// Download objects missing from `data/` before anything else starts.
+    #[serde(default = "default_object_storage_restore_on_start")]
+    pub restore_on_start: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    #[serde(default)]
+    pub imap: Option<ImapConfig>,
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImapConfig {
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    pub password_env: String,
+    #[serde(default = "default_imap_mailbox")]
+    pub mailbox: String,
+    #[serde(default = "default_imap_tls")]
+    pub tls: bool,
+    #[serde(default = "default_email_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    #[serde(default = "default_email_alignment")]
+    pub telos_alignment: f32,
+    /// Larger messages are flagged and skipped instead of downloaded.
+    #[serde(default = "default_imap_max_message_bytes")]
+    pub max_message_bytes: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password_env: Option<String>,
+    pub from: String,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+/// `HI_APP_ROOT`, falling back to the working directory.
+pub fn app_root() -> anyhow::Result<PathBuf> {
+    match env::var("HI_APP_ROOT") {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(env::current_dir()?),
+    }
 }
 
 impl AppConfig {
     pub fn load() -> anyhow::Result<Self> {
-        let root = match env::var("HI_APP_ROOT") {
-            Ok(path) => PathBuf::from(path),
-            Err(_) => env::current_dir()?,
-        };
+        Self::load_from(&app_root()?)
+    }
+
+    /// Load `config/` under `root`, with `root/data` as the data dir.
+    pub fn load_from(root: &Path) -> anyhow::Result<Self> {
+        let overrides = ConfigOverrides::from_env()?;
+        for section in overrides.unknown_sections() {
+            tracing::warn!(section, "ignoring env overrides for unknown config section");
+        }
+        Self::load_with(root, &overrides)
+    }
+
+    /// The named workspaces of this config, loaded from their own roots.
+    /// Env overrides are left out: they belong to the main workspace, and
+    /// would otherwise hand its secrets to every other one.
+    pub fn load_workspaces(&self) -> anyhow::Result<Vec<(String, AppConfig)>> {
+        let app_root = self.config_dir.parent().unwrap_or(Path::new("."));
+        let mut loaded = Vec::new();
+        for (name, workspace) in &self.workspaces.workspaces {
+            validate_workspace_name(name)?;
+            let root = app_root.join(&workspace.root);
+            let mut config = Self::load_with(&root, &ConfigOverrides::default())
+                .with_context(|| format!("loading workspace {name:?} from {root:?}"))?;
+            config.workspace = Some(name.clone());
+            config.server = self.server.clone();
+            config.server.pid_file = None;
+            if !config.workspaces.workspaces.is_empty() {
+                tracing::warn!(workspace = %name, "ignoring workspaces nested in a workspace");
+                config.workspaces = WorkspacesConfig::default();
+            }
+            loaded.push((name.clone(), config));
+        }
+        Ok(loaded)
+    }
+
+    /// Load this config's files again, the way they were loaded first.
+    pub fn reload(&self) -> anyhow::Result<Self> {
+        let root = self.config_dir.parent().unwrap_or(Path::new("."));
+        match &self.workspace {
+            None => Self::load_from(root),
+            Some(name) => {
+                let mut fresh = Self::load_with(root, &ConfigOverrides::default())?;
+                fresh.workspace = Some(name.clone());
+                Ok(fresh)
+            }
+        }
+    }
+
+    fn load_with(root: &Path, overrides: &ConfigOverrides) -> anyhow::Result<Self> {
         let data_dir = root.join("data");
         let config_dir = root.join("config");
-        let beat: BeatConfig = storage::load_yaml(config_dir.join("beat.yml"))?;
-        let agent: AgentConfig = storage::load_yaml(config_dir.join("agent.yml"))?;
-        let llm: LlmProviderConfig = storage::load_yaml(config_dir.join("llm.yml"))?;
-        let telegram = {
-            let path = config_dir.join("telegram.yml");
-            if path.exists() {
-                Some(storage::load_yaml(path)?)
-            } else {
-                None
-            }
-        };
+        let beat: BeatConfig = overrides.load(&config_dir, "beat")?;
+        let agent: AgentConfig = overrides.load(&config_dir, "agent")?;
+        let mut llm: LlmProviderConfig = overrides.load(&config_dir, "llm")?;
+        llm.resolve_secrets(root)?;
+        let llm_recording = overrides.load_or_default(&config_dir, "llm_recording")?;
+        let llm_cache = overrides.load_optional(&config_dir, "llm_cache")?;
+        let mut telegram: Option<TelegramConfig> =
+            overrides.load_optional(&config_dir, "telegram")?;
+        if let Some(telegram) = &mut telegram {
+            telegram.resolve_secrets(root)?;
+        }
+        let email = overrides.load_optional(&config_dir, "email")?;
+        let github = overrides.load_optional(&config_dir, "github")?;
+        let feeds = overrides.load_optional(&config_dir, "feeds")?;
+        let calendar = overrides.load_optional(&config_dir, "calendar")?;
+        let webhooks = overrides.load_or_default(&config_dir, "webhooks")?;
+        let notifications = overrides.load_or_default(&config_dir, "notifications")?;
+        let outbox = overrides.load_or_default(&config_dir, "outbox")?;
+        let sources = overrides.load_or_default(&config_dir, "sources")?;
+        let acceptance = overrides.load_or_default(&config_dir, "acceptance")?;
+        let object_storage = overrides.load_optional(&config_dir, "storage")?;
+        let memory = overrides.load_or_default(&config_dir, "memory")?;
+        let retention = overrides.load_or_default(&config_dir, "retention")?;
+        let disk = overrides.load_or_default(&config_dir, "disk")?;
+        let walk = overrides.load_or_default(&config_dir, "walk")?;
+        let jobs = overrides.load_or_default(&config_dir, "jobs")?;
+        let tools = overrides.load_or_default(&config_dir, "tools")?;
+        let ui = overrides.load_or_default(&config_dir, "ui")?;
+        let workspaces = overrides.load_or_default(&config_dir, "workspaces")?;
 
         storage::ensure_data_layout(&data_dir)?;
 
@@ -90,19 +1344,104 @@ impl AppConfig {
             beat,
             agent,
             llm,
+            llm_recording,
+            llm_cache,
             telegram,
+            email,
+            github,
+            feeds,
+            calendar,
+            webhooks,
+            notifications,
+            outbox,
+            sources,
+            acceptance,
+            memory,
+            retention,
+            disk,
+            walk,
+            jobs,
+            tools,
+            storage: object_storage,
+            ui,
+            workspaces,
+            workspace: None,
             server: ServerConfig {
                 bind_addr: env::var("HI_SERVER_BIND")
                     .unwrap_or_else(|_| "0.0.0.0:8080".to_string()),
+                pid_file: env::var_os("HI_PID_FILE").map(PathBuf::from),
+                role: match env::var("HI_ROLE") {
+                    Ok(role) => role.parse().with_context(|| "parsing HI_ROLE")?,
+                    Err(_) => Role::default(),
+                },
             },
         })
     }
 }
 
+impl AppConfig {
+    /// `server.pid_file`, or `hi_telos.pid` in the app root; processes
+    /// running a single role use `hi_telos-<role>.pid` so a server and a
+    /// worker sharing the root do not overwrite each other's.
+    pub fn pid_file(&self) -> PathBuf {
+        if let Some(path) = &self.server.pid_file {
+            return path.clone();
+        }
+        let name = match self.server.role {
+            Role::All => "hi_telos.pid".to_string(),
+            role => format!("hi_telos-{}.pid", role.as_str()),
+        };
+        self.config_dir
+            .parent()
+            .map(|root| root.join(&name))
+            .unwrap_or_else(|| PathBuf::from(name))
+    }
+
+    /// [`LlmRecordingConfig::dir`] resolved against the app root.
+    pub fn llm_recording_dir(&self) -> PathBuf {
+        match &self.llm_recording.dir {
+            Some(dir) => self
+                .config_dir
+                .parent()
+                .map(|root| root.join(dir))
+                .unwrap_or_else(|| dir.clone()),
+            None => self.data_dir.join("llm_recordings"),
+        }
+    }
+
+    /// [`LlmCacheConfig::dir`] resolved against the app root.
+    pub fn llm_cache_dir(&self) -> PathBuf {
+        match self.llm_cache.as_ref().and_then(|cache| cache.dir.as_ref()) {
+            Some(dir) => self
+                .config_dir
+                .parent()
+                .map(|root| root.join(dir))
+                .unwrap_or_else(|| dir.clone()),
+            None => self.data_dir.join("llm_cache"),
+        }
+    }
+
+    /// [`RunCommandConfig::working_dir`] resolved against the app root.
+    pub fn run_command_dir(&self) -> Option<PathBuf> {
+        let dir = &self.tools.run_command.as_ref()?.working_dir;
+        Some(
+            self.config_dir
+                .parent()
+                .map(|root| root.join(dir))
+                .unwrap_or_else(|| dir.clone()),
+        )
+    }
+}
+
 impl BeatConfig {
     pub fn interval(&self) -> Duration {
         Duration::from_secs(self.interval_minutes * 60)
     }
+
+    /// Gap without a finished beat after which the watchdog raises an alert.
+    pub fn watchdog_threshold(&self) -> Duration {
+        self.interval() * self.watchdog.missed_beats.max(1)
+    }
 }
 
 impl ServerConfig {
@@ -111,10 +1450,178 @@ impl ServerConfig {
     }
 }
 
+fn default_object_storage_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_object_storage_sync_interval_secs() -> u64 {
+    60
+}
+
+fn default_retention_interval_minutes() -> u64 {
+    24 * 60
+}
+
+fn default_fetch_max_bytes() -> usize {
+    512 * 1024
+}
+
+fn default_fetch_timeout_secs() -> u64 {
+    15
+}
+
+fn default_mcp_timeout_secs() -> u64 {
+    60
+}
+
+fn default_search_max_results() -> usize {
+    5
+}
+
+fn default_brave_api_key_env() -> String {
+    "BRAVE_API_KEY".to_string()
+}
+
+fn default_bing_api_key_env() -> String {
+    "BING_API_KEY".to_string()
+}
+
+fn default_command_timeout_secs() -> u64 {
+    30
+}
+
+fn default_command_max_output_bytes() -> usize {
+    16 * 1024
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_disk_min_free_mb() -> u64 {
+    512
+}
+
+fn default_disk_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_ui_messages_refresh_secs() -> u64 {
+    3
+}
+
+fn default_ui_intents_refresh_secs() -> u64 {
+    3
+}
+
+fn default_ui_markdown_refresh_secs() -> u64 {
+    5
+}
+
+fn default_ui_logs_refresh_secs() -> u64 {
+    4
+}
+
+fn default_object_storage_restore_on_start() -> bool {
+    true
+}
+
 fn default_intent_threshold() -> f32 {
     0.5
 }
 
+fn default_watchdog_missed_beats() -> u32 {
+    3
+}
+
+fn default_calendar_poll_interval_minutes() -> u64 {
+    15
+}
+
+fn default_calendar_lead_time_minutes() -> i64 {
+    60
+}
+
+fn default_calendar_alignment() -> f32 {
+    0.9
+}
+
+fn default_feed_poll_interval_minutes() -> u64 {
+    60
+}
+
+fn default_feed_alignment() -> f32 {
+    0.5
+}
+
+fn default_feed_max_new_entries() -> usize {
+    5
+}
+
+fn default_github_api_base() -> String {
+    "https://api.github.com".to_string()
+}
+
+fn default_webhook_signature_header() -> String {
+    "x-hi-signature".to_string()
+}
+
+fn default_webhook_source() -> String {
+    "webhook".to_string()
+}
+
+fn default_webhook_alignment() -> f32 {
+    0.8
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    3
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_imap_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+fn default_history_max_tokens() -> usize {
+    3_000
+}
+
+fn default_history_keep_recent_steps() -> usize {
+    3
+}
+
+fn default_imap_tls() -> bool {
+    true
+}
+
+fn default_openai_function_calling() -> bool {
+    true
+}
+
+fn default_email_poll_interval_secs() -> u64 {
+    300
+}
+
+fn default_imap_max_message_bytes() -> usize {
+    25 * 1024 * 1024
+}
+
+fn default_email_alignment() -> f32 {
+    1.0
+}
+
+fn default_weekly_review_day() -> Weekday {
+    Weekday::Mon
+}
+
+fn default_weekly_review_alignment() -> f32 {
+    1.0
+}
+
 fn default_agent_max_steps() -> usize {
     1
 }
@@ -123,10 +1630,34 @@ fn default_agent_persona() -> String {
     "TelosOps".to_string()
 }
 
-fn default_openai_api_key_env() -> String {
+pub(crate) fn default_openai_api_key_env() -> String {
     "OPENAI_API_KEY".to_string()
 }
 
+fn default_telegram_poll_timeout_secs() -> u64 {
+    30
+}
+
+fn default_session_max_turns() -> usize {
+    10
+}
+
+fn default_session_window_minutes() -> i64 {
+    120
+}
+
+fn default_outbox_max_attempts() -> u32 {
+    5
+}
+
+fn default_outbox_retry_base_secs() -> u64 {
+    30
+}
+
+fn default_outbox_retry_max_secs() -> u64 {
+    3600
+}
+
 fn default_telegram_api_base() -> String {
     "https://api.telegram.org".to_string()
 }
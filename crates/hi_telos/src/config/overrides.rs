@@ -0,0 +1,260 @@
+use std::{collections::BTreeMap, env, path::Path};
+
+use anyhow::{Context, bail};
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+
+use crate::storage;
+
+/// Env var holding a YAML (or JSON) mapping of section name to settings,
+/// e.g. `{beat: {interval_minutes: 15}, llm: {provider: open_ai}}`.
+pub const CONFIG_OVERRIDES_ENV: &str = "HI_CONFIG_OVERRIDES";
+
+/// Every `config/<section>.yml` that [`ConfigOverrides`] can reach.
+pub const CONFIG_SECTIONS: &[&str] = &[
+    "beat",
+    "agent",
+    "llm",
+    "llm_recording",
+    "llm_cache",
+    "telegram",
+    "email",
+    "github",
+    "feeds",
+    "calendar",
+    "webhooks",
+    "notifications",
+    "outbox",
+    "sources",
+    "acceptance",
+    "storage",
+    "memory",
+    "retention",
+    "disk",
+    "walk",
+    "jobs",
+    "tools",
+    "ui",
+    "workspaces",
+];
+
+const ENV_PREFIX: &str = "HI_";
+const ENV_SEPARATOR: &str = "__";
+
+/// Settings from the environment layered over `config/<section>.yml`, so
+/// containers can configure the app without templating YAML.
+///
+/// `HI_BEAT__INTERVAL_MINUTES=15` sets `interval_minutes` in `beat.yml`;
+/// further `__` segments reach nested keys (`HI_BEAT__APPROVAL__SOURCES`).
+/// Values are parsed as YAML scalars, so quote them (`'"12345"'`) to force
+/// a string. Single variables win over [`CONFIG_OVERRIDES_ENV`], which wins
+/// over the files.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    sections: BTreeMap<String, Value>,
+}
+
+impl ConfigOverrides {
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_vars(env::vars())
+    }
+
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> anyhow::Result<Self> {
+        let mut blob = None;
+        let mut single = Vec::new();
+        for (name, value) in vars {
+            if name == CONFIG_OVERRIDES_ENV {
+                blob = Some(value);
+            } else if let Some(path) = name.strip_prefix(ENV_PREFIX)
+                && path.contains(ENV_SEPARATOR)
+            {
+                single.push((path.to_string(), value));
+            }
+        }
+
+        let mut overrides = Self::default();
+        if let Some(blob) = blob.filter(|blob| !blob.trim().is_empty()) {
+            let parsed: BTreeMap<String, Value> = serde_yaml::from_str(&blob)
+                .with_context(|| format!("parsing {CONFIG_OVERRIDES_ENV} as a mapping"))?;
+            for (section, value) in parsed {
+                overrides.merge(&section.to_lowercase(), value);
+            }
+        }
+        // Sorted so that nested keys land after their parents deterministically.
+        single.sort();
+        for (path, raw) in single {
+            let mut keys = path.split(ENV_SEPARATOR).map(str::to_lowercase);
+            let section = keys.next().unwrap_or_default();
+            let keys: Vec<String> = keys.collect();
+            if section.is_empty() || keys.iter().any(String::is_empty) {
+                bail!("malformed config override {ENV_PREFIX}{path}");
+            }
+            let value = keys.iter().rev().fold(parse_scalar(&raw), |value, key| {
+                let mut mapping = Mapping::new();
+                mapping.insert(Value::String(key.clone()), value);
+                Value::Mapping(mapping)
+            });
+            overrides.merge(&section, value);
+        }
+        Ok(overrides)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Section names that have overrides.
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.sections.keys().map(String::as_str)
+    }
+
+    /// Overridden sections that match no config file, usually a typo.
+    pub fn unknown_sections(&self) -> impl Iterator<Item = &str> {
+        self.sections()
+            .filter(|section| !CONFIG_SECTIONS.contains(section))
+    }
+
+    fn merge(&mut self, section: &str, value: Value) {
+        match self.sections.get_mut(section) {
+            Some(existing) => deep_merge(existing, value),
+            None => {
+                self.sections.insert(section.to_string(), value);
+            }
+        }
+    }
+
+    /// `config/<section>.yml`, which must exist unless overrides supply it.
+    pub fn load<T: DeserializeOwned>(&self, config_dir: &Path, section: &str) -> anyhow::Result<T> {
+        match self.load_optional(config_dir, section)? {
+            Some(value) => Ok(value),
+            None => storage::load_yaml(config_dir.join(format!("{section}.yml"))),
+        }
+    }
+
+    /// `None` when neither the file nor any override exists.
+    pub fn load_optional<T: DeserializeOwned>(
+        &self,
+        config_dir: &Path,
+        section: &str,
+    ) -> anyhow::Result<Option<T>> {
+        let path = config_dir.join(format!("{section}.yml"));
+        let Some(overlay) = self.sections.get(section) else {
+            return if path.exists() {
+                storage::load_yaml(path).map(Some)
+            } else {
+                Ok(None)
+            };
+        };
+
+        let mut value = if path.exists() {
+            storage::load_yaml::<Value>(path.clone())?
+        } else {
+            Value::Mapping(Mapping::new())
+        };
+        deep_merge(&mut value, overlay.clone());
+        serde_yaml::from_value(value)
+            .map(Some)
+            .with_context(|| format!("applying env overrides to {:?}", path))
+    }
+
+    /// Like [`Self::load_optional`], falling back to `T::default()`.
+    pub fn load_or_default<T: DeserializeOwned + Default>(
+        &self,
+        config_dir: &Path,
+        section: &str,
+    ) -> anyhow::Result<T> {
+        Ok(self.load_optional(config_dir, section)?.unwrap_or_default())
+    }
+}
+
+fn parse_scalar(raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return Value::String(raw.to_string());
+    }
+    serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BeatConfig, LlmProviderConfig, TelegramConfig};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn env_vars_override_files_and_blob() {
+        let temp = tempdir().unwrap();
+        fs::write(
+            temp.path().join("beat.yml"),
+            "interval_minutes: 30\nintent_threshold: 0.4\napproval:\n  sources: [github]\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join("llm.yml"), "provider: local_stub\n").unwrap();
+
+        let overrides = ConfigOverrides::from_vars(vars(&[
+            (
+                "HI_CONFIG_OVERRIDES",
+                "{beat: {interval_minutes: 10, intent_threshold: 0.8}, llm: {provider: open_ai, model: gpt-4o-mini}}",
+            ),
+            ("HI_BEAT__INTERVAL_MINUTES", "15"),
+            ("HI_BEAT__APPROVAL__MAX_COST_ESTIMATE", "2.5"),
+            ("HI_TELEGRAM__BOT_TOKEN", "123:abc"),
+            ("HI_BAET__INTERVAL_MINUTES", "1"),
+            ("HI_SERVER_BIND", "0.0.0.0:9000"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            overrides.sections().collect::<Vec<_>>(),
+            vec!["baet", "beat", "llm", "telegram"]
+        );
+        assert_eq!(
+            overrides.unknown_sections().collect::<Vec<_>>(),
+            vec!["baet"]
+        );
+
+        let beat: BeatConfig = overrides.load(temp.path(), "beat").unwrap();
+        assert_eq!(beat.interval_minutes, 15);
+        assert_eq!(beat.intent_threshold, 0.8);
+        assert_eq!(beat.approval.sources, vec!["github".to_string()]);
+        assert_eq!(beat.approval.max_cost_estimate, Some(2.5));
+
+        let llm: LlmProviderConfig = overrides.load(temp.path(), "llm").unwrap();
+        assert!(
+            matches!(llm, LlmProviderConfig::OpenAi { ref model, .. } if model == "gpt-4o-mini")
+        );
+
+        // A section can come from the environment alone.
+        let telegram: Option<TelegramConfig> =
+            overrides.load_optional(temp.path(), "telegram").unwrap();
+        assert_eq!(telegram.unwrap().bot_token, "123:abc");
+        let email: Option<serde_yaml::Value> =
+            overrides.load_optional(temp.path(), "email").unwrap();
+        assert!(email.is_none());
+
+        assert!(ConfigOverrides::from_vars(vars(&[("HI_BEAT____X", "1")])).is_err());
+    }
+}
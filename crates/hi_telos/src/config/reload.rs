@@ -0,0 +1,344 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use tokio::{select, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+
+use super::AppConfig;
+use crate::{events::ConfigReloaded, state::AppContext};
+
+/// How often `config/` is checked for edits.
+const CONFIG_POLL_INTERVAL_SECS: u64 = 2;
+
+/// A setting whose new value is now in effect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// What applying a freshly loaded config did.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigReload {
+    pub applied: Vec<ConfigChange>,
+    /// Sections that changed on disk but are only read at startup.
+    pub needs_restart: Vec<String>,
+}
+
+impl ConfigReload {
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.needs_restart.is_empty()
+    }
+}
+
+/// Copy the settings that are safe to change at runtime from `fresh` into
+/// `current`: the beat interval and thresholds, the agent personas, step
+/// limit and session window, notification rules, the outbox retry policy,
+/// source policies, acceptance plan documents, the disk guard and the walk
+/// policy. Everything
+/// else keeps its running value and is reported in
+/// [`ConfigReload::needs_restart`].
+pub fn merge_reloadable(current: &AppConfig, fresh: &AppConfig) -> (AppConfig, ConfigReload) {
+    let mut applied = Vec::new();
+    let (old, new) = (&current.beat, &fresh.beat);
+    diff(
+        &mut applied,
+        "beat.interval_minutes",
+        &old.interval_minutes,
+        &new.interval_minutes,
+    );
+    diff(
+        &mut applied,
+        "beat.intent_threshold",
+        &old.intent_threshold,
+        &new.intent_threshold,
+    );
+    diff(
+        &mut applied,
+        "beat.weekly_review",
+        &old.weekly_review,
+        &new.weekly_review,
+    );
+    diff(&mut applied, "beat.approval", &old.approval, &new.approval);
+    diff(&mut applied, "beat.watchdog", &old.watchdog, &new.watchdog);
+    let (old, new) = (&current.agent, &fresh.agent);
+    diff(
+        &mut applied,
+        "agent.max_react_steps",
+        &old.max_react_steps,
+        &new.max_react_steps,
+    );
+    diff(&mut applied, "agent.persona", &old.persona, &new.persona);
+    diff(&mut applied, "agent.session", &old.session, &new.session);
+    diff(&mut applied, "agent.personas", &old.personas, &new.personas);
+    diff(
+        &mut applied,
+        "agent.default_persona",
+        &old.default_persona,
+        &new.default_persona,
+    );
+    diff(
+        &mut applied,
+        "agent.sp_recall",
+        &old.sp_recall,
+        &new.sp_recall,
+    );
+    // Rules can carry channel URLs and tokens, so only the names are logged.
+    let rule_names = |config: &AppConfig| -> Vec<String> {
+        config
+            .notifications
+            .rules
+            .iter()
+            .map(|rule| rule.name.clone())
+            .collect()
+    };
+    if format!("{:?}", current.notifications) != format!("{:?}", fresh.notifications) {
+        applied.push(ConfigChange {
+            field: "notifications.rules".to_string(),
+            before: format!("{:?}", rule_names(current)),
+            after: format!("{:?}", rule_names(fresh)),
+        });
+    }
+
+    diff(&mut applied, "outbox", &current.outbox, &fresh.outbox);
+
+    diff(
+        &mut applied,
+        "sources",
+        &current.sources.policies,
+        &fresh.sources.policies,
+    );
+
+    diff(
+        &mut applied,
+        "acceptance.docs",
+        &current.acceptance.docs,
+        &fresh.acceptance.docs,
+    );
+    diff(
+        &mut applied,
+        "disk.min_free_mb",
+        &current.disk.min_free_mb,
+        &fresh.disk.min_free_mb,
+    );
+    diff(
+        &mut applied,
+        "disk.check_interval_secs",
+        &current.disk.check_interval_secs,
+        &fresh.disk.check_interval_secs,
+    );
+
+    diff(&mut applied, "walk", &current.walk, &fresh.walk);
+
+    let mut needs_restart = Vec::new();
+    let mut restart_only = |section: &str, changed: bool| {
+        if changed {
+            needs_restart.push(section.to_string());
+        }
+    };
+    restart_only("llm", changed(&current.llm, &fresh.llm));
+    restart_only(
+        "llm_recording",
+        changed(&current.llm_recording, &fresh.llm_recording),
+    );
+    restart_only("llm_cache", changed(&current.llm_cache, &fresh.llm_cache));
+    restart_only("telegram", changed(&current.telegram, &fresh.telegram));
+    restart_only("email", changed(&current.email, &fresh.email));
+    restart_only("github", changed(&current.github, &fresh.github));
+    restart_only("feeds", changed(&current.feeds, &fresh.feeds));
+    restart_only("calendar", changed(&current.calendar, &fresh.calendar));
+    restart_only("webhooks", changed(&current.webhooks, &fresh.webhooks));
+    restart_only("memory", changed(&current.memory, &fresh.memory));
+    restart_only("jobs", changed(&current.jobs, &fresh.jobs));
+    restart_only("retention", changed(&current.retention, &fresh.retention));
+    restart_only("storage", changed(&current.storage, &fresh.storage));
+    restart_only("ui", changed(&current.ui, &fresh.ui));
+    restart_only("tools", changed(&current.tools, &fresh.tools));
+    restart_only(
+        "workspaces",
+        changed(&current.workspaces, &fresh.workspaces),
+    );
+
+    let mut merged = current.clone();
+    merged.beat = fresh.beat.clone();
+    merged.agent = fresh.agent.clone();
+    merged.notifications = fresh.notifications.clone();
+    merged.outbox = fresh.outbox.clone();
+    merged.sources = fresh.sources.clone();
+    merged.acceptance = fresh.acceptance.clone();
+    merged.disk = fresh.disk.clone();
+    merged.walk = fresh.walk.clone();
+    (
+        merged,
+        ConfigReload {
+            applied,
+            needs_restart,
+        },
+    )
+}
+
+/// The configs have no `PartialEq`; their `Debug` output covers every field.
+fn changed<T: Debug>(before: &T, after: &T) -> bool {
+    format!("{before:?}") != format!("{after:?}")
+}
+
+fn diff<T: Debug>(changes: &mut Vec<ConfigChange>, field: &str, before: &T, after: &T) {
+    if changed(before, after) {
+        changes.push(ConfigChange {
+            field: field.to_string(),
+            before: format!("{before:?}"),
+            after: format!("{after:?}"),
+        });
+    }
+}
+
+fn config_mtimes(config_dir: &Path) -> BTreeMap<PathBuf, SystemTime> {
+    let Ok(entries) = std::fs::read_dir(config_dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yml"))
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|meta| meta.modified()).ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+/// Poll `config/` and apply edits without a restart; see
+/// [`merge_reloadable`]. A file that fails to parse is logged and the
+/// running config is kept.
+pub fn spawn_watcher(ctx: AppContext) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let config_dir = ctx.config().config_dir.clone();
+        if config_dir.parent().is_none() {
+            warn!(dir = ?config_dir, "config dir has no parent; hot reload disabled");
+            return;
+        }
+        let mut seen = config_mtimes(&config_dir);
+        loop {
+            select! {
+                _ = sleep(Duration::from_secs(CONFIG_POLL_INTERVAL_SECS)) => {}
+                _ = ctx.wait_for_shutdown() => break,
+            }
+            let current = config_mtimes(&config_dir);
+            if current == seen {
+                continue;
+            }
+            seen = current;
+
+            let fresh = match ctx.config().reload() {
+                Ok(fresh) => fresh,
+                Err(err) => {
+                    warn!(error = ?err, "config changed but failed to load; keeping current");
+                    continue;
+                }
+            };
+            let reload = ctx.reload_config(&fresh);
+            if reload.is_empty() {
+                continue;
+            }
+            for change in &reload.applied {
+                info!(
+                    field = %change.field,
+                    before = %change.before,
+                    after = %change.after,
+                    "config reloaded"
+                );
+            }
+            if !reload.needs_restart.is_empty() {
+                warn!(
+                    sections = ?reload.needs_restart,
+                    "config changes need a restart to take effect"
+                );
+            }
+            ctx.events()
+                .publish_config_reloaded(ConfigReloaded::new(reload, ctx.now()));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent::AgentRuntime, fixtures, llm::LocalStubClient};
+    use std::{fs, sync::Arc};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn reload_applies_safe_settings_and_flags_the_rest() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        let config = AppConfig::load_from(&root).unwrap();
+        let agent = AgentRuntime::new(config.agent.clone(), Arc::new(LocalStubClient));
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let mut reloads = ctx.events().subscribe_config_reloads();
+
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 15\nintent_threshold: 0.9\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 2\npersona: NightOwl\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("config/llm.yml"),
+            "provider: open_ai\nmodel: gpt-4o-mini\n",
+        )
+        .unwrap();
+
+        let fresh = AppConfig::load_from(&root).unwrap();
+        let reload = ctx.reload_config(&fresh);
+        let fields: Vec<&str> = reload
+            .applied
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect();
+        assert!(fields.contains(&"beat.interval_minutes"));
+        assert!(fields.contains(&"agent.persona"));
+        assert_eq!(reload.needs_restart, vec!["llm".to_string()]);
+
+        let config = ctx.config();
+        assert_eq!(config.beat.interval_minutes, 15);
+        assert_eq!(config.agent.persona, "NightOwl");
+        assert!(matches!(
+            config.llm,
+            super::super::LlmProviderConfig::LocalStub
+        ));
+        assert_eq!(ctx.agent().agent_config().persona, "NightOwl");
+
+        // Applying the same files again changes nothing.
+        assert!(ctx.reload_config(&fresh).applied.is_empty());
+        assert!(reloads.try_recv().is_err());
+
+        let watcher = spawn_watcher(ctx.clone());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        fs::write(root.join("config/agent.yml"), "persona: EarlyBird\n").unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), reloads.recv())
+            .await
+            .expect("reload event")
+            .unwrap();
+        assert!(
+            event
+                .reload
+                .applied
+                .iter()
+                .any(|change| change.field == "agent.persona" && change.after.contains("EarlyBird"))
+        );
+        assert_eq!(ctx.agent().agent_config().persona, "EarlyBird");
+
+        ctx.request_shutdown();
+        watcher.await.unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{Context, bail};
+
+use super::{LlmProviderConfig, TelegramConfig};
+
+/// Read a secret kept outside the YAML: from `file` (relative paths are
+/// resolved against the app root, e.g. `/run/secrets/telegram_token` or a
+/// file mounted next to it) or from the stdout of `command`, an argv such
+/// as `["pass", "show", "hi/telegram"]` that runs without a shell. Leading
+/// and trailing whitespace is dropped. `None` when neither is set.
+pub fn resolve_secret(
+    root: &Path,
+    field: &str,
+    file: Option<&Path>,
+    command: Option<&[String]>,
+) -> anyhow::Result<Option<String>> {
+    let secret = match (file, command) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => bail!("set only one of {field}_file and {field}_command"),
+        (Some(file), None) => {
+            let path = root.join(file);
+            fs::read_to_string(&path).with_context(|| format!("reading {field}_file {path:?}"))?
+        }
+        (None, Some(command)) => run_secret_command(field, command)?,
+    };
+    let secret = secret.trim();
+    if secret.is_empty() {
+        bail!("{field} from {field}_file / {field}_command is empty");
+    }
+    Ok(Some(secret.to_string()))
+}
+
+fn run_secret_command(field: &str, command: &[String]) -> anyhow::Result<String> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("{field}_command is empty");
+    };
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("running {field}_command {program:?}"))?;
+    if !output.status.success() {
+        bail!(
+            "{field}_command {program:?} failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("{field}_command {program:?} printed non-UTF-8 output"))
+}
+
+impl TelegramConfig {
+    /// Fill [`Self::bot_token`] from `bot_token_file` / `bot_token_command`
+    /// when the token is not inline.
+    pub fn resolve_secrets(&mut self, root: &Path) -> anyhow::Result<()> {
+        let resolved = resolve_secret(
+            root,
+            "bot_token",
+            self.bot_token_file.as_deref(),
+            self.bot_token_command.as_deref(),
+        )
+        .context("resolving telegram.yml bot_token")?;
+        match resolved {
+            Some(_) if !self.bot_token.is_empty() => {
+                bail!("telegram.yml sets bot_token inline and via bot_token_file/command")
+            }
+            Some(token) => self.bot_token = token,
+            None if self.bot_token.is_empty() => {
+                bail!("telegram.yml needs bot_token, bot_token_file or bot_token_command")
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+impl LlmProviderConfig {
+    /// Fill the OpenAI `api_key` from `api_key_file` / `api_key_command`;
+    /// without either the client keeps reading `api_key_env`.
+    pub fn resolve_secrets(&mut self, root: &Path) -> anyhow::Result<()> {
+        if let LlmProviderConfig::OpenAi {
+            api_key,
+            api_key_file,
+            api_key_command,
+            ..
+        } = self
+        {
+            *api_key = resolve_secret(
+                root,
+                "api_key",
+                api_key_file.as_deref(),
+                api_key_command.as_deref(),
+            )
+            .context("resolving llm.yml api_key")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn secrets_come_from_files_or_commands() {
+        let temp = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let token_path = outside.path().join("telegram_token");
+        fs::write(&token_path, "123:from-file\n").unwrap();
+
+        let mut telegram: TelegramConfig =
+            serde_yaml::from_str(&format!("bot_token_file: {}\n", token_path.display())).unwrap();
+        telegram.resolve_secrets(temp.path()).unwrap();
+        assert_eq!(telegram.bot_token, "123:from-file");
+
+        let mut telegram: TelegramConfig =
+            serde_yaml::from_str("bot_token_command: [echo, '  456:from-command ']\n").unwrap();
+        telegram.resolve_secrets(temp.path()).unwrap();
+        assert_eq!(telegram.bot_token, "456:from-command");
+
+        fs::write(temp.path().join("relative_token"), "789:relative").unwrap();
+        let mut telegram: TelegramConfig =
+            serde_yaml::from_str("bot_token_file: relative_token\n").unwrap();
+        telegram.resolve_secrets(temp.path()).unwrap();
+        assert_eq!(telegram.bot_token, "789:relative");
+
+        for yaml in [
+            "api_base: https://api.telegram.org\n",
+            "bot_token: 1:inline\nbot_token_command: [echo, '2:cmd']\n",
+            "bot_token_command: ['false']\n",
+            "bot_token_file: missing\n",
+            "bot_token_command: [printf, '']\n",
+        ] {
+            let mut telegram: TelegramConfig = serde_yaml::from_str(yaml).unwrap();
+            assert!(telegram.resolve_secrets(temp.path()).is_err(), "{yaml}");
+        }
+
+        let mut llm: LlmProviderConfig = serde_yaml::from_str(&format!(
+            "provider: open_ai\nmodel: gpt-4o-mini\napi_key_file: {}\n",
+            token_path.display()
+        ))
+        .unwrap();
+        llm.resolve_secrets(temp.path()).unwrap();
+        assert!(matches!(
+            llm,
+            LlmProviderConfig::OpenAi { api_key: Some(ref key), .. } if key == "123:from-file"
+        ));
+
+        let mut llm: LlmProviderConfig =
+            serde_yaml::from_str("provider: open_ai\nmodel: gpt-4o-mini\n").unwrap();
+        llm.resolve_secrets(temp.path()).unwrap();
+        assert!(matches!(
+            llm,
+            LlmProviderConfig::OpenAi { api_key: None, .. }
+        ));
+    }
+}
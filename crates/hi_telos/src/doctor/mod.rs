@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::{
+    backup::BACKUPS_DIR,
+    llm::LlmLogEntry,
+    storage::{
+        self, MemoryEntry, MessageLogEntry, OutboxMessage, PendingQuestion, SchemaKind,
+        StructuredContent,
+    },
+};
+
+/// Unparseable files and lines are moved under
+/// `data/quarantine/<timestamp>/`, keeping their path in the data dir.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+const QUESTIONS_DIR: &str = "intent/waiting/questions";
+const WAITING_DIR: &str = "intent/waiting";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    MissingDir,
+    Corrupt,
+    Orphaned,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// Path relative to the data dir.
+    pub path: String,
+    pub detail: String,
+    /// Set when `--repair` fixed it (recreated, quarantined or requeued).
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DoctorReport {
+    pub files_checked: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl DoctorReport {
+    pub fn unresolved(&self) -> usize {
+        self.issues.iter().filter(|issue| !issue.repaired).count()
+    }
+}
+
+/// Check the data dir: the directory layout, that every intent, memory,
+/// log and state file parses, and that paused runs still match their
+/// intents. With `repair`, missing directories are recreated, corrupt files
+/// (or just the corrupt lines of a JSONL file) are quarantined, orphaned
+/// questions are quarantined and orphaned waiting intents are requeued.
+pub fn run(data_dir: &Path, repair: bool, now: DateTime<Utc>) -> anyhow::Result<DoctorReport> {
+    let mut doctor = Doctor {
+        data_dir,
+        repair,
+        now,
+        quarantine: data_dir
+            .join(QUARANTINE_DIR)
+            .join(now.format("%Y%m%dT%H%M%SZ").to_string()),
+        report: DoctorReport::default(),
+    };
+
+    doctor.check_layout()?;
+    for path in doctor.files() {
+        doctor.check_file(&path)?;
+    }
+    doctor.check_questions()?;
+    Ok(doctor.report)
+}
+
+struct Doctor<'a> {
+    data_dir: &'a Path,
+    repair: bool,
+    now: DateTime<Utc>,
+    quarantine: PathBuf,
+    report: DoctorReport,
+}
+
+impl Doctor<'_> {
+    fn check_layout(&mut self) -> anyhow::Result<()> {
+        let missing = storage::missing_layout_dirs(self.data_dir);
+        if self.repair && !missing.is_empty() {
+            storage::ensure_data_layout(self.data_dir)?;
+        }
+        for dir in missing {
+            self.report.issues.push(Issue {
+                kind: IssueKind::MissingDir,
+                path: dir.to_string(),
+                detail: "required directory is missing".to_string(),
+                repaired: self.repair,
+            });
+        }
+        Ok(())
+    }
+
+    /// Every file the doctor can validate, skipping snapshots, earlier
+    /// quarantines, restore staging and hidden files (temp files, the lock).
+    fn files(&self) -> Vec<PathBuf> {
+        WalkDir::new(self.data_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                let skipped_dir =
+                    entry.depth() == 1 && (name == BACKUPS_DIR || name == QUARANTINE_DIR);
+                let hidden = entry.depth() > 0 && name.starts_with('.');
+                !(skipped_dir || hidden)
+            })
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    fn check_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let relative = self.relative(path);
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        match extension {
+            Some("md") if relative.starts_with("intent/") => {
+                self.report.files_checked += 1;
+                if let Err(err) = storage::read_intent_id(path) {
+                    self.corrupt_file(path, format!("{err:#}"))?;
+                }
+            }
+            Some("json") => {
+                self.report.files_checked += 1;
+                let checked = fs::read_to_string(path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|raw| validate_record(&relative, &raw));
+                if let Err(err) = checked {
+                    self.corrupt_file(path, format!("{err:#}"))?;
+                }
+            }
+            Some("jsonl") => {
+                self.report.files_checked += 1;
+                self.check_lines(path, &relative)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn check_lines(&mut self, path: &Path, relative: &str) -> anyhow::Result<()> {
+        let raw = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) => return self.corrupt_file(path, format!("{err:#}")),
+        };
+        let mut good = String::new();
+        let mut bad = String::new();
+        let mut bad_lines = Vec::new();
+        for (index, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if validate_record(relative, line).is_ok() {
+                good.push_str(line);
+                good.push('\n');
+            } else {
+                bad.push_str(line);
+                bad.push('\n');
+                bad_lines.push((index + 1).to_string());
+            }
+        }
+        if bad_lines.is_empty() {
+            return Ok(());
+        }
+
+        if self.repair {
+            let destination = self.quarantine_path(relative)?;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&destination)
+                .and_then(|mut file| file.write_all(bad.as_bytes()))
+                .with_context(|| format!("quarantining lines to {:?}", destination))?;
+            storage::write_atomic(path, good)?;
+        }
+        self.report.issues.push(Issue {
+            kind: IssueKind::Corrupt,
+            path: relative.to_string(),
+            detail: format!("unparseable line(s) {}", bad_lines.join(", ")),
+            repaired: self.repair,
+        });
+        Ok(())
+    }
+
+    /// Questions need their intent in `intent/waiting` and vice versa;
+    /// otherwise the run can never resume.
+    fn check_questions(&mut self) -> anyhow::Result<()> {
+        let mut waiting: HashMap<Uuid, PathBuf> = HashMap::new();
+        let waiting_dir = self.data_dir.join(WAITING_DIR);
+        if waiting_dir.is_dir() {
+            for entry in fs::read_dir(&waiting_dir)? {
+                let path = entry?.path();
+                if path.is_file()
+                    && let Ok(Some(id)) = storage::read_intent_id(&path)
+                {
+                    waiting.insert(id, path);
+                }
+            }
+        }
+
+        let mut asked = HashSet::new();
+        let questions_dir = self.data_dir.join(QUESTIONS_DIR);
+        if questions_dir.is_dir() {
+            let mut paths: Vec<PathBuf> = fs::read_dir(&questions_dir)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            paths.sort();
+            for path in paths {
+                let Some(id) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<Uuid>().ok())
+                else {
+                    continue;
+                };
+                if waiting.contains_key(&id) {
+                    asked.insert(id);
+                    continue;
+                }
+                let relative = self.relative(&path);
+                if self.repair {
+                    self.move_to_quarantine(&path, &relative)?;
+                }
+                self.report.issues.push(Issue {
+                    kind: IssueKind::Orphaned,
+                    path: relative,
+                    detail: "question for an intent that is no longer waiting".to_string(),
+                    repaired: self.repair,
+                });
+            }
+        }
+
+        let mut orphans: Vec<_> = waiting
+            .into_iter()
+            .filter(|(id, _)| !asked.contains(id))
+            .map(|(_, path)| path)
+            .collect();
+        orphans.sort();
+        for path in orphans {
+            let relative = self.relative(&path);
+            if self.repair {
+                storage::promote_to_queue(&path, self.data_dir, self.now)?;
+            }
+            self.report.issues.push(Issue {
+                kind: IssueKind::Orphaned,
+                path: relative,
+                detail: "waiting intent without a pending question".to_string(),
+                repaired: self.repair,
+            });
+        }
+        Ok(())
+    }
+
+    fn corrupt_file(&mut self, path: &Path, detail: String) -> anyhow::Result<()> {
+        let relative = self.relative(path);
+        if self.repair {
+            self.move_to_quarantine(path, &relative)?;
+        }
+        self.report.issues.push(Issue {
+            kind: IssueKind::Corrupt,
+            path: relative,
+            detail,
+            repaired: self.repair,
+        });
+        Ok(())
+    }
+
+    fn move_to_quarantine(&self, path: &Path, relative: &str) -> anyhow::Result<()> {
+        let destination = self.quarantine_path(relative)?;
+        fs::rename(path, &destination)
+            .with_context(|| format!("quarantining {:?} to {:?}", path, destination))
+    }
+
+    fn quarantine_path(&self, relative: &str) -> anyhow::Result<PathBuf> {
+        let destination = self.quarantine.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {:?}", parent))?;
+        }
+        Ok(destination)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(self.data_dir)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Parse one record with the type its location implies; files the doctor
+/// has no type for only need to be valid JSON.
+fn validate_record(relative: &str, raw: &str) -> anyhow::Result<()> {
+    // Day indexes next to the logs are plain JSON.
+    if relative.starts_with("logs/llm/") && relative.ends_with(".jsonl") {
+        storage::parse_record::<LlmLogEntry>(SchemaKind::LlmLog, raw)?;
+    } else if relative.starts_with("memory/") {
+        storage::parse_record::<MemoryEntry>(SchemaKind::MemoryEntry, raw)?;
+    } else if relative.starts_with("messages/") {
+        serde_json::from_str::<MessageLogEntry>(raw)?;
+    } else if relative.starts_with(QUESTIONS_DIR) {
+        serde_json::from_str::<PendingQuestion>(raw)?;
+    } else if relative.starts_with("outbox/") {
+        serde_json::from_str::<OutboxMessage>(raw)?;
+    } else if relative == "sp/index.json" {
+        storage::parse_record::<Value>(SchemaKind::SpIndex, raw)?;
+    } else if relative.starts_with("mock/text_structure") {
+        let mut snapshot = storage::parse_record::<Value>(SchemaKind::StructuredSnapshot, raw)?;
+        serde_json::from_value::<StructuredContent>(snapshot["content"].take())?;
+    } else {
+        serde_json::from_str::<Value>(raw)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reports_then_quarantines_corrupt_and_orphaned_entries() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        fs::remove_dir(data_dir.join("mock/text_structure_history")).unwrap();
+
+        let log = data_dir.join("logs/llm/2025/01/01.jsonl");
+        fs::create_dir_all(log.parent().unwrap()).unwrap();
+        let good = serde_json::to_string(&LlmLogEntry::new(
+            Uuid::new_v4(),
+            Utc::now(),
+            "plan",
+            "prompt",
+            "response",
+            &crate::llm::LlmIdentity {
+                provider: "local_stub",
+                model: None,
+            },
+        ))
+        .unwrap();
+        fs::write(&log, format!("{good}\n{{truncated\n")).unwrap();
+        fs::write(data_dir.join("intent/inbox/broken.md"), "---\nid: [\n---\n").unwrap();
+        let stuck = Uuid::new_v4();
+        fs::write(
+            data_dir.join("intent/waiting/stuck.md"),
+            format!("---\nid: {stuck}\nsummary: Stuck\n---\n"),
+        )
+        .unwrap();
+
+        let now = Utc.with_ymd_and_hms(2025, 2, 1, 8, 0, 0).unwrap();
+        let report = run(data_dir, false, now).unwrap();
+        let kinds: Vec<_> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.path.as_str()))
+            .collect();
+        assert!(kinds.contains(&(IssueKind::MissingDir, "mock/text_structure_history")));
+        assert!(kinds.contains(&(IssueKind::Corrupt, "logs/llm/2025/01/01.jsonl")));
+        assert!(kinds.contains(&(IssueKind::Corrupt, "intent/inbox/broken.md")));
+        assert!(kinds.contains(&(IssueKind::Orphaned, "intent/waiting/stuck.md")));
+        assert_eq!(report.unresolved(), report.issues.len());
+        assert!(data_dir.join("intent/inbox/broken.md").exists());
+
+        let repaired = run(data_dir, true, now).unwrap();
+        assert_eq!(repaired.unresolved(), 0);
+        assert_eq!(fs::read_to_string(&log).unwrap(), format!("{good}\n"));
+        let quarantine = data_dir.join("quarantine/20250201T080000Z");
+        assert_eq!(
+            fs::read_to_string(quarantine.join("logs/llm/2025/01/01.jsonl")).unwrap(),
+            "{truncated\n"
+        );
+        assert!(quarantine.join("intent/inbox/broken.md").exists());
+        assert!(storage::scan_inbox(data_dir).unwrap().is_empty());
+        let queue = storage::scan_queue(data_dir).unwrap();
+        assert_eq!(queue[0].intent.id, stuck);
+
+        let clean = run(data_dir, false, now).unwrap();
+        assert!(clean.issues.is_empty(), "{:?}", clean.issues);
+    }
+}
@@ -0,0 +1,617 @@
+use std::{collections::BTreeMap, env, future::Future, path::Path, sync::Arc, time::Duration};
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    transport::smtp::authentication::Credentials,
+};
+use mail_parser::MessageParser;
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{ClientConfig, RootCertStore, pki_types::ServerName},
+};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::{AppConfig, ImapConfig, SmtpConfig, SmtpSecurity},
+    sources::{IngestMode, IngestSource},
+    state::AppContext,
+    storage::{self, IntentDraft, MessageDirection, MessageLogEntry},
+    text,
+};
+
+pub const FROM_KEY: &str = "email_from";
+pub const MESSAGE_ID_KEY: &str = "email_message_id";
+
+const EMAIL_SOURCE: &str = "email";
+const EMAIL_SUMMARY_MAX_CHARS: usize = 80;
+/// Bound on connecting and on every read from the IMAP server, so a stalled
+/// server cannot hang the poll.
+const IMAP_TIMEOUT_SECS: u64 = 60;
+/// Keyword set on messages that cannot be parsed or exceed
+/// `max_message_bytes`, so later polls skip them instead of failing on them
+/// again.
+const UNPARSEABLE_KEYWORD: &str = "$HiUnparseable";
+/// Ingested Message-IDs live in `data/email/seen.json`, so a message whose
+/// `\Seen` flag could not be stored is not ingested twice.
+const EMAIL_STATE_NAMESPACE: &str = "email";
+
+/// Polls the IMAP mailbox in the `imap` section of `config/email.yml`.
+#[derive(Debug, Default)]
+pub struct EmailSource;
+
+#[async_trait]
+impl IngestSource for EmailSource {
+    fn name(&self) -> &'static str {
+        EMAIL_SOURCE
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        let imap = config.email.as_ref()?.imap.as_ref()?;
+        Some(IngestMode::Poll {
+            interval_secs: imap.poll_interval_secs.max(1),
+        })
+    }
+
+    async fn poll(&self, ctx: &AppContext) -> anyhow::Result<usize> {
+        let config = ctx.config();
+        let Some(imap) = config.email.as_ref().and_then(|email| email.imap.as_ref()) else {
+            return Ok(0);
+        };
+        poll_mailbox(&config.data_dir, imap, ctx.now()).await
+    }
+}
+
+/// Turn every unread message in the configured mailbox into an inbox intent
+/// created at `now` and mark it as seen. A message that fails to ingest is
+/// logged and left for the next poll; one that cannot be parsed at all, or is
+/// larger than `max_message_bytes`, is flagged so it is not fetched again.
+/// Returns the number of intents created.
+pub async fn poll_mailbox(
+    data_dir: &Path,
+    config: &ImapConfig,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let password = env::var(&config.password_env)
+        .with_context(|| format!("reading imap password from {}", config.password_env))?;
+    let tcp = within(TcpStream::connect((config.host.as_str(), config.port)))
+        .await
+        .with_context(|| format!("connecting to imap server {}:{}", config.host, config.port))?;
+
+    if config.tls {
+        let stream = within(tls_connector().connect(server_name(&config.host)?, tcp))
+            .await
+            .with_context(|| "establishing imap tls session")?;
+        poll_with_stream(stream, data_dir, config, &password, now).await
+    } else {
+        poll_with_stream(tcp, data_dir, config, &password, now).await
+    }
+}
+
+/// `io` with the IMAP timeout applied.
+async fn within<T>(io: impl Future<Output = std::io::Result<T>>) -> anyhow::Result<T> {
+    match timeout(Duration::from_secs(IMAP_TIMEOUT_SECS), io).await {
+        Ok(result) => Ok(result?),
+        Err(_) => bail!("imap server did not respond within {IMAP_TIMEOUT_SECS}s"),
+    }
+}
+
+async fn poll_with_stream<S>(
+    stream: S,
+    data_dir: &Path,
+    config: &ImapConfig,
+    password: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut session = ImapSession::connect(stream, config.max_message_bytes).await?;
+    session
+        .command(&format!(
+            "LOGIN {} {}",
+            quote(&config.username),
+            quote(password)
+        ))
+        .await
+        .with_context(|| "imap login")?;
+    session
+        .command(&format!("SELECT {}", quote(&config.mailbox)))
+        .await
+        .with_context(|| format!("selecting mailbox {}", config.mailbox))?;
+
+    let uids: Vec<u32> = session
+        .command(&format!(
+            "UID SEARCH UNSEEN UNKEYWORD {UNPARSEABLE_KEYWORD}"
+        ))
+        .await?
+        .iter()
+        .filter_map(|response| response.line.strip_prefix("* SEARCH"))
+        .flat_map(|rest| rest.split_whitespace().filter_map(|uid| uid.parse().ok()))
+        .collect();
+
+    let mailbox = format!("{}/{}/{}", config.host, config.username, config.mailbox);
+    let mut seen = storage::load_seen_state(data_dir, EMAIL_STATE_NAMESPACE)?;
+    let mut created = 0;
+    for uid in uids {
+        let size = session
+            .command(&format!("UID FETCH {uid} RFC822.SIZE"))
+            .await?
+            .iter()
+            .find_map(|response| {
+                let (_, rest) = response.line.split_once("RFC822.SIZE ")?;
+                rest.split(|c: char| !c.is_ascii_digit())
+                    .next()?
+                    .parse::<usize>()
+                    .ok()
+            });
+        if size.is_some_and(|size| size > config.max_message_bytes) {
+            warn!(uid, size, "skipping email larger than max_message_bytes");
+            session.flag(uid, UNPARSEABLE_KEYWORD).await;
+            continue;
+        }
+
+        let responses = session
+            .command(&format!("UID FETCH {} BODY.PEEK[]", uid))
+            .await?;
+        let Some(raw) = responses
+            .into_iter()
+            .find_map(|response| response.literals.into_iter().next())
+        else {
+            warn!(uid, "imap fetch returned no message body");
+            continue;
+        };
+
+        let Some(message) = MessageParser::default().parse(&raw) else {
+            warn!(uid, "skipping unparseable email message");
+            session.flag(uid, UNPARSEABLE_KEYWORD).await;
+            continue;
+        };
+        let message_id = message.message_id().map(str::to_string);
+        if message_id
+            .as_deref()
+            .is_some_and(|id| seen.contains(&mailbox, id))
+        {
+            warn!(uid, "email was already ingested, marking it seen");
+            session.flag(uid, "\\Seen").await;
+            continue;
+        }
+        if let Err(err) = ingest_email(data_dir, &message, config.telos_alignment, now).await {
+            warn!(uid, error = ?err, "failed to ingest email, leaving it unread");
+            continue;
+        }
+        created += 1;
+        if let Some(id) = &message_id {
+            seen.insert(&mailbox, id);
+            if let Err(err) = storage::save_seen_state(data_dir, EMAIL_STATE_NAMESPACE, &seen) {
+                warn!(uid, error = ?err, "failed to remember ingested email");
+            }
+        }
+        session.flag(uid, "\\Seen").await;
+    }
+
+    let _ = session.command("LOGOUT").await;
+    Ok(created)
+}
+
+async fn ingest_email(
+    data_dir: &Path,
+    message: &mail_parser::Message<'_>,
+    telos_alignment: f32,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let subject = message
+        .subject()
+        .unwrap_or("(no subject)")
+        .trim()
+        .to_string();
+    let from = message
+        .from()
+        .and_then(|from| from.first())
+        .and_then(|addr| addr.address())
+        .unwrap_or("unknown")
+        .to_string();
+    let message_id = message.message_id().map(str::to_string);
+    let text = message
+        .body_text(0)
+        .map(|body| body.trim().to_string())
+        .unwrap_or_default();
+
+    let log_entry = MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction: MessageDirection::Inbound,
+        source: EMAIL_SOURCE.to_string(),
+        chat_id: from.clone(),
+        author: Some(from.clone()),
+        text: format!("{}\n\n{}", subject, text),
+        timestamp: message
+            .date()
+            .and_then(|date| DateTime::from_timestamp(date.to_timestamp(), 0))
+            .unwrap_or(now),
+        metadata: Some(json!({ "message_id": message_id, "subject": subject })),
+    };
+    if let Err(err) = storage::append_message_entry(data_dir, &log_entry).await {
+        warn!(error = ?err, "failed to persist inbound email log");
+    }
+
+    let summary = text::truncate(&subject, EMAIL_SUMMARY_MAX_CHARS);
+    let body = format!(
+        "From: {}\nSubject: {}\nMessage-ID: {}\n\n{}",
+        from,
+        subject,
+        message_id.as_deref().unwrap_or("unknown"),
+        text
+    );
+
+    // Where a reply to this intent would go.
+    let mut metadata = BTreeMap::from([(FROM_KEY.to_string(), from)]);
+    if let Some(message_id) = message_id {
+        metadata.insert(MESSAGE_ID_KEY.to_string(), message_id);
+    }
+    storage::persist_intent_at(
+        data_dir,
+        &IntentDraft {
+            source: EMAIL_SOURCE.to_string(),
+            summary,
+            telos_alignment,
+            body,
+            due_at: None,
+            metadata,
+        },
+        now,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Send a plain-text email through the configured SMTP relay and record it
+/// in the outbound message log.
+pub async fn send_email(
+    data_dir: &Path,
+    config: &SmtpConfig,
+    to: &str,
+    subject: &str,
+    text: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let message = Message::builder()
+        .from(
+            config
+                .from
+                .parse()
+                .with_context(|| "parsing smtp from address")?,
+        )
+        .to(to
+            .parse()
+            .with_context(|| format!("parsing recipient {to}"))?)
+        .subject(subject)
+        .body(text.to_string())
+        .with_context(|| "building email message")?;
+
+    let mut builder = match config.security {
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        }
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+    };
+    if let Some(port) = config.port {
+        builder = builder.port(port);
+    }
+    if let (Some(username), Some(password_env)) = (&config.username, &config.password_env) {
+        let password = env::var(password_env)
+            .with_context(|| format!("reading smtp password from {password_env}"))?;
+        builder = builder.credentials(Credentials::new(username.clone(), password));
+    }
+
+    builder
+        .build()
+        .send(message)
+        .await
+        .with_context(|| "sending email via smtp")?;
+
+    let entry = MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction: MessageDirection::Outbound,
+        source: EMAIL_SOURCE.to_string(),
+        chat_id: to.to_string(),
+        author: Some("telos".to_string()),
+        text: text.to_string(),
+        timestamp: now,
+        metadata: Some(json!({ "subject": subject })),
+    };
+    if let Err(err) = storage::append_message_entry(data_dir, &entry).await {
+        warn!(error = ?err, "failed to persist outbound email log");
+    }
+
+    Ok(())
+}
+
+fn tls_connector() -> TlsConnector {
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = ClientConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("ring provider supports default protocol versions")
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+fn server_name(host: &str) -> anyhow::Result<ServerName<'static>> {
+    ServerName::try_from(host.to_string()).with_context(|| format!("invalid imap host {host}"))
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct ImapResponse {
+    line: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// Just enough of IMAP4rev1 to log in, search and fetch: tagged commands,
+/// untagged responses and `{n}` literals.
+struct ImapSession<S> {
+    stream: BufReader<S>,
+    next_tag: u32,
+    max_literal_bytes: usize,
+}
+
+impl<S> ImapSession<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn connect(stream: S, max_literal_bytes: usize) -> anyhow::Result<Self> {
+        let mut session = Self {
+            stream: BufReader::new(stream),
+            next_tag: 1,
+            max_literal_bytes,
+        };
+        let greeting = session.read_response().await?;
+        if !greeting.line.starts_with("* OK") && !greeting.line.starts_with("* PREAUTH") {
+            bail!("unexpected imap greeting: {}", greeting.line);
+        }
+        Ok(session)
+    }
+
+    async fn command(&mut self, command: &str) -> anyhow::Result<Vec<ImapResponse>> {
+        let tag = format!("A{:04}", self.next_tag);
+        self.next_tag += 1;
+        self.stream
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await?;
+        self.stream.flush().await?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let response = self.read_response().await?;
+            if let Some(status) = response.line.strip_prefix(&format!("{} ", tag)) {
+                if status.starts_with("OK") {
+                    return Ok(untagged);
+                }
+                bail!("imap command failed: {}", status);
+            }
+            untagged.push(response);
+        }
+    }
+
+    /// Add `flag` to a message. A failure is only logged: the messages
+    /// already ingested in this poll must still be flagged.
+    async fn flag(&mut self, uid: u32, flag: &str) {
+        if let Err(err) = self
+            .command(&format!("UID STORE {uid} +FLAGS ({flag})"))
+            .await
+        {
+            warn!(uid, flag, error = ?err, "failed to flag email");
+        }
+    }
+
+    async fn read_response(&mut self) -> anyhow::Result<ImapResponse> {
+        let mut line = String::new();
+        let mut literals = Vec::new();
+        loop {
+            let mut chunk = String::new();
+            if within(self.stream.read_line(&mut chunk)).await? == 0 {
+                bail!("imap connection closed");
+            }
+            let chunk = chunk.trim_end_matches(['\r', '\n']);
+            line.push_str(chunk);
+
+            let Some(size) = chunk
+                .strip_suffix('}')
+                .and_then(|rest| rest.rsplit_once('{'))
+                .and_then(|(_, size)| size.parse::<usize>().ok())
+            else {
+                return Ok(ImapResponse { line, literals });
+            };
+            if size > self.max_literal_bytes {
+                bail!(
+                    "imap literal of {size} bytes exceeds the {} byte limit",
+                    self.max_literal_bytes
+                );
+            }
+            let mut literal = vec![0; size];
+            within(self.stream.read_exact(&mut literal)).await?;
+            literals.push(literal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+
+    use crate::storage::{MessageLogQuery, ensure_data_layout, scan_inbox};
+
+    const RAW_EMAIL: &str = "From: Ada <ada@example.com>\r\nTo: hi@example.com\r\nSubject: Plan the launch\r\nMessage-ID: <m1@example.com>\r\n\r\nPlease draft the launch checklist.\r\n";
+
+    /// Serves `messages` as `(uid, raw)` pairs. Setting `\Seen` fails when
+    /// `seen_fails` is set.
+    async fn fake_imap_server(
+        listener: TcpListener,
+        messages: &[(u32, &str)],
+        seen_fails: bool,
+    ) -> Vec<String> {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(socket);
+        let mut commands = Vec::new();
+        stream.write_all(b"* OK ready\r\n").await.unwrap();
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let (tag, command) = line.trim_end().split_once(' ').unwrap();
+            commands.push(command.to_string());
+            if seen_fails && command.ends_with("+FLAGS (\\Seen)") {
+                stream
+                    .write_all(format!("{tag} NO store failed\r\n").as_bytes())
+                    .await
+                    .unwrap();
+                continue;
+            }
+            let reply = if command.starts_with("UID SEARCH") {
+                let uids: Vec<_> = messages.iter().map(|(uid, _)| uid.to_string()).collect();
+                format!("* SEARCH {}\r\n", uids.join(" "))
+            } else if let Some(rest) = command
+                .strip_prefix("UID FETCH ")
+                .and_then(|rest| rest.strip_suffix(" RFC822.SIZE"))
+            {
+                let uid: u32 = rest.parse().unwrap();
+                let (_, raw) = messages.iter().find(|(id, _)| *id == uid).unwrap();
+                format!("* 1 FETCH (UID {uid} RFC822.SIZE {})\r\n", raw.len())
+            } else if let Some(rest) = command.strip_prefix("UID FETCH ") {
+                let uid: u32 = rest.split_whitespace().next().unwrap().parse().unwrap();
+                let (_, raw) = messages.iter().find(|(id, _)| *id == uid).unwrap();
+                format!(
+                    "* 1 FETCH (UID {uid} BODY[] {{{}}}\r\n{})\r\n",
+                    raw.len(),
+                    raw
+                )
+            } else {
+                String::new()
+            };
+            stream
+                .write_all(format!("{}{} OK done\r\n", reply, tag).as_bytes())
+                .await
+                .unwrap();
+            if command == "LOGOUT" {
+                break;
+            }
+        }
+        commands
+    }
+
+    fn imap_config(port: u16) -> ImapConfig {
+        unsafe {
+            env::set_var("HI_TEST_IMAP_PASSWORD", "secret");
+        }
+        ImapConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: "hi@example.com".to_string(),
+            password_env: "HI_TEST_IMAP_PASSWORD".to_string(),
+            mailbox: "INBOX".to_string(),
+            tls: false,
+            poll_interval_secs: 60,
+            telos_alignment: 1.0,
+            max_message_bytes: 1024,
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn failed_seen_flag_does_not_duplicate_the_intent() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).unwrap();
+
+        for (poll, expected) in [(1, 1), (2, 0)] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let config = imap_config(listener.local_addr().unwrap().port());
+            let server =
+                tokio::spawn(
+                    async move { fake_imap_server(listener, &[(7, RAW_EMAIL)], true).await },
+                );
+
+            let created = poll_mailbox(data_dir, &config, Utc::now()).await.unwrap();
+            assert_eq!(created, expected, "poll {poll}");
+            let commands = server.await.unwrap();
+            assert!(commands.contains(&"UID STORE 7 +FLAGS (\\Seen)".to_string()));
+        }
+        assert_eq!(scan_inbox(data_dir).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn unread_email_becomes_intent_and_is_marked_seen() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // An empty message cannot be parsed and one above the size limit is
+        // never downloaded; both are flagged and skipped.
+        let oversized = format!("{RAW_EMAIL}{}", "x".repeat(1024));
+        let server = tokio::spawn(async move {
+            fake_imap_server(
+                listener,
+                &[(6, ""), (7, RAW_EMAIL), (8, oversized.as_str())],
+                false,
+            )
+            .await
+        });
+
+        let config = imap_config(port);
+
+        let now = Utc::now();
+        let created = poll_mailbox(data_dir, &config, now).await.unwrap();
+        assert_eq!(created, 1);
+
+        let commands = server.await.unwrap();
+        assert_eq!(commands[0], "LOGIN \"hi@example.com\" \"secret\"");
+        assert!(commands.contains(&"UID SEARCH UNSEEN UNKEYWORD $HiUnparseable".to_string()));
+        assert!(commands.contains(&"UID STORE 6 +FLAGS ($HiUnparseable)".to_string()));
+        assert!(commands.contains(&"UID STORE 7 +FLAGS (\\Seen)".to_string()));
+        assert!(commands.contains(&"UID STORE 8 +FLAGS ($HiUnparseable)".to_string()));
+        assert!(!commands.contains(&"UID FETCH 8 BODY.PEEK[]".to_string()));
+
+        let inbox = scan_inbox(data_dir).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].intent.source, "email");
+        assert_eq!(inbox[0].intent.summary, "Plan the launch");
+        assert_eq!(inbox[0].intent.created_at.timestamp(), now.timestamp());
+        let metadata = &inbox[0].intent.metadata;
+        assert_eq!(metadata[FROM_KEY], "ada@example.com");
+        assert_eq!(metadata[MESSAGE_ID_KEY], "m1@example.com");
+        let content = std::fs::read_to_string(&inbox[0].path).unwrap();
+        assert!(content.contains("Please draft the launch checklist."));
+
+        let messages = storage::read_messages(
+            data_dir,
+            MessageLogQuery {
+                source: Some("email".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].chat_id, "ada@example.com");
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{config::ConfigReload, tasks::Intent};
+
+const EVENT_BUS_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentEventKind {
+    Completed,
+    Failed,
+    Deferred,
+    /// The intent is held in `intent/pending_approval` for a human decision.
+    PendingApproval,
+    /// A weekly review (digest) intent finished; published after `Completed`.
+    DigestReady,
+}
+
+impl IntentEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntentEventKind::Completed => "completed",
+            IntentEventKind::Failed => "failed",
+            IntentEventKind::Deferred => "deferred",
+            IntentEventKind::PendingApproval => "pending_approval",
+            IntentEventKind::DigestReady => "digest_ready",
+        }
+    }
+}
+
+/// Lifecycle notification published by the orchestrator.
+#[derive(Debug, Clone, Serialize)]
+pub struct IntentEvent {
+    pub id: Uuid,
+    pub kind: IntentEventKind,
+    pub timestamp: DateTime<Utc>,
+    pub intent: Intent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_answer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl IntentEvent {
+    pub fn new(kind: IntentEventKind, intent: &Intent, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            timestamp,
+            intent: intent.clone(),
+            final_answer: None,
+            error: None,
+        }
+    }
+}
+
+/// Published when edits under `config/` were picked up at runtime.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReloaded {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub reload: ConfigReload,
+}
+
+impl ConfigReloaded {
+    pub fn new(reload: ConfigReload, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp,
+            reload,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewChangeKind {
+    Saved,
+    Restored,
+    Deleted,
+}
+
+/// Published when a structured text preview is written, restored from its
+/// history or reset, so open editors can refetch it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewChanged {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub kind: PreviewChangeKind,
+    /// Preview name; `default` for the unnamed preview.
+    pub name: String,
+    /// Snapshot id for `restored`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl PreviewChanged {
+    pub fn new(kind: PreviewChangeKind, name: &str, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp,
+            kind,
+            name: name.to_string(),
+            history_id: None,
+            note: None,
+        }
+    }
+}
+
+/// Fan-out channel for intent lifecycle events. Publishing never blocks and
+/// is a no-op when nobody is subscribed.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<IntentEvent>,
+    config_tx: broadcast::Sender<ConfigReloaded>,
+    preview_tx: broadcast::Sender<PreviewChanged>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        let (config_tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        let (preview_tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self {
+            tx,
+            config_tx,
+            preview_tx,
+        }
+    }
+}
+
+impl EventBus {
+    pub fn publish(&self, event: IntentEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<IntentEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn publish_config_reloaded(&self, event: ConfigReloaded) {
+        let _ = self.config_tx.send(event);
+    }
+
+    pub fn subscribe_config_reloads(&self) -> broadcast::Receiver<ConfigReloaded> {
+        self.config_tx.subscribe()
+    }
+
+    pub fn publish_preview_changed(&self, event: PreviewChanged) {
+        let _ = self.preview_tx.send(event);
+    }
+
+    pub fn subscribe_preview_changes(&self) -> broadcast::Receiver<PreviewChanged> {
+        self.preview_tx.subscribe()
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use flate2::{Compression, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use walkdir::WalkDir;
+
+use crate::{
+    backup::{ArchiveSpec, StagingDir, stage_archive},
+    storage,
+};
+
+/// First entry of every export; import refuses archives without it.
+const MANIFEST_NAME: &str = "hi_export.json";
+const EXPORT_FORMAT: u32 = 1;
+pub(crate) const STAGING_PREFIX: &str = ".import-";
+
+/// Data dirs carried by an export. Logs, messages, the outbox and backups
+/// are machine-local history and stay behind.
+pub const EXPORTED_DATA_DIRS: &[&str] = &["intent", "journals", "memory", "sp"];
+
+/// Replaces inline secrets in exported config files.
+pub const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format: u32,
+    /// Data schema of the exporting build; see [`storage::SCHEMA_VERSION`].
+    pub schema_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub config_files: Vec<String>,
+    pub data_files: usize,
+    /// `file: key.path` of every value replaced by [`REDACTED`].
+    pub redacted: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub manifest: ExportManifest,
+    pub data_files: usize,
+    /// Config files the target did not have yet.
+    pub config_written: Vec<String>,
+    /// Config files left alone because the target already has them.
+    pub config_skipped: Vec<String>,
+}
+
+/// Build a portable `.tar.gz` of the workspace in memory; see
+/// [`prepare_export`] and [`PreparedExport::write_to`].
+pub fn export_archive(
+    config_dir: &Path,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<(ExportManifest, Vec<u8>)> {
+    let prepared = prepare_export(config_dir, data_dir, now)?;
+    let bytes = prepared.write_to(data_dir, Vec::new())?;
+    Ok((prepared.manifest, bytes))
+}
+
+/// An export ready to be written: config files read and redacted, data
+/// files listed.
+pub struct PreparedExport {
+    pub manifest: ExportManifest,
+    configs: Vec<(String, String)>,
+    data_files: Vec<String>,
+}
+
+/// Collect what goes into a portable export: `config/*.yml` with inline
+/// secrets redacted (example files are skipped) and every file under
+/// [`EXPORTED_DATA_DIRS`], stored as `data/<path>`.
+pub fn prepare_export(
+    config_dir: &Path,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PreparedExport> {
+    let mut configs = Vec::new();
+    let mut redacted = Vec::new();
+    for name in config_file_names(config_dir)? {
+        let path = config_dir.join(&name);
+        let raw =
+            fs::read_to_string(&path).with_context(|| format!("reading config {:?}", path))?;
+        let mut value: YamlValue =
+            serde_yaml::from_str(&raw).with_context(|| format!("parsing config {:?}", path))?;
+        let mut keys = Vec::new();
+        redact_secrets(&mut value, "", &mut keys);
+        let contents = if keys.is_empty() {
+            raw
+        } else {
+            serde_yaml::to_string(&value)?
+        };
+        redacted.extend(keys.into_iter().map(|key| format!("{name}: {key}")));
+        configs.push((name, contents));
+    }
+
+    let data_files = exported_data_files(data_dir);
+    let manifest = ExportManifest {
+        format: EXPORT_FORMAT,
+        schema_version: storage::SCHEMA_VERSION,
+        created_at: now,
+        config_files: configs.iter().map(|(name, _)| name.clone()).collect(),
+        data_files: data_files.len(),
+        redacted,
+    };
+    Ok(PreparedExport {
+        manifest,
+        configs,
+        data_files,
+    })
+}
+
+impl PreparedExport {
+    /// Write the `.tar.gz` to `output`, reading data files from `data_dir`
+    /// as it goes, and return the writer.
+    pub fn write_to<W: Write>(&self, data_dir: &Path, output: W) -> anyhow::Result<W> {
+        let mtime = self.manifest.created_at.timestamp().max(0) as u64;
+        let mut archive = tar::Builder::new(GzEncoder::new(output, Compression::default()));
+        append_bytes(
+            &mut archive,
+            MANIFEST_NAME,
+            &serde_json::to_vec_pretty(&self.manifest)?,
+            mtime,
+        )?;
+        for (name, contents) in &self.configs {
+            append_bytes(
+                &mut archive,
+                &format!("config/{name}"),
+                contents.as_bytes(),
+                mtime,
+            )?;
+        }
+        for relative in &self.data_files {
+            archive
+                .append_path_with_name(data_dir.join(relative), format!("data/{relative}"))
+                .with_context(|| format!("adding {:?} to export", relative))?;
+        }
+        archive
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .context("finishing export archive")
+    }
+}
+
+/// Load an export into this workspace; see [`stage_import`] and
+/// [`StagedImport::apply`].
+pub fn import_archive(
+    config_dir: &Path,
+    data_dir: &Path,
+    archive: &[u8],
+) -> anyhow::Result<ImportReport> {
+    stage_import(data_dir, archive)?.apply(config_dir, data_dir)
+}
+
+/// An export unpacked and checked in a staging dir inside the data dir,
+/// not yet applied. Dropping it removes the staging dir.
+pub struct StagedImport {
+    staging: StagingDir,
+    pub manifest: ExportManifest,
+}
+
+/// Unpack `archive` next to the live data, so a bad export is rejected
+/// before anything is replaced.
+pub fn stage_import(data_dir: &Path, archive: impl Read) -> anyhow::Result<StagedImport> {
+    let spec = ArchiveSpec {
+        kind: "export",
+        manifest_name: MANIFEST_NAME,
+        staging_prefix: STAGING_PREFIX,
+    };
+    let mut config_files = 0;
+    let (staging, manifest, files) = stage_archive(
+        data_dir,
+        archive,
+        &spec,
+        check_manifest,
+        |manifest: &ExportManifest, relative| {
+            if let Ok(name) = relative.strip_prefix("config") {
+                let name = name.to_string_lossy();
+                if !manifest.config_files.iter().any(|listed| *listed == name) {
+                    bail!("config file {name:?} is not listed in the export manifest");
+                }
+                config_files += 1;
+            } else if let Ok(path) = relative.strip_prefix("data") {
+                let exported = path.components().next().is_some_and(|dir| {
+                    EXPORTED_DATA_DIRS
+                        .iter()
+                        .any(|name| dir.as_os_str() == *name)
+                });
+                if !exported {
+                    bail!("unexpected data path in export: {}", relative.display());
+                }
+            } else {
+                bail!("unexpected path in export: {}", relative.display());
+            }
+            Ok(true)
+        },
+    )?;
+    let data_files = files - config_files;
+    if data_files != manifest.data_files || config_files != manifest.config_files.len() {
+        bail!(
+            "export is incomplete: manifest lists {} data and {} config files, archive has {} and {}",
+            manifest.data_files,
+            manifest.config_files.len(),
+            data_files,
+            config_files
+        );
+    }
+    Ok(StagedImport { staging, manifest })
+}
+
+impl StagedImport {
+    /// Replace each of [`EXPORTED_DATA_DIRS`] wholesale and add the config
+    /// files the target lacks. Existing config is never overwritten, so
+    /// local secrets survive.
+    pub fn apply(self, config_dir: &Path, data_dir: &Path) -> anyhow::Result<ImportReport> {
+        let staging = self.staging.path();
+        for dir in EXPORTED_DATA_DIRS {
+            let live = data_dir.join(dir);
+            if live.exists() {
+                fs::remove_dir_all(&live).with_context(|| format!("removing {:?}", live))?;
+            }
+            let staged = staging.join("data").join(dir);
+            if staged.exists() {
+                fs::rename(&staged, &live)
+                    .with_context(|| format!("moving imported {:?}", live))?;
+            }
+        }
+        storage::ensure_data_layout(data_dir)?;
+
+        fs::create_dir_all(config_dir)
+            .with_context(|| format!("creating config dir {:?}", config_dir))?;
+        let mut config_written = Vec::new();
+        let mut config_skipped = Vec::new();
+        for name in &self.manifest.config_files {
+            let target = config_dir.join(name);
+            if target.exists() {
+                config_skipped.push(name.clone());
+                continue;
+            }
+            let staged = staging.join("config").join(name);
+            storage::write_atomic(&target, fs::read(&staged)?)
+                .with_context(|| format!("writing imported config {:?}", target))?;
+            config_written.push(name.clone());
+        }
+
+        Ok(ImportReport {
+            data_files: self.manifest.data_files,
+            manifest: self.manifest,
+            config_written,
+            config_skipped,
+        })
+    }
+}
+
+fn check_manifest(manifest: &ExportManifest) -> anyhow::Result<()> {
+    if manifest.format != EXPORT_FORMAT {
+        bail!("unsupported export format {}", manifest.format);
+    }
+    if manifest.schema_version > storage::SCHEMA_VERSION {
+        bail!(
+            "export has schema version {}, newer than supported {}; upgrade hi_telos",
+            manifest.schema_version,
+            storage::SCHEMA_VERSION
+        );
+    }
+    if let Some(name) = manifest
+        .config_files
+        .iter()
+        .find(|name| !is_config_name(name))
+    {
+        bail!("invalid config file name {name:?} in export manifest");
+    }
+    Ok(())
+}
+
+fn append_bytes<W: Write>(
+    archive: &mut tar::Builder<GzEncoder<W>>,
+    name: &str,
+    bytes: &[u8],
+    mtime: u64,
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, bytes)
+        .with_context(|| format!("adding {name} to export"))
+}
+
+/// `*.yml` directly in the config dir, without the `*.example.yml` templates.
+fn config_file_names(config_dir: &Path) -> anyhow::Result<Vec<String>> {
+    if !config_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in
+        fs::read_dir(config_dir).with_context(|| format!("reading config dir {:?}", config_dir))?
+    {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if entry.file_type()?.is_file() && name.ends_with(".yml") && !name.ends_with(".example.yml")
+        {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// A plain `*.yml` file name, with no directory part.
+fn is_config_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) && name.ends_with(".yml")
+}
+
+/// Whether a config key holds a secret inline. `*_env` keys only name the
+/// environment variable that holds it and are kept.
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    !key.ends_with("_env")
+        && ["token", "secret", "password", "api_key"]
+            .iter()
+            .any(|marker| key.contains(marker))
+}
+
+fn redact_secrets(value: &mut YamlValue, path: &str, redacted: &mut Vec<String>) {
+    match value {
+        YamlValue::Mapping(mapping) => {
+            for (key, child) in mapping.iter_mut() {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let child_path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                };
+                if is_secret_key(key) && !child.is_null() && !child.is_mapping() {
+                    *child = YamlValue::String(REDACTED.to_string());
+                    redacted.push(child_path);
+                } else {
+                    redact_secrets(child, &child_path, redacted);
+                }
+            }
+        }
+        YamlValue::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                redact_secrets(item, &format!("{path}[{index}]"), redacted);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn exported_data_files(data_dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for dir in EXPORTED_DATA_DIRS {
+        files.extend(
+            WalkDir::new(data_dir.join(dir))
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+                .filter_map(|entry| {
+                    let relative = entry.path().strip_prefix(data_dir).ok()?;
+                    let parts: Vec<&str> = relative
+                        .components()
+                        .map(|component| component.as_os_str().to_str())
+                        .collect::<Option<_>>()?;
+                    Some(parts.join("/"))
+                }),
+        );
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn workspace() -> (TempDir, PathBuf, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let config_dir = temp.path().join("config");
+        let data_dir = temp.path().join("data");
+        fs::create_dir_all(&config_dir).unwrap();
+        storage::ensure_data_layout(&data_dir).unwrap();
+        (temp, config_dir, data_dir)
+    }
+
+    #[test]
+    fn export_round_trip_redacts_secrets_and_keeps_local_config() {
+        let (_source, source_config, source_data) = workspace();
+        fs::write(source_config.join("beat.yml"), "interval_minutes: 10\n").unwrap();
+        fs::write(
+            source_config.join("telegram.yml"),
+            "bot_token: \"123:ABC\"\nwebhook_secret: hush\nallowed_chat_ids: [1]\n",
+        )
+        .unwrap();
+        fs::write(
+            source_config.join("github.yml"),
+            "webhook_secret_env: HI_GITHUB_WEBHOOK_SECRET\n",
+        )
+        .unwrap();
+        fs::write(source_config.join("email.example.yml"), "imap: {}\n").unwrap();
+        fs::write(source_data.join("intent/queue/a.md"), "queued").unwrap();
+        fs::write(source_data.join("journals/today.md"), "journal").unwrap();
+        fs::write(source_data.join("messages/skip.jsonl"), "{}").unwrap();
+
+        let (manifest, archive) = export_archive(&source_config, &source_data, Utc::now()).unwrap();
+        assert_eq!(
+            manifest.config_files,
+            vec!["beat.yml", "github.yml", "telegram.yml"]
+        );
+        assert_eq!(manifest.data_files, 2);
+        assert_eq!(
+            manifest.redacted,
+            vec!["telegram.yml: bot_token", "telegram.yml: webhook_secret"]
+        );
+
+        let (_target, target_config, target_data) = workspace();
+        fs::write(target_config.join("beat.yml"), "interval_minutes: 5\n").unwrap();
+        fs::write(target_data.join("intent/history/old.md"), "old").unwrap();
+        fs::write(target_data.join("messages/local.jsonl"), "{}").unwrap();
+
+        let report = import_archive(&target_config, &target_data, &archive).unwrap();
+        assert_eq!(report.data_files, 2);
+        assert_eq!(report.config_written, vec!["github.yml", "telegram.yml"]);
+        assert_eq!(report.config_skipped, vec!["beat.yml"]);
+
+        let telegram = fs::read_to_string(target_config.join("telegram.yml")).unwrap();
+        assert!(telegram.contains(REDACTED) && !telegram.contains("123:ABC"));
+        assert!(!telegram.contains("hush"));
+        assert_eq!(
+            fs::read_to_string(target_config.join("github.yml")).unwrap(),
+            "webhook_secret_env: HI_GITHUB_WEBHOOK_SECRET\n"
+        );
+        assert_eq!(
+            fs::read_to_string(target_config.join("beat.yml")).unwrap(),
+            "interval_minutes: 5\n"
+        );
+        assert_eq!(
+            fs::read_to_string(target_data.join("intent/queue/a.md")).unwrap(),
+            "queued"
+        );
+        assert!(!target_data.join("intent/history/old.md").exists());
+        assert!(target_data.join("intent/history").is_dir());
+        assert!(target_data.join("messages/local.jsonl").exists());
+        assert!(!target_data.join("messages/skip.jsonl").exists());
+
+        assert!(import_archive(&target_config, &target_data, b"not a tarball").is_err());
+        assert!(fs::read_dir(&target_data).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(STAGING_PREFIX)
+        }));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use tracing::warn;
+
+use crate::{
+    config::{AppConfig, FeedSourceConfig, FeedsConfig},
+    sources::{IngestMode, IngestSource},
+    state::AppContext,
+    storage::{self, IntentDraft},
+    text,
+};
+
+pub const FEED_SOURCE: &str = "feed";
+pub const FEED_URL_KEY: &str = "feed_url";
+pub const ENTRY_LINK_KEY: &str = "feed_entry_link";
+
+const FEED_STATE_NAMESPACE: &str = "feeds";
+const FEED_SUMMARY_MAX_CHARS: usize = 80;
+const FEED_BODY_MAX_CHARS: usize = 2_000;
+const FEED_TIMEOUT_SECS: u64 = 30;
+
+/// One item from an RSS `<item>` or Atom `<entry>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub summary: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+}
+
+/// Polls the RSS/Atom feeds in `config/feeds.yml`.
+#[derive(Debug)]
+pub struct FeedsSource {
+    client: Client,
+}
+
+impl Default for FeedsSource {
+    fn default() -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(FEED_TIMEOUT_SECS))
+            .build()
+            .expect("building feed client");
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl IngestSource for FeedsSource {
+    fn name(&self) -> &'static str {
+        "feeds"
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        let feeds = config
+            .feeds
+            .as_ref()
+            .filter(|feeds| !feeds.feeds.is_empty())?;
+        Some(IngestMode::Poll {
+            interval_secs: feeds.poll_interval_minutes.max(1) * 60,
+        })
+    }
+
+    async fn poll(&self, ctx: &AppContext) -> anyhow::Result<usize> {
+        let config = ctx.config();
+        let Some(feeds) = &config.feeds else {
+            return Ok(0);
+        };
+        Ok(poll_feeds(&self.client, &config.data_dir, feeds, ctx.now()).await)
+    }
+}
+
+/// Poll every configured feed once; a failing feed is logged and skipped.
+/// Intents are created at `now`. Returns the number of intents created.
+pub async fn poll_feeds(
+    client: &Client,
+    data_dir: &Path,
+    config: &FeedsConfig,
+    now: DateTime<Utc>,
+) -> usize {
+    let mut created = 0;
+    for feed in &config.feeds {
+        match poll_feed(client, data_dir, config, feed, now).await {
+            Ok(count) => created += count,
+            Err(err) => warn!(url = %feed.url, error = ?err, "failed to poll feed"),
+        }
+    }
+    created
+}
+
+async fn poll_feed(
+    client: &Client,
+    data_dir: &Path,
+    config: &FeedsConfig,
+    feed: &FeedSourceConfig,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let response = client
+        .get(&feed.url)
+        .send()
+        .await
+        .with_context(|| format!("fetching feed {}", feed.url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("feed returned status {}", response.status()));
+    }
+    let xml = response
+        .text()
+        .await
+        .with_context(|| format!("reading feed body {}", feed.url))?;
+    let (title, mut entries) = parse_feed(&xml)?;
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.published));
+
+    let mut seen = storage::load_seen_state(data_dir, FEED_STATE_NAMESPACE)?;
+    let feed_name = feed
+        .name
+        .clone()
+        .or(title)
+        .unwrap_or_else(|| feed.url.clone());
+    let alignment = feed.telos_alignment.unwrap_or(config.telos_alignment);
+
+    let mut created = 0;
+    for entry in &entries {
+        if seen.contains(&feed.url, &entry.id) {
+            continue;
+        }
+        // Anything past the per-poll cap is marked seen as well so a newly
+        // added feed does not flood the inbox with its whole archive.
+        if created < config.max_new_entries {
+            let draft = entry_intent(&feed.url, &feed_name, alignment, entry);
+            if let Err(err) = storage::persist_intent_at(data_dir, &draft, now).await {
+                // The entries persisted before this one must not come back
+                // as duplicates on the next poll.
+                storage::save_seen_state(data_dir, FEED_STATE_NAMESPACE, &seen)?;
+                return Err(err);
+            }
+            created += 1;
+        }
+        seen.insert(&feed.url, &entry.id);
+    }
+    storage::save_seen_state(data_dir, FEED_STATE_NAMESPACE, &seen)?;
+    Ok(created)
+}
+
+fn entry_intent(
+    feed_url: &str,
+    feed_name: &str,
+    telos_alignment: f32,
+    entry: &FeedEntry,
+) -> IntentDraft {
+    let title = entry.title.trim();
+    let summary = text::truncate(&format!("Review: {}", title), FEED_SUMMARY_MAX_CHARS);
+
+    let mut excerpt = entry
+        .summary
+        .as_deref()
+        .map(|summary| text::truncate(&strip_tags(summary), FEED_BODY_MAX_CHARS))
+        .unwrap_or_default();
+    if excerpt.is_empty() {
+        excerpt = "(no summary)".to_string();
+    }
+    let body = format!(
+        "## Review this article\n\nFeed: {feed_name}\nTitle: {title}\nLink: {link}\nPublished: {published}\n\n{excerpt}\n\nDecide whether it matters for the current Telos; summarise the takeaways or dismiss it.",
+        link = entry.link.as_deref().unwrap_or("(none)"),
+        published = entry
+            .published
+            .map(|published| published.to_rfc3339())
+            .unwrap_or_else(|| "unknown".to_string()),
+    );
+
+    let mut metadata = BTreeMap::from([(FEED_URL_KEY.to_string(), feed_url.to_string())]);
+    if let Some(link) = &entry.link {
+        metadata.insert(ENTRY_LINK_KEY.to_string(), link.clone());
+    }
+
+    IntentDraft {
+        source: FEED_SOURCE.to_string(),
+        summary,
+        telos_alignment,
+        body,
+        due_at: None,
+        metadata,
+    }
+}
+
+/// Parse an RSS 2.0 or Atom document into its title and entries. Entries
+/// without a guid/id fall back to their link, then their title.
+pub fn parse_feed(xml: &str) -> anyhow::Result<(Option<String>, Vec<FeedEntry>)> {
+    let document = roxmltree::Document::parse(xml).with_context(|| "parsing feed xml")?;
+    let root = document.root_element();
+
+    let (container, item_tag) = match root.tag_name().name() {
+        "rss" => (
+            root.children()
+                .find(|node| node.has_tag_name("channel"))
+                .ok_or_else(|| anyhow!("rss feed without channel"))?,
+            "item",
+        ),
+        "feed" => (root, "entry"),
+        "RDF" => (root, "item"),
+        other => return Err(anyhow!("unsupported feed root element <{other}>")),
+    };
+
+    let title = child_text(container, "title");
+    let entries = container
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == item_tag)
+        .filter_map(parse_entry)
+        .collect();
+    Ok((title, entries))
+}
+
+fn parse_entry(node: roxmltree::Node<'_, '_>) -> Option<FeedEntry> {
+    let title = child_text(node, "title").unwrap_or_default();
+    let link = node
+        .children()
+        .filter(|child| child.tag_name().name() == "link")
+        .find_map(|child| {
+            let rel = child.attribute("rel").unwrap_or("alternate");
+            if rel != "alternate" {
+                return None;
+            }
+            child
+                .attribute("href")
+                .map(str::to_string)
+                .or_else(|| child.text().map(|text| text.trim().to_string()))
+                .filter(|href| !href.is_empty())
+        });
+    let summary = ["description", "summary", "content"]
+        .iter()
+        .find_map(|name| child_text(node, name));
+    let published = ["pubDate", "published", "updated", "date"]
+        .iter()
+        .filter_map(|name| child_text(node, name))
+        .find_map(|value| parse_date(&value));
+
+    let id = ["guid", "id"]
+        .iter()
+        .find_map(|name| child_text(node, name))
+        .or_else(|| link.clone())
+        .or_else(|| (!title.is_empty()).then(|| title.clone()))?;
+
+    Some(FeedEntry {
+        id,
+        title,
+        link,
+        summary,
+        published,
+    })
+}
+
+fn child_text(node: roxmltree::Node<'_, '_>, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+        .map(|child| {
+            child
+                .descendants()
+                .filter(|text| text.is_text())
+                .filter_map(|text| text.text())
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .filter(|text| !text.is_empty())
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Feed summaries are usually HTML; intents only need the text.
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use httpmock::prelude::*;
+    use tempfile::TempDir;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <title>Example Blog</title>
+  <item>
+    <title>Older post</title>
+    <link>https://example.com/older</link>
+    <guid>older</guid>
+    <pubDate>Mon, 06 Jan 2025 09:00:00 GMT</pubDate>
+  </item>
+  <item>
+    <title>Newer post</title>
+    <link>https://example.com/newer</link>
+    <guid>newer</guid>
+    <description><![CDATA[<p>Hello <b>world</b></p>]]></description>
+    <pubDate>Tue, 07 Jan 2025 09:00:00 GMT</pubDate>
+  </item>
+</channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Feed</title>
+  <entry>
+    <title>Atom entry</title>
+    <link rel="alternate" href="https://example.org/a"/>
+    <link rel="edit" href="https://example.org/edit/a"/>
+    <id>urn:uuid:1</id>
+    <updated>2025-01-08T10:00:00Z</updated>
+    <summary>Short summary</summary>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parses_rss_and_atom() {
+        let (title, entries) = parse_feed(RSS).expect("rss");
+        assert_eq!(title.as_deref(), Some("Example Blog"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].id, "newer");
+        assert_eq!(
+            entries[1].summary.as_deref().map(strip_tags).as_deref(),
+            Some("Hello world")
+        );
+        assert!(entries[1].published > entries[0].published);
+
+        let (title, entries) = parse_feed(ATOM).expect("atom");
+        assert_eq!(title.as_deref(), Some("Atom Feed"));
+        assert_eq!(entries[0].id, "urn:uuid:1");
+        assert_eq!(entries[0].link.as_deref(), Some("https://example.org/a"));
+        assert!(entries[0].published.is_some());
+
+        assert!(parse_feed("<html></html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn polling_deduplicates_and_caps_new_entries() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/feed.xml");
+                then.status(200).body(RSS);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = FeedsConfig {
+            poll_interval_minutes: 60,
+            telos_alignment: 0.5,
+            max_new_entries: 1,
+            feeds: vec![FeedSourceConfig {
+                url: server.url("/feed.xml"),
+                name: None,
+                telos_alignment: None,
+            }],
+        };
+
+        let client = FeedsSource::default().client;
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        assert_eq!(poll_feeds(&client, data_dir, &config, now).await, 1);
+        let inbox = storage::scan_inbox(data_dir).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].intent.created_at, now);
+        assert_eq!(inbox[0].intent.source, FEED_SOURCE);
+        assert_eq!(inbox[0].intent.summary, "Review: Newer post");
+        assert_eq!(
+            inbox[0]
+                .intent
+                .metadata
+                .get(ENTRY_LINK_KEY)
+                .map(String::as_str),
+            Some("https://example.com/newer")
+        );
+
+        // The capped older entry was marked seen too, so nothing is re-created.
+        assert_eq!(poll_feeds(&client, data_dir, &config, now).await, 0);
+        assert_eq!(storage::scan_inbox(data_dir).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    agent::{AgentOutcome, AgentStep},
+    storage::{self, JournalLinks, MemorySnapshotInput, MessageDirection, MessageLogEntry},
+    tasks::{Intent, PRIORITY_KEY},
+};
+
+const VERBS: &[&str] = &[
+    "Draft",
+    "Review",
+    "Summarize",
+    "Plan",
+    "Triage",
+    "Outline",
+    "Research",
+    "Schedule",
+    "Polish",
+    "Follow up on",
+];
+
+const OBJECTS: &[&str] = &[
+    "the weekly report",
+    "the launch checklist",
+    "the onboarding guide",
+    "the Q3 roadmap",
+    "the incident postmortem",
+    "the API changelog",
+    "the budget sheet",
+    "the reading list",
+    "the team retro notes",
+    "the release notes",
+];
+
+const SOURCES: &[&str] = &["telegram", "email", "feed", "user", "calendar"];
+
+const CHATS: &[&str] = &["1001", "1002", "1003"];
+
+const INBOUND: &[&str] = &[
+    "Can you check where the roadmap stands?",
+    "Remind me about the retro tomorrow.",
+    "What did we decide about the budget?",
+    "Please summarize today's feed items.",
+    "Is the release still on track?",
+    "Add the onboarding guide to my list.",
+];
+
+const OUTBOUND: &[&str] = &[
+    "Done — the summary is in today's journal.",
+    "Queued it; I'll report back after the next beat.",
+    "The roadmap has two open items left.",
+    "Noted. I'll remind you at 9:00.",
+    "Release is on track; no blockers reported.",
+];
+
+/// How much synthetic data [`generate_fixture_data`] writes.
+#[derive(Debug, Clone)]
+pub struct FixtureSpec {
+    /// Intents spread over the window. About three in four are archived
+    /// with a journal entry and an L1 memory; the rest wait in the inbox,
+    /// the queue or the failed queue.
+    pub intents: usize,
+    /// Length of the window in days, ending at `end`.
+    pub days: u32,
+    /// Telegram messages per day, as inbound/outbound pairs.
+    pub messages_per_day: usize,
+    /// The same seed yields the same intents and messages.
+    pub seed: u64,
+    pub end: DateTime<Utc>,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            intents: 50,
+            days: 14,
+            messages_per_day: 20,
+            seed: 1,
+            end: Utc::now(),
+        }
+    }
+}
+
+/// What [`generate_fixture_data`] wrote.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct FixtureReport {
+    /// Intents per stage: `history`, `inbox`, `queue` or `failed`.
+    pub intents: BTreeMap<&'static str, usize>,
+    pub journal_entries: usize,
+    pub memory_entries: usize,
+    pub messages: usize,
+}
+
+/// Synthesize intents, journals, memory entries and message logs under
+/// `data_dir` with timestamps spread over the last `spec.days` days, so load
+/// tests and UI demos do not depend on hand-written markdown. Existing data
+/// is kept; generated files are added next to it.
+pub async fn generate_fixture_data(data_dir: &Path, spec: &FixtureSpec) -> Result<FixtureReport> {
+    storage::ensure_data_layout(data_dir)?;
+    let mut rng = SplitMix(spec.seed);
+    let days = i64::from(spec.days.max(1));
+    let start = spec.end - Duration::days(days);
+    let mut report = FixtureReport::default();
+
+    for _ in 0..spec.intents {
+        let day = rng.below(days as u64) as i64;
+        // Mostly waking hours, 07:00–23:00.
+        let offset = Duration::hours(7) + Duration::seconds(rng.below(16 * 3600) as i64);
+        let mut created_at = start + Duration::days(day) + offset;
+        if created_at > spec.end {
+            created_at = spec.end - Duration::seconds(rng.below(3600) as i64 + 60);
+        }
+
+        let intent = synth_intent(&mut rng, created_at);
+        let body = format!(
+            "Requested via {} on {}.\n\n- context: generated fixture\n",
+            intent.source,
+            created_at.format("%Y-%m-%d %H:%M")
+        );
+        let file_name = format!("{}-{}.md", created_at.format("%Y%m%dT%H%M%S"), intent.id);
+
+        let stage = match rng.below(100) {
+            0..75 => "history",
+            75..85 => "inbox",
+            85..95 => "queue",
+            _ => "failed",
+        };
+        let dir = match stage {
+            "history" => "intent/history",
+            "inbox" => "intent/inbox",
+            "queue" => "intent/queue",
+            _ => "intent/queue/failed",
+        };
+        let path = data_dir.join(dir).join(&file_name);
+        storage::write_intent_file(&path, &intent, &body)?;
+        *report.intents.entry(stage).or_default() += 1;
+
+        if stage == "history" {
+            let finished_at = created_at + Duration::seconds(30 + rng.below(20 * 60) as i64);
+            archive_run(data_dir, &mut rng, &intent, &path, finished_at).await?;
+            report.journal_entries += 1;
+            report.memory_entries += 1;
+        }
+    }
+
+    for day in 0..days {
+        let day_start = start + Duration::days(day) + Duration::hours(7);
+        for sent in (0..spec.messages_per_day).step_by(2) {
+            let at = day_start + Duration::seconds(rng.below(16 * 3600) as i64);
+            if at > spec.end {
+                continue;
+            }
+            let chat_id = rng.pick(CHATS);
+            let inbound = MessageLogEntry {
+                id: rng.uuid(),
+                direction: MessageDirection::Inbound,
+                source: "telegram".to_string(),
+                chat_id: chat_id.to_string(),
+                author: Some("demo".to_string()),
+                text: rng.pick(INBOUND).to_string(),
+                timestamp: at,
+                metadata: None,
+            };
+            storage::append_message_entry(data_dir, &inbound).await?;
+            report.messages += 1;
+            if sent + 1 == spec.messages_per_day {
+                break;
+            }
+
+            let reply = MessageLogEntry {
+                id: rng.uuid(),
+                direction: MessageDirection::Outbound,
+                author: None,
+                text: rng.pick(OUTBOUND).to_string(),
+                timestamp: (at + Duration::seconds(5 + rng.below(90) as i64)).min(spec.end),
+                ..inbound
+            };
+            storage::append_message_entry(data_dir, &reply).await?;
+            report.messages += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+fn synth_intent(rng: &mut SplitMix, created_at: DateTime<Utc>) -> Intent {
+    let mut metadata = BTreeMap::new();
+    match rng.below(10) {
+        0 => {
+            metadata.insert(PRIORITY_KEY.to_string(), "high".to_string());
+        }
+        1 => {
+            metadata.insert(PRIORITY_KEY.to_string(), "low".to_string());
+        }
+        _ => {}
+    }
+    Intent {
+        id: rng.uuid(),
+        source: rng.pick(SOURCES).to_string(),
+        summary: format!("{} {}", rng.pick(VERBS), rng.pick(OBJECTS)),
+        telos_alignment: (50 + rng.below(50)) as f32 / 100.0,
+        created_at,
+        due_at: (rng.below(4) == 0).then(|| created_at + Duration::days(2)),
+        metadata,
+        storage_path: None,
+    }
+}
+
+/// Journal and memory for an intent that ran and finished at `finished_at`.
+async fn archive_run(
+    data_dir: &Path,
+    rng: &mut SplitMix,
+    intent: &Intent,
+    history_path: &Path,
+    finished_at: DateTime<Utc>,
+) -> Result<()> {
+    let steps = (0..1 + rng.below(3))
+        .map(|index| AgentStep {
+            thought: format!(
+                "Step {}: gather context for '{}'",
+                index + 1,
+                intent.summary
+            ),
+            action: rng
+                .pick(&["summarize_intent", "search_memory", "read_journal"])
+                .to_string(),
+            observation: format!("Found {} related notes", rng.below(6)),
+            question: None,
+            input: None,
+            sources: Vec::new(),
+        })
+        .collect();
+    let outcome = AgentOutcome {
+        steps,
+        final_answer: format!("Completed: {}", intent.summary.to_lowercase()),
+    };
+
+    let entry_id = rng.uuid();
+    let links = JournalLinks {
+        run_id: rng.uuid(),
+        intent_id: intent.id,
+        memory_ids: vec![entry_id],
+    };
+    let journal_path = storage::append_journal_entry_at(
+        data_dir,
+        finished_at,
+        intent,
+        &outcome,
+        &links,
+        None,
+        true,
+    )
+    .await
+    .with_context(|| format!("writing fixture journal for {}", intent.id))?;
+    storage::ingest_memory_snapshot_at(
+        data_dir,
+        MemorySnapshotInput {
+            entry_id,
+            intent: intent.clone(),
+            outcome,
+            journal_path,
+            history_path: Some(history_path.to_path_buf()),
+        },
+        finished_at,
+    )
+    .await
+    .with_context(|| format!("writing fixture memory for {}", intent.id))?;
+    Ok(())
+}
+
+/// Small deterministic generator (SplitMix64); fixtures need repeatable
+/// variety, not statistical quality.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+
+    fn uuid(&mut self) -> Uuid {
+        uuid::Builder::from_random_bytes(
+            ((u128::from(self.next()) << 64) | u128::from(self.next())).to_be_bytes(),
+        )
+        .into_uuid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{MemoryLevel, MemoryQuery, MessageLogQuery};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn generates_requested_volume_within_window() {
+        let temp = tempdir().unwrap();
+        let spec = FixtureSpec {
+            intents: 12,
+            days: 3,
+            messages_per_day: 4,
+            seed: 7,
+            end: "2025-03-10T12:00:00Z".parse().unwrap(),
+        };
+
+        let report = generate_fixture_data(temp.path(), &spec).await.unwrap();
+        assert_eq!(report.intents.values().sum::<usize>(), 12);
+        assert_eq!(report.messages, 12);
+
+        let history = storage::scan_history(temp.path()).unwrap();
+        assert_eq!(history.len(), report.intents["history"]);
+        assert_eq!(report.memory_entries, history.len());
+        let start = spec.end - Duration::days(3);
+        assert!(history.iter().all(|record| {
+            record.intent.created_at >= start && record.intent.created_at <= spec.end
+        }));
+
+        let memories = storage::read_memory_entries(
+            temp.path(),
+            MemoryQuery {
+                level: MemoryLevel::L1,
+                limit: usize::MAX,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(memories.len(), history.len());
+        assert!(memories.iter().all(|entry| entry.created_at <= spec.end));
+
+        let messages = storage::read_messages(
+            temp.path(),
+            MessageLogQuery {
+                limit: 100,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 12);
+
+        let again = tempdir().unwrap();
+        generate_fixture_data(again.path(), &spec).await.unwrap();
+        let ids = |dir: &Path| {
+            let mut ids: Vec<Uuid> = storage::scan_history(dir)
+                .unwrap()
+                .into_iter()
+                .map(|record| record.intent.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(temp.path()), ids(again.path()));
+    }
+}
@@ -3,6 +3,10 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
+mod generator;
+
+pub use generator::{FixtureReport, FixtureSpec, generate_fixture_data};
+
 const CORE_FIXTURE_DIR: &str = "tests/fixtures/core";
 
 /// Return the on-disk location of the bundled core fixture.
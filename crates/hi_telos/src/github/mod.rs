@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, env, path::Path, time::Duration};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    config::{AppConfig, GithubConfig},
+    server::{self, ServerState},
+    sources::{IngestMode, IngestSource},
+    storage::{self, IntentDraft, PersistedIntent},
+    tasks::Intent,
+    text,
+};
+
+pub const GITHUB_SOURCE: &str = "github";
+pub const ISSUE_URL_KEY: &str = "github_issue_url";
+pub const REPO_KEY: &str = "github_repo";
+pub const ISSUE_NUMBER_KEY: &str = "github_issue_number";
+
+const GITHUB_SUMMARY_MAX_CHARS: usize = 80;
+
+/// Bound on posting one issue comment.
+const GITHUB_TIMEOUT_SECS: u64 = 15;
+
+/// Queued issues and handled deliveries live in `data/github/seen.json`.
+const GITHUB_STATE_NAMESPACE: &str = "github";
+const SEEN_ISSUES: &str = "issues";
+const SEEN_DELIVERIES: &str = "deliveries";
+
+/// Held across the seen-state check and update, so an `opened` and a
+/// `labeled` delivery for the same issue arriving together queue it once.
+static SEEN_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Issue events pushed to `/webhook/github` by the repos in
+/// `config/github.yml`.
+#[derive(Debug, Default)]
+pub struct GithubSource;
+
+#[async_trait]
+impl IngestSource for GithubSource {
+    fn name(&self) -> &'static str {
+        GITHUB_SOURCE
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        config.github.as_ref()?;
+        Some(IngestMode::Webhook {
+            path: server::GITHUB_WEBHOOK_PATH,
+        })
+    }
+
+    fn routes(&self) -> Router<ServerState> {
+        server::github_webhook_routes()
+    }
+}
+
+/// The subset of GitHub's `issues` webhook payload that becomes an intent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IssuesEvent {
+    pub action: String,
+    pub issue: Issue,
+    #[serde(default)]
+    pub label: Option<Label>,
+    pub repository: Repository,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+}
+
+/// Map an `opened` or `labeled` issue event to an intent draft, applying the
+/// repo and label filters. `labeled` only counts when the newly added label
+/// is one of the configured labels; [`queue_issue_intent`] keeps an issue
+/// that matches more than once from being queued twice.
+pub fn issue_intent(config: &GithubConfig, event: &IssuesEvent) -> Option<IntentDraft> {
+    let repo = &event.repository.full_name;
+    if !config.repos.is_empty()
+        && !config
+            .repos
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(repo))
+    {
+        return None;
+    }
+
+    let label_matches = |name: &str| {
+        config
+            .labels
+            .iter()
+            .any(|label| label.eq_ignore_ascii_case(name))
+    };
+    let accepted = match event.action.as_str() {
+        "opened" => {
+            config.labels.is_empty()
+                || event
+                    .issue
+                    .labels
+                    .iter()
+                    .any(|label| label_matches(&label.name))
+        }
+        "labeled" => event
+            .label
+            .as_ref()
+            .is_some_and(|label| label_matches(&label.name)),
+        _ => false,
+    };
+    if !accepted {
+        return None;
+    }
+
+    let issue = &event.issue;
+    let title = issue.title.trim();
+    let summary = text::truncate(title, GITHUB_SUMMARY_MAX_CHARS);
+    let labels = issue
+        .labels
+        .iter()
+        .map(|label| label.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let body = format!(
+        "## GitHub issue {repo}#{number}\n\nURL: {url}\nLabels: {labels}\n\n{text}",
+        number = issue.number,
+        url = issue.html_url,
+        labels = if labels.is_empty() { "(none)" } else { &labels },
+        text = issue.body.as_deref().unwrap_or_default().trim(),
+    );
+
+    let metadata = BTreeMap::from([
+        (ISSUE_URL_KEY.to_string(), issue.html_url.clone()),
+        (REPO_KEY.to_string(), repo.clone()),
+        (ISSUE_NUMBER_KEY.to_string(), issue.number.to_string()),
+    ]);
+
+    Some(IntentDraft {
+        source: GITHUB_SOURCE.to_string(),
+        summary,
+        telos_alignment: config.telos_alignment,
+        body,
+        due_at: None,
+        metadata,
+    })
+}
+
+/// Persist the draft for an accepted issue event unless the webhook delivery
+/// was handled before or the issue was already queued, e.g. by the `opened`
+/// event that GitHub sends alongside `labeled`. Returns `None` for duplicates.
+pub async fn queue_issue_intent(
+    data_dir: &Path,
+    delivery: Option<&str>,
+    event: &IssuesEvent,
+    draft: &IntentDraft,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<PersistedIntent>> {
+    let _guard = SEEN_LOCK.lock().await;
+    let mut seen = storage::load_seen_state(data_dir, GITHUB_STATE_NAMESPACE)?;
+    let issue = format!(
+        "{}#{}",
+        event.repository.full_name.to_lowercase(),
+        event.issue.number
+    );
+    if delivery.is_some_and(|id| seen.contains(SEEN_DELIVERIES, id))
+        || seen.contains(SEEN_ISSUES, &issue)
+    {
+        return Ok(None);
+    }
+
+    let persisted = storage::persist_intent_at(data_dir, draft, now).await?;
+    seen.insert(SEEN_ISSUES, &issue);
+    if let Some(id) = delivery {
+        seen.insert(SEEN_DELIVERIES, id);
+    }
+    storage::save_seen_state(data_dir, GITHUB_STATE_NAMESPACE, &seen)?;
+    Ok(Some(persisted))
+}
+
+/// Post `body` as a comment on `repo#number` using the token from
+/// `token_env`.
+pub async fn post_issue_comment(
+    config: &GithubConfig,
+    repo: &str,
+    number: u64,
+    body: &str,
+) -> anyhow::Result<()> {
+    let token_env = config
+        .token_env
+        .as_deref()
+        .ok_or_else(|| anyhow!("github token_env not configured"))?;
+    let token = env::var(token_env).with_context(|| format!("reading {token_env}"))?;
+
+    let url = format!(
+        "{}/repos/{}/issues/{}/comments",
+        config.api_base.trim_end_matches('/'),
+        repo,
+        number
+    );
+    let client = Client::builder()
+        .timeout(Duration::from_secs(GITHUB_TIMEOUT_SECS))
+        .build()
+        .context("building github client")?;
+    let response = client
+        .post(url)
+        .bearer_auth(token)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .header(reqwest::header::USER_AGENT, "hi-telos")
+        .json(&json!({ "body": body }))
+        .send()
+        .await
+        .with_context(|| "posting github issue comment")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("github returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+/// GitHub tool used after an intent completes: when the intent came from an
+/// issue, reply there with the final answer. Returns `false` when the intent
+/// carries no issue metadata or commenting is disabled.
+pub async fn comment_final_answer(
+    config: &GithubConfig,
+    intent: &Intent,
+    final_answer: &str,
+) -> anyhow::Result<bool> {
+    if config.token_env.is_none() {
+        return Ok(false);
+    }
+    let (Some(repo), Some(number)) = (
+        intent.metadata.get(REPO_KEY),
+        intent
+            .metadata
+            .get(ISSUE_NUMBER_KEY)
+            .and_then(|number| number.parse::<u64>().ok()),
+    ) else {
+        return Ok(false);
+    };
+
+    post_issue_comment(config, repo, number, final_answer).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use httpmock::prelude::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn config(api_base: String) -> GithubConfig {
+        GithubConfig {
+            webhook_secret_env: "HI_GITHUB_WEBHOOK_SECRET".to_string(),
+            token_env: Some("HI_TEST_GITHUB_TOKEN".to_string()),
+            repos: vec!["cklxx/HI".to_string()],
+            labels: vec!["hi".to_string()],
+            telos_alignment: 0.8,
+            api_base,
+        }
+    }
+
+    fn event(action: &str, repo: &str, labels: &[&str], added: Option<&str>) -> IssuesEvent {
+        serde_json::from_value(json!({
+            "action": action,
+            "issue": {
+                "number": 42,
+                "title": "Flaky beat scheduler",
+                "body": "Beats skip after sleep.",
+                "html_url": "https://github.com/cklxx/HI/issues/42",
+                "labels": labels.iter().map(|name| json!({"name": name})).collect::<Vec<_>>(),
+            },
+            "label": added.map(|name| json!({"name": name})),
+            "repository": {"full_name": repo},
+        }))
+        .expect("event")
+    }
+
+    #[test]
+    fn filters_by_repo_and_label() {
+        let config = config(String::new());
+
+        let draft = issue_intent(&config, &event("opened", "cklxx/HI", &["hi"], None))
+            .expect("opened with label");
+        assert_eq!(draft.source, GITHUB_SOURCE);
+        assert_eq!(draft.summary, "Flaky beat scheduler");
+        assert_eq!(
+            draft.metadata.get(ISSUE_URL_KEY).map(String::as_str),
+            Some("https://github.com/cklxx/HI/issues/42")
+        );
+        assert!(draft.body.contains("Beats skip after sleep."));
+
+        assert!(issue_intent(&config, &event("opened", "other/repo", &["hi"], None)).is_none());
+        assert!(issue_intent(&config, &event("opened", "cklxx/HI", &["bug"], None)).is_none());
+        assert!(issue_intent(&config, &event("closed", "cklxx/HI", &["hi"], None)).is_none());
+        assert!(
+            issue_intent(&config, &event("labeled", "cklxx/HI", &["hi"], Some("hi"))).is_some()
+        );
+        assert!(
+            issue_intent(
+                &config,
+                &event("labeled", "cklxx/HI", &["hi", "bug"], Some("bug"))
+            )
+            .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn issue_is_queued_once_across_events_and_redeliveries() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).expect("layout");
+        let config = config(String::new());
+        let now = Utc::now();
+
+        let opened = event("opened", "cklxx/HI", &["hi"], None);
+        let draft = issue_intent(&config, &opened).expect("opened");
+        let queued = queue_issue_intent(data_dir, Some("delivery-1"), &opened, &draft, now)
+            .await
+            .expect("queue opened");
+        assert!(queued.is_some());
+
+        let redelivered = queue_issue_intent(data_dir, Some("delivery-1"), &opened, &draft, now)
+            .await
+            .expect("redelivery");
+        assert!(redelivered.is_none());
+
+        let labeled = event("labeled", "cklxx/HI", &["hi"], Some("hi"));
+        let draft = issue_intent(&config, &labeled).expect("labeled");
+        let relabeled = queue_issue_intent(data_dir, Some("delivery-2"), &labeled, &draft, now)
+            .await
+            .expect("queue labeled");
+        assert!(relabeled.is_none());
+        assert_eq!(storage::scan_inbox(data_dir).expect("inbox").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn final_answer_is_posted_as_issue_comment() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/repos/cklxx/HI/issues/42/comments")
+                    .header("authorization", "Bearer gh-token")
+                    .json_body(json!({"body": "Fixed in the scheduler."}));
+                then.status(201);
+            })
+            .await;
+        unsafe {
+            env::set_var("HI_TEST_GITHUB_TOKEN", "gh-token");
+        }
+
+        let config = config(server.base_url());
+        let draft = issue_intent(&config, &event("opened", "cklxx/HI", &["hi"], None)).unwrap();
+        let mut intent = Intent {
+            id: Uuid::new_v4(),
+            source: draft.source,
+            summary: draft.summary,
+            telos_alignment: draft.telos_alignment,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: draft.metadata,
+            storage_path: None,
+        };
+
+        let posted = comment_final_answer(&config, &intent, "Fixed in the scheduler.")
+            .await
+            .expect("comment");
+        assert!(posted);
+        mock.assert_async().await;
+
+        intent.metadata.clear();
+        let skipped = comment_final_answer(&config, &intent, "ignored")
+            .await
+            .expect("skip");
+        assert!(!skipped);
+    }
+}
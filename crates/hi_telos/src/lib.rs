@@ -1,9 +1,30 @@
 pub mod agent;
+pub mod backup;
+pub mod calendar;
+pub mod clock;
 pub mod config;
+pub mod doctor;
+pub mod email;
+pub mod events;
+pub mod export;
+pub mod feeds;
 pub mod fixtures;
+pub mod github;
+pub mod lifecycle;
 pub mod llm;
+pub mod maintenance;
+pub mod notifications;
+pub mod object_store;
 pub mod orchestrator;
+pub mod outbox;
 pub mod server;
+pub mod sessions;
+pub mod sources;
 pub mod state;
 pub mod storage;
 pub mod tasks;
+pub mod telegram;
+pub mod text;
+pub mod tools;
+pub mod watchdog;
+pub mod webhooks;
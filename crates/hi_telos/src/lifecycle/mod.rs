@@ -0,0 +1,145 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use tracing::warn;
+
+/// Resolve on the first SIGTERM or SIGINT (Ctrl-C elsewhere) and name it,
+/// so containers stopped with SIGTERM shut down as gracefully as Ctrl-C.
+pub async fn shutdown_signal() -> anyhow::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).with_context(|| "installing SIGTERM handler")?;
+        let mut interrupt =
+            signal(SignalKind::interrupt()).with_context(|| "installing SIGINT handler")?;
+        tokio::select! {
+            _ = terminate.recv() => Ok("SIGTERM"),
+            _ = interrupt.recv() => Ok("SIGINT"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .with_context(|| "waiting for Ctrl-C")?;
+        Ok("ctrl_c")
+    }
+}
+
+/// The process id written to a file for supervisors and scripts, removed
+/// again when the value is dropped.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating pid file dir {:?}", parent))?;
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("writing pid file {:?}", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another process has claimed it since.
+        let ours = fs::read_to_string(&self.path)
+            .is_ok_and(|content| content.trim() == std::process::id().to_string());
+        if ours && let Err(err) = fs::remove_file(&self.path) {
+            warn!(path = ?self.path, error = ?err, "failed to remove pid file");
+        }
+    }
+}
+
+/// Send `state` (e.g. `READY=1`) to systemd's notify socket. Does nothing
+/// and returns `false` unless the service runs with `Type=notify`, i.e.
+/// `NOTIFY_SOCKET` is set.
+pub fn sd_notify(state: &str) -> anyhow::Result<bool> {
+    let Some(socket) = env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+    send_notify(Path::new(&socket), state)?;
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn send_notify(socket: &Path, state: &str) -> anyhow::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+
+    let datagram = UnixDatagram::unbound().with_context(|| "opening notify socket")?;
+    // A leading `@` names a Linux abstract socket.
+    #[cfg(target_os = "linux")]
+    if let Some(abstract_name) = socket.as_os_str().as_encoded_bytes().strip_prefix(b"@") {
+        use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+        let addr = SocketAddr::from_abstract_name(abstract_name)
+            .with_context(|| format!("parsing notify socket {:?}", socket))?;
+        datagram
+            .send_to_addr(state.as_bytes(), &addr)
+            .with_context(|| format!("notifying {:?}", socket))?;
+        return Ok(());
+    }
+    datagram
+        .send_to(state.as_bytes(), socket)
+        .with_context(|| format!("notifying {:?}", socket))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_notify(_socket: &Path, _state: &str) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn pid_file_holds_pid_until_dropped() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("run/hi_telos.pid");
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(pid_file.path()).unwrap().trim(),
+            std::process::id().to_string()
+        );
+        drop(pid_file);
+        assert!(!path.exists());
+
+        let pid_file = PidFile::create(&path).unwrap();
+        fs::write(&path, "1\n").unwrap();
+        drop(pid_file);
+        assert!(path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sd_notify_writes_to_the_notify_socket() {
+        use std::os::unix::net::UnixDatagram;
+
+        let temp = tempdir().unwrap();
+        let socket = temp.path().join("notify.sock");
+        let listener = UnixDatagram::bind(&socket).unwrap();
+        send_notify(&socket, "READY=1").unwrap();
+        let mut buf = [0u8; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
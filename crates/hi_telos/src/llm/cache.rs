@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::LlmIdentity;
+use crate::storage;
+
+/// One cached provider response, stored as `<key>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub key: String,
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub response: String,
+    pub cached_at: DateTime<Utc>,
+}
+
+/// Provider responses kept on disk for `ttl`, so identical prompts (replayed
+/// intents, repeated scoring) are answered without calling the provider.
+/// Unlike [`super::ReplayLlmClient`], a miss or an expired entry falls
+/// through to the provider.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    pub fn new(dir: PathBuf, ttl: std::time::Duration) -> Self {
+        Self {
+            dir,
+            ttl: Duration::from_std(ttl).unwrap_or(Duration::MAX),
+        }
+    }
+
+    /// The cached response to `prompt` from the provider and model of
+    /// `identity`, when one was stored less than the TTL before `now`.
+    pub async fn get(
+        &self,
+        identity: &LlmIdentity,
+        prompt: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<String>> {
+        let path = self.path(identity, prompt);
+        let raw = match tokio::fs::read_to_string(&path).await {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading llm cache entry {:?}", path));
+            }
+        };
+        let cached: CachedResponse = serde_json::from_str(&raw)
+            .with_context(|| format!("parsing llm cache entry {:?}", path))?;
+        // A different prompt under the same key would be a hash collision;
+        // treat it as a miss rather than answer the wrong question.
+        if cached.prompt != prompt || now - cached.cached_at >= self.ttl {
+            return Ok(None);
+        }
+        Ok(Some(cached.response))
+    }
+
+    /// Store `response` as the answer to `prompt`, replacing any older entry.
+    pub async fn put(
+        &self,
+        identity: &LlmIdentity,
+        prompt: &str,
+        response: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let key = cache_key(identity, prompt);
+        let entry = CachedResponse {
+            key: key.clone(),
+            provider: identity.provider.to_string(),
+            model: identity.model.clone(),
+            prompt: prompt.to_string(),
+            response: response.to_string(),
+            cached_at: now,
+        };
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("creating llm cache dir {:?}", self.dir))?;
+        let path = self.dir.join(format!("{key}.json"));
+        storage::write_atomic_async(&path, serde_json::to_vec_pretty(&entry)?)
+            .await
+            .with_context(|| format!("writing llm cache entry {:?}", path))
+    }
+
+    fn path(&self, identity: &LlmIdentity, prompt: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", cache_key(identity, prompt)))
+    }
+}
+
+/// The hex SHA-256 of the provider, model and prompt, so switching models
+/// does not serve another model's answers.
+fn cache_key(identity: &LlmIdentity, prompt: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [identity.provider, identity.model.as_deref().unwrap_or("")] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.update(prompt.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn serves_responses_until_they_expire() {
+        let temp = tempdir().unwrap();
+        let cache = ResponseCache::new(
+            temp.path().join("llm_cache"),
+            std::time::Duration::from_secs(60),
+        );
+        let identity = LlmIdentity::new("openai", Some("gpt-4o".to_string()));
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+
+        assert_eq!(cache.get(&identity, "prompt", at).await.unwrap(), None);
+        cache.put(&identity, "prompt", "answer", at).await.unwrap();
+        assert_eq!(
+            cache
+                .get(&identity, "prompt", at + Duration::seconds(59))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("answer")
+        );
+        assert_eq!(
+            cache
+                .get(&identity, "prompt", at + Duration::seconds(60))
+                .await
+                .unwrap(),
+            None
+        );
+
+        let other_model = LlmIdentity::new("openai", Some("gpt-4o-mini".to_string()));
+        assert_eq!(cache.get(&other_model, "prompt", at).await.unwrap(), None);
+        assert_eq!(cache.get(&identity, "prompt 2", at).await.unwrap(), None);
+    }
+}
@@ -9,12 +9,55 @@ use serde_json::json;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::config::{OpenAiParams, OpenAiResponseFormat};
+use crate::storage;
+
+mod cache;
+mod recording;
+mod scripted;
+mod status;
+
+pub use cache::{CachedResponse, ResponseCache};
+pub use recording::{LlmRecorder, LlmRecording, RecordingLlmClient, ReplayLlmClient, prompt_hash};
+pub use scripted::{LlmScript, ScriptedLlmClient, ScriptedResponse};
+pub use status::{LlmStatus, check_llm};
+
 #[async_trait]
 pub trait LlmClient: Send + Sync {
     async fn chat(&self, prompt: &str) -> anyhow::Result<String>;
+
+    /// [`Self::chat`] for an answer shaped like `schema`, returned as its
+    /// JSON text. Clients without function calling fall back to `chat`,
+    /// whose prompts already ask for that JSON.
+    async fn chat_structured(
+        &self,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> anyhow::Result<String> {
+        let _ = schema;
+        self.chat(prompt).await
+    }
+
+    /// Model ids the provider serves, or `None` for clients that cannot
+    /// list them. Used by [`check_llm`] to confirm the provider is reachable
+    /// and knows the configured model.
+    async fn list_models(&self) -> anyhow::Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
     fn identity(&self) -> LlmIdentity;
 }
 
+/// The JSON object a caller expects back, declared as a function to
+/// providers that support function calling.
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the object.
+    pub parameters: serde_json::Value,
+}
+
 #[derive(Debug, Default)]
 pub struct LocalStubClient;
 
@@ -40,8 +83,16 @@ impl LlmClient for LocalStubClient {
                 "final_answer": format!("{persona} completed the plan for '{intent}'"),
             });
             Ok(response.to_string())
+        } else if prompt.contains("# Phase: SUMMARY") {
+            let lines = prompt.split_once("Document:\n").map_or(0, |(_, document)| {
+                document.lines().count().saturating_sub(1)
+            });
+            let response = serde_json::json!({
+                "final_answer": format!("Summary of a {lines}-line document"),
+            });
+            Ok(response.to_string())
         } else {
-            anyhow::bail!("stub LLM only supports THINK and FINAL phases");
+            anyhow::bail!("stub LLM only supports THINK, FINAL and SUMMARY phases");
         }
     }
 
@@ -57,8 +108,14 @@ pub struct OpenAiClient {
     api_key: String,
     base_url: String,
     organization: Option<String>,
+    function_calling: bool,
+    params: OpenAiParams,
 }
 
+const DEFAULT_OPENAI_TEMPERATURE: f64 = 0.2;
+const DEFAULT_OPENAI_SYSTEM_PROMPT: &str =
+    "You are TelosOps agent executing a ReAct loop. Always answer with valid JSON.";
+
 impl OpenAiClient {
     pub fn from_env(
         api_key_env: &str,
@@ -89,27 +146,70 @@ impl OpenAiClient {
             api_key,
             base_url: normalized_base,
             organization,
+            function_calling: true,
+            params: OpenAiParams::default(),
         })
     }
-}
 
-#[async_trait]
-impl LlmClient for OpenAiClient {
-    async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+    /// Whether [`LlmClient::chat_structured`] declares its schema as a
+    /// function; turn off for OpenAI-compatible servers without `tools`.
+    pub fn with_function_calling(mut self, enabled: bool) -> Self {
+        self.function_calling = enabled;
+        self
+    }
+
+    /// Temperature, `max_tokens`, system prompt and response format for
+    /// every request; unset ones keep their defaults.
+    pub fn with_params(mut self, params: OpenAiParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    async fn complete(
+        &self,
+        prompt: &str,
+        schema: Option<&ResponseSchema>,
+    ) -> anyhow::Result<String> {
         let url = format!("{}/chat/completions", self.base_url);
-        let mut request = self
-            .http
-            .post(url)
-            .bearer_auth(&self.api_key)
-            .json(&json!({
-                "model": self.model,
-                "temperature": 0.2,
-                "response_format": {"type": "json_object"},
-                "messages": [
-                    {"role": "system", "content": "You are TelosOps agent executing a ReAct loop. Always answer with valid JSON."},
-                    {"role": "user", "content": prompt}
-                ],
-            }));
+        let system_prompt = self
+            .params
+            .system_prompt
+            .as_deref()
+            .unwrap_or(DEFAULT_OPENAI_SYSTEM_PROMPT);
+        let mut body = json!({
+            "model": self.model,
+            "temperature": self.params.temperature.unwrap_or(DEFAULT_OPENAI_TEMPERATURE),
+            "messages": [
+                {"role": "system", "content": system_prompt},
+                {"role": "user", "content": prompt}
+            ],
+        });
+        if let Some(max_tokens) = self.params.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        match schema {
+            Some(schema) => {
+                body["tools"] = json!([{
+                    "type": "function",
+                    "function": {
+                        "name": schema.name,
+                        "description": schema.description,
+                        "parameters": schema.parameters,
+                    },
+                }]);
+                body["tool_choice"] = json!({
+                    "type": "function",
+                    "function": { "name": schema.name },
+                });
+            }
+            None => match self.params.response_format {
+                Some(OpenAiResponseFormat::Text) => {}
+                Some(OpenAiResponseFormat::JsonObject) | None => {
+                    body["response_format"] = json!({"type": "json_object"})
+                }
+            },
+        }
+        let mut request = self.http.post(url).bearer_auth(&self.api_key).json(&body);
 
         if let Some(org) = &self.organization {
             request = request.header("OpenAI-Organization", org);
@@ -127,16 +227,72 @@ impl LlmClient for OpenAiClient {
             .await
             .with_context(|| "parsing OpenAI response body")?;
 
-        payload
+        let message = payload
             .get("choices")
             .and_then(|choices| choices.as_array())
             .and_then(|choices| choices.first())
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("content"))
-            .and_then(|content| content.as_str())
+            .and_then(|choice| choice.get("message"));
+        // A model may still answer in text when asked for a function call.
+        let arguments = message
+            .and_then(|message| message.get("tool_calls"))
+            .and_then(|calls| calls.as_array())
+            .and_then(|calls| calls.first())
+            .and_then(|call| call.get("function"))
+            .and_then(|function| function.get("arguments"))
+            .and_then(|arguments| arguments.as_str());
+        arguments
+            .or_else(|| {
+                message
+                    .and_then(|message| message.get("content"))
+                    .and_then(|content| content.as_str())
+            })
             .map(|content| content.to_string())
             .ok_or_else(|| anyhow!("missing message content in OpenAI response"))
     }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+        self.complete(prompt, None).await
+    }
+
+    async fn chat_structured(
+        &self,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> anyhow::Result<String> {
+        let schema = self.function_calling.then_some(schema);
+        self.complete(prompt, schema).await
+    }
+
+    async fn list_models(&self) -> anyhow::Result<Option<Vec<String>>> {
+        let mut request = self
+            .http
+            .get(format!("{}/models", self.base_url))
+            .bearer_auth(&self.api_key);
+        if let Some(org) = &self.organization {
+            request = request.header("OpenAI-Organization", org);
+        }
+        let payload: serde_json::Value = request
+            .send()
+            .await
+            .with_context(|| "listing OpenAI models")?
+            .error_for_status()
+            .with_context(|| "OpenAI returned an error status")?
+            .json()
+            .await
+            .with_context(|| "parsing OpenAI model list")?;
+        let models = payload
+            .get("data")
+            .and_then(|data| data.as_array())
+            .ok_or_else(|| anyhow!("missing data in OpenAI model list"))?
+            .iter()
+            .filter_map(|model| model.get("id").and_then(|id| id.as_str()))
+            .map(str::to_string)
+            .collect();
+        Ok(Some(models))
+    }
 
     fn identity(&self) -> LlmIdentity {
         LlmIdentity::new("openai", Some(self.model.clone()))
@@ -164,6 +320,8 @@ impl LlmIdentity {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmLogEntry {
+    #[serde(default)]
+    pub schema_version: u32,
     pub run_id: Uuid,
     pub timestamp: DateTime<Utc>,
     pub phase: String,
@@ -171,6 +329,20 @@ pub struct LlmLogEntry {
     pub response: String,
     pub provider: String,
     pub model: Option<String>,
+    /// Wall-clock time the provider took to answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Version of the prompt templates the run used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// Experiment the run took part in, and its variant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Answered from the response cache instead of the provider.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_hit: bool,
 }
 
 impl LlmLogEntry {
@@ -183,6 +355,7 @@ impl LlmLogEntry {
         identity: &LlmIdentity,
     ) -> Self {
         Self {
+            schema_version: storage::SCHEMA_VERSION,
             run_id,
             timestamp,
             phase: phase.into(),
@@ -190,8 +363,34 @@ impl LlmLogEntry {
             response: response.into(),
             provider: identity.provider.to_string(),
             model: identity.model.clone(),
+            latency_ms: None,
+            prompt_version: None,
+            experiment: None,
+            variant: None,
+            cache_hit: false,
         }
     }
+
+    pub fn with_latency(mut self, latency: std::time::Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+
+    pub fn with_cache_hit(mut self, cache_hit: bool) -> Self {
+        self.cache_hit = cache_hit;
+        self
+    }
+
+    /// Stamp the entry with the run's prompt version and, when the run is
+    /// part of an experiment, its `(experiment, variant)`.
+    pub fn with_prompt(mut self, version: &str, experiment: Option<(&str, &str)>) -> Self {
+        self.prompt_version = Some(version.to_string());
+        if let Some((name, variant)) = experiment {
+            self.experiment = Some(name.to_string());
+            self.variant = Some(variant.to_string());
+        }
+        self
+    }
 }
 
 #[cfg(test)]
@@ -229,13 +428,25 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn stub_returns_summary_payload() {
+        let client = LocalStubClient;
+        let response = client
+            .chat("# Phase: SUMMARY\nIntent: Summarize 'Notes'\nDocument:\nfirst\nsecond\nRespond with JSON containing final_answer.")
+            .await
+            .expect("stub should handle SUMMARY phase");
+
+        let parsed: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(parsed["final_answer"], "Summary of a 2-line document");
+    }
+
     #[tokio::test]
     async fn stub_rejects_unknown_phase() {
         let client = LocalStubClient;
         let err = client.chat("# Phase: PLAN").await.unwrap_err();
         assert!(
             err.to_string()
-                .contains("stub LLM only supports THINK and FINAL")
+                .contains("stub LLM only supports THINK, FINAL and SUMMARY")
         );
     }
 
@@ -275,6 +486,93 @@ mod tests {
         mock.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn openai_client_declares_schema_as_function() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/chat/completions")
+                    .json_body_partial(
+                        r#"{"tools":[{"type":"function","function":{"name":"final_answer"}}],"tool_choice":{"type":"function","function":{"name":"final_answer"}}}"#,
+                    );
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(r#"{"choices":[{"message":{"content":null,"tool_calls":[{"type":"function","function":{"name":"final_answer","arguments":"{\"final_answer\":\"called\"}"}}]}}]}"#);
+            })
+            .await;
+        let text_mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/chat/completions")
+                    .json_body_partial(r#"{"response_format":{"type":"json_object"}}"#);
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(r#"{"choices":[{"message":{"content":"{\"final_answer\":\"text\"}"}}]}"#);
+            })
+            .await;
+        let schema = ResponseSchema {
+            name: "final_answer",
+            description: "The answer",
+            parameters: json!({"type": "object"}),
+        };
+        let client = OpenAiClient::new(
+            "test-key".to_string(),
+            "gpt-test",
+            Some(server.base_url()),
+            None,
+        )
+        .unwrap();
+
+        let response = client
+            .chat_structured("# Phase: FINAL", &schema)
+            .await
+            .unwrap();
+        assert_eq!(response, r#"{"final_answer":"called"}"#);
+        mock.assert_async().await;
+
+        let response = client
+            .with_function_calling(false)
+            .chat_structured("# Phase: FINAL", &schema)
+            .await
+            .unwrap();
+        assert_eq!(response, r#"{"final_answer":"text"}"#);
+        text_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn openai_client_sends_configured_params() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/chat/completions")
+                    .json_body_partial(
+                        r#"{"temperature":0.0,"max_tokens":256,"messages":[{"role":"system","content":"Answer tersely."}]}"#,
+                    );
+                then.status(200)
+                    .header("content-type", "application/json")
+                    .body(r#"{"choices":[{"message":{"content":"plain"}}]}"#);
+            })
+            .await;
+        let client = OpenAiClient::new(
+            "test-key".to_string(),
+            "gpt-test",
+            Some(server.base_url()),
+            None,
+        )
+        .unwrap()
+        .with_params(OpenAiParams {
+            temperature: Some(0.0),
+            max_tokens: Some(256),
+            system_prompt: Some("Answer tersely.".to_string()),
+            response_format: Some(OpenAiResponseFormat::Text),
+        });
+
+        assert_eq!(client.chat("# Phase: THINK").await.unwrap(), "plain");
+        mock.assert_async().await;
+    }
+
     #[test]
     fn openai_client_requires_env_key() {
         let var = "HI_TEST_OPENAI_KEY";
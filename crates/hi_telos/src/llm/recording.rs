@@ -0,0 +1,199 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{LlmClient, LlmIdentity, ResponseSchema};
+use crate::{config::LlmRecordingMode, storage};
+
+/// One saved provider response, stored as `<prompt_hash>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmRecording {
+    pub prompt_hash: String,
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub response: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Key a recording is stored under: the hex SHA-256 of the prompt.
+pub fn prompt_hash(prompt: &str) -> String {
+    hex::encode(Sha256::digest(prompt.as_bytes()))
+}
+
+fn recording_path(dir: &Path, prompt: &str) -> PathBuf {
+    dir.join(format!("{}.json", prompt_hash(prompt)))
+}
+
+/// Passes every call through to `inner` and saves the response, so a later
+/// [`ReplayLlmClient`] can serve it without the provider.
+pub struct RecordingLlmClient {
+    inner: Arc<dyn LlmClient>,
+    dir: PathBuf,
+}
+
+impl RecordingLlmClient {
+    pub fn new(inner: Arc<dyn LlmClient>, dir: PathBuf) -> Self {
+        Self { inner, dir }
+    }
+
+    /// Save `response` as the recording for `prompt` and pass it on.
+    async fn record(&self, prompt: &str, response: String) -> anyhow::Result<String> {
+        let identity = self.inner.identity();
+        let recording = LlmRecording {
+            prompt_hash: prompt_hash(prompt),
+            provider: identity.provider.to_string(),
+            model: identity.model,
+            prompt: prompt.to_string(),
+            response: response.clone(),
+            recorded_at: Utc::now(),
+        };
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("creating llm recording dir {:?}", self.dir))?;
+        let path = recording_path(&self.dir, prompt);
+        storage::write_atomic_async(&path, serde_json::to_vec_pretty(&recording)?)
+            .await
+            .with_context(|| format!("writing llm recording {:?}", path))?;
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmClient for RecordingLlmClient {
+    async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+        let response = self.inner.chat(prompt).await?;
+        self.record(prompt, response).await
+    }
+
+    async fn chat_structured(
+        &self,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> anyhow::Result<String> {
+        let response = self.inner.chat_structured(prompt, schema).await?;
+        self.record(prompt, response).await
+    }
+
+    async fn list_models(&self) -> anyhow::Result<Option<Vec<String>>> {
+        self.inner.list_models().await
+    }
+
+    fn identity(&self) -> LlmIdentity {
+        self.inner.identity()
+    }
+}
+
+/// Answers from recordings only, for deterministic offline runs. A prompt
+/// that was never recorded is an error rather than a provider call.
+pub struct ReplayLlmClient {
+    dir: PathBuf,
+    identity: LlmIdentity,
+}
+
+impl ReplayLlmClient {
+    pub fn new(dir: PathBuf, identity: LlmIdentity) -> Self {
+        Self { dir, identity }
+    }
+}
+
+#[async_trait]
+impl LlmClient for ReplayLlmClient {
+    async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+        let path = recording_path(&self.dir, prompt);
+        let raw = match tokio::fs::read_to_string(&path).await {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => bail!(
+                "no llm recording for prompt {} in {:?}; run once with mode: record",
+                prompt_hash(prompt),
+                self.dir
+            ),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading llm recording {:?}", path));
+            }
+        };
+        let recording: LlmRecording = serde_json::from_str(&raw)
+            .with_context(|| format!("parsing llm recording {:?}", path))?;
+        Ok(recording.response)
+    }
+
+    fn identity(&self) -> LlmIdentity {
+        self.identity.clone()
+    }
+}
+
+/// Applies a [`LlmRecordingMode`] to the clients the agent builds.
+#[derive(Debug, Clone)]
+pub struct LlmRecorder {
+    pub mode: LlmRecordingMode,
+    pub dir: PathBuf,
+}
+
+impl LlmRecorder {
+    /// The client to use for a provider with `identity`. `build` is only
+    /// called when the provider is actually contacted, so replaying needs
+    /// no credentials.
+    pub fn wrap(
+        &self,
+        identity: LlmIdentity,
+        build: impl FnOnce() -> anyhow::Result<Arc<dyn LlmClient>>,
+    ) -> anyhow::Result<Arc<dyn LlmClient>> {
+        Ok(match self.mode {
+            LlmRecordingMode::Off => build()?,
+            LlmRecordingMode::Record => {
+                Arc::new(RecordingLlmClient::new(build()?, self.dir.clone()))
+            }
+            LlmRecordingMode::Replay => Arc::new(ReplayLlmClient::new(self.dir.clone(), identity)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LocalStubClient;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn replays_recorded_responses_by_prompt() {
+        let temp = tempdir().unwrap();
+        let recorder = |mode| LlmRecorder {
+            mode,
+            dir: temp.path().join("recordings"),
+        };
+        let identity = LocalStubClient.identity();
+        let prompt = "# Phase: FINAL\nIntent: Ship it\nPersona: TelosOps\n";
+
+        let recording = recorder(LlmRecordingMode::Record)
+            .wrap(identity.clone(), || Ok(Arc::new(LocalStubClient)))
+            .unwrap();
+        let live = recording.chat(prompt).await.unwrap();
+
+        let replay = recorder(LlmRecordingMode::Replay)
+            .wrap(identity, || panic!("replay must not build the provider"))
+            .unwrap();
+        assert_eq!(replay.chat(prompt).await.unwrap(), live);
+        assert_eq!(replay.identity().provider, "local_stub");
+
+        let err = replay
+            .chat("# Phase: FINAL\nIntent: Other\n")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no llm recording"));
+
+        let saved: LlmRecording = serde_json::from_str(
+            &std::fs::read_to_string(recording_path(&temp.path().join("recordings"), prompt))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(saved.prompt, prompt);
+        assert_eq!(saved.model.as_deref(), Some("local_stub"));
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::Path;
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use super::{LlmClient, LlmIdentity, extract_value};
+
+/// Canned replies for [`ScriptedLlmClient`], usually loaded from YAML:
+///
+/// ```yaml
+/// model: scripted-v1
+/// responses:
+///   - phase: THINK
+///     step: 1
+///     response: { thought: "...", action: "search", observation: "..." }
+///   - phase: FINAL
+///     intent: weekly report
+///     response: { final_answer: "..." }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LlmScript {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub responses: Vec<ScriptedResponse>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedResponse {
+    /// `THINK`, `FINAL` or `SUMMARY` (history and document summaries),
+    /// matched against the prompt's `# Phase:` line.
+    pub phase: String,
+    /// 1-based THINK step; any step when omitted.
+    #[serde(default)]
+    pub step: Option<usize>,
+    /// Substring of the prompt's `Intent:` line; any intent when omitted.
+    #[serde(default)]
+    pub intent: Option<String>,
+    /// Maps and lists are sent as JSON, strings verbatim, so a script can
+    /// also feed the agent malformed output.
+    pub response: serde_yaml::Value,
+    /// Keep answering with this entry instead of consuming it on first use.
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl ScriptedResponse {
+    fn matches(&self, phase: &str, step: Option<usize>, intent: &str) -> bool {
+        self.phase.eq_ignore_ascii_case(phase)
+            && self.step.is_none_or(|wanted| step == Some(wanted))
+            && self
+                .intent
+                .as_deref()
+                .is_none_or(|wanted| intent.contains(wanted))
+    }
+
+    fn render(&self) -> anyhow::Result<String> {
+        match &self.response {
+            serde_yaml::Value::String(raw) => Ok(raw.clone()),
+            value => serde_json::to_string(value).context("encoding scripted response"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ScriptState {
+    used: Vec<bool>,
+    prompts: Vec<String>,
+}
+
+/// Mock LLM that answers each prompt with the first unused scripted reply
+/// matching its phase, step and intent, for deterministic tests of agent
+/// flows. A prompt without a match is an error, so a scenario fails loudly
+/// when the agent takes a turn the script did not plan for.
+#[derive(Debug)]
+pub struct ScriptedLlmClient {
+    script: LlmScript,
+    state: Mutex<ScriptState>,
+}
+
+impl ScriptedLlmClient {
+    pub fn new(script: LlmScript) -> Self {
+        let state = ScriptState {
+            used: vec![false; script.responses.len()],
+            prompts: Vec::new(),
+        };
+        Self {
+            script,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn from_yaml(yaml: &str) -> anyhow::Result<Self> {
+        let script = serde_yaml::from_str(yaml).context("parsing llm script")?;
+        Ok(Self::new(script))
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("reading llm script {:?}", path))?;
+        Self::from_yaml(&yaml).with_context(|| format!("loading llm script {:?}", path))
+    }
+
+    /// Every prompt received so far, in order.
+    pub fn prompts(&self) -> Vec<String> {
+        self.state.lock().prompts.clone()
+    }
+
+    /// Indexes of one-shot responses that were never used.
+    pub fn unused(&self) -> Vec<usize> {
+        let state = self.state.lock();
+        self.script
+            .responses
+            .iter()
+            .enumerate()
+            .filter(|(index, response)| !response.repeat && !state.used[*index])
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LlmClient for ScriptedLlmClient {
+    async fn chat(&self, prompt: &str) -> anyhow::Result<String> {
+        let phase = extract_value(prompt, "# Phase:").unwrap_or_default();
+        let step = extract_value(prompt, "Step:").and_then(|value| value.parse().ok());
+        let intent = extract_value(prompt, "Intent:").unwrap_or_default();
+
+        let mut state = self.state.lock();
+        state.prompts.push(prompt.to_string());
+        let found = self
+            .script
+            .responses
+            .iter()
+            .enumerate()
+            .find(|(index, response)| {
+                (response.repeat || !state.used[*index]) && response.matches(&phase, step, &intent)
+            });
+        let Some((index, response)) = found else {
+            bail!(
+                "scripted LLM has no response for phase {phase:?} step {step:?} intent {intent:?}"
+            );
+        };
+        state.used[index] = true;
+        response.render()
+    }
+
+    fn identity(&self) -> LlmIdentity {
+        LlmIdentity::new("scripted", self.script.model.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+model: scripted-v1
+responses:
+  - phase: THINK
+    step: 1
+    intent: report
+    response: { thought: "t1", action: "search", observation: "o1" }
+  - phase: THINK
+    response: "not json"
+    repeat: true
+  - phase: FINAL
+    response: { final_answer: "done" }
+"#;
+
+    fn prompt(phase: &str, step: usize, intent: &str) -> String {
+        format!("# Phase: {phase}\nIntent: {intent}\nStep: {step}\n")
+    }
+
+    #[tokio::test]
+    async fn replies_in_script_order_by_phase_step_and_intent() {
+        let client = ScriptedLlmClient::from_yaml(SCRIPT).unwrap();
+        assert_eq!(client.identity().model.as_deref(), Some("scripted-v1"));
+
+        let first = client
+            .chat(&prompt("THINK", 1, "Draft weekly report"))
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(value["action"], "search");
+
+        // The step-1 entry is spent; the repeating fallback answers after it.
+        for _ in 0..2 {
+            let raw = client
+                .chat(&prompt("THINK", 1, "Draft weekly report"))
+                .await
+                .unwrap();
+            assert_eq!(raw, "not json");
+        }
+        assert_eq!(client.unused(), vec![2]);
+
+        // A summary is its own phase and leaves the FINAL answer in place.
+        assert!(client.chat(&prompt("SUMMARY", 1, "x")).await.is_err());
+        assert_eq!(client.unused(), vec![2]);
+
+        let final_raw = client.chat(&prompt("FINAL", 1, "x")).await.unwrap();
+        assert_eq!(final_raw, r#"{"final_answer":"done"}"#);
+        assert!(client.chat(&prompt("FINAL", 1, "x")).await.is_err());
+        assert!(client.unused().is_empty());
+        assert_eq!(client.prompts().len(), 6);
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::timeout;
+
+use super::LlmClient;
+
+/// How long [`check_llm`] waits for the provider.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of probing the configured LLM provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct LlmStatus {
+    pub provider: String,
+    pub model: Option<String>,
+    /// Reachable, and the model is listed when the provider lists models.
+    pub ok: bool,
+    /// Whether the provider lists `model`; `None` when it cannot list models.
+    pub model_available: Option<bool>,
+    /// Model ids the provider serves, sorted.
+    pub models: Vec<String>,
+    /// Round trip of the model listing; `None` when it failed.
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Ask `client`'s provider for its models, catching bad keys, unreachable
+/// endpoints and unknown model names before the first beat does.
+pub async fn check_llm(client: &dyn LlmClient, checked_at: DateTime<Utc>) -> LlmStatus {
+    let identity = client.identity();
+    let mut status = LlmStatus {
+        provider: identity.provider.to_string(),
+        model: identity.model,
+        ok: false,
+        model_available: None,
+        models: Vec::new(),
+        latency_ms: None,
+        error: None,
+        checked_at,
+    };
+
+    let started = Instant::now();
+    let listed = match timeout(STATUS_TIMEOUT, client.list_models()).await {
+        Ok(listed) => listed,
+        Err(_) => Err(anyhow::anyhow!(
+            "no answer within {}s",
+            STATUS_TIMEOUT.as_secs()
+        )),
+    };
+    match listed {
+        Ok(listed) => {
+            status.latency_ms = Some(started.elapsed().as_millis() as u64);
+            if let Some(mut models) = listed {
+                models.sort();
+                status.model_available = Some(
+                    status
+                        .model
+                        .as_ref()
+                        .is_some_and(|model| models.contains(model)),
+                );
+                status.models = models;
+            }
+            status.ok = status.model_available != Some(false);
+            if !status.ok {
+                status.error = Some(format!(
+                    "model {} is not served by {}",
+                    status.model.as_deref().unwrap_or("(none)"),
+                    status.provider
+                ));
+            }
+        }
+        Err(err) => status.error = Some(format!("{err:#}")),
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LocalStubClient, OpenAiClient};
+    use httpmock::prelude::*;
+
+    #[tokio::test]
+    async fn reports_reachability_and_model_availability() {
+        let stub = check_llm(&LocalStubClient, Utc::now()).await;
+        assert!(stub.ok);
+        assert_eq!(stub.provider, "local_stub");
+        assert_eq!(stub.model_available, None);
+
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/models")
+                    .header("authorization", "Bearer good-key");
+                then.status(200)
+                    .json_body(serde_json::json!({"object": "list", "data": [
+                        {"id": "gpt-4o-mini", "object": "model"},
+                        {"id": "gpt-4o", "object": "model"},
+                    ]}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/models")
+                    .header("authorization", "Bearer bad-key");
+                then.status(401)
+                    .json_body(serde_json::json!({"error": {"message": "Incorrect API key"}}));
+            })
+            .await;
+        let client = |key: &str, model: &str| {
+            OpenAiClient::new(key.to_string(), model, Some(server.base_url()), None).unwrap()
+        };
+
+        let good = check_llm(&client("good-key", "gpt-4o"), Utc::now()).await;
+        assert!(good.ok, "{:?}", good.error);
+        assert_eq!(good.model_available, Some(true));
+        assert_eq!(good.models, vec!["gpt-4o", "gpt-4o-mini"]);
+        assert!(good.latency_ms.is_some());
+
+        let typo = check_llm(&client("good-key", "gpt-4-o"), Utc::now()).await;
+        assert!(!typo.ok);
+        assert_eq!(typo.model_available, Some(false));
+        assert_eq!(
+            typo.error.as_deref(),
+            Some("model gpt-4-o is not served by openai")
+        );
+
+        let bad_key = check_llm(&client("bad-key", "gpt-4o"), Utc::now()).await;
+        assert!(!bad_key.ok);
+        assert_eq!(bad_key.latency_ms, None);
+        assert!(bad_key.error.unwrap().contains("401"));
+    }
+}
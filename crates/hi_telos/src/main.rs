@@ -2,36 +2,317 @@ use std::sync::Arc;
 
 use hi_telos::{
     agent::AgentRuntime,
-    config, orchestrator,
+    config::{self, Role},
+    doctor, lifecycle, maintenance, notifications,
+    object_store::{self, ObjectSync},
+    orchestrator::{self, OrchestratorHandle},
+    outbox,
     server::{self, ServerState},
     state::AppContext,
+    storage, tools, watchdog, webhooks,
 };
-use tracing::error;
+use tokio::{net::TcpListener, task::JoinHandle};
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     config::init_tracing();
-    let config = config::AppConfig::load()?;
-    let agent_runtime = AgentRuntime::from_app_config(&config)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("doctor") {
+        return run_doctor(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("check-config") {
+        return run_check_config(&args[1..]);
+    }
+    let role = parse_role(&args)?;
+    let mut config = config::AppConfig::load()?;
+    if let Some(role) = role {
+        config.server.role = role;
+    }
+    let role = config.server.role;
+    info!(role = role.as_str(), "starting");
+    let pid_path = config.pid_file();
+    let named = config.load_workspaces()?;
+    let mut workspaces = vec![start_workspace(config).await?];
+    for (name, config) in named {
+        info!(workspace = %name, data_dir = ?config.data_dir, "starting workspace");
+        workspaces.push(start_workspace(config).await?);
+    }
+    let pid_file = lifecycle::PidFile::create(&pid_path)?;
+    info!(path = ?pid_file.path(), "wrote pid file");
+
+    let ctx = workspaces[0].ctx.clone();
+    let mut server_task = None;
+    if role.runs_server() {
+        let named_states = workspaces[1..]
+            .iter()
+            .map(|workspace| {
+                let state = ServerState::new(workspace.ctx.clone(), workspace.orchestrator.clone());
+                (workspace.name.clone(), state)
+            })
+            .collect();
+        let server_state = ServerState::new(ctx.clone(), workspaces[0].orchestrator.clone())
+            .with_workspaces(named_states);
+        let listener = TcpListener::bind(ctx.config().server.addr()).await?;
+        server_task = Some(tokio::spawn(async move {
+            if let Err(err) = server::serve_with_listener(listener, server_state).await {
+                error!(error = ?err, "server error");
+            }
+        }));
+    }
+    if let Err(err) = lifecycle::sd_notify("READY=1") {
+        warn!(error = ?err, "failed to notify systemd of readiness");
+    }
+
+    let signal = lifecycle::shutdown_signal().await?;
+    info!(signal, "shutting down");
+    if let Err(err) = lifecycle::sd_notify("STOPPING=1") {
+        warn!(error = ?err, "failed to notify systemd of shutdown");
+    }
+    for workspace in &workspaces {
+        workspace.ctx.request_shutdown();
+    }
+
+    if let Some(task) = server_task
+        && let Err(err) = task.await
+    {
+        error!(error = ?err, task = "server", "task join error");
+    }
+    for workspace in workspaces {
+        workspace.stop().await;
+    }
+
+    drop(pid_file);
+    Ok(())
+}
+
+/// Everything one workspace runs besides the shared HTTP server.
+struct Workspace {
+    name: String,
+    ctx: AppContext,
+    orchestrator: OrchestratorHandle,
+    // Joined in this order on shutdown.
+    tasks: Vec<(&'static str, JoinHandle<()>)>,
+    object_sync: Option<Arc<ObjectSync>>,
+    data_lock: Option<storage::DataDirLock>,
+}
+
+/// Start the orchestrator, scheduled jobs and workers for one workspace, as far as
+/// its role asks for them.
+async fn start_workspace(config: config::AppConfig) -> anyhow::Result<Workspace> {
+    let name = config
+        .workspace
+        .clone()
+        .unwrap_or_else(|| "default".to_string());
+    let role = config.server.role;
+    // Only one orchestrator may work a data dir; `server` processes share it.
+    let data_lock = if role.runs_worker() {
+        let lock = storage::DataDirLock::acquire(&config.data_dir)?;
+        info!(workspace = %name, lock = ?lock.path(), "locked data dir");
+        Some(lock)
+    } else {
+        None
+    };
+    let object_sync = if role.runs_worker() {
+        ObjectSync::from_config(&config)?
+    } else {
+        None
+    };
+    if let Some(sync) = &object_sync
+        && sync.restore_on_start()
+    {
+        let report = sync.restore().await?;
+        info!(
+            workspace = %name,
+            restored = report.restored,
+            failed = report.failed,
+            "restored data dir from object storage"
+        );
+    }
+    if role.runs_worker() {
+        let migration = storage::migrate_data_dir(&config.data_dir)?;
+        if migration.from != migration.to {
+            info!(
+                workspace = %name,
+                from = migration.from,
+                to = migration.to,
+                files = migration.files_upgraded,
+                "migrated data dir schema"
+            );
+        }
+    }
+    let mut agent_tools = tools::ToolRegistry::from_config(&config)?;
+    tools::connect_mcp_servers(&config, &mut agent_tools).await;
+    let agent_runtime = AgentRuntime::from_app_config(&config)?.with_tools(agent_tools);
     let ctx = AppContext::new(config, Arc::new(agent_runtime));
+    tokio::spawn(log_llm_status(name.clone(), ctx.agent()));
+
+    let mut tasks: Vec<(&'static str, JoinHandle<()>)> = Vec::new();
+    let orchestrator_handle = if role.runs_worker() {
+        let (handle, task) = orchestrator::spawn(ctx.clone());
+        tasks.push(("orchestrator", task));
+        handle
+    } else {
+        OrchestratorHandle::remote(&ctx)
+    };
+
+    let scheduler = orchestrator::Scheduler::from_config(ctx.clone(), &orchestrator_handle, role)?;
+    tasks.extend(scheduler.spawn().map(|task| ("scheduler", task)));
+    if role.runs_worker() {
+        let workers = [
+            (
+                "webhook dispatcher",
+                webhooks::spawn_dispatcher(ctx.clone()),
+            ),
+            ("outbox worker", Some(outbox::spawn_worker(ctx.clone()))),
+            (
+                "notification router",
+                Some(notifications::spawn_router(ctx.clone())),
+            ),
+            ("beat watchdog", Some(watchdog::spawn(ctx.clone()))),
+        ];
+        tasks.extend(
+            workers
+                .into_iter()
+                .filter_map(|(name, task)| Some((name, task?))),
+        );
+    }
+    tasks.push(("disk guard", maintenance::spawn_disk_guard(ctx.clone())));
+    tasks.push(("config watcher", config::spawn_watcher(ctx.clone())));
+    if let Some(sync) = object_sync.clone() {
+        tasks.push((
+            "object storage sync",
+            object_store::spawn_sync(ctx.clone(), sync),
+        ));
+    }
 
-    let (orchestrator_handle, orchestrator_task) = orchestrator::spawn(ctx.clone());
+    Ok(Workspace {
+        name,
+        ctx,
+        orchestrator: orchestrator_handle,
+        tasks,
+        object_sync,
+        data_lock,
+    })
+}
+
+impl Workspace {
+    /// Join the tasks after shutdown was requested, push the data dir one
+    /// last time and release its lock.
+    async fn stop(self) {
+        for (task, handle) in self.tasks {
+            if let Err(err) = handle.await {
+                error!(error = ?err, workspace = %self.name, task, "task join error");
+            }
+        }
 
-    let server_state = ServerState::new(ctx.clone(), orchestrator_handle.clone());
-    let server_task = tokio::spawn(async move {
-        if let Err(err) = server::serve(server_state).await {
-            error!(error = ?err, "server error");
+        // Push whatever the stopped tasks wrote last.
+        if let Some(sync) = self.object_sync
+            && let Err(err) = sync.push().await
+        {
+            error!(error = ?err, workspace = %self.name, "final object storage sync failed");
         }
-    });
+        drop(self.data_lock);
+    }
+}
 
-    tokio::signal::ctrl_c().await?;
-    ctx.request_shutdown();
+/// Probe the LLM provider once at startup so a bad key or model name shows
+/// up in the log rather than at the first beat.
+async fn log_llm_status(workspace: String, agent: Arc<AgentRuntime>) {
+    let status = agent.llm_status().await;
+    if status.ok {
+        info!(
+            %workspace,
+            provider = %status.provider,
+            model = ?status.model,
+            latency_ms = ?status.latency_ms,
+            "llm provider reachable"
+        );
+    } else {
+        warn!(
+            %workspace,
+            provider = %status.provider,
+            model = ?status.model,
+            error = ?status.error,
+            "llm provider check failed; see /api/llm/status"
+        );
+    }
+}
 
-    let _ = server_task.await;
+/// `--role server|worker|all` (or `--role=...`), overriding `HI_ROLE`.
+fn parse_role(args: &[String]) -> anyhow::Result<Option<Role>> {
+    let value = match args {
+        [] => return Ok(None),
+        [flag, value] if flag == "--role" => value.as_str(),
+        [flag] if flag.starts_with("--role=") => &flag["--role=".len()..],
+        _ => anyhow::bail!("usage: hi_telos [--role server|worker|all]"),
+    };
+    value.parse().map(Some)
+}
 
-    if let Err(err) = orchestrator_task.await {
-        error!(error = ?err, "orchestrator task join error");
+/// `hi_telos doctor [--repair]`: check the data dir under `HI_APP_ROOT` and
+/// exit non-zero while problems remain. Repairing takes the data dir lock,
+/// so it refuses to run next to a live instance.
+fn run_doctor(args: &[String]) -> anyhow::Result<()> {
+    let repair = match args {
+        [] => false,
+        [flag] if flag == "--repair" => true,
+        _ => anyhow::bail!("usage: hi_telos doctor [--repair]"),
+    };
+    let data_dir = config::app_root()?.join("data");
+    let _lock = if repair {
+        Some(storage::DataDirLock::acquire(&data_dir)?)
+    } else {
+        None
+    };
+
+    let report = doctor::run(&data_dir, repair, chrono::Utc::now())?;
+    for issue in &report.issues {
+        let status = if issue.repaired { " (repaired)" } else { "" };
+        println!(
+            "{:?}: {} — {}{status}",
+            issue.kind, issue.path, issue.detail
+        );
+    }
+    println!(
+        "checked {} files in {:?}: {} issue(s), {} unresolved",
+        report.files_checked,
+        data_dir,
+        report.issues.len(),
+        report.unresolved()
+    );
+    if report.unresolved() > 0 {
+        anyhow::bail!("data dir has problems; rerun with --repair to quarantine them");
     }
+    Ok(())
+}
 
+/// `hi_telos check-config`: load `config/` under `HI_APP_ROOT` with the
+/// current env overrides, print what looks wrong and exit non-zero on
+/// errors. Starts nothing and touches no files.
+fn run_check_config(args: &[String]) -> anyhow::Result<()> {
+    if !args.is_empty() {
+        anyhow::bail!("usage: hi_telos check-config");
+    }
+    let root = config::app_root()?;
+    let vars = std::env::vars().collect();
+    let report = config::check_config(&root, &vars);
+    for finding in &report.findings {
+        let mark = match finding.level {
+            config::CheckLevel::Ok => "✔",
+            config::CheckLevel::Warning => "!",
+            config::CheckLevel::Error => "✘",
+        };
+        println!("{mark} {}: {}", finding.section, finding.message);
+    }
+    println!(
+        "checked {:?}: {} error(s), {} warning(s)",
+        root.join("config"),
+        report.errors(),
+        report.warnings()
+    );
+    if report.errors() > 0 {
+        anyhow::bail!("config has errors");
+    }
     Ok(())
 }
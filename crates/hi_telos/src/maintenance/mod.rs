@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::{select, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+
+use crate::{
+    config::AppConfig,
+    orchestrator::{Job, JobSchedule},
+    state::AppContext,
+    storage,
+};
+
+/// Enforces `config/retention.yml`, by default at startup and every
+/// `interval_minutes` after that. Off when no category has a rule.
+#[derive(Debug, Default)]
+pub struct RetentionJob;
+
+#[async_trait]
+impl Job for RetentionJob {
+    fn name(&self) -> &'static str {
+        "retention"
+    }
+
+    fn default_schedule(&self, config: &AppConfig) -> Option<JobSchedule> {
+        if config.retention.policy.is_empty() {
+            return None;
+        }
+        let minutes = config.retention.interval_minutes.max(1);
+        Some(JobSchedule::Every(Duration::from_secs(minutes * 60)))
+    }
+
+    async fn run(&self, ctx: &AppContext) -> anyhow::Result<()> {
+        let config = ctx.config();
+        let policy = config.retention.policy.clone();
+        let data_dir = config.data_dir.clone();
+        drop(config);
+        let now = ctx.now();
+        let report = tokio::task::spawn_blocking(move || {
+            storage::apply_retention(&data_dir, &policy, now, false)
+        })
+        .await??;
+        if report.pruned_files() > 0 {
+            info!(
+                files = report.pruned_files(),
+                bytes = report.pruned_bytes(),
+                "applied data retention"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Compacts or deletes L1 memory past `memory.retention`'s age once its L2
+/// rollup exists, by default at startup and every beat interval after that.
+/// Off without a policy.
+#[derive(Debug, Default)]
+pub struct MemoryRetentionJob;
+
+#[async_trait]
+impl Job for MemoryRetentionJob {
+    fn name(&self) -> &'static str {
+        "memory_retention"
+    }
+
+    fn default_schedule(&self, config: &AppConfig) -> Option<JobSchedule> {
+        config.memory.retention.as_ref()?;
+        let minutes = config.beat.interval_minutes.max(1);
+        Some(JobSchedule::Every(Duration::from_secs(minutes * 60)))
+    }
+
+    async fn run(&self, ctx: &AppContext) -> anyhow::Result<()> {
+        let config = ctx.config();
+        let Some(policy) = config.memory.retention.clone() else {
+            return Ok(());
+        };
+        let data_dir = config.data_dir.clone();
+        drop(config);
+        let report = storage::apply_memory_retention(&data_dir, &policy, ctx.now()).await?;
+        if report != storage::MemoryRetentionReport::default() {
+            info!(
+                compacted = report.days_compacted,
+                deleted = report.days_deleted,
+                skipped = report.days_skipped_without_rollup,
+                stale = report.days_skipped_stale_rollup,
+                "memory retention applied"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Check the data dir's disk at startup and every `disk.check_interval_secs`
+/// after that, pausing logs and history while less than `disk.min_free_mb`
+/// is free. Limits are read per check, so config reloads apply.
+pub fn spawn_disk_guard(ctx: AppContext) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let config = ctx.config();
+            let data_dir = config.data_dir.clone();
+            let disk = config.disk.clone();
+            drop(config);
+
+            let dir = data_dir.clone();
+            match tokio::task::spawn_blocking(move || storage::disk_space(&dir)).await {
+                Ok(Ok(space)) => {
+                    // A `min_free_mb` of 0 never counts as low.
+                    let low = space.available_bytes < disk.min_free_bytes();
+                    if ctx.set_writes_paused(low) {
+                        if low {
+                            warn!(
+                                available_mb = space.available_bytes / (1024 * 1024),
+                                min_free_mb = disk.min_free_mb,
+                                "disk space low; pausing logs and history"
+                            );
+                        } else {
+                            info!(
+                                available_mb = space.available_bytes / (1024 * 1024),
+                                "disk space recovered; resuming logs and history"
+                            );
+                        }
+                    }
+                }
+                Ok(Err(err)) => warn!(error = ?err, "failed to check free disk space"),
+                Err(err) => warn!(error = ?err, "disk guard task join failure"),
+            }
+
+            select! {
+                _ = sleep(Duration::from_secs(disk.check_interval_secs.max(1))) => {}
+                _ = ctx.wait_for_shutdown() => break,
+            }
+        }
+    })
+}
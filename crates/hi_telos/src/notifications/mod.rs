@@ -0,0 +1,544 @@
+use std::{env, path::Path, time::Duration};
+
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde_json::json;
+use tokio::{select, sync::broadcast::error::RecvError, task::JoinHandle};
+use tracing::warn;
+
+use crate::{
+    config::{
+        NotificationChannel, NotificationRule, NotificationTarget, QuietHours, TelegramConfig,
+    },
+    events::{IntentEvent, IntentEventKind},
+    outbox::{self, Channels},
+    state::AppContext,
+    storage::{OutboxMessage, OutboxTarget, WebhookDelivery},
+    text,
+    watchdog::BeatAlert,
+    webhooks::{self, WebhookEvent},
+};
+
+/// Tags live in intent metadata as a comma-separated list.
+pub const TAGS_KEY: &str = "tags";
+
+const NOTIFICATION_MAX_CHARS: usize = 1_000;
+
+/// Bound on one Slack or ntfy request, so a hung server cannot hold up the
+/// outbox.
+const NOTIFY_TIMEOUT_SECS: u64 = 10;
+
+/// ntfy priorities run from 1 (min) to 5 (urgent).
+const NTFY_PRIORITY_DEFAULT: u8 = 3;
+const NTFY_PRIORITY_HIGH: u8 = 4;
+const NTFY_PRIORITY_URGENT: u8 = 5;
+
+/// Route intent lifecycle events to the channels of every matching rule in
+/// `config/notifications.yml`. Rules are read per event, so edits picked up
+/// by config hot reload apply to the next event.
+pub fn spawn_router(ctx: AppContext) -> JoinHandle<()> {
+    let mut events = ctx.events().subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = select! {
+                received = events.recv() => match received {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "notification router lagged behind intent events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ctx.wait_for_shutdown() => break,
+            };
+
+            let config = ctx.config();
+            let data_dir = config.data_dir.clone();
+            let rules = config.notifications.rules.clone();
+            drop(config);
+            let channels = Channels::of(&ctx);
+
+            for rule in rules.iter().filter(|rule| rule_matches(rule, &event)) {
+                for target in &rule.channels {
+                    let sent =
+                        notify(&data_dir, &channels, target, &event, rule.urgent, ctx.now()).await;
+                    if let Err(err) = sent {
+                        warn!(rule = %rule.name, error = ?err, "notification failed");
+                    }
+                }
+            }
+        }
+    })
+}
+
+pub fn rule_matches(rule: &NotificationRule, event: &IntentEvent) -> bool {
+    let intent = &event.intent;
+    let event_ok = rule.events.is_empty()
+        || rule
+            .events
+            .iter()
+            .any(|kind| kind.eq_ignore_ascii_case(event.kind.as_str()));
+    let source_ok = rule.sources.is_empty()
+        || rule
+            .sources
+            .iter()
+            .any(|source| source.eq_ignore_ascii_case(&intent.source));
+    let tags_ok = rule.tags.is_empty()
+        || intent.metadata.get(TAGS_KEY).is_some_and(|tags| {
+            tags.split(',').map(str::trim).any(|tag| {
+                rule.tags
+                    .iter()
+                    .any(|wanted| wanted.eq_ignore_ascii_case(tag))
+            })
+        });
+    let alignment_ok = rule
+        .min_alignment
+        .is_none_or(|min| intent.telos_alignment >= min)
+        && rule
+            .max_alignment
+            .is_none_or(|max| intent.telos_alignment <= max);
+
+    event_ok && source_ok && tags_ok && alignment_ok
+}
+
+/// Queue one event for one channel in the outbox, which sends it right
+/// away unless the channel is in its quiet hours (`urgent` skips them).
+/// Webhooks get the event as signed JSON like `webhooks.yml`; `urgent` also
+/// raises the ntfy priority.
+pub async fn notify(
+    data_dir: &Path,
+    channels: &Channels,
+    target: &NotificationTarget,
+    event: &IntentEvent,
+    urgent: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let webhook = WebhookEvent::for_intent(event)?;
+    let priority = if urgent {
+        NTFY_PRIORITY_HIGH
+    } else {
+        NTFY_PRIORITY_DEFAULT
+    };
+    let message = outbox_message(
+        channels.telegram.as_ref(),
+        &target.channel,
+        &render(event),
+        priority,
+        webhook,
+        now,
+    )?;
+    queue(
+        data_dir,
+        channels,
+        message,
+        target.quiet_hours.as_ref(),
+        urgent,
+    )
+    .await
+}
+
+/// Queue a beat watchdog alert for one channel. Alerts are always urgent
+/// (and top priority on ntfy); webhooks receive the alert as JSON.
+pub async fn notify_beat_alert(
+    data_dir: &Path,
+    channels: &Channels,
+    channel: &NotificationChannel,
+    alert: &BeatAlert,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let webhook = WebhookEvent {
+        id: alert.id,
+        header: alert.kind.header().to_string(),
+        name: alert.kind.as_str().to_string(),
+        payload: serde_json::to_value(alert)?,
+    };
+    let message = outbox_message(
+        channels.telegram.as_ref(),
+        channel,
+        &alert.render(),
+        NTFY_PRIORITY_URGENT,
+        webhook,
+        now,
+    )?;
+    queue(data_dir, channels, message, None, true).await
+}
+
+fn outbox_message(
+    telegram: Option<&TelegramConfig>,
+    channel: &NotificationChannel,
+    text: &str,
+    ntfy_priority: u8,
+    webhook: WebhookEvent,
+    now: DateTime<Utc>,
+) -> anyhow::Result<OutboxMessage> {
+    let target = match channel {
+        NotificationChannel::Telegram { chat_id } => {
+            let telegram = telegram.ok_or_else(|| anyhow!("telegram not configured"))?;
+            let chat_id = chat_id
+                .or(telegram.default_chat_id)
+                .ok_or_else(|| anyhow!("no telegram chat_id for notification"))?;
+            return Ok(OutboxMessage::new("telegram", chat_id, text, now));
+        }
+        NotificationChannel::Slack { webhook_url_env } => OutboxTarget::Slack {
+            webhook_url_env: webhook_url_env.clone(),
+        },
+        NotificationChannel::Webhook(target) => webhook.into_target(target.clone()),
+        NotificationChannel::Ntfy {
+            server,
+            topic,
+            token_env,
+        } => OutboxTarget::Ntfy {
+            server: server.clone(),
+            topic: topic.clone(),
+            token_env: token_env.clone(),
+            priority: ntfy_priority,
+        },
+    };
+    Ok(OutboxMessage::for_target(target, text, now))
+}
+
+async fn queue(
+    data_dir: &Path,
+    channels: &Channels,
+    mut message: OutboxMessage,
+    quiet_hours: Option<&QuietHours>,
+    urgent: bool,
+) -> anyhow::Result<()> {
+    message.urgent = urgent;
+    message.quiet_hours = quiet_hours.cloned();
+    let now = message.created_at;
+    outbox::deliver_or_queue(data_dir, channels, message, now).await?;
+    Ok(())
+}
+
+/// Deliver a notification the outbox holds for a channel other than
+/// Telegram; `attempt` and `now` go into the webhook delivery log, which is
+/// only written when `log` is set.
+pub async fn send_to_target(
+    data_dir: &Path,
+    target: &OutboxTarget,
+    text: &str,
+    attempt: u32,
+    log: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(NOTIFY_TIMEOUT_SECS))
+        .build()
+        .context("building notification client")?;
+    match target {
+        OutboxTarget::Slack { webhook_url_env } => send_slack(&client, webhook_url_env, text).await,
+        OutboxTarget::Ntfy {
+            server,
+            topic,
+            token_env,
+            priority,
+        } => {
+            let ntfy = Ntfy {
+                server,
+                topic,
+                token_env: token_env.as_deref(),
+            };
+            send_ntfy(&client, ntfy, text, *priority).await
+        }
+        OutboxTarget::Webhook {
+            webhook,
+            event_id,
+            header,
+            event,
+            payload,
+        } => {
+            let event = WebhookEvent {
+                id: *event_id,
+                header: header.clone(),
+                name: event.clone(),
+                payload: payload.clone(),
+            };
+            let client = webhooks::client()?;
+            let log_dir = log.then_some(data_dir);
+            let delivery = webhooks::deliver(&client, log_dir, webhook, &event, attempt, now).await;
+            delivered(delivery)
+        }
+    }
+}
+
+async fn send_slack(client: &Client, webhook_url_env: &str, text: &str) -> anyhow::Result<()> {
+    let url = env::var(webhook_url_env).with_context(|| format!("reading {webhook_url_env}"))?;
+    let response = client
+        .post(url)
+        .json(&json!({ "text": text }))
+        .send()
+        .await
+        .with_context(|| "posting slack notification")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("slack returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+/// An ntfy channel's settings, borrowed from the config.
+struct Ntfy<'a> {
+    server: &'a str,
+    topic: &'a str,
+    token_env: Option<&'a str>,
+}
+
+/// Publishes as JSON to the server root, which unlike the header-based API
+/// carries non-ASCII titles. The first paragraph of `text` is the title.
+async fn send_ntfy(
+    client: &Client,
+    ntfy: Ntfy<'_>,
+    text: &str,
+    priority: u8,
+) -> anyhow::Result<()> {
+    let (title, message) = match text.split_once("\n\n") {
+        Some((title, message)) => (title, message),
+        None => (text, text),
+    };
+    let mut request = client.post(ntfy.server.trim_end_matches('/')).json(&json!({
+        "topic": ntfy.topic,
+        "title": title,
+        "message": message,
+        "priority": priority,
+    }));
+    if let Some(token_env) = ntfy.token_env {
+        let token = env::var(token_env).with_context(|| format!("reading {token_env}"))?;
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| "posting ntfy notification")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("ntfy returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+fn delivered(delivery: WebhookDelivery) -> anyhow::Result<()> {
+    if !delivery.delivered {
+        return Err(anyhow!(
+            "webhook delivery failed: {}",
+            delivery.error.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Plain-text notification body shared by chat channels.
+pub fn render(event: &IntentEvent) -> String {
+    let intent = &event.intent;
+    let headline = match event.kind {
+        IntentEventKind::Completed => format!("✅ Completed: {}", intent.summary),
+        IntentEventKind::Failed => format!("❌ Failed: {}", intent.summary),
+        IntentEventKind::Deferred => format!(
+            "⏸ Deferred ({:.2}): {}",
+            intent.telos_alignment, intent.summary
+        ),
+        IntentEventKind::PendingApproval => format!("🔐 Needs approval: {}", intent.summary),
+        IntentEventKind::DigestReady => format!("📰 Digest ready: {}", intent.summary),
+    };
+    let detail = event
+        .final_answer
+        .as_deref()
+        .or(event.error.as_deref())
+        .map(str::trim)
+        .filter(|detail| !detail.is_empty());
+
+    let text = match detail {
+        Some(detail) => format!("{headline}\n\n{detail}"),
+        None => headline,
+    };
+    text::truncate(&text, NOTIFICATION_MAX_CHARS)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use httpmock::prelude::*;
+    use serial_test::serial;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    use crate::{
+        storage::{self, OutboxStatus},
+        tasks::Intent,
+    };
+
+    fn event(kind: IntentEventKind, source: &str, alignment: f32, tags: &str) -> IntentEvent {
+        let intent = Intent {
+            id: Uuid::new_v4(),
+            source: source.to_string(),
+            summary: "Rotate keys".to_string(),
+            telos_alignment: alignment,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: BTreeMap::from([(TAGS_KEY.to_string(), tags.to_string())]),
+            storage_path: None,
+        };
+        let mut event = IntentEvent::new(kind, &intent, Utc::now());
+        event.error = Some("timeout".to_string());
+        event
+    }
+
+    fn rule(channels: Vec<NotificationTarget>) -> NotificationRule {
+        NotificationRule {
+            name: "ops".to_string(),
+            events: vec!["failed".to_string()],
+            sources: vec!["github".to_string()],
+            tags: vec!["ops".to_string()],
+            min_alignment: Some(0.5),
+            max_alignment: None,
+            urgent: false,
+            channels,
+        }
+    }
+
+    fn target(channel: NotificationChannel) -> NotificationTarget {
+        NotificationTarget {
+            channel,
+            quiet_hours: None,
+        }
+    }
+
+    #[test]
+    fn channels_take_their_own_quiet_hours() {
+        let rule: NotificationRule = serde_yaml::from_str(
+            "name: ops\nchannels:\n  - type: telegram\n  - type: slack\n    webhook_url_env: X\n    quiet_hours: {start: \"23:00\", end: \"08:00\"}\n",
+        )
+        .unwrap();
+        assert!(rule.channels[0].quiet_hours.is_none());
+        assert!(matches!(
+            rule.channels[1].channel,
+            NotificationChannel::Slack { .. }
+        ));
+        let quiet = rule.channels[1].quiet_hours.as_ref().unwrap();
+        assert_eq!(quiet.end.to_string(), "08:00:00");
+    }
+
+    #[test]
+    fn rules_filter_on_event_source_tags_and_alignment() {
+        let rule = rule(Vec::new());
+        assert!(rule_matches(
+            &rule,
+            &event(IntentEventKind::Failed, "github", 0.8, "infra, ops")
+        ));
+        assert!(!rule_matches(
+            &rule,
+            &event(IntentEventKind::Completed, "github", 0.8, "ops")
+        ));
+        assert!(!rule_matches(
+            &rule,
+            &event(IntentEventKind::Failed, "telegram", 0.8, "ops")
+        ));
+        assert!(!rule_matches(
+            &rule,
+            &event(IntentEventKind::Failed, "github", 0.8, "docs")
+        ));
+        assert!(!rule_matches(
+            &rule,
+            &event(IntentEventKind::Failed, "github", 0.2, "ops")
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn slack_channel_posts_rendered_text() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/slack")
+                    .json_body(json!({"text": "❌ Failed: Rotate keys\n\ntimeout"}));
+                then.status(200);
+            })
+            .await;
+        unsafe {
+            env::set_var("HI_TEST_SLACK_WEBHOOK", server.url("/slack"));
+        }
+
+        let temp = TempDir::new().unwrap();
+        let channel = target(NotificationChannel::Slack {
+            webhook_url_env: "HI_TEST_SLACK_WEBHOOK".to_string(),
+        });
+        notify(
+            temp.path(),
+            &Channels::default(),
+            &channel,
+            &event(IntentEventKind::Failed, "github", 0.8, "ops"),
+            false,
+            Utc::now(),
+        )
+        .await
+        .expect("slack notification");
+        mock.assert_async().await;
+        let queued = storage::list_outbox_messages(temp.path(), None, 10).unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].source, "slack");
+        assert_eq!(queued[0].status, OutboxStatus::Delivered);
+
+        let telegram = target(NotificationChannel::Telegram { chat_id: None });
+        assert!(
+            notify(
+                temp.path(),
+                &Channels::default(),
+                &telegram,
+                &event(IntentEventKind::Failed, "github", 0.8, "ops"),
+                false,
+                Utc::now(),
+            )
+            .await
+            .is_err()
+        );
+        unsafe {
+            env::remove_var("HI_TEST_SLACK_WEBHOOK");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn ntfy_channel_publishes_title_message_and_priority() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/")
+                    .header("authorization", "Bearer tk_test")
+                    .json_body(json!({
+                        "topic": "hi-alerts",
+                        "title": "❌ Failed: Rotate keys",
+                        "message": "timeout",
+                        "priority": 4,
+                    }));
+                then.status(200);
+            })
+            .await;
+        unsafe {
+            env::set_var("HI_TEST_NTFY_TOKEN", "tk_test");
+        }
+
+        let temp = TempDir::new().unwrap();
+        let channel = target(NotificationChannel::Ntfy {
+            server: format!("{}/", server.base_url()),
+            topic: "hi-alerts".to_string(),
+            token_env: Some("HI_TEST_NTFY_TOKEN".to_string()),
+        });
+        notify(
+            temp.path(),
+            &Channels::default(),
+            &channel,
+            &event(IntentEventKind::Failed, "github", 0.8, "ops"),
+            true,
+            Utc::now(),
+        )
+        .await
+        .expect("ntfy notification");
+        mock.assert_async().await;
+        unsafe {
+            env::remove_var("HI_TEST_NTFY_TOKEN");
+        }
+    }
+}
@@ -0,0 +1,681 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, anyhow};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use tokio::{fs, select, sync::Mutex, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::{
+    config::{AppConfig, ObjectStorageConfig},
+    state::AppContext,
+    storage,
+};
+
+/// Bound on one S3 request. Generous because a single put can carry a
+/// large file from the data dir.
+const OBJECT_STORE_TIMEOUT_SECS: u64 = 300;
+
+/// Minimal S3 client signing requests with AWS Signature Version 4. Only the
+/// calls the sync needs are implemented: list, get, put and delete.
+pub struct S3Client {
+    http: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: u64,
+}
+
+impl S3Client {
+    pub fn new(
+        config: &ObjectStorageConfig,
+        access_key: String,
+        secret_key: String,
+    ) -> anyhow::Result<Self> {
+        let http = Client::builder()
+            .timeout(Duration::from_secs(OBJECT_STORE_TIMEOUT_SECS))
+            .build()
+            .context("building object storage client")?;
+        Ok(Self {
+            http,
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            bucket: config.bucket.clone(),
+            region: config.region.clone(),
+            access_key,
+            secret_key,
+        })
+    }
+
+    /// Every object under `prefix`, following continuation tokens.
+    pub async fn list_objects(&self, prefix: &str) -> anyhow::Result<Vec<ObjectInfo>> {
+        let mut objects = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.to_string()),
+            ];
+            if let Some(token) = &continuation {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+            let response = self.send(Method::GET, "", &query, Vec::new()).await?;
+            let body = response.text().await.context("reading list response")?;
+            let page = parse_list_response(&body)?;
+            objects.extend(page.objects);
+            match page.next_token {
+                Some(token) if page.truncated => continuation = Some(token),
+                _ => break,
+            }
+        }
+        Ok(objects)
+    }
+
+    pub async fn get_object(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let response = self.send(Method::GET, key, &[], Vec::new()).await?;
+        let bytes = response
+            .bytes()
+            .await
+            .with_context(|| format!("reading object {key}"))?;
+        Ok(bytes.to_vec())
+    }
+
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        self.send(Method::PUT, key, &[], body).await.map(|_| ())
+    }
+
+    pub async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+        self.send(Method::DELETE, key, &[], Vec::new())
+            .await
+            .map(|_| ())
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(String, String)],
+        body: Vec<u8>,
+    ) -> anyhow::Result<reqwest::Response> {
+        let mut path = format!("/{}", uri_encode(&self.bucket, true));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&uri_encode(key, false));
+        }
+        let mut sorted: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, true), uri_encode(value, true)))
+            .collect();
+        sorted.sort();
+        let canonical_query = sorted
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut url = format!("{}{}", self.endpoint, path);
+        if !canonical_query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query);
+        }
+        let parsed = Url::parse(&url).with_context(|| format!("invalid object url {url}"))?;
+        let host = match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(anyhow!("object storage endpoint has no host: {url}")),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let canonical_request = format!(
+            "{method}\n{path}\n{canonical_query}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+            self.access_key
+        );
+
+        let response = self
+            .http
+            .request(method.clone(), parsed)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("{method} {url}"))?;
+        let status = response.status();
+        // Deleting an object that is already gone is not an error.
+        let already_deleted = method == Method::DELETE && status == StatusCode::NOT_FOUND;
+        if !status.is_success() && !already_deleted {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{method} {url} returned {status}: {detail}"));
+        }
+        Ok(response)
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{secret}").as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// RFC 3986 encoding as SigV4 expects it; `/` is kept in object keys.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+struct ListPage {
+    objects: Vec<ObjectInfo>,
+    truncated: bool,
+    next_token: Option<String>,
+}
+
+fn parse_list_response(body: &str) -> anyhow::Result<ListPage> {
+    let document = roxmltree::Document::parse(body).context("parsing list response")?;
+    let root = document.root_element();
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|child| child.tag_name().name() == name)
+            .and_then(|child| child.text())
+            .map(str::to_string)
+    };
+
+    let objects = root
+        .children()
+        .filter(|node| node.tag_name().name() == "Contents")
+        .filter_map(|node| {
+            Some(ObjectInfo {
+                key: child_text(node, "Key")?,
+                size: child_text(node, "Size")?.parse().ok()?,
+            })
+        })
+        .collect();
+    Ok(ListPage {
+        objects,
+        truncated: child_text(root, "IsTruncated").as_deref() == Some("true"),
+        next_token: child_text(root, "NextContinuationToken"),
+    })
+}
+
+/// Size and mtime of a file when it was last uploaded or restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub restored: usize,
+    /// Objects that could not be downloaded or written; see
+    /// [`ObjectSync::restore`].
+    pub failed: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    pub uploaded: usize,
+    pub deleted: usize,
+    pub failed: usize,
+}
+
+/// Mirrors `data/` to a bucket: restores missing files on start, then
+/// uploads changed files and deletes objects whose file is gone. Local
+/// files win; the bucket is a copy that outlives the container.
+pub struct ObjectSync {
+    client: S3Client,
+    data_dir: PathBuf,
+    prefix: String,
+    interval: Duration,
+    restore_on_start: bool,
+    state: Mutex<SyncState>,
+}
+
+#[derive(Debug, Default)]
+struct SyncState {
+    /// Files as last uploaded or restored, by path relative to the data dir.
+    synced: BTreeMap<String, FileStamp>,
+    /// Set once [`ObjectSync::restore`] has brought the data dir up to the
+    /// bucket, so any object without a local file is stale.
+    restored: bool,
+    /// Objects whose restore failed. They are never deleted as stale.
+    unrestored: BTreeSet<String>,
+}
+
+impl ObjectSync {
+    /// `None` unless `config/storage.yml` exists.
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Option<Arc<Self>>> {
+        let Some(storage_config) = &config.storage else {
+            return Ok(None);
+        };
+        let access_key = env::var(&storage_config.access_key_env)
+            .with_context(|| format!("reading {}", storage_config.access_key_env))?;
+        let secret_key = env::var(&storage_config.secret_key_env)
+            .with_context(|| format!("reading {}", storage_config.secret_key_env))?;
+        Ok(Some(Arc::new(Self::new(
+            storage_config,
+            config.data_dir.clone(),
+            S3Client::new(storage_config, access_key, secret_key)?,
+        ))))
+    }
+
+    pub fn new(config: &ObjectStorageConfig, data_dir: PathBuf, client: S3Client) -> Self {
+        let mut prefix = config.prefix.trim_start_matches('/').to_string();
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        Self {
+            client,
+            data_dir,
+            prefix,
+            interval: Duration::from_secs(config.sync_interval_secs.max(1)),
+            restore_on_start: config.restore_on_start,
+            state: Mutex::new(SyncState::default()),
+        }
+    }
+
+    pub fn restore_on_start(&self) -> bool {
+        self.restore_on_start
+    }
+
+    /// Download every object missing from `data/`. Files that already exist
+    /// are left alone. An object that fails to download or write is logged
+    /// and skipped, so one bad object does not keep the instance from
+    /// starting; only listing the bucket can fail the restore.
+    pub async fn restore(&self) -> anyhow::Result<RestoreReport> {
+        let objects = self.client.list_objects(&self.prefix).await?;
+        let mut state = self.state.lock().await;
+        let mut report = RestoreReport::default();
+        for object in objects {
+            let Some(relative) = self.relative_key(&object.key) else {
+                continue;
+            };
+            let path = self.data_dir.join(&relative);
+            if !path.exists() {
+                match self.restore_object(&object.key, &path).await {
+                    Ok(()) => {
+                        state.unrestored.remove(&relative);
+                        report.restored += 1;
+                    }
+                    Err(err) => {
+                        warn!(key = %object.key, error = ?err, "failed to restore object");
+                        state.unrestored.insert(relative);
+                        report.failed += 1;
+                        continue;
+                    }
+                }
+            }
+            // A local file of a different size is uploaded on the next push.
+            if let Some(stamp) = stamp(&path).await
+                && stamp.len == object.size
+            {
+                state.synced.insert(relative, stamp);
+            }
+        }
+        state.restored = true;
+        Ok(report)
+    }
+
+    async fn restore_object(&self, key: &str, path: &Path) -> anyhow::Result<()> {
+        let body = self.client.get_object(key).await?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("creating {:?}", parent))?;
+        }
+        storage::write_atomic(path, body).with_context(|| format!("restoring {:?}", path))
+    }
+
+    /// The data-dir path an object key mirrors, or `None` for folder
+    /// markers and keys that would land outside the data dir.
+    fn relative_key(&self, key: &str) -> Option<String> {
+        // Keys ending in `/` are folder markers some consoles create.
+        let relative = key
+            .strip_prefix(&self.prefix)
+            .filter(|relative| !relative.ends_with('/'))?;
+        if storage::sanitize_data_relative_path(relative).is_err() {
+            warn!(key = %key, "skipping object outside the data dir");
+            return None;
+        }
+        Some(relative.to_string())
+    }
+
+    /// Upload files changed since the last push and delete objects whose
+    /// file no longer exists, e.g. an intent moved from queue to history.
+    /// Stale objects are found by listing the bucket once the data dir has
+    /// been restored from it; before that (or when listing fails) only
+    /// files this process uploaded or restored are deleted.
+    pub async fn push(&self) -> anyhow::Result<SyncReport> {
+        let data_dir = self.data_dir.clone();
+        let local = tokio::task::spawn_blocking(move || local_files(&data_dir))
+            .await
+            .context("listing data files join failure")?;
+
+        let mut state = self.state.lock().await;
+        let mut report = SyncReport::default();
+        let mut present = BTreeSet::new();
+        for relative in local {
+            let path = self.data_dir.join(&relative);
+            let Some(current) = stamp(&path).await else {
+                continue;
+            };
+            present.insert(relative.clone());
+            if state.synced.get(&relative) == Some(&current) {
+                continue;
+            }
+            let key = format!("{}{relative}", self.prefix);
+            let uploaded = match fs::read(&path).await {
+                Ok(body) => self.client.put_object(&key, body).await,
+                Err(err) => Err(err.into()),
+            };
+            match uploaded {
+                Ok(()) => {
+                    state.unrestored.remove(&relative);
+                    state.synced.insert(relative, current);
+                    report.uploaded += 1;
+                }
+                Err(err) => {
+                    warn!(key = %key, error = ?err, "failed to upload data file");
+                    report.failed += 1;
+                }
+            }
+        }
+
+        let mut known: BTreeSet<String> = state.synced.keys().cloned().collect();
+        if state.restored {
+            match self.client.list_objects(&self.prefix).await {
+                Ok(objects) => known.extend(
+                    objects
+                        .iter()
+                        .filter_map(|object| self.relative_key(&object.key)),
+                ),
+                Err(err) => {
+                    warn!(error = ?err, "listing objects failed, deleting only synced files")
+                }
+            }
+        }
+        let removed: Vec<String> = known
+            .into_iter()
+            .filter(|relative| !present.contains(relative) && !state.unrestored.contains(relative))
+            .collect();
+        for relative in removed {
+            let key = format!("{}{relative}", self.prefix);
+            match self.client.delete_object(&key).await {
+                Ok(()) => {
+                    state.synced.remove(&relative);
+                    report.deleted += 1;
+                }
+                Err(err) => {
+                    warn!(key = %key, error = ?err, "failed to delete object");
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Push `data/` every `sync_interval_secs` until shutdown. The final push
+/// after the other tasks have stopped is left to the caller.
+pub fn spawn_sync(ctx: AppContext, sync: Arc<ObjectSync>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            select! {
+                _ = sleep(sync.interval) => {}
+                _ = ctx.wait_for_shutdown() => break,
+            }
+            match sync.push().await {
+                Ok(report) if report.uploaded + report.deleted > 0 => info!(
+                    uploaded = report.uploaded,
+                    deleted = report.deleted,
+                    failed = report.failed,
+                    "synced data dir to object storage"
+                ),
+                Ok(_) => {}
+                Err(err) => warn!(error = ?err, "object storage sync failed"),
+            }
+        }
+    })
+}
+
+fn local_files(data_dir: &Path) -> Vec<String> {
+    WalkDir::new(data_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(data_dir).ok()?;
+            let parts: Vec<&str> = relative
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<_>>()?;
+            Some(parts.join("/"))
+        })
+        // The lock belongs to this instance, not to the data.
+        .filter(|relative| relative != storage::DATA_DIR_LOCK_FILE)
+        .collect()
+}
+
+async fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).await.ok()?;
+    Some(FileStamp {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use tempfile::TempDir;
+
+    fn config(endpoint: String) -> ObjectStorageConfig {
+        ObjectStorageConfig {
+            endpoint,
+            bucket: "telos".to_string(),
+            region: "us-east-1".to_string(),
+            prefix: "hi".to_string(),
+            access_key_env: "UNUSED".to_string(),
+            secret_key_env: "UNUSED".to_string(),
+            sync_interval_secs: 60,
+            restore_on_start: true,
+        }
+    }
+
+    fn list_body(objects: &[(&str, u64)]) -> String {
+        let contents: String = objects
+            .iter()
+            .map(|(key, size)| format!("<Contents><Key>{key}</Key><Size>{size}</Size></Contents>"))
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <IsTruncated>false</IsTruncated>{contents}</ListBucketResult>"#
+        )
+    }
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20150830",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+        assert_eq!(uri_encode("a b/c~", false), "a%20b/c~");
+        assert_eq!(uri_encode("a b/c~", true), "a%20b%2Fc~");
+    }
+
+    #[tokio::test]
+    async fn restore_then_push_mirrors_data_dir() {
+        let server = MockServer::start_async().await;
+        let list = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/telos")
+                    .query_param("list-type", "2")
+                    .query_param("prefix", "hi/")
+                    .header_exists("authorization");
+                then.status(200).body(list_body(&[
+                    ("hi/journals/2025-01-01.md", 5),
+                    ("hi/intent/queue/old.md", 3),
+                    ("hi/journals/broken.md", 4),
+                    ("hi/notes/draft.md", 9),
+                ]));
+            })
+            .await;
+        let journal = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/telos/hi/journals/2025-01-01.md");
+                then.status(200).body("entry");
+            })
+            .await;
+        let broken = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/telos/hi/journals/broken.md");
+                then.status(500);
+            })
+            .await;
+        let upload = server
+            .mock_async(|when, then| {
+                when.method(PUT)
+                    .path("/telos/hi/intent/history/new%20intent.md")
+                    .body("done");
+                then.status(200);
+            })
+            .await;
+        let delete = server
+            .mock_async(|when, then| {
+                when.method(DELETE).path("/telos/hi/intent/queue/old.md");
+                then.status(204);
+            })
+            .await;
+        let delete_draft = server
+            .mock_async(|when, then| {
+                when.method(DELETE).path("/telos/hi/notes/draft.md");
+                then.status(204);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path().to_path_buf();
+        std::fs::create_dir_all(data_dir.join("intent/queue")).unwrap();
+        std::fs::write(data_dir.join("intent/queue/old.md"), "old").unwrap();
+        std::fs::create_dir_all(data_dir.join("notes")).unwrap();
+        std::fs::write(data_dir.join("notes/draft.md"), "x").unwrap();
+
+        let config = config(server.base_url());
+        let client = S3Client::new(&config, "AKID".to_string(), "secret".to_string()).unwrap();
+        let sync = ObjectSync::new(&config, data_dir.clone(), client);
+
+        // The broken object is skipped instead of failing the restore.
+        assert_eq!(
+            sync.restore().await.unwrap(),
+            RestoreReport {
+                restored: 1,
+                failed: 1,
+            }
+        );
+        journal.assert_async().await;
+        broken.assert_async().await;
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join("journals/2025-01-01.md")).unwrap(),
+            "entry"
+        );
+        assert!(!data_dir.join("journals/broken.md").exists());
+
+        // The draft never matched its object, so only the bucket listing
+        // shows it as stale once the local file is gone. The object that
+        // failed to restore is kept.
+        std::fs::remove_file(data_dir.join("notes/draft.md")).unwrap();
+        assert_eq!(
+            sync.push().await.unwrap(),
+            SyncReport {
+                uploaded: 0,
+                deleted: 1,
+                failed: 0,
+            }
+        );
+        delete_draft.assert_async().await;
+        list.assert_hits_async(2).await;
+        list.delete_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/telos")
+                    .query_param("list-type", "2");
+                then.status(200).body(list_body(&[
+                    ("hi/journals/2025-01-01.md", 5),
+                    ("hi/intent/queue/old.md", 3),
+                    ("hi/journals/broken.md", 4),
+                ]));
+            })
+            .await;
+
+        std::fs::create_dir_all(data_dir.join("intent/history")).unwrap();
+        std::fs::write(data_dir.join("intent/history/new intent.md"), "done").unwrap();
+        std::fs::remove_file(data_dir.join("intent/queue/old.md")).unwrap();
+        let report = sync.push().await.unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                uploaded: 1,
+                deleted: 1,
+                failed: 0,
+            }
+        );
+        upload.assert_async().await;
+        delete.assert_async().await;
+    }
+}
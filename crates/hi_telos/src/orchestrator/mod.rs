@@ -1,21 +1,45 @@
 use std::collections::HashMap;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use async_trait::async_trait;
 use tokio::{
     select,
     sync::mpsc::{self, Sender},
     task::JoinHandle,
-    time::{interval, sleep},
+    time::{Instant, interval, interval_at, sleep},
 };
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{agent::AgentInput, state::AppContext, storage, tasks::Intent};
+use crate::{
+    agent::{AgentInput, AgentRun, FailedRun},
+    clock::SharedClock,
+    config::{AppConfig, SourcePolicy},
+    events::{IntentEvent, IntentEventKind},
+    github,
+    outbox::{self, Channels},
+    server, sessions,
+    state::AppContext,
+    storage::{self, IntentEdit, IntentRecord},
+    tasks::{
+        ESTIMATED_TOKENS_KEY, EXPERIMENT_KEY, EXPERIMENT_VARIANT_KEY, HOLD_REASON_KEY, Intent,
+        IntentState, PERSONA_KEY, PRIORITY_KEY, PROMPT_VERSION_KEY, SLA_ALERTED_AT_KEY,
+    },
+    telegram,
+};
+
+mod scheduler;
+
+pub use scheduler::{CronSchedule, Job, JobBoard, JobSchedule, JobStatus, Scheduler};
 
 const STORAGE_RETRY_ATTEMPTS: usize = 3;
 const STORAGE_RETRY_DELAY_MS: u64 = 200;
 const INTENT_REQUEUE_ATTEMPTS: u8 = 3;
+/// How often the orchestrator looks for beats requested by a `server` role
+/// process sharing the data dir.
+const BEAT_REQUEST_POLL: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub enum OrchestratorCommand {
@@ -24,18 +48,43 @@ pub enum OrchestratorCommand {
 
 #[derive(Clone)]
 pub struct OrchestratorHandle {
-    tx: Sender<OrchestratorCommand>,
+    target: BeatTarget,
+}
+
+#[derive(Clone)]
+enum BeatTarget {
+    Local(Sender<OrchestratorCommand>),
+    /// The worker process polls the data dir for requests.
+    DataDir(PathBuf, SharedClock),
 }
 
 impl OrchestratorHandle {
+    /// Handle for a process without an orchestrator: beat requests are left
+    /// in the data dir of `ctx` for the worker process sharing it.
+    pub fn remote(ctx: &AppContext) -> Self {
+        Self {
+            target: BeatTarget::DataDir(ctx.config().data_dir.clone(), ctx.clock()),
+        }
+    }
+
     pub async fn request_beat(&self) -> anyhow::Result<()> {
-        self.tx
-            .send(OrchestratorCommand::RequestBeat)
-            .await
-            .map_err(|err| anyhow::anyhow!("orchestrator shutdown: {err}"))
+        match &self.target {
+            BeatTarget::Local(tx) => tx
+                .send(OrchestratorCommand::RequestBeat)
+                .await
+                .map_err(|err| anyhow::anyhow!("orchestrator shutdown: {err}")),
+            BeatTarget::DataDir(data_dir, clock) => storage::request_beat(data_dir, clock.now()),
+        }
     }
 }
 
+/// Inbox intents that did not go straight to the queue.
+#[derive(Default)]
+struct InboxTriage {
+    deferred: Vec<Intent>,
+    held: Vec<Intent>,
+}
+
 pub struct BeatOrchestrator {
     ctx: AppContext,
     cmd_rx: mpsc::Receiver<OrchestratorCommand>,
@@ -47,39 +96,119 @@ impl BeatOrchestrator {
     }
 
     async fn process_intent(&self, intent: &Intent) -> anyhow::Result<()> {
+        let started = Instant::now();
         let backlog_size = {
             let intents = self.ctx.intents();
             let queue = intents.read();
             queue.len()
         };
 
+        let conversation = {
+            let config = self.ctx.config();
+            sessions::intent_turns(
+                &config.data_dir,
+                &config.agent.session,
+                intent,
+                self.ctx.now(),
+            )
+            .unwrap_or_else(|err| {
+                warn!(intent = %intent.summary, error = ?err, "failed to load session context");
+                Vec::new()
+            })
+        };
+
+        // A run that paused on `ask_user` resumes from its earlier steps.
+        let pending = {
+            let config = self.ctx.config();
+            storage::load_pending_question(&config.data_dir, intent.id)?
+        }
+        .filter(|pending| pending.answer.is_some());
+        let prior_steps = pending
+            .as_ref()
+            .map(storage::PendingQuestion::resumed_steps)
+            .unwrap_or_default();
+
+        let config = self.ctx.config();
+        let precedents = storage::find_sp_precedents(
+            &config.data_dir,
+            &intent.summary,
+            &config.agent.sp_recall,
+        )
+        .await
+        .unwrap_or_else(|err| {
+            warn!(intent = %intent.summary, error = ?err, "failed to look up SP precedents");
+            Vec::new()
+        });
+        drop(config);
+
         let agent = self.ctx.agent();
         let run = agent
             .run_react(AgentInput {
                 intent: intent.clone(),
                 backlog_size,
+                conversation,
+                prior_steps,
+                precedents,
             })
             .await?;
+        if let Some(question) = run.question.clone() {
+            return self.park_for_question(intent, &run, question).await;
+        }
         let outcome = run.outcome.clone();
         let llm_logs = run.llm_logs.clone();
+        let memory_entry_id = Uuid::new_v4();
+        let links = storage::JournalLinks {
+            run_id: run.run_id,
+            intent_id: intent.id,
+            memory_ids: vec![memory_entry_id],
+        };
 
         let config = self.ctx.config();
         let data_dir = config.data_dir.clone();
         drop(config);
+        let finished_at = self.ctx.now();
+        let timing = storage::RunTiming::new(started.elapsed(), &llm_logs);
+        self.save_run_artifacts(
+            &data_dir,
+            storage::RunArtifacts {
+                run_id: run.run_id,
+                intent: Some(intent),
+                status: storage::RunStatus::Completed,
+                detail: Some(&outcome.final_answer),
+                llm_logs: &llm_logs,
+                files: &run.files,
+            },
+        );
 
-        self.run_with_retry(&intent.summary, "llm_logs", || {
-            let data_dir = data_dir.clone();
-            let llm_logs = llm_logs.clone();
-            async move { storage::append_llm_logs(&data_dir, &llm_logs).await }
-        })
-        .await?;
+        // Logs are the first thing to go when the disk runs low.
+        if !self.ctx.writes_paused() {
+            self.run_with_retry(&intent.summary, "llm_logs", || {
+                let data_dir = data_dir.clone();
+                let llm_logs = llm_logs.clone();
+                async move { storage::append_llm_logs(&data_dir, &llm_logs).await }
+            })
+            .await?;
+        }
 
+        let keep_history = !self.ctx.writes_paused();
         let journal_path = self
             .run_with_retry(&intent.summary, "journal", || {
                 let data_dir = data_dir.clone();
                 let intent = intent.clone();
                 let outcome = outcome.clone();
-                async move { storage::append_journal_entry(&data_dir, &intent, &outcome).await }
+                let links = links.clone();
+                async move {
+                    storage::append_journal_entry_at(
+                        &data_dir,
+                        finished_at,
+                        &intent,
+                        &outcome,
+                        &links,
+                        Some(timing),
+                        keep_history,
+                    )
+                    .await
+                }
             })
             .await?;
 
@@ -87,7 +216,7 @@ impl BeatOrchestrator {
             let data_dir = data_dir.clone();
             let intent = intent.clone();
             let outcome = outcome.clone();
-            async move { storage::update_sp_index(&data_dir, &intent, &outcome).await }
+            async move { storage::update_sp_index(&data_dir, &intent, &outcome, finished_at).await }
         })
         .await?;
 
@@ -95,9 +224,30 @@ impl BeatOrchestrator {
             .run_with_retry(&intent.summary, "archive", || {
                 let data_dir = data_dir.clone();
                 let intent = intent.clone();
-                async move { storage::archive_intent(&intent, &data_dir).await }
+                async move { storage::archive_intent(&intent, &data_dir, finished_at).await }
             })
             .await?;
+        if let Some(history_path) = history_path.as_deref() {
+            let mut metadata = timing.metadata().to_vec();
+            metadata.push((PROMPT_VERSION_KEY, run.prompts.version.clone()));
+            if let Some((experiment, variant)) = &run.prompts.experiment {
+                metadata.push((EXPERIMENT_KEY, experiment.clone()));
+                metadata.push((EXPERIMENT_VARIANT_KEY, variant.clone()));
+            }
+            let entries = metadata
+                .iter()
+                .map(|(key, value)| (*key, value.as_str()))
+                .collect::<Vec<_>>();
+            if let Err(err) = storage::set_intent_metadata(history_path, &entries) {
+                warn!(intent = %intent.summary, error = ?err, "failed to record run metadata");
+            }
+        }
+
+        if pending.is_some()
+            && let Err(err) = storage::clear_pending_question(&data_dir, intent.id)
+        {
+            warn!(intent = %intent.summary, error = ?err, "failed to clear answered question");
+        }
 
         let memory_intent = intent.clone();
         let memory_outcome = outcome.clone();
@@ -111,14 +261,16 @@ impl BeatOrchestrator {
             let journal_path = memory_journal.clone();
             let history_path = memory_history.clone();
             async move {
-                storage::ingest_memory_snapshot(
+                storage::ingest_memory_snapshot_at(
                     &data_dir,
                     storage::MemorySnapshotInput {
+                        entry_id: memory_entry_id,
                         intent,
                         outcome,
                         journal_path,
                         history_path,
                     },
+                    finished_at,
                 )
                 .await
                 .map(|_| ())
@@ -126,10 +278,219 @@ impl BeatOrchestrator {
         })
         .await?;
 
-        info!(intent = %intent.summary, final = %outcome.final_answer, "beat handled");
+        let mut event = IntentEvent::new(IntentEventKind::Completed, intent, self.ctx.now());
+        event.final_answer = Some(outcome.final_answer.clone());
+        self.ctx.events().publish(event);
+        if intent.source == storage::WEEKLY_REVIEW_SOURCE {
+            let mut event = IntentEvent::new(IntentEventKind::DigestReady, intent, self.ctx.now());
+            event.final_answer = Some(outcome.final_answer.clone());
+            self.ctx.events().publish(event);
+        }
+
+        self.reply_to_github_issue(intent, &outcome.final_answer)
+            .await;
+        self.reply_to_telegram_chat(intent, &outcome.final_answer)
+            .await;
+
+        let waited_secs = (finished_at - intent.created_at).num_seconds();
+        info!(
+            intent = %intent.summary,
+            final = %outcome.final_answer,
+            waited_secs,
+            duration_ms = timing.duration_ms,
+            llm_latency_ms = timing.llm_latency_ms,
+            overdue = intent.is_overdue(finished_at),
+            "beat handled"
+        );
         Ok(())
     }
 
+    /// Save a run's prompts, responses and tool files under
+    /// `runs/<run_id>/`. Artifacts are for debugging only, so failing to
+    /// write them does not fail the intent, and they are skipped while the
+    /// disk guard has paused logs.
+    fn save_run_artifacts(&self, data_dir: &Path, artifacts: storage::RunArtifacts<'_>) {
+        if self.ctx.writes_paused() {
+            return;
+        }
+        if let Err(err) = storage::write_run_artifacts(data_dir, &artifacts, self.ctx.now()) {
+            warn!(run_id = %artifacts.run_id, error = ?err, "failed to write run artifacts");
+        }
+    }
+
+    /// Pause `intent` on the agent's question: keep its steps, move the file to
+    /// `intent/waiting` and ask the originating Telegram chat. The reply (or
+    /// `POST /api/intents/:id/answer`) puts it back in the queue.
+    async fn park_for_question(
+        &self,
+        intent: &Intent,
+        run: &AgentRun,
+        question: String,
+    ) -> anyhow::Result<()> {
+        let config = self.ctx.config();
+        let data_dir = config.data_dir.clone();
+        let telegram_enabled = config.telegram.is_some();
+        drop(config);
+
+        let llm_logs = run.llm_logs.clone();
+        if !self.ctx.writes_paused() {
+            self.run_with_retry(&intent.summary, "llm_logs", || {
+                let data_dir = data_dir.clone();
+                let llm_logs = llm_logs.clone();
+                async move { storage::append_llm_logs(&data_dir, &llm_logs).await }
+            })
+            .await?;
+        }
+        self.save_run_artifacts(
+            &data_dir,
+            storage::RunArtifacts {
+                run_id: run.run_id,
+                intent: Some(intent),
+                status: storage::RunStatus::Waiting,
+                detail: Some(&question),
+                llm_logs: &run.llm_logs,
+                files: &run.files,
+            },
+        );
+
+        let chat_id = telegram::origin_chat_id(intent);
+        let pending = storage::PendingQuestion {
+            intent_id: intent.id,
+            run_id: run.run_id,
+            summary: intent.summary.clone(),
+            question: question.clone(),
+            steps: run.outcome.steps.clone(),
+            source: intent.source.clone(),
+            chat_id: chat_id.map(|chat_id| chat_id.to_string()),
+            asked_at: self.ctx.now(),
+            answer: None,
+            answered_at: None,
+        };
+        let path = intent
+            .storage_path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("intent {} has no storage path", intent.id))?;
+        storage::park_intent_for_question(path, &data_dir, &pending, self.ctx.now())?;
+        info!(intent = %intent.summary, question = %question, "intent waiting for user input");
+
+        if let Some(chat_id) = chat_id.filter(|_| telegram_enabled) {
+            let text = format!(
+                "❓ {}\n\n{}\n\nReply in this chat to continue.",
+                intent.summary, question
+            );
+            if let Err(err) = outbox::send_or_queue(
+                &data_dir,
+                &Channels::of(&self.ctx),
+                chat_id,
+                &text,
+                false,
+                self.ctx.now(),
+            )
+            .await
+            {
+                warn!(
+                    intent = %intent.summary,
+                    chat_id,
+                    error = ?err,
+                    "failed to queue clarification question for telegram chat"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Post the final answer back to the originating GitHub issue, if any. A
+    /// failed comment does not fail the intent, which is already archived.
+    async fn reply_to_github_issue(&self, intent: &Intent, final_answer: &str) {
+        let Some(github_config) = self.ctx.config().github.clone() else {
+            return;
+        };
+        match github::comment_final_answer(&github_config, intent, final_answer).await {
+            Ok(true) => info!(
+                intent = %intent.summary,
+                issue = intent.metadata.get(github::ISSUE_URL_KEY).map(String::as_str).unwrap_or_default(),
+                "posted final answer to github issue"
+            ),
+            Ok(false) => {}
+            Err(err) => warn!(
+                intent = %intent.summary,
+                error = ?err,
+                "failed to post final answer to github issue"
+            ),
+        }
+    }
+
+    /// Send the final answer back to the chat a Telegram intent came from
+    /// through the outbox, so a failed send is retried.
+    async fn reply_to_telegram_chat(&self, intent: &Intent, final_answer: &str) {
+        let Some(chat_id) = telegram::origin_chat_id(intent) else {
+            return;
+        };
+        let data_dir = self.ctx.config().data_dir.clone();
+        let channels = Channels::of(&self.ctx);
+        if channels.telegram.is_none() {
+            return;
+        }
+
+        let text = telegram::outcome_message(intent, final_answer);
+        if let Err(err) =
+            outbox::send_or_queue(&data_dir, &channels, chat_id, &text, false, self.ctx.now()).await
+        {
+            warn!(
+                intent = %intent.summary,
+                chat_id,
+                error = ?err,
+                "failed to queue final answer for telegram chat"
+            );
+        }
+    }
+
+    /// The queued intent as it is on disk now: a `server` role process
+    /// sharing the data dir may have edited or cancelled it since it was
+    /// queued. `None` when it is gone.
+    fn reload_queued(intent: Intent) -> Option<Intent> {
+        let Some(path) = intent.storage_path.as_deref() else {
+            return Some(intent);
+        };
+        match storage::load_intent(path) {
+            // Files without an id or creation time get fresh ones on every
+            // read; keep the ones the queue already knows.
+            Ok(Some(fresh)) => Some(Intent {
+                id: intent.id,
+                created_at: intent.created_at,
+                ..fresh
+            }),
+            Ok(None) => {
+                info!(intent = %intent.summary, "skipping intent removed from the queue");
+                None
+            }
+            Err(err) => {
+                warn!(intent = %intent.summary, error = ?err, "failed to reload queued intent");
+                Some(intent)
+            }
+        }
+    }
+
+    /// Record a lifecycle move that does not change the intent's directory.
+    fn record_state(&self, intent: &Intent, state: IntentState, reason: Option<&str>) {
+        let Some(path) = intent.storage_path.as_deref() else {
+            return;
+        };
+        if let Err(err) = storage::record_intent_state(path, state, reason, self.ctx.now()) {
+            warn!(intent = %intent.summary, error = ?err, state = %state, "failed to record intent state");
+        }
+    }
+
+    /// Note why `path` failed in its front matter before it is quarantined.
+    fn record_failure(path: &Path, failure: &storage::IntentFailure) -> anyhow::Result<()> {
+        let metadata = failure.metadata();
+        let entries = metadata
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect::<Vec<_>>();
+        storage::set_intent_metadata(path, &entries)
+    }
+
     async fn run_with_retry<F, Fut, T>(
         &self,
         summary: &str,
@@ -167,9 +528,11 @@ impl BeatOrchestrator {
             warn!(error = ?err, "failed to bootstrap intent queue");
         }
 
-        let beat_interval = self.ctx.config().beat.interval();
+        let mut beat_interval = self.ctx.config().beat.interval();
         let mut ticker = interval(beat_interval);
-        let shutdown = self.ctx.shutdown_notifier();
+        let ctx = self.ctx.clone();
+        let mut reloads = ctx.events().subscribe_config_reloads();
+        let mut remote_requests = interval(BEAT_REQUEST_POLL);
 
         loop {
             select! {
@@ -177,6 +540,25 @@ impl BeatOrchestrator {
                     info!("beat ticker fired");
                     self.run_beat().await;
                 }
+                _ = remote_requests.tick() => {
+                    let data_dir = self.ctx.config().data_dir.clone();
+                    match storage::take_beat_request(&data_dir) {
+                        Ok(true) => {
+                            info!("beat requested through the data dir");
+                            self.run_beat().await;
+                        }
+                        Ok(false) => {}
+                        Err(err) => warn!(error = ?err, "failed to check for beat requests"),
+                    }
+                }
+                Ok(_) = reloads.recv() => {
+                    let reloaded = self.ctx.config().beat.interval();
+                    if reloaded != beat_interval {
+                        info!(?reloaded, "beat interval changed");
+                        beat_interval = reloaded;
+                        ticker = interval_at(Instant::now() + beat_interval, beat_interval);
+                    }
+                }
                 Some(cmd) = self.cmd_rx.recv() => {
                     match cmd {
                         OrchestratorCommand::RequestBeat => {
@@ -185,7 +567,7 @@ impl BeatOrchestrator {
                         }
                     }
                 }
-                _ = shutdown.notified() => {
+                _ = ctx.wait_for_shutdown() => {
                     info!("beat orchestrator shutting down");
                     break;
                 }
@@ -193,12 +575,27 @@ impl BeatOrchestrator {
         }
     }
 
-    async fn run_beat(&self) {
-        if let Err(err) = self.ingest_inbox() {
-            warn!(error = ?err, "failed to ingest inbox");
+    async fn run_beat(&mut self) {
+        let gate = self.ctx.beat_gate();
+        let _running = gate.lock().await;
+
+        match self.ingest_inbox() {
+            Ok(triage) => {
+                self.request_telegram_approval(&triage.deferred).await;
+                self.request_telegram_hold_approval(&triage.held).await;
+            }
+            Err(err) => warn!(error = ?err, "failed to ingest inbox"),
+        }
+
+        self.alert_overdue_intents().await;
+        self.snapshot_acceptance_metrics().await;
+
+        if let Err(err) = self.resume_answered_questions() {
+            warn!(error = ?err, "failed to resume answered questions");
         }
 
         let mut attempts: HashMap<Uuid, u8> = HashMap::new();
+        let mut failed_runs: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
 
         loop {
             let next_intent = {
@@ -208,10 +605,15 @@ impl BeatOrchestrator {
             };
 
             if let Some(intent) = next_intent {
+                let Some(intent) = Self::reload_queued(intent) else {
+                    continue;
+                };
                 let intent_id = intent.id;
+                self.record_state(&intent, IntentState::Processing, None);
                 match self.process_intent(&intent).await {
                     Ok(()) => {
                         attempts.remove(&intent_id);
+                        failed_runs.remove(&intent_id);
                     }
                     Err(err) => {
                         let entry = attempts.entry(intent_id).or_insert(0);
@@ -221,6 +623,33 @@ impl BeatOrchestrator {
                         let data_dir = config.data_dir.clone();
                         drop(config);
 
+                        // Keep the calls of the failed run so the failure
+                        // report can link to them.
+                        if let Some(run) = err.downcast_ref::<FailedRun>() {
+                            failed_runs.entry(intent_id).or_default().push(run.run_id);
+                            if !self.ctx.writes_paused()
+                                && let Err(log_err) =
+                                    storage::append_llm_logs(&data_dir, &run.llm_logs).await
+                            {
+                                warn!(
+                                    intent = %intent.summary,
+                                    error = ?log_err,
+                                    "failed to persist llm logs of failed run"
+                                );
+                            }
+                            self.save_run_artifacts(
+                                &data_dir,
+                                storage::RunArtifacts {
+                                    run_id: run.run_id,
+                                    intent: Some(&intent),
+                                    status: storage::RunStatus::Failed,
+                                    detail: Some(&format!("{err:#}")),
+                                    llm_logs: &run.llm_logs,
+                                    files: &run.files,
+                                },
+                            );
+                        }
+
                         if *entry >= INTENT_REQUEUE_ATTEMPTS {
                             warn!(
                                 intent = %intent.summary,
@@ -229,9 +658,20 @@ impl BeatOrchestrator {
                                 "intent failed after max retries"
                             );
 
+                            let failure = storage::IntentFailure::new(
+                                &err,
+                                self.ctx.now(),
+                                failed_runs.remove(&intent_id).unwrap_or_default(),
+                            );
                             if let Some(path) = intent.storage_path.as_ref()
-                                && let Err(move_err) =
-                                    storage::quarantine_failed_intent(path, &data_dir)
+                                && let Err(move_err) = Self::record_failure(path, &failure)
+                                    .and_then(|()| {
+                                        storage::quarantine_failed_intent(
+                                            path,
+                                            &data_dir,
+                                            self.ctx.now(),
+                                        )
+                                    })
                             {
                                 warn!(
                                     intent = %intent.summary,
@@ -240,6 +680,11 @@ impl BeatOrchestrator {
                                 );
                             }
 
+                            let mut event =
+                                IntentEvent::new(IntentEventKind::Failed, &intent, self.ctx.now());
+                            event.error = Some(format!("{err:#}"));
+                            self.ctx.events().publish(event);
+
                             attempts.remove(&intent_id);
                         } else {
                             warn!(
@@ -248,6 +693,7 @@ impl BeatOrchestrator {
                                 error = ?err,
                                 "intent processing failed, will retry"
                             );
+                            self.record_state(&intent, IntentState::Queued, Some("retrying"));
                             let intents = self.ctx.intents();
                             intents.write().push_front(intent);
                         }
@@ -258,30 +704,258 @@ impl BeatOrchestrator {
                 break;
             }
         }
+
+        let finished = self.ctx.now();
+        self.ctx.record_beat(finished);
+        self.ctx.note_data_changed();
+        let data_dir = self.ctx.config().data_dir.clone();
+        if let Err(err) = storage::record_last_beat(&data_dir, finished) {
+            warn!(error = ?err, "failed to record beat time");
+        }
+    }
+
+    /// Warn once per intent that passes its `due_at` while still pending, and
+    /// push the alert to Telegram when a default chat is configured. The
+    /// alert is marked in the intent's metadata before it goes out.
+    async fn alert_overdue_intents(&self) {
+        let data_dir = self.ctx.config().data_dir.clone();
+        let channels = Channels::of(&self.ctx);
+
+        let now = self.ctx.now();
+        let pending = match storage::list_pending_intents(&data_dir, now) {
+            Ok(pending) => pending,
+            Err(err) => {
+                warn!(error = ?err, "failed to scan pending intents for SLA");
+                return;
+            }
+        };
+
+        for item in pending.into_iter().filter(|item| item.overdue) {
+            if item.intent.sla_alerted() {
+                continue;
+            }
+            let Some(path) = item.intent.storage_path.as_deref() else {
+                continue;
+            };
+            let alerted_at = now.to_rfc3339();
+            if let Err(err) =
+                storage::set_intent_metadata(path, &[(SLA_ALERTED_AT_KEY, &alerted_at)])
+            {
+                warn!(error = ?err, intent = %item.intent.summary, "failed to mark SLA alert");
+                continue;
+            }
+
+            let due_at = item.intent.due_at.unwrap_or(now);
+            warn!(
+                intent = %item.intent.summary,
+                due_at = %due_at,
+                stage = item.stage,
+                "intent breached its SLA"
+            );
+
+            let Some(chat_id) = channels
+                .telegram
+                .as_ref()
+                .and_then(|telegram| telegram.default_chat_id)
+            else {
+                continue;
+            };
+            let text = format!(
+                "⏰ Intent overdue: {}\nDue: {}\nWaiting: {} min ({})",
+                item.intent.summary,
+                due_at.to_rfc3339(),
+                item.time_in_queue_secs / 60,
+                item.stage,
+            );
+            if let Err(err) =
+                outbox::send_or_queue(&data_dir, &channels, chat_id, &text, true, now).await
+            {
+                warn!(error = ?err, intent = %item.intent.summary, "failed to queue SLA alert");
+            }
+        }
     }
 
-    fn ingest_inbox(&self) -> anyhow::Result<()> {
+    /// Track acceptance progress over time; only changes are recorded.
+    async fn snapshot_acceptance_metrics(&self) {
+        let config = self.ctx.config();
+        let Some(root) = config.config_dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let docs = config.acceptance.docs.clone();
+        let data_dir = config.data_dir.clone();
+        drop(config);
+
+        match server::snapshot_acceptance_metrics(&root, &docs, &data_dir, self.ctx.now()).await {
+            Ok(true) => info!("acceptance metrics snapshot recorded"),
+            Ok(false) => {}
+            Err(err) => warn!(error = ?err, "failed to snapshot acceptance metrics"),
+        }
+    }
+
+    /// Queue inbox intents that meet the alignment threshold (or were
+    /// approved), hold those the approval gate or a token ceiling catches
+    /// and defer the rest.
+    fn ingest_inbox(&self) -> anyhow::Result<InboxTriage> {
         let config = self.ctx.config();
         let data_dir = config.data_dir.clone();
         let threshold = config.beat.intent_threshold;
+        let approval = config.beat.approval.clone();
+        let sources = config.sources.clone();
         drop(config);
 
+        let today = self.ctx.now().date_naive();
+        let mut queued_today = storage::load_queued_tokens(&data_dir, today);
+        let mut triage = InboxTriage::default();
         let new_intents = storage::scan_inbox(&data_dir)?;
-        for record in new_intents {
-            if record.intent.telos_alignment >= threshold {
-                let queue_path = storage::promote_to_queue(&record.path, &data_dir)?;
+        for mut record in new_intents {
+            let policy = sources.policy_for(&record.intent.source);
+            if let Some(policy) = policy {
+                apply_source_policy(&mut record, policy)?;
+            }
+            let estimate = self.estimate_tokens(&mut record)?;
+            let auto_approved = policy.is_some_and(|policy| policy.auto_approve);
+            let approved = record.intent.is_approved();
+            let hold_reason = approval.hold_reason(&record.intent, queued_today);
+            if !approved && record.intent.telos_alignment < threshold {
+                let deferred_path = storage::defer_intent(&record.path, &data_dir, self.ctx.now())?;
+                self.ctx.events().publish(IntentEvent::new(
+                    IntentEventKind::Deferred,
+                    &record.intent,
+                    self.ctx.now(),
+                ));
+                let mut intent = record.intent;
+                intent.storage_path = Some(deferred_path);
+                triage.deferred.push(intent);
+            } else if let Some(reason) = hold_reason.filter(|_| !approved && !auto_approved) {
+                info!(
+                    intent = %record.intent.summary,
+                    estimate,
+                    queued_today,
+                    reason,
+                    "holding intent for approval"
+                );
+                storage::set_intent_metadata(&record.path, &[(HOLD_REASON_KEY, reason)])?;
+                record
+                    .intent
+                    .metadata
+                    .insert(HOLD_REASON_KEY.to_string(), reason.to_string());
+                let held_path =
+                    storage::hold_for_approval(&record.path, &data_dir, self.ctx.now())?;
+                self.ctx.events().publish(IntentEvent::new(
+                    IntentEventKind::PendingApproval,
+                    &record.intent,
+                    self.ctx.now(),
+                ));
+                let mut intent = record.intent;
+                intent.storage_path = Some(held_path);
+                triage.held.push(intent);
+            } else {
+                let queue_path =
+                    storage::promote_to_queue(&record.path, &data_dir, self.ctx.now())?;
+                queued_today = storage::add_queued_tokens(&data_dir, today, estimate)?;
                 let mut intent = record.intent;
                 intent.storage_path = Some(queue_path);
                 let intents = self.ctx.intents();
-                intents.write().push(intent);
-            } else {
-                storage::defer_intent(&record.path, &data_dir)?;
+                intents.write().enqueue(intent);
             }
         }
 
+        Ok(triage)
+    }
+
+    /// The intent's [`ESTIMATED_TOKENS_KEY`], estimated and written to its
+    /// front matter the first time the beat sees it.
+    fn estimate_tokens(&self, record: &mut IntentRecord) -> anyhow::Result<u64> {
+        if let Some(estimate) = record.intent.estimated_tokens() {
+            return Ok(estimate);
+        }
+        let estimate = self
+            .ctx
+            .agent()
+            .estimate_intent_tokens(&record.intent, &record.body);
+        let value = estimate.to_string();
+        storage::set_intent_metadata(&record.path, &[(ESTIMATED_TOKENS_KEY, &value)])?;
+        record
+            .intent
+            .metadata
+            .insert(ESTIMATED_TOKENS_KEY.to_string(), value);
+        Ok(estimate)
+    }
+
+    /// Queue waiting intents whose question has been answered.
+    fn resume_answered_questions(&self) -> anyhow::Result<()> {
+        let data_dir = self.ctx.config().data_dir.clone();
+        for pending in storage::list_pending_questions(&data_dir)? {
+            if pending.answer.is_none() {
+                continue;
+            }
+            if let Some(record) =
+                storage::requeue_answered_intent(&data_dir, pending.intent_id, self.ctx.now())?
+            {
+                info!(intent = %record.intent.summary, "resuming intent with user answer");
+                let intents = self.ctx.intents();
+                intents.write().enqueue(record.intent);
+            }
+        }
         Ok(())
     }
 
+    /// Ask the originating Telegram chat whether a deferred intent should be
+    /// approved, deferred or discarded.
+    async fn request_telegram_approval(&self, deferred: &[Intent]) {
+        let data_dir = self.ctx.config().data_dir.clone();
+        let channels = Channels::of(&self.ctx);
+        if channels.telegram.is_none() {
+            return;
+        }
+
+        for intent in deferred {
+            let Some(chat_id) = telegram::origin_chat_id(intent) else {
+                continue;
+            };
+            let message = telegram::approval_request(chat_id, intent, self.ctx.now());
+            if let Err(err) =
+                outbox::deliver_or_queue(&data_dir, &channels, message, self.ctx.now()).await
+            {
+                warn!(
+                    intent = %intent.summary,
+                    chat_id,
+                    error = ?err,
+                    "failed to queue telegram approval request"
+                );
+            }
+        }
+    }
+
+    /// Ask for a decision on intents held by the approval gate, in the chat
+    /// they came from or else the default chat.
+    async fn request_telegram_hold_approval(&self, held: &[Intent]) {
+        let data_dir = self.ctx.config().data_dir.clone();
+        let channels = Channels::of(&self.ctx);
+        let Some(telegram_config) = &channels.telegram else {
+            return;
+        };
+
+        for intent in held {
+            let Some(chat_id) =
+                telegram::origin_chat_id(intent).or(telegram_config.default_chat_id)
+            else {
+                continue;
+            };
+            let message = telegram::hold_request(chat_id, intent, self.ctx.now());
+            if let Err(err) =
+                outbox::deliver_or_queue(&data_dir, &channels, message, self.ctx.now()).await
+            {
+                warn!(
+                    intent = %intent.summary,
+                    chat_id,
+                    error = ?err,
+                    "failed to queue telegram hold approval request"
+                );
+            }
+        }
+    }
+
     async fn load_existing_queue(&self) -> anyhow::Result<()> {
         let config = self.ctx.config();
         let data_dir = config.data_dir.clone();
@@ -296,20 +970,115 @@ impl BeatOrchestrator {
         let mut queue = intents.write();
         for mut record in existing {
             record.intent.storage_path = Some(record.path.clone());
-            queue.push(record.intent);
+            queue.enqueue(record.intent);
         }
+        queue.pin(&storage::load_queue_order(&data_dir));
 
         Ok(())
     }
 }
 
+/// Apply `policy` to an inbox intent, both in memory and in its file so the
+/// defaults survive a restart.
+fn apply_source_policy(record: &mut IntentRecord, policy: &SourcePolicy) -> anyhow::Result<()> {
+    let intent = &mut record.intent;
+    if let Some(alignment) = policy.telos_alignment
+        && !intent.is_approved()
+        && alignment != intent.telos_alignment
+    {
+        storage::edit_intent(
+            &record.path,
+            &IntentEdit {
+                telos_alignment: Some(alignment),
+                ..IntentEdit::default()
+            },
+        )?;
+        intent.telos_alignment = alignment;
+    }
+
+    let defaults = [
+        (
+            PRIORITY_KEY,
+            policy
+                .priority
+                .map(|priority| priority.as_str().to_string()),
+        ),
+        (PERSONA_KEY, policy.persona.clone()),
+    ];
+    let missing: Vec<(&str, String)> = defaults
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .filter(|(key, _)| !intent.metadata.contains_key(*key))
+        .collect();
+    if !missing.is_empty() {
+        let entries: Vec<(&str, &str)> = missing
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect();
+        storage::set_intent_metadata(&record.path, &entries)?;
+        for (key, value) in missing {
+            intent.metadata.insert(key.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
 pub fn spawn(ctx: AppContext) -> (OrchestratorHandle, JoinHandle<()>) {
     let (tx, rx) = mpsc::channel(32);
     let orchestrator = BeatOrchestrator::new(ctx.clone(), rx);
-    let handle = OrchestratorHandle { tx: tx.clone() };
+    let handle = OrchestratorHandle {
+        target: BeatTarget::Local(tx.clone()),
+    };
     let join = tokio::spawn(async move {
         orchestrator.run().await;
         drop(tx);
     });
     (handle, join)
 }
+
+/// Drops the weekly review intent into the inbox once `beat.weekly_review`'s
+/// weekday arrives and asks for a beat to pick it up. Checks at startup and
+/// hourly after that; off without `weekly_review`.
+pub struct WeeklyReviewJob {
+    orchestrator: OrchestratorHandle,
+}
+
+impl WeeklyReviewJob {
+    pub fn new(orchestrator: OrchestratorHandle) -> Self {
+        Self { orchestrator }
+    }
+}
+
+#[async_trait]
+impl Job for WeeklyReviewJob {
+    fn name(&self) -> &'static str {
+        "weekly_review"
+    }
+
+    fn default_schedule(&self, config: &AppConfig) -> Option<JobSchedule> {
+        config.beat.weekly_review.as_ref()?;
+        Some(JobSchedule::Every(Duration::from_secs(60 * 60)))
+    }
+
+    async fn run(&self, ctx: &AppContext) -> anyhow::Result<()> {
+        let config = ctx.config();
+        let Some(review) = config.beat.weekly_review.clone() else {
+            return Ok(());
+        };
+        let data_dir = config.data_dir.clone();
+        drop(config);
+
+        let created = storage::create_weekly_review_intent(
+            &data_dir,
+            ctx.now(),
+            review.weekday,
+            review.telos_alignment,
+        )
+        .await?;
+        if let Some(persisted) = created {
+            info!(id = %persisted.id, "weekly review intent created");
+            self.orchestrator.request_beat().await?;
+        }
+        Ok(())
+    }
+}
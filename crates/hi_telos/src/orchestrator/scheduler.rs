@@ -0,0 +1,506 @@
+use std::{collections::BTreeMap, fmt, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Context, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, DurationRound, TimeDelta, Timelike, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use tokio::{select, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{OrchestratorHandle, WeeklyReviewJob};
+use crate::{
+    config::{AppConfig, JobConfig, Role},
+    maintenance::{MemoryRetentionJob, RetentionJob},
+    sources::{IngestMode, SourceJob},
+    state::AppContext,
+};
+
+/// Shortest wait before a back-to-back job (`Every(0)`, like a long poll)
+/// runs again after failing.
+const JOB_ERROR_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Periodic work besides the beat. Jobs register in a [`Scheduler`], which
+/// runs each on its schedule and reports on it through `/api/jobs`.
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Name in logs, `/api/jobs` and `config/jobs.yml`.
+    fn name(&self) -> &'static str;
+
+    /// When the job runs unless `config/jobs.yml` says otherwise; `None`
+    /// leaves it off.
+    fn default_schedule(&self, config: &AppConfig) -> Option<JobSchedule>;
+
+    async fn run(&self, ctx: &AppContext) -> anyhow::Result<()>;
+
+    /// Called once when the scheduler stops the job at shutdown.
+    async fn stop(&self, ctx: &AppContext) {
+        let _ = ctx;
+    }
+}
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobSchedule {
+    /// At startup and every interval after that.
+    Every(Duration),
+    /// At the times a cron expression matches.
+    Cron(CronSchedule),
+}
+
+impl JobSchedule {
+    /// `config`'s schedule: `cron` or `interval_secs`, whichever is set.
+    pub fn from_config(config: &JobConfig) -> anyhow::Result<Option<Self>> {
+        match (&config.cron, config.interval_secs) {
+            (Some(_), Some(_)) => bail!("set either cron or interval_secs, not both"),
+            (Some(cron), None) => Ok(Some(Self::Cron(cron.parse()?))),
+            (None, Some(0)) => bail!("interval_secs must be above 0"),
+            (None, Some(secs)) => Ok(Some(Self::Every(Duration::from_secs(secs)))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The first run after `now`, or `now` itself for an interval job's
+    /// first run.
+    fn next_run(&self, now: DateTime<Utc>, first: bool) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Every(_) if first => Some(now),
+            Self::Every(interval) => TimeDelta::from_std(*interval)
+                .ok()
+                .and_then(|interval| now.checked_add_signed(interval)),
+            Self::Cron(cron) => cron.next_after(now),
+        }
+    }
+}
+
+impl fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Every(interval) if interval.subsec_nanos() == 0 => {
+                write!(f, "every {}s", interval.as_secs())
+            }
+            Self::Every(interval) => write!(f, "every {}ms", interval.as_millis()),
+            Self::Cron(cron) => write!(f, "cron {cron}"),
+        }
+    }
+}
+
+/// A five-field cron expression (minute, hour, day of month, month, day of
+/// week) evaluated in UTC. Fields take `*`, numbers, `a-b` ranges, `,`
+/// lists and `/n` steps; Sunday is `0` or `7`. As in cron, a day matches
+/// when either day field does if both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> anyhow::Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!("cron {expression:?} needs 5 fields, found {}", fields.len());
+        };
+        let parse = |field: &str, min: u32, max: u32, name: &str| {
+            parse_cron_field(field, min, max)
+                .with_context(|| format!("cron {expression:?}: invalid {name} field {field:?}"))
+        };
+        let mut weekdays = parse(weekday, 0, 7, "day of week")?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: parse(minute, 0, 59, "minute")?,
+            hours: parse(hour, 0, 23, "hour")?,
+            days: parse(day, 1, 31, "day of month")?,
+            months: parse(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// Enough to reach any date a valid expression matches, e.g. 29 February.
+const CRON_SEARCH_DAYS: u32 = 366 * 8;
+
+impl CronSchedule {
+    /// The first matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = after.duration_trunc(TimeDelta::minutes(1)).ok()? + TimeDelta::minutes(1);
+        let end = at + TimeDelta::days(CRON_SEARCH_DAYS.into());
+        while at < end {
+            if !self.day_matches(at) {
+                at = at.duration_trunc(TimeDelta::days(1)).ok()? + TimeDelta::days(1);
+            } else if !bit(self.hours, at.hour()) {
+                at = at.duration_trunc(TimeDelta::hours(1)).ok()? + TimeDelta::hours(1);
+            } else if !bit(self.minutes, at.minute()) {
+                at += TimeDelta::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, at: DateTime<Utc>) -> bool {
+        if !bit(self.months, at.month()) {
+            return false;
+        }
+        let day = bit(self.days, at.day());
+        let weekday = bit(self.weekdays, at.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// The values `field` allows within `min..=max`, as a bit set.
+fn parse_cron_field(field: &str, min: u32, max: u32) -> anyhow::Result<u64> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>()?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("step must be above 0");
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (start.parse()?, end.parse()?),
+                // `5/15` means from 5 to the end in steps of 15.
+                None if part.contains('/') => (range.parse()?, max),
+                None => {
+                    let value = range.parse()?;
+                    (value, value)
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(anyhow!("{range} is outside {min}-{max}"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// How a job has been doing, for `/api/jobs`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub schedule: String,
+    pub jitter_secs: u64,
+    pub running: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_ok: Option<bool>,
+    pub last_error: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+/// Status of every scheduled job, shared between the scheduler and the
+/// server.
+#[derive(Debug, Clone, Default)]
+pub struct JobBoard {
+    jobs: Arc<RwLock<BTreeMap<String, JobStatus>>>,
+}
+
+impl JobBoard {
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs.read().values().cloned().collect()
+    }
+
+    fn update(&self, name: &str, change: impl FnOnce(&mut JobStatus)) {
+        if let Some(status) = self.jobs.write().get_mut(name) {
+            change(status);
+        }
+    }
+}
+
+struct ScheduledJob {
+    job: Arc<dyn Job>,
+    schedule: JobSchedule,
+    jitter: Duration,
+}
+
+/// Runs [`Job`]s on their schedules until shutdown.
+pub struct Scheduler {
+    ctx: AppContext,
+    jobs: Vec<ScheduledJob>,
+}
+
+impl Scheduler {
+    pub fn new(ctx: AppContext) -> Self {
+        Self {
+            ctx,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// The built-in jobs `role` runs, with the schedules `config/jobs.yml`
+    /// gives them: maintenance and the weekly review on a worker, a poll job
+    /// per polling source on a server. Jobs that find something for the
+    /// beat request one from `orchestrator`.
+    pub fn from_config(
+        ctx: AppContext,
+        orchestrator: &OrchestratorHandle,
+        role: Role,
+    ) -> anyhow::Result<Self> {
+        let mut scheduler = Self::new(ctx.clone());
+        if role.runs_worker() {
+            scheduler.add(Arc::new(RetentionJob))?;
+            scheduler.add(Arc::new(MemoryRetentionJob))?;
+            scheduler.add(Arc::new(WeeklyReviewJob::new(orchestrator.clone())))?;
+        }
+        if role.runs_server() {
+            let config = ctx.config();
+            for source in ctx.sources().iter() {
+                if let Some(IngestMode::Poll { .. }) = source.mode(&config) {
+                    let job = SourceJob::new(Arc::clone(source), orchestrator.clone());
+                    scheduler.add(Arc::new(job))?;
+                }
+            }
+        }
+        Ok(scheduler)
+    }
+
+    /// Schedule `job` per `config/jobs.yml`, falling back to its default
+    /// schedule. Jobs that are disabled or have no schedule are skipped.
+    pub fn add(&mut self, job: Arc<dyn Job>) -> anyhow::Result<()> {
+        let config = self.ctx.config();
+        let settings = config
+            .jobs
+            .jobs
+            .get(job.name())
+            .cloned()
+            .unwrap_or_default();
+        if !settings.enabled {
+            return Ok(());
+        }
+        let configured = JobSchedule::from_config(&settings)
+            .with_context(|| format!("scheduling job {}", job.name()))?;
+        let Some(schedule) = configured.or_else(|| job.default_schedule(&config)) else {
+            return Ok(());
+        };
+        self.jobs.retain(|known| known.job.name() != job.name());
+        self.jobs.push(ScheduledJob {
+            job,
+            schedule,
+            jitter: Duration::from_secs(settings.jitter_secs),
+        });
+        Ok(())
+    }
+
+    /// Start every job, returning `None` when none is scheduled.
+    pub fn spawn(self) -> Option<JoinHandle<()>> {
+        if self.jobs.is_empty() {
+            return None;
+        }
+        let board = self.ctx.jobs().clone();
+        for scheduled in &self.jobs {
+            board.jobs.write().insert(
+                scheduled.job.name().to_string(),
+                JobStatus {
+                    name: scheduled.job.name().to_string(),
+                    schedule: scheduled.schedule.to_string(),
+                    jitter_secs: scheduled.jitter.as_secs(),
+                    ..JobStatus::default()
+                },
+            );
+        }
+        let ctx = self.ctx;
+        let tasks: Vec<_> = self
+            .jobs
+            .into_iter()
+            .map(|scheduled| tokio::spawn(run_job(ctx.clone(), scheduled)))
+            .collect();
+        Some(tokio::spawn(async move {
+            for task in tasks {
+                if let Err(err) = task.await {
+                    warn!(error = ?err, "scheduled job task join failure");
+                }
+            }
+        }))
+    }
+}
+
+async fn run_job(ctx: AppContext, scheduled: ScheduledJob) {
+    let name = scheduled.job.name();
+    let board = ctx.jobs().clone();
+    let back_to_back = scheduled.schedule == JobSchedule::Every(Duration::ZERO);
+    let mut first = true;
+    let mut failed = false;
+    loop {
+        let now = ctx.now();
+        let Some(mut next) = scheduled.schedule.next_run(now, first) else {
+            warn!(job = name, schedule = %scheduled.schedule, "job schedule has no next run");
+            break;
+        };
+        first = false;
+        if failed && back_to_back {
+            next += TimeDelta::from_std(JOB_ERROR_BACKOFF).unwrap_or_default();
+        }
+        let next = next + TimeDelta::from_std(jitter(scheduled.jitter)).unwrap_or_default();
+        board.update(name, |status| status.next_run = Some(next));
+        select! {
+            _ = sleep((next - now).to_std().unwrap_or_default()) => {}
+            _ = ctx.wait_for_shutdown() => break,
+        }
+
+        let started_at = ctx.now();
+        let started = std::time::Instant::now();
+        board.update(name, |status| {
+            status.running = true;
+            status.next_run = None;
+            status.last_started_at = Some(started_at);
+        });
+        let gate = ctx.restore_gate();
+        let running = gate.read().await;
+        let result = scheduled.job.run(&ctx).await;
+        drop(running);
+        ctx.note_data_changed();
+        if let Err(err) = &result {
+            warn!(job = name, error = ?err, "scheduled job failed");
+        } else {
+            info!(job = name, "scheduled job finished");
+        }
+        board.update(name, |status| {
+            status.running = false;
+            status.last_finished_at = Some(ctx.now());
+            status.last_duration_ms = Some(started.elapsed().as_millis() as u64);
+            status.last_ok = Some(result.is_ok());
+            status.last_error = result.as_ref().err().map(|err| format!("{err:#}"));
+            status.runs += 1;
+            status.failures += u64::from(result.is_err());
+        });
+        failed = result.is_err();
+    }
+    scheduled.job.stop(&ctx).await;
+}
+
+/// A random delay of up to `max`, so jobs on many instances do not all
+/// start at the same moment.
+fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis((Uuid::new_v4().as_u128() % u128::from(max_ms + 1)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent::AgentRuntime, fixtures};
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    #[test]
+    fn cron_finds_the_next_matching_minute() {
+        let at = |y, m, d, h, min| Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap();
+        let next = |expression: &str, after| {
+            expression
+                .parse::<CronSchedule>()
+                .unwrap()
+                .next_after(after)
+                .unwrap()
+        };
+
+        // 2025-03-01 is a Saturday.
+        let now = at(2025, 3, 1, 9, 30);
+        assert_eq!(next("*/15 * * * *", now), at(2025, 3, 1, 9, 45));
+        assert_eq!(next("0 3 * * *", now), at(2025, 3, 2, 3, 0));
+        assert_eq!(next("0 9 * * 1-5", now), at(2025, 3, 3, 9, 0));
+        assert_eq!(next("30 8 * * 7", now), at(2025, 3, 2, 8, 30));
+        assert_eq!(next("0 0 29 2 *", now), at(2028, 2, 29, 0, 0));
+        // Both day fields restricted: either one matches.
+        assert_eq!(next("0 12 15 * 1", now), at(2025, 3, 3, 12, 0));
+
+        for invalid in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(invalid.parse::<CronSchedule>().is_err(), "{invalid}");
+        }
+    }
+
+    struct CountingJob(AtomicUsize);
+
+    #[async_trait]
+    impl Job for CountingJob {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn default_schedule(&self, _config: &AppConfig) -> Option<JobSchedule> {
+            Some(JobSchedule::Every(Duration::from_millis(10)))
+        }
+
+        async fn run(&self, _ctx: &AppContext) -> anyhow::Result<()> {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                bail!("first run fails");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn scheduled_jobs_run_and_report_status() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        let config = AppConfig::load_from(&root).unwrap();
+        let agent = AgentRuntime::from_app_config(&config).unwrap();
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let job = Arc::new(CountingJob(AtomicUsize::new(0)));
+        let mut scheduler = Scheduler::new(ctx.clone());
+        scheduler.add(job.clone()).unwrap();
+        let task = scheduler.spawn().unwrap();
+        while job.0.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        ctx.request_shutdown();
+        task.await.unwrap();
+
+        let statuses = ctx.jobs().statuses();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert_eq!(status.schedule, "every 10ms");
+        assert!(status.runs >= 3);
+        assert_eq!(status.failures, 1);
+        assert_eq!(status.last_ok, Some(true));
+        assert!(status.last_finished_at.is_some());
+    }
+}
@@ -0,0 +1,481 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use tokio::{select, task::JoinHandle, time::sleep};
+use tracing::warn;
+
+use crate::{
+    config::{OutboxConfig, TelegramConfig},
+    notifications,
+    state::AppContext,
+    storage::{self, OutboxMessage, OutboxStatus, OutboxTarget},
+    telegram,
+};
+
+const OUTBOX_POLL_INTERVAL_SECS: u64 = 10;
+
+/// What the outbox needs from a workspace to deliver its messages.
+#[derive(Debug, Clone, Default)]
+pub struct Channels {
+    pub telegram: Option<TelegramConfig>,
+    pub retry: OutboxConfig,
+    /// Whether webhook deliveries go into the delivery log; off while the
+    /// disk guard has paused non-essential writes.
+    pub log_deliveries: bool,
+}
+
+impl Channels {
+    /// The channels of `ctx`'s workspace as configured now.
+    pub fn of(ctx: &AppContext) -> Self {
+        let config = ctx.config();
+        Self {
+            telegram: config.telegram.clone(),
+            retry: config.outbox.clone(),
+            log_deliveries: !ctx.writes_paused(),
+        }
+    }
+}
+
+/// Retry pending outbound messages in the background.
+pub fn spawn_worker(ctx: AppContext) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let data_dir = ctx.config().data_dir.clone();
+            let channels = Channels::of(&ctx);
+
+            let gate = ctx.restore_gate();
+            let running = gate.read().await;
+            if let Err(err) = flush_due(&data_dir, &channels, ctx.now()).await {
+                warn!(error = ?err, "outbox flush failed");
+            }
+            drop(running);
+
+            select! {
+                _ = sleep(Duration::from_secs(OUTBOX_POLL_INTERVAL_SECS)) => {}
+                _ = ctx.wait_for_shutdown() => break,
+            }
+        }
+    })
+}
+
+/// Queue a Telegram message and try to deliver it right away; see
+/// [`deliver_or_queue`].
+pub async fn send_or_queue(
+    data_dir: &Path,
+    channels: &Channels,
+    chat_id: i64,
+    text: &str,
+    urgent: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<OutboxMessage> {
+    let mut message = OutboxMessage::new("telegram", chat_id, text, now);
+    message.urgent = urgent;
+    deliver_or_queue(data_dir, channels, message, now).await
+}
+
+/// Store `message` in the outbox and try to deliver it right away. A failed
+/// first attempt leaves the message pending for the worker; the returned
+/// record tells the caller which of the two happened. During the quiet
+/// hours of its channel a non-urgent message is only queued, due when the
+/// window ends.
+pub async fn deliver_or_queue(
+    data_dir: &Path,
+    channels: &Channels,
+    mut message: OutboxMessage,
+    now: DateTime<Utc>,
+) -> anyhow::Result<OutboxMessage> {
+    if let Some(until) = quiet_until(channels.telegram.as_ref(), &message, now) {
+        message.next_attempt_at = until;
+        storage::save_outbox_message(data_dir, &message)?;
+        return Ok(message);
+    }
+
+    // Keep the worker away from the message while the inline attempt runs.
+    message.next_attempt_at = now + channels.retry.retry_delay(1);
+    storage::save_outbox_message(data_dir, &message)?;
+
+    attempt(data_dir, channels, &mut message, now).await?;
+    Ok(message)
+}
+
+/// Attempt every pending message that is due. Returns how many went out.
+pub async fn flush_due(
+    data_dir: &Path,
+    channels: &Channels,
+    now: DateTime<Utc>,
+) -> anyhow::Result<usize> {
+    let mut delivered = 0;
+    for mut message in storage::due_outbox_messages(data_dir, now)? {
+        // A retry that comes due inside quiet hours waits for the window to end.
+        if let Some(until) = quiet_until(channels.telegram.as_ref(), &message, now) {
+            message.next_attempt_at = until;
+            storage::save_outbox_message(data_dir, &message)?;
+            continue;
+        }
+        attempt(data_dir, channels, &mut message, now).await?;
+        if message.status == OutboxStatus::Delivered {
+            delivered += 1;
+        }
+    }
+    Ok(delivered)
+}
+
+/// End of the quiet-hours window `message` has to wait for, if any: its
+/// channel's own, or for Telegram messages the one in `telegram.yml`.
+fn quiet_until(
+    telegram: Option<&TelegramConfig>,
+    message: &OutboxMessage,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if message.urgent {
+        return None;
+    }
+    let telegram_quiet = || {
+        telegram
+            .filter(|_| message.target.is_none())
+            .and_then(|telegram| telegram.quiet_hours.as_ref())
+    };
+    message
+        .quiet_hours
+        .as_ref()
+        .or_else(telegram_quiet)
+        .filter(|quiet| quiet.contains(now))
+        .map(|quiet| quiet.end_after(now))
+}
+
+async fn attempt(
+    data_dir: &Path,
+    channels: &Channels,
+    message: &mut OutboxMessage,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    message.attempts += 1;
+    message.updated_at = now;
+    let sent = match (&message.target, &channels.telegram) {
+        (Some(target), _) => notifications::send_to_target(
+            data_dir,
+            target,
+            &message.text,
+            message.attempts,
+            channels.log_deliveries,
+            now,
+        )
+        .await
+        .map(|()| None),
+        (None, Some(config)) => telegram::send_logged_message_with_markup(
+            data_dir,
+            config,
+            message.chat_id,
+            &message.text,
+            message.reply_markup.clone(),
+            now,
+        )
+        .await
+        .map(|result| result.message_id),
+        (None, None) => Err(anyhow!("telegram not configured")),
+    };
+    match sent {
+        Ok(provider_message_id) => {
+            message.status = OutboxStatus::Delivered;
+            message.provider_message_id = provider_message_id;
+            message.last_error = None;
+        }
+        Err(err) => {
+            warn!(
+                id = %message.id,
+                source = %message.source,
+                attempts = message.attempts,
+                error = ?err,
+                "outbound message failed"
+            );
+            message.last_error = Some(format!("{err:#}"));
+            let max_attempts = match &message.target {
+                Some(OutboxTarget::Webhook { webhook, .. }) => webhook.max_attempts,
+                _ => channels.retry.max_attempts,
+            };
+            if message.attempts >= max_attempts.max(1) {
+                message.status = OutboxStatus::Failed;
+            } else {
+                message.next_attempt_at = now + channels.retry.retry_delay(message.attempts);
+            }
+        }
+    }
+    storage::save_outbox_message(data_dir, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use crate::config::TelegramMode;
+
+    fn config(api_base: String) -> TelegramConfig {
+        TelegramConfig {
+            bot_token: "TEST".to_string(),
+            bot_token_file: None,
+            bot_token_command: None,
+            default_chat_id: None,
+            webhook_secret: None,
+            public_url: None,
+            api_base,
+            mode: TelegramMode::Webhook,
+            poll_timeout_secs: 0,
+            allowed_chat_ids: Vec::new(),
+            allowed_usernames: Vec::new(),
+            unauthorized_reply: None,
+            quiet_hours: None,
+        }
+    }
+
+    fn channels(config: &TelegramConfig) -> Channels {
+        Channels {
+            telegram: Some(config.clone()),
+            retry: OutboxConfig {
+                max_attempts: 2,
+                retry_base_secs: 30,
+                retry_max_secs: 60,
+            },
+            log_deliveries: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_send_is_retried_then_marked_failed() {
+        let server = MockServer::start_async().await;
+        let failing = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/botTEST/sendMessage");
+                then.status(500);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        let config = config(server.base_url());
+
+        let now = Utc::now();
+        let queued = send_or_queue(data_dir, &channels(&config), 5, "hello", false, now)
+            .await
+            .unwrap();
+        assert_eq!(queued.status, OutboxStatus::Pending);
+        assert_eq!(queued.attempts, 1);
+        assert!(queued.last_error.is_some());
+
+        // Not due yet: the backoff holds the message back.
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), now).await.unwrap(),
+            0
+        );
+        failing.assert_hits_async(1).await;
+
+        let later = now + chrono::Duration::seconds(31);
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), later)
+                .await
+                .unwrap(),
+            0
+        );
+        let stored = storage::load_outbox_message(data_dir, queued.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, OutboxStatus::Failed);
+        assert_eq!(stored.attempts, 2);
+
+        failing.delete_async().await;
+        let ok = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/botTEST/sendMessage");
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": {"message_id": 77}}));
+            })
+            .await;
+        let delivered = send_or_queue(data_dir, &channels(&config), 5, "again", false, later)
+            .await
+            .unwrap();
+        ok.assert_async().await;
+        assert_eq!(delivered.status, OutboxStatus::Delivered);
+        assert_eq!(delivered.provider_message_id, Some(77));
+
+        let failed =
+            storage::list_outbox_messages(data_dir, Some(OutboxStatus::Failed), 10).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, queued.id);
+        assert_eq!(
+            storage::list_outbox_messages(data_dir, None, 10)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_messages_are_quarantined_instead_of_blocking_the_flush() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        std::fs::create_dir_all(data_dir.join("outbox")).unwrap();
+        std::fs::write(data_dir.join("outbox/broken.json"), "{").unwrap();
+
+        let config = config("http://127.0.0.1:9".to_string());
+        assert!(
+            storage::list_outbox_messages(data_dir, None, 10)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(data_dir.join("outbox/broken.json").exists());
+
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), Utc::now())
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(!data_dir.join("outbox/broken.json").exists());
+        let quarantined = walkdir::WalkDir::new(data_dir.join("quarantine"))
+            .into_iter()
+            .filter_map(Result::ok)
+            .any(|entry| entry.path().ends_with("outbox/broken.json"));
+        assert!(quarantined);
+    }
+
+    #[tokio::test]
+    async fn quiet_hours_hold_non_urgent_messages() {
+        let server = MockServer::start_async().await;
+        let send = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/botTEST/sendMessage");
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": {"message_id": 1}}));
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        let config = TelegramConfig {
+            quiet_hours: Some(
+                serde_yaml::from_str("start: \"22:00\"\nend: \"07:00\"\nutc_offset_minutes: 480")
+                    .unwrap(),
+            ),
+            ..config(server.base_url())
+        };
+        // 15:30 UTC is 23:30 at UTC+8.
+        let night = DateTime::parse_from_rfc3339("2025-01-06T15:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let morning = DateTime::parse_from_rfc3339("2025-01-06T23:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let telegram = |text: &str, urgent: bool| {
+            let mut message = OutboxMessage::new("telegram", 5, text, night);
+            message.urgent = urgent;
+            message
+        };
+        let held = deliver_or_queue(
+            data_dir,
+            &channels(&config),
+            telegram("digest", false),
+            night,
+        )
+        .await
+        .unwrap();
+        assert_eq!(held.status, OutboxStatus::Pending);
+        assert_eq!(held.attempts, 0);
+        assert_eq!(held.next_attempt_at, morning);
+
+        let urgent = deliver_or_queue(data_dir, &channels(&config), telegram("pager", true), night)
+            .await
+            .unwrap();
+        assert_eq!(urgent.status, OutboxStatus::Delivered);
+        send.assert_hits_async(1).await;
+
+        let still_night = night + chrono::Duration::hours(2);
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), still_night)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), morning)
+                .await
+                .unwrap(),
+            1
+        );
+        send.assert_hits_async(2).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn quiet_hours_are_per_channel() {
+        let server = MockServer::start_async().await;
+        let slack = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/slack");
+                then.status(200);
+            })
+            .await;
+        unsafe {
+            std::env::set_var("HI_TEST_OUTBOX_SLACK", server.url("/slack"));
+        }
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        // Telegram is quiet all night; Slack has no window of its own.
+        let config = TelegramConfig {
+            quiet_hours: Some(serde_yaml::from_str("start: \"22:00\"\nend: \"07:00\"").unwrap()),
+            ..config(server.base_url())
+        };
+        let night = DateTime::parse_from_rfc3339("2025-01-06T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let target = OutboxTarget::Slack {
+            webhook_url_env: "HI_TEST_OUTBOX_SLACK".to_string(),
+        };
+
+        let sent = deliver_or_queue(
+            data_dir,
+            &channels(&config),
+            OutboxMessage::for_target(target.clone(), "build failed", night),
+            night,
+        )
+        .await
+        .unwrap();
+        assert_eq!(sent.status, OutboxStatus::Delivered);
+        slack.assert_hits_async(1).await;
+
+        let mut quiet = OutboxMessage::for_target(target, "digest", night);
+        quiet.quiet_hours = Some(serde_yaml::from_str("start: \"23:00\"\nend: \"06:00\"").unwrap());
+        let held = deliver_or_queue(data_dir, &channels(&config), quiet, night)
+            .await
+            .unwrap();
+        assert_eq!(held.status, OutboxStatus::Pending);
+        assert_eq!(
+            held.next_attempt_at.to_rfc3339(),
+            "2025-01-07T06:00:00+00:00"
+        );
+
+        // Slack's window closes before Telegram's; its message goes out then.
+        let six = held.next_attempt_at;
+        assert_eq!(
+            flush_due(data_dir, &channels(&config), six).await.unwrap(),
+            1
+        );
+        slack.assert_hits_async(2).await;
+        unsafe {
+            std::env::remove_var("HI_TEST_OUTBOX_SLACK");
+        }
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially_up_to_cap() {
+        let config = OutboxConfig::default();
+        assert_eq!(config.retry_delay(1), Duration::from_secs(30));
+        assert_eq!(config.retry_delay(3), Duration::from_secs(120));
+        assert_eq!(config.retry_delay(20), Duration::from_secs(3600));
+    }
+}
@@ -1,9 +1,15 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use tokio::fs;
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::warn;
+
+use crate::storage;
 
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct AcceptanceSummary {
@@ -15,8 +21,29 @@ pub struct AcceptanceSummary {
     pub validation_plan: Vec<ValidationEntry>,
 }
 
+/// One plan document of an [`AcceptanceOverview`].
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct AcceptanceDocument {
+    /// File stem, accepted as `?doc=` by the module and update endpoints.
+    pub id: String,
+    #[serde(flatten)]
+    pub summary: AcceptanceSummary,
+}
+
+/// All configured plans: their aggregate at the top level, whose `source`
+/// is the first document with the latest `updated_at`, and each plan under
+/// `documents`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct AcceptanceOverview {
+    #[serde(flatten)]
+    pub summary: AcceptanceSummary,
+    pub documents: Vec<AcceptanceDocument>,
+}
+
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct ModuleAcceptanceSummary {
+    /// [`AcceptanceDocument::id`] of the plan the module was found in.
+    pub doc: String,
     pub module: String,
     pub metrics: ModuleAcceptanceMetrics,
     pub tasks: Vec<TaskMatrixEntry>,
@@ -41,7 +68,7 @@ pub struct TodoItem {
     pub label: String,
 }
 
-#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct AcceptanceMetrics {
     pub modules_total: usize,
     pub modules_completed: usize,
@@ -51,13 +78,22 @@ pub struct AcceptanceMetrics {
     pub overall_status: AcceptanceOverallStatus,
 }
 
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AcceptanceOverallStatus {
     Complete,
     InProgress,
 }
 
+/// Aggregate [`AcceptanceMetrics`] as of `recorded_at`, one line of
+/// `metrics/acceptance/YYYY-MM.jsonl` under the data dir.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AcceptanceSnapshot {
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub metrics: AcceptanceMetrics,
+}
+
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct TaskMatrixEntry {
     pub module: String,
@@ -89,6 +125,25 @@ pub async fn load_acceptance_summary(doc_path: &Path) -> anyhow::Result<Acceptan
         validation_plan,
     } = parse_acceptance_plan(&content);
 
+    Ok(summarize(
+        AcceptanceSource {
+            doc_path: doc_path.display().to_string(),
+            updated_at,
+        },
+        task_matrix,
+        completed_todos,
+        pending_todos,
+        validation_plan,
+    ))
+}
+
+fn summarize(
+    source: AcceptanceSource,
+    task_matrix: Vec<TaskMatrixEntry>,
+    completed_todos: Vec<TodoItem>,
+    pending_todos: Vec<TodoItem>,
+    validation_plan: Vec<ValidationEntry>,
+) -> AcceptanceSummary {
     let ModuleCompletionCounts {
         total: modules_total,
         completed: modules_completed,
@@ -103,19 +158,217 @@ pub async fn load_acceptance_summary(doc_path: &Path) -> anyhow::Result<Acceptan
         overall_status: determine_overall_status(&task_matrix, &pending_todos),
     };
 
-    Ok(AcceptanceSummary {
-        source: AcceptanceSource {
-            doc_path: doc_path.display().to_string(),
-            updated_at,
-        },
+    AcceptanceSummary {
+        source,
         metrics,
         task_matrix,
         completed_todos,
         pending_todos,
         validation_plan,
+    }
+}
+
+/// [`AcceptanceDocument::id`] of the plan at `path`.
+pub fn doc_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Files under `root` matching `patterns` (see `AcceptanceConfig::docs`),
+/// in pattern order and without duplicates.
+pub fn resolve_plan_docs(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let mut docs = Vec::new();
+    for pattern in patterns {
+        let path = root.join(pattern.trim());
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let mut matched = if !name.contains(['*', '?']) {
+            vec![path.clone()]
+        } else {
+            let dir = path.parent().unwrap_or(root);
+            std::fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| {
+                            storage::wildcard_match(name, &entry.file_name().to_string_lossy())
+                        })
+                        .map(|entry| entry.path())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        matched.retain(|path| path.is_file() && !docs.contains(path));
+        matched.sort();
+        docs.extend(matched);
+    }
+    docs
+}
+
+/// [`resolve_plan_docs`], narrowed to the plan named `doc` (an id or a
+/// path relative to `root`) when given.
+pub fn select_plan_docs(root: &Path, patterns: &[String], doc: Option<&str>) -> Vec<PathBuf> {
+    let docs = resolve_plan_docs(root, patterns);
+    match doc.map(str::trim).filter(|doc| !doc.is_empty()) {
+        None => docs,
+        Some(doc) => docs
+            .into_iter()
+            .filter(|path| doc_id(path) == doc || *path == root.join(doc))
+            .collect(),
+    }
+}
+
+/// Load every plan in `docs` and aggregate them.
+pub async fn load_acceptance_overview(docs: &[PathBuf]) -> anyhow::Result<AcceptanceOverview> {
+    if docs.is_empty() {
+        anyhow::bail!("no acceptance plan documents found");
+    }
+    let mut documents = Vec::with_capacity(docs.len());
+    for doc_path in docs {
+        documents.push(AcceptanceDocument {
+            id: doc_id(doc_path),
+            summary: load_acceptance_summary(doc_path).await?,
+        });
+    }
+
+    let mut source = documents[0].summary.source.clone();
+    source.updated_at = documents
+        .iter()
+        .filter_map(|document| document.summary.source.updated_at)
+        .max();
+    let mut task_matrix = Vec::new();
+    let mut completed_todos = Vec::new();
+    let mut pending_todos = Vec::new();
+    let mut validation_plan = Vec::new();
+    for document in &documents {
+        let summary = &document.summary;
+        task_matrix.extend(summary.task_matrix.iter().cloned());
+        completed_todos.extend(summary.completed_todos.iter().cloned());
+        pending_todos.extend(summary.pending_todos.iter().cloned());
+        validation_plan.extend(summary.validation_plan.iter().cloned());
+    }
+
+    Ok(AcceptanceOverview {
+        summary: summarize(
+            source,
+            task_matrix,
+            completed_todos,
+            pending_todos,
+            validation_plan,
+        ),
+        documents,
     })
 }
 
+const ACCEPTANCE_METRICS_DIR: &str = "metrics/acceptance";
+
+/// Aggregate the plans matched by `patterns` under `root` and append their
+/// metrics to the history when they differ from the last snapshot. Returns
+/// whether a snapshot was written; nothing is recorded without any plan.
+pub async fn snapshot_acceptance_metrics(
+    root: &Path,
+    patterns: &[String],
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    let docs = resolve_plan_docs(root, patterns);
+    if docs.is_empty() {
+        return Ok(false);
+    }
+    let overview = load_acceptance_overview(&docs).await?;
+    record_acceptance_snapshot(data_dir, &overview.summary.metrics, now).await
+}
+
+/// Append `metrics` unless they equal the latest recorded snapshot.
+pub async fn record_acceptance_snapshot(
+    data_dir: &Path,
+    metrics: &AcceptanceMetrics,
+    now: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    let history = load_acceptance_history(data_dir, None, Some(1)).await?;
+    if history.last().is_some_and(|last| &last.metrics == metrics) {
+        return Ok(false);
+    }
+
+    let dir = data_dir.join(ACCEPTANCE_METRICS_DIR);
+    fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("creating {}", dir.display()))?;
+    let path = dir.join(format!("{}.jsonl", now.format("%Y-%m")));
+    let snapshot = AcceptanceSnapshot {
+        recorded_at: now,
+        metrics: metrics.clone(),
+    };
+    let mut line = serde_json::to_string(&snapshot)?;
+    line.push('\n');
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("opening {}", path.display()))?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(true)
+}
+
+/// Recorded snapshots, oldest first. `since` drops earlier ones and
+/// `limit` keeps only the most recent.
+pub async fn load_acceptance_history(
+    data_dir: &Path,
+    since: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+) -> anyhow::Result<Vec<AcceptanceSnapshot>> {
+    if limit == Some(0) {
+        return Ok(Vec::new());
+    }
+    let dir = data_dir.join(ACCEPTANCE_METRICS_DIR);
+    let mut files = Vec::new();
+    let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("reading {}", dir.display())),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            files.push(path);
+        }
+    }
+    // `YYYY-MM` names sort chronologically; walk newest first so `limit`
+    // can stop early.
+    files.sort();
+
+    let mut snapshots = Vec::new();
+    'files: for path in files.iter().rev() {
+        let content = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("reading {}", path.display()))?;
+        let mut month = Vec::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<AcceptanceSnapshot>(line) {
+                Ok(snapshot) => month.push(snapshot),
+                Err(err) => {
+                    warn!(error = ?err, path = %path.display(), "skipping malformed acceptance snapshot")
+                }
+            }
+        }
+        for snapshot in month.into_iter().rev() {
+            if since.is_some_and(|since| snapshot.recorded_at < since) {
+                break 'files;
+            }
+            snapshots.push(snapshot);
+            if limit.is_some_and(|limit| snapshots.len() >= limit) {
+                break 'files;
+            }
+        }
+    }
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
 pub async fn load_module_acceptance_summary(
     doc_path: &Path,
     module_query: &str,
@@ -155,12 +408,170 @@ pub async fn load_module_acceptance_summary(
     };
 
     Ok(Some(ModuleAcceptanceSummary {
+        doc: doc_id(doc_path),
         module: module_name,
         metrics,
         tasks,
     }))
 }
 
+/// Set the status cell of `task` in the task matrix of `module` (matched
+/// like [`load_module_acceptance_summary`]), rewriting the plan in place.
+/// `None` when the module or task is not in the matrix.
+pub async fn update_task_status(
+    doc_path: &Path,
+    module_query: &str,
+    task: &str,
+    status: &str,
+) -> anyhow::Result<Option<ModuleAcceptanceSummary>> {
+    let content = read_plan(doc_path).await?;
+    let Some((module, updated)) = set_task_status(&content, module_query, task, status) else {
+        return Ok(None);
+    };
+    storage::write_atomic_async(doc_path, updated)
+        .await
+        .with_context(|| format!("failed to write acceptance plan at {}", doc_path.display()))?;
+    load_module_acceptance_summary(doc_path, &module).await
+}
+
+/// Check (`done`) or uncheck the TODO bullet labelled `label`, moving it to
+/// the end of the completed (4.1) or pending (4.2) list. `None` when no
+/// bullet has that label.
+pub async fn update_todo(
+    doc_path: &Path,
+    label: &str,
+    done: bool,
+) -> anyhow::Result<Option<AcceptanceSummary>> {
+    let content = read_plan(doc_path).await?;
+    let Some(updated) = set_todo_done(&content, label, done) else {
+        return Ok(None);
+    };
+    storage::write_atomic_async(doc_path, updated)
+        .await
+        .with_context(|| format!("failed to write acceptance plan at {}", doc_path.display()))?;
+    load_acceptance_summary(doc_path).await.map(Some)
+}
+
+async fn read_plan(doc_path: &Path) -> anyhow::Result<String> {
+    fs::read_to_string(doc_path)
+        .await
+        .with_context(|| format!("failed to read acceptance plan at {}", doc_path.display()))
+}
+
+/// The section each line belongs to, using the same headings as
+/// [`parse_acceptance_plan`]. Heading lines themselves map to `None`.
+fn line_sections(lines: &[&str]) -> Vec<PlanSection> {
+    let mut current = PlanSection::None;
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with("## 2.") {
+                current = PlanSection::TaskMatrix;
+            } else if trimmed.starts_with("### 4.1") {
+                current = PlanSection::Completed;
+            } else if trimmed.starts_with("### 4.2") {
+                current = PlanSection::Pending;
+            } else if trimmed.starts_with("## ") || trimmed.starts_with("### ") {
+                current = PlanSection::None;
+            } else {
+                return current;
+            }
+            PlanSection::None
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanSection {
+    None,
+    TaskMatrix,
+    Completed,
+    Pending,
+}
+
+fn join_lines(lines: &[String], original: &str) -> String {
+    let mut joined = lines.join("\n");
+    if original.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+fn set_task_status(
+    markdown: &str,
+    module_query: &str,
+    task: &str,
+    status: &str,
+) -> Option<(String, String)> {
+    let parsed = parse_acceptance_plan(markdown);
+    let module = resolve_module_name(&parsed.task_matrix, module_query)?;
+    let lines: Vec<&str> = markdown.lines().collect();
+    let sections = line_sections(&lines);
+    let index = lines.iter().zip(&sections).position(|(line, section)| {
+        *section == PlanSection::TaskMatrix
+            && parse_task_matrix_row(line.trim()).is_some_and(|row| {
+                row.module.eq_ignore_ascii_case(module.trim()) && row.task == task.trim()
+            })
+    })?;
+
+    let mut updated: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    let row = parse_task_matrix_row(lines[index].trim())?;
+    updated[index] = format!("| {} | {} | {} |", row.module, row.task, status.trim());
+    Some((module, join_lines(&updated, markdown)))
+}
+
+fn set_todo_done(markdown: &str, label: &str, done: bool) -> Option<String> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let sections = line_sections(&lines);
+    let is_todo =
+        |section: PlanSection| matches!(section, PlanSection::Completed | PlanSection::Pending);
+    let index = lines.iter().zip(&sections).position(|(line, section)| {
+        is_todo(*section) && parse_bullet(line.trim()).as_deref() == Some(label.trim())
+    })?;
+
+    let (target, checkbox) = if done {
+        (PlanSection::Completed, "[x]")
+    } else {
+        (PlanSection::Pending, "[ ]")
+    };
+    let bullet = format!("- {checkbox} {}", label.trim());
+    let mut updated: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    if sections[index] == target {
+        updated[index] = bullet;
+        return Some(join_lines(&updated, markdown));
+    }
+
+    updated.remove(index);
+    let mut sections = sections;
+    sections.remove(index);
+    let last_bullet = (0..updated.len())
+        .rev()
+        .find(|&i| sections[i] == target && updated[i].trim().starts_with('-'));
+    let insert_at = match last_bullet {
+        Some(i) => i + 1,
+        None => {
+            // Right below the heading, after its blank line if it has one.
+            let heading = if done { "### 4.1" } else { "### 4.2" };
+            let heading = updated
+                .iter()
+                .position(|line| line.trim().starts_with(heading))?;
+            match updated.get(heading + 1) {
+                Some(next) if next.trim().is_empty() => heading + 2,
+                _ => heading + 1,
+            }
+        }
+    };
+    updated.insert(insert_at, bullet);
+    if updated
+        .get(insert_at + 1)
+        .is_some_and(|next| next.trim().starts_with('#'))
+    {
+        updated.insert(insert_at + 1, String::new());
+    }
+    Some(join_lines(&updated, markdown))
+}
+
 struct ParsedAcceptancePlan {
     task_matrix: Vec<TaskMatrixEntry>,
     completed_todos: Vec<TodoItem>,
@@ -731,4 +1142,182 @@ mod tests {
         assert_eq!(total, 3);
         assert_eq!(completed, 2);
     }
+
+    #[test]
+    fn set_task_status_rewrites_only_the_matching_row() {
+        let markdown = "## 2. 任务矩阵\n| 模块 | 任务 | 状态 |\n| --- | --- | --- |\n| API | Build endpoint | 进行中 |\n| API | Wire auth | 进行中 |\n\n## 3. 其他\n| API | Build endpoint | 进行中 |\n";
+
+        let (module, updated) =
+            set_task_status(markdown, "api", "Build endpoint", "✅").expect("row updated");
+        assert_eq!(module, "API");
+        assert_eq!(
+            updated,
+            markdown.replacen(
+                "| API | Build endpoint | 进行中 |",
+                "| API | Build endpoint | ✅ |",
+                1
+            )
+        );
+        assert!(set_task_status(markdown, "API", "Missing", "✅").is_none());
+        assert!(set_task_status(markdown, "ops", "Build endpoint", "✅").is_none());
+    }
+
+    #[test]
+    fn set_todo_done_moves_bullets_between_lists() {
+        let markdown = "### 4.1 已完成清单\n- [x] Done A\n\n### 4.2 进行中/待定\n- [ ] Pending B\n- Pending C\n\n## 5. 验证方案概览\n";
+
+        let checked = set_todo_done(markdown, "Pending B", true).expect("checked");
+        assert_eq!(
+            checked,
+            "### 4.1 已完成清单\n- [x] Done A\n- [x] Pending B\n\n### 4.2 进行中/待定\n- Pending C\n\n## 5. 验证方案概览\n"
+        );
+        let ParsedAcceptancePlan {
+            completed_todos,
+            pending_todos,
+            ..
+        } = parse_acceptance_plan(&checked);
+        assert_eq!(completed_todos.len(), 2);
+        assert_eq!(pending_todos.len(), 1);
+
+        let unchecked = set_todo_done(&checked, "Done A", false).expect("unchecked");
+        assert!(unchecked.contains("### 4.2 进行中/待定\n- Pending C\n- [ ] Done A\n"));
+
+        // Already in place: only the checkbox is normalized.
+        let same = set_todo_done(markdown, "Pending C", false).expect("normalized");
+        assert!(same.contains("\n- [ ] Pending C\n"));
+
+        let emptied = set_todo_done(
+            "### 4.1 已完成清单\n\n### 4.2 进行中/待定\n- [ ] Only\n",
+            "Only",
+            true,
+        )
+        .expect("moved");
+        assert_eq!(
+            emptied,
+            "### 4.1 已完成清单\n\n- [x] Only\n\n### 4.2 进行中/待定\n"
+        );
+        assert!(set_todo_done(markdown, "Unknown", true).is_none());
+    }
+
+    #[tokio::test]
+    async fn overview_aggregates_every_matching_document() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("docs/plans")).unwrap();
+        std::fs::write(
+            root.join("docs/main.md"),
+            "## 2. 任务矩阵\n| 模块 | 任务 | 状态 |\n| --- | --- | --- |\n| API | Build endpoint | ✅ |\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docs/plans/ops.md"),
+            "## 2. 任务矩阵\n| 模块 | 任务 | 状态 |\n| --- | --- | --- |\n| Ops | Harden deploy | 进行中 |\n\n### 4.2 进行中/待定\n- [ ] Rotate keys\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("docs/plans/notes.txt"), "ignored").unwrap();
+
+        let patterns = vec![
+            "docs/main.md".to_string(),
+            "docs/plans/*.md".to_string(),
+            "docs/*.md".to_string(),
+            "docs/missing.md".to_string(),
+        ];
+        let docs = resolve_plan_docs(root, &patterns);
+        assert_eq!(
+            docs,
+            vec![root.join("docs/main.md"), root.join("docs/plans/ops.md")]
+        );
+
+        let overview = load_acceptance_overview(&docs).await.expect("overview");
+        assert_eq!(overview.documents.len(), 2);
+        assert_eq!(overview.documents[1].id, "ops");
+        assert_eq!(overview.summary.task_matrix.len(), 2);
+        assert_eq!(overview.summary.metrics.modules_total, 2);
+        assert_eq!(overview.summary.metrics.modules_completed, 1);
+        assert_eq!(overview.summary.metrics.todos_pending, 1);
+        assert_eq!(
+            overview.summary.source.doc_path,
+            root.join("docs/main.md").display().to_string()
+        );
+        assert_eq!(
+            overview.documents[0].summary.metrics.overall_status,
+            AcceptanceOverallStatus::Complete
+        );
+
+        assert_eq!(
+            select_plan_docs(root, &patterns, Some("ops")),
+            vec![root.join("docs/plans/ops.md")]
+        );
+        assert_eq!(
+            select_plan_docs(root, &patterns, Some("docs/main.md")),
+            vec![root.join("docs/main.md")]
+        );
+        assert!(select_plan_docs(root, &patterns, Some("nope")).is_empty());
+        assert!(load_acceptance_overview(&[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn acceptance_history_records_only_changes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let data_dir = tmp.path();
+        let metrics = AcceptanceMetrics {
+            modules_total: 2,
+            modules_completed: 1,
+            todos_completed: 1,
+            todos_pending: 3,
+            validation_steps: 2,
+            overall_status: AcceptanceOverallStatus::InProgress,
+        };
+        let at = |raw: &str| raw.parse::<DateTime<Utc>>().unwrap();
+
+        assert!(
+            record_acceptance_snapshot(data_dir, &metrics, at("2025-01-30T09:00:00Z"))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !record_acceptance_snapshot(data_dir, &metrics, at("2025-01-31T09:00:00Z"))
+                .await
+                .unwrap()
+        );
+        let done = AcceptanceMetrics {
+            modules_completed: 2,
+            todos_completed: 4,
+            todos_pending: 0,
+            overall_status: AcceptanceOverallStatus::Complete,
+            ..metrics.clone()
+        };
+        assert!(
+            record_acceptance_snapshot(data_dir, &done, at("2025-02-03T09:00:00Z"))
+                .await
+                .unwrap()
+        );
+        assert!(data_dir.join("metrics/acceptance/2025-01.jsonl").exists());
+        assert!(data_dir.join("metrics/acceptance/2025-02.jsonl").exists());
+
+        let history = load_acceptance_history(data_dir, None, None).await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|snapshot| snapshot.recorded_at)
+                .collect::<Vec<_>>(),
+            vec![at("2025-01-30T09:00:00Z"), at("2025-02-03T09:00:00Z")]
+        );
+        assert_eq!(history[1].metrics, done);
+
+        let recent = load_acceptance_history(data_dir, Some(at("2025-02-01T00:00:00Z")), None)
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        let latest = load_acceptance_history(data_dir, None, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(latest, recent);
+        assert!(
+            load_acceptance_history(&data_dir.join("missing"), None, None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
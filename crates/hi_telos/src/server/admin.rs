@@ -0,0 +1,441 @@
+use std::{fs::File, path::PathBuf};
+
+use anyhow::Context;
+use axum::{
+    Json, Router,
+    body::{self, Body},
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    fs,
+    io::{self, AsyncWriteExt},
+    task,
+};
+use tokio_stream::StreamExt;
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tracing::{info, warn};
+
+use crate::{
+    backup::{self, BackupInfo, BackupManifest, StagingDir},
+    export::{self, ImportReport},
+    storage,
+};
+
+use super::ServerState;
+
+/// Uploaded archives are streamed to disk, up to this size.
+const UPLOAD_LIMIT_BYTES: u64 = 1024 * 1024 * 1024;
+/// The `{"file_name": "..."}` form of a restore request.
+const RESTORE_REQUEST_LIMIT_BYTES: usize = 64 * 1024;
+const UPLOAD_FILE_NAME: &str = "upload.tar.gz";
+/// Buffer between the blocking tar writer and the response body.
+const EXPORT_PIPE_BYTES: usize = 64 * 1024;
+
+pub fn router() -> Router<ServerState> {
+    Router::new()
+        .route("/api/admin/backup", post(create_backup))
+        .route("/api/admin/backups", get(list_backups))
+        .route("/api/admin/retention", get(retention_report))
+        .route("/api/admin/storage", get(storage_report))
+        .route("/api/admin/restore", post(restore_backup))
+        .route("/api/export", get(export_workspace))
+        .route("/api/import", post(import_workspace))
+}
+
+fn admin_error(status: StatusCode, err: anyhow::Error) -> Response {
+    (status, Json(json!({ "error": format!("{err:#}") }))).into_response()
+}
+
+/// Stream an uploaded archive into a staging dir in the data dir rather
+/// than memory. The file goes away with the returned dir.
+async fn receive_upload(data_dir: PathBuf, body: Body) -> Result<(StagingDir, PathBuf), Response> {
+    let staging = task::spawn_blocking(move || StagingDir::for_upload(&data_dir))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .map_err(|err| admin_error(StatusCode::INTERNAL_SERVER_ERROR, err))?;
+    let path = staging.path().join(UPLOAD_FILE_NAME);
+    let mut file = fs::File::create(&path)
+        .await
+        .map_err(|err| admin_error(StatusCode::INTERNAL_SERVER_ERROR, err.into()))?;
+
+    let mut received = 0;
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|err| admin_error(StatusCode::BAD_REQUEST, err.into()))?;
+        received += chunk.len() as u64;
+        if received > UPLOAD_LIMIT_BYTES {
+            return Err(admin_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                anyhow::anyhow!("upload exceeds {UPLOAD_LIMIT_BYTES} bytes"),
+            ));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|err| admin_error(StatusCode::INTERNAL_SERVER_ERROR, err.into()))?;
+    }
+    if received == 0 {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+    file.flush()
+        .await
+        .map_err(|err| admin_error(StatusCode::INTERNAL_SERVER_ERROR, err.into()))?;
+    Ok((staging, path))
+}
+
+/// Swapping the data dir pauses beats, which only works in the process
+/// running them.
+fn requires_worker(state: &ServerState) -> Option<Response> {
+    let role = state.ctx().config().server.role;
+    (!role.runs_worker()).then(|| {
+        admin_error(
+            StatusCode::CONFLICT,
+            anyhow::anyhow!(
+                "not available in the {} role; use a process running the orchestrator",
+                role.as_str()
+            ),
+        )
+    })
+}
+
+async fn create_backup(State(state): State<ServerState>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let now = state.ctx().now();
+    match task::spawn_blocking(move || backup::create_snapshot(&data_dir, now)).await {
+        Ok(Ok(snapshot)) => {
+            info!(file = %snapshot.file_name, files = snapshot.files, "created data backup");
+            (StatusCode::CREATED, Json(snapshot)).into_response()
+        }
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to create data backup");
+            admin_error(StatusCode::INTERNAL_SERVER_ERROR, err)
+        }
+        Err(err) => {
+            warn!(error = ?err, "backup task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BackupListResponse {
+    backups: Vec<BackupInfo>,
+}
+
+async fn list_backups(State(state): State<ServerState>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    match task::spawn_blocking(move || backup::list_snapshots(&data_dir)).await {
+        Ok(Ok(backups)) => Json(BackupListResponse { backups }).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to list data backups");
+            admin_error(StatusCode::INTERNAL_SERVER_ERROR, err)
+        }
+        Err(err) => {
+            warn!(error = ?err, "backup listing task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// What the configured retention policy would prune right now. Nothing is
+/// deleted; the maintenance task does that on its own schedule.
+async fn retention_report(State(state): State<ServerState>) -> Response {
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    let policy = config.retention.policy.clone();
+    drop(config);
+    let now = state.ctx().now();
+    match task::spawn_blocking(move || storage::apply_retention(&data_dir, &policy, now, true))
+        .await
+    {
+        Ok(Ok(report)) => Json(report).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to compute retention report");
+            admin_error(StatusCode::INTERNAL_SERVER_ERROR, err)
+        }
+        Err(err) => {
+            warn!(error = ?err, "retention report task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StorageReport {
+    #[serde(flatten)]
+    usage: storage::StorageUsage,
+    min_free_mb: u64,
+    writes_paused: bool,
+}
+
+async fn storage_report(State(state): State<ServerState>) -> Response {
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    let min_free_mb = config.disk.min_free_mb;
+    drop(config);
+    let writes_paused = state.ctx().writes_paused();
+    match task::spawn_blocking(move || storage::storage_usage(&data_dir)).await {
+        Ok(Ok(usage)) => Json(StorageReport {
+            usage,
+            min_free_mb,
+            writes_paused,
+        })
+        .into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to measure data dir usage");
+            admin_error(StatusCode::INTERNAL_SERVER_ERROR, err)
+        }
+        Err(err) => {
+            warn!(error = ?err, "storage report task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreRequest {
+    /// Name of a snapshot in `data/backups`.
+    file_name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreResponse {
+    manifest: BackupManifest,
+    /// Snapshot of the data replaced by the restore.
+    pre_restore_backup: String,
+    queued: usize,
+}
+
+/// Restore a snapshot: either `{"file_name": "..."}` naming one in
+/// `data/backups`, or the `.tar.gz` itself as the request body, which is
+/// streamed to disk first. Beats, the outbox worker and scheduled jobs are
+/// paused for the duration and the in-memory queue is reloaded afterwards.
+async fn restore_backup(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    if let Some(response) = requires_worker(&state) {
+        return response;
+    }
+    let data_dir = state.ctx().config().data_dir.clone();
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+
+    let (upload, path) = if is_json {
+        let body = match body::to_bytes(body, RESTORE_REQUEST_LIMIT_BYTES).await {
+            Ok(body) => body,
+            Err(err) => return admin_error(StatusCode::BAD_REQUEST, err.into()),
+        };
+        let request: RestoreRequest = match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(err) => return admin_error(StatusCode::BAD_REQUEST, err.into()),
+        };
+        match backup::snapshot_path(&data_dir, &request.file_name) {
+            Ok(path) => (None, path),
+            Err(err) => return admin_error(StatusCode::NOT_FOUND, err),
+        }
+    } else {
+        match receive_upload(data_dir.clone(), body).await {
+            Ok((upload, path)) => (Some(upload), path),
+            Err(response) => return response,
+        }
+    };
+
+    let gate = state.ctx().beat_gate();
+    let _paused = gate.lock().await;
+    let restore_gate = state.ctx().restore_gate();
+    let _swapping = restore_gate.write().await;
+
+    let restore_dir = data_dir.clone();
+    let now = state.ctx().now();
+    let restored = task::spawn_blocking(move || {
+        let archive = File::open(&path).with_context(|| format!("opening {:?}", path))?;
+        let staged = backup::stage_snapshot(&restore_dir, archive)?;
+        drop(upload);
+        let pre_restore = backup::create_snapshot(&restore_dir, now)?;
+        let manifest = staged.apply(&restore_dir)?;
+        // Snapshots from older builds are upgraded right away.
+        storage::migrate_data_dir(&restore_dir)?;
+        anyhow::Ok((pre_restore, manifest))
+    })
+    .await;
+    let (pre_restore, manifest) = match restored {
+        Ok(Ok(restored)) => restored,
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to restore data backup");
+            return admin_error(StatusCode::UNPROCESSABLE_ENTITY, err);
+        }
+        Err(err) => {
+            warn!(error = ?err, "restore task join failure");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let queued = reload_queue(&state, "restore");
+    info!(
+        files = manifest.files,
+        created_at = %manifest.created_at,
+        queued,
+        "restored data backup"
+    );
+
+    Json(RestoreResponse {
+        manifest,
+        pre_restore_backup: pre_restore.file_name,
+        queued,
+    })
+    .into_response()
+}
+
+/// Replace the in-memory queue with what is on disk after the data dir was
+/// swapped out underneath it.
+fn reload_queue(state: &ServerState, after: &str) -> usize {
+    let data_dir = state.ctx().config().data_dir.clone();
+    match storage::scan_queue(&data_dir) {
+        Ok(records) => {
+            let intents = state.ctx().intents();
+            let mut queue = intents.write();
+            queue.clear();
+            let queued = records.len();
+            for record in records {
+                queue.enqueue(record.intent);
+            }
+            queue.pin(&storage::load_queue_order(&data_dir));
+            queued
+        }
+        Err(err) => {
+            warn!(error = ?err, after, "failed to reload queue");
+            0
+        }
+    }
+}
+
+/// Download a portable workspace archive; see [`export::prepare_export`].
+/// The archive is streamed as it is written rather than built in memory,
+/// so a failure after the headers went out shows up as a truncated body.
+async fn export_workspace(State(state): State<ServerState>) -> Response {
+    let (config_dir, data_dir) = {
+        let config = state.ctx().config();
+        (config.config_dir.clone(), config.data_dir.clone())
+    };
+    let now = state.ctx().now();
+    let prepare_dir = data_dir.clone();
+    let prepared =
+        match task::spawn_blocking(move || export::prepare_export(&config_dir, &prepare_dir, now))
+            .await
+        {
+            Ok(Ok(prepared)) => prepared,
+            Ok(Err(err)) => {
+                warn!(error = ?err, "failed to export workspace");
+                return admin_error(StatusCode::INTERNAL_SERVER_ERROR, err);
+            }
+            Err(err) => {
+                warn!(error = ?err, "export task join failure");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+    info!(
+        data_files = prepared.manifest.data_files,
+        config_files = prepared.manifest.config_files.len(),
+        redacted = prepared.manifest.redacted.len(),
+        "exporting workspace"
+    );
+
+    let (reader, writer) = io::duplex(EXPORT_PIPE_BYTES);
+    let writer = SyncIoBridge::new(writer);
+    task::spawn_blocking(move || {
+        if let Err(err) = prepared.write_to(&data_dir, writer) {
+            warn!(error = ?err, "failed to stream workspace export");
+        }
+    });
+
+    let file_name = format!("hi-export-{}.tar.gz", now.format("%Y%m%dT%H%M%SZ"));
+    (
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(reader)),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Serialize)]
+struct ImportResponse {
+    #[serde(flatten)]
+    report: ImportReport,
+    /// Snapshot of the data replaced by the import.
+    pre_import_backup: String,
+    queued: usize,
+}
+
+/// Load an archive from `GET /api/export` (the request body, streamed to
+/// disk first). Beats, the outbox worker and scheduled jobs are paused, the
+/// data dir is snapshotted first, and the result is migrated to the current
+/// schema and reloaded into the queue.
+async fn import_workspace(State(state): State<ServerState>, body: Body) -> Response {
+    if let Some(response) = requires_worker(&state) {
+        return response;
+    }
+    let (config_dir, data_dir) = {
+        let config = state.ctx().config();
+        (config.config_dir.clone(), config.data_dir.clone())
+    };
+    let (upload, path) = match receive_upload(data_dir.clone(), body).await {
+        Ok(received) => received,
+        Err(response) => return response,
+    };
+
+    let gate = state.ctx().beat_gate();
+    let _paused = gate.lock().await;
+    let restore_gate = state.ctx().restore_gate();
+    let _swapping = restore_gate.write().await;
+
+    let now = state.ctx().now();
+    let imported = task::spawn_blocking(move || {
+        let archive = File::open(&path).with_context(|| format!("opening {:?}", path))?;
+        let staged = export::stage_import(&data_dir, archive)?;
+        drop(upload);
+        let pre_import = backup::create_snapshot(&data_dir, now)?;
+        let report = staged.apply(&config_dir, &data_dir)?;
+        storage::migrate_data_dir(&data_dir)?;
+        anyhow::Ok((pre_import, report))
+    })
+    .await;
+    let (pre_import, report) = match imported {
+        Ok(Ok(imported)) => imported,
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to import workspace");
+            return admin_error(StatusCode::UNPROCESSABLE_ENTITY, err);
+        }
+        Err(err) => {
+            warn!(error = ?err, "import task join failure");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let queued = reload_queue(&state, "import");
+    info!(
+        data_files = report.data_files,
+        config_written = report.config_written.len(),
+        queued,
+        "imported workspace"
+    );
+
+    Json(ImportResponse {
+        report,
+        pre_import_backup: pre_import.file_name,
+        queued,
+    })
+    .into_response()
+}
@@ -0,0 +1,81 @@
+use axum::{
+    Router,
+    extract::Path,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use sha2::{Digest, Sha256};
+
+use super::{ServerState, conditional::Validators};
+
+/// Static files for the `/ui` pages, compiled into the binary so the server
+/// has no runtime asset directory to find.
+const ASSETS: &[(&str, &str, &str)] = &[
+    (
+        "theme.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/theme.js"),
+    ),
+    (
+        "ui.css",
+        "text/css; charset=utf-8",
+        include_str!("../../assets/ui.css"),
+    ),
+    (
+        "common.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/common.js"),
+    ),
+    (
+        "messages.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/messages.js"),
+    ),
+    (
+        "chat.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/chat.js"),
+    ),
+    (
+        "intents.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/intents.js"),
+    ),
+    (
+        "markdown.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/markdown.js"),
+    ),
+    (
+        "logs.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../../assets/logs.js"),
+    ),
+];
+
+pub fn router() -> Router<ServerState> {
+    Router::new().route("/ui/assets/:name", get(serve_asset))
+}
+
+async fn serve_asset(Path(name): Path<String>, headers: HeaderMap) -> Response {
+    let Some(&(_, content_type, body)) = ASSETS.iter().find(|(asset, _, _)| *asset == name) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    // Assets only change with the binary, so a content hash is a stable tag.
+    let digest = hex::encode(Sha256::digest(body.as_bytes()));
+    let validators = Validators {
+        etag: format!("\"{}\"", &digest[..16]),
+        last_modified: None,
+    };
+    if validators.not_modified(&headers) {
+        return validators.not_modified_response();
+    }
+
+    let mut response = body.into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    validators.apply(response)
+}
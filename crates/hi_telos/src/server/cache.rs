@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use parking_lot::Mutex;
+
+use super::acceptance::AcceptanceOverview;
+use crate::storage::{SpEntry, SpEntryPage, SpEntryQuery};
+
+/// How long a per-query value is reused at most; bounds how stale values
+/// computed against the current time (SP scores decay) can get.
+const KEYED_CACHE_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Distinct queries kept per cache; past it the cache starts over.
+const KEYED_CACHE_MAX_KEYS: usize = 64;
+
+/// The size and mtime of each file a cached value was read from, so writes
+/// elsewhere in the data dir leave it warm. A value is reused while its
+/// stamp is unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct FileStamp {
+    files: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+}
+
+impl FileStamp {
+    pub(super) fn of(paths: &[PathBuf]) -> Self {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let stat = fs::metadata(path)
+                    .ok()
+                    .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+                (path.clone(), stat)
+            })
+            .collect();
+        Self { files }
+    }
+}
+
+/// One parsed value, reloaded once any file it was read from changes.
+#[derive(Debug)]
+pub(super) struct ReadCache<T> {
+    slot: Mutex<Option<(FileStamp, T)>>,
+}
+
+impl<T> Default for ReadCache<T> {
+    fn default() -> Self {
+        Self {
+            slot: Mutex::new(None),
+        }
+    }
+}
+
+impl<T: Clone> ReadCache<T> {
+    /// The cached value while `paths` are unchanged, else what `load`
+    /// returns. The stamp is taken before loading, so a write racing the
+    /// load leaves a stale stamp and the next call reloads.
+    pub(super) async fn get_or_load<F, Fut>(&self, paths: &[PathBuf], load: F) -> anyhow::Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let stamp = FileStamp::of(paths);
+        if let Some((cached, value)) = self.slot.lock().as_ref()
+            && *cached == stamp
+        {
+            return Ok(value.clone());
+        }
+        let value = load().await?;
+        *self.slot.lock() = Some((stamp, value.clone()));
+        Ok(value)
+    }
+}
+
+/// Values computed per request key, all dropped once the data generation
+/// moves on (a write by this process) or a file they were read from changes
+/// (a write by another process sharing the data dir).
+#[derive(Debug)]
+pub(super) struct KeyedCache<K, V> {
+    slot: Mutex<Option<KeyedSlot<K, V>>>,
+}
+
+#[derive(Debug)]
+struct KeyedSlot<K, V> {
+    generation: u64,
+    stamp: FileStamp,
+    values: HashMap<K, (Instant, V)>,
+}
+
+impl<K, V> Default for KeyedCache<K, V> {
+    fn default() -> Self {
+        Self {
+            slot: Mutex::new(None),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> KeyedCache<K, V> {
+    /// The value cached for `key` while `generation` and `paths` are
+    /// unchanged and it is younger than [`KEYED_CACHE_MAX_AGE`], else what
+    /// `load` returns.
+    pub(super) async fn get_or_load<F, Fut>(
+        &self,
+        generation: u64,
+        paths: &[PathBuf],
+        key: K,
+        load: F,
+    ) -> anyhow::Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<V>>,
+    {
+        let stamp = FileStamp::of(paths);
+        if let Some(slot) = self.slot.lock().as_ref()
+            && slot.generation == generation
+            && slot.stamp == stamp
+            && let Some((built_at, value)) = slot.values.get(&key)
+            && built_at.elapsed() < KEYED_CACHE_MAX_AGE
+        {
+            return Ok(value.clone());
+        }
+        let value = load().await?;
+
+        let mut slot = self.slot.lock();
+        let current = slot
+            .as_ref()
+            .is_some_and(|slot| slot.generation == generation && slot.stamp == stamp);
+        if !current {
+            *slot = Some(KeyedSlot {
+                generation,
+                stamp,
+                values: HashMap::new(),
+            });
+        }
+        if let Some(slot) = slot.as_mut() {
+            if slot.values.len() >= KEYED_CACHE_MAX_KEYS {
+                slot.values.clear();
+            }
+            slot.values.insert(key, (Instant::now(), value.clone()));
+        }
+        Ok(value)
+    }
+}
+
+/// Parsed files the dashboard polls, shared by the handlers of one
+/// workspace. The markdown tree has its own cache in
+/// [`super::ServerState::markdown_tree`].
+#[derive(Debug, Default)]
+pub(super) struct ReadCaches {
+    pub(super) sp_entries: ReadCache<Arc<Vec<SpEntry>>>,
+    pub(super) sp_pages: KeyedCache<SpEntryQuery, Arc<SpEntryPage>>,
+    pub(super) acceptance: ReadCache<Arc<AcceptanceOverview>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn values_reload_only_when_their_files_change() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("plan.md");
+        fs::write(&path, "one").unwrap();
+        let paths = [path.clone()];
+        let cache = ReadCache::<String>::default();
+        let loads = AtomicUsize::new(0);
+        let load = || async {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Ok(fs::read_to_string(&path)?)
+        };
+
+        assert_eq!(cache.get_or_load(&paths, load).await.unwrap(), "one");
+        assert_eq!(cache.get_or_load(&paths, load).await.unwrap(), "one");
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        // A different size is a change even within one mtime tick.
+        fs::write(&path, "three").unwrap();
+        assert_eq!(cache.get_or_load(&paths, load).await.unwrap(), "three");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        // Writes to other files keep the cache warm.
+        crate::storage::write_atomic(&temp.path().join("outbox.json"), "{}").unwrap();
+        assert_eq!(cache.get_or_load(&paths, load).await.unwrap(), "three");
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+        fs::remove_file(&path).unwrap();
+        assert!(cache.get_or_load(&paths, load).await.is_err());
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn keyed_values_reload_after_a_data_change() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("index.json");
+        fs::write(&path, "[]").unwrap();
+        let paths = [path.clone()];
+        let cache = KeyedCache::<&str, usize>::default();
+        let loads = AtomicUsize::new(0);
+        let load = || async { Ok(loads.fetch_add(1, Ordering::SeqCst)) };
+
+        assert_eq!(cache.get_or_load(1, &paths, "a", load).await.unwrap(), 0);
+        assert_eq!(cache.get_or_load(1, &paths, "a", load).await.unwrap(), 0);
+        assert_eq!(cache.get_or_load(1, &paths, "b", load).await.unwrap(), 1);
+        assert_eq!(cache.get_or_load(1, &paths, "a", load).await.unwrap(), 0);
+
+        // A write by this process drops every key.
+        assert_eq!(cache.get_or_load(2, &paths, "b", load).await.unwrap(), 2);
+        assert_eq!(cache.get_or_load(2, &paths, "a", load).await.unwrap(), 3);
+
+        // So does one by another process, seen through the file stamp.
+        fs::write(&path, "[{}]").unwrap();
+        assert_eq!(cache.get_or_load(2, &paths, "a", load).await.unwrap(), 4);
+    }
+}
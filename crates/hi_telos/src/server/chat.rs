@@ -0,0 +1,316 @@
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, wrappers::UnboundedReceiverStream};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    agent::{AgentInput, AgentRun, AgentStep, ConversationTurn},
+    clock::SharedClock,
+    sessions::{self, SessionKey},
+    storage::{self, MessageDirection, MessageLogEntry},
+    tasks::Intent,
+};
+
+use super::ServerState;
+
+const CHAT_SOURCE: &str = "api";
+
+pub fn router() -> Router<ServerState> {
+    Router::new()
+        .route("/api/chat", post(chat))
+        .route("/api/chat/stream", post(chat_stream))
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatRequest {
+    message: String,
+    /// Conversation identifier recorded in the message log.
+    #[serde(default)]
+    chat_id: Option<String>,
+    /// Earlier turns supplied by the caller, oldest first. When empty, the
+    /// recent messages logged for `chat_id` are used instead.
+    #[serde(default)]
+    history: Vec<ConversationTurn>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ChatResponse {
+    pub(super) run_id: Uuid,
+    pub(super) chat_id: String,
+    pub(super) final_answer: String,
+    /// Set when the agent asked the user something instead of answering. The
+    /// run is not parked; the next message in the chat carries the answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) question: Option<String>,
+    pub(super) steps: Vec<AgentStep>,
+}
+
+/// One chat message ready to run: the inbound side is already logged.
+struct PreparedChat {
+    data_dir: PathBuf,
+    chat_id: String,
+    input: AgentInput,
+    clock: SharedClock,
+    /// Whether the disk guard has paused logs and run artifacts.
+    writes_paused: bool,
+}
+
+async fn prepare_chat(state: &ServerState, payload: ChatRequest) -> Option<PreparedChat> {
+    let message = payload.message.trim().to_string();
+    if message.is_empty() {
+        return None;
+    }
+    let chat_id = payload
+        .chat_id
+        .filter(|chat_id| !chat_id.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    let session = config.agent.session.clone();
+    let sp_recall = config.agent.sp_recall.clone();
+    drop(config);
+    let backlog_size = state.ctx().intents().read().len();
+
+    let now = state.ctx().now();
+    // Without explicit history, continue the session recorded for `chat_id`.
+    let conversation = if payload.history.is_empty() {
+        let key = SessionKey::new(CHAT_SOURCE, &chat_id);
+        sessions::load_turns(&data_dir, &session, &key, None, now).unwrap_or_else(|err| {
+            warn!(error = ?err, chat_id = %chat_id, "failed to load chat session");
+            Vec::new()
+        })
+    } else {
+        payload.history
+    };
+
+    let intent = Intent {
+        id: Uuid::new_v4(),
+        source: CHAT_SOURCE.to_string(),
+        summary: message.clone(),
+        telos_alignment: 1.0,
+        created_at: now,
+        due_at: None,
+        metadata: Default::default(),
+        storage_path: None,
+    };
+    log_message(
+        &data_dir,
+        MessageDirection::Inbound,
+        &chat_id,
+        &message,
+        None,
+        now,
+    )
+    .await;
+
+    let precedents = storage::find_sp_precedents(&data_dir, &message, &sp_recall)
+        .await
+        .unwrap_or_else(|err| {
+            warn!(error = ?err, chat_id = %chat_id, "failed to look up SP precedents");
+            Vec::new()
+        });
+
+    Some(PreparedChat {
+        data_dir,
+        chat_id,
+        input: AgentInput {
+            intent,
+            backlog_size,
+            conversation,
+            prior_steps: Vec::new(),
+            precedents,
+        },
+        clock: state.ctx().clock(),
+        writes_paused: state.ctx().writes_paused(),
+    })
+}
+
+/// Persist the run's LLM calls and the reply, and shape the response. The
+/// LLM calls and run artifacts are skipped while `writes_paused`.
+async fn finish_chat(
+    data_dir: &Path,
+    chat_id: String,
+    run: AgentRun,
+    writes_paused: bool,
+    now: DateTime<Utc>,
+) -> ChatResponse {
+    let reply = run.question.as_deref().unwrap_or(&run.outcome.final_answer);
+    if !writes_paused {
+        if let Err(err) = storage::append_llm_logs(data_dir, &run.llm_logs).await {
+            warn!(error = ?err, run_id = %run.run_id, "failed to persist chat llm logs");
+        }
+        let artifacts = storage::RunArtifacts {
+            run_id: run.run_id,
+            intent: None,
+            status: match run.question {
+                Some(_) => storage::RunStatus::Waiting,
+                None => storage::RunStatus::Completed,
+            },
+            detail: Some(reply),
+            llm_logs: &run.llm_logs,
+            files: &run.files,
+        };
+        if let Err(err) = storage::write_run_artifacts(data_dir, &artifacts, now) {
+            warn!(error = ?err, run_id = %run.run_id, "failed to write chat run artifacts");
+        }
+    }
+    log_message(
+        data_dir,
+        MessageDirection::Outbound,
+        &chat_id,
+        reply,
+        Some(run.run_id),
+        now,
+    )
+    .await;
+
+    ChatResponse {
+        run_id: run.run_id,
+        chat_id,
+        final_answer: run.outcome.final_answer,
+        question: run.question,
+        steps: run.outcome.steps,
+    }
+}
+
+/// Run the agent for one message right away, outside the beat loop. Nothing
+/// is queued or archived; the LLM calls and both sides of the exchange are
+/// logged like any other run and message.
+async fn chat(
+    State(state): State<ServerState>,
+    Json(payload): Json<ChatRequest>,
+) -> impl IntoResponse {
+    let Some(prepared) = prepare_chat(&state, payload).await else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let run = match state.ctx().agent().run_react(prepared.input).await {
+        Ok(run) => run,
+        Err(err) => {
+            warn!(error = ?err, "synchronous chat run failed");
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": format!("{err:#}") })),
+            )
+                .into_response();
+        }
+    };
+
+    Json(
+        finish_chat(
+            &prepared.data_dir,
+            prepared.chat_id,
+            run,
+            prepared.writes_paused,
+            prepared.clock.now(),
+        )
+        .await,
+    )
+    .into_response()
+}
+
+/// [`chat`] over SSE: a `step` event per THINK step as the agent takes it,
+/// then one `final` event carrying the same body `/api/chat` returns, or an
+/// `error` event when the run fails.
+async fn chat_stream(
+    State(state): State<ServerState>,
+    Json(payload): Json<ChatRequest>,
+) -> Response {
+    let Some(prepared) = prepare_chat(&state, payload).await else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let agent = state.ctx().agent();
+    tokio::spawn(async move {
+        let step_tx = tx.clone();
+        let result = agent
+            .run_react_observed(prepared.input, move |step| {
+                let _ = step_tx.send(json_event("step", step));
+            })
+            .await;
+        let event = match result {
+            Ok(run) => {
+                let response = finish_chat(
+                    &prepared.data_dir,
+                    prepared.chat_id,
+                    run,
+                    prepared.writes_paused,
+                    prepared.clock.now(),
+                )
+                .await;
+                json_event("final", &response)
+            }
+            Err(err) => {
+                warn!(error = ?err, "streamed chat run failed");
+                json_event("error", &json!({ "error": format!("{err:#}") }))
+            }
+        };
+        let _ = tx.send(event);
+    });
+
+    let stream = UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text(": keep-alive"),
+        )
+        .into_response()
+}
+
+fn json_event<T: Serialize>(name: &str, data: &T) -> Event {
+    match serde_json::to_string(data) {
+        Ok(json) => Event::default().event(name).data(json),
+        Err(err) => {
+            warn!(error = ?err, event = name, "failed to serialize chat event");
+            Event::default()
+                .event("error")
+                .data("{\"error\":\"serialization failure\"}")
+        }
+    }
+}
+
+async fn log_message(
+    data_dir: &Path,
+    direction: MessageDirection,
+    chat_id: &str,
+    text: &str,
+    run_id: Option<Uuid>,
+    timestamp: DateTime<Utc>,
+) {
+    let author = match direction {
+        MessageDirection::Inbound => None,
+        MessageDirection::Outbound => Some("telos".to_string()),
+    };
+    let entry = MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction,
+        source: CHAT_SOURCE.to_string(),
+        chat_id: chat_id.to_string(),
+        author,
+        text: text.to_string(),
+        timestamp,
+        metadata: run_id.map(|run_id| json!({ "run_id": run_id })),
+    };
+    if let Err(err) = storage::append_message_entry(data_dir, &entry).await {
+        warn!(error = ?err, "failed to persist chat message log");
+    }
+}
@@ -0,0 +1,113 @@
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SubsecRound, Utc};
+
+/// Validators for one representation of a resource: an entity tag and,
+/// for files, the modification time.
+#[derive(Debug, Clone)]
+pub(super) struct Validators {
+    pub etag: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+impl Validators {
+    /// Whether the client's cached copy is still current. `If-None-Match`
+    /// wins over `If-Modified-Since` when both are sent (RFC 9110 §13.2.2).
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(value) = headers.get(header::IF_NONE_MATCH) {
+            let Ok(value) = value.to_str() else {
+                return false;
+            };
+            return value
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || weak_eq(candidate, &self.etag));
+        }
+        let since = headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok());
+        match (since, self.last_modified) {
+            // HTTP dates have one-second resolution.
+            (Some(since), Some(modified)) => modified.trunc_subsecs(0) <= since,
+            _ => false,
+        }
+    }
+
+    /// `304 Not Modified` carrying the validators.
+    pub fn not_modified_response(&self) -> Response {
+        self.apply(StatusCode::NOT_MODIFIED.into_response())
+    }
+
+    /// Attach `ETag`, `Last-Modified` and `Cache-Control: no-cache`, so
+    /// clients revalidate on every poll instead of reusing stale copies.
+    pub fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, value);
+        }
+        if let Some(modified) = self.last_modified
+            && let Ok(value) = HeaderValue::from_str(&http_date(modified))
+        {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        response
+    }
+}
+
+fn weak_eq(left: &str, right: &str) -> bool {
+    left.trim_start_matches("W/") == right.trim_start_matches("W/")
+}
+
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn matches_etags_before_dates() {
+        let modified = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        let validators = Validators {
+            etag: "W/\"abc\"".to_string(),
+            last_modified: Some(modified + chrono::Duration::milliseconds(500)),
+        };
+        let headers = |pairs: &[(header::HeaderName, &str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(name.clone(), HeaderValue::from_str(value).unwrap());
+            }
+            map
+        };
+
+        assert!(!validators.not_modified(&HeaderMap::new()));
+        assert!(
+            validators.not_modified(&headers(&[(header::IF_NONE_MATCH, "\"other\", \"abc\"")]))
+        );
+        assert!(!validators.not_modified(&headers(&[
+            (header::IF_NONE_MATCH, "\"other\""),
+            (header::IF_MODIFIED_SINCE, &http_date(modified)),
+        ])));
+        assert!(validators.not_modified(&headers(&[(
+            header::IF_MODIFIED_SINCE,
+            &http_date(modified)
+        )])));
+        assert!(!validators.not_modified(&headers(&[(
+            header::IF_MODIFIED_SINCE,
+            &http_date(modified - chrono::Duration::seconds(1))
+        )])));
+
+        let response = validators.not_modified_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers()[header::LAST_MODIFIED],
+            "Thu, 02 Jan 2025 03:04:05 GMT"
+        );
+    }
+}
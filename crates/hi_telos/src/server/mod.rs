@@ -1,47 +1,124 @@
-use std::{net::SocketAddr, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, anyhow};
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse},
-    routing::{get, post},
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    middleware::{self, Next},
+    response::{
+        Html, IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{any, get, patch, post},
 };
-use chrono::{DateTime, Utc};
-use reqwest::Client;
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tokio::{net::TcpListener, task};
+use sha2::{Digest, Sha256};
+use tokio::{io, net::TcpListener, task};
+use tokio_stream::{
+    StreamExt,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tower::ServiceExt;
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
 use uuid::Uuid;
 
 mod acceptance;
+mod admin;
+mod assets;
+mod cache;
+mod chat;
+mod conditional;
+mod telegram_admin;
 mod ui;
+mod webhook;
+
+pub use acceptance::{resolve_plan_docs, snapshot_acceptance_metrics};
+pub use webhook::{
+    GENERIC_WEBHOOK_PATH, GITHUB_WEBHOOK_PATH, InboundWebhookSource, generic_webhook_routes,
+    github_webhook_routes,
+};
 
 use crate::{
+    email,
+    events::{PreviewChangeKind, PreviewChanged},
     orchestrator::OrchestratorHandle,
+    outbox::{self, Channels},
     state::AppContext,
     storage::{
-        self, LoadedStructuredTextPreview, MemoryLevel, MemoryQuery, MessageDirection,
-        MessageLogEntry, MessageLogQuery, StructuredContent, StructuredTextHistoryEntry,
-        StructuredTextHistoryFilters,
+        self, IntentDraft, LoadedStructuredTextPreview, MemoryLevel, MemoryQuery, MessageDirection,
+        MessageLogEntry, MessageLogQuery, OutboxMessage, OutboxStatus, StructuredContent,
+        StructuredTextHistoryEntry, StructuredTextHistoryFilters,
+    },
+    tasks::{
+        COST_ESTIMATE_KEY, Intent, IntentPriority, IntentQueue, IntentState, IntentTransition,
+        PERSONA_KEY, PRIORITY_KEY,
     },
+    telegram::{self, TelegramIngest, TelegramUpdate},
+    watchdog,
 };
 
 const DEFAULT_TEXT_STRUCTURE_HISTORY_LIMIT: usize = 10;
 
+/// How long a cached markdown tree is trusted without a write from this
+/// process; bounds how late edits made by hand show up.
+const MD_TREE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Buffer between the blocking memory export writer and the response body.
+const MEMORY_EXPORT_PIPE_BYTES: usize = 64 * 1024;
+
 #[derive(Clone)]
 pub struct ServerState {
     ctx: AppContext,
     orchestrator: OrchestratorHandle,
+    md_tree: Arc<Mutex<Option<MdTreeSnapshot>>>,
+    caches: Arc<cache::ReadCaches>,
+    /// API routers of the named workspaces, served under `/api/w/:name`.
+    workspaces: Arc<BTreeMap<String, Router>>,
+}
+
+/// The markdown file list with the data generation and walk policy it was
+/// built with.
+#[derive(Debug, Clone)]
+struct MdTreeSnapshot {
+    files: Arc<Vec<String>>,
+    etag: String,
+    generation: u64,
+    walk: Arc<storage::WalkPolicy>,
+    built_at: Instant,
 }
 
 impl ServerState {
     pub fn new(ctx: AppContext, orchestrator: OrchestratorHandle) -> Self {
-        Self { ctx, orchestrator }
+        Self {
+            ctx,
+            orchestrator,
+            md_tree: Arc::new(Mutex::new(None)),
+            caches: Arc::default(),
+            workspaces: Arc::new(BTreeMap::new()),
+        }
+    }
+
+    /// Serve each named workspace's `/api` routes under `/api/w/:name`.
+    pub fn with_workspaces(mut self, workspaces: Vec<(String, ServerState)>) -> Self {
+        let routers = workspaces
+            .into_iter()
+            .map(|(name, state)| (name, router(state)))
+            .collect();
+        self.workspaces = Arc::new(routers);
+        self
     }
 
     fn ctx(&self) -> &AppContext {
@@ -51,6 +128,85 @@ impl ServerState {
     fn orchestrator(&self) -> &OrchestratorHandle {
         &self.orchestrator
     }
+
+    /// The markdown tree, rescanned only after the app wrote to the data
+    /// dir, `config/walk.yml` changed or the cache aged past
+    /// [`MD_TREE_CACHE_TTL`].
+    async fn markdown_tree(&self) -> anyhow::Result<MdTreeSnapshot> {
+        let generation = self.ctx().data_generation();
+        let config = self.ctx().config();
+        if let Some(cached) = self.md_tree.lock().as_ref()
+            && cached.generation == generation
+            && *cached.walk == config.walk
+            && cached.built_at.elapsed() < MD_TREE_CACHE_TTL
+        {
+            return Ok(cached.clone());
+        }
+
+        let data_dir = config.data_dir.clone();
+        let walk = Arc::new(config.walk.clone());
+        let policy = Arc::clone(&walk);
+        let files =
+            task::spawn_blocking(move || storage::list_markdown_tree(&data_dir, &policy)).await??;
+        let digest = Sha256::digest(files.join("\n").as_bytes());
+        let snapshot = MdTreeSnapshot {
+            etag: format!("\"{}\"", &hex::encode(digest)[..16]),
+            files: Arc::new(files),
+            generation,
+            walk,
+            built_at: Instant::now(),
+        };
+        *self.md_tree.lock() = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// The SP index entries, reread once `sp/index.json` changes.
+    async fn sp_entries(&self) -> anyhow::Result<Arc<Vec<storage::SpEntry>>> {
+        let data_dir = self.ctx().config().data_dir.clone();
+        self.caches
+            .sp_entries
+            .get_or_load(&[storage::sp_index_path(&data_dir)], || async {
+                Ok(Arc::new(storage::read_sp_entries(&data_dir).await?))
+            })
+            .await
+    }
+
+    /// One page of `/api/sp/entries`, recomputed after a data change or
+    /// once `sp/index.json` changes.
+    async fn sp_entry_page(
+        &self,
+        query: storage::SpEntryQuery,
+    ) -> anyhow::Result<Arc<storage::SpEntryPage>> {
+        let data_dir = self.ctx().config().data_dir.clone();
+        let now = self.ctx().now();
+        self.caches
+            .sp_pages
+            .get_or_load(
+                self.ctx().data_generation(),
+                &[storage::sp_index_path(&data_dir)],
+                query.clone(),
+                || async {
+                    Ok(Arc::new(
+                        storage::load_sp_entries(&data_dir, &query, now).await?,
+                    ))
+                },
+            )
+            .await
+    }
+
+    /// The overview of the acceptance plans `docs`, reparsed once one of
+    /// them changes.
+    async fn acceptance_overview(
+        &self,
+        docs: &[PathBuf],
+    ) -> anyhow::Result<Arc<acceptance::AcceptanceOverview>> {
+        self.caches
+            .acceptance
+            .get_or_load(docs, || async {
+                Ok(Arc::new(acceptance::load_acceptance_overview(docs).await?))
+            })
+            .await
+    }
 }
 
 pub async fn serve(state: ServerState) -> anyhow::Result<()> {
@@ -75,21 +231,50 @@ pub async fn serve_with_listener(listener: TcpListener, state: ServerState) -> a
 fn router(state: ServerState) -> Router {
     Router::new()
         .route("/healthz", get(health))
+        .route("/api/status", get(status))
+        .route("/api/workspaces", get(list_workspaces))
+        .route("/api/w/:workspace/*rest", any(workspace_api))
         .route("/api/sp", get(sp_summary))
+        .route("/api/sp/entries", get(sp_entries))
         .route("/api/meta/acceptance", get(acceptance_overview))
+        .route("/api/meta/acceptance/history", get(acceptance_history))
         .route(
             "/api/meta/acceptance/module/:module",
             get(acceptance_module_overview),
         )
+        .route(
+            "/api/meta/acceptance/module/:module/task",
+            patch(update_acceptance_task),
+        )
+        .route("/api/meta/acceptance/todo", patch(update_acceptance_todo))
         .route("/api/md/tree", get(md_tree))
         .route("/api/md/file", get(md_file))
+        .route("/api/md/file/history", get(md_file_history))
+        .route("/api/md/file/diff", get(md_file_diff))
+        .route("/api/md/file/revert", post(md_file_revert))
+        .route("/api/llm/status", get(llm_status))
+        .route("/api/sources", get(list_sources))
+        .route("/api/jobs", get(list_jobs))
         .route("/api/logs/llm", get(llm_logs))
+        .route("/api/logs/llm/stream", get(llm_log_stream))
+        .route("/api/stats", get(stats))
+        .route("/api/failures", get(failures))
+        .route("/api/experiments", get(experiments))
+        .route("/api/runs/:run_id", get(run_manifest))
+        .route("/api/runs/:run_id/artifacts/*path", get(run_artifact))
+        .route("/api/journals", get(journal_days))
+        .route("/api/journals/:date", get(journal_day))
+        .route("/api/beat", post(trigger_beat))
         .route(
             "/api/mock/text_structure",
             get(text_structure_preview)
                 .post(update_text_structure_preview)
                 .delete(reset_text_structure_preview),
         )
+        .route(
+            "/api/mock/text_structure/from_markdown",
+            post(text_structure_from_markdown),
+        )
         .route(
             "/api/mock/text_structure/history",
             get(text_structure_history),
@@ -102,22 +287,157 @@ fn router(state: ServerState) -> Router {
             "/api/mock/text_structure/history/:id/restore",
             post(restore_text_structure_history_entry),
         )
+        .route(
+            "/api/mock/text_structure/stream",
+            get(text_structure_change_stream),
+        )
+        .route("/api/mock/text_structures", get(text_structure_previews))
+        .route(
+            "/api/mock/text_structure/:name",
+            get(text_structure_preview)
+                .post(update_text_structure_preview)
+                .delete(reset_text_structure_preview),
+        )
+        .route(
+            "/api/mock/text_structure/:name/from_markdown",
+            post(text_structure_from_markdown),
+        )
+        .route(
+            "/api/mock/text_structure/:name/history",
+            get(text_structure_history),
+        )
+        .route(
+            "/api/mock/text_structure/:name/history/:id",
+            get(text_structure_history_entry),
+        )
+        .route(
+            "/api/mock/text_structure/:name/history/:id/restore",
+            post(restore_text_structure_history_entry),
+        )
         .route("/api/messages", get(list_messages))
         .route("/api/messages/send", post(send_message))
+        .route("/api/messages/outbox", get(list_outbox))
         .route("/api/memory", get(memory_timeline))
-        .route("/webhook/telegram", post(telegram_webhook))
-        .route("/api/intents", post(create_intent))
+        .route("/api/memory/tags", get(memory_tags))
+        .route("/api/memory/export", get(memory_export))
+        .route("/api/memory/:id/tags", post(update_memory_entry_tags))
+        .route("/api/memory/:id/anchors", get(memory_entry_anchors))
+        .route("/api/intents", get(list_intents).post(create_intent))
+        .route("/api/intents/questions", get(list_questions))
+        .route("/api/intents/queue", get(queue_order).put(reorder_queue))
+        .route("/api/intents/:id", get(intent_detail).patch(edit_intent))
+        .route("/api/intents/:id/approve", post(approve_intent))
+        .route("/api/intents/:id/reject", post(reject_intent))
+        .route("/api/intents/:id/answer", post(answer_question))
+        .route("/api/intents/:id/retry", post(retry_intent))
+        .route("/api/intents/:id/cancel", post(cancel_intent))
+        .route("/api/intents/:id/bump", post(bump_intent))
+        .route("/api/intents/:id/history", get(intent_history))
         .merge(ui::router())
+        .merge(assets::router())
+        .merge(chat::router())
+        .merge(admin::router())
+        .merge(state.ctx().sources().router())
+        .merge(telegram_admin::router())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            note_data_change,
+        ))
         .layer(TraceLayer::new_for_http())
         .with_state(state)
 }
 
+/// Count any request that may have written (all but `GET` and `HEAD`) as a
+/// data change once it is answered, so cached reads refresh.
+async fn note_data_change(
+    State(state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let writes = !matches!(*request.method(), Method::GET | Method::HEAD);
+    let response = next.run(request).await;
+    if writes {
+        state.ctx().note_data_changed();
+    }
+    response
+}
+
 async fn shutdown_signal(ctx: AppContext) {
-    ctx.shutdown_notifier().notified().await;
+    ctx.wait_for_shutdown().await;
+}
+
+/// `503` once the beat loop of this process has missed its watchdog
+/// deadline, so external monitors notice a stalled orchestrator. A `server`
+/// role process runs no beats and only reports on the worker's.
+async fn health(State(state): State<ServerState>) -> Response {
+    let runs_worker = state.ctx().config().server.role.runs_worker();
+    if runs_worker && watchdog::beat_health(state.ctx()).overdue {
+        (StatusCode::SERVICE_UNAVAILABLE, "beat overdue").into_response()
+    } else {
+        "ok".into_response()
+    }
+}
+
+async fn status(State(state): State<ServerState>) -> impl IntoResponse {
+    let role = state.ctx().config().server.role;
+    Json(serde_json::json!({
+        "role": role.as_str(),
+        "beat": watchdog::beat_health(state.ctx()),
+    }))
+}
+
+async fn llm_status(State(state): State<ServerState>) -> impl IntoResponse {
+    Json(state.ctx().agent().llm_status().await)
+}
+
+async fn list_sources(State(state): State<ServerState>) -> impl IntoResponse {
+    let sources = state.ctx().sources().statuses(&state.ctx().config());
+    Json(serde_json::json!({ "sources": sources }))
+}
+
+async fn list_jobs(State(state): State<ServerState>) -> impl IntoResponse {
+    Json(serde_json::json!({ "jobs": state.ctx().jobs().statuses() }))
+}
+
+async fn list_workspaces(State(state): State<ServerState>) -> impl IntoResponse {
+    let names: Vec<&String> = state.workspaces.keys().collect();
+    Json(serde_json::json!({ "workspaces": names }))
 }
 
-async fn health() -> &'static str {
-    "ok"
+/// Hand `/api/w/:workspace/<rest>` to that workspace's router as
+/// `/api/<rest>`.
+async fn workspace_api(
+    State(state): State<ServerState>,
+    Path((workspace, _)): Path<(String, String)>,
+    mut request: Request,
+) -> Response {
+    let Some(router) = state.workspaces.get(&workspace) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("unknown workspace {workspace}"),
+        )
+            .into_response();
+    };
+    // Cut the raw path rather than reusing the decoded `rest` capture, so
+    // escaped characters reach the workspace router unchanged.
+    let raw = request.uri().path();
+    let rest = raw
+        .strip_prefix("/api/w/")
+        .and_then(|path| path.split_once('/'))
+        .map(|(_, rest)| rest)
+        .unwrap_or_default();
+    let path = match request.uri().query() {
+        Some(query) => format!("/api/{rest}?{query}"),
+        None => format!("/api/{rest}"),
+    };
+    match path.parse::<Uri>() {
+        Ok(uri) => *request.uri_mut() = uri,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+    match router.clone().oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -127,15 +447,14 @@ struct SpSummary {
 }
 
 async fn sp_summary(State(state): State<ServerState>) -> Json<SpSummary> {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    let payload = match storage::load_sp_index(&data_dir).await {
-        Ok(index) => SpSummary {
-            top_used: index.top_used,
-            most_recent: index.most_recent,
-        },
+    let payload = match state.sp_entries().await {
+        Ok(entries) => {
+            let index = storage::summarize_sp_index(entries.to_vec(), state.ctx().now());
+            SpSummary {
+                top_used: index.top_used,
+                most_recent: index.most_recent,
+            }
+        }
         Err(err) => {
             warn!(error = ?err, "failed to load SP index");
             SpSummary {
@@ -148,25 +467,105 @@ async fn sp_summary(State(state): State<ServerState>) -> Json<SpSummary> {
     Json(payload)
 }
 
-async fn acceptance_overview(State(state): State<ServerState>) -> impl IntoResponse {
+const DEFAULT_SP_ENTRIES_LIMIT: usize = 50;
+const MAX_SP_ENTRIES_LIMIT: usize = 200;
+
+#[derive(Debug, Default, Deserialize)]
+struct SpEntriesQuery {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    intent: Option<Uuid>,
+    #[serde(default)]
+    sort: storage::SpSort,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Every SP entry, paged, with scores decayed to now.
+async fn sp_entries(
+    State(state): State<ServerState>,
+    Query(query): Query<SpEntriesQuery>,
+) -> impl IntoResponse {
+    let query = storage::SpEntryQuery {
+        category: query
+            .category
+            .filter(|category| !category.trim().is_empty()),
+        intent: query.intent,
+        sort: query.sort,
+        offset: query.offset,
+        limit: query
+            .limit
+            .unwrap_or(DEFAULT_SP_ENTRIES_LIMIT)
+            .min(MAX_SP_ENTRIES_LIMIT),
+    };
+    match state.sp_entry_page(query).await {
+        Ok(page) => Json(page.as_ref()).into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to load SP entries");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Plan documents from `acceptance.yml` under the app root, narrowed to
+/// `doc` when given.
+fn acceptance_docs(state: &ServerState, doc: Option<&str>) -> Option<Vec<PathBuf>> {
     let config = state.ctx().config();
-    let config_dir = config.config_dir.clone();
-    drop(config);
+    let root = config.config_dir.parent()?;
+    Some(acceptance::select_plan_docs(
+        root,
+        &config.acceptance.docs,
+        doc,
+    ))
+}
 
-    let Some(root) = config_dir.parent() else {
+#[derive(Debug, Default, Deserialize)]
+struct AcceptanceDocQuery {
+    /// Document id (file stem) or path relative to the app root.
+    #[serde(default)]
+    doc: Option<String>,
+}
+
+async fn acceptance_overview(State(state): State<ServerState>) -> impl IntoResponse {
+    let Some(docs) = acceptance_docs(&state, None) else {
         return StatusCode::INTERNAL_SERVER_ERROR.into_response();
     };
 
-    let doc_path = root.join("docs/work_acceptance_plan.md");
+    match state.acceptance_overview(&docs).await {
+        Ok(overview) => Json(overview.as_ref()).into_response(),
+        Err(err) => {
+            warn!(error = ?err, docs = ?docs, "failed to load acceptance summary");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AcceptanceHistoryQuery {
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct AcceptanceHistoryResponse {
+    snapshots: Vec<acceptance::AcceptanceSnapshot>,
+}
 
-    match acceptance::load_acceptance_summary(&doc_path).await {
-        Ok(summary) => Json(summary).into_response(),
+/// Metrics recorded by the beat whenever the plans changed, oldest first.
+async fn acceptance_history(
+    State(state): State<ServerState>,
+    Query(query): Query<AcceptanceHistoryQuery>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+    match acceptance::load_acceptance_history(&data_dir, query.since, query.limit).await {
+        Ok(snapshots) => Json(AcceptanceHistoryResponse { snapshots }).into_response(),
         Err(err) => {
-            warn!(
-                error = ?err,
-                path = %doc_path.display(),
-                "failed to load acceptance summary"
-            );
+            warn!(error = ?err, "failed to load acceptance history");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
@@ -175,30 +574,112 @@ async fn acceptance_overview(State(state): State<ServerState>) -> impl IntoRespo
 async fn acceptance_module_overview(
     State(state): State<ServerState>,
     Path(module): Path<String>,
+    Query(query): Query<AcceptanceDocQuery>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let config_dir = config.config_dir.clone();
-    drop(config);
+    let Some(docs) = acceptance_docs(&state, query.doc.as_deref()) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    for doc_path in &docs {
+        match acceptance::load_module_acceptance_summary(doc_path, &module).await {
+            Ok(Some(summary)) => return Json(summary).into_response(),
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    error = ?err,
+                    module = module,
+                    path = %doc_path.display(),
+                    "failed to load acceptance module summary"
+                );
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+    StatusCode::NOT_FOUND.into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct AcceptanceTaskUpdate {
+    /// Task cell text, matched exactly.
+    task: String,
+    status: String,
+}
+
+/// A markdown table cell must stay on one line and cannot contain `|`.
+fn valid_table_cell(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && !value.contains(['|', '\n', '\r'])
+}
 
-    let Some(root) = config_dir.parent() else {
+async fn update_acceptance_task(
+    State(state): State<ServerState>,
+    Path(module): Path<String>,
+    Query(query): Query<AcceptanceDocQuery>,
+    Json(payload): Json<AcceptanceTaskUpdate>,
+) -> impl IntoResponse {
+    if !valid_table_cell(&payload.status) || payload.task.trim().is_empty() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let Some(docs) = acceptance_docs(&state, query.doc.as_deref()) else {
         return StatusCode::INTERNAL_SERVER_ERROR.into_response();
     };
 
-    let doc_path = root.join("docs/work_acceptance_plan.md");
+    for doc_path in &docs {
+        match acceptance::update_task_status(doc_path, &module, &payload.task, &payload.status)
+            .await
+        {
+            Ok(Some(summary)) => return Json(summary).into_response(),
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    error = ?err,
+                    module = module,
+                    path = %doc_path.display(),
+                    "failed to update acceptance task"
+                );
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+    StatusCode::NOT_FOUND.into_response()
+}
 
-    match acceptance::load_module_acceptance_summary(&doc_path, &module).await {
-        Ok(Some(summary)) => Json(summary).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(err) => {
-            warn!(
-                error = ?err,
-                module = module,
-                path = %doc_path.display(),
-                "failed to load acceptance module summary"
-            );
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+#[derive(Debug, Deserialize)]
+struct AcceptanceTodoUpdate {
+    /// Bullet text without the checkbox, matched exactly.
+    label: String,
+    done: bool,
+    /// Limits the search to one plan; see [`AcceptanceDocQuery::doc`].
+    #[serde(default)]
+    doc: Option<String>,
+}
+
+async fn update_acceptance_todo(
+    State(state): State<ServerState>,
+    Json(payload): Json<AcceptanceTodoUpdate>,
+) -> impl IntoResponse {
+    if payload.label.trim().is_empty() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let Some(docs) = acceptance_docs(&state, payload.doc.as_deref()) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    for doc_path in &docs {
+        match acceptance::update_todo(doc_path, &payload.label, payload.done).await {
+            Ok(Some(summary)) => return Json(summary).into_response(),
+            Ok(None) => {}
+            Err(err) => {
+                warn!(
+                    error = ?err,
+                    path = %doc_path.display(),
+                    "failed to update acceptance todo"
+                );
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
         }
     }
+    StatusCode::NOT_FOUND.into_response()
 }
 
 #[derive(Debug, Serialize)]
@@ -206,908 +687,4614 @@ struct MdTreeResponse {
     files: Vec<String>,
 }
 
-async fn md_tree(State(state): State<ServerState>) -> Json<MdTreeResponse> {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    let files = match storage::list_markdown_tree(&data_dir) {
-        Ok(files) => files,
+async fn md_tree(State(state): State<ServerState>, headers: HeaderMap) -> Response {
+    let tree = match state.markdown_tree().await {
+        Ok(tree) => tree,
         Err(err) => {
             warn!(error = ?err, "failed to list markdown tree");
-            Vec::new()
+            return Json(MdTreeResponse { files: Vec::new() }).into_response();
         }
     };
 
-    Json(MdTreeResponse { files })
+    let validators = conditional::Validators {
+        etag: tree.etag,
+        last_modified: None,
+    };
+    if validators.not_modified(&headers) {
+        return validators.not_modified_response();
+    }
+    validators.apply(
+        Json(MdTreeResponse {
+            files: tree.files.to_vec(),
+        })
+        .into_response(),
+    )
 }
 
-#[derive(Debug, Deserialize)]
-struct MdFileQuery {
-    path: String,
-    #[serde(default)]
-    render: Option<bool>,
+async fn stats(State(state): State<ServerState>) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let now = state.ctx().now();
+
+    match task::spawn_blocking(move || storage::load_stats(&data_dir, now)).await {
+        Ok(Ok(snapshot)) => Json(snapshot).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to compute stats");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "stats task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct MdFileResponse {
-    path: String,
-    content: String,
+#[derive(Debug, Deserialize)]
+struct ExperimentsQuery {
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
 }
 
-async fn md_file(
+/// Prompt versions in effect and per-variant outcomes of each experiment
+/// found in the LLM logs.
+async fn experiments(
     State(state): State<ServerState>,
-    Query(params): Query<MdFileQuery>,
+    Query(query): Query<ExperimentsQuery>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
+    let data_dir = state.ctx().config().data_dir.clone();
+    let versions = state.ctx().agent().prompt_versions();
 
-    let sanitized = match storage::sanitize_data_relative_path(&params.path) {
-        Ok(path) => path,
+    match task::spawn_blocking(move || storage::load_experiment_reports(&data_dir, query.since))
+        .await
+    {
+        Ok(Ok(experiments)) => Json(serde_json::json!({
+            "prompt_version": versions.prompt_version,
+            "active": versions.experiment,
+            "experiments": experiments,
+        }))
+        .into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to load experiment reports");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
         Err(err) => {
-            warn!(error = ?err, "invalid markdown path requested");
-            return StatusCode::BAD_REQUEST.into_response();
+            warn!(error = ?err, "experiment report task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
-    };
+    }
+}
 
-    match storage::read_markdown_file(&data_dir, &sanitized).await {
-        Ok(content) => {
-            if params.render.unwrap_or(false) {
-                let html = render_markdown(&content);
-                Html(html).into_response()
-            } else {
-                Json(MdFileResponse {
-                    path: sanitized.to_string_lossy().to_string(),
-                    content,
-                })
-                .into_response()
-            }
+#[derive(Debug, Deserialize)]
+struct FailuresQuery {
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
+}
+
+/// Quarantined intents grouped by failure class.
+async fn failures(
+    State(state): State<ServerState>,
+    Query(query): Query<FailuresQuery>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    match task::spawn_blocking(move || storage::load_failure_report(&data_dir, query.since)).await {
+        Ok(Ok(report)) => Json(report).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to load failure report");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
         Err(err) => {
-            let status = if err
-                .downcast_ref::<std::io::Error>()
-                .map(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
-                .unwrap_or(false)
-            {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::BAD_REQUEST
-            };
-            warn!(error = ?err, path = %params.path, "failed to load markdown file");
-            status.into_response()
+            warn!(error = ?err, "failure report task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-enum TextStructurePreviewSource {
-    Inline,
-    File,
+#[derive(Debug, Deserialize)]
+struct JournalDaysQuery {
+    #[serde(default)]
+    from: Option<NaiveDate>,
+    #[serde(default)]
+    to: Option<NaiveDate>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TextStructurePreviewResponse {
-    #[serde(flatten)]
-    content: StructuredContent,
-    source: TextStructurePreviewSource,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    note: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    updated_at: Option<DateTime<Utc>>,
-}
-
-async fn text_structure_preview(
+/// Calendar of days with journal entries: entry counts and first headings.
+async fn journal_days(
     State(state): State<ServerState>,
-) -> Json<TextStructurePreviewResponse> {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
+    Query(params): Query<JournalDaysQuery>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
 
-    match storage::load_structured_text_preview(&data_dir).await {
-        Ok(Some(LoadedStructuredTextPreview {
-            content,
-            note,
-            updated_at,
-        })) => Json(TextStructurePreviewResponse {
-            content,
-            source: TextStructurePreviewSource::File,
-            note,
-            updated_at,
-        }),
-        Ok(None) => Json(TextStructurePreviewResponse {
-            content: StructuredContent::mock_payload(),
-            source: TextStructurePreviewSource::Inline,
-            note: None,
-            updated_at: None,
-        }),
+    match task::spawn_blocking(move || {
+        storage::list_journal_days(&data_dir, params.from, params.to)
+    })
+    .await
+    {
+        Ok(Ok(days)) => Json(serde_json::json!({ "days": days })).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to list journal days");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
         Err(err) => {
-            warn!(error = ?err, "failed to load structured text preview; falling back to inline mock");
-            Json(TextStructurePreviewResponse {
-                content: StructuredContent::mock_payload(),
-                source: TextStructurePreviewSource::Inline,
-                note: None,
-                updated_at: None,
-            })
+            warn!(error = ?err, "journal listing task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
-async fn update_text_structure_preview(
+/// Parsed entries of one day (`YYYY-MM-DD`); 404 when it has none.
+async fn journal_day(
     State(state): State<ServerState>,
-    Json(payload): Json<TextStructurePreviewUpdate>,
+    Path(date): Path<String>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    let (content, note) = payload.into_parts();
+    let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let data_dir = state.ctx().config().data_dir.clone();
 
-    match storage::save_structured_text_preview(&data_dir, &content, note.as_deref()).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
-        Err(err) => {
-            warn!(error = ?err, "failed to persist structured text preview");
+    match task::spawn_blocking(move || storage::load_journal_day(&data_dir, date)).await {
+        Ok(Ok(entries)) if entries.is_empty() => StatusCode::NOT_FOUND.into_response(),
+        Ok(Ok(entries)) => {
+            Json(serde_json::json!({ "date": date, "entries": entries })).into_response()
+        }
+        Ok(Err(err)) => {
+            warn!(error = ?err, %date, "failed to load journal day");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
-    }
-}
-
-async fn reset_text_structure_preview(State(state): State<ServerState>) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    match storage::delete_structured_text_preview(&data_dir).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(err) => {
-            warn!(error = ?err, "failed to delete structured text preview");
+            warn!(error = ?err, "journal day task join failure");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct TextStructureHistoryQuery {
+struct MdFileQuery {
+    path: String,
     #[serde(default)]
-    limit: Option<usize>,
+    render: Option<bool>,
+    /// With `render`, return a full UI page instead of a fragment.
     #[serde(default)]
-    since: Option<DateTime<Utc>>,
-    #[serde(default, rename = "q")]
-    query: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum TextStructurePreviewUpdate {
-    Content(StructuredContent),
-    WithNote {
-        content: StructuredContent,
-        #[serde(default)]
-        note: Option<String>,
-    },
-}
-
-impl TextStructurePreviewUpdate {
-    fn into_parts(self) -> (StructuredContent, Option<String>) {
-        match self {
-            TextStructurePreviewUpdate::Content(content) => (content, None),
-            TextStructurePreviewUpdate::WithNote { content, note } => (content, note),
-        }
-    }
+    page: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
-struct TextStructureHistoryResponse {
-    entries: Vec<StructuredTextHistoryEntry>,
+#[derive(Debug, Serialize)]
+struct MdFileResponse {
+    path: String,
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    links: Vec<storage::JournalLinks>,
 }
 
-async fn text_structure_history(
+async fn md_file(
     State(state): State<ServerState>,
-    Query(params): Query<TextStructureHistoryQuery>,
-) -> Json<TextStructureHistoryResponse> {
+    Query(params): Query<MdFileQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
     let config = state.ctx().config();
     let data_dir = config.data_dir.clone();
     drop(config);
 
-    let TextStructureHistoryQuery {
-        limit,
-        since,
-        query,
-    } = params;
-    let limit = limit.unwrap_or(DEFAULT_TEXT_STRUCTURE_HISTORY_LIMIT);
-    let filters = StructuredTextHistoryFilters {
-        since,
-        note_query: query,
-    };
-    let filters = if filters == StructuredTextHistoryFilters::default() {
-        None
-    } else {
-        Some(filters)
+    let sanitized = match storage::sanitize_data_relative_path(&params.path) {
+        Ok(path) => path,
+        Err(err) => {
+            warn!(error = ?err, "invalid markdown path requested");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
     };
-    let filter_ref = filters.as_ref();
 
-    match storage::list_structured_text_history(&data_dir, limit, filter_ref).await {
-        Ok(entries) => Json(TextStructureHistoryResponse { entries }),
-        Err(err) => {
-            warn!(error = ?err, "failed to list structured text history");
-            Json(TextStructureHistoryResponse {
-                entries: Vec::new(),
-            })
+    let render = params.render.unwrap_or(false);
+    let page = render && params.page.unwrap_or(false);
+    let loaded = async {
+        let path = storage::resolve_markdown_file(&data_dir, &sanitized)?;
+        let metadata = tokio::fs::metadata(&path).await?;
+        let modified = DateTime::<Utc>::from(metadata.modified()?);
+        // Size and mtime stand in for a content hash, so a matching
+        // request never reads the file.
+        let variant = match (render, page) {
+            (false, _) => "json",
+            (true, false) => "html",
+            (true, true) => "page",
+        };
+        let validators = conditional::Validators {
+            etag: format!(
+                "W/\"{:x}-{:x}-{variant}\"",
+                metadata.len(),
+                modified.timestamp_nanos_opt().unwrap_or_default()
+            ),
+            last_modified: Some(modified),
+        };
+        if validators.not_modified(&headers) {
+            return Ok((validators, None));
         }
+        let content = tokio::fs::read_to_string(&path).await?;
+        anyhow::Ok((validators, Some(content)))
     }
-}
-
-async fn text_structure_history_entry(
-    State(state): State<ServerState>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
+    .await;
 
-    match storage::load_structured_text_history_entry(&data_dir, &id).await {
-        Ok(Some(entry)) => Json(entry).into_response(),
-        Ok(None) => StatusCode::NOT_FOUND.into_response(),
-        Err(err) => {
-            if err.root_cause().is::<chrono::ParseError>() {
-                StatusCode::BAD_REQUEST.into_response()
+    match loaded {
+        Ok((validators, None)) => validators.not_modified_response(),
+        Ok((validators, Some(content))) => {
+            let response = if render {
+                let html = render_markdown(&content);
+                if page {
+                    ui::render_markdown_page(
+                        &state.ctx().config().ui,
+                        &sanitized.to_string_lossy(),
+                        &html,
+                    )
+                    .into_response()
+                } else {
+                    Html(html).into_response()
+                }
             } else {
-                warn!(error = ?err, id = %id, "failed to load structured text history entry");
-                StatusCode::INTERNAL_SERVER_ERROR.into_response()
-            }
+                Json(MdFileResponse {
+                    path: sanitized.to_string_lossy().to_string(),
+                    links: storage::parse_journal_links(&content),
+                    content,
+                })
+                .into_response()
+            };
+            validators.apply(response)
         }
-    }
-}
-
-async fn restore_text_structure_history_entry(
-    State(state): State<ServerState>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    match storage::restore_structured_text_preview_from_history(&data_dir, &id).await {
-        Ok(true) => StatusCode::NO_CONTENT.into_response(),
-        Ok(false) => StatusCode::NOT_FOUND.into_response(),
         Err(err) => {
-            if err.root_cause().is::<chrono::ParseError>() {
-                StatusCode::BAD_REQUEST.into_response()
+            let status = if err
+                .downcast_ref::<std::io::Error>()
+                .map(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
+                .unwrap_or(false)
+            {
+                StatusCode::NOT_FOUND
             } else {
-                warn!(error = ?err, id = %id, "failed to restore structured text history entry");
-                StatusCode::INTERNAL_SERVER_ERROR.into_response()
-            }
+                StatusCode::BAD_REQUEST
+            };
+            warn!(error = ?err, path = %params.path, "failed to load markdown file");
+            status.into_response()
         }
     }
 }
 
-fn render_markdown(markdown: &str) -> String {
-    use pulldown_cmark::{Options, Parser, html};
-
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-
-    let parser = Parser::new_ext(markdown, options);
-    let mut output = String::new();
-    html::push_html(&mut output, parser);
-    output
-}
-
 #[derive(Debug, Deserialize)]
-struct LlmLogsQuery {
-    #[serde(default)]
-    level: Option<String>,
-    #[serde(default)]
-    model: Option<String>,
-    #[serde(default)]
-    run_id: Option<Uuid>,
-    #[serde(default)]
-    since: Option<String>,
-    #[serde(default)]
-    limit: Option<usize>,
+struct MdHistoryQuery {
+    path: String,
 }
 
 #[derive(Debug, Serialize)]
-struct LlmLogsResponse {
-    entries: Vec<crate::llm::LlmLogEntry>,
+struct MdHistoryResponse {
+    path: String,
+    revisions: Vec<storage::MarkdownRevision>,
 }
 
-async fn llm_logs(
+/// Revisions recorded for a markdown file, newest first.
+async fn md_file_history(
     State(state): State<ServerState>,
-    Query(params): Query<LlmLogsQuery>,
+    Query(params): Query<MdHistoryQuery>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
-
-    let since = params
-        .since
-        .as_deref()
-        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
-        .map(|dt| dt.with_timezone(&Utc));
-
-    let query = storage::LlmLogQuery {
-        phase: params.level.clone(),
-        model: params.model.clone(),
-        run_id: params.run_id,
-        since,
-        limit: params.limit.unwrap_or(100),
+    let data_dir = state.ctx().config().data_dir.clone();
+    let relative = match storage::markdown_relative_path(&params.path) {
+        Ok(relative) => relative,
+        Err(err) => {
+            warn!(error = ?err, "invalid markdown history path requested");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
     };
 
-    match storage::read_llm_logs(&data_dir, query).await {
-        Ok(entries) => Json(LlmLogsResponse { entries }).into_response(),
+    match storage::list_markdown_revisions(&data_dir, &relative).await {
+        Ok(revisions) => Json(MdHistoryResponse {
+            path: relative.to_string_lossy().to_string(),
+            revisions,
+        })
+        .into_response(),
         Err(err) => {
-            warn!(error = ?err, "failed to read llm logs");
+            warn!(error = ?err, path = %params.path, "failed to list markdown history");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
+/// Revision id, or `current` for the file as it is on disk.
+const MD_CURRENT_REVISION: &str = "current";
+
 #[derive(Debug, Deserialize)]
-struct MessageQueryParams {
-    #[serde(default)]
-    dir: Option<String>,
-    #[serde(default)]
-    src: Option<String>,
+struct MdDiffQuery {
+    path: String,
+    /// Defaults to the latest revision.
     #[serde(default)]
-    limit: Option<usize>,
+    from: Option<String>,
+    /// Defaults to `current`.
     #[serde(default)]
-    since: Option<String>,
+    to: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct MessageListResponse {
-    entries: Vec<MessageLogEntry>,
+#[derive(Debug, Serialize)]
+struct MdDiffResponse {
+    path: String,
+    from: String,
+    to: String,
+    diff: String,
 }
 
-async fn list_messages(
+/// Unified diff between two revisions of a markdown file, or between a
+/// revision and the file on disk.
+async fn md_file_diff(
     State(state): State<ServerState>,
-    Query(params): Query<MessageQueryParams>,
+    Query(params): Query<MdDiffQuery>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let data_dir = config.data_dir.clone();
-    drop(config);
+    let data_dir = state.ctx().config().data_dir.clone();
+    let relative = match storage::markdown_relative_path(&params.path) {
+        Ok(relative) => relative,
+        Err(err) => {
+            warn!(error = ?err, "invalid markdown diff path requested");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
 
-    let direction = match params.dir.as_deref().filter(|value| !value.is_empty()) {
-        Some(raw) => match MessageDirection::from_str(raw) {
-            Ok(direction) => Some(direction),
-            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    let from = match params.from {
+        Some(from) => from,
+        None => match storage::list_markdown_revisions(&data_dir, &relative).await {
+            Ok(revisions) => match revisions.into_iter().next() {
+                Some(latest) => latest.id,
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+            Err(err) => {
+                warn!(error = ?err, path = %params.path, "failed to list markdown history");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
         },
-        None => None,
     };
-
-    let source = match params.src.as_deref().filter(|value| !value.is_empty()) {
-        Some("all") => None,
-        Some(other) => Some(other.to_string()),
-        None => None,
+    let to = params.to.unwrap_or_else(|| MD_CURRENT_REVISION.to_string());
+
+    let load = |id: String| {
+        let data_dir = data_dir.clone();
+        let relative = relative.clone();
+        async move {
+            if id == MD_CURRENT_REVISION {
+                storage::read_markdown_file(&data_dir, &relative).await
+            } else {
+                storage::read_markdown_revision(&data_dir, &relative, &id).await
+            }
+        }
+    };
+    let (old, new) = match tokio::try_join!(load(from.clone()), load(to.clone())) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!(error = ?err, path = %params.path, "failed to load markdown revisions");
+            return StatusCode::NOT_FOUND.into_response();
+        }
     };
 
-    let since = params
-        .since
-        .as_deref()
-        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
-        .map(|dt| dt.with_timezone(&Utc));
+    Json(MdDiffResponse {
+        path: relative.to_string_lossy().to_string(),
+        diff: storage::diff_markdown(&old, &new, &from, &to),
+        from,
+        to,
+    })
+    .into_response()
+}
 
-    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+#[derive(Debug, Deserialize)]
+struct MdRevertRequest {
+    path: String,
+    revision: String,
+}
 
-    let query = MessageLogQuery {
-        source,
-        direction,
-        since,
-        limit,
+/// Write a revision back over a markdown file. The content it replaces is
+/// recorded first, so the revert shows up in the history and can be undone.
+async fn md_file_revert(
+    State(state): State<ServerState>,
+    Json(request): Json<MdRevertRequest>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let relative = match storage::markdown_relative_path(&request.path) {
+        Ok(relative) => relative,
+        Err(err) => {
+            warn!(error = ?err, "invalid markdown revert path requested");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
     };
 
-    let handle = task::spawn_blocking(move || storage::read_messages(&data_dir, query));
-    match handle.await {
-        Ok(Ok(entries)) => Json(MessageListResponse { entries }).into_response(),
-        Ok(Err(err)) => {
-            warn!(error = ?err, "failed to load message logs");
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    if let Err(err) = storage::read_markdown_revision(&data_dir, &relative, &request.revision).await
+    {
+        warn!(error = ?err, path = %request.path, "markdown revision not found");
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let keep_history = !state.ctx().writes_paused();
+    match storage::revert_markdown(
+        &data_dir,
+        &relative,
+        &request.revision,
+        keep_history,
+        state.ctx().now(),
+    )
+    .await
+    {
+        Ok(content) => {
+            info!(path = %request.path, revision = %request.revision, "reverted markdown file");
+            Json(MdFileResponse {
+                path: relative.to_string_lossy().to_string(),
+                links: storage::parse_journal_links(&content),
+                content,
+            })
+            .into_response()
         }
         Err(err) => {
-            warn!(error = ?err, "message log task join failure");
+            warn!(error = ?err, path = %request.path, "failed to revert markdown file");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct SendMessageRequest {
-    #[serde(default)]
-    source: Option<String>,
-    text: String,
-    #[serde(default)]
-    chat_id: Option<i64>,
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum TextStructurePreviewSource {
+    Inline,
+    File,
 }
 
-#[derive(Debug, Serialize)]
-struct SendMessageResponse {
-    ok: bool,
+#[derive(Debug, Serialize, Deserialize)]
+struct TextStructurePreviewResponse {
+    #[serde(flatten)]
+    content: StructuredContent,
+    source: TextStructurePreviewSource,
     #[serde(skip_serializing_if = "Option::is_none")]
-    provider_message_id: Option<String>,
+    note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at: Option<DateTime<Utc>>,
 }
 
-async fn send_message(
-    State(state): State<ServerState>,
-    Json(payload): Json<SendMessageRequest>,
-) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let Some(telegram) = config.telegram.clone() else {
-        return StatusCode::NOT_IMPLEMENTED.into_response();
-    };
-    let data_dir = config.data_dir.clone();
-    drop(config);
+/// Path parameters of the preview routes. Every route exists once for the
+/// default preview (no `name`) and once under `/api/mock/text_structure/:name`.
+#[derive(Debug, Default, Deserialize)]
+struct TextStructurePath {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    id: Option<String>,
+}
 
-    let source = payload.source.unwrap_or_else(|| "telegram".to_string());
-    if source != "telegram" {
-        return StatusCode::BAD_REQUEST.into_response();
+impl TextStructurePath {
+    fn from_request(path: Option<Path<TextStructurePath>>) -> Self {
+        path.map(|Path(path)| path).unwrap_or_default()
     }
 
-    let text = payload.text.trim().to_string();
-    if text.is_empty() {
-        return StatusCode::BAD_REQUEST.into_response();
+    /// The preview name, `None` for the default one.
+    fn preview_name(&self) -> anyhow::Result<Option<&str>> {
+        match self.name.as_deref() {
+            Some(name) => storage::normalize_preview_name(name),
+            None => Ok(None),
+        }
     }
+}
 
-    let chat_id = match payload.chat_id.or(telegram.default_chat_id) {
-        Some(id) => id,
-        None => return StatusCode::BAD_REQUEST.into_response(),
-    };
-
-    let send_result = match dispatch_telegram_message(&telegram, chat_id, &text).await {
-        Ok(result) => result,
-        Err(err) => {
-            warn!(error = ?err, "failed to push telegram message");
-            return StatusCode::BAD_GATEWAY.into_response();
-        }
-    };
+fn publish_preview_change(
+    state: &ServerState,
+    kind: PreviewChangeKind,
+    name: Option<&str>,
+    note: Option<&str>,
+    history_id: Option<&str>,
+) {
+    let name = name.unwrap_or(storage::DEFAULT_PREVIEW_NAME);
+    let mut event = PreviewChanged::new(kind, name, state.ctx().now());
+    event.note = note.map(str::to_string);
+    event.history_id = history_id.map(str::to_string);
+    state.ctx().events().publish_preview_changed(event);
+}
 
-    let entry = MessageLogEntry {
-        id: Uuid::new_v4(),
-        direction: MessageDirection::Outbound,
-        source: "telegram".to_string(),
-        chat_id: chat_id.to_string(),
-        author: Some("telos".to_string()),
-        text: text.clone(),
-        timestamp: Utc::now(),
-        metadata: Some(json!({ "message_id": send_result.message_id })),
-    };
+#[derive(Debug, Deserialize)]
+struct TextStructureStreamQuery {
+    /// Only changes to this preview; `default` for the unnamed one.
+    #[serde(default)]
+    name: Option<String>,
+}
 
-    if let Err(err) = storage::append_message_entry(&data_dir, &entry).await {
-        warn!(error = ?err, "failed to persist outbound message log");
-    }
+/// Preview saves, restores and resets as server-sent `preview_changed`
+/// events; a `lagged` event reports changes a slow client missed, after
+/// which it should refetch.
+async fn text_structure_change_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<TextStructureStreamQuery>,
+) -> impl IntoResponse {
+    let receiver = state.ctx().events().subscribe_preview_changes();
+    let stream = BroadcastStream::new(receiver).filter_map(move |received| match received {
+        Ok(event) => params
+            .name
+            .as_ref()
+            .is_none_or(|name| *name == event.name)
+            .then(|| Event::default().event("preview_changed").json_data(&event)),
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => Some(Ok(Event::default()
+            .event("lagged")
+            .data(skipped.to_string()))),
+    });
 
-    Json(SendMessageResponse {
-        ok: true,
-        provider_message_id: send_result.message_id.map(|id| id.to_string()),
-    })
-    .into_response()
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text(": keep-alive"),
+        )
+        .into_response()
 }
 
-#[derive(Debug, Deserialize)]
-struct MemoryQueryParams {
-    level: Option<String>,
-    limit: Option<usize>,
-    since: Option<String>,
-    tag: Option<String>,
+fn invalid_preview_name(err: anyhow::Error) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({ "error": err.to_string() })),
+    )
+        .into_response()
 }
 
-#[derive(Debug, Serialize)]
-struct MemoryTimelineResponse {
-    level: MemoryLevel,
-    entries: Vec<storage::MemoryEntry>,
+async fn text_structure_previews(State(state): State<ServerState>) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+    match storage::list_structured_text_previews(&data_dir).await {
+        Ok(previews) => Json(serde_json::json!({ "previews": previews })).into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to list structured text previews");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
 
-async fn memory_timeline(
+async fn text_structure_preview(
     State(state): State<ServerState>,
-    Query(params): Query<MemoryQueryParams>,
-) -> impl IntoResponse {
+    path: Option<Path<TextStructurePath>>,
+) -> Response {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
+    };
     let config = state.ctx().config();
     let data_dir = config.data_dir.clone();
     drop(config);
 
-    let level = match params
-        .level
-        .as_deref()
-        .map(parse_memory_level)
-        .unwrap_or(Some(MemoryLevel::L2))
-    {
-        Some(level) => level,
-        None => return StatusCode::BAD_REQUEST.into_response(),
-    };
-
-    let since = match params.since.as_deref() {
-        Some(raw) => match DateTime::parse_from_rfc3339(raw) {
-            Ok(value) => Some(value.with_timezone(&Utc)),
-            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
-        },
-        None => None,
-    };
+    match storage::load_structured_text_preview(&data_dir, name).await {
+        Ok(Some(LoadedStructuredTextPreview {
+            content,
+            note,
+            updated_at,
+        })) => Json(TextStructurePreviewResponse {
+            content,
+            source: TextStructurePreviewSource::File,
+            note,
+            updated_at,
+        })
+        .into_response(),
+        // Named previews have no built-in fixture to fall back to.
+        Ok(None) if name.is_some() => StatusCode::NOT_FOUND.into_response(),
+        Ok(None) => Json(TextStructurePreviewResponse {
+            content: StructuredContent::mock_payload(),
+            source: TextStructurePreviewSource::Inline,
+            note: None,
+            updated_at: None,
+        })
+        .into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to load structured text preview; falling back to inline mock");
+            Json(TextStructurePreviewResponse {
+                content: StructuredContent::mock_payload(),
+                source: TextStructurePreviewSource::Inline,
+                note: None,
+                updated_at: None,
+            })
+            .into_response()
+        }
+    }
+}
 
-    let limit = params.limit.unwrap_or(20).clamp(1, 200);
-    let query = MemoryQuery {
-        level,
-        limit,
-        since,
-        tag: params.tag.clone(),
+async fn update_text_structure_preview(
+    State(state): State<ServerState>,
+    path: Option<Path<TextStructurePath>>,
+    Json(payload): Json<TextStructurePreviewUpdate>,
+) -> impl IntoResponse {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
     };
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
 
-    let data_dir_clone = data_dir.clone();
-    let query_clone = query.clone();
+    let (content, note) = payload.into_parts();
+    let violations = content.validate(note.as_deref());
+    if !violations.is_empty() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "error": "invalid structured text",
+                "violations": violations,
+            })),
+        )
+            .into_response();
+    }
 
-    let entries = match task::spawn_blocking(move || {
-        storage::read_memory_entries(&data_dir_clone, query_clone)
-    })
+    let keep_history = !state.ctx().writes_paused();
+    match storage::save_structured_text_preview(
+        &data_dir,
+        name,
+        &content,
+        note.as_deref(),
+        keep_history,
+        state.ctx().now(),
+    )
     .await
     {
-        Ok(Ok(entries)) => entries,
-        Ok(Err(err)) => {
-            warn!(error = ?err, "failed to load memory timeline");
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        Ok(()) => {
+            publish_preview_change(
+                &state,
+                PreviewChangeKind::Saved,
+                name,
+                note.as_deref(),
+                None,
+            );
+            StatusCode::NO_CONTENT.into_response()
         }
         Err(err) => {
-            warn!(error = ?err, "memory timeline task panicked");
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            warn!(error = ?err, "failed to persist structured text preview");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
-    };
-
-    Json(MemoryTimelineResponse {
-        level: query.level,
-        entries,
-    })
-    .into_response()
-}
-
-fn parse_memory_level(raw: &str) -> Option<MemoryLevel> {
-    match raw.to_ascii_uppercase().as_str() {
-        "L1" => Some(MemoryLevel::L1),
-        "L2" => Some(MemoryLevel::L2),
-        _ => None,
-    }
-}
-
-#[derive(Debug, Deserialize)]
-struct TelegramUpdate {
-    #[serde(default)]
-    message: Option<TelegramMessage>,
-    #[serde(default)]
-    channel_post: Option<TelegramMessage>,
-}
-
-impl TelegramUpdate {
-    fn primary_message(&self) -> Option<&TelegramMessage> {
-        self.message.as_ref().or(self.channel_post.as_ref())
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct TelegramMessage {
-    message_id: i64,
-    date: i64,
-    #[serde(default)]
-    text: Option<String>,
-    chat: TelegramChat,
-    #[serde(default)]
-    from: Option<TelegramUser>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TelegramChat {
-    id: i64,
-    #[serde(default, rename = "title")]
-    _title: Option<String>,
-    #[serde(default, rename = "username")]
-    _username: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct TelegramUser {
-    #[serde(default, rename = "id")]
-    _id: i64,
+struct TextStructureFromMarkdownRequest {
+    /// Markdown file relative to the data dir.
     #[serde(default)]
-    username: Option<String>,
+    path: Option<String>,
+    /// Raw markdown, instead of `path`.
     #[serde(default)]
-    first_name: Option<String>,
+    markdown: Option<String>,
+    /// Replace the summary with one written by the configured LLM.
     #[serde(default)]
-    last_name: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TelegramWebhookResponse {
-    status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    intent_id: Option<Uuid>,
+    summarize: bool,
 }
 
-async fn telegram_webhook(
+/// Convert markdown into the preview (see
+/// [`storage::structured_content_from_markdown`]) and save it with a note
+/// recording where it came from.
+async fn text_structure_from_markdown(
     State(state): State<ServerState>,
-    headers: HeaderMap,
-    Json(update): Json<TelegramUpdate>,
+    path: Option<Path<TextStructurePath>>,
+    Json(request): Json<TextStructureFromMarkdownRequest>,
 ) -> impl IntoResponse {
-    let config = state.ctx().config();
-    let Some(telegram) = config.telegram.clone() else {
-        return StatusCode::NOT_IMPLEMENTED.into_response();
+    let slot = TextStructurePath::from_request(path);
+    let name = match slot.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
     };
-    let data_dir = config.data_dir.clone();
-    drop(config);
+    let data_dir = state.ctx().config().data_dir.clone();
 
-    if let Some(expected) = telegram.webhook_secret.as_ref() {
-        match headers
-            .get("x-telegram-bot-api-secret-token")
-            .and_then(|value| value.to_str().ok())
-        {
-            Some(provided) if provided == expected => {}
-            Some(_) => return StatusCode::UNAUTHORIZED.into_response(),
-            None => return StatusCode::UNAUTHORIZED.into_response(),
+    let (markdown, fallback_title, origin) = match (request.path, request.markdown) {
+        (Some(path), None) => {
+            let relative = match storage::sanitize_data_relative_path(&path) {
+                Ok(relative) => relative,
+                Err(err) => {
+                    warn!(error = ?err, path = %path, "invalid markdown path for preview");
+                    return StatusCode::BAD_REQUEST.into_response();
+                }
+            };
+            match storage::read_markdown_file(&data_dir, &relative).await {
+                Ok(markdown) => {
+                    let stem = relative
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let origin = format!("markdown {}", relative.display());
+                    (markdown, stem, origin)
+                }
+                Err(err) => {
+                    warn!(error = ?err, path = %path, "failed to read markdown for preview");
+                    return StatusCode::NOT_FOUND.into_response();
+                }
+            }
+        }
+        (None, Some(markdown)) => (
+            markdown,
+            "Markdown import".to_string(),
+            "submitted markdown".to_string(),
+        ),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "pass exactly one of path or markdown" })),
+            )
+                .into_response();
         }
-    }
-
-    let Some(message) = update.primary_message() else {
-        return Json(TelegramWebhookResponse {
-            status: "ignored".to_string(),
-            intent_id: None,
-        })
-        .into_response();
-    };
-
-    let Some(text) = message
-        .text
-        .as_ref()
-        .map(|t| t.trim())
-        .filter(|t| !t.is_empty())
-    else {
-        return Json(TelegramWebhookResponse {
-            status: "ignored".to_string(),
-            intent_id: None,
-        })
-        .into_response();
     };
 
-    let timestamp = DateTime::<Utc>::from_timestamp(message.date, 0).unwrap_or_else(Utc::now);
-
-    let author = message.from.as_ref().and_then(|from| {
-        if let Some(username) = from.username.clone() {
-            Some(username)
-        } else {
-            let mut name = String::new();
-            if let Some(first) = &from.first_name {
-                name.push_str(first);
-            }
-            if let Some(last) = &from.last_name {
-                if !name.is_empty() {
-                    name.push(' ');
+    let mut content = storage::structured_content_from_markdown(&markdown, &fallback_title);
+    let mut note = format!("Generated from {origin}");
+    if request.summarize {
+        let agent = state.ctx().agent();
+        match agent.summarize_document(&content.title, &markdown).await {
+            Ok((summary, llm_logs)) => {
+                if !state.ctx().writes_paused()
+                    && let Err(err) = storage::append_llm_logs(&data_dir, &llm_logs).await
+                {
+                    warn!(error = ?err, "failed to persist summary LLM logs");
                 }
-                name.push_str(last);
+                if let Some(entry) = llm_logs.first() {
+                    note.push_str(&format!("; summary by {}", entry.provider));
+                    if let Some(model) = &entry.model {
+                        note.push_str(&format!("/{model}"));
+                    }
+                }
+                content.summary = summary;
+            }
+            Err(err) => {
+                warn!(error = ?err, "failed to summarize markdown for preview");
+                return StatusCode::BAD_GATEWAY.into_response();
             }
-            if name.is_empty() { None } else { Some(name) }
         }
-    });
-
-    let log_entry = MessageLogEntry {
-        id: Uuid::new_v4(),
-        direction: MessageDirection::Inbound,
-        source: "telegram".to_string(),
-        chat_id: message.chat.id.to_string(),
-        author: author.clone(),
-        text: text.to_string(),
-        timestamp,
-        metadata: Some(json!({ "message_id": message.message_id })),
-    };
-
-    if let Err(err) = storage::append_message_entry(&data_dir, &log_entry).await {
-        warn!(error = ?err, "failed to persist inbound telegram message");
     }
 
-    let mut summary: String = text.chars().take(80).collect();
-    if text.chars().count() > 80 {
-        summary.push('…');
+    let violations = content.validate(Some(&note));
+    if !violations.is_empty() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "error": "invalid structured text",
+                "violations": violations,
+            })),
+        )
+            .into_response();
     }
+    let keep_history = !state.ctx().writes_paused();
+    if let Err(err) = storage::save_structured_text_preview(
+        &data_dir,
+        name,
+        &content,
+        Some(&note),
+        keep_history,
+        state.ctx().now(),
+    )
+    .await
+    {
+        warn!(error = ?err, "failed to persist structured text preview");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    publish_preview_change(&state, PreviewChangeKind::Saved, name, Some(&note), None);
 
-    let body = format!(
-        "Telegram chat: {}
-Author: {}
-Message ID: {}
-
-{}",
-        message.chat.id,
-        author.clone().unwrap_or_else(|| "unknown".to_string()),
-        message.message_id,
-        text
-    );
+    let updated_at = match storage::load_structured_text_preview(&data_dir, name).await {
+        Ok(Some(loaded)) => loaded.updated_at,
+        _ => None,
+    };
+    Json(TextStructurePreviewResponse {
+        content,
+        source: TextStructurePreviewSource::File,
+        note: Some(note),
+        updated_at,
+    })
+    .into_response()
+}
 
-    let intent_result = storage::persist_intent(&data_dir, "telegram", &summary, 1.0, &body).await;
+async fn reset_text_structure_preview(
+    State(state): State<ServerState>,
+    path: Option<Path<TextStructurePath>>,
+) -> impl IntoResponse {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
+    };
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
 
-    let intent_id = match intent_result {
-        Ok(record) => {
-            if let Err(err) = state.orchestrator().request_beat().await {
-                warn!(error = ?err, "failed to request beat after telegram intent");
-            }
-            Some(record.id)
+    match storage::delete_structured_text_preview(&data_dir, name).await {
+        Ok(()) => {
+            publish_preview_change(&state, PreviewChangeKind::Deleted, name, None, None);
+            StatusCode::NO_CONTENT.into_response()
         }
         Err(err) => {
-            warn!(error = ?err, "failed to persist intent from telegram message");
-            None
+            warn!(error = ?err, "failed to delete structured text preview");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
-    };
+    }
+}
 
-    Json(TelegramWebhookResponse {
-        status: "queued".to_string(),
-        intent_id,
-    })
-    .into_response()
+#[derive(Debug, Deserialize)]
+struct TextStructureHistoryQuery {
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    since: Option<DateTime<Utc>>,
+    #[serde(default, rename = "q")]
+    query: Option<String>,
+    /// `next_cursor` of the previous page.
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    offset: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TextStructurePreviewUpdate {
+    Content(StructuredContent),
+    WithNote {
+        content: StructuredContent,
+        #[serde(default)]
+        note: Option<String>,
+    },
 }
 
-struct TelegramSendResult {
-    message_id: Option<i64>,
+impl TextStructurePreviewUpdate {
+    fn into_parts(self) -> (StructuredContent, Option<String>) {
+        match self {
+            TextStructurePreviewUpdate::Content(content) => (content, None),
+            TextStructurePreviewUpdate::WithNote { content, note } => (content, note),
+        }
+    }
 }
 
-async fn dispatch_telegram_message(
-    config: &crate::config::TelegramConfig,
-    chat_id: i64,
-    text: &str,
-) -> anyhow::Result<TelegramSendResult> {
-    let client = Client::new();
-    let base = config.api_base.trim_end_matches('/');
-    let url = format!("{}/bot{}/sendMessage", base, config.bot_token);
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct TextStructureHistoryResponse {
+    entries: Vec<StructuredTextHistoryEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
 
-    let response = client
-        .post(url)
-        .json(&json!({
-            "chat_id": chat_id,
-            "text": text,
-        }))
-        .send()
-        .await
-        .with_context(|| "sending telegram message")?;
+async fn text_structure_history(
+    State(state): State<ServerState>,
+    path: Option<Path<TextStructurePath>>,
+    Query(params): Query<TextStructureHistoryQuery>,
+) -> impl IntoResponse {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
+    };
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let TextStructureHistoryQuery {
+        limit,
+        since,
+        query,
+        cursor,
+        offset,
+    } = params;
+    let limit = limit.unwrap_or(DEFAULT_TEXT_STRUCTURE_HISTORY_LIMIT);
+    let filters = StructuredTextHistoryFilters {
+        since,
+        note_query: query,
+    };
+    let filters = if filters == StructuredTextHistoryFilters::default() {
+        None
+    } else {
+        Some(filters)
+    };
+    let filter_ref = filters.as_ref();
+    let cursor = cursor.as_deref().map(str::trim).filter(|id| !id.is_empty());
+
+    match storage::list_structured_text_history_page(
+        &data_dir, name, limit, filter_ref, cursor, offset,
+    )
+    .await
+    {
+        Ok(page) => Json(TextStructureHistoryResponse {
+            entries: page.entries,
+            next_cursor: page.next_cursor,
+        })
+        .into_response(),
+        Err(err) if err.root_cause().is::<chrono::ParseError>() => {
+            StatusCode::BAD_REQUEST.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "failed to list structured text history");
+            Json(TextStructureHistoryResponse {
+                entries: Vec::new(),
+                next_cursor: None,
+            })
+            .into_response()
+        }
+    }
+}
+
+async fn text_structure_history_entry(
+    State(state): State<ServerState>,
+    path: Option<Path<TextStructurePath>>,
+) -> impl IntoResponse {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
+    };
+    let Some(id) = path.id.as_deref() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
 
-    if !response.status().is_success() {
-        return Err(anyhow!("telegram returned status {}", response.status()));
+    match storage::load_structured_text_history_entry(&data_dir, name, id).await {
+        Ok(Some(entry)) => Json(entry).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            if err.root_cause().is::<chrono::ParseError>() {
+                StatusCode::BAD_REQUEST.into_response()
+            } else {
+                warn!(error = ?err, id = %id, "failed to load structured text history entry");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
     }
+}
 
-    let payload: serde_json::Value = response
-        .json()
-        .await
-        .with_context(|| "decoding telegram response")?;
+async fn restore_text_structure_history_entry(
+    State(state): State<ServerState>,
+    path: Option<Path<TextStructurePath>>,
+) -> impl IntoResponse {
+    let path = TextStructurePath::from_request(path);
+    let name = match path.preview_name() {
+        Ok(name) => name,
+        Err(err) => return invalid_preview_name(err),
+    };
+    let Some(id) = path.id.as_deref() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
 
-    let ok = payload
-        .get("ok")
-        .and_then(|flag| flag.as_bool())
-        .unwrap_or(false);
-    if !ok {
-        return Err(anyhow!("telegram send rejected: {}", payload));
+    let keep_history = !state.ctx().writes_paused();
+    match storage::restore_structured_text_preview_from_history(
+        &data_dir,
+        name,
+        id,
+        keep_history,
+        state.ctx().now(),
+    )
+    .await
+    {
+        Ok(true) => {
+            publish_preview_change(&state, PreviewChangeKind::Restored, name, None, Some(id));
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            if err.root_cause().is::<chrono::ParseError>() {
+                StatusCode::BAD_REQUEST.into_response()
+            } else {
+                warn!(error = ?err, id = %id, "failed to restore structured text history entry");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
     }
+}
 
-    let message_id = payload
-        .get("result")
-        .or_else(|| payload.get("message"))
-        .and_then(|value| value.get("message_id"))
-        .and_then(|value| value.as_i64());
+/// Markdown to HTML with anything scriptable stripped: journals quote
+/// Telegram and email text verbatim, so raw HTML in them is untrusted.
+fn render_markdown(markdown: &str) -> String {
+    use pulldown_cmark::{Options, Parser, html};
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
 
-    Ok(TelegramSendResult { message_id })
+    let parser = Parser::new_ext(markdown, options);
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    ammonia::clean(&output)
 }
 
 #[derive(Debug, Deserialize)]
-struct NewIntentRequest {
-    #[serde(default = "default_source")]
-    source: String,
-    summary: String,
-    #[serde(default = "default_alignment")]
-    telos_alignment: f32,
+struct LlmLogsQuery {
     #[serde(default)]
-    body: String,
+    level: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    run_id: Option<Uuid>,
+    #[serde(default)]
+    since: Option<String>,
+    /// RFC3339, inclusive.
+    #[serde(default)]
+    until: Option<String>,
+    /// `next_cursor` of the previous page.
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
-struct NewIntentResponse {
-    id: Uuid,
-    path: String,
-    beat_scheduled: bool,
+struct LlmLogsResponse {
+    entries: Vec<crate::llm::LlmLogEntry>,
+    /// Set when the page is full; pass it back as `cursor` for older entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
 }
 
-async fn create_intent(
+async fn llm_logs(
     State(state): State<ServerState>,
-    Json(payload): Json<NewIntentRequest>,
+    Query(params): Query<LlmLogsQuery>,
 ) -> impl IntoResponse {
     let config = state.ctx().config();
     let data_dir = config.data_dir.clone();
     drop(config);
 
-    let NewIntentRequest {
-        source,
-        summary,
-        telos_alignment,
-        body,
-    } = payload;
-
-    let persist_result =
-        storage::persist_intent(&data_dir, &source, &summary, telos_alignment, &body).await;
+    let parse_time = |value: Option<&str>| {
+        value
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    let cursor = match params.cursor.as_deref().map(storage::LlmLogCursor::parse) {
+        Some(Ok(cursor)) => Some(cursor),
+        Some(Err(_)) => return StatusCode::BAD_REQUEST.into_response(),
+        None => None,
+    };
 
-    match persist_result {
-        Ok(record) => {
-            let beat_scheduled = match state.orchestrator().request_beat().await {
-                Ok(()) => true,
-                Err(err) => {
-                    warn!(error = ?err, "failed to schedule beat after intent creation");
-                    false
-                }
-            };
+    let query = storage::LlmLogQuery {
+        phase: params.level.clone(),
+        model: params.model.clone(),
+        run_id: params.run_id,
+        since: parse_time(params.since.as_deref()),
+        until: parse_time(params.until.as_deref()),
+        cursor,
+        limit: params.limit.filter(|limit| *limit > 0).unwrap_or(100),
+    };
+    let limit = query.limit;
 
-            let body = Json(NewIntentResponse {
-                id: record.id,
-                path: record.path.to_string_lossy().to_string(),
-                beat_scheduled,
-            });
-            (StatusCode::ACCEPTED, body).into_response()
+    match storage::read_llm_logs(&data_dir, query).await {
+        Ok(entries) => {
+            let next_cursor = (entries.len() >= limit)
+                .then(|| storage::LlmLogCursor::after_page(cursor.as_ref(), &entries))
+                .flatten()
+                .map(|cursor| cursor.encode());
+            Json(LlmLogsResponse {
+                entries,
+                next_cursor,
+            })
+            .into_response()
         }
         Err(err) => {
-            warn!(error = ?err, "failed to persist intent");
+            warn!(error = ?err, "failed to read llm logs");
             StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
 }
 
-fn default_source() -> String {
-    "user".to_string()
-}
-
-fn default_alignment() -> f32 {
-    0.5
-}
+/// LLM calls as they complete, as server-sent `llm_log` events carrying
+/// the entry JSON. `level`, `model` and `run_id` filter like
+/// `/api/logs/llm`; a `lagged` event reports entries a slow client missed.
+/// Answers 409 in the `server` role.
+async fn llm_log_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<LlmLogsQuery>,
+) -> Response {
+    // LLM calls are made, and broadcast, in the worker; a `server` role
+    // process would stream nothing.
+    if !state.ctx().config().server.role.runs_worker() {
+        return StatusCode::CONFLICT.into_response();
+    }
+    let stream = BroadcastStream::new(state.ctx().agent().subscribe_llm_logs()).filter_map(
+        move |received| match received {
+            Ok(entry) => {
+                let level_ok = params
+                    .level
+                    .as_ref()
+                    .is_none_or(|level| entry.phase.eq_ignore_ascii_case(level));
+                let model_ok = params.model.as_ref().is_none_or(|model| {
+                    entry
+                        .model
+                        .as_ref()
+                        .is_some_and(|value| value.eq_ignore_ascii_case(model))
+                });
+                let run_ok = params.run_id.is_none_or(|run_id| entry.run_id == run_id);
+                (level_ok && model_ok && run_ok)
+                    .then(|| Event::default().event("llm_log").json_data(&entry))
+            }
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => Some(Ok(Event::default()
+                .event("lagged")
+                .data(skipped.to_string()))),
+        },
+    );
+
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text(": keep-alive"),
+        )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageQueryParams {
+    #[serde(default)]
+    dir: Option<String>,
+    #[serde(default)]
+    src: Option<String>,
+    #[serde(default)]
+    chat_id: Option<String>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    since: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MessageListResponse {
+    entries: Vec<MessageLogEntry>,
+}
+
+async fn list_messages(
+    State(state): State<ServerState>,
+    Query(params): Query<MessageQueryParams>,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let direction = match params.dir.as_deref().filter(|value| !value.is_empty()) {
+        Some(raw) => match MessageDirection::from_str(raw) {
+            Ok(direction) => Some(direction),
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        },
+        None => None,
+    };
+
+    let source = match params.src.as_deref().filter(|value| !value.is_empty()) {
+        Some("all") => None,
+        Some(other) => Some(other.to_string()),
+        None => None,
+    };
+
+    let since = params
+        .since
+        .as_deref()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|dt| dt.with_timezone(&Utc));
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+
+    let chat_id = params.chat_id.filter(|value| !value.is_empty());
+
+    let query = MessageLogQuery {
+        source,
+        direction,
+        chat_id,
+        since,
+        limit,
+    };
+
+    let handle = task::spawn_blocking(move || storage::read_messages(&data_dir, query));
+    match handle.await {
+        Ok(Ok(entries)) => Json(MessageListResponse { entries }).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to load message logs");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "message log task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMessageRequest {
+    #[serde(default)]
+    source: Option<String>,
+    text: String,
+    #[serde(default)]
+    chat_id: Option<i64>,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    subject: Option<String>,
+    /// Telegram only: deliver even during quiet hours.
+    #[serde(default)]
+    urgent: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SendMessageResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider_message_id: Option<String>,
+    /// Set for queued channels; a pending message is retried in the background.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outbox_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<OutboxStatus>,
+}
+
+async fn send_message(
+    State(state): State<ServerState>,
+    Json(payload): Json<SendMessageRequest>,
+) -> impl IntoResponse {
+    let source = payload
+        .source
+        .clone()
+        .unwrap_or_else(|| "telegram".to_string());
+    match source.as_str() {
+        "telegram" => {}
+        "email" => return send_email_message(state, payload).await,
+        _ => return StatusCode::BAD_REQUEST.into_response(),
+    }
+
+    let data_dir = state.ctx().config().data_dir.clone();
+    let channels = Channels::of(state.ctx());
+    let Some(telegram) = &channels.telegram else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+
+    let text = payload.text.trim().to_string();
+    if text.is_empty() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let chat_id = match payload.chat_id.or(telegram.default_chat_id) {
+        Some(id) => id,
+        None => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    let message = match outbox::send_or_queue(
+        &data_dir,
+        &channels,
+        chat_id,
+        &text,
+        payload.urgent,
+        state.ctx().now(),
+    )
+    .await
+    {
+        Ok(message) => message,
+        Err(err) => {
+            warn!(error = ?err, "failed to queue telegram message");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let delivered = message.status == OutboxStatus::Delivered;
+    let status = if delivered {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+    (
+        status,
+        Json(SendMessageResponse {
+            ok: delivered,
+            provider_message_id: message.provider_message_id.map(|id| id.to_string()),
+            outbox_id: Some(message.id),
+            status: Some(message.status),
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct OutboxParams {
+    status: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutboxResponse {
+    messages: Vec<OutboxMessage>,
+}
+
+async fn list_outbox(
+    State(state): State<ServerState>,
+    Query(params): Query<OutboxParams>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    let status = match params.status.as_deref().map(OutboxStatus::from_str) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(_)) => return StatusCode::BAD_REQUEST.into_response(),
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+
+    match task::spawn_blocking(move || storage::list_outbox_messages(&data_dir, status, limit))
+        .await
+    {
+        Ok(Ok(messages)) => Json(OutboxResponse { messages }).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to load outbox");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "outbox task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn send_email_message(state: ServerState, payload: SendMessageRequest) -> Response {
+    let config = state.ctx().config();
+    let Some(smtp) = config.email.as_ref().and_then(|email| email.smtp.clone()) else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let text = payload.text.trim();
+    let Some(to) = payload.to.as_deref().filter(|to| !to.trim().is_empty()) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if text.is_empty() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let subject = payload.subject.as_deref().unwrap_or("Message from Telos");
+
+    match email::send_email(
+        &data_dir,
+        &smtp,
+        to.trim(),
+        subject,
+        text,
+        state.ctx().now(),
+    )
+    .await
+    {
+        Ok(()) => Json(SendMessageResponse {
+            ok: true,
+            provider_message_id: None,
+            outbox_id: None,
+            status: None,
+        })
+        .into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to send email");
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryQueryParams {
+    level: Option<String>,
+    limit: Option<usize>,
+    since: Option<String>,
+    tag: Option<String>,
+    /// Free-text search over entry summaries and details.
+    q: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryTimelineResponse {
+    level: MemoryLevel,
+    entries: Vec<storage::MemoryEntry>,
+}
+
+async fn memory_timeline(
+    State(state): State<ServerState>,
+    Query(params): Query<MemoryQueryParams>,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let level = match params
+        .level
+        .as_deref()
+        .map(parse_memory_level)
+        .unwrap_or(Some(MemoryLevel::L2))
+    {
+        Some(level) => level,
+        None => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    let since = match params.since.as_deref() {
+        Some(raw) => match DateTime::parse_from_rfc3339(raw) {
+            Ok(value) => Some(value.with_timezone(&Utc)),
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        },
+        None => None,
+    };
+
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+    let query = MemoryQuery {
+        level,
+        limit,
+        since,
+        tag: params.tag.clone(),
+        text: params.q.filter(|q| !q.trim().is_empty()),
+    };
+
+    let data_dir_clone = data_dir.clone();
+    let query_clone = query.clone();
+
+    let entries = match task::spawn_blocking(move || {
+        storage::read_memory_entries(&data_dir_clone, query_clone)
+    })
+    .await
+    {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to load memory timeline");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        Err(err) => {
+            warn!(error = ?err, "memory timeline task panicked");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    Json(MemoryTimelineResponse {
+        level: query.level,
+        entries,
+    })
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryExportParams {
+    level: Option<String>,
+    since: Option<String>,
+    tag: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+async fn memory_export(
+    State(state): State<ServerState>,
+    Query(params): Query<MemoryExportParams>,
+) -> impl IntoResponse {
+    let Some(format) =
+        storage::MemoryExportFormat::parse(params.format.as_deref().unwrap_or("markdown"))
+    else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let level = match params
+        .level
+        .as_deref()
+        .map(parse_memory_level)
+        .unwrap_or(Some(MemoryLevel::L1))
+    {
+        Some(level) => level,
+        None => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    let since = match params.since.as_deref() {
+        Some(raw) => match DateTime::parse_from_rfc3339(raw) {
+            Ok(value) => Some(value.with_timezone(&Utc)),
+            Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+        },
+        None => None,
+    };
+
+    let query = MemoryQuery {
+        level,
+        limit: usize::MAX,
+        since,
+        tag: params.tag.clone(),
+        text: None,
+    };
+
+    let data_dir = state.ctx().config().data_dir.clone();
+    let read_dir = data_dir.clone();
+    let export = match task::spawn_blocking(move || storage::export_memory(&read_dir, query)).await
+    {
+        Ok(Ok(export)) => export,
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to export memory");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+        Err(err) => {
+            warn!(error = ?err, "memory export task join failure");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let exported_at = state.ctx().now();
+    let (reader, writer) = io::duplex(MEMORY_EXPORT_PIPE_BYTES);
+    let writer = SyncIoBridge::new(writer);
+    task::spawn_blocking(move || {
+        if let Err(err) = export.write_to(&data_dir, format, exported_at, writer) {
+            warn!(error = ?err, "failed to stream memory export");
+        }
+    });
+
+    let (content_type, file_name) = match format {
+        storage::MemoryExportFormat::Markdown => {
+            ("text/markdown; charset=utf-8", "memory-export.md")
+        }
+        storage::MemoryExportFormat::Json => ("application/json", "memory-export.json"),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(reader)),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryTagsParams {
+    level: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryTagsResponse {
+    level: MemoryLevel,
+    tags: Vec<storage::MemoryTagCount>,
+}
+
+async fn memory_tags(
+    State(state): State<ServerState>,
+    Query(params): Query<MemoryTagsParams>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    let level = match params
+        .level
+        .as_deref()
+        .map(parse_memory_level)
+        .unwrap_or(Some(MemoryLevel::L1))
+    {
+        Some(level) => level,
+        None => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    match task::spawn_blocking(move || storage::memory_tag_counts(&data_dir, level)).await {
+        Ok(Ok(tags)) => Json(MemoryTagsResponse { level, tags }).into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to count memory tags");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "memory tag task panicked");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryTagUpdateRequest {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+async fn update_memory_entry_tags(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MemoryTagUpdateRequest>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    match storage::update_memory_tags(
+        &data_dir,
+        id,
+        &payload.add,
+        &payload.remove,
+        state.ctx().now(),
+    )
+    .await
+    {
+        Ok(Some(entry)) => Json(entry).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, id = %id, "failed to update memory tags");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MemoryAnchorsParams {
+    preview: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryAnchorsResponse {
+    id: Uuid,
+    level: MemoryLevel,
+    anchors: Vec<storage::ResolvedMemoryAnchor>,
+}
+
+/// The `run.json` of a run's artifact directory.
+async fn run_manifest(
+    State(state): State<ServerState>,
+    Path(run_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    match task::spawn_blocking(move || storage::load_run_manifest(&data_dir, run_id)).await {
+        Ok(Ok(Some(manifest))) => Json(manifest).into_response(),
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, %run_id, "failed to load run manifest");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "run manifest task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// One file of a run's artifact directory, as plain text so saved pages
+/// are never rendered, or as bytes when it is not UTF-8.
+async fn run_artifact(
+    State(state): State<ServerState>,
+    Path((run_id, path)): Path<(Uuid, String)>,
+) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let Ok(file) = storage::run_artifact_path(&data_dir, run_id, &path) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    if !file.is_file() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let content = match tokio::fs::read(&file).await {
+        Ok(content) => content,
+        Err(err) => {
+            warn!(error = ?err, ?file, "failed to read run artifact");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let content_type = match std::str::from_utf8(&content) {
+        Ok(_) => "text/plain; charset=utf-8",
+        Err(_) => "application/octet-stream",
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+async fn memory_entry_anchors(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<MemoryAnchorsParams>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    match storage::find_memory_entry(&data_dir, id).await {
+        Ok(Some(entry)) => {
+            let anchors = storage::resolve_memory_anchors(&data_dir, &entry, params.preview).await;
+            Json(MemoryAnchorsResponse {
+                id: entry.id,
+                level: entry.level,
+                anchors,
+            })
+            .into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, id = %id, "failed to load memory entry");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn parse_memory_level(raw: &str) -> Option<MemoryLevel> {
+    match raw.to_ascii_uppercase().as_str() {
+        "L1" => Some(MemoryLevel::L1),
+        "L2" => Some(MemoryLevel::L2),
+        _ => None,
+    }
+}
+
+pub const TELEGRAM_WEBHOOK_PATH: &str = "/webhook/telegram";
+
+pub fn telegram_webhook_routes() -> Router<ServerState> {
+    Router::new().route(TELEGRAM_WEBHOOK_PATH, post(telegram_webhook))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TelegramWebhookResponse {
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intent_id: Option<Uuid>,
+}
+
+async fn telegram_webhook(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    Json(update): Json<TelegramUpdate>,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let Some(telegram) = config.telegram.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let data_dir = config.data_dir.clone();
+    drop(config);
+    let channels = Channels::of(state.ctx());
+
+    if let Some(expected) = telegram.webhook_secret.as_ref() {
+        match headers
+            .get("x-telegram-bot-api-secret-token")
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(provided) if provided == expected => {}
+            Some(_) => return StatusCode::UNAUTHORIZED.into_response(),
+            None => return StatusCode::UNAUTHORIZED.into_response(),
+        }
+    }
+
+    let ingested = telegram::ingest_update(
+        &data_dir,
+        &telegram,
+        &channels,
+        state.ctx().telegram_updates(),
+        &update,
+        state.ctx().now(),
+    )
+    .await;
+    if ingested.needs_beat()
+        && let Err(err) = state.orchestrator().request_beat().await
+    {
+        warn!(error = ?err, "failed to request beat after telegram update");
+    }
+
+    let (status, intent_id) = match ingested {
+        TelegramIngest::Ignored => ("ignored".to_string(), None),
+        TelegramIngest::Duplicate => ("duplicate".to_string(), None),
+        TelegramIngest::Unauthorized => ("unauthorized".to_string(), None),
+        TelegramIngest::Queued { intent_id } => ("queued".to_string(), intent_id),
+        TelegramIngest::Answered { intent_id } => ("answered".to_string(), Some(intent_id)),
+        TelegramIngest::Resolved {
+            intent_id,
+            decision,
+        } => (decision.as_str().to_string(), Some(intent_id)),
+    };
+    Json(TelegramWebhookResponse { status, intent_id }).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct IntentListParams {
+    #[serde(default)]
+    overdue: Option<bool>,
+    /// One of [`storage::INTENT_STAGES`]; without it, the intents still
+    /// waiting to run (`inbox`, `pending_approval`, `queue`).
+    #[serde(default)]
+    stage: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct IntentListResponse {
+    intents: Vec<storage::PendingIntent>,
+}
+
+async fn list_intents(
+    State(state): State<ServerState>,
+    Query(params): Query<IntentListParams>,
+) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    let stage = params.stage.clone();
+    let now = state.ctx().now();
+    let listed = task::spawn_blocking(move || match stage.as_deref() {
+        Some(stage) => storage::list_stage_intents(&data_dir, stage, now),
+        None => storage::list_pending_intents(&data_dir, now).map(Some),
+    })
+    .await;
+    match listed {
+        Ok(Ok(Some(mut intents))) => {
+            if let Some(overdue) = params.overdue {
+                intents.retain(|pending| pending.overdue == overdue);
+            }
+            Json(IntentListResponse { intents }).into_response()
+        }
+        Ok(Ok(None)) => StatusCode::BAD_REQUEST.into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, "failed to list pending intents");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "intent listing task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NewIntentRequest {
+    #[serde(default = "default_source")]
+    source: String,
+    summary: String,
+    #[serde(default = "default_alignment")]
+    telos_alignment: f32,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    due_at: Option<DateTime<Utc>>,
+    /// Checked against `beat.approval.max_cost_estimate`.
+    #[serde(default)]
+    cost_estimate: Option<f64>,
+    /// `low`, `normal` or `high`; `high` intents are queued ahead of others.
+    #[serde(default)]
+    priority: Option<IntentPriority>,
+    /// Entry of `agent.personas` to run as; unknown names are rejected.
+    #[serde(default)]
+    persona: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct NewIntentResponse {
+    id: Uuid,
+    path: String,
+    beat_scheduled: bool,
+}
+
+async fn create_intent(
+    State(state): State<ServerState>,
+    Json(payload): Json<NewIntentRequest>,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let NewIntentRequest {
+        source,
+        summary,
+        telos_alignment,
+        body,
+        due_at,
+        cost_estimate,
+        priority,
+        persona,
+    } = payload;
+    let persona = persona
+        .map(|persona| persona.trim().to_string())
+        .filter(|persona| !persona.is_empty());
+    if let Some(persona) = &persona
+        && let Err(err) = state
+            .ctx()
+            .agent()
+            .agent_config()
+            .persona_for(Some(persona))
+    {
+        warn!(error = %err, "rejecting intent with unknown persona");
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let persist_result = storage::persist_intent_at(
+        &data_dir,
+        &IntentDraft {
+            source,
+            summary,
+            telos_alignment,
+            body,
+            due_at,
+            metadata: cost_estimate
+                .map(|cost| (COST_ESTIMATE_KEY.to_string(), cost.to_string()))
+                .into_iter()
+                .chain(
+                    priority
+                        .map(|priority| (PRIORITY_KEY.to_string(), priority.as_str().to_string())),
+                )
+                .chain(persona.map(|persona| (PERSONA_KEY.to_string(), persona)))
+                .collect(),
+        },
+        state.ctx().now(),
+    )
+    .await;
+
+    match persist_result {
+        Ok(record) => {
+            let beat_scheduled = match state.orchestrator().request_beat().await {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!(error = ?err, "failed to schedule beat after intent creation");
+                    false
+                }
+            };
+
+            let body = Json(NewIntentResponse {
+                id: record.id,
+                path: record.path.to_string_lossy().to_string(),
+                beat_scheduled,
+            });
+            (StatusCode::ACCEPTED, body).into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "failed to persist intent");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct QuestionListResponse {
+    questions: Vec<storage::PendingQuestion>,
+}
+
+/// Runs paused on `ask_user`, answered or not, oldest first.
+async fn list_questions(State(state): State<ServerState>) -> impl IntoResponse {
+    let data_dir = state.ctx().config().data_dir.clone();
+    match storage::list_pending_questions(&data_dir) {
+        Ok(questions) => Json(QuestionListResponse { questions }).into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to list pending questions");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnswerRequest {
+    answer: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AnswerResponse {
+    intent_id: Uuid,
+    beat_scheduled: bool,
+}
+
+/// Answer the question an intent is waiting on, for intents that did not come
+/// from a chat the user can reply in.
+async fn answer_question(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<AnswerRequest>,
+) -> impl IntoResponse {
+    let answer = payload.answer.trim();
+    if answer.is_empty() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let data_dir = state.ctx().config().data_dir.clone();
+
+    match storage::answer_pending_question(&data_dir, id, answer, state.ctx().now()) {
+        Ok(Some(_)) => {
+            let beat_scheduled = match state.orchestrator().request_beat().await {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!(error = ?err, "failed to schedule beat after answer");
+                    false
+                }
+            };
+            Json(AnswerResponse {
+                intent_id: id,
+                beat_scheduled,
+            })
+            .into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, intent_id = %id, "failed to record answer");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApprovalResponse {
+    intent_id: Uuid,
+    status: &'static str,
+    beat_scheduled: bool,
+}
+
+/// Release an intent held in `intent/pending_approval` or deferred for low
+/// alignment into the inbox; it is queued on the beat this schedules.
+async fn approve_intent(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    decide_pending_approval(state, id, true).await
+}
+
+/// Discard an intent held in `intent/pending_approval` or deferred.
+async fn reject_intent(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    decide_pending_approval(state, id, false).await
+}
+
+async fn decide_pending_approval(state: ServerState, id: Uuid, approve: bool) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let found = match storage::find_pending_approval_intent(&data_dir, id) {
+        Ok(None) => storage::find_deferred_intent(&data_dir, id),
+        found => found,
+    };
+    let record = match found {
+        Ok(Some(record)) => record,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, intent_id = %id, "failed to look up pending approval");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let moved = if approve {
+        storage::approve_intent(&record.path, &data_dir, state.ctx().now())
+    } else {
+        storage::discard_intent(&record.path, &data_dir, state.ctx().now())
+    };
+    if let Err(err) = moved {
+        warn!(error = ?err, intent_id = %id, approve, "failed to apply approval decision");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    let beat_scheduled = approve
+        && match state.orchestrator().request_beat().await {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(error = ?err, "failed to schedule beat after approval");
+                false
+            }
+        };
+    Json(ApprovalResponse {
+        intent_id: id,
+        status: if approve { "approved" } else { "rejected" },
+        beat_scheduled,
+    })
+    .into_response()
+}
+
+/// Put an intent that exhausted its retries back into the inbox, approved so
+/// the beat this schedules queues it straight away.
+async fn retry_intent(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let record = match storage::find_intent(&data_dir, id) {
+        Ok(Some(("failed", record))) => record,
+        Ok(Some(_)) => return StatusCode::CONFLICT.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, intent_id = %id, "failed to look up intent to retry");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Err(err) = storage::approve_intent(&record.path, &data_dir, state.ctx().now()) {
+        warn!(error = ?err, intent_id = %id, "failed to retry intent");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    let beat_scheduled = match state.orchestrator().request_beat().await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(error = ?err, "failed to schedule beat after retry");
+            false
+        }
+    };
+    Json(ApprovalResponse {
+        intent_id: id,
+        status: "retried",
+        beat_scheduled,
+    })
+    .into_response()
+}
+
+/// Discard an intent that has not run yet. A queued intent is only
+/// cancelled while it is still waiting in memory; once a beat has picked it
+/// up the request is refused with `409`.
+async fn cancel_intent(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let (stage, record) = match storage::find_intent(&data_dir, id) {
+        Ok(Some(found)) => found,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, intent_id = %id, "failed to look up intent to cancel");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // A `server` role process has no queue of its own; the worker rereads
+    // each intent before running it.
+    let local_queue = state.ctx().config().server.role.runs_worker();
+    let discarded = match stage {
+        // A waiting intent has an open question; it resumes once answered.
+        "history" | "cancelled" | "waiting" => return StatusCode::CONFLICT.into_response(),
+        "queue" if local_queue => {
+            // Holding the queue lock keeps the beat from popping the intent
+            // between the check and the move.
+            let intents = state.ctx().intents();
+            let mut queue = intents.write();
+            if queue.remove(id).is_none() {
+                return StatusCode::CONFLICT.into_response();
+            }
+            storage::discard_intent(&record.path, &data_dir, state.ctx().now())
+        }
+        _ => storage::discard_intent(&record.path, &data_dir, state.ctx().now()),
+    };
+    if let Err(err) = discarded {
+        warn!(error = ?err, intent_id = %id, "failed to cancel intent");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    Json(ApprovalResponse {
+        intent_id: id,
+        status: "cancelled",
+        beat_scheduled: false,
+    })
+    .into_response()
+}
+
+#[derive(Debug, Serialize)]
+struct QueueOrderResponse {
+    /// Every waiting intent in the order it will run.
+    intent_ids: Vec<Uuid>,
+    /// How many of them, from the head, were put there by hand.
+    pinned: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReorderRequest {
+    intent_ids: Vec<Uuid>,
+}
+
+/// The in-memory queue lives in the worker; a `server` role process has
+/// none to show or change.
+fn requires_local_queue(state: &ServerState) -> Option<Response> {
+    (!state.ctx().config().server.role.runs_worker()).then(|| StatusCode::CONFLICT.into_response())
+}
+
+/// Persist the pinned part of the queue and answer with the whole order.
+fn queue_order_response(state: &ServerState, queue: &IntentQueue) -> Response {
+    let pinned = queue.pinned_ids();
+    let data_dir = state.ctx().config().data_dir.clone();
+    if let Err(err) = storage::save_queue_order(&data_dir, &pinned) {
+        warn!(error = ?err, "failed to save queue order");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    Json(QueueOrderResponse {
+        intent_ids: queue.ids(),
+        pinned: pinned.len(),
+    })
+    .into_response()
+}
+
+async fn queue_order(State(state): State<ServerState>) -> Response {
+    if let Some(response) = requires_local_queue(&state) {
+        return response;
+    }
+    let intents = state.ctx().intents();
+    let queue = intents.read();
+    Json(QueueOrderResponse {
+        intent_ids: queue.ids(),
+        pinned: queue.pinned_ids().len(),
+    })
+    .into_response()
+}
+
+/// Move a queued intent to the head of the queue, ahead of any priority.
+/// `409` once a beat has picked it up.
+async fn bump_intent(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    if let Some(response) = requires_local_queue(&state) {
+        return response;
+    }
+    let intents = state.ctx().intents();
+    let mut queue = intents.write();
+    if !queue.bump(id) {
+        drop(queue);
+        let data_dir = state.ctx().config().data_dir.clone();
+        return match storage::find_intent(&data_dir, id) {
+            Ok(Some(_)) => StatusCode::CONFLICT.into_response(),
+            Ok(None) => StatusCode::NOT_FOUND.into_response(),
+            Err(err) => {
+                warn!(error = ?err, intent_id = %id, "failed to look up intent to bump");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        };
+    }
+    queue_order_response(&state, &queue)
+}
+
+/// Put the listed queued intents at the head of the queue in that order;
+/// the rest keep their places behind them. `400` names an id that is not
+/// waiting in the queue.
+async fn reorder_queue(
+    State(state): State<ServerState>,
+    Json(request): Json<ReorderRequest>,
+) -> Response {
+    if let Some(response) = requires_local_queue(&state) {
+        return response;
+    }
+    let intents = state.ctx().intents();
+    let mut queue = intents.write();
+    if let Err(id) = queue.reorder(&request.intent_ids) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("intent {id} is not queued or listed twice"),
+        )
+            .into_response();
+    }
+    queue_order_response(&state, &queue)
+}
+
+#[derive(Debug, Serialize)]
+struct BeatResponse {
+    beat_scheduled: bool,
+}
+
+/// Ask the orchestrator for a beat now instead of waiting for the interval.
+async fn trigger_beat(State(state): State<ServerState>) -> Response {
+    match state.orchestrator().request_beat().await {
+        Ok(()) => Json(BeatResponse {
+            beat_scheduled: true,
+        })
+        .into_response(),
+        Err(err) => {
+            warn!(error = ?err, "failed to schedule requested beat");
+            StatusCode::SERVICE_UNAVAILABLE.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EditIntentResponse {
+    intent_id: Uuid,
+    stage: &'static str,
+    intent: Intent,
+}
+
+/// One intent in any stage, with its whole body.
+async fn intent_detail(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let now = state.ctx().now();
+    match task::spawn_blocking(move || storage::find_intent(&data_dir, id)).await {
+        Ok(Ok(Some((stage, record)))) => {
+            Json(storage::PendingIntent::new(stage, record, now)).into_response()
+        }
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, intent_id = %id, "failed to look up intent");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "intent lookup task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct IntentHistoryResponse {
+    intent_id: Uuid,
+    stage: &'static str,
+    /// `None` for intents written before states were recorded.
+    state: Option<IntentState>,
+    transitions: Vec<IntentTransition>,
+}
+
+/// The lifecycle audit trail of an intent in any stage.
+async fn intent_history(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let found = task::spawn_blocking(move || {
+        let Some((stage, record)) = storage::find_intent(&data_dir, id)? else {
+            return Ok(None);
+        };
+        let (state, transitions) = storage::load_intent_history(&record.path)?;
+        anyhow::Ok(Some(IntentHistoryResponse {
+            intent_id: id,
+            stage,
+            state,
+            transitions,
+        }))
+    })
+    .await;
+    match found {
+        Ok(Ok(Some(history))) => Json(history).into_response(),
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(err)) => {
+            warn!(error = ?err, intent_id = %id, "failed to read intent history");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "intent history task join failure");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Change the summary, body, alignment or due date of an intent that has
+/// not run yet; the same rules as [`cancel_intent`] apply to queued ones.
+async fn edit_intent(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Json(edit): Json<storage::IntentEdit>,
+) -> Response {
+    if edit
+        .summary
+        .as_deref()
+        .is_some_and(|summary| summary.trim().is_empty())
+        || edit
+            .telos_alignment
+            .is_some_and(|alignment| !(0.0..=1.0).contains(&alignment))
+    {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let data_dir = state.ctx().config().data_dir.clone();
+    let (stage, mut record) = match storage::find_intent(&data_dir, id) {
+        Ok(Some(found)) => found,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(error = ?err, intent_id = %id, "failed to look up intent to edit");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let local_queue = state.ctx().config().server.role.runs_worker();
+    let edited = match stage {
+        "history" | "cancelled" => return StatusCode::CONFLICT.into_response(),
+        "queue" if local_queue => {
+            let intents = state.ctx().intents();
+            let mut queue = intents.write();
+            let Some(queued) = queue.get_mut(id) else {
+                return StatusCode::CONFLICT.into_response();
+            };
+            let edited = storage::edit_intent(&record.path, &edit);
+            if edited.is_ok() {
+                edit.apply_to(queued);
+            }
+            edited
+        }
+        _ => storage::edit_intent(&record.path, &edit),
+    };
+    if let Err(err) = edited {
+        warn!(error = ?err, intent_id = %id, "failed to edit intent");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    edit.apply_to(&mut record.intent);
+    Json(EditIntentResponse {
+        intent_id: id,
+        stage,
+        intent: record.intent,
+    })
+    .into_response()
+}
+
+fn default_source() -> String {
+    "user".to_string()
+}
+
+fn default_alignment() -> f32 {
+    0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        agent::{AgentOutcome, AgentRuntime},
+        clock::ManualClock,
+        config::AppConfig,
+        orchestrator,
+        state::AppContext,
+        storage::{
+            self, MemorySnapshotInput, MessageDirection, MessageLogEntry, MessageLogQuery,
+            StructuredContent, StructuredSection, write_markdown,
+        },
+        tasks::Intent,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::Duration;
+    use http_body_util::BodyExt;
+    use httpmock::MockServer;
+    use serde_json::json;
+    use serial_test::serial;
+    use std::{fs, sync::Arc};
+    use tempfile::TempDir;
+    use tokio::task;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[serial]
+    async fn acceptance_overview_reflects_markdown_plan() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::create_dir_all(root.join("docs")).expect("docs dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::write(
+            root.join("docs/work_acceptance_plan.md"),
+            "## 2. 任务矩阵\n| 模块 | 任务 | 状态 |\n| --- | --- | --- |\n| API | 汇总验收计划 | ✅ |\n\n## 4. TODO 追踪\n\n### 4.1 已完成清单\n- [x] 已完成事项\n\n### 4.2 进行中/待定\n- 待处理事项\n\n## 5. 验证方案概览\n| 类型 | 验证内容 | 指令/方式 |\n| --- | --- | --- |\n| 端到端 | 核心链路 | cargo test --test e2e |\n",
+        )
+        .expect("plan doc");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/meta/acceptance")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("acceptance response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert!(
+            payload["source"]["doc_path"]
+                .as_str()
+                .unwrap()
+                .ends_with("docs/work_acceptance_plan.md")
+        );
+        assert_eq!(payload["task_matrix"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["completed_todos"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["pending_todos"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["validation_plan"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["metrics"]["modules_total"], serde_json::json!(1));
+        assert_eq!(
+            payload["metrics"]["modules_completed"],
+            serde_json::json!(1)
+        );
+        assert_eq!(payload["metrics"]["todos_completed"], serde_json::json!(1));
+        assert_eq!(payload["metrics"]["todos_pending"], serde_json::json!(1));
+        assert_eq!(payload["metrics"]["validation_steps"], serde_json::json!(1));
+        assert_eq!(
+            payload["metrics"]["overall_status"],
+            serde_json::json!("in_progress")
+        );
+        assert_eq!(
+            payload["completed_todos"][0]["label"],
+            serde_json::Value::String("已完成事项".to_string())
+        );
+        assert_eq!(
+            payload["pending_todos"][0]["label"],
+            serde_json::Value::String("待处理事项".to_string())
+        );
+        assert_eq!(
+            payload["validation_plan"][0]["command"],
+            serde_json::Value::String("cargo test --test e2e".to_string())
+        );
+        assert_eq!(
+            payload["task_matrix"][0]["module"],
+            serde_json::Value::String("API".to_string())
+        );
+        assert_eq!(
+            payload["task_matrix"][0]["status"],
+            serde_json::Value::String("✅".to_string())
+        );
+
+        let module_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/meta/acceptance/module/API")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("module response");
+        assert_eq!(module_response.status(), StatusCode::OK);
+        let body = module_response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        let module_payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(module_payload["module"], "API");
+        assert_eq!(module_payload["tasks"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            module_payload["metrics"]["tasks_total"],
+            serde_json::json!(1)
+        );
+        assert_eq!(
+            module_payload["metrics"]["overall_status"],
+            serde_json::json!("complete")
+        );
+
+        let fuzzy_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/meta/acceptance/module/api")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("fuzzy module response");
+        assert_eq!(fuzzy_response.status(), StatusCode::OK);
+
+        let missing_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/meta/acceptance/module/unknown")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("missing module response");
+        assert_eq!(missing_response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(payload["documents"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["documents"][0]["id"], "work_acceptance_plan");
+        assert_eq!(module_payload["doc"], "work_acceptance_plan");
+        for (uri, status) in [
+            (
+                "/api/meta/acceptance/module/API?doc=work_acceptance_plan",
+                StatusCode::OK,
+            ),
+            (
+                "/api/meta/acceptance/module/API?doc=other",
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{uri}");
+        }
+
+        let patch = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .method("PATCH")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
+        };
+        let task_response = app
+            .clone()
+            .oneshot(patch(
+                "/api/meta/acceptance/module/api/task",
+                json!({ "task": "汇总验收计划", "status": "进行中" }),
+            ))
+            .await
+            .expect("task update response");
+        assert_eq!(task_response.status(), StatusCode::OK);
+        let body = task_response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        let module_payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(module_payload["tasks"][0]["status"], "进行中");
+        assert_eq!(
+            module_payload["metrics"]["overall_status"],
+            serde_json::json!("in_progress")
+        );
+        let plan = fs::read_to_string(root.join("docs/work_acceptance_plan.md")).unwrap();
+        assert!(plan.contains("| API | 汇总验收计划 | 进行中 |\n"));
+
+        for (uri, body, status) in [
+            (
+                "/api/meta/acceptance/module/API/task",
+                json!({ "task": "不存在", "status": "✅" }),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                "/api/meta/acceptance/module/API/task",
+                json!({ "task": "汇总验收计划", "status": "a | b" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                "/api/meta/acceptance/todo",
+                json!({ "label": "不存在", "done": true }),
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let response = app.clone().oneshot(patch(uri, body)).await.unwrap();
+            assert_eq!(response.status(), status, "{uri}");
+        }
+
+        let todo_response = app
+            .clone()
+            .oneshot(patch(
+                "/api/meta/acceptance/todo",
+                json!({ "label": "待处理事项", "done": true }),
+            ))
+            .await
+            .expect("todo update response");
+        assert_eq!(todo_response.status(), StatusCode::OK);
+        let body = todo_response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["metrics"]["todos_completed"], serde_json::json!(2));
+        assert_eq!(payload["metrics"]["todos_pending"], serde_json::json!(0));
+        let plan = fs::read_to_string(root.join("docs/work_acceptance_plan.md")).unwrap();
+        assert!(plan.contains("- [x] 已完成事项\n- [x] 待处理事项\n"));
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn messages_api_returns_recent_entries() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10
+intent_threshold: 0.5
+",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1
+persona: TelosOps
+",
+        )
+        .expect("agent config");
+        fs::write(
+            root.join("config/llm.yml"),
+            "provider: local_stub
+",
+        )
+        .expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
+
+        let now = Utc::now();
+        let inbound = MessageLogEntry {
+            id: Uuid::new_v4(),
+            direction: MessageDirection::Inbound,
+            source: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            author: Some("alice".to_string()),
+            text: "inbound ping".to_string(),
+            timestamp: now - Duration::seconds(30),
+            metadata: None,
+        };
+        let outbound = MessageLogEntry {
+            id: Uuid::new_v4(),
+            direction: MessageDirection::Outbound,
+            source: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            author: Some("bot".to_string()),
+            text: "outbound pong".to_string(),
+            timestamp: now,
+            metadata: None,
+        };
+
+        storage::append_message_entry(&data_dir, &inbound)
+            .await
+            .expect("write inbound message");
+        storage::append_message_entry(&data_dir, &outbound)
+            .await
+            .expect("write outbound message");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/messages?dir=in&src=telegram")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("messages response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: MessageListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload.entries.len(), 1);
+        assert_eq!(payload.entries[0].direction, MessageDirection::Inbound);
+        assert_eq!(payload.entries[0].text, "inbound ping");
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn memory_timeline_returns_rollup() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::create_dir_all(root.join("docs")).expect("docs dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::write(root.join("docs/work_acceptance_plan.md"), "# plan\n").expect("plan doc");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        storage::ensure_data_layout(&data_dir).expect("layout");
+
+        let journal_path = data_dir.join("journals/2025/01/01.md");
+        fs::create_dir_all(journal_path.parent().unwrap()).expect("journal dir");
+        fs::write(&journal_path, "entry").expect("journal file");
+
+        let history_path = data_dir.join("intent/history/sample.md");
+        fs::create_dir_all(history_path.parent().unwrap()).expect("history dir");
+        fs::write(&history_path, "intent").expect("history file");
+
+        let intent = Intent {
+            id: Uuid::new_v4(),
+            source: "telegram".to_string(),
+            summary: "Summarize roadmap".to_string(),
+            telos_alignment: 0.9,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
+            storage_path: None,
+        };
+        let outcome = AgentOutcome {
+            steps: Vec::new(),
+            final_answer: "Highlights captured".to_string(),
+        };
+
+        storage::ingest_memory_snapshot(
+            &data_dir,
+            MemorySnapshotInput {
+                entry_id: Uuid::new_v4(),
+                intent: intent.clone(),
+                outcome,
+                journal_path: journal_path.clone(),
+                history_path: Some(history_path.clone()),
+            },
+        )
+        .await
+        .expect("ingest memory");
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/memory?level=L2&limit=10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("memory response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["level"], serde_json::json!("L2"));
+        let entries = payload["entries"].as_array().unwrap();
+        assert!(!entries.is_empty());
+        assert!(!entries[0]["summary"].as_str().unwrap().is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/memory/tags")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("memory tags response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["level"], serde_json::json!("L1"));
+        assert!(
+            payload["tags"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|tag| tag["tag"] == "telegram" && tag["count"] == 1)
+        );
+
+        for (query, expected) in [("ROADMAP", 1), ("unrelated", 0)] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/api/memory?level=L1&q={query}"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .expect("memory search response");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(payload["entries"].as_array().unwrap().len(), expected);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/beat")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("beat response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["beat_scheduled"], true);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn telegram_webhook_appends_message_and_intent() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10
+intent_threshold: 0.5
+",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1
+persona: TelosOps
+",
+        )
+        .expect("agent config");
+        fs::write(
+            root.join("config/llm.yml"),
+            "provider: local_stub
+",
+        )
+        .expect("llm config");
+        fs::write(
+            root.join("config/telegram.yml"),
+            "bot_token: TEST_TOKEN
+webhook_secret: secret-token
+default_chat_id: 12345
+",
+        )
+        .expect("telegram config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
+
+        let update = json!({
+            "update_id": 1,
+            "message": {
+                "message_id": 99,
+                "date": Utc::now().timestamp(),
+                "chat": {"id": 4242, "type": "private"},
+                "from": {"id": 7, "username": "alice"},
+                "text": "Hello Telos",
+            }
+        });
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/webhook/telegram")
+                    .header("content-type", "application/json")
+                    .header("X-Telegram-Bot-Api-Secret-Token", "secret-token")
+                    .body(Body::from(serde_json::to_vec(&update).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .expect("webhook response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: TelegramWebhookResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload.status, "queued");
+        assert!(payload.intent_id.is_some());
+
+        let logs = task::spawn_blocking({
+            let data_dir = data_dir.clone();
+            move || {
+                storage::read_messages(
+                    &data_dir,
+                    MessageLogQuery {
+                        source: Some("telegram".to_string()),
+                        direction: Some(MessageDirection::Inbound),
+                        limit: 5,
+                        ..Default::default()
+                    },
+                )
+            }
+        })
+        .await
+        .expect("join")
+        .expect("load inbound logs");
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].text, "Hello Telos");
+
+        // Telegram retries the same update_id; it must not create a second intent.
+        let replay = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/webhook/telegram")
+                    .header("content-type", "application/json")
+                    .header("X-Telegram-Bot-Api-Secret-Token", "secret-token")
+                    .body(Body::from(serde_json::to_vec(&update).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .expect("replay response");
+        assert_eq!(replay.status(), StatusCode::OK);
+        let body = replay.into_body().collect().await.unwrap().to_bytes();
+        let payload: TelegramWebhookResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload.status, "duplicate");
+        assert!(payload.intent_id.is_none());
+
+        let mut has_intent = false;
+        for dir in ["intent/inbox", "intent/queue", "intent/history"] {
+            let intent_dir = data_dir.join(dir);
+            if intent_dir.exists()
+                && fs::read_dir(&intent_dir)
+                    .map(|mut entries| entries.next().is_some())
+                    .unwrap_or(false)
+            {
+                has_intent = true;
+                break;
+            }
+        }
+        assert!(has_intent, "telegram webhook should create intent markdown");
+
+        ctx.request_shutdown();
+        join.abort();
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn send_message_uses_telegram_api() {
+        let server = MockServer::start_async().await;
+        let token = "TEST_TOKEN";
+        let path = format!("/bot{token}/sendMessage");
+        let mock = {
+            let expected_path = path.clone();
+            server
+                .mock_async(move |when, then| {
+                    when.method("POST").path(expected_path.as_str());
+                    then.status(200)
+                        .header("content-type", "application/json")
+                        .json_body(json!({
+                            "ok": true,
+                            "result": {"message_id": 9001}
+                        }));
+                })
+                .await
+        };
+
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10
+intent_threshold: 0.5
+",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1
+persona: TelosOps
+",
+        )
+        .expect("agent config");
+        fs::write(
+            root.join("config/llm.yml"),
+            "provider: local_stub
+",
+        )
+        .expect("llm config");
+        fs::write(
+            root.join("config/telegram.yml"),
+            format!(
+                "bot_token: {token}
+default_chat_id: 777
+webhook_secret: test
+api_base: {}
+",
+                server.base_url()
+            ),
+        )
+        .expect("telegram config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/messages/send")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&json!({
+                            "text": "Ping from test"
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .expect("send response");
+        assert_eq!(response.status(), StatusCode::OK);
+        mock.assert_async().await;
+
+        let logs = task::spawn_blocking({
+            let data_dir = data_dir.clone();
+            move || {
+                storage::read_messages(
+                    &data_dir,
+                    MessageLogQuery {
+                        source: Some("telegram".to_string()),
+                        direction: Some(MessageDirection::Outbound),
+                        limit: 5,
+                        ..Default::default()
+                    },
+                )
+            }
+        })
+        .await
+        .expect("join")
+        .expect("load outbound logs");
+        assert!(!logs.is_empty());
+        assert!(logs.iter().any(|entry| entry.text == "Ping from test"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/messages/outbox?status=delivered")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("outbox response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let outbox: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let messages = outbox["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["text"], "Ping from test");
+        assert_eq!(messages[0]["provider_message_id"], 9001);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn telegram_webhook_management_calls_bot_api() {
+        let server = MockServer::start_async().await;
+        let set_mock = server
+            .mock_async(|when, then| {
+                when.method("POST")
+                    .path("/botTEST_TOKEN/setWebhook")
+                    .json_body_partial(
+                        r#"{"url": "https://hi.example.com/webhook/telegram", "secret_token": "test"}"#,
+                    );
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": true}));
+            })
+            .await;
+        let delete_mock = server
+            .mock_async(|when, then| {
+                when.method("POST")
+                    .path("/botTEST_TOKEN/deleteWebhook")
+                    .json_body(json!({"drop_pending_updates": true}));
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": true}));
+            })
+            .await;
+        let info_mock = server
+            .mock_async(|when, then| {
+                when.method("POST").path("/botTEST_TOKEN/getWebhookInfo");
+                then.status(200).json_body(json!({
+                    "ok": true,
+                    "result": {"url": "https://hi.example.com/webhook/telegram", "pending_update_count": 0}
+                }));
+            })
+            .await;
+
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::write(
+            root.join("config/telegram.yml"),
+            format!(
+                "bot_token: TEST_TOKEN\nwebhook_secret: test\npublic_url: https://hi.example.com/\napi_base: {}\n",
+                server.base_url()
+            ),
+        )
+        .expect("telegram config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/telegram/webhook/register")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("register response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["url"], "https://hi.example.com/webhook/telegram");
+        set_mock.assert_async().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/telegram/webhook/delete")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"drop_pending_updates":true}"#))
+                    .unwrap(),
+            )
+            .await
+            .expect("delete response");
+        assert_eq!(response.status(), StatusCode::OK);
+        delete_mock.assert_async().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/telegram/webhook/info")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("info response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let info: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(info["pending_update_count"], 0);
+        info_mock.assert_async().await;
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn chat_endpoint_runs_agent_synchronously() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/chat")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&json!({
+                            "message": "Summarize the week",
+                            "chat_id": "desk",
+                            "history": [{"role": "user", "text": "Hi"}],
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .expect("chat response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let reply: chat::ChatResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(reply.chat_id, "desk");
+        assert!(reply.final_answer.contains("Summarize the week"));
+        assert_eq!(reply.steps.len(), 1);
+
+        let logs = storage::read_messages(
+            &data_dir,
+            MessageLogQuery {
+                source: Some("api".to_string()),
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .expect("chat logs");
+        assert_eq!(logs.len(), 2);
+        assert!(
+            logs.iter()
+                .any(|entry| entry.direction == MessageDirection::Outbound
+                    && entry.text == reply.final_answer)
+        );
+        assert!(storage::scan_inbox(&data_dir).unwrap().is_empty());
+
+        // A follow-up without history continues the logged session.
+        let follow_up = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/chat")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"message": "Make it shorter", "chat_id": "desk"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .expect("follow-up response");
+        assert_eq!(follow_up.status(), StatusCode::OK);
+        let body = follow_up.into_body().collect().await.unwrap().to_bytes();
+        let follow_up: chat::ChatResponse = serde_json::from_slice(&body).unwrap();
+        let prompts = storage::read_llm_logs(
+            &data_dir,
+            storage::LlmLogQuery {
+                run_id: Some(follow_up.run_id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("llm logs");
+        assert!(!prompts.is_empty());
+        assert!(prompts.iter().all(|entry| {
+            entry.prompt.contains("- user: Summarize the week")
+                && entry
+                    .prompt
+                    .contains(&format!("- assistant: {}", reply.final_answer))
+        }));
+
+        let streamed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/chat/stream")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"message": "Stream it", "chat_id": "desk"}"#))
+                    .unwrap(),
+            )
+            .await
+            .expect("stream response");
+        assert_eq!(streamed.status(), StatusCode::OK);
+        let body = streamed.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let step_at = body.find("event: step").expect("step event");
+        let final_at = body.find("event: final").expect("final event");
+        assert!(step_at < final_at);
+        let final_data = body[final_at..]
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .expect("final data");
+        let final_reply: chat::ChatResponse = serde_json::from_str(final_data).unwrap();
+        assert_eq!(final_reply.chat_id, "desk");
+        assert!(final_reply.final_answer.contains("Stream it"));
+
+        let empty = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/chat")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"message": "  "}"#))
+                    .unwrap(),
+            )
+            .await
+            .expect("empty chat response");
+        assert_eq!(empty.status(), StatusCode::BAD_REQUEST);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn restart_requeues_by_priority() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let mut ids = Vec::new();
+        for (minute, priority) in ["low", "normal", "high", "normal"].into_iter().enumerate() {
+            let draft = IntentDraft {
+                source: "user".to_string(),
+                summary: format!("{priority} intent"),
+                telos_alignment: 0.9,
+                metadata: BTreeMap::from([(PRIORITY_KEY.to_string(), priority.to_string())]),
+                ..IntentDraft::default()
+            };
+            let created_at = chrono::Utc::now() + chrono::Duration::minutes(minute as i64);
+            let persisted = storage::persist_intent_at(&data_dir, &draft, created_at)
+                .await
+                .unwrap();
+            storage::promote_to_queue(&persisted.path, &data_dir, Utc::now()).unwrap();
+            ids.push(persisted.id);
+        }
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let gate = ctx.beat_gate();
+        let beats_paused = gate.lock().await;
+        let (_handle, join) = orchestrator::spawn(ctx.clone());
+        for _ in 0..50 {
+            if ctx.intents().read().len() == 4 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(ctx.intents().read().ids(), [ids[2], ids[1], ids[3], ids[0]]);
+
+        drop(beats_paused);
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn queued_intents_can_be_bumped_and_reordered() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let mut ids = Vec::new();
+        for (minute, summary) in ["First", "Second", "Third"].into_iter().enumerate() {
+            let draft = IntentDraft {
+                source: "user".to_string(),
+                summary: summary.to_string(),
+                telos_alignment: 0.9,
+                ..IntentDraft::default()
+            };
+            let created_at = chrono::Utc::now() + chrono::Duration::minutes(minute as i64);
+            let persisted = storage::persist_intent_at(&data_dir, &draft, created_at)
+                .await
+                .unwrap();
+            storage::promote_to_queue(&persisted.path, &data_dir, Utc::now()).unwrap();
+            ids.push(persisted.id);
+        }
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let gate = ctx.beat_gate();
+        let beats_paused = gate.lock().await;
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        for _ in 0..50 {
+            if ctx.intents().read().len() == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let send = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
+        };
+        let read_json = |response: Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+        let id_list = |order: &[Uuid]| json!(order);
+
+        let bumped = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/bump", ids[2]),
+                json!({}),
+            ))
+            .await
+            .expect("bump");
+        assert_eq!(bumped.status(), StatusCode::OK);
+        let bumped = read_json(bumped).await;
+        assert_eq!(bumped["intent_ids"], id_list(&[ids[2], ids[0], ids[1]]));
+        assert_eq!(bumped["pinned"], 1);
+        assert_eq!(storage::load_queue_order(&data_dir), [ids[2]]);
+
+        let reordered = app
+            .clone()
+            .oneshot(send(
+                "PUT",
+                "/api/intents/queue".to_string(),
+                json!({"intent_ids": [ids[1], ids[0]]}),
+            ))
+            .await
+            .expect("reorder");
+        assert_eq!(reordered.status(), StatusCode::OK);
+        let reordered = read_json(reordered).await;
+        assert_eq!(reordered["intent_ids"], id_list(&[ids[1], ids[0], ids[2]]));
+        assert_eq!(reordered["pinned"], 3);
+
+        let unknown = app
+            .clone()
+            .oneshot(send(
+                "PUT",
+                "/api/intents/queue".to_string(),
+                json!({"intent_ids": [Uuid::new_v4()]}),
+            ))
+            .await
+            .expect("reorder unknown");
+        assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+        let missing = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/bump", Uuid::new_v4()),
+                json!({}),
+            ))
+            .await
+            .expect("bump missing");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let listed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/intents/queue")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("queue order");
+        assert_eq!(
+            read_json(listed).await["intent_ids"],
+            id_list(&[ids[1], ids[0], ids[2]])
+        );
+
+        // A restart picks the pinned order up again.
+        let mut restored = IntentQueue::default();
+        for record in storage::scan_queue(&data_dir).unwrap() {
+            restored.enqueue(record.intent);
+        }
+        restored.pin(&storage::load_queue_order(&data_dir));
+        assert_eq!(restored.ids(), [ids[1], ids[0], ids[2]]);
+
+        drop(beats_paused);
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn intent_actions_edit_cancel_and_retry() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let draft = |summary: &str| IntentDraft {
+            source: "user".to_string(),
+            summary: summary.to_string(),
+            telos_alignment: 0.9,
+            body: "original body".to_string(),
+            ..IntentDraft::default()
+        };
+        let queued = storage::persist_intent(&data_dir, &draft("Queued"))
+            .await
+            .unwrap();
+        storage::promote_to_queue(&queued.path, &data_dir, Utc::now()).unwrap();
+        let failed = storage::persist_intent(&data_dir, &draft("Failed"))
+            .await
+            .unwrap();
+        let failed_queued = storage::promote_to_queue(&failed.path, &data_dir, Utc::now()).unwrap();
+        storage::record_intent_state(
+            &failed_queued,
+            IntentState::Processing,
+            None,
+            chrono::Utc::now(),
+        )
+        .unwrap();
+        storage::quarantine_failed_intent(&failed_queued, &data_dir, Utc::now()).unwrap();
+        let deferred = storage::persist_intent(&data_dir, &draft("Deferred"))
+            .await
+            .unwrap();
+        let deferred_path = storage::defer_intent(&deferred.path, &data_dir, Utc::now()).unwrap();
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        // Keep beats from popping the queued intent until its checks are done.
+        let gate = ctx.beat_gate();
+        let beats_paused = gate.lock().await;
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        for _ in 0..50 {
+            if ctx.intents().read().len() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(ctx.intents().read().len(), 1);
+
+        let send = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
+        };
+
+        let listed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/intents?stage=failed")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("list failed");
+        let body = listed.into_body().collect().await.unwrap().to_bytes();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed["intents"][0]["summary"], "Failed");
+        assert_eq!(listed["intents"][0]["body"], "original body");
+        assert!(listed["intents"][0].get("body_truncated").is_none());
+        let unknown = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/intents?stage=limbo")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("list unknown stage");
+        assert_eq!(unknown.status(), StatusCode::BAD_REQUEST);
+
+        let edited = app
+            .clone()
+            .oneshot(send(
+                "PATCH",
+                format!("/api/intents/{}", queued.id),
+                json!({"summary": "Queued, edited", "telos_alignment": 0.7}),
+            ))
+            .await
+            .expect("edit queued");
+        assert_eq!(edited.status(), StatusCode::OK);
+        {
+            let intents = ctx.intents();
+            let mut queue = intents.write();
+            let intent = queue.get_mut(queued.id).expect("still queued");
+            assert_eq!(intent.summary, "Queued, edited");
+            assert!((intent.telos_alignment - 0.7).abs() < f32::EPSILON);
+        }
+        let invalid = app
+            .clone()
+            .oneshot(send(
+                "PATCH",
+                format!("/api/intents/{}", queued.id),
+                json!({"summary": "  "}),
+            ))
+            .await
+            .expect("invalid edit");
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let edited = app
+            .clone()
+            .oneshot(send(
+                "PATCH",
+                format!("/api/intents/{}", deferred.id),
+                json!({"body": "rewritten body"}),
+            ))
+            .await
+            .expect("edit deferred");
+        assert_eq!(edited.status(), StatusCode::OK);
+        let content = fs::read_to_string(&deferred_path).unwrap();
+        assert!(content.contains("summary: Deferred"));
+        assert!(content.contains("rewritten body"));
+        assert!(!content.contains("original body"));
+        let detail = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/intents/{}", deferred.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("intent detail");
+        assert_eq!(detail.status(), StatusCode::OK);
+        let body = detail.into_body().collect().await.unwrap().to_bytes();
+        let detail: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(detail["stage"], "deferred");
+        assert_eq!(detail["body"], "rewritten body");
+        let missing = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/intents/{}", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("missing intent detail");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let cancelled = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/cancel", queued.id),
+                json!({}),
+            ))
+            .await
+            .expect("cancel queued");
+        assert_eq!(cancelled.status(), StatusCode::OK);
+        assert!(ctx.intents().read().is_empty());
+        assert!(storage::scan_queue(&data_dir).unwrap().is_empty());
+        assert_eq!(
+            fs::read_dir(data_dir.join("intent/inbox/discarded"))
+                .unwrap()
+                .count(),
+            1
+        );
+
+        let not_failed = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/retry", deferred.id),
+                json!({}),
+            ))
+            .await
+            .expect("retry deferred");
+        assert_eq!(not_failed.status(), StatusCode::CONFLICT);
+
+        drop(beats_paused);
+        let retried = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/retry", failed.id),
+                json!({}),
+            ))
+            .await
+            .expect("retry failed");
+        assert_eq!(retried.status(), StatusCode::OK);
+
+        let mut archived = Vec::new();
+        for _ in 0..50 {
+            archived = storage::scan_history(&data_dir).unwrap();
+            if !archived.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].intent.id, failed.id);
+
+        let archived_cancel = app
+            .clone()
+            .oneshot(send(
+                "POST",
+                format!("/api/intents/{}/cancel", failed.id),
+                json!({}),
+            ))
+            .await
+            .expect("cancel archived");
+        assert_eq!(archived_cancel.status(), StatusCode::CONFLICT);
+
+        let history = |id: Uuid| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(format!("/api/intents/{id}/history"))
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .expect("intent history");
+                let status = response.status();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+                )
+            }
+        };
+        let (status, retried_history) = history(failed.id).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(retried_history["stage"], "history");
+        assert_eq!(retried_history["state"], "done");
+        let states: Vec<_> = retried_history["transitions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|transition| transition["to"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            states,
+            [
+                "received",
+                "queued",
+                "processing",
+                "failed",
+                "received",
+                "queued",
+                "processing",
+                "done"
+            ]
+        );
+        assert_eq!(retried_history["transitions"][4]["from"], "failed");
+        assert_eq!(retried_history["transitions"][4]["reason"], "approved");
+        let (status, cancelled_history) = history(queued.id).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cancelled_history["stage"], "cancelled");
+        assert_eq!(cancelled_history["state"], "cancelled");
+        let (status, _) = history(Uuid::new_v4()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn intents_over_the_daily_token_ceiling_wait_for_approval() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\napproval:\n  daily_estimated_tokens: 800\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let now = chrono::Utc::now();
+        let mut ids = Vec::new();
+        for (minute, summary) in ["Tidy notes", "Plan trip"].into_iter().enumerate() {
+            let draft = IntentDraft {
+                source: "user".to_string(),
+                summary: summary.to_string(),
+                telos_alignment: 0.9,
+                body: "Short body".to_string(),
+                ..IntentDraft::default()
+            };
+            let created_at = now + chrono::Duration::minutes(minute as i64);
+            let persisted = storage::persist_intent_at(&data_dir, &draft, created_at)
+                .await
+                .unwrap();
+            ids.push(persisted.id);
+        }
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        handle.request_beat().await.unwrap();
+
+        let mut held = Vec::new();
+        for _ in 0..50 {
+            held = storage::scan_pending_approval(&data_dir).unwrap();
+            if !held.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].intent.id, ids[1]);
+        let estimate = held[0]
+            .intent
+            .estimated_tokens()
+            .expect("estimate recorded");
+        assert!(estimate > 400 && estimate <= 800, "{estimate}");
+        assert_eq!(
+            held[0]
+                .intent
+                .metadata
+                .get("hold_reason")
+                .map(String::as_str),
+            Some("daily_estimated_tokens")
+        );
+        let (_, first) = storage::find_intent(&data_dir, ids[0]).unwrap().unwrap();
+        let first_estimate = first.intent.estimated_tokens().expect("estimate recorded");
+        assert_eq!(
+            storage::load_queued_tokens(&data_dir, ctx.now().date_naive()),
+            first_estimate
+        );
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn costly_intents_wait_for_approval() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\napproval:\n  max_cost_estimate: 5.0\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\npersonas:\n  writer:\n    prompt: Scribe\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+
+        let post = |uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
+        };
+        let created = app
+            .clone()
+            .oneshot(post(
+                "/api/intents".to_string(),
+                json!({
+                    "summary": "Rent GPUs",
+                    "telos_alignment": 0.9,
+                    "cost_estimate": 20.0,
+                    "priority": "high",
+                    "persona": "writer",
+                }),
+            ))
+            .await
+            .expect("create response");
+        assert_eq!(created.status(), StatusCode::ACCEPTED);
+        let body = created.into_body().collect().await.unwrap().to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id: Uuid = created["id"].as_str().unwrap().parse().unwrap();
+
+        let mut held = Vec::new();
+        for _ in 0..50 {
+            held = storage::scan_pending_approval(&data_dir).unwrap();
+            if !held.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].intent.id, id);
+        assert_eq!(
+            held[0].intent.metadata.get(PERSONA_KEY).map(String::as_str),
+            Some("writer")
+        );
+
+        let unknown_persona = app
+            .clone()
+            .oneshot(post(
+                "/api/intents".to_string(),
+                json!({ "summary": "Write a poem", "persona": "poet" }),
+            ))
+            .await
+            .expect("create response");
+        assert_eq!(unknown_persona.status(), StatusCode::BAD_REQUEST);
+
+        let listed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/intents?stage=pending_approval")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("list response");
+        let body = listed.into_body().collect().await.unwrap().to_bytes();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed["intents"].as_array().unwrap().len(), 1);
+        assert_eq!(listed["intents"][0]["stage"], "pending_approval");
+
+        let missing = app
+            .clone()
+            .oneshot(post(
+                format!("/api/intents/{}/reject", Uuid::new_v4()),
+                json!({}),
+            ))
+            .await
+            .expect("reject response");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let approved = app
+            .clone()
+            .oneshot(post(format!("/api/intents/{id}/approve"), json!({})))
+            .await
+            .expect("approve response");
+        assert_eq!(approved.status(), StatusCode::OK);
+
+        let mut archived = Vec::new();
+        for _ in 0..50 {
+            archived = storage::scan_history(&data_dir).unwrap();
+            if !archived.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(archived.len(), 1);
+        assert!(archived[0].intent.is_approved());
+        assert_eq!(archived[0].intent.priority(), IntentPriority::High);
+        assert!(
+            storage::scan_pending_approval(&data_dir)
+                .unwrap()
+                .is_empty()
+        );
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        agent::{AgentOutcome, AgentRuntime},
-        config::AppConfig,
-        orchestrator,
-        state::AppContext,
-        storage::{
-            self, MemorySnapshotInput, MessageDirection, MessageLogEntry, MessageLogQuery,
-            StructuredContent, StructuredSection, write_markdown,
-        },
-        tasks::Intent,
-    };
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-    };
-    use chrono::Duration;
-    use http_body_util::BodyExt;
-    use httpmock::MockServer;
-    use serde_json::json;
-    use serial_test::serial;
-    use std::{fs, sync::Arc};
-    use tempfile::TempDir;
-    use tokio::task;
-    use tower::ServiceExt;
-    use uuid::Uuid;
+    #[tokio::test]
+    #[serial]
+    async fn export_and_import_round_trip() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        // Let the startup beat finish before writing to the queue.
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        drop(ctx.beat_gate().lock().await);
+
+        let persisted = storage::persist_intent(
+            &data_dir,
+            &IntentDraft {
+                source: "test".to_string(),
+                summary: "Carry me over".to_string(),
+                telos_alignment: 0.9,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("persist intent");
+        let queued_path =
+            storage::promote_to_queue(&persisted.path, &data_dir, Utc::now()).unwrap();
+
+        let exported = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/export")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("export response");
+        assert_eq!(exported.status(), StatusCode::OK);
+        assert_eq!(
+            exported.headers().get("content-type").unwrap(),
+            "application/gzip"
+        );
+        let archive = exported.into_body().collect().await.unwrap().to_bytes();
+
+        fs::remove_file(&queued_path).unwrap();
+        fs::remove_file(root.join("config/agent.yml")).unwrap();
+
+        let import = |body: Vec<u8>| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/import")
+                .header("content-type", "application/gzip")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let garbage = app
+            .clone()
+            .oneshot(import(b"not a tarball".to_vec()))
+            .await
+            .expect("import response");
+        assert_eq!(garbage.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let imported = app
+            .clone()
+            .oneshot(import(archive.to_vec()))
+            .await
+            .expect("import response");
+        assert_eq!(imported.status(), StatusCode::OK);
+        let body = imported.into_body().collect().await.unwrap().to_bytes();
+        let imported: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(imported["queued"], 1);
+        assert_eq!(imported["config_written"], json!(["agent.yml"]));
+        assert_eq!(imported["config_skipped"], json!(["beat.yml", "llm.yml"]));
+        assert!(
+            data_dir
+                .join("backups")
+                .join(imported["pre_import_backup"].as_str().unwrap())
+                .exists()
+        );
+        assert!(queued_path.exists());
+        assert!(root.join("config/agent.yml").exists());
+        assert_eq!(ctx.intents().read().len(), 1);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
 
     #[tokio::test]
     #[serial]
-    async fn acceptance_overview_reflects_markdown_plan() {
+    async fn backup_and_restore_round_trip() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        // Let the startup beat finish before writing to the queue.
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        drop(ctx.beat_gate().lock().await);
+
+        let persisted = storage::persist_intent(
+            &data_dir,
+            &IntentDraft {
+                source: "test".to_string(),
+                summary: "Keep me".to_string(),
+                telos_alignment: 0.9,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("persist intent");
+        let queued_path =
+            storage::promote_to_queue(&persisted.path, &data_dir, Utc::now()).unwrap();
+
+        let post = |uri: &str, content_type: &str, body: Vec<u8>| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let created = app
+            .clone()
+            .oneshot(post("/api/admin/backup", "application/json", Vec::new()))
+            .await
+            .expect("backup response");
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let body = created.into_body().collect().await.unwrap().to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let file_name = created["file_name"].as_str().unwrap().to_string();
+        assert!(data_dir.join("backups").join(&file_name).exists());
+
+        fs::remove_file(&queued_path).unwrap();
+
+        let missing = app
+            .clone()
+            .oneshot(post(
+                "/api/admin/restore",
+                "application/json",
+                br#"{"file_name": "nope.tar.gz"}"#.to_vec(),
+            ))
+            .await
+            .expect("restore response");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let garbage = app
+            .clone()
+            .oneshot(post(
+                "/api/admin/restore",
+                "application/gzip",
+                b"not a tarball".to_vec(),
+            ))
+            .await
+            .expect("restore response");
+        assert_eq!(garbage.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let restored = app
+            .clone()
+            .oneshot(post(
+                "/api/admin/restore",
+                "application/json",
+                serde_json::to_vec(&json!({ "file_name": file_name })).unwrap(),
+            ))
+            .await
+            .expect("restore response");
+        assert_eq!(restored.status(), StatusCode::OK);
+        let body = restored.into_body().collect().await.unwrap().to_bytes();
+        let restored: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(restored["queued"], 1);
+        assert!(queued_path.exists());
+        assert_eq!(ctx.intents().read().len(), 1);
+
+        // The upload form streams the archive to a staging dir first.
+        let archive = fs::read(data_dir.join("backups").join(&file_name)).unwrap();
+        let uploaded = app
+            .clone()
+            .oneshot(post("/api/admin/restore", "application/gzip", archive))
+            .await
+            .expect("restore response");
+        assert_eq!(uploaded.status(), StatusCode::OK);
+        assert_eq!(ctx.intents().read().len(), 1);
+        let leftovers = fs::read_dir(&data_dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".restore-"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let listed = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/admin/backups")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("list response");
+        let body = listed.into_body().collect().await.unwrap().to_bytes();
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // The original snapshot plus the one taken before each restore.
+        assert_eq!(listed["backups"].as_array().unwrap().len(), 3);
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
 
+    #[tokio::test]
+    #[serial]
+    async fn retention_report_is_a_dry_run() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
         fs::create_dir_all(root.join("config")).expect("config dir");
-        fs::create_dir_all(root.join("docs")).expect("docs dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
         fs::write(
-            root.join("config/beat.yml"),
-            "interval_minutes: 10\nintent_threshold: 0.5\n",
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
         )
-        .expect("beat config");
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::write(
+            root.join("config/retention.yml"),
+            "llm_logs:\n  max_age_days: 7\n",
+        )
+        .expect("retention config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+
+        let old_log = data_dir.join("logs/llm/2020/01/01.jsonl");
+        fs::create_dir_all(old_log.parent().unwrap()).unwrap();
+        fs::write(&old_log, "{}\n").unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/admin/retention")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("retention response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["categories"][0]["category"], "llm_logs");
+        assert_eq!(
+            report["categories"][0]["pruned"][0]["path"],
+            "logs/llm/2020/01/01.jsonl"
+        );
+        assert!(old_log.exists());
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn storage_report_lists_dir_sizes_and_paused_writes() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
         fs::write(
             root.join("config/agent.yml"),
             "max_react_steps: 1\npersona: TelosOps\n",
         )
         .expect("agent config");
         fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::write(root.join("config/disk.yml"), "min_free_mb: 64\n").expect("disk config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let handle = orchestrator::OrchestratorHandle::remote(&ctx);
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        let report = || async {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/api/admin/storage")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .expect("storage response");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let identity = crate::llm::LlmIdentity::new("local_stub", None);
+        let entry =
+            crate::llm::LlmLogEntry::new(Uuid::new_v4(), ctx.now(), "final", "p", "r", &identity);
+        storage::append_llm_logs(&data_dir, std::slice::from_ref(&entry))
+            .await
+            .unwrap();
+        let usage = report().await;
+        assert_eq!(usage["min_free_mb"], 64);
+        assert_eq!(usage["writes_paused"], false);
+        let logs = usage["dirs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|dir| dir["name"] == "logs")
+            .cloned()
+            .unwrap();
+        assert_eq!(logs["files"], 2);
+        assert!(usage["disk"]["available_bytes"].as_u64().unwrap() > 0);
+
+        // While paused, a chat run's LLM calls are not logged.
+        assert!(ctx.set_writes_paused(true));
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/chat")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"message": "Hello", "chat_id": "desk"}"#))
+                    .unwrap(),
+            )
+            .await
+            .expect("chat response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let usage = report().await;
+        assert_eq!(usage["writes_paused"], true);
+        let logged = storage::read_llm_logs(&data_dir, storage::LlmLogQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(logged.len(), 1);
+        assert!(ctx.set_writes_paused(false));
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn markdown_endpoints_return_tree_and_file() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+
+        fs::create_dir_all(root.join("config")).expect("config dir");
         fs::write(
-            root.join("docs/work_acceptance_plan.md"),
-            "## 2. 任务矩阵\n| 模块 | 任务 | 状态 |\n| --- | --- | --- |\n| API | 汇总验收计划 | ✅ |\n\n## 4. TODO 追踪\n\n### 4.1 已完成清单\n- [x] 已完成事项\n\n### 4.2 进行中/待定\n- 待处理事项\n\n## 5. 验证方案概览\n| 类型 | 验证内容 | 指令/方式 |\n| --- | --- | --- |\n| 端到端 | 核心链路 | cargo test --test e2e |\n",
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
         )
-        .expect("plan doc");
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
 
         unsafe {
             std::env::set_var("HI_APP_ROOT", root);
@@ -1116,9 +5303,35 @@ mod tests {
 
         let config = AppConfig::load().expect("load config");
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let data_dir = config.data_dir.clone();
         let ctx = AppContext::new(config, Arc::new(agent));
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
+
+        let sample_path = data_dir.join("journals/2025/01/01.md");
+        write_markdown(&sample_path, "# Heading\nBody")
+            .await
+            .expect("write sample");
+
+        let preview_payload = StructuredContent {
+            title: "Preview Title".to_string(),
+            summary: "Preview summary for front-end snapshot.".to_string(),
+            sections: vec![StructuredSection {
+                heading: "Section".to_string(),
+                body: vec!["Line".to_string()],
+                children: vec![],
+            }],
+        };
+        tokio::fs::create_dir_all(data_dir.join("mock"))
+            .await
+            .expect("create mock dir");
+        tokio::fs::write(
+            data_dir.join("mock/text_structure.json"),
+            serde_json::to_string(&preview_payload).unwrap(),
+        )
+        .await
+        .expect("write preview");
+
         let state = ServerState::new(ctx.clone(), handle);
         let app = super::router(state.clone());
 
@@ -1126,209 +5339,263 @@ mod tests {
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/meta/acceptance")
+                    .uri("/api/md/tree")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("acceptance response");
+            .expect("tree response");
         assert_eq!(response.status(), StatusCode::OK);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-
         assert!(
-            payload["source"]["doc_path"]
-                .as_str()
+            payload["files"]
+                .as_array()
                 .unwrap()
-                .ends_with("docs/work_acceptance_plan.md")
-        );
-        assert_eq!(payload["task_matrix"].as_array().unwrap().len(), 1);
-        assert_eq!(payload["completed_todos"].as_array().unwrap().len(), 1);
-        assert_eq!(payload["pending_todos"].as_array().unwrap().len(), 1);
-        assert_eq!(payload["validation_plan"].as_array().unwrap().len(), 1);
-        assert_eq!(payload["metrics"]["modules_total"], serde_json::json!(1));
-        assert_eq!(
-            payload["metrics"]["modules_completed"],
-            serde_json::json!(1)
-        );
-        assert_eq!(payload["metrics"]["todos_completed"], serde_json::json!(1));
-        assert_eq!(payload["metrics"]["todos_pending"], serde_json::json!(1));
-        assert_eq!(payload["metrics"]["validation_steps"], serde_json::json!(1));
-        assert_eq!(
-            payload["metrics"]["overall_status"],
-            serde_json::json!("in_progress")
-        );
-        assert_eq!(
-            payload["completed_todos"][0]["label"],
-            serde_json::Value::String("已完成事项".to_string())
-        );
-        assert_eq!(
-            payload["pending_todos"][0]["label"],
-            serde_json::Value::String("待处理事项".to_string())
-        );
-        assert_eq!(
-            payload["validation_plan"][0]["command"],
-            serde_json::Value::String("cargo test --test e2e".to_string())
-        );
-        assert_eq!(
-            payload["task_matrix"][0]["module"],
-            serde_json::Value::String("API".to_string())
-        );
-        assert_eq!(
-            payload["task_matrix"][0]["status"],
-            serde_json::Value::String("✅".to_string())
+                .contains(&serde_json::Value::String(
+                    "journals/2025/01/01.md".to_string()
+                ))
         );
 
-        let module_response = app
+        let response = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/meta/acceptance/module/API")
+                    .uri("/api/md/file?path=journals/2025/01/01.md")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("module response");
-        assert_eq!(module_response.status(), StatusCode::OK);
-        let body = module_response
-            .into_body()
-            .collect()
-            .await
-            .unwrap()
-            .to_bytes();
-        let module_payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(module_payload["module"], "API");
-        assert_eq!(module_payload["tasks"].as_array().unwrap().len(), 1);
-        assert_eq!(
-            module_payload["metrics"]["tasks_total"],
-            serde_json::json!(1)
-        );
-        assert_eq!(
-            module_payload["metrics"]["overall_status"],
-            serde_json::json!("complete")
+            .expect("file response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let file_payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(file_payload["path"], "journals/2025/01/01.md");
+        assert!(
+            file_payload["content"]
+                .as_str()
+                .unwrap()
+                .contains("# Heading")
         );
 
-        let fuzzy_response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/meta/acceptance/module/api")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("fuzzy module response");
-        assert_eq!(fuzzy_response.status(), StatusCode::OK);
-
-        let missing_response = app
+        let response = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/meta/acceptance/module/unknown")
+                    .uri("/api/md/file?path=journals/2025/01/01.md&render=true")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("missing module response");
-        assert_eq!(missing_response.status(), StatusCode::NOT_FOUND);
-
-        ctx.request_shutdown();
-        let _ = join.await;
-
-        unsafe {
-            std::env::remove_var("HI_APP_ROOT");
-            std::env::remove_var("HI_SERVER_BIND");
-        }
-    }
+            .expect("render response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("<h1>Heading</h1>"));
 
-    #[tokio::test]
-    #[serial]
-    async fn messages_api_returns_recent_entries() {
-        let tmp = TempDir::new().expect("tempdir");
-        let root = tmp.path();
+        let conditional_get =
+            |uri: &'static str, condition: Option<(header::HeaderName, String)>| {
+                let app = app.clone();
+                async move {
+                    let mut request = Request::builder().uri(uri);
+                    if let Some((name, value)) = condition {
+                        request = request.header(name, value);
+                    }
+                    app.oneshot(request.body(Body::empty()).unwrap())
+                        .await
+                        .expect("conditional response")
+                }
+            };
+        let tree = conditional_get("/api/md/tree", None).await;
+        let tree_etag = tree.headers()[header::ETAG].to_str().unwrap().to_string();
+        let cached = conditional_get(
+            "/api/md/tree",
+            Some((header::IF_NONE_MATCH, tree_etag.clone())),
+        )
+        .await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
 
-        fs::create_dir_all(root.join("config")).expect("config dir");
-        fs::write(
-            root.join("config/beat.yml"),
-            "interval_minutes: 10
-intent_threshold: 0.5
-",
+        let file = conditional_get("/api/md/file?path=journals/2025/01/01.md", None).await;
+        let file_etag = file.headers()[header::ETAG].to_str().unwrap().to_string();
+        let last_modified = file.headers()[header::LAST_MODIFIED]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let cached = conditional_get(
+            "/api/md/file?path=journals/2025/01/01.md",
+            Some((header::IF_NONE_MATCH, file_etag.clone())),
         )
-        .expect("beat config");
-        fs::write(
-            root.join("config/agent.yml"),
-            "max_react_steps: 1
-persona: TelosOps
-",
+        .await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        let cached = conditional_get(
+            "/api/md/file?path=journals/2025/01/01.md",
+            Some((header::IF_MODIFIED_SINCE, last_modified)),
         )
-        .expect("agent config");
-        fs::write(
-            root.join("config/llm.yml"),
-            "provider: local_stub
-",
+        .await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        // Rendered HTML is a different representation of the same file.
+        let rendered = conditional_get(
+            "/api/md/file?path=journals/2025/01/01.md&render=true",
+            Some((header::IF_NONE_MATCH, file_etag)),
         )
-        .expect("llm config");
-
-        unsafe {
-            std::env::set_var("HI_APP_ROOT", root);
-            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
-        }
-
-        let config = AppConfig::load().expect("load config");
-        let data_dir = config.data_dir.clone();
-        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
-        let ctx = AppContext::new(config, Arc::new(agent));
-
-        let (handle, join) = orchestrator::spawn(ctx.clone());
-        let state = ServerState::new(ctx.clone(), handle);
-        let app = super::router(state.clone());
+        .await;
+        assert_eq!(rendered.status(), StatusCode::OK);
 
-        let now = Utc::now();
-        let inbound = MessageLogEntry {
-            id: Uuid::new_v4(),
-            direction: MessageDirection::Inbound,
-            source: "telegram".to_string(),
-            chat_id: "42".to_string(),
-            author: Some("alice".to_string()),
-            text: "inbound ping".to_string(),
-            timestamp: now - Duration::seconds(30),
-            metadata: None,
+        let untrusted_path = data_dir.join("journals/2025/01/02.md");
+        write_markdown(
+            &untrusted_path,
+            "# From Telegram\n<script>alert(1)</script>\n\n<img src=\"x\" onerror=\"alert(2)\">\n\n[link](javascript:alert(3))",
+        )
+        .await
+        .expect("write untrusted sample");
+        let render = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .expect("render response");
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
         };
-        let outbound = MessageLogEntry {
-            id: Uuid::new_v4(),
-            direction: MessageDirection::Outbound,
-            source: "telegram".to_string(),
-            chat_id: "42".to_string(),
-            author: Some("bot".to_string()),
-            text: "outbound pong".to_string(),
-            timestamp: now,
-            metadata: None,
+        let fragment = render("/api/md/file?path=journals/2025/01/02.md&render=true").await;
+        assert!(fragment.contains("<h1>From Telegram</h1>"));
+        assert!(!fragment.contains("<script"), "{fragment}");
+        assert!(!fragment.contains("onerror"), "{fragment}");
+        assert!(!fragment.contains("javascript:"), "{fragment}");
+        assert!(!fragment.contains("<!DOCTYPE html>"));
+        let page = render("/api/md/file?path=journals/2025/01/02.md&render=true&page=true").await;
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains(r#"<section class="markdown"><h1>From Telegram</h1>"#));
+        assert!(!page.contains("alert(1)"));
+
+        // A data change noted by the workspace invalidates the cached tree.
+        ctx.note_data_changed();
+        let refreshed =
+            conditional_get("/api/md/tree", Some((header::IF_NONE_MATCH, tree_etag))).await;
+        assert_eq!(refreshed.status(), StatusCode::OK);
+
+        let revision = storage::record_markdown_revision(
+            &data_dir,
+            std::path::Path::new("journals/2025/01/01.md"),
+            chrono::Utc::now(),
+        )
+        .await
+        .expect("record revision")
+        .expect("new revision");
+        fs::write(&sample_path, "# Heading\nEdited by hand").expect("edit sample");
+
+        let get_json = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .expect("markdown history response");
+                let status = response.status();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+                )
+            }
         };
+        let (status, history) =
+            get_json("/api/md/file/history?path=journals/2025/01/01.md".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history["revisions"][0]["id"], revision.id.as_str());
+
+        let (status, diff) =
+            get_json("/api/md/file/diff?path=journals/2025/01/01.md".to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(diff["from"], revision.id.as_str());
+        assert_eq!(diff["to"], "current");
+        assert!(
+            diff["diff"]
+                .as_str()
+                .unwrap()
+                .contains("-Body\n\\ No newline at end of file\n+Edited by hand")
+        );
+        let (status, _) = get_json(
+            "/api/md/file/diff?path=journals/2025/01/01.md&from=20000101T000000000000Z".to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
 
-        storage::append_message_entry(&data_dir, &inbound)
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/md/file/revert")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        json!({"path": "journals/2025/01/01.md", "revision": revision.id})
+                            .to_string(),
+                    ))
+                    .unwrap(),
+            )
             .await
-            .expect("write inbound message");
-        storage::append_message_entry(&data_dir, &outbound)
+            .expect("revert response");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(fs::read_to_string(&sample_path).unwrap(), "# Heading\nBody");
+        let (_, history) =
+            get_json("/api/md/file/history?path=journals/2025/01/01.md".to_string()).await;
+        // The hand edit was kept as a revision before being reverted.
+        assert_eq!(history["revisions"].as_array().unwrap().len(), 3);
+
+        let identity = crate::llm::LlmIdentity::new("local_stub", Some("local_stub".to_string()));
+        let log_entry = crate::llm::LlmLogEntry::new(
+            Uuid::new_v4(),
+            chrono::Utc::now(),
+            "THINK",
+            "prompt",
+            "response",
+            &identity,
+        );
+        storage::append_llm_logs(&data_dir, std::slice::from_ref(&log_entry))
             .await
-            .expect("write outbound message");
+            .expect("append log");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/logs/llm?level=think&limit=1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("logs response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(payload["entries"][0]["phase"], "THINK");
 
         let response = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/messages?dir=in&src=telegram")
+                    .uri("/api/mock/text_structure")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("messages response");
+            .expect("text structure response");
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: MessageListResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload.entries.len(), 1);
-        assert_eq!(payload.entries[0].direction, MessageDirection::Inbound);
-        assert_eq!(payload.entries[0].text, "inbound ping");
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["title"], preview_payload.title);
+        assert_eq!(payload["summary"], preview_payload.summary);
+        assert_eq!(
+            payload["sections"].as_array().unwrap()[0]["heading"],
+            preview_payload.sections[0].heading
+        );
+        assert_eq!(payload["source"], "file");
+        assert!(payload["updated_at"].as_str().is_some());
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -1341,12 +5608,11 @@ persona: TelosOps
 
     #[tokio::test]
     #[serial]
-    async fn memory_timeline_returns_rollup() {
+    async fn structured_text_preview_can_be_updated_via_post() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
         fs::create_dir_all(root.join("config")).expect("config dir");
-        fs::create_dir_all(root.join("docs")).expect("docs dir");
         fs::write(
             root.join("config/beat.yml"),
             "interval_minutes: 10\nintent_threshold: 0.5\n",
@@ -1358,7 +5624,6 @@ persona: TelosOps
         )
         .expect("agent config");
         fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
-        fs::write(root.join("docs/work_acceptance_plan.md"), "# plan\n").expect("plan doc");
 
         unsafe {
             std::env::set_var("HI_APP_ROOT", root);
@@ -1366,66 +5631,275 @@ persona: TelosOps
         }
 
         let config = AppConfig::load().expect("load config");
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
         let data_dir = config.data_dir.clone();
-        storage::ensure_data_layout(&data_dir).expect("layout");
-
-        let journal_path = data_dir.join("journals/2025/01/01.md");
-        fs::create_dir_all(journal_path.parent().unwrap()).expect("journal dir");
-        fs::write(&journal_path, "entry").expect("journal file");
+        let ctx = AppContext::new(config, Arc::new(agent));
 
-        let history_path = data_dir.join("intent/history/sample.md");
-        fs::create_dir_all(history_path.parent().unwrap()).expect("history dir");
-        fs::write(&history_path, "intent").expect("history file");
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
 
-        let intent = Intent {
-            id: Uuid::new_v4(),
-            source: "telegram".to_string(),
-            summary: "Summarize roadmap".to_string(),
-            telos_alignment: 0.9,
-            created_at: Utc::now(),
-            storage_path: None,
+        let desired = StructuredContent {
+            title: "Custom Title".to_string(),
+            summary: "Custom summary".to_string(),
+            sections: vec![StructuredSection {
+                heading: "Custom heading".to_string(),
+                body: vec!["Line".to_string()],
+                children: vec![],
+            }],
         };
-        let outcome = AgentOutcome {
-            steps: Vec::new(),
-            final_answer: "Highlights captured".to_string(),
+        let initial_note = "Initial skeleton";
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/mock/text_structure")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "content": desired.clone(),
+                            "note": initial_note,
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .expect("post response");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let stored = tokio::fs::read_to_string(data_dir.join("mock/text_structure.json"))
+            .await
+            .expect("read stored");
+        let stored: serde_json::Value = serde_json::from_str(&stored).expect("parse stored");
+        assert_eq!(stored["content"], serde_json::to_value(&desired).unwrap());
+        assert_eq!(
+            stored["note"],
+            serde_json::Value::String(initial_note.to_string())
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("get response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let fetched: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse fetched");
+        assert_eq!(fetched.content, desired);
+        assert_eq!(fetched.source, TextStructurePreviewSource::File);
+        assert_eq!(fetched.note.as_deref(), Some(initial_note));
+        assert!(fetched.updated_at.is_some());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure/history?limit=1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let history: TextStructureHistoryResponse =
+            serde_json::from_slice(&body).expect("parse history");
+        assert_eq!(history.entries.len(), 1);
+        assert_eq!(history.entries[0].content, desired);
+        assert_eq!(history.entries[0].note.as_deref(), Some(initial_note));
+        let history_id = history.entries[0].id.clone();
+
+        let history_entry_uri = format!("/api/mock/text_structure/history/{history_id}");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(history_entry_uri.clone())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history entry response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let entry: StructuredTextHistoryEntry =
+            serde_json::from_slice(&body).expect("parse history entry");
+        assert_eq!(entry.id, history_id);
+        assert_eq!(entry.content, desired);
+        assert_eq!(entry.note.as_deref(), Some(initial_note));
+
+        let updated = StructuredContent {
+            title: "Updated".to_string(),
+            summary: "Updated summary".to_string(),
+            sections: vec![StructuredSection {
+                heading: "Updated heading".to_string(),
+                body: vec!["Updated".to_string()],
+                children: vec![],
+            }],
         };
+        let updated_note = "Revision 2";
 
-        storage::ingest_memory_snapshot(
-            &data_dir,
-            MemorySnapshotInput {
-                intent: intent.clone(),
-                outcome,
-                journal_path: journal_path.clone(),
-                history_path: Some(history_path.clone()),
-            },
-        )
-        .await
-        .expect("ingest memory");
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/mock/text_structure")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "content": updated.clone(),
+                            "note": updated_note,
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .expect("post response");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("get response after update");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let fetched: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse fetched");
+        assert_eq!(fetched.content, updated);
+        assert_eq!(fetched.note.as_deref(), Some(updated_note));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure/history?limit=5")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history list response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let expanded: TextStructureHistoryResponse =
+            serde_json::from_slice(&body).expect("parse expanded history");
+        assert!(expanded.entries.len() >= 2);
+
+        let older = expanded.entries[1].saved_at + chrono::Duration::milliseconds(1);
+        let since_uri = format!(
+            "/api/mock/text_structure/history?since={}",
+            older.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+        );
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(since_uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history since response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let filtered_since: TextStructureHistoryResponse =
+            serde_json::from_slice(&body).expect("parse since history");
+        assert_eq!(filtered_since.entries.len(), 1);
+        assert_eq!(
+            filtered_since.entries[0].note.as_deref(),
+            Some(updated_note)
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure/history?q=revision")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history query response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let filtered_query: TextStructureHistoryResponse =
+            serde_json::from_slice(&body).expect("parse query history");
+        assert_eq!(filtered_query.entries.len(), 1);
+        assert_eq!(
+            filtered_query.entries[0].note.as_deref(),
+            Some(updated_note)
+        );
 
-        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
-        let ctx = AppContext::new(config, Arc::new(agent));
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure/history?q=custom")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("history content query response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let filtered_content: TextStructureHistoryResponse =
+            serde_json::from_slice(&body).expect("parse content history");
+        assert_eq!(filtered_content.entries.len(), 1);
+        assert!(
+            filtered_content.entries[0]
+                .content
+                .summary
+                .contains("Custom summary")
+        );
 
-        let (handle, join) = orchestrator::spawn(ctx.clone());
-        let state = ServerState::new(ctx.clone(), handle);
-        let app = super::router(state.clone());
+        let restore_uri = format!("/api/mock/text_structure/history/{history_id}/restore");
 
         let response = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/api/memory?level=L2&limit=10")
+                    .method("POST")
+                    .uri(restore_uri)
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("memory response");
-        assert_eq!(response.status(), StatusCode::OK);
+            .expect("restore response");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("get response after restore");
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["level"], serde_json::json!("L2"));
-        let entries = payload["entries"].as_array().unwrap();
-        assert!(!entries.is_empty());
-        assert!(!entries[0]["summary"].as_str().unwrap().is_empty());
+        let restored: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse restored");
+        assert_eq!(restored.content, desired);
+        assert_eq!(restored.note.as_deref(), Some(initial_note));
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -1438,39 +5912,22 @@ persona: TelosOps
 
     #[tokio::test]
     #[serial]
-    async fn telegram_webhook_appends_message_and_intent() {
+    async fn structured_text_preview_can_be_reset_via_delete() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
         fs::create_dir_all(root.join("config")).expect("config dir");
         fs::write(
             root.join("config/beat.yml"),
-            "interval_minutes: 10
-intent_threshold: 0.5
-",
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
         )
         .expect("beat config");
         fs::write(
             root.join("config/agent.yml"),
-            "max_react_steps: 1
-persona: TelosOps
-",
+            "max_react_steps: 1\npersona: TelosOps\n",
         )
         .expect("agent config");
-        fs::write(
-            root.join("config/llm.yml"),
-            "provider: local_stub
-",
-        )
-        .expect("llm config");
-        fs::write(
-            root.join("config/telegram.yml"),
-            "bot_token: TEST_TOKEN
-webhook_secret: secret-token
-default_chat_id: 12345
-",
-        )
-        .expect("telegram config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
 
         unsafe {
             std::env::set_var("HI_APP_ROOT", root);
@@ -1478,80 +5935,204 @@ default_chat_id: 12345
         }
 
         let config = AppConfig::load().expect("load config");
-        let data_dir = config.data_dir.clone();
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let data_dir = config.data_dir.clone();
         let ctx = AppContext::new(config, Arc::new(agent));
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
         let state = ServerState::new(ctx.clone(), handle);
         let app = super::router(state.clone());
 
-        let update = json!({
-            "update_id": 1,
-            "message": {
-                "message_id": 99,
-                "date": Utc::now().timestamp(),
-                "chat": {"id": 4242, "type": "private"},
-                "from": {"id": 7, "username": "alice"},
-                "text": "Hello Telos",
-            }
-        });
+        let desired = StructuredContent {
+            title: "Custom Title".to_string(),
+            summary: "Custom summary".to_string(),
+            sections: vec![StructuredSection {
+                heading: "Custom heading".to_string(),
+                body: vec!["Line".to_string()],
+                children: vec![],
+            }],
+        };
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/mock/text_structure")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&desired).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .expect("post response");
+
+        assert!(data_dir.join("mock/text_structure.json").exists());
 
         let response = app
             .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/webhook/telegram")
+                    .uri("/api/mock/text_structure")
                     .header("content-type", "application/json")
-                    .header("X-Telegram-Bot-Api-Secret-Token", "secret-token")
-                    .body(Body::from(serde_json::to_vec(&update).unwrap()))
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "content": {"title": "", "summary": "", "sections": []},
+                        }))
+                        .unwrap(),
+                    ))
                     .unwrap(),
             )
             .await
-            .expect("webhook response");
+            .expect("invalid post response");
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let rejected: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(rejected["violations"][0]["field"], "title");
+        let stored = tokio::fs::read_to_string(data_dir.join("mock/text_structure.json"))
+            .await
+            .unwrap();
+        assert!(stored.contains("Custom Title"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/mock/text_structure")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("delete response");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        assert!(!data_dir.join("mock/text_structure.json").exists());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/mock/text_structure")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("get response");
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: TelegramWebhookResponse = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload.status, "queued");
-        assert!(payload.intent_id.is_some());
+        let fetched: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse fetched");
+        assert_eq!(fetched.source, TextStructurePreviewSource::Inline);
+        assert!(fetched.note.is_none());
+        assert!(fetched.updated_at.is_none());
+        assert_eq!(
+            fetched.content.title,
+            StructuredContent::mock_payload().title
+        );
 
-        let logs = task::spawn_blocking({
-            let data_dir = data_dir.clone();
-            move || {
-                storage::read_messages(
-                    &data_dir,
-                    MessageLogQuery {
-                        source: Some("telegram".to_string()),
-                        direction: Some(MessageDirection::Inbound),
-                        limit: 5,
-                        ..Default::default()
-                    },
-                )
-            }
-        })
-        .await
-        .expect("join")
-        .expect("load inbound logs");
-        assert_eq!(logs.len(), 1);
-        assert_eq!(logs[0].text, "Hello Telos");
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/messages")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("messages page");
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response
+            .headers()
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        assert!(content_type.starts_with("text/html"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("消息面板"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/logs")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("logs page");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("日志面板"));
+        assert!(html.contains("/ui/assets/logs.js"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/assets/ui.css")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("stylesheet");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()[axum::http::header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/css")
+        );
+        let etag = response.headers()[axum::http::header::ETAG].clone();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("ul.tree"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/assets/ui.css")
+                    .header(axum::http::header::IF_NONE_MATCH, etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("stylesheet revalidation");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/assets/missing.js")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("missing asset");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
-        let mut has_intent = false;
-        for dir in ["intent/inbox", "intent/queue", "intent/history"] {
-            let intent_dir = data_dir.join(dir);
-            if intent_dir.exists()
-                && fs::read_dir(&intent_dir)
-                    .map(|mut entries| entries.next().is_some())
-                    .unwrap_or(false)
-            {
-                has_intent = true;
-                break;
-            }
-        }
-        assert!(has_intent, "telegram webhook should create intent markdown");
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/ui/messages/stream")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .expect("messages stream");
+        assert_eq!(response.status(), StatusCode::OK);
+        let stream_type = response
+            .headers()
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        assert!(stream_type.starts_with("text/event-stream"));
 
         ctx.request_shutdown();
-        join.abort();
+        let _ = join.await;
 
         unsafe {
             std::env::remove_var("HI_APP_ROOT");
@@ -1561,61 +6142,28 @@ default_chat_id: 12345
 
     #[tokio::test]
     #[serial]
-    async fn send_message_uses_telegram_api() {
-        let server = MockServer::start_async().await;
-        let token = "TEST_TOKEN";
-        let path = format!("/bot{token}/sendMessage");
-        let mock = {
-            let expected_path = path.clone();
-            server
-                .mock_async(move |when, then| {
-                    when.method("POST").path(expected_path.as_str());
-                    then.status(200)
-                        .header("content-type", "application/json")
-                        .json_body(json!({
-                            "ok": true,
-                            "result": {"message_id": 9001}
-                        }));
-                })
-                .await
-        };
-
+    async fn structured_text_preview_can_be_generated_from_markdown() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
         fs::create_dir_all(root.join("config")).expect("config dir");
         fs::write(
             root.join("config/beat.yml"),
-            "interval_minutes: 10
-intent_threshold: 0.5
-",
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
         )
         .expect("beat config");
         fs::write(
             root.join("config/agent.yml"),
-            "max_react_steps: 1
-persona: TelosOps
-",
+            "max_react_steps: 1\npersona: TelosOps\n",
         )
         .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::create_dir_all(root.join("data/docs")).expect("docs dir");
         fs::write(
-            root.join("config/llm.yml"),
-            "provider: local_stub
-",
-        )
-        .expect("llm config");
-        fs::write(
-            root.join("config/telegram.yml"),
-            format!(
-                "bot_token: {token}
-default_chat_id: 777
-webhook_secret: test
-api_base: {}
-",
-                server.base_url()
-            ),
+            root.join("data/docs/plan.md"),
+            "# Launch plan\n\nShip it.\n\n## Steps\n\n- build\n- release\n",
         )
-        .expect("telegram config");
+        .expect("markdown doc");
 
         unsafe {
             std::env::set_var("HI_APP_ROOT", root);
@@ -1623,53 +6171,82 @@ api_base: {}
         }
 
         let config = AppConfig::load().expect("load config");
-        let data_dir = config.data_dir.clone();
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let data_dir = config.data_dir.clone();
         let ctx = AppContext::new(config, Arc::new(agent));
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
         let state = ServerState::new(ctx.clone(), handle);
         let app = super::router(state.clone());
 
+        let post = |payload: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/mock/text_structure/from_markdown")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap()
+        };
+
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/messages/send")
-                    .header("content-type", "application/json")
-                    .body(Body::from(
-                        serde_json::to_vec(&json!({
-                            "text": "Ping from test"
-                        }))
-                        .unwrap(),
-                    ))
-                    .unwrap(),
-            )
+            .oneshot(post(serde_json::json!({ "path": "docs/plan.md" })))
             .await
-            .expect("send response");
+            .expect("path response");
         assert_eq!(response.status(), StatusCode::OK);
-        mock.assert_async().await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let generated: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse generated");
+        assert_eq!(generated.source, TextStructurePreviewSource::File);
+        assert_eq!(generated.content.title, "Launch plan");
+        assert_eq!(generated.content.summary, "Ship it.");
+        assert_eq!(generated.content.sections[0].heading, "Steps");
+        assert_eq!(
+            generated.content.sections[0].body,
+            vec!["- build", "- release"]
+        );
+        assert_eq!(
+            generated.note.as_deref(),
+            Some("Generated from markdown docs/plan.md")
+        );
+        let loaded = storage::load_structured_text_preview(&data_dir, None)
+            .await
+            .unwrap()
+            .expect("saved preview");
+        assert_eq!(loaded.content.title, "Launch plan");
 
-        let logs = task::spawn_blocking({
-            let data_dir = data_dir.clone();
-            move || {
-                storage::read_messages(
-                    &data_dir,
-                    MessageLogQuery {
-                        source: Some("telegram".to_string()),
-                        direction: Some(MessageDirection::Outbound),
-                        limit: 5,
-                        ..Default::default()
-                    },
-                )
-            }
-        })
-        .await
-        .expect("join")
-        .expect("load outbound logs");
-        assert!(!logs.is_empty());
-        assert!(logs.iter().any(|entry| entry.text == "Ping from test"));
+        let response = app
+            .clone()
+            .oneshot(post(serde_json::json!({
+                "markdown": "## Notes\n\nUntitled body\n",
+                "summarize": true,
+            })))
+            .await
+            .expect("markdown response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let generated: TextStructurePreviewResponse =
+            serde_json::from_slice(&body).expect("parse summarized");
+        assert_eq!(generated.content.title, "Markdown import");
+        // Answered as a SUMMARY prompt, not as a run's FINAL answer.
+        assert!(generated.content.summary.starts_with("Summary of a "));
+        let note = generated.note.unwrap();
+        assert!(note.starts_with("Generated from submitted markdown; summary by local_stub"));
+
+        for payload in [
+            serde_json::json!({}),
+            serde_json::json!({ "path": "docs/plan.md", "markdown": "# x" }),
+            serde_json::json!({ "path": "../secret.md" }),
+        ] {
+            let response = app.clone().oneshot(post(payload)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let response = app
+            .clone()
+            .oneshot(post(serde_json::json!({ "path": "docs/missing.md" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -1682,7 +6259,7 @@ api_base: {}
 
     #[tokio::test]
     #[serial]
-    async fn markdown_endpoints_return_tree_and_file() {
+    async fn named_structured_text_previews_are_independent() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
@@ -1706,146 +6283,167 @@ api_base: {}
 
         let config = AppConfig::load().expect("load config");
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
-        let data_dir = config.data_dir.clone();
         let ctx = AppContext::new(config, Arc::new(agent));
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
 
-        let sample_path = data_dir.join("journals/2025/01/01.md");
-        write_markdown(&sample_path, "# Heading\nBody")
-            .await
-            .expect("write sample");
-
-        let preview_payload = StructuredContent {
-            title: "Preview Title".to_string(),
-            summary: "Preview summary for front-end snapshot.".to_string(),
-            sections: vec![StructuredSection {
-                heading: "Section".to_string(),
-                body: vec!["Line".to_string()],
-                children: vec![],
-            }],
+        let request = |method: &str, uri: &str, body: Option<serde_json::Value>| {
+            let builder = Request::builder().method(method).uri(uri);
+            match body {
+                Some(body) => builder
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+                None => builder.body(Body::empty()).unwrap(),
+            }
         };
-        tokio::fs::create_dir_all(data_dir.join("mock"))
+        let fixture = |title: &str| {
+            serde_json::json!({
+                "content": {"title": title, "summary": "s", "sections": []},
+                "note": format!("{title} note"),
+            })
+        };
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/mock/text_structure/checkout", None))
             .await
-            .expect("create mock dir");
-        tokio::fs::write(
-            data_dir.join("mock/text_structure.json"),
-            serde_json::to_string(&preview_payload).unwrap(),
-        )
-        .await
-        .expect("write preview");
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
-        let state = ServerState::new(ctx.clone(), handle);
-        let app = super::router(state.clone());
+        let stream = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                "/api/mock/text_structure/stream?name=checkout",
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(stream.status(), StatusCode::OK);
+        let mut stream = stream.into_body();
+
+        for (uri, title) in [
+            ("/api/mock/text_structure/checkout", "Checkout"),
+            ("/api/mock/text_structure/checkout", "Checkout v2"),
+            ("/api/mock/text_structure/onboarding", "Onboarding"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request("POST", uri, Some(fixture(title))))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        }
 
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/md/tree")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(request("GET", "/api/mock/text_structure/checkout", None))
             .await
-            .expect("tree response");
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert!(
-            payload["files"]
-                .as_array()
-                .unwrap()
-                .contains(&serde_json::Value::String(
-                    "journals/2025/01/01.md".to_string()
-                ))
-        );
+        let fetched: TextStructurePreviewResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.content.title, "Checkout v2");
+        assert_eq!(fetched.source, TextStructurePreviewSource::File);
 
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/md/file?path=journals/2025/01/01.md")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(request("GET", "/api/mock/text_structure", None))
             .await
-            .expect("file response");
-        assert_eq!(response.status(), StatusCode::OK);
+            .unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let file_payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(file_payload["path"], "journals/2025/01/01.md");
-        assert!(
-            file_payload["content"]
-                .as_str()
-                .unwrap()
-                .contains("# Heading")
-        );
+        let fetched: TextStructurePreviewResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fetched.source, TextStructurePreviewSource::Inline);
 
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/md/file?path=journals/2025/01/01.md&render=true")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(request(
+                "GET",
+                "/api/mock/text_structure/checkout/history",
+                None,
+            ))
             .await
-            .expect("render response");
-        assert_eq!(response.status(), StatusCode::OK);
+            .unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let html = String::from_utf8(body.to_vec()).unwrap();
-        assert!(html.contains("<h1>Heading</h1>"));
+        let history: TextStructureHistoryResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(history.entries.len(), 2);
+        let first_id = history.entries[1].id.clone();
 
-        let identity = crate::llm::LlmIdentity::new("local_stub", Some("local_stub".to_string()));
-        let log_entry = crate::llm::LlmLogEntry::new(
-            Uuid::new_v4(),
-            chrono::Utc::now(),
-            "THINK",
-            "prompt",
-            "response",
-            &identity,
-        );
-        storage::append_llm_logs(&data_dir, std::slice::from_ref(&log_entry))
+        let response = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                &format!("/api/mock/text_structure/history/{first_id}"),
+                None,
+            ))
             .await
-            .expect("append log");
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/logs/llm?level=think&limit=1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(request(
+                "POST",
+                &format!("/api/mock/text_structure/checkout/history/{first_id}/restore"),
+                None,
+            ))
             .await
-            .expect("logs response");
-        assert_eq!(response.status(), StatusCode::OK);
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let mut events = String::new();
+        while events.matches("event: preview_changed").count() < 3 {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), stream.frame())
+                .await
+                .expect("preview event")
+                .expect("stream open")
+                .unwrap();
+            if let Ok(data) = frame.into_data() {
+                events.push_str(&String::from_utf8_lossy(&data));
+            }
+        }
+        assert!(events.contains("\"kind\":\"saved\""));
+        assert!(events.contains("\"note\":\"Checkout v2 note\""));
+        assert!(events.contains(&format!("\"history_id\":\"{first_id}\"")));
+        assert!(!events.contains("onboarding"));
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/mock/text_structures", None))
+            .await
+            .unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["entries"].as_array().unwrap().len(), 1);
-        assert_eq!(payload["entries"][0]["phase"], "THINK");
+        let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let previews = listed["previews"].as_array().unwrap();
+        assert_eq!(previews.len(), 2);
+        assert_eq!(previews[0]["name"], "checkout");
+        assert_eq!(previews[0]["title"], "Checkout");
+        assert_eq!(previews[1]["name"], "onboarding");
 
         let response = app
             .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(request(
+                "DELETE",
+                "/api/mock/text_structure/onboarding",
+                None,
+            ))
             .await
-            .expect("text structure response");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        assert_eq!(payload["title"], preview_payload.title);
-        assert_eq!(payload["summary"], preview_payload.summary);
-        assert_eq!(
-            payload["sections"].as_array().unwrap()[0]["heading"],
-            preview_payload.sections[0].heading
-        );
-        assert_eq!(payload["source"], "file");
-        assert!(payload["updated_at"].as_str().is_some());
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/api/mock/text_structure/Bad%20Name",
+                Some(fixture("x")),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -1858,7 +6456,7 @@ api_base: {}
 
     #[tokio::test]
     #[serial]
-    async fn structured_text_preview_can_be_updated_via_post() {
+    async fn journal_api_lists_days_and_entries() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
@@ -1874,6 +6472,12 @@ api_base: {}
         )
         .expect("agent config");
         fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        fs::create_dir_all(root.join("data/journals/2025/01/01")).expect("journal dir");
+        fs::write(
+            root.join("data/journals/2025/01/01/run.md"),
+            "## 10:00:00 — Review inbox\n\nFinal answer: all clear\n",
+        )
+        .expect("journal entry");
 
         unsafe {
             std::env::set_var("HI_APP_ROOT", root);
@@ -1882,274 +6486,143 @@ api_base: {}
 
         let config = AppConfig::load().expect("load config");
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
-        let data_dir = config.data_dir.clone();
         let ctx = AppContext::new(config, Arc::new(agent));
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
         let state = ServerState::new(ctx.clone(), handle);
         let app = super::router(state.clone());
 
-        let desired = StructuredContent {
-            title: "Custom Title".to_string(),
-            summary: "Custom summary".to_string(),
-            sections: vec![StructuredSection {
-                heading: "Custom heading".to_string(),
-                body: vec!["Line".to_string()],
-                children: vec![],
-            }],
-        };
-        let initial_note = "Initial skeleton";
-
-        let response = app
-            .clone()
-            .oneshot(
+        let get = |uri: &str| {
+            app.clone().oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri("/api/mock/text_structure")
-                    .header("content-type", "application/json")
-                    .body(Body::from(
-                        serde_json::to_vec(&serde_json::json!({
-                            "content": desired.clone(),
-                            "note": initial_note,
-                        }))
-                        .unwrap(),
-                    ))
+                    .uri(uri.to_string())
+                    .body(Body::empty())
                     .unwrap(),
             )
-            .await
-            .expect("post response");
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        };
 
-        let stored = tokio::fs::read_to_string(data_dir.join("mock/text_structure.json"))
-            .await
-            .expect("read stored");
-        let stored: serde_json::Value = serde_json::from_str(&stored).expect("parse stored");
-        assert_eq!(stored["content"], serde_json::to_value(&desired).unwrap());
+        let response = get("/api/journals").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let calendar: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(calendar["days"][0]["date"], "2025-01-01");
+        assert_eq!(calendar["days"][0]["entries"], 1);
         assert_eq!(
-            stored["note"],
-            serde_json::Value::String(initial_note.to_string())
+            calendar["days"][0]["headings"][0],
+            "10:00:00 — Review inbox"
         );
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("get response");
-        assert_eq!(response.status(), StatusCode::OK);
+        let response = get("/api/journals?from=2025-01-02").await.unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let fetched: TextStructurePreviewResponse =
-            serde_json::from_slice(&body).expect("parse fetched");
-        assert_eq!(fetched.content, desired);
-        assert_eq!(fetched.source, TextStructurePreviewSource::File);
-        assert_eq!(fetched.note.as_deref(), Some(initial_note));
-        assert!(fetched.updated_at.is_some());
+        let calendar: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(calendar["days"].as_array().unwrap().len(), 0);
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure/history?limit=1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("history response");
+        let response = get("/api/journals/2025-01-01").await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let history: TextStructureHistoryResponse =
-            serde_json::from_slice(&body).expect("parse history");
-        assert_eq!(history.entries.len(), 1);
-        assert_eq!(history.entries[0].content, desired);
-        assert_eq!(history.entries[0].note.as_deref(), Some(initial_note));
-        let history_id = history.entries[0].id.clone();
+        let day: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(day["entries"][0]["title"], "Review inbox");
+        assert_eq!(day["entries"][0]["time"], "10:00:00");
+        assert_eq!(day["entries"][0]["final_answer"], "all clear");
+        assert_eq!(day["entries"][0]["path"], "journals/2025/01/01/run.md");
 
-        let history_entry_uri = format!("/api/mock/text_structure/history/{history_id}");
-
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri(history_entry_uri.clone())
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("history entry response");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let entry: StructuredTextHistoryEntry =
-            serde_json::from_slice(&body).expect("parse history entry");
-        assert_eq!(entry.id, history_id);
-        assert_eq!(entry.content, desired);
-        assert_eq!(entry.note.as_deref(), Some(initial_note));
+        let response = get("/api/journals/2025-01-02").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = get("/api/journals/yesterday").await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-        let updated = StructuredContent {
-            title: "Updated".to_string(),
-            summary: "Updated summary".to_string(),
-            sections: vec![StructuredSection {
-                heading: "Updated heading".to_string(),
-                body: vec!["Updated".to_string()],
-                children: vec![],
-            }],
-        };
-        let updated_note = "Revision 2";
+        ctx.request_shutdown();
+        let _ = join.await;
 
-        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/api/mock/text_structure")
-                    .header("content-type", "application/json")
-                    .body(Body::from(
-                        serde_json::to_vec(&serde_json::json!({
-                            "content": updated.clone(),
-                            "note": updated_note,
-                        }))
-                        .unwrap(),
-                    ))
-                    .unwrap(),
-            )
-            .await
-            .expect("post response");
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    #[tokio::test]
+    #[serial]
+    async fn failures_api_groups_quarantined_intents() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("get response after update");
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let fetched: TextStructurePreviewResponse =
-            serde_json::from_slice(&body).expect("parse fetched");
-        assert_eq!(fetched.content, updated);
-        assert_eq!(fetched.note.as_deref(), Some(updated_note));
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(
+            root.join("config/beat.yml"),
+            "interval_minutes: 10\nintent_threshold: 0.5\n",
+        )
+        .expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+        let failed_dir = root.join("data/intent/queue/failed");
+        fs::create_dir_all(&failed_dir).expect("failed dir");
+        let run_id = Uuid::new_v4();
+        fs::write(
+            failed_dir.join("timeout.md"),
+            format!(
+                "---\nsummary: Slow provider\nmetadata:\n  failure_class: provider_timeout\n  failure_error: request timed out\n  failed_at: 2025-01-02T08:00:00Z\n  failed_runs: {run_id}\n---\n"
+            ),
+        )
+        .expect("failed intent");
+        fs::write(
+            failed_dir.join("legacy.md"),
+            "---\nsummary: Old failure\n---\n",
+        )
+        .expect("legacy failed intent");
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure/history?limit=5")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("history list response");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let expanded: TextStructureHistoryResponse =
-            serde_json::from_slice(&body).expect("parse expanded history");
-        assert!(expanded.entries.len() >= 2);
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
 
-        let older = expanded.entries[1].saved_at + chrono::Duration::milliseconds(1);
-        let since_uri = format!(
-            "/api/mock/text_structure/history?since={}",
-            older.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
-        );
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri(since_uri)
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("history since response");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let filtered_since: TextStructureHistoryResponse =
-            serde_json::from_slice(&body).expect("parse since history");
-        assert_eq!(filtered_since.entries.len(), 1);
-        assert_eq!(
-            filtered_since.entries[0].note.as_deref(),
-            Some(updated_note)
-        );
+        let config = AppConfig::load().expect("load config");
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure/history?q=revision")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("history query response");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let filtered_query: TextStructureHistoryResponse =
-            serde_json::from_slice(&body).expect("parse query history");
-        assert_eq!(filtered_query.entries.len(), 1);
-        assert_eq!(
-            filtered_query.entries[0].note.as_deref(),
-            Some(updated_note)
-        );
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let state = ServerState::new(ctx.clone(), handle);
+        let app = super::router(state.clone());
 
-        let response = app
-            .clone()
-            .oneshot(
+        let get = |uri: &str| {
+            app.clone().oneshot(
                 Request::builder()
-                    .uri("/api/mock/text_structure/history?q=custom")
+                    .uri(uri.to_string())
                     .body(Body::empty())
                     .unwrap(),
             )
-            .await
-            .expect("history content query response");
+        };
+
+        let response = get("/api/failures").await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let filtered_content: TextStructureHistoryResponse =
-            serde_json::from_slice(&body).expect("parse content history");
-        assert_eq!(filtered_content.entries.len(), 1);
-        assert!(
-            filtered_content.entries[0]
-                .content
-                .summary
-                .contains("Custom summary")
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["total"], 2);
+        let timeout = report["classes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|class| class["class"] == "provider_timeout")
+            .expect("timeout class");
+        assert_eq!(timeout["count"], 1);
+        assert_eq!(timeout["per_day"][0]["date"], "2025-01-02");
+        assert_eq!(timeout["intents"][0]["summary"], "Slow provider");
+        assert_eq!(timeout["intents"][0]["error"], "request timed out");
+        assert_eq!(
+            timeout["intents"][0]["run_links"][0],
+            format!("/api/logs/llm?run_id={run_id}")
         );
 
-        let restore_uri = format!("/api/mock/text_structure/history/{history_id}/restore");
-
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri(restore_uri)
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("restore response");
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
-
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+        let response = get("/api/failures?since=2025-01-03T00:00:00Z")
             .await
-            .expect("get response after restore");
+            .unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let restored: TextStructurePreviewResponse =
-            serde_json::from_slice(&body).expect("parse restored");
-        assert_eq!(restored.content, desired);
-        assert_eq!(restored.note.as_deref(), Some(initial_note));
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["total"], 1);
+        assert_eq!(report["classes"][0]["class"], "other");
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -2162,14 +6635,14 @@ api_base: {}
 
     #[tokio::test]
     #[serial]
-    async fn structured_text_preview_can_be_reset_via_delete() {
+    async fn health_reports_overdue_beats() {
         let tmp = TempDir::new().expect("tempdir");
         let root = tmp.path();
 
         fs::create_dir_all(root.join("config")).expect("config dir");
         fs::write(
             root.join("config/beat.yml"),
-            "interval_minutes: 10\nintent_threshold: 0.5\n",
+            "interval_minutes: 10\nintent_threshold: 0.5\nwatchdog:\n  missed_beats: 2\n",
         )
         .expect("beat config");
         fs::write(
@@ -2186,127 +6659,239 @@ api_base: {}
 
         let config = AppConfig::load().expect("load config");
         let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
-        let data_dir = config.data_dir.clone();
-        let ctx = AppContext::new(config, Arc::new(agent));
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let ctx = AppContext::new(config, Arc::new(agent)).with_clock(clock.clone());
 
         let (handle, join) = orchestrator::spawn(ctx.clone());
         let state = ServerState::new(ctx.clone(), handle);
         let app = super::router(state.clone());
 
-        let desired = StructuredContent {
-            title: "Custom Title".to_string(),
-            summary: "Custom summary".to_string(),
-            sections: vec![StructuredSection {
-                heading: "Custom heading".to_string(),
-                body: vec!["Line".to_string()],
-                children: vec![],
-            }],
-        };
-
-        app.clone()
-            .oneshot(
+        let get = |uri: &str| {
+            app.clone().oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri("/api/mock/text_structure")
-                    .header("content-type", "application/json")
-                    .body(Body::from(serde_json::to_vec(&desired).unwrap()))
+                    .uri(uri.to_string())
+                    .body(Body::empty())
                     .unwrap(),
             )
-            .await
-            .expect("post response");
+        };
 
-        assert!(data_dir.join("mock/text_structure.json").exists());
+        let response = get("/healthz").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("delete response");
-        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        clock.advance(Duration::minutes(21));
+        let response = get("/healthz").await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = get("/api/status").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["beat"]["overdue"], true);
+        assert_eq!(status["beat"]["threshold_seconds"], 1200);
 
-        assert!(!data_dir.join("mock/text_structure.json").exists());
+        ctx.record_beat(ctx.now());
+        let response = get("/healthz").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get("/api/status").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["beat"]["overdue"], false);
+        assert_eq!(status["beat"]["seconds_since_beat"], 0);
+        assert!(status["beat"]["last_beat_at"].is_string());
 
-        let response = app
-            .clone()
-            .oneshot(
-                Request::builder()
-                    .uri("/api/mock/text_structure")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .expect("get response");
+        let response = get("/api/llm/status").await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let fetched: TextStructurePreviewResponse =
-            serde_json::from_slice(&body).expect("parse fetched");
-        assert_eq!(fetched.source, TextStructurePreviewSource::Inline);
-        assert!(fetched.note.is_none());
-        assert!(fetched.updated_at.is_none());
-        assert_eq!(
-            fetched.content.title,
-            StructuredContent::mock_payload().title
-        );
+        let llm: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(llm["provider"], "local_stub");
+        assert_eq!(llm["ok"], true);
+        assert!(llm["model_available"].is_null());
 
-        let response = app
+        let response = get("/api/experiments").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let experiments: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(experiments["prompt_version"].as_str().unwrap().len(), 12);
+        assert!(experiments["active"].is_null());
+        assert_eq!(experiments["experiments"], serde_json::json!([]));
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn llm_log_stream_sends_entries_as_they_complete() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let (handle, join) = orchestrator::spawn(ctx.clone());
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+
+        let stream = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/ui/messages")
+                    .uri("/api/logs/llm/stream?level=final")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
-            .expect("messages page");
-        assert_eq!(response.status(), StatusCode::OK);
-        let content_type = response
+            .expect("stream response");
+        assert_eq!(stream.status(), StatusCode::OK);
+        let stream_type = stream
             .headers()
             .get(axum::http::header::CONTENT_TYPE)
             .and_then(|value| value.to_str().ok())
             .unwrap_or_default();
-        assert!(content_type.starts_with("text/html"));
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let html = String::from_utf8(body.to_vec()).unwrap();
-        assert!(html.contains("消息面板"));
+        assert!(stream_type.starts_with("text/event-stream"));
+        let mut stream = stream.into_body();
 
-        let response = app
+        let created = app
             .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/ui/logs")
-                    .body(Body::empty())
+                    .method("POST")
+                    .uri("/api/intents")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "summary": "Stream my logs",
+                            "telos_alignment": 0.9,
+                        }))
+                        .unwrap(),
+                    ))
                     .unwrap(),
             )
             .await
-            .expect("logs page");
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let html = String::from_utf8(body.to_vec()).unwrap();
-        assert!(html.contains("日志面板"));
+            .expect("create intent");
+        assert!(created.status().is_success());
 
-        let response = app
-            .clone()
-            .oneshot(
+        let mut events = String::new();
+        while !events.contains("event: llm_log") {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), stream.frame())
+                .await
+                .expect("llm log event")
+                .expect("stream open")
+                .unwrap();
+            if let Ok(data) = frame.into_data() {
+                events.push_str(&String::from_utf8_lossy(&data));
+            }
+        }
+        assert!(events.contains("\"phase\":\"FINAL\""));
+        assert!(!events.contains("\"phase\":\"THINK\""));
+
+        ctx.request_shutdown();
+        let _ = join.await;
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn server_role_shares_the_data_dir_with_a_worker() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        fs::create_dir_all(root.join("config")).expect("config dir");
+        fs::write(root.join("config/beat.yml"), "interval_minutes: 10\n").expect("beat config");
+        fs::write(
+            root.join("config/agent.yml"),
+            "max_react_steps: 1\npersona: TelosOps\n",
+        )
+        .expect("agent config");
+        fs::write(root.join("config/llm.yml"), "provider: local_stub\n").expect("llm config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+            std::env::set_var("HI_ROLE", "server");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        assert_eq!(config.server.role, crate::config::Role::Server);
+        assert_eq!(config.pid_file(), root.join("hi_telos-server.pid"));
+        let data_dir = config.data_dir.clone();
+        let queued = storage::persist_intent(
+            &data_dir,
+            &IntentDraft {
+                source: "user".to_string(),
+                summary: "Queued elsewhere".to_string(),
+                telos_alignment: 0.9,
+                ..IntentDraft::default()
+            },
+        )
+        .await
+        .unwrap();
+        storage::promote_to_queue(&queued.path, &data_dir, Utc::now()).unwrap();
+
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let handle = orchestrator::OrchestratorHandle::remote(&ctx);
+        let app = super::router(ServerState::new(ctx.clone(), handle));
+        let request = |method: &str, uri: &str| {
+            app.clone().oneshot(
                 Request::builder()
-                    .uri("/ui/messages/stream")
+                    .method(method)
+                    .uri(uri.to_string())
                     .body(Body::empty())
                     .unwrap(),
             )
+        };
+
+        // The queue lives in the worker; the server works on the file.
+        let cancelled = request("POST", &format!("/api/intents/{}/cancel", queued.id))
             .await
-            .expect("messages stream");
-        assert_eq!(response.status(), StatusCode::OK);
-        let stream_type = response
-            .headers()
-            .get(axum::http::header::CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .unwrap_or_default();
-        assert!(stream_type.starts_with("text/event-stream"));
+            .unwrap();
+        assert_eq!(cancelled.status(), StatusCode::OK);
+        assert!(storage::scan_queue(&data_dir).unwrap().is_empty());
+
+        let beat = request("POST", "/api/beat").await.unwrap();
+        assert_eq!(beat.status(), StatusCode::OK);
+        assert!(data_dir.join(storage::BEAT_REQUEST_FILE).exists());
+
+        let restore = request("POST", "/api/admin/restore").await.unwrap();
+        assert_eq!(restore.status(), StatusCode::CONFLICT);
+        let llm_stream = request("GET", "/api/logs/llm/stream").await.unwrap();
+        assert_eq!(llm_stream.status(), StatusCode::CONFLICT);
+
+        storage::record_last_beat(&data_dir, ctx.now()).unwrap();
+        let response = request("GET", "/api/status").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status["role"], "server");
+        assert!(status["beat"]["last_beat_at"].is_string());
+        assert_eq!(status["beat"]["overdue"], false);
+
+        // A worker picks the request up from the data dir.
+        let (_, join) = orchestrator::spawn(ctx.clone());
+        for _ in 0..100 {
+            if !data_dir.join(storage::BEAT_REQUEST_FILE).exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert!(!data_dir.join(storage::BEAT_REQUEST_FILE).exists());
 
         ctx.request_shutdown();
         let _ = join.await;
@@ -2314,6 +6899,114 @@ api_base: {}
         unsafe {
             std::env::remove_var("HI_APP_ROOT");
             std::env::remove_var("HI_SERVER_BIND");
+            std::env::remove_var("HI_ROLE");
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn named_workspaces_are_served_under_their_prefix() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        for config_dir in [root.join("config"), root.join("workspaces/work/config")] {
+            fs::create_dir_all(&config_dir).expect("config dir");
+            fs::write(config_dir.join("beat.yml"), "interval_minutes: 10\n").expect("beat");
+            fs::write(
+                config_dir.join("agent.yml"),
+                "max_react_steps: 1\npersona: TelosOps\n",
+            )
+            .expect("agent config");
+            fs::write(config_dir.join("llm.yml"), "provider: local_stub\n").expect("llm");
+        }
+        fs::write(
+            root.join("config/workspaces.yml"),
+            "workspaces:\n  work:\n    root: workspaces/work\n",
+        )
+        .expect("workspaces config");
+
+        unsafe {
+            std::env::set_var("HI_APP_ROOT", root);
+            std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+            std::env::set_var("HI_BEAT__INTERVAL_MINUTES", "20");
+        }
+
+        let config = AppConfig::load().expect("load config");
+        let named = config.load_workspaces().expect("load workspaces");
+        assert_eq!(named.len(), 1);
+        let (name, work_config) = &named[0];
+        assert_eq!(name, "work");
+        assert_eq!(work_config.workspace.as_deref(), Some("work"));
+        assert_eq!(work_config.data_dir, root.join("workspaces/work/data"));
+        // Env overrides stay with the main workspace.
+        assert_eq!(config.beat.interval_minutes, 20);
+        assert_eq!(work_config.beat.interval_minutes, 10);
+
+        let mut states = Vec::new();
+        for (name, config) in named {
+            storage::ensure_data_layout(&config.data_dir).unwrap();
+            let draft = IntentDraft {
+                source: "user".to_string(),
+                summary: format!("Only in {name}"),
+                telos_alignment: 0.9,
+                ..IntentDraft::default()
+            };
+            storage::persist_intent(&config.data_dir, &draft)
+                .await
+                .unwrap();
+            let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+            let ctx = AppContext::new(config, Arc::new(agent));
+            let handle = orchestrator::OrchestratorHandle::remote(&ctx);
+            states.push((name, ServerState::new(ctx, handle)));
+        }
+        storage::ensure_data_layout(&config.data_dir).unwrap();
+        let agent = AgentRuntime::from_app_config(&config).expect("agent runtime");
+        let ctx = AppContext::new(config, Arc::new(agent));
+        let handle = orchestrator::OrchestratorHandle::remote(&ctx);
+        let app = super::router(ServerState::new(ctx, handle).with_workspaces(states));
+        let get_json = |uri: &str| {
+            let request = Request::builder()
+                .uri(uri.to_string())
+                .body(Body::empty())
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).ok(),
+                )
+            }
+        };
+
+        let (_, listed) = get_json("/api/workspaces").await;
+        assert_eq!(listed.unwrap()["workspaces"], serde_json::json!(["work"]));
+
+        let (status, intents) = get_json("/api/w/work/api/intents").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(intents.is_none());
+        let (status, intents) = get_json("/api/w/work/intents?stage=inbox").await;
+        assert_eq!(status, StatusCode::OK);
+        let intents = intents.unwrap();
+        let summaries: Vec<_> = intents["intents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|intent| intent["summary"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(summaries, vec!["Only in work".to_string()]);
+
+        let (_, intents) = get_json("/api/intents?stage=inbox").await;
+        assert!(intents.unwrap()["intents"].as_array().unwrap().is_empty());
+
+        let (status, _) = get_json("/api/w/play/intents").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        unsafe {
+            std::env::remove_var("HI_APP_ROOT");
+            std::env::remove_var("HI_SERVER_BIND");
+            std::env::remove_var("HI_BEAT__INTERVAL_MINUTES");
         }
     }
 }
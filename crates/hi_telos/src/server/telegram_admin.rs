@@ -0,0 +1,96 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+use crate::telegram;
+
+use super::ServerState;
+
+pub fn router() -> Router<ServerState> {
+    Router::new()
+        .route("/api/telegram/webhook/register", post(register_webhook))
+        .route("/api/telegram/webhook/delete", post(delete_webhook))
+        .route("/api/telegram/webhook/info", get(webhook_info))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegisterWebhookRequest {
+    /// Overrides `{public_url}/webhook/telegram`.
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DeleteWebhookRequest {
+    #[serde(default)]
+    drop_pending_updates: bool,
+}
+
+fn bot_api_error(action: &str, err: anyhow::Error) -> Response {
+    warn!(error = ?err, action, "telegram bot api call failed");
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(json!({ "error": format!("{err:#}") })),
+    )
+        .into_response()
+}
+
+async fn register_webhook(
+    State(state): State<ServerState>,
+    payload: Option<Json<RegisterWebhookRequest>>,
+) -> Response {
+    let Some(config) = state.ctx().config().telegram.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+    let Some(url) = request.url.or_else(|| telegram::webhook_url(&config)) else {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": "set public_url in telegram.yml or pass url" })),
+        )
+            .into_response();
+    };
+
+    match telegram::set_webhook(&config, &url).await {
+        Ok(result) => Json(json!({
+            "url": url,
+            "secret_token": config.webhook_secret.is_some(),
+            "result": result,
+        }))
+        .into_response(),
+        Err(err) => bot_api_error("setWebhook", err),
+    }
+}
+
+async fn delete_webhook(
+    State(state): State<ServerState>,
+    payload: Option<Json<DeleteWebhookRequest>>,
+) -> Response {
+    let Some(config) = state.ctx().config().telegram.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+
+    match telegram::delete_webhook(&config, request.drop_pending_updates).await {
+        Ok(result) => Json(json!({ "result": result })).into_response(),
+        Err(err) => bot_api_error("deleteWebhook", err),
+    }
+}
+
+async fn webhook_info(State(state): State<ServerState>) -> Response {
+    let Some(config) = state.ctx().config().telegram.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+
+    match telegram::get_webhook_info(&config).await {
+        Ok(info) => Json(info).into_response(),
+        Err(err) => bot_api_error("getWebhookInfo", err),
+    }
+}
@@ -1,29 +1,27 @@
-use std::{
-    convert::Infallible,
-    path::{Path, PathBuf},
-    time::Duration,
-};
+use std::{collections::BTreeMap, convert::Infallible, path::PathBuf, time::Duration};
 
 use anyhow::Context;
+use askama::Template;
 use axum::{
     Router,
-    extract::State,
+    extract::{Query, State},
     response::sse::{Event, KeepAlive, Sse},
     response::{Html, IntoResponse},
     routing::get,
 };
-use chrono::Local;
-use serde::Serialize;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
 use tokio::task;
 use tokio_stream::{StreamExt, wrappers::IntervalStream};
 use tracing::warn;
 
 use crate::{
-    llm::LlmLogEntry,
+    config::UiConfig,
     storage::{
-        self, IntentRecord, LlmLogQuery, MemoryEntry, MemoryLevel, MemoryQuery, MessageDirection,
-        MessageLogEntry, MessageLogQuery, SpIndex,
+        self, MemoryEntry, MemoryLevel, MemoryQuery, MessageDirection, MessageLogEntry,
+        MessageLogQuery, PendingIntent, SpIndex,
     },
+    text,
 };
 
 use super::{ServerState, acceptance};
@@ -32,242 +30,166 @@ pub fn router() -> Router<ServerState> {
     Router::new()
         .route("/ui/messages", get(ui_messages))
         .route("/ui/messages/stream", get(ui_messages_stream))
+        .route("/ui/intents", get(ui_intents))
+        .route("/ui/intents/stream", get(ui_intents_stream))
+        .route("/ui/chat", get(ui_chat))
         .route("/ui/md", get(ui_markdown))
         .route("/ui/md/stream", get(ui_markdown_stream))
         .route("/ui/logs", get(ui_logs))
         .route("/ui/logs/stream", get(ui_logs_stream))
 }
 
-async fn ui_messages() -> Html<String> {
-    let body = String::from(
-        r#"<section><h2>Inbox</h2><pre id="inbox">Loading…</pre></section>
-         <section><h2>Queue</h2><pre id="queue">Loading…</pre></section>
-         <section><h2>Archive</h2><pre id="history">Loading…</pre></section>
-         <section><h2>Telegram Inbound</h2><pre id="telegram-in">Loading…</pre></section>
-         <section><h2>Telegram Outbound</h2><pre id="telegram-out">Loading…</pre></section>"#,
-    );
-
-    let script = r#"
-(function() {
-  const status = document.getElementById('status');
-  function updateStatus(text) {
-    if (status) {
-      status.textContent = text;
-    }
-  }
+/// Links in the page header, in display order.
+const NAV: [(&str, &str); 5] = [
+    ("/ui/messages", "Messages"),
+    ("/ui/intents", "Intents"),
+    ("/ui/chat", "Chat"),
+    ("/ui/md", "Markdown"),
+    ("/ui/logs", "Logs"),
+];
+
+struct NavLink {
+    href: &'static str,
+    label: &'static str,
+    active: bool,
+}
 
-  function renderLines(id, lines) {
-    const target = document.getElementById(id);
-    if (!target) {
-      return;
-    }
-    if (!lines || lines.length === 0) {
-      target.textContent = '—';
-      return;
-    }
-    target.textContent = lines.join('\n');
-  }
-
-  updateStatus('连接中 …');
-  const source = new EventSource('/ui/messages/stream');
-  source.onopen = function() {
-    updateStatus('已连接');
-  };
-  source.onerror = function() {
-    updateStatus('连接断开，等待重试 …');
-  };
-  source.onmessage = function(event) {
-    updateStatus('已连接');
-    try {
-      const payload = JSON.parse(event.data);
-      renderLines('inbox', payload.inbox || []);
-      renderLines('queue', payload.queue || []);
-      renderLines('history', payload.history || []);
-      renderLines('telegram-in', payload.telegram_in || []);
-      renderLines('telegram-out', payload.telegram_out || []);
-    } catch (err) {
-      updateStatus('数据解析失败');
+/// What the shared page header needs: navigation, the default theme and,
+/// for pages fed by a polled stream, its refresh rate.
+struct Chrome {
+    nav: Vec<NavLink>,
+    theme: &'static str,
+    refresh_secs: Option<u64>,
+}
+
+fn chrome(current: &str, ui: &UiConfig, refresh_secs: Option<u64>) -> Chrome {
+    Chrome {
+        nav: NAV
+            .iter()
+            .map(|&(href, label)| NavLink {
+                href,
+                label,
+                active: href == current,
+            })
+            .collect(),
+        theme: ui.theme.as_str(),
+        refresh_secs,
     }
-  };
-})();
-"#;
-
-    render_page(
-        "HI Telos — Messages",
-        "消息面板",
-        "/ui/messages",
-        &body,
-        script,
-    )
 }
 
-async fn ui_markdown() -> Html<String> {
-    let body = String::from(
-        r#"<section><h2>Markdown Tree</h2><ul id="file-list" class="tree"><li>Loading…</li></ul></section>
-         <section><h2>验收概览</h2><pre id="acceptance">Loading…</pre></section>
-         <section><h2>Viewer</h2><div id="file-viewer" class="viewer"><em>选择左侧 Markdown 查看内容</em></div></section>"#,
-    );
+#[derive(Template)]
+#[template(path = "messages.html")]
+struct MessagesPage {
+    chrome: Chrome,
+}
 
-    let script = r#"
-(function() {
-  const status = document.getElementById('status');
-  function updateStatus(text) {
-    if (status) {
-      status.textContent = text;
-    }
-  }
+#[derive(Template)]
+#[template(path = "intents.html")]
+struct IntentsPage {
+    chrome: Chrome,
+}
 
-  function clearChildren(node) {
-    while (node.firstChild) {
-      node.removeChild(node.firstChild);
-    }
-  }
+#[derive(Template)]
+#[template(path = "chat.html")]
+struct ChatPage {
+    chrome: Chrome,
+}
 
-  function renderAcceptance(lines) {
-    const block = document.getElementById('acceptance');
-    if (!block) {
-      return;
-    }
-    if (!lines || lines.length === 0) {
-      block.textContent = '暂无数据';
-      return;
-    }
-    block.textContent = lines.join('\n');
-  }
+#[derive(Template)]
+#[template(path = "markdown.html")]
+struct MarkdownPage {
+    chrome: Chrome,
+}
 
-  function renderFiles(files) {
-    const list = document.getElementById('file-list');
-    if (!list) {
-      return;
-    }
-    clearChildren(list);
-    if (!files || files.length === 0) {
-      const item = document.createElement('li');
-      item.textContent = '暂无 Markdown 文件';
-      list.appendChild(item);
-      return;
-    }
+#[derive(Template)]
+#[template(path = "logs.html")]
+struct LogsPage {
+    chrome: Chrome,
+}
 
-    files.forEach(function(path) {
-      const item = document.createElement('li');
-      const button = document.createElement('button');
-      button.textContent = path;
-      button.type = 'button';
-      button.onclick = function() {
-        loadFile(path);
-      };
-      item.appendChild(button);
-      list.appendChild(item);
-    });
-  }
-
-  function loadFile(path) {
-    const viewer = document.getElementById('file-viewer');
-    if (!viewer) {
-      return;
-    }
-    viewer.innerHTML = '<em>载入中…</em>';
-    fetch('/api/md/file?path=' + encodeURIComponent(path) + '&render=true')
-      .then(function(response) {
-        if (!response.ok) {
-          throw new Error('HTTP ' + response.status);
-        }
-        return response.text();
-      })
-      .then(function(html) {
-        viewer.innerHTML = html;
-      })
-      .catch(function(err) {
-        viewer.textContent = '读取失败：' + err;
-      });
-  }
-
-  updateStatus('连接中 …');
-  const source = new EventSource('/ui/md/stream');
-  source.onopen = function() {
-    updateStatus('已连接');
-  };
-  source.onerror = function() {
-    updateStatus('连接断开，等待重试 …');
-  };
-  source.onmessage = function(event) {
-    updateStatus('已连接');
-    try {
-      const payload = JSON.parse(event.data);
-      renderFiles(payload.files || []);
-      renderAcceptance(payload.acceptance || []);
-    } catch (err) {
-      updateStatus('数据解析失败');
-    }
-  };
-})();
-"#;
-
-    render_page(
-        "HI Telos — Markdown",
-        "Markdown 面板",
-        "/ui/md",
-        &body,
-        script,
-    )
+#[derive(Template)]
+#[template(path = "markdown_document.html")]
+struct MarkdownDocumentPage<'a> {
+    chrome: Chrome,
+    path: &'a str,
+    html: &'a str,
 }
 
-async fn ui_logs() -> Html<String> {
-    let body = String::from(
-        r#"<section><h2>LLM Logs</h2><pre id="logs">Loading…</pre></section>
-         <section><h2>SP Index</h2><pre id="sp">Loading…</pre></section>
-         <section><h2>Memory Rollup</h2><pre id="memory">Loading…</pre></section>"#,
-    );
+async fn ui_messages(State(state): State<ServerState>) -> Html<String> {
+    messages_page(&state.ctx().config().ui)
+}
 
-    let script = r#"
-(function() {
-  const status = document.getElementById('status');
-  function updateStatus(text) {
-    if (status) {
-      status.textContent = text;
-    }
-  }
+fn messages_page(ui: &UiConfig) -> Html<String> {
+    render_template(&MessagesPage {
+        chrome: chrome("/ui/messages", ui, Some(ui.refresh_secs.messages)),
+    })
+}
 
-  function renderLines(id, lines) {
-    const target = document.getElementById(id);
-    if (!target) {
-      return;
-    }
-    if (!lines || lines.length === 0) {
-      target.textContent = '—';
-      return;
-    }
-    target.textContent = lines.join('\n\n');
-  }
-
-  updateStatus('连接中 …');
-  const source = new EventSource('/ui/logs/stream');
-  source.onopen = function() {
-    updateStatus('已连接');
-  };
-  source.onerror = function() {
-    updateStatus('连接断开，等待重试 …');
-  };
-  source.onmessage = function(event) {
-    updateStatus('已连接');
-    try {
-      const payload = JSON.parse(event.data);
-      renderLines('logs', payload.logs || []);
-      renderLines('sp', payload.sp || []);
-      renderLines('memory', payload.memory || []);
-    } catch (err) {
-      updateStatus('数据解析失败');
-    }
-  };
-})();
-"#;
+async fn ui_intents(State(state): State<ServerState>) -> Html<String> {
+    intents_page(&state.ctx().config().ui)
+}
+
+fn intents_page(ui: &UiConfig) -> Html<String> {
+    render_template(&IntentsPage {
+        chrome: chrome("/ui/intents", ui, Some(ui.refresh_secs.intents)),
+    })
+}
+
+async fn ui_chat(State(state): State<ServerState>) -> Html<String> {
+    chat_page(&state.ctx().config().ui)
+}
+
+fn chat_page(ui: &UiConfig) -> Html<String> {
+    render_template(&ChatPage {
+        chrome: chrome("/ui/chat", ui, None),
+    })
+}
+
+async fn ui_markdown(State(state): State<ServerState>) -> Html<String> {
+    markdown_page(&state.ctx().config().ui)
+}
+
+fn markdown_page(ui: &UiConfig) -> Html<String> {
+    render_template(&MarkdownPage {
+        chrome: chrome("/ui/md", ui, Some(ui.refresh_secs.markdown)),
+    })
+}
+
+async fn ui_logs(State(state): State<ServerState>) -> Html<String> {
+    logs_page(&state.ctx().config().ui)
+}
+
+fn logs_page(ui: &UiConfig) -> Html<String> {
+    render_template(&LogsPage {
+        chrome: chrome("/ui/logs", ui, Some(ui.refresh_secs.logs)),
+    })
+}
 
-    render_page("HI Telos — Logs", "日志面板", "/ui/logs", &body, script)
+#[derive(Debug, Deserialize)]
+struct RefreshQuery {
+    /// Seconds between payloads, overriding `config/ui.yml`.
+    #[serde(default)]
+    refresh: Option<u64>,
 }
 
-async fn ui_messages_stream(State(state): State<ServerState>) -> impl IntoResponse {
-    let mut interval = tokio::time::interval(Duration::from_secs(3));
+/// Fastest and slowest refresh a client may ask for.
+const REFRESH_SECS_RANGE: (u64, u64) = (1, 3600);
+
+fn refresh_interval(requested: Option<u64>, configured: u64) -> tokio::time::Interval {
+    let secs = requested
+        .unwrap_or(configured)
+        .clamp(REFRESH_SECS_RANGE.0, REFRESH_SECS_RANGE.1);
+    let mut interval = tokio::time::interval(Duration::from_secs(secs));
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval
+}
+
+async fn ui_messages_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<RefreshQuery>,
+) -> impl IntoResponse {
+    let configured = state.ctx().config().ui.refresh_secs.messages;
+    let interval = refresh_interval(params.refresh, configured);
 
     let stream = IntervalStream::new(interval)
         .map(move |_| state.clone())
@@ -282,9 +204,32 @@ async fn ui_messages_stream(State(state): State<ServerState>) -> impl IntoRespon
         .into_response()
 }
 
-async fn ui_markdown_stream(State(state): State<ServerState>) -> impl IntoResponse {
-    let mut interval = tokio::time::interval(Duration::from_secs(5));
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+async fn ui_intents_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<RefreshQuery>,
+) -> impl IntoResponse {
+    let configured = state.ctx().config().ui.refresh_secs.intents;
+    let interval = refresh_interval(params.refresh, configured);
+
+    let stream = IntervalStream::new(interval)
+        .map(move |_| state.clone())
+        .then(|state| async move { to_event(build_intents_payload(&state).await, "intents") });
+
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_secs(15))
+                .text(": keep-alive"),
+        )
+        .into_response()
+}
+
+async fn ui_markdown_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<RefreshQuery>,
+) -> impl IntoResponse {
+    let configured = state.ctx().config().ui.refresh_secs.markdown;
+    let interval = refresh_interval(params.refresh, configured);
 
     let stream = IntervalStream::new(interval)
         .map(move |_| state.clone())
@@ -299,9 +244,12 @@ async fn ui_markdown_stream(State(state): State<ServerState>) -> impl IntoRespon
         .into_response()
 }
 
-async fn ui_logs_stream(State(state): State<ServerState>) -> impl IntoResponse {
-    let mut interval = tokio::time::interval(Duration::from_secs(4));
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+async fn ui_logs_stream(
+    State(state): State<ServerState>,
+    Query(params): Query<RefreshQuery>,
+) -> impl IntoResponse {
+    let configured = state.ctx().config().ui.refresh_secs.logs;
+    let interval = refresh_interval(params.refresh, configured);
 
     let stream = IntervalStream::new(interval)
         .map(move |_| state.clone())
@@ -316,122 +264,25 @@ async fn ui_logs_stream(State(state): State<ServerState>) -> impl IntoResponse {
         .into_response()
 }
 
-fn render_page(
-    title: &str,
-    heading: &str,
-    current: &str,
-    body: &str,
-    script: &str,
-) -> Html<String> {
-    let nav = format!(
-        "{} | {} | {}",
-        nav_link("/ui/messages", current, "Messages"),
-        nav_link("/ui/md", current, "Markdown"),
-        nav_link("/ui/logs", current, "Logs"),
-    );
-
-    let html = format!(
-        r#"<!DOCTYPE html>
-<html lang="zh-CN">
-<head>
-<meta charset="utf-8" />
-<title>{title}</title>
-<style>
-body {{
-  font-family: 'Courier New', monospace;
-  background: #101010;
-  color: #00ff90;
-  margin: 0;
-}}
-a {{
-  color: #00d0ff;
-  text-decoration: none;
-}}
-a.active {{
-  text-decoration: underline;
-}}
-header {{
-  border-bottom: 1px solid #00ff90;
-  padding: 1rem;
-}}
-header h1 {{
-  margin: 0 0 0.5rem 0;
-}}
-header p {{
-  margin: 0;
-}}
-main {{
-  padding: 1rem;
-  display: grid;
-  gap: 1rem;
-}}
-section {{
-  border: 1px solid #00ff90;
-  padding: 1rem;
-  background: #050505;
-}}
-pre {{
-  white-space: pre-wrap;
-  word-break: break-word;
-  margin: 0;
-}}
-ul.tree {{
-  list-style: none;
-  padding: 0;
-  margin: 0;
-}}
-ul.tree li {{
-  margin: 0.25rem 0;
-}}
-ul.tree button {{
-  font-family: 'Courier New', monospace;
-  background: #050505;
-  color: #00ff90;
-  border: 1px solid #00ff90;
-  padding: 0.25rem 0.5rem;
-  cursor: pointer;
-}}
-ul.tree button:hover {{
-  background: #00ff90;
-  color: #050505;
-}}
-.viewer {{
-  min-height: 240px;
-  border: 1px dashed #00ff90;
-  padding: 0.5rem;
-  background: #000;
-  color: #e0ffe0;
-}}
-</style>
-</head>
-<body>
-<header>
-  <h1>{heading}</h1>
-  <nav>{nav}</nav>
-  <p id="status">连接中 …</p>
-</header>
-<main>{body}</main>
-<script>
-{script}
-</script>
-</body>
-</html>
-"#,
-        title = title,
-        heading = heading,
-        nav = nav,
-        body = body,
-        script = script,
-    );
-
-    Html(html)
-}
-
-fn nav_link(href: &str, current: &str, label: &str) -> String {
-    if href == current {
-        format!("<a href=\"{}\" class=\"active\">{}</a>", href, label)
-    } else {
-        format!("<a href=\"{}\">{}</a>", href, label)
+/// A rendered (already sanitized) markdown document in the page shell, for
+/// `/api/md/file?render=true&page=true`.
+pub(super) fn render_markdown_page(ui: &UiConfig, path: &str, html: &str) -> Html<String> {
+    render_template(&MarkdownDocumentPage {
+        chrome: chrome("/ui/md", ui, None),
+        path,
+        html,
+    })
+}
+
+/// Templates are checked at compile time, so rendering only fails on a
+/// formatter error; the page degrades to a plain message rather than a 500.
+fn render_template(template: &impl Template) -> Html<String> {
+    match template.render() {
+        Ok(html) => Html(html),
+        Err(err) => {
+            warn!(error = ?err, "failed to render UI template");
+            Html("<p>页面渲染失败</p>".to_string())
+        }
     }
 }
 
@@ -456,13 +307,16 @@ where
 
 #[derive(Debug, Serialize)]
 struct UiMessagesPayload {
-    inbox: Vec<String>,
-    queue: Vec<String>,
-    history: Vec<String>,
     telegram_in: Vec<String>,
     telegram_out: Vec<String>,
 }
 
+/// Intents per stage, newest first, for the `/ui/intents` action lists.
+#[derive(Debug, Serialize)]
+struct UiIntentsPayload {
+    stages: BTreeMap<&'static str, Vec<PendingIntent>>,
+}
+
 #[derive(Debug, Serialize)]
 struct UiMarkdownPayload {
     files: Vec<String>,
@@ -471,7 +325,6 @@ struct UiMarkdownPayload {
 
 #[derive(Debug, Serialize)]
 struct UiLogsPayload {
-    logs: Vec<String>,
     sp: Vec<String>,
     memory: Vec<String>,
 }
@@ -479,30 +332,6 @@ struct UiLogsPayload {
 async fn build_messages_payload(state: &ServerState) -> anyhow::Result<UiMessagesPayload> {
     let data_dir = state.ctx().config().data_dir.clone();
 
-    let inbox = spawn_scan(data_dir.clone(), storage::scan_inbox)
-        .await?
-        .into_iter()
-        .rev()
-        .take(12)
-        .map(format_intent_line)
-        .collect();
-
-    let queue = spawn_scan(data_dir.clone(), storage::scan_queue)
-        .await?
-        .into_iter()
-        .rev()
-        .take(12)
-        .map(format_intent_line)
-        .collect();
-
-    let history = spawn_scan(data_dir.clone(), storage::scan_history)
-        .await?
-        .into_iter()
-        .rev()
-        .take(20)
-        .map(format_intent_line)
-        .collect();
-
     let telegram_in = spawn_messages(
         data_dir.clone(),
         MessageLogQuery {
@@ -532,21 +361,28 @@ async fn build_messages_payload(state: &ServerState) -> anyhow::Result<UiMessage
     .collect();
 
     Ok(UiMessagesPayload {
-        inbox,
-        queue,
-        history,
         telegram_in,
         telegram_out,
     })
 }
 
-async fn spawn_scan<F>(data_dir: PathBuf, op: F) -> anyhow::Result<Vec<IntentRecord>>
-where
-    F: Fn(&Path) -> anyhow::Result<Vec<IntentRecord>> + Send + 'static,
-{
-    task::spawn_blocking(move || op(&data_dir))
-        .await
-        .context("scan intents join failure")?
+async fn build_intents_payload(state: &ServerState) -> anyhow::Result<UiIntentsPayload> {
+    let data_dir = state.ctx().config().data_dir.clone();
+    let now = state.ctx().now();
+    task::spawn_blocking(move || {
+        let mut stages = BTreeMap::new();
+        for (stage, _) in storage::INTENT_STAGES {
+            let mut intents =
+                storage::list_stage_intents(&data_dir, stage, now)?.unwrap_or_default();
+            intents.reverse();
+            let limit = if stage == "history" { 20 } else { 50 };
+            intents.truncate(limit);
+            stages.insert(stage, intents);
+        }
+        Ok(UiIntentsPayload { stages })
+    })
+    .await
+    .context("scan intents join failure")?
 }
 
 async fn spawn_messages(
@@ -558,17 +394,6 @@ async fn spawn_messages(
         .context("scan messages join failure")?
 }
 
-fn format_intent_line(record: IntentRecord) -> String {
-    let intent = record.intent;
-    format!(
-        "{} | {} | {:.2} | {}",
-        intent.created_at.format("%Y-%m-%d %H:%M:%S"),
-        intent.source,
-        intent.telos_alignment,
-        intent.summary,
-    )
-}
-
 fn format_message_line(entry: MessageLogEntry) -> String {
     let stamp = entry
         .timestamp
@@ -579,11 +404,7 @@ fn format_message_line(entry: MessageLogEntry) -> String {
         MessageDirection::Outbound => "OUT",
     };
     let author = entry.author.clone().unwrap_or_else(|| entry.source.clone());
-    let mut text = entry.text.replace('\n', " ");
-    if text.len() > 160 {
-        text.truncate(157);
-        text.push('…');
-    }
+    let text = text::truncate(&entry.text.replace('\n', " "), 160);
     format!(
         "{} [{}] {} #{} | {}",
         stamp, direction, author, entry.chat_id, text
@@ -595,11 +416,7 @@ fn format_memory_entry(entry: MemoryEntry) -> String {
         .updated_at
         .with_timezone(&Local)
         .format("%Y-%m-%d %H:%M:%S");
-    let mut headline = entry.summary.replace('\n', " ");
-    if headline.len() > 120 {
-        headline.truncate(117);
-        headline.push('…');
-    }
+    let headline = text::truncate(&entry.summary.replace('\n', " "), 120);
     let details: Vec<String> = entry
         .details
         .iter()
@@ -624,27 +441,27 @@ fn format_memory_entry(entry: MemoryEntry) -> String {
 }
 
 async fn build_markdown_payload(state: &ServerState) -> anyhow::Result<UiMarkdownPayload> {
-    let (data_dir, config_dir) = {
-        let config = state.ctx().config();
-        (config.data_dir.clone(), config.config_dir.clone())
-    };
-
-    let files = task::spawn_blocking(move || storage::list_markdown_tree(&data_dir))
-        .await
-        .context("scan markdown join failure")??;
+    let config = state.ctx().config();
+    let docs = config
+        .config_dir
+        .parent()
+        .map(|root| acceptance::resolve_plan_docs(root, &config.acceptance.docs))
+        .unwrap_or_default();
+    drop(config);
+    let files = state.markdown_tree().await?.files.to_vec();
 
-    let acceptance = acceptance_summary_lines(config_dir)
+    let acceptance = acceptance_summary_lines(state, &docs)
         .await
         .unwrap_or_default();
 
     Ok(UiMarkdownPayload { files, acceptance })
 }
 
-async fn acceptance_summary_lines(config_dir: PathBuf) -> Option<Vec<String>> {
-    let root = config_dir.parent()?;
-    let doc_path = root.join("docs/work_acceptance_plan.md");
-    let summary = acceptance::load_acceptance_summary(&doc_path).await.ok()?;
-    let metrics = summary.metrics;
+async fn acceptance_summary_lines(state: &ServerState, docs: &[PathBuf]) -> Option<Vec<String>> {
+    let overview = state.acceptance_overview(docs).await.ok()?;
+    let documents = overview.documents.len();
+    let summary = &overview.summary;
+    let metrics = &summary.metrics;
 
     let status = match metrics.overall_status {
         acceptance::AcceptanceOverallStatus::Complete => "完成",
@@ -664,6 +481,9 @@ async fn acceptance_summary_lines(config_dir: PathBuf) -> Option<Vec<String>> {
         format!("整体状态：{}", status),
     ];
 
+    if documents > 1 {
+        lines.push(format!("计划文档：{documents} 份"));
+    }
     if let Some(updated) = summary.source.updated_at {
         lines.push(format!("最近更新：{}", updated.format("%Y-%m-%d %H:%M:%S")));
     }
@@ -674,19 +494,9 @@ async fn acceptance_summary_lines(config_dir: PathBuf) -> Option<Vec<String>> {
 async fn build_logs_payload(state: &ServerState) -> anyhow::Result<UiLogsPayload> {
     let data_dir = state.ctx().config().data_dir.clone();
 
-    let logs = storage::read_llm_logs(
-        &data_dir,
-        LlmLogQuery {
-            limit: 20,
-            ..Default::default()
-        },
-    )
-    .await?
-    .into_iter()
-    .map(format_log_entry)
-    .collect();
-
-    let sp_lines = sp_summary_lines(&data_dir).await.unwrap_or_default();
+    let sp_lines = sp_summary_lines(state, state.ctx().now())
+        .await
+        .unwrap_or_default();
 
     let memory_lines = task::spawn_blocking({
         let data_dir = data_dir.clone();
@@ -698,6 +508,7 @@ async fn build_logs_payload(state: &ServerState) -> anyhow::Result<UiLogsPayload
                     limit: 6,
                     since: None,
                     tag: None,
+                    text: None,
                 },
             )
         }
@@ -709,47 +520,18 @@ async fn build_logs_payload(state: &ServerState) -> anyhow::Result<UiLogsPayload
     .collect();
 
     Ok(UiLogsPayload {
-        logs,
         sp: sp_lines,
         memory: memory_lines,
     })
 }
 
-fn format_log_entry(entry: LlmLogEntry) -> String {
-    let mut prompt = entry.prompt.replace('\n', " ");
-    if prompt.len() > 160 {
-        prompt.truncate(157);
-        prompt.push('…');
-    }
-    let mut response = entry.response.replace('\n', " ");
-    if response.len() > 160 {
-        response.truncate(157);
-        response.push('…');
-    }
-    format!(
-        "{} [{}] {}{}\n→ {}",
-        entry.timestamp.with_timezone(&Local).format("%H:%M:%S"),
-        entry.phase.to_uppercase(),
-        entry.provider,
-        entry
-            .model
-            .as_ref()
-            .map(|model| format!("/{}", model))
-            .unwrap_or_default(),
-        response_line(prompt, response),
-    )
-}
-
-fn response_line(prompt: String, response: String) -> String {
-    format!(" {}", prompt) + "\n   ↳ " + &response
-}
-
-async fn sp_summary_lines(data_dir: &Path) -> Option<Vec<String>> {
-    match storage::load_sp_index(data_dir).await {
-        Ok(SpIndex {
-            top_used,
-            most_recent,
-        }) => {
+async fn sp_summary_lines(state: &ServerState, now: DateTime<Utc>) -> Option<Vec<String>> {
+    match state.sp_entries().await {
+        Ok(entries) => {
+            let SpIndex {
+                top_used,
+                most_recent,
+            } = storage::summarize_sp_index(entries.to_vec(), now);
             let mut lines = Vec::new();
             if !top_used.is_empty() {
                 lines.push("Top Used:".to_string());
@@ -784,19 +566,65 @@ mod tests {
 
     #[tokio::test]
     async fn retro_pages_render_expected_shell() {
-        let Html(html) = ui_messages().await;
+        let ui = UiConfig::default();
+        let Html(html) = messages_page(&ui);
         assert!(html.contains("消息面板"));
         assert!(html.contains("/ui/messages/stream"));
         assert!(html.contains("telegram-in"));
         assert!(html.contains("telegram-out"));
-
-        let Html(html) = ui_markdown().await;
+        assert!(html.contains("data-theme=\"dark\""));
+        assert!(html.contains("data-refresh=\"3\""));
+        assert!(html.contains("id=\"stream-pause\""));
+        assert!(html.contains("/ui/assets/theme.js"));
+
+        let Html(html) = intents_page(&ui);
+        assert!(html.contains("/ui/intents/stream"));
+        assert!(html.contains("stage-pending_approval"));
+        assert!(html.contains("stage-failed"));
+        assert!(html.contains("/ui/assets/intents.js"));
+        assert!(html.contains("id=\"new-intent\""));
+
+        let Html(html) = chat_page(&ui);
+        assert!(html.contains("对话面板"));
+        assert!(html.contains("/api/chat/stream"));
+        assert!(html.contains("/ui/assets/chat.js"));
+        assert!(!html.contains("stream-pause"));
+
+        let Html(html) = markdown_page(&ui);
         assert!(html.contains("Markdown 面板"));
         assert!(html.contains("/ui/md/stream"));
 
-        let Html(html) = ui_logs().await;
+        let Html(html) = logs_page(&ui);
         assert!(html.contains("日志面板"));
         assert!(html.contains("/ui/logs/stream"));
+        assert!(html.contains("/api/logs/llm/stream"));
         assert!(html.contains("Memory Rollup"));
+        assert!(html.contains("id=\"log-filters\""));
+        assert!(html.contains("data-logs-api=\"/api/logs/llm\""));
+
+        let ui: UiConfig =
+            serde_yaml::from_str("theme: light\nrefresh_secs:\n  logs: 30\n").unwrap();
+        assert_eq!(ui.refresh_secs.messages, 3);
+        let Html(html) = logs_page(&ui);
+        assert!(html.contains("data-theme=\"light\""));
+        assert!(html.contains("data-refresh=\"30\""));
+    }
+
+    #[test]
+    fn message_lines_cut_multibyte_text_without_panicking() {
+        let entry = MessageLogEntry {
+            id: uuid::Uuid::new_v4(),
+            direction: MessageDirection::Inbound,
+            source: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            author: None,
+            text: "整理本周的意图与日志。".repeat(20),
+            timestamp: Utc::now(),
+            metadata: None,
+        };
+        let line = format_message_line(entry);
+        let text = line.split_once(" | ").unwrap().1;
+        assert!(text.ends_with('…'));
+        assert_eq!(text.chars().count(), 160);
     }
 }
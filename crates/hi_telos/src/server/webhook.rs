@@ -0,0 +1,327 @@
+use std::env;
+
+use async_trait::async_trait;
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::{AppConfig, InboundWebhookConfig},
+    github::{self, IssuesEvent},
+    sources::{IngestMode, IngestSource},
+    storage::{self, IntentDraft},
+    text,
+};
+
+use super::ServerState;
+
+const WEBHOOK_SUMMARY_MAX_CHARS: usize = 80;
+const GITHUB_SIGNATURE_HEADER: &str = "x-hub-signature-256";
+const GITHUB_EVENT_HEADER: &str = "x-github-event";
+const GITHUB_DELIVERY_HEADER: &str = "x-github-delivery";
+
+pub const GENERIC_WEBHOOK_PATH: &str = "/webhook/generic";
+pub const GITHUB_WEBHOOK_PATH: &str = "/webhook/github";
+
+pub fn generic_webhook_routes() -> Router<ServerState> {
+    Router::new().route(GENERIC_WEBHOOK_PATH, post(generic_webhook))
+}
+
+/// Signed JSON payloads pushed to `/webhook/generic` and mapped to intents
+/// by the `inbound` section of `config/webhooks.yml`.
+#[derive(Debug, Default)]
+pub struct InboundWebhookSource;
+
+#[async_trait]
+impl IngestSource for InboundWebhookSource {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        config.webhooks.inbound.as_ref()?;
+        Some(IngestMode::Webhook {
+            path: GENERIC_WEBHOOK_PATH,
+        })
+    }
+
+    fn routes(&self) -> Router<ServerState> {
+        generic_webhook_routes()
+    }
+}
+
+pub fn github_webhook_routes() -> Router<ServerState> {
+    Router::new().route(GITHUB_WEBHOOK_PATH, post(github_webhook))
+}
+
+#[derive(Debug, Serialize)]
+struct GenericWebhookResponse {
+    status: String,
+    intent_id: Uuid,
+}
+
+async fn generic_webhook(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let Some(inbound) = config.webhooks.inbound.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let secret = match env::var(&inbound.secret_env) {
+        Ok(secret) => secret,
+        Err(err) => {
+            warn!(error = ?err, env = %inbound.secret_env, "generic webhook secret missing");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let signature = headers
+        .get(inbound.signature_header.as_str())
+        .and_then(|value| value.to_str().ok());
+    if !signature.is_some_and(|signature| verify_signature(&secret, &body, signature)) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    let Some(draft) = map_payload(&inbound, &payload) else {
+        return StatusCode::UNPROCESSABLE_ENTITY.into_response();
+    };
+
+    match storage::persist_intent_at(&data_dir, &draft, state.ctx().now()).await {
+        Ok(record) => {
+            if let Err(err) = state.orchestrator().request_beat().await {
+                warn!(error = ?err, "failed to request beat after webhook intent");
+            }
+            (
+                StatusCode::ACCEPTED,
+                Json(GenericWebhookResponse {
+                    status: "queued".to_string(),
+                    intent_id: record.id,
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "failed to persist intent from generic webhook");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn github_webhook(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let config = state.ctx().config();
+    let Some(github_config) = config.github.clone() else {
+        return StatusCode::NOT_IMPLEMENTED.into_response();
+    };
+    let data_dir = config.data_dir.clone();
+    drop(config);
+
+    let secret = match env::var(&github_config.webhook_secret_env) {
+        Ok(secret) => secret,
+        Err(err) => {
+            warn!(error = ?err, env = %github_config.webhook_secret_env, "github webhook secret missing");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let signature = headers
+        .get(GITHUB_SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !signature.is_some_and(|signature| verify_signature(&secret, &body, signature)) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    match headers
+        .get(GITHUB_EVENT_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some("issues") => {}
+        Some("ping") => return StatusCode::OK.into_response(),
+        _ => return StatusCode::NO_CONTENT.into_response(),
+    }
+
+    let event: IssuesEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let Some(draft) = github::issue_intent(&github_config, &event) else {
+        return StatusCode::NO_CONTENT.into_response();
+    };
+    let delivery = headers
+        .get(GITHUB_DELIVERY_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    match github::queue_issue_intent(&data_dir, delivery, &event, &draft, state.ctx().now()).await {
+        Ok(None) => StatusCode::NO_CONTENT.into_response(),
+        Ok(Some(record)) => {
+            if let Err(err) = state.orchestrator().request_beat().await {
+                warn!(error = ?err, "failed to request beat after github intent");
+            }
+            (
+                StatusCode::ACCEPTED,
+                Json(GenericWebhookResponse {
+                    status: "queued".to_string(),
+                    intent_id: record.id,
+                }),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "failed to persist intent from github webhook");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Accepts either a bare hex digest or the `sha256=<hex>` form used by
+/// GitHub-style senders.
+fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = signature.trim();
+    let hex_digest = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(hex_digest) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn map_payload(config: &InboundWebhookConfig, payload: &Value) -> Option<IntentDraft> {
+    let summary = lookup_path(payload, &config.summary_path)
+        .map(value_to_text)
+        .filter(|summary| !summary.trim().is_empty())?;
+    let summary_short = text::truncate(summary.trim(), WEBHOOK_SUMMARY_MAX_CHARS);
+
+    let body = match config.body_path.as_deref() {
+        Some(path) => lookup_path(payload, path).map(value_to_text)?,
+        None => serde_json::to_string_pretty(payload).ok()?,
+    };
+    let source = config
+        .source_path
+        .as_deref()
+        .and_then(|path| lookup_path(payload, path))
+        .map(value_to_text)
+        .filter(|source| !source.trim().is_empty())
+        .unwrap_or_else(|| config.default_source.clone());
+
+    Some(IntentDraft {
+        source,
+        summary: summary_short,
+        telos_alignment: config.telos_alignment,
+        body,
+        due_at: None,
+        metadata: Default::default(),
+    })
+}
+
+/// Resolve a dot path such as `alerts.0.labels.alertname`; a leading `$.` is
+/// accepted for JSONPath-style configs.
+fn lookup_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    if path.is_empty() {
+        return Some(value);
+    }
+    path.split('.')
+        .try_fold(value, |current, segment| match current {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|idx| items.get(idx)),
+            Value::Object(map) => map.get(segment),
+            _ => None,
+        })
+}
+
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> InboundWebhookConfig {
+        InboundWebhookConfig {
+            secret_env: "HI_WEBHOOK_SECRET".to_string(),
+            signature_header: "x-hi-signature".to_string(),
+            summary_path: "$.alerts.0.labels.alertname".to_string(),
+            body_path: Some("alerts.0.annotations.description".to_string()),
+            source_path: Some("receiver".to_string()),
+            default_source: "webhook".to_string(),
+            telos_alignment: 0.8,
+        }
+    }
+
+    #[test]
+    fn signature_accepts_prefixed_and_bare_digests() {
+        let body = br#"{"ok":true}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"topsecret").unwrap();
+        mac.update(body);
+        let digest = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_signature("topsecret", body, &digest));
+        assert!(verify_signature(
+            "topsecret",
+            body,
+            &format!("sha256={digest}")
+        ));
+        assert!(!verify_signature("other", body, &digest));
+        assert!(!verify_signature("topsecret", body, "not-hex"));
+    }
+
+    #[test]
+    fn payload_paths_map_to_intent_fields() {
+        let payload = json!({
+            "receiver": "grafana",
+            "alerts": [{
+                "labels": {"alertname": "Disk almost full"},
+                "annotations": {"description": "/data is at 95%"}
+            }]
+        });
+
+        let draft = map_payload(&config(), &payload).expect("draft");
+        assert_eq!(draft.summary, "Disk almost full");
+        assert_eq!(draft.body, "/data is at 95%");
+        assert_eq!(draft.source, "grafana");
+
+        let missing = json!({"alerts": []});
+        assert!(map_payload(&config(), &missing).is_none());
+
+        // 79 chars and the "é" as e + U+0301 straddling the cut.
+        let long = format!("{}e\u{301} and more", "a".repeat(78));
+        let payload = json!({"alerts": [{
+            "labels": {"alertname": long},
+            "annotations": {"description": "-"}
+        }]});
+        let draft = map_payload(&config(), &payload).expect("draft");
+        assert_eq!(draft.summary, format!("{}…", "a".repeat(78)));
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    agent::ConversationTurn,
+    config::SessionConfig,
+    storage::{self, MessageDirection, MessageLogEntry, MessageLogQuery},
+    tasks::Intent,
+    telegram,
+};
+
+/// A conversation is identified by the channel (`source`) and its chat id,
+/// as recorded in the message log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionKey {
+    pub source: String,
+    pub chat_id: String,
+}
+
+impl SessionKey {
+    pub fn new(source: &str, chat_id: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            chat_id: chat_id.to_string(),
+        }
+    }
+
+    /// Session an intent belongs to, when it came from a chat channel.
+    pub fn for_intent(intent: &Intent) -> Option<Self> {
+        telegram::origin_chat_id(intent)
+            .map(|chat_id| Self::new(&intent.source, &chat_id.to_string()))
+    }
+}
+
+/// Recent turns of `key`, oldest first: at most `max_turns` messages from
+/// the last `window_minutes`. An inbound message whose `message_id` equals
+/// `exclude_message_id` is skipped so the message being answered is not
+/// repeated as its own context.
+pub fn load_turns(
+    data_dir: &Path,
+    config: &SessionConfig,
+    key: &SessionKey,
+    exclude_message_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<ConversationTurn>> {
+    if config.max_turns == 0 {
+        return Ok(Vec::new());
+    }
+
+    let entries = storage::read_messages(
+        data_dir,
+        MessageLogQuery {
+            source: Some(key.source.clone()),
+            direction: None,
+            chat_id: Some(key.chat_id.clone()),
+            since: Some(now - Duration::minutes(config.window_minutes)),
+            limit: usize::MAX,
+        },
+    )?;
+
+    let mut turns: Vec<ConversationTurn> = entries
+        .iter()
+        .filter(|entry| !is_excluded(entry, exclude_message_id))
+        .take(config.max_turns)
+        .map(turn_from_entry)
+        .collect();
+    turns.reverse();
+    Ok(turns)
+}
+
+/// Context for running `intent`: its chat's recent turns, or nothing for
+/// intents that did not come from a conversation.
+pub fn intent_turns(
+    data_dir: &Path,
+    config: &SessionConfig,
+    intent: &Intent,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<ConversationTurn>> {
+    let Some(key) = SessionKey::for_intent(intent) else {
+        return Ok(Vec::new());
+    };
+    let message_id = intent
+        .metadata
+        .get(telegram::MESSAGE_ID_KEY)
+        .map(String::as_str);
+    load_turns(data_dir, config, &key, message_id, now)
+}
+
+fn is_excluded(entry: &MessageLogEntry, exclude_message_id: Option<&str>) -> bool {
+    let Some(excluded) = exclude_message_id else {
+        return false;
+    };
+    entry.direction == MessageDirection::Inbound
+        && entry
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("message_id"))
+            .is_some_and(|id| {
+                id.as_str() == Some(excluded)
+                    || id.as_i64().map(|id| id.to_string()).as_deref() == Some(excluded)
+            })
+}
+
+fn turn_from_entry(entry: &MessageLogEntry) -> ConversationTurn {
+    let role = match entry.direction {
+        MessageDirection::Inbound => "user",
+        MessageDirection::Outbound => "assistant",
+    };
+    ConversationTurn {
+        role: role.to_string(),
+        text: entry.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    async fn log(
+        data_dir: &Path,
+        direction: MessageDirection,
+        chat_id: &str,
+        text: &str,
+        message_id: i64,
+        at: DateTime<Utc>,
+    ) {
+        storage::append_message_entry(
+            data_dir,
+            &MessageLogEntry {
+                id: Uuid::new_v4(),
+                direction,
+                source: "telegram".to_string(),
+                chat_id: chat_id.to_string(),
+                author: None,
+                text: text.to_string(),
+                timestamp: at,
+                metadata: Some(json!({ "message_id": message_id })),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn follow_up_sees_earlier_turns_of_same_chat() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        let now = Utc::now();
+        let minutes = |m: i64| now - Duration::minutes(m);
+
+        log(
+            data_dir,
+            MessageDirection::Inbound,
+            "99",
+            "too old",
+            1,
+            minutes(600),
+        )
+        .await;
+        log(
+            data_dir,
+            MessageDirection::Inbound,
+            "99",
+            "Write a haiku",
+            2,
+            minutes(10),
+        )
+        .await;
+        log(
+            data_dir,
+            MessageDirection::Outbound,
+            "99",
+            "Autumn moon…",
+            3,
+            minutes(9),
+        )
+        .await;
+        log(
+            data_dir,
+            MessageDirection::Inbound,
+            "7",
+            "other chat",
+            4,
+            minutes(8),
+        )
+        .await;
+        log(
+            data_dir,
+            MessageDirection::Inbound,
+            "99",
+            "actually make it shorter",
+            5,
+            minutes(1),
+        )
+        .await;
+
+        let intent = Intent {
+            id: Uuid::new_v4(),
+            source: "telegram".to_string(),
+            summary: "actually make it shorter".to_string(),
+            telos_alignment: 1.0,
+            created_at: now,
+            due_at: None,
+            metadata: BTreeMap::from([
+                (telegram::CHAT_ID_KEY.to_string(), "99".to_string()),
+                (telegram::MESSAGE_ID_KEY.to_string(), "5".to_string()),
+            ]),
+            storage_path: None,
+        };
+
+        let turns = intent_turns(data_dir, &SessionConfig::default(), &intent, now).unwrap();
+        assert_eq!(
+            turns,
+            vec![
+                ConversationTurn {
+                    role: "user".to_string(),
+                    text: "Write a haiku".to_string(),
+                },
+                ConversationTurn {
+                    role: "assistant".to_string(),
+                    text: "Autumn moon…".to_string(),
+                },
+            ]
+        );
+
+        let capped = SessionConfig {
+            max_turns: 1,
+            ..SessionConfig::default()
+        };
+        let turns = intent_turns(data_dir, &capped, &intent, now).unwrap();
+        assert_eq!(turns.len(), 1);
+        assert_eq!(turns[0].text, "Autumn moon…");
+
+        let mut unrelated = intent.clone();
+        unrelated.metadata.clear();
+        assert!(
+            intent_turns(data_dir, &SessionConfig::default(), &unrelated, now)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use axum::Router;
+use serde::Serialize;
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::{
+    calendar::CalendarSource,
+    config::AppConfig,
+    email::EmailSource,
+    feeds::FeedsSource,
+    github::GithubSource,
+    orchestrator::{Job, JobSchedule, OrchestratorHandle},
+    server::{InboundWebhookSource, ServerState},
+    state::AppContext,
+    telegram::TelegramSource,
+};
+
+/// How a source receives items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IngestMode {
+    /// [`IngestSource::poll`] runs every `interval_secs`; `0` for sources
+    /// whose poll waits for items itself, like a long poll.
+    Poll { interval_secs: u64 },
+    /// Items are pushed to the source's [`IngestSource::routes`] at `path`.
+    Webhook { path: &'static str },
+}
+
+/// A channel intents arrive through. Sources register in a
+/// [`SourceRegistry`], which mounts the routes of webhook sources; polling
+/// sources run as [`SourceJob`]s in the scheduler.
+#[async_trait]
+pub trait IngestSource: Send + Sync {
+    /// Name in logs and `/api/sources`.
+    fn name(&self) -> &'static str;
+
+    /// How the source runs under `config`, or `None` when it is not
+    /// configured.
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode>;
+
+    /// Routes pushed items arrive on. They are mounted whether or not the
+    /// source is configured and answer `501` until it is.
+    fn routes(&self) -> Router<ServerState> {
+        Router::new()
+    }
+
+    /// Called before the first poll, and again before the next one until it
+    /// succeeds.
+    async fn start(&self, ctx: &AppContext) -> anyhow::Result<()> {
+        let _ = ctx;
+        Ok(())
+    }
+
+    /// Fetch new items and persist them as intents, returning how many
+    /// intents were created or updated.
+    async fn poll(&self, ctx: &AppContext) -> anyhow::Result<usize> {
+        let _ = ctx;
+        Ok(0)
+    }
+
+    /// Called once at shutdown if the source started.
+    async fn stop(&self, ctx: &AppContext) {
+        let _ = ctx;
+    }
+}
+
+/// A source and how it runs, for `/api/sources`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceStatus {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub mode: Option<IngestMode>,
+}
+
+/// The intent sources of a workspace, in registration order.
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: Vec<Arc<dyn IngestSource>>,
+}
+
+impl Default for SourceRegistry {
+    /// The built-in sources.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Arc::new(EmailSource));
+        registry.register(Arc::new(TelegramSource::default()));
+        registry.register(Arc::new(FeedsSource::default()));
+        registry.register(Arc::new(CalendarSource::default()));
+        registry.register(Arc::new(GithubSource));
+        registry.register(Arc::new(InboundWebhookSource));
+        registry
+    }
+}
+
+impl SourceRegistry {
+    pub fn empty() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    /// Add `source`, replacing any source of the same name.
+    pub fn register(&mut self, source: Arc<dyn IngestSource>) {
+        self.sources.retain(|known| known.name() != source.name());
+        self.sources.push(source);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn IngestSource>> {
+        self.sources.iter().find(|source| source.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn IngestSource>> {
+        self.sources.iter()
+    }
+
+    /// Every source with its mode under `config`.
+    pub fn statuses(&self, config: &AppConfig) -> Vec<SourceStatus> {
+        self.sources
+            .iter()
+            .map(|source| {
+                let mode = source.mode(config);
+                SourceStatus {
+                    name: source.name(),
+                    enabled: mode.is_some(),
+                    mode,
+                }
+            })
+            .collect()
+    }
+
+    /// The routes of every source.
+    pub fn router(&self) -> Router<ServerState> {
+        self.sources.iter().fold(Router::new(), |router, source| {
+            router.merge(source.routes())
+        })
+    }
+}
+
+impl std::fmt::Debug for SourceRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.sources.iter().map(|source| source.name()))
+            .finish()
+    }
+}
+
+/// Polls a source on the interval its [`IngestMode::Poll`] gives, asking
+/// `orchestrator` for a beat whenever a poll ingests something. Named after
+/// the source in `/api/jobs` and `config/jobs.yml`.
+pub struct SourceJob {
+    source: Arc<dyn IngestSource>,
+    orchestrator: OrchestratorHandle,
+    started: OnceCell<()>,
+}
+
+impl SourceJob {
+    pub fn new(source: Arc<dyn IngestSource>, orchestrator: OrchestratorHandle) -> Self {
+        Self {
+            source,
+            orchestrator,
+            started: OnceCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Job for SourceJob {
+    fn name(&self) -> &'static str {
+        self.source.name()
+    }
+
+    fn default_schedule(&self, config: &AppConfig) -> Option<JobSchedule> {
+        match self.source.mode(config)? {
+            IngestMode::Poll { interval_secs } => {
+                Some(JobSchedule::Every(Duration::from_secs(interval_secs)))
+            }
+            IngestMode::Webhook { .. } => None,
+        }
+    }
+
+    async fn run(&self, ctx: &AppContext) -> anyhow::Result<()> {
+        self.started
+            .get_or_try_init(|| self.source.start(ctx))
+            .await?;
+        let count = self.source.poll(ctx).await?;
+        if count > 0 {
+            info!(source = self.source.name(), count, "intents ingested");
+            if let Err(err) = self.orchestrator.request_beat().await {
+                warn!(source = self.source.name(), error = ?err, "failed to request beat after ingested intents");
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop(&self, ctx: &AppContext) {
+        if self.started.initialized() {
+            self.source.stop(ctx).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{agent::AgentRuntime, fixtures, orchestrator::Scheduler, storage};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    /// Ingests one intent on its first poll, then nothing.
+    #[derive(Default)]
+    struct OnceSource {
+        polls: AtomicUsize,
+        stopped: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl IngestSource for OnceSource {
+        fn name(&self) -> &'static str {
+            "once"
+        }
+
+        fn mode(&self, _config: &AppConfig) -> Option<IngestMode> {
+            Some(IngestMode::Poll { interval_secs: 0 })
+        }
+
+        async fn poll(&self, _ctx: &AppContext) -> anyhow::Result<usize> {
+            let polls = self.polls.fetch_add(1, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            Ok(usize::from(polls == 0))
+        }
+
+        async fn stop(&self, _ctx: &AppContext) {
+            self.stopped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_sources_poll_until_shutdown() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        let config = AppConfig::load_from(&root).unwrap();
+        let data_dir = config.data_dir.clone();
+        let agent = AgentRuntime::from_app_config(&config).unwrap();
+        let ctx = AppContext::new(config, Arc::new(agent));
+
+        let source = Arc::new(OnceSource::default());
+        let mut registry = SourceRegistry::empty();
+        registry.register(source.clone());
+        let statuses = registry.statuses(&ctx.config());
+        assert_eq!(statuses.len(), 1);
+        assert!(statuses[0].enabled);
+
+        let mut scheduler = Scheduler::new(ctx.clone());
+        let orchestrator = OrchestratorHandle::remote(&ctx);
+        for source in registry.iter() {
+            let job = SourceJob::new(Arc::clone(source), orchestrator.clone());
+            scheduler.add(Arc::new(job)).unwrap();
+        }
+        let task = scheduler.spawn().unwrap();
+        while source.polls.load(Ordering::SeqCst) < 3 {
+            tokio::task::yield_now().await;
+        }
+        ctx.request_shutdown();
+        task.await.unwrap();
+        assert_eq!(source.stopped.load(Ordering::SeqCst), 1);
+        assert!(storage::take_beat_request(&data_dir).unwrap());
+
+        let statuses = ctx.jobs().statuses();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "once");
+        assert_eq!(statuses[0].schedule, "every 0s");
+        assert_eq!(statuses[0].failures, 0);
+    }
+
+    #[test]
+    fn builtin_sources_follow_the_config() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        std::fs::write(
+            root.join("config/feeds.yml"),
+            "poll_interval_minutes: 15\nfeeds:\n  - url: https://example.com/feed.xml\n",
+        )
+        .unwrap();
+        let config = AppConfig::load_from(&root).unwrap();
+
+        let statuses = SourceRegistry::default().statuses(&config);
+        let feeds = statuses
+            .iter()
+            .find(|status| status.name == "feeds")
+            .unwrap();
+        assert_eq!(feeds.mode, Some(IngestMode::Poll { interval_secs: 900 }));
+        let github = statuses
+            .iter()
+            .find(|status| status.name == "github")
+            .unwrap();
+        assert!(!github.enabled);
+    }
+}
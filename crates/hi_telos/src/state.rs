@@ -1,30 +1,102 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
 
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
-use tokio::sync::Notify;
+use tokio::sync::{Mutex, Notify, RwLock as AsyncRwLock};
 
-use crate::{agent::AgentRuntime, config::AppConfig, tasks::IntentQueue};
+use crate::{
+    agent::AgentRuntime,
+    clock::{self, SharedClock},
+    config::{self, AppConfig, ConfigReload},
+    events::EventBus,
+    orchestrator::JobBoard,
+    sources::SourceRegistry,
+    tasks::IntentQueue,
+    telegram::InFlightUpdates,
+};
 
 #[derive(Clone)]
 pub struct AppContext {
-    config: Arc<AppConfig>,
+    config: Arc<RwLock<Arc<AppConfig>>>,
     shutdown: Arc<Notify>,
+    shutdown_requested: Arc<AtomicBool>,
     intents: Arc<RwLock<IntentQueue>>,
     agent: Arc<AgentRuntime>,
+    sources: Arc<SourceRegistry>,
+    jobs: JobBoard,
+    events: EventBus,
+    beat_gate: Arc<Mutex<()>>,
+    restore_gate: Arc<AsyncRwLock<()>>,
+    clock: SharedClock,
+    started_at: DateTime<Utc>,
+    last_beat: Arc<RwLock<Option<DateTime<Utc>>>>,
+    writes_paused: Arc<AtomicBool>,
+    data_generation: Arc<AtomicU64>,
+    telegram_updates: InFlightUpdates,
 }
 
 impl AppContext {
     pub fn new(config: AppConfig, agent: Arc<AgentRuntime>) -> Self {
+        let clock = clock::system_clock();
         Self {
-            config: Arc::new(config),
+            config: Arc::new(RwLock::new(Arc::new(config))),
             shutdown: Arc::new(Notify::new()),
+            shutdown_requested: Arc::new(AtomicBool::new(false)),
             intents: Arc::new(RwLock::new(IntentQueue::default())),
             agent,
+            sources: Arc::new(SourceRegistry::default()),
+            jobs: JobBoard::default(),
+            events: EventBus::default(),
+            beat_gate: Arc::new(Mutex::new(())),
+            restore_gate: Arc::new(AsyncRwLock::new(())),
+            started_at: clock.now(),
+            clock,
+            last_beat: Arc::new(RwLock::new(None)),
+            writes_paused: Arc::new(AtomicBool::new(false)),
+            data_generation: Arc::new(AtomicU64::new(0)),
+            telegram_updates: InFlightUpdates::default(),
         }
     }
 
+    /// Replace the system clock, e.g. with a
+    /// [`ManualClock`](crate::clock::ManualClock) in tests.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.started_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Ingest from `sources` instead of the built-in ones.
+    pub fn with_sources(mut self, sources: SourceRegistry) -> Self {
+        self.sources = Arc::new(sources);
+        self
+    }
+
+    pub fn clock(&self) -> SharedClock {
+        Arc::clone(&self.clock)
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
     pub fn config(&self) -> Arc<AppConfig> {
-        Arc::clone(&self.config)
+        Arc::clone(&self.config.read())
+    }
+
+    /// Swap in the runtime-safe parts of `fresh`; see
+    /// [`config::merge_reloadable`].
+    pub fn reload_config(&self, fresh: &AppConfig) -> ConfigReload {
+        let mut config = self.config.write();
+        let (merged, reload) = config::merge_reloadable(&config, fresh);
+        if !reload.applied.is_empty() {
+            self.agent.update_config(merged.agent.clone());
+            *config = Arc::new(merged);
+        }
+        reload
     }
 
     pub fn intents(&self) -> Arc<RwLock<IntentQueue>> {
@@ -39,7 +111,96 @@ impl AppContext {
         Arc::clone(&self.agent)
     }
 
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    /// Status of the jobs the scheduler runs in this process.
+    pub fn jobs(&self) -> &JobBoard {
+        &self.jobs
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Telegram updates this workspace is applying right now.
+    pub fn telegram_updates(&self) -> &InFlightUpdates {
+        &self.telegram_updates
+    }
+
+    /// Held by the orchestrator for the length of a beat. Holding it
+    /// elsewhere (e.g. during a restore) waits for the running beat to end
+    /// and keeps the next one from starting.
+    pub fn beat_gate(&self) -> Arc<Mutex<()>> {
+        Arc::clone(&self.beat_gate)
+    }
+
+    /// Read-held by background tasks that write the data dir outside a
+    /// beat (the outbox worker, scheduled jobs). A restore or import holds
+    /// it for writing so none of them runs while the data is swapped.
+    pub fn restore_gate(&self) -> Arc<AsyncRwLock<()>> {
+        Arc::clone(&self.restore_gate)
+    }
+
+    /// When this context was created, standing in for the last beat until
+    /// the first one finishes.
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// Finish time of the most recent beat.
+    pub fn last_beat(&self) -> Option<DateTime<Utc>> {
+        *self.last_beat.read()
+    }
+
+    pub fn record_beat(&self, at: DateTime<Utc>) {
+        *self.last_beat.write() = Some(at);
+    }
+
+    /// Pause or resume non-essential writes (logs, history) for this
+    /// workspace. Returns whether that changed anything.
+    pub fn set_writes_paused(&self, paused: bool) -> bool {
+        self.writes_paused.swap(paused, Ordering::SeqCst) != paused
+    }
+
+    /// Whether the disk guard has paused logs and history because the data
+    /// dir's disk is low on space.
+    pub fn writes_paused(&self) -> bool {
+        self.writes_paused.load(Ordering::SeqCst)
+    }
+
+    /// Moves on whenever this workspace may have written to its data dir
+    /// (an API write, a beat, a scheduled job), so read caches such as the
+    /// markdown tree know to refresh. Edits made outside the process are not
+    /// seen; caches pair this with a short TTL.
+    pub fn data_generation(&self) -> u64 {
+        self.data_generation.load(Ordering::Acquire)
+    }
+
+    pub fn note_data_changed(&self) {
+        self.data_generation.fetch_add(1, Ordering::AcqRel);
+    }
+
     pub fn request_shutdown(&self) {
+        self.shutdown_requested.store(true, Ordering::SeqCst);
         self.shutdown.notify_waiters();
     }
+
+    pub fn is_shutdown_requested(&self) -> bool {
+        self.shutdown_requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolve once shutdown has been requested, even if the request happened
+    /// before this future was first polled, e.g. a SIGTERM that arrives while
+    /// a task is between two waits.
+    pub async fn wait_for_shutdown(&self) {
+        let notified = self.shutdown.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.is_shutdown_requested() {
+            return;
+        }
+        notified.await;
+    }
 }
@@ -0,0 +1,87 @@
+use std::fs::{self, File};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, anyhow};
+use tokio::fs as async_fs;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+/// Replace `path` with `contents` so that readers, and whatever is left after
+/// a crash, see either the old file or the new one but never a partial
+/// write: the bytes go to a temp file in the same directory, are fsynced and
+/// then renamed over `path`.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    let temp_path = temp_path(path)?;
+    let written = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .with_context(|| format!("writing temp file {:?}", temp_path))
+        .and_then(|()| {
+            fs::rename(&temp_path, path)
+                .with_context(|| format!("renaming {:?} to {:?}", temp_path, path))
+        });
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+/// Async counterpart of [`write_atomic`].
+pub async fn write_atomic_async(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    let temp_path = temp_path(path)?;
+    let written = async {
+        let mut file = async_fs::File::create(&temp_path).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await
+    }
+    .await
+    .with_context(|| format!("writing temp file {:?}", temp_path));
+    let written = match written {
+        Ok(()) => async_fs::rename(&temp_path, path)
+            .await
+            .with_context(|| format!("renaming {:?} to {:?}", temp_path, path)),
+        Err(err) => Err(err),
+    };
+    if written.is_err() {
+        let _ = async_fs::remove_file(&temp_path).await;
+    }
+    written
+}
+
+/// A hidden sibling of `path`, unique per write so concurrent writers never
+/// share a temp file.
+fn temp_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("path missing file name: {:?}", path))?;
+    Ok(path.with_file_name(format!(".{file_name}.{}.tmp", Uuid::new_v4().simple())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn replaces_file_without_leaving_temp_files() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("state.json");
+
+        write_atomic(&path, b"{\"v\":1}").unwrap();
+        write_atomic_async(&path, b"{\"v\":2}").await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"v\":2}");
+
+        let missing_dir = temp.path().join("missing/state.json");
+        assert!(write_atomic(&missing_dir, b"x").is_err());
+
+        let entries: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("state.json")]);
+    }
+}
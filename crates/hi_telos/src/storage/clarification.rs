@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{agent::AgentStep, tasks::IntentState};
+
+use super::{IntentRecord, scan_intent_dir, write_atomic};
+
+const WAITING_DIR: &str = "intent/waiting";
+const QUESTIONS_DIR: &str = "intent/waiting/questions";
+
+/// A run paused on `ask_user`. The intent file sits in `intent/waiting`
+/// while this state is kept in `intent/waiting/questions/<intent-id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingQuestion {
+    pub intent_id: Uuid,
+    pub run_id: Uuid,
+    pub summary: String,
+    pub question: String,
+    /// Steps taken before pausing; the last one asked the question.
+    pub steps: Vec<AgentStep>,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<String>,
+    pub asked_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answered_at: Option<DateTime<Utc>>,
+}
+
+impl PendingQuestion {
+    /// Steps to resume from: the asking step observes the user's answer.
+    pub fn resumed_steps(&self) -> Vec<AgentStep> {
+        let mut steps = self.steps.clone();
+        if let (Some(answer), Some(last)) = (&self.answer, steps.last_mut()) {
+            // Keep the question once the observation no longer carries it.
+            last.question = Some(self.question.clone());
+            last.observation = format!("User answered: {}", answer.trim());
+        }
+        steps
+    }
+}
+
+/// Move the intent file at `path` into `intent/waiting` and record the
+/// question it is waiting on.
+pub fn park_intent_for_question(
+    path: &Path,
+    data_dir: &Path,
+    question: &PendingQuestion,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
+    save_pending_question(data_dir, question)?;
+    let waiting_dir = data_dir.join(WAITING_DIR);
+    fs::create_dir_all(&waiting_dir)
+        .with_context(|| format!("ensuring waiting dir {:?}", waiting_dir))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
+    let destination = waiting_dir.join(file_name);
+    super::record_intent_state(path, IntentState::Waiting, None, now)?;
+    fs::rename(path, &destination)
+        .with_context(|| format!("moving intent to waiting: {:?}", path))?;
+    Ok(destination)
+}
+
+pub fn load_pending_question(
+    data_dir: &Path,
+    intent_id: Uuid,
+) -> anyhow::Result<Option<PendingQuestion>> {
+    let path = question_path(data_dir, intent_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("reading question {:?}", path))?;
+    let question =
+        serde_json::from_str(&content).with_context(|| format!("parsing question {:?}", path))?;
+    Ok(Some(question))
+}
+
+/// Every paused run, oldest question first.
+pub fn list_pending_questions(data_dir: &Path) -> anyhow::Result<Vec<PendingQuestion>> {
+    let dir = data_dir.join(QUESTIONS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut questions = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("reading questions dir {:?}", dir))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("reading question {:?}", path))?;
+        let question: PendingQuestion = serde_json::from_str(&content)
+            .with_context(|| format!("parsing question {:?}", path))?;
+        questions.push(question);
+    }
+    questions.sort_by_key(|question| question.asked_at);
+    Ok(questions)
+}
+
+/// Record `answer` for the intent's open question. Returns `None` when the
+/// intent is not waiting or was already answered.
+pub fn answer_pending_question(
+    data_dir: &Path,
+    intent_id: Uuid,
+    answer: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<PendingQuestion>> {
+    let Some(mut question) = load_pending_question(data_dir, intent_id)? else {
+        return Ok(None);
+    };
+    if question.answer.is_some() {
+        return Ok(None);
+    }
+    question.answer = Some(answer.to_string());
+    question.answered_at = Some(now);
+    save_pending_question(data_dir, &question)?;
+    Ok(Some(question))
+}
+
+/// Answer the most recent open question asked in `source`/`chat_id`.
+pub fn answer_chat_question(
+    data_dir: &Path,
+    source: &str,
+    chat_id: &str,
+    answer: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<PendingQuestion>> {
+    let open = list_pending_questions(data_dir)?
+        .into_iter()
+        .rev()
+        .find(|question| {
+            question.answer.is_none()
+                && question.source == source
+                && question.chat_id.as_deref() == Some(chat_id)
+        });
+    match open {
+        Some(question) => answer_pending_question(data_dir, question.intent_id, answer, now),
+        None => Ok(None),
+    }
+}
+
+/// Move the waiting intent back into `intent/queue` once its question has an
+/// answer. Returns `None` when its file is no longer in `intent/waiting`.
+pub fn requeue_answered_intent(
+    data_dir: &Path,
+    intent_id: Uuid,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<IntentRecord>> {
+    let Some(record) = scan_intent_dir(&data_dir.join(WAITING_DIR))?
+        .into_iter()
+        .find(|record| record.intent.id == intent_id)
+    else {
+        return Ok(None);
+    };
+    let queue_path = super::promote_to_queue(&record.path, data_dir, now)?;
+    let mut intent = record.intent;
+    intent.storage_path = Some(queue_path.clone());
+    Ok(Some(IntentRecord {
+        path: queue_path,
+        intent,
+        body: record.body,
+    }))
+}
+
+pub fn clear_pending_question(data_dir: &Path, intent_id: Uuid) -> anyhow::Result<()> {
+    let path = question_path(data_dir, intent_id);
+    if path.exists() {
+        fs::remove_file(&path).with_context(|| format!("removing question {:?}", path))?;
+    }
+    Ok(())
+}
+
+fn save_pending_question(data_dir: &Path, question: &PendingQuestion) -> anyhow::Result<()> {
+    let dir = data_dir.join(QUESTIONS_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("creating questions dir {:?}", dir))?;
+    let path = question_path(data_dir, question.intent_id);
+    write_atomic(&path, serde_json::to_vec_pretty(question)?)
+        .with_context(|| format!("writing question {:?}", path))
+}
+
+fn question_path(data_dir: &Path, intent_id: Uuid) -> PathBuf {
+    data_dir
+        .join(QUESTIONS_DIR)
+        .join(format!("{intent_id}.json"))
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+/// Space on the file system holding a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DiskSpace {
+    pub total_bytes: u64,
+    /// Available to this process, i.e. without the root reserve.
+    pub available_bytes: u64,
+}
+
+pub fn disk_space(path: &Path) -> anyhow::Result<DiskSpace> {
+    Ok(DiskSpace {
+        total_bytes: fs2::total_space(path)
+            .with_context(|| format!("reading disk size of {:?}", path))?,
+        available_bytes: fs2::available_space(path)
+            .with_context(|| format!("reading free space of {:?}", path))?,
+    })
+}
+
+/// Size of one top-level directory of the data dir.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirUsage {
+    pub name: String,
+    pub bytes: u64,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageUsage {
+    pub total_bytes: u64,
+    /// Largest first.
+    pub dirs: Vec<DirUsage>,
+    /// Files directly in the data dir, such as state JSON and markers.
+    pub top_level_bytes: u64,
+    pub disk: DiskSpace,
+}
+
+/// Sizes of the data dir's top-level directories (journals, logs, memory
+/// …) and the space left on its disk.
+pub fn storage_usage(data_dir: &Path) -> anyhow::Result<StorageUsage> {
+    let entries =
+        fs::read_dir(data_dir).with_context(|| format!("reading data dir {:?}", data_dir))?;
+    let mut dirs = Vec::new();
+    let mut top_level_bytes = 0;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("reading data dir {:?}", data_dir))?;
+        let file_type = entry.file_type()?;
+        if file_type.is_file() {
+            top_level_bytes += entry.metadata()?.len();
+        } else if file_type.is_dir() {
+            let (bytes, files) = dir_size(&entry.path())?;
+            dirs.push(DirUsage {
+                name: entry.file_name().to_string_lossy().to_string(),
+                bytes,
+                files,
+            });
+        }
+    }
+    dirs.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(StorageUsage {
+        total_bytes: top_level_bytes + dirs.iter().map(|dir| dir.bytes).sum::<u64>(),
+        dirs,
+        top_level_bytes,
+        disk: disk_space(data_dir)?,
+    })
+}
+
+fn dir_size(root: &Path) -> anyhow::Result<(u64, usize)> {
+    let mut bytes = 0;
+    let mut files = 0;
+    for entry in WalkDir::new(root) {
+        let entry = entry.with_context(|| format!("walking {:?}", root))?;
+        if entry.file_type().is_file() {
+            bytes += entry
+                .metadata()
+                .with_context(|| format!("reading metadata of {:?}", entry.path()))?
+                .len();
+            files += 1;
+        }
+    }
+    Ok((bytes, files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn usage_is_grouped_by_top_level_dir() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        fs::create_dir_all(data_dir.join("logs/llm/2025/01")).unwrap();
+        fs::write(data_dir.join("logs/llm/2025/01/01.jsonl"), vec![b'x'; 300]).unwrap();
+        fs::write(data_dir.join("logs/other.jsonl"), vec![b'x'; 100]).unwrap();
+        fs::create_dir_all(data_dir.join("journals")).unwrap();
+        fs::write(data_dir.join("journals/a.md"), vec![b'x'; 50]).unwrap();
+        fs::write(data_dir.join(".last_beat"), vec![b'x'; 7]).unwrap();
+
+        let usage = storage_usage(data_dir).unwrap();
+        let dirs: Vec<_> = usage
+            .dirs
+            .iter()
+            .map(|dir| (dir.name.as_str(), dir.bytes, dir.files))
+            .collect();
+        assert_eq!(dirs, vec![("logs", 400, 2), ("journals", 50, 1)]);
+        assert_eq!(usage.top_level_bytes, 7);
+        assert_eq!(usage.total_bytes, 457);
+        assert!(usage.disk.total_bytes >= usage.disk.available_bytes);
+    }
+}
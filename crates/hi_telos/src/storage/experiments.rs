@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{SchemaKind, llm_index, parse_record};
+use crate::llm::LlmLogEntry;
+
+/// Outcomes of one experiment, per variant in name order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExperimentReport {
+    pub name: String,
+    pub variants: Vec<VariantOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantOutcome {
+    pub variant: String,
+    /// Prompt versions seen for the variant; more than one means its
+    /// templates changed while the experiment ran.
+    pub prompt_versions: Vec<String>,
+    pub runs: usize,
+    /// Runs that reached the FINAL phase; the others failed or paused on a
+    /// question.
+    pub completed: usize,
+    pub completion_rate: f64,
+    /// THINK steps per run.
+    pub avg_steps: f64,
+    pub avg_llm_latency_ms: f64,
+    pub tool_calls: usize,
+}
+
+#[derive(Debug, Default)]
+struct RunTally {
+    steps: usize,
+    completed: bool,
+    latency_ms: u64,
+    tool_calls: usize,
+}
+
+/// Per-variant outcomes of every experiment in the LLM logs, from runs
+/// logged at or after `since`.
+pub fn load_experiment_reports(
+    data_dir: &Path,
+    since: Option<DateTime<Utc>>,
+) -> anyhow::Result<Vec<ExperimentReport>> {
+    let log_root = data_dir.join("logs/llm");
+    if !log_root.exists() {
+        return Ok(Vec::new());
+    }
+
+    type VariantRuns = (BTreeSet<String>, HashMap<Uuid, RunTally>);
+    let mut experiments: BTreeMap<String, BTreeMap<String, VariantRuns>> = BTreeMap::new();
+    for (_, file) in llm_index::day_logs(&log_root, since.map(|since| since.date_naive()))? {
+        let content =
+            fs::read_to_string(&file).with_context(|| format!("reading llm log {:?}", file))?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let entry: LlmLogEntry = parse_record(SchemaKind::LlmLog, line)?;
+            let (Some(experiment), Some(variant)) = (entry.experiment, entry.variant) else {
+                continue;
+            };
+            if since.is_some_and(|since| entry.timestamp < since) {
+                continue;
+            }
+            let (versions, runs) = experiments
+                .entry(experiment)
+                .or_default()
+                .entry(variant)
+                .or_default();
+            versions.extend(entry.prompt_version);
+            let tally = runs.entry(entry.run_id).or_default();
+            tally.latency_ms += entry.latency_ms.unwrap_or_default();
+            match entry.phase.as_str() {
+                "THINK" => tally.steps += 1,
+                "FINAL" => tally.completed = true,
+                "TOOL" => tally.tool_calls += 1,
+                _ => {}
+            }
+        }
+    }
+
+    Ok(experiments
+        .into_iter()
+        .map(|(name, variants)| ExperimentReport {
+            name,
+            variants: variants
+                .into_iter()
+                .map(|(variant, (versions, runs))| {
+                    let count = runs.len();
+                    let per_run = |total: usize| total as f64 / count.max(1) as f64;
+                    let completed = runs.values().filter(|run| run.completed).count();
+                    VariantOutcome {
+                        variant,
+                        prompt_versions: versions.into_iter().collect(),
+                        runs: count,
+                        completed,
+                        completion_rate: per_run(completed),
+                        avg_steps: per_run(runs.values().map(|run| run.steps).sum()),
+                        avg_llm_latency_ms: per_run(
+                            runs.values().map(|run| run.latency_ms as usize).sum(),
+                        ),
+                        tool_calls: runs.values().map(|run| run.tool_calls).sum(),
+                    }
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{llm::LlmIdentity, storage::append_llm_logs};
+    use chrono::TimeZone;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn reports_outcomes_per_variant() {
+        let temp = tempdir().unwrap();
+        let identity = LlmIdentity::new("local_stub", None);
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+        let entry = |run_id: Uuid, phase: &str, variant: Option<&str>, latency: u64| {
+            let entry = LlmLogEntry::new(run_id, at, phase, "p", "r", &identity)
+                .with_latency(Duration::from_millis(latency));
+            match variant {
+                Some(variant) => entry.with_prompt(
+                    &format!("version-{variant}"),
+                    Some(("terse-final", variant)),
+                ),
+                None => entry.with_prompt("version-base", None),
+            }
+        };
+        let (a1, a2, b1, plain) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        append_llm_logs(
+            temp.path(),
+            &[
+                entry(a1, "THINK", Some("a"), 100),
+                entry(a1, "TOOL", Some("a"), 0),
+                entry(a1, "THINK", Some("a"), 100),
+                entry(a1, "FINAL", Some("a"), 100),
+                entry(a2, "THINK", Some("a"), 100),
+                entry(b1, "THINK", Some("b"), 50),
+                entry(b1, "FINAL", Some("b"), 50),
+                entry(plain, "THINK", None, 10),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let reports = load_experiment_reports(temp.path(), None).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].name, "terse-final");
+        let a = &reports[0].variants[0];
+        assert_eq!(
+            (a.variant.as_str(), a.runs, a.completed, a.tool_calls),
+            ("a", 2, 1, 1)
+        );
+        assert_eq!(a.prompt_versions, vec!["version-a"]);
+        assert_eq!(a.completion_rate, 0.5);
+        assert_eq!(a.avg_steps, 1.5);
+        assert_eq!(a.avg_llm_latency_ms, 200.0);
+        let b = &reports[0].variants[1];
+        assert_eq!((b.variant.as_str(), b.runs, b.completed), ("b", 1, 1));
+        assert_eq!(b.avg_llm_latency_ms, 100.0);
+
+        let later = at + chrono::Duration::days(1);
+        assert!(
+            load_experiment_reports(temp.path(), Some(later))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
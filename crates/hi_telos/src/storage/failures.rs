@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{DailyCount, scan_intent_dir};
+use crate::{
+    agent::FailedRun,
+    tasks::{FAILED_AT_KEY, FAILED_RUNS_KEY, FAILURE_CLASS_KEY, FAILURE_ERROR_KEY},
+    text,
+};
+
+const FAILED_DIR: &str = "intent/queue/failed";
+/// Longest error message kept in the intent's front matter.
+const MAX_FAILURE_ERROR_CHARS: usize = 500;
+/// Intents listed per class in the report, most recent first.
+const FAILURE_REPORT_INTENTS: usize = 20;
+
+/// Rough cause of an intent failure, derived from the error chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    /// The LLM answered with something that is not the expected JSON.
+    LlmParse,
+    ProviderTimeout,
+    /// Any other error talking to the provider, e.g. an error status.
+    Provider,
+    Storage,
+    /// Errors that fit none of the above, and intents quarantined before
+    /// failures were classified.
+    Other,
+}
+
+impl FailureClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureClass::LlmParse => "llm_parse",
+            FailureClass::ProviderTimeout => "provider_timeout",
+            FailureClass::Provider => "provider",
+            FailureClass::Storage => "storage",
+            FailureClass::Other => "other",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "llm_parse" => FailureClass::LlmParse,
+            "provider_timeout" => FailureClass::ProviderTimeout,
+            "provider" => FailureClass::Provider,
+            "storage" => FailureClass::Storage,
+            _ => FailureClass::Other,
+        }
+    }
+}
+
+/// Classify an error from processing an intent. JSON errors only count as
+/// LLM parse errors inside an agent run; elsewhere they come from reading
+/// stored records.
+pub fn classify_failure(err: &anyhow::Error) -> FailureClass {
+    let in_run = err.downcast_ref::<FailedRun>().is_some();
+    let mut class = FailureClass::Other;
+    for cause in err.chain() {
+        if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+            return if http.is_timeout() {
+                FailureClass::ProviderTimeout
+            } else {
+                FailureClass::Provider
+            };
+        }
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return FailureClass::ProviderTimeout;
+        }
+        if cause.is::<serde_json::Error>() && in_run {
+            class = FailureClass::LlmParse;
+        } else if class == FailureClass::Other
+            && (cause.is::<std::io::Error>()
+                || cause.is::<serde_json::Error>()
+                || cause.is::<serde_yaml::Error>())
+        {
+            class = FailureClass::Storage;
+        }
+    }
+    class
+}
+
+/// Why an intent was quarantined, kept in its front matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentFailure {
+    pub class: FailureClass,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+    pub run_ids: Vec<Uuid>,
+}
+
+impl IntentFailure {
+    pub fn new(err: &anyhow::Error, failed_at: DateTime<Utc>, run_ids: Vec<Uuid>) -> Self {
+        let error = format!("{err:#}")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let error = text::truncate(&error, MAX_FAILURE_ERROR_CHARS);
+        Self {
+            class: classify_failure(err),
+            error,
+            failed_at,
+            run_ids,
+        }
+    }
+
+    pub fn metadata(&self) -> Vec<(&'static str, String)> {
+        let mut metadata = vec![
+            (FAILURE_CLASS_KEY, self.class.as_str().to_string()),
+            (FAILURE_ERROR_KEY, self.error.clone()),
+            (FAILED_AT_KEY, self.failed_at.to_rfc3339()),
+        ];
+        if !self.run_ids.is_empty() {
+            let run_ids = self
+                .run_ids
+                .iter()
+                .map(Uuid::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            metadata.push((FAILED_RUNS_KEY, run_ids));
+        }
+        metadata
+    }
+}
+
+/// Quarantined intents grouped by [`FailureClass`], largest group first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FailureReport {
+    pub total: usize,
+    pub classes: Vec<FailureClassSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailureClassSummary {
+    pub class: FailureClass,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_failed_at: Option<DateTime<Utc>>,
+    /// Failures per day, oldest first.
+    pub per_day: Vec<DailyCount>,
+    pub intents: Vec<FailedIntent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedIntent {
+    pub id: Uuid,
+    pub summary: String,
+    pub source: String,
+    /// Relative to the data dir.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub run_ids: Vec<Uuid>,
+    /// `/api/logs/llm` queries for each failed run.
+    pub run_links: Vec<String>,
+}
+
+/// Summarize `intent/queue/failed`, counting only intents that failed at or
+/// after `since` when given. Intents without a recorded failure time fall
+/// back to their creation time.
+pub fn load_failure_report(
+    data_dir: &Path,
+    since: Option<DateTime<Utc>>,
+) -> anyhow::Result<FailureReport> {
+    let mut grouped: HashMap<FailureClass, Vec<FailedIntent>> = HashMap::new();
+    for record in scan_intent_dir(&data_dir.join(FAILED_DIR))? {
+        let metadata = &record.intent.metadata;
+        let failed_at = metadata
+            .get(FAILED_AT_KEY)
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|value| value.with_timezone(&Utc));
+        if since.is_some_and(|since| failed_at.unwrap_or(record.intent.created_at) < since) {
+            continue;
+        }
+        let class = metadata
+            .get(FAILURE_CLASS_KEY)
+            .map(|value| FailureClass::parse(value))
+            .unwrap_or(FailureClass::Other);
+        let run_ids: Vec<Uuid> = metadata
+            .get(FAILED_RUNS_KEY)
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|id| Uuid::parse_str(id.trim()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let path = record
+            .path
+            .strip_prefix(data_dir)
+            .unwrap_or(&record.path)
+            .to_string_lossy()
+            .to_string();
+        grouped.entry(class).or_default().push(FailedIntent {
+            id: record.intent.id,
+            summary: record.intent.summary.clone(),
+            source: record.intent.source.clone(),
+            path,
+            failed_at,
+            error: metadata.get(FAILURE_ERROR_KEY).cloned(),
+            run_links: run_ids
+                .iter()
+                .map(|run_id| format!("/api/logs/llm?run_id={run_id}"))
+                .collect(),
+            run_ids,
+        });
+    }
+
+    let mut report = FailureReport::default();
+    for (class, mut intents) in grouped {
+        let failed_at = |intent: &FailedIntent| intent.failed_at;
+        intents.sort_by_key(|intent| std::cmp::Reverse(failed_at(intent)));
+        let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for intent in &intents {
+            if let Some(failed_at) = intent.failed_at {
+                *per_day.entry(failed_at.date_naive()).or_default() += 1;
+            }
+        }
+        report.total += intents.len();
+        report.classes.push(FailureClassSummary {
+            class,
+            count: intents.len(),
+            last_failed_at: intents.iter().find_map(failed_at),
+            per_day: per_day
+                .into_iter()
+                .map(|(date, count)| DailyCount { date, count })
+                .collect(),
+            intents: intents.into_iter().take(FAILURE_REPORT_INTENTS).collect(),
+        });
+    }
+    report
+        .classes
+        .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.class.cmp(&b.class)));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use crate::storage::{ensure_data_layout, set_intent_metadata};
+
+    #[test]
+    fn failures_are_classified_and_grouped() {
+        let parse_err = serde_json::from_str::<u32>("nope").unwrap_err();
+        let in_run = anyhow::Error::new(FailedRun {
+            run_id: Uuid::nil(),
+            llm_logs: Vec::new(),
+            files: Vec::new(),
+            error: anyhow::Error::new(parse_err).context("parsing final answer: nope"),
+        });
+        assert_eq!(classify_failure(&in_run), FailureClass::LlmParse);
+        let io_err = anyhow::Error::new(std::io::Error::other("disk full")).context("journal");
+        assert_eq!(classify_failure(&io_err), FailureClass::Storage);
+        let stored = anyhow::Error::new(serde_json::from_str::<u32>("x").unwrap_err());
+        assert_eq!(classify_failure(&stored), FailureClass::Storage);
+        assert_eq!(
+            classify_failure(&anyhow::anyhow!("boom")),
+            FailureClass::Other
+        );
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).unwrap();
+        let failed_dir = data_dir.join(FAILED_DIR);
+        let now = "2025-03-02T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let run_id = Uuid::new_v4();
+        for (name, err, failed_at) in [
+            ("a.md", &in_run, now - chrono::Duration::days(1)),
+            ("b.md", &in_run, now),
+            ("c.md", &io_err, now),
+        ] {
+            let path = failed_dir.join(name);
+            fs::write(&path, format!("---\nsummary: {name}\n---\n")).unwrap();
+            let failure = IntentFailure::new(err, failed_at, vec![run_id]);
+            let metadata = failure.metadata();
+            let entries = metadata
+                .iter()
+                .map(|(key, value)| (*key, value.as_str()))
+                .collect::<Vec<_>>();
+            set_intent_metadata(&path, &entries).unwrap();
+        }
+        fs::write(failed_dir.join("legacy.md"), "---\nsummary: legacy\n---\n").unwrap();
+
+        let report = load_failure_report(data_dir, None).unwrap();
+        assert_eq!(report.total, 4);
+        let parse = &report.classes[0];
+        assert_eq!(parse.class, FailureClass::LlmParse);
+        assert_eq!(parse.count, 2);
+        assert_eq!(parse.last_failed_at, Some(now));
+        assert_eq!(parse.per_day.len(), 2);
+        assert_eq!(parse.intents[0].summary, "b.md");
+        assert_eq!(
+            parse.intents[0].run_links,
+            vec![format!("/api/logs/llm?run_id={run_id}")]
+        );
+        assert!(
+            parse.intents[0]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("parsing final answer")
+        );
+        let classes: Vec<_> = report.classes.iter().map(|class| class.class).collect();
+        assert!(classes.contains(&FailureClass::Storage));
+        assert!(classes.contains(&FailureClass::Other));
+
+        let recent = load_failure_report(data_dir, Some(now)).unwrap();
+        let parse = recent
+            .classes
+            .iter()
+            .find(|class| class.class == FailureClass::LlmParse)
+            .unwrap();
+        assert_eq!(parse.count, 1);
+    }
+}
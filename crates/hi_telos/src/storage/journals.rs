@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{NaiveDate, NaiveTime};
+use serde::Serialize;
+
+use super::{
+    JOURNAL_INDEX_MARKER, JOURNAL_LEGACY_MARKER, JournalLinks, RunTiming, journal_day_dir,
+    list_markdown_files,
+};
+
+/// Headings shown per day in [`list_journal_days`].
+const JOURNAL_DAY_HEADINGS: usize = 3;
+
+/// One calendar day with journal entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalDay {
+    pub date: NaiveDate,
+    pub entries: usize,
+    /// Headings of the earliest entries, at most three.
+    pub headings: Vec<String>,
+    /// The day index, for `/api/md/file`.
+    pub path: String,
+}
+
+/// One `## HH:MM:SS — summary` section of a day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalEntry {
+    pub heading: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<NaiveTime>,
+    pub title: String,
+    /// File holding the entry, relative to the data dir.
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<JournalLinks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_answer: Option<String>,
+    /// Absent for entries written before durations were recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<RunTiming>,
+    /// Markdown below the heading, without the link comment.
+    pub body: String,
+}
+
+/// Days with journal entries between `from` and `to` (inclusive), oldest
+/// first. Days whose files hold no entries are left out.
+pub fn list_journal_days(
+    data_dir: &Path,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> anyhow::Result<Vec<JournalDay>> {
+    let mut days = Vec::new();
+    for date in journal_dates(data_dir)? {
+        if from.is_some_and(|from| date < from) || to.is_some_and(|to| date > to) {
+            continue;
+        }
+        let entries = load_journal_day(data_dir, date)?;
+        if entries.is_empty() {
+            continue;
+        }
+        days.push(JournalDay {
+            date,
+            entries: entries.len(),
+            headings: entries
+                .iter()
+                .take(JOURNAL_DAY_HEADINGS)
+                .map(|entry| entry.heading.clone())
+                .collect(),
+            path: day_index_path(date),
+        });
+    }
+    Ok(days)
+}
+
+/// Entries of one day in time order: the per-intent files under
+/// `journals/YYYY/MM/DD/` plus sections kept verbatim in the day index from
+/// before the split.
+pub fn load_journal_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<Vec<JournalEntry>> {
+    let day_dir = journal_day_dir(data_dir, date);
+    let relative = |path: &Path| {
+        path.strip_prefix(data_dir)
+            .map(|relative| relative.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut entries = Vec::new();
+    let index_path = day_dir.with_extension("md");
+    if index_path.is_file() {
+        let content = fs::read_to_string(&index_path)
+            .with_context(|| format!("reading journal index {:?}", index_path))?;
+        let legacy = if content.starts_with(JOURNAL_INDEX_MARKER) {
+            content
+                .split_once(JOURNAL_LEGACY_MARKER)
+                .map(|(_, legacy)| legacy)
+                .unwrap_or_default()
+        } else {
+            content.as_str()
+        };
+        entries.extend(parse_journal_entries(legacy, &relative(&index_path)));
+    }
+    if day_dir.is_dir() {
+        for path in list_markdown_files(&day_dir) {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("reading journal file {:?}", path))?;
+            entries.extend(parse_journal_entries(&content, &relative(&path)));
+        }
+    }
+
+    entries.sort_by(|a, b| (a.time, &a.heading).cmp(&(b.time, &b.heading)));
+    Ok(entries)
+}
+
+/// Split a journal document into its `## ` sections. The legacy block's own
+/// "Earlier entries" heading is not an entry.
+fn parse_journal_entries(content: &str, path: &str) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in content.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            entries.extend(
+                current
+                    .take()
+                    .map(|(heading, body)| entry(heading, &body, path)),
+            );
+            if heading.trim() != "Earlier entries" {
+                current = Some((heading.trim().to_string(), Vec::new()));
+            }
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    entries.extend(current.map(|(heading, body)| entry(heading, &body, path)));
+    entries
+}
+
+fn entry(heading: String, lines: &[&str], path: &str) -> JournalEntry {
+    let (time, title) = match heading.split_once(" — ") {
+        Some((time, title)) => match NaiveTime::parse_from_str(time.trim(), "%H:%M:%S") {
+            Ok(time) => (Some(time), title.trim().to_string()),
+            Err(_) => (None, heading.clone()),
+        },
+        None => (None, heading.clone()),
+    };
+    let links = lines
+        .iter()
+        .find_map(|line| JournalLinks::from_comment(line));
+    let final_answer = lines
+        .iter()
+        .find_map(|line| line.strip_prefix("Final answer: "))
+        .map(|answer| answer.trim().to_string());
+    let timing = lines.iter().find_map(|line| RunTiming::from_line(line));
+    let body = lines
+        .iter()
+        .filter(|line| JournalLinks::from_comment(line).is_none())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+
+    JournalEntry {
+        heading,
+        time,
+        title,
+        path: path.to_string(),
+        links,
+        final_answer,
+        timing,
+        body,
+    }
+}
+
+/// Dates with a `journals/YYYY/MM/DD.md` index or `DD/` directory.
+fn journal_dates(data_dir: &Path) -> anyhow::Result<Vec<NaiveDate>> {
+    let root = data_dir.join("journals");
+    let mut dates = Vec::new();
+    for year in numeric_children(&root)? {
+        for month in numeric_children(&root.join(&year))? {
+            for day in numeric_children(&root.join(&year).join(&month))? {
+                let date = format!("{year}-{month}-{day}");
+                if let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                    dates.push(date);
+                }
+            }
+        }
+    }
+    dates.sort();
+    dates.dedup();
+    Ok(dates)
+}
+
+/// Names of `dir`'s entries that are all digits once `.md` is dropped.
+fn numeric_children(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("reading {:?}", dir)),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        let name = name.strip_suffix(".md").unwrap_or(&name);
+        if !name.is_empty() && name.chars().all(|ch| ch.is_ascii_digit()) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+fn day_index_path(date: NaiveDate) -> String {
+    format!("journals/{}.md", date.format("%Y/%m/%d"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn journal_days_combine_intent_files_and_legacy_entries() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        let month = data_dir.join("journals/2025/01");
+        fs::create_dir_all(month.join("02")).unwrap();
+        fs::write(
+            month.join("01.md"),
+            "## 09:00:00 — Legacy run\nIntent processed: Legacy run\nFinal answer: ok\n",
+        )
+        .unwrap();
+        fs::write(
+            month.join("02/a.md"),
+            "## 12:30:00 — Later run\n<!-- hi:links run_id=00000000-0000-0000-0000-000000000001 intent_id=00000000-0000-0000-0000-000000000002 memory_ids= -->\n\nFinal answer: done\nDuration: 900 ms (LLM 700 ms)\n",
+        )
+        .unwrap();
+        fs::write(month.join("02/b.md"), "## 08:15:00 — Early run\n\nbody\n").unwrap();
+        fs::write(
+            month.join("02.md"),
+            format!(
+                "{JOURNAL_INDEX_MARKER}\n# Journal 2025-01-02\n\n- [x](02/a.md)\n\n{JOURNAL_LEGACY_MARKER}\n## Earlier entries\n\n## 07:00:00 — Before split\n"
+            ),
+        )
+        .unwrap();
+        fs::write(month.join("03.md"), "# Notes without entries\n").unwrap();
+
+        let days = list_journal_days(data_dir, None, None).unwrap();
+        let summary: Vec<_> = days
+            .iter()
+            .map(|day| (day.date.to_string(), day.entries))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)]
+        );
+        assert_eq!(
+            days[1].headings,
+            vec![
+                "07:00:00 — Before split",
+                "08:15:00 — Early run",
+                "12:30:00 — Later run"
+            ]
+        );
+        assert_eq!(days[1].path, "journals/2025/01/02.md");
+
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let from = list_journal_days(data_dir, Some(date), None).unwrap();
+        assert_eq!(from.len(), 1);
+
+        let entries = load_journal_day(data_dir, date).unwrap();
+        let later = &entries[2];
+        assert_eq!(later.title, "Later run");
+        assert_eq!(later.path, "journals/2025/01/02/a.md");
+        assert_eq!(later.final_answer.as_deref(), Some("done"));
+        assert_eq!(later.links.as_ref().unwrap().run_id.as_u128(), 1);
+        assert_eq!(later.timing.map(|timing| timing.llm_latency_ms), Some(700));
+        assert_eq!(
+            later.body,
+            "Final answer: done\nDuration: 900 ms (LLM 700 ms)"
+        );
+        assert!(entries[1].timing.is_none());
+        assert_eq!(entries[0].path, "journals/2025/01/02.md");
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tokio::fs as async_fs;
+use uuid::Uuid;
+
+use crate::llm::LlmLogEntry;
+
+use super::{LlmLogQuery, SchemaKind, parse_record, write_atomic_async};
+
+/// Sits next to `logs/llm/YYYY/MM/DD.jsonl` as `DD.index.json`.
+const INDEX_SUFFIX: &str = ".index.json";
+
+/// What one day of LLM logs contains, so lookups by run or model can skip
+/// days without reading them. `bytes` is the log size the index was built
+/// against; a log that grew or shrank behind its back (older builds, a
+/// crash between the append and the index update) gets its index rebuilt.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct LlmDayIndex {
+    pub entries: usize,
+    pub models: BTreeSet<String>,
+    pub run_ids: BTreeSet<Uuid>,
+    pub bytes: u64,
+}
+
+impl LlmDayIndex {
+    pub fn record(&mut self, entry: &LlmLogEntry) {
+        self.entries += 1;
+        if let Some(model) = &entry.model {
+            self.models.insert(model.clone());
+        }
+        self.run_ids.insert(entry.run_id);
+    }
+
+    /// False only when the day certainly has no entry for the query's run
+    /// or model.
+    pub fn may_contain(&self, query: &LlmLogQuery) -> bool {
+        let run_ok = query
+            .run_id
+            .is_none_or(|run_id| self.run_ids.contains(&run_id));
+        let model_ok = query.model.as_ref().is_none_or(|wanted| {
+            self.models
+                .iter()
+                .any(|model| model.eq_ignore_ascii_case(wanted))
+        });
+        run_ok && model_ok
+    }
+}
+
+/// Day logs under `log_root`, newest first. Years, months and days before
+/// `since` are skipped without being listed.
+pub(super) fn day_logs(
+    log_root: &Path,
+    since: Option<NaiveDate>,
+) -> anyhow::Result<Vec<(NaiveDate, PathBuf)>> {
+    let mut days = Vec::new();
+    for (year, year_dir) in numbered_entries(log_root, None)? {
+        if since.is_some_and(|since| (year as i32) < since.year()) {
+            continue;
+        }
+        for (month, month_dir) in numbered_entries(&year_dir, None)? {
+            if since.is_some_and(|since| (year as i32, month) < (since.year(), since.month())) {
+                continue;
+            }
+            for (day, path) in numbered_entries(&month_dir, Some(".jsonl"))? {
+                let Some(date) = NaiveDate::from_ymd_opt(year as i32, month, day) else {
+                    continue;
+                };
+                if since.is_some_and(|since| date < since) {
+                    continue;
+                }
+                days.push((date, path));
+            }
+        }
+    }
+    days.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    Ok(days)
+}
+
+/// Entries of `dir` named `<number><suffix>`.
+fn numbered_entries(dir: &Path, suffix: Option<&str>) -> anyhow::Result<Vec<(u32, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading llm log dir {:?}", dir))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        let number = match suffix {
+            Some(suffix) => name.strip_suffix(suffix),
+            None => Some(name),
+        };
+        if let Some(number) = number.and_then(|number| number.parse().ok()) {
+            entries.push((number, entry.path()));
+        }
+    }
+    Ok(entries)
+}
+
+pub(super) fn index_path(log_path: &Path) -> PathBuf {
+    let stem = log_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    log_path.with_file_name(format!("{stem}{INDEX_SUFFIX}"))
+}
+
+/// The day's index, rebuilt and saved first when missing or stale.
+pub(super) async fn load_day_index(log_path: &Path) -> anyhow::Result<LlmDayIndex> {
+    let bytes = async_fs::metadata(log_path)
+        .await
+        .with_context(|| format!("reading llm log metadata {:?}", log_path))?
+        .len();
+    if let Some(index) = read_index(log_path).await
+        && index.bytes == bytes
+    {
+        return Ok(index);
+    }
+    rebuild_day_index(log_path).await
+}
+
+/// Fold freshly appended `entries` into the day's index. `previous_bytes`
+/// is the log size before the append; when the index does not match it, the
+/// whole day is re-read instead.
+pub(super) async fn update_day_index(
+    log_path: &Path,
+    entries: &[&LlmLogEntry],
+    previous_bytes: u64,
+) -> anyhow::Result<()> {
+    let Some(mut index) = read_index(log_path)
+        .await
+        .filter(|index| index.bytes == previous_bytes)
+    else {
+        rebuild_day_index(log_path).await?;
+        return Ok(());
+    };
+    for entry in entries {
+        index.record(entry);
+    }
+    index.bytes = async_fs::metadata(log_path).await?.len();
+    write_index(log_path, &index).await
+}
+
+async fn rebuild_day_index(log_path: &Path) -> anyhow::Result<LlmDayIndex> {
+    let content = async_fs::read_to_string(log_path)
+        .await
+        .with_context(|| format!("reading llm log {:?}", log_path))?;
+    let mut index = LlmDayIndex {
+        bytes: content.len() as u64,
+        ..LlmDayIndex::default()
+    };
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let entry: LlmLogEntry = parse_record(SchemaKind::LlmLog, line)
+            .with_context(|| format!("indexing llm log {:?}", log_path))?;
+        index.record(&entry);
+    }
+    write_index(log_path, &index).await?;
+    Ok(index)
+}
+
+async fn read_index(log_path: &Path) -> Option<LlmDayIndex> {
+    let raw = async_fs::read_to_string(index_path(log_path)).await.ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+async fn write_index(log_path: &Path, index: &LlmDayIndex) -> anyhow::Result<()> {
+    write_atomic_async(&index_path(log_path), serde_json::to_vec(index)?).await
+}
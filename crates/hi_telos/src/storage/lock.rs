@@ -0,0 +1,99 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use chrono::Utc;
+
+/// Lock file at the top of the data dir. It is left in place on exit; the
+/// lock itself goes away with the process, so a stale file never blocks a
+/// restart.
+pub const DATA_DIR_LOCK_FILE: &str = ".hi_telos.lock";
+
+/// Exclusive advisory lock on a data dir, held for as long as the value
+/// lives. Keeps a second instance pointed at the same `HI_APP_ROOT` from
+/// writing to the queue and SP index underneath the first.
+#[derive(Debug)]
+pub struct DataDirLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl DataDirLock {
+    pub fn acquire(data_dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(data_dir)
+            .with_context(|| format!("creating data dir {:?}", data_dir))?;
+        let path = data_dir.join(DATA_DIR_LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("opening lock file {:?}", path))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                let holder = holder.trim();
+                let holder = if holder.is_empty() {
+                    "unknown process"
+                } else {
+                    holder
+                };
+                bail!(
+                    "data dir {:?} is already in use by another hi_telos instance ({holder}); \
+                     stop it or point HI_APP_ROOT at a different directory",
+                    data_dir
+                );
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(err).with_context(|| format!("locking {:?}", path));
+            }
+        }
+
+        file.set_len(0)
+            .and_then(|()| file.rewind())
+            .and_then(|()| {
+                writeln!(
+                    file,
+                    "pid {} since {}",
+                    std::process::id(),
+                    Utc::now().to_rfc3339()
+                )
+            })
+            .with_context(|| format!("writing lock file {:?}", path))?;
+
+        Ok(Self { _file: file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn second_lock_on_same_data_dir_fails_until_released() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path().join("data");
+
+        let lock = DataDirLock::acquire(&data_dir).unwrap();
+        let err = DataDirLock::acquire(&data_dir).unwrap_err().to_string();
+        assert!(err.contains("already in use"), "{err}");
+        assert!(
+            err.contains(&format!("pid {}", std::process::id())),
+            "{err}"
+        );
+
+        drop(lock);
+        let relocked = DataDirLock::acquire(&data_dir).unwrap();
+        assert!(relocked.path().exists());
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDateTime, SubsecRound, Utc};
+use serde::Serialize;
+use similar::TextDiff;
+use tokio::fs;
+
+use super::{sanitize_data_relative_path, write_atomic_async};
+
+/// Revisions of `data/<path>.md` live in `data/md_history/<path>.md/`, one
+/// `<id>.rev` file each. The extension keeps them out of the markdown tree.
+pub const MD_HISTORY_DIR: &str = "md_history";
+const REVISION_EXTENSION: &str = "rev";
+const MD_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MarkdownRevision {
+    pub id: String,
+    pub saved_at: DateTime<Utc>,
+    pub bytes: u64,
+}
+
+/// A data-relative markdown path, checked the same way reads are.
+pub fn markdown_relative_path(path: &str) -> Result<PathBuf> {
+    let relative = sanitize_data_relative_path(path)?;
+    if relative.extension().and_then(|ext| ext.to_str()) != Some("md") {
+        bail!("only markdown files have history");
+    }
+    if relative.starts_with(MD_HISTORY_DIR) {
+        bail!("markdown history is not itself versioned");
+    }
+    Ok(relative)
+}
+
+fn history_dir(data_dir: &Path, relative: &Path) -> PathBuf {
+    data_dir.join(MD_HISTORY_DIR).join(relative)
+}
+
+/// Save the file's current content as a revision unless it matches the
+/// latest one. Called before and after every write the app makes, so an
+/// edit made by hand in between is kept too. Returns the new revision, or
+/// `None` when nothing changed or the file does not exist.
+pub async fn record_markdown_revision(
+    data_dir: &Path,
+    relative: &Path,
+    now: DateTime<Utc>,
+) -> Result<Option<MarkdownRevision>> {
+    let current = match fs::read_to_string(data_dir.join(relative)).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("reading markdown {:?}", relative));
+        }
+    };
+
+    let revisions = list_markdown_revisions(data_dir, relative).await?;
+    if let Some(latest) = revisions.first()
+        && read_markdown_revision(data_dir, relative, &latest.id).await? == current
+    {
+        return Ok(None);
+    }
+
+    let dir = history_dir(data_dir, relative);
+    fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("creating markdown history dir {:?}", dir))?;
+    // Ids have microsecond precision; revisions recorded within the same
+    // microsecond would collide.
+    let now = now.trunc_subsecs(6);
+    let saved_at = match revisions.first() {
+        Some(latest) if latest.saved_at >= now => {
+            latest.saved_at + chrono::Duration::microseconds(1)
+        }
+        _ => now,
+    };
+    let id = format_revision_id(saved_at);
+    write_atomic_async(&revision_path(&dir, &id), &current).await?;
+
+    for stale in revisions.iter().skip(MD_HISTORY_LIMIT - 1) {
+        let _ = fs::remove_file(revision_path(&dir, &stale.id)).await;
+    }
+
+    Ok(Some(MarkdownRevision {
+        id,
+        saved_at,
+        bytes: current.len() as u64,
+    }))
+}
+
+/// Revisions of `relative`, newest first.
+pub async fn list_markdown_revisions(
+    data_dir: &Path,
+    relative: &Path,
+) -> Result<Vec<MarkdownRevision>> {
+    let dir = history_dir(data_dir, relative);
+    let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("reading markdown history {:?}", dir));
+        }
+    };
+
+    let mut revisions = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(REVISION_EXTENSION) {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let Ok(saved_at) = parse_revision_id(id) else {
+            continue;
+        };
+        revisions.push(MarkdownRevision {
+            id: id.to_string(),
+            saved_at,
+            bytes: entry.metadata().await?.len(),
+        });
+    }
+    revisions.sort_by_key(|revision| std::cmp::Reverse(revision.saved_at));
+    Ok(revisions)
+}
+
+pub async fn read_markdown_revision(data_dir: &Path, relative: &Path, id: &str) -> Result<String> {
+    parse_revision_id(id)?;
+    let path = revision_path(&history_dir(data_dir, relative), id);
+    fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("reading markdown revision {id} of {:?}", relative))
+}
+
+/// Write revision `id` back over the file, recording the content it
+/// replaces first so a revert can itself be undone. Without `keep_history`
+/// (the disk guard paused it) no revisions are recorded.
+pub async fn revert_markdown(
+    data_dir: &Path,
+    relative: &Path,
+    id: &str,
+    keep_history: bool,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let content = read_markdown_revision(data_dir, relative, id).await?;
+    if keep_history {
+        record_markdown_revision(data_dir, relative, now).await?;
+    }
+    let path = data_dir.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    write_atomic_async(&path, &content).await?;
+    if keep_history {
+        record_markdown_revision(data_dir, relative, now).await?;
+    }
+    Ok(content)
+}
+
+/// Unified diff from `old` to `new`, with three lines of context.
+pub fn diff_markdown(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+fn revision_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.{REVISION_EXTENSION}"))
+}
+
+fn format_revision_id(saved_at: DateTime<Utc>) -> String {
+    saved_at.format("%Y%m%dT%H%M%S%6fZ").to_string()
+}
+
+fn parse_revision_id(id: &str) -> Result<DateTime<Utc>> {
+    let trimmed = id
+        .strip_suffix('Z')
+        .ok_or_else(|| anyhow!("invalid markdown revision id: {id}"))?;
+    let naive = NaiveDateTime::parse_from_str(trimmed, "%Y%m%dT%H%M%S%6f")
+        .with_context(|| format!("invalid markdown revision id: {id}"))?;
+    Ok(DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn records_diffs_and_reverts_revisions() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        let relative = markdown_relative_path("journals/2025/01/01.md").unwrap();
+        let path = data_dir.join(&relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let now = Utc::now();
+
+        assert!(
+            record_markdown_revision(data_dir, &relative, now)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        std::fs::write(&path, "# Day\none\n").unwrap();
+        let first = record_markdown_revision(data_dir, &relative, now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            record_markdown_revision(data_dir, &relative, now)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::write(&path, "# Day\ntwo\n").unwrap();
+        let second = record_markdown_revision(data_dir, &relative, now)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(second.saved_at > first.saved_at);
+        let revisions = list_markdown_revisions(data_dir, &relative).await.unwrap();
+        assert_eq!(revisions, vec![second.clone(), first.clone()]);
+
+        let old = read_markdown_revision(data_dir, &relative, &first.id)
+            .await
+            .unwrap();
+        let diff = diff_markdown(&old, "# Day\ntwo\n", &first.id, "current");
+        assert!(diff.contains("-one\n+two\n"), "{diff}");
+
+        // A hand edit made after the last recorded write survives a revert.
+        std::fs::write(&path, "# Day\nhand edit\n").unwrap();
+        let reverted = revert_markdown(data_dir, &relative, &first.id, true, now)
+            .await
+            .unwrap();
+        assert_eq!(reverted, "# Day\none\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Day\none\n");
+        let revisions = list_markdown_revisions(data_dir, &relative).await.unwrap();
+        assert_eq!(revisions.len(), 4);
+        let hand_edit = read_markdown_revision(data_dir, &relative, &revisions[1].id)
+            .await
+            .unwrap();
+        assert_eq!(hand_edit, "# Day\nhand edit\n");
+
+        assert!(
+            read_markdown_revision(data_dir, &relative, "../../x")
+                .await
+                .is_err()
+        );
+        assert!(markdown_relative_path("sp/index.json").is_err());
+        assert!(markdown_relative_path("md_history/a.md").is_err());
+    }
+}
@@ -1,15 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 
 use anyhow::{Context, anyhow};
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::warn;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-use crate::{agent::AgentOutcome, tasks::Intent};
+use crate::{agent::AgentOutcome, tasks::Intent, text};
+
+use super::{SCHEMA_VERSION, SchemaKind, parse_record, write_atomic_async};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -26,6 +32,8 @@ pub struct MemoryAnchor {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
+    #[serde(default)]
+    pub schema_version: u32,
     pub id: Uuid,
     pub level: MemoryLevel,
     pub summary: String,
@@ -39,6 +47,8 @@ pub struct MemoryEntry {
 
 #[derive(Debug, Clone)]
 pub struct MemorySnapshotInput {
+    /// Id for the new L1 entry, allocated up front so the journal can link to it.
+    pub entry_id: Uuid,
     pub intent: Intent,
     pub outcome: AgentOutcome,
     pub journal_path: PathBuf,
@@ -51,6 +61,36 @@ pub struct MemoryQuery {
     pub limit: usize,
     pub since: Option<DateTime<Utc>>,
     pub tag: Option<String>,
+    /// Case-insensitive substring matched against summary and details.
+    pub text: Option<String>,
+}
+
+/// How aged L1 entries are treated once their daily L2 rollup exists.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryRetentionMode {
+    /// Keep the entries but drop their details, leaving summary/anchors/tags.
+    #[default]
+    Compact,
+    /// Remove the day's L1 file entirely.
+    Delete,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct MemoryRetentionPolicy {
+    pub l1_max_age_days: u64,
+    #[serde(default)]
+    pub mode: MemoryRetentionMode,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct MemoryRetentionReport {
+    pub days_compacted: usize,
+    pub days_deleted: usize,
+    pub days_skipped_without_rollup: usize,
+    /// Days whose rollup predates some of their L1 entries, e.g. an entry
+    /// appended by hand; left alone until the rollup is rebuilt.
+    pub days_skipped_stale_rollup: usize,
 }
 
 impl Default for MemoryQuery {
@@ -60,7 +100,33 @@ impl Default for MemoryQuery {
             limit: 20,
             since: None,
             tag: None,
+            text: None,
+        }
+    }
+}
+
+impl MemoryQuery {
+    fn matches(&self, entry: &MemoryEntry) -> bool {
+        if self.since.is_some_and(|since| entry.created_at < since) {
+            return false;
+        }
+        if let Some(tag) = self.tag.as_ref()
+            && !entry
+                .tags
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(tag))
+        {
+            return false;
+        }
+        if let Some(text) = self.text.as_ref() {
+            let needle = text.to_lowercase();
+            return entry.summary.to_lowercase().contains(&needle)
+                || entry
+                    .details
+                    .iter()
+                    .any(|detail| detail.to_lowercase().contains(&needle));
         }
+        true
     }
 }
 
@@ -68,7 +134,15 @@ pub async fn ingest_memory_snapshot(
     data_dir: &Path,
     input: MemorySnapshotInput,
 ) -> anyhow::Result<MemoryEntry> {
-    let now = Utc::now();
+    ingest_memory_snapshot_at(data_dir, input, Utc::now()).await
+}
+
+/// [`ingest_memory_snapshot`] for a run that finished at `now`.
+pub async fn ingest_memory_snapshot_at(
+    data_dir: &Path,
+    input: MemorySnapshotInput,
+    now: DateTime<Utc>,
+) -> anyhow::Result<MemoryEntry> {
     let mut anchors = Vec::new();
 
     if let Some(history) = input.history_path.as_ref()
@@ -85,7 +159,7 @@ pub async fn ingest_memory_snapshot(
     let summary = format!(
         "{} ⇒ {}",
         input.intent.summary,
-        truncate(&input.outcome.final_answer, 160)
+        text::truncate(&input.outcome.final_answer, 160)
     );
 
     let mut details = Vec::new();
@@ -97,7 +171,8 @@ pub async fn ingest_memory_snapshot(
     }
 
     let entry = MemoryEntry {
-        id: Uuid::new_v4(),
+        schema_version: SCHEMA_VERSION,
+        id: input.entry_id,
         level: MemoryLevel::L1,
         summary,
         details,
@@ -109,11 +184,29 @@ pub async fn ingest_memory_snapshot(
     };
 
     persist_l1_entry(data_dir, &entry).await?;
-    rebuild_l2_for_day(data_dir, now.date_naive()).await?;
+    rebuild_l2_for_day(data_dir, now.date_naive(), now).await?;
 
     Ok(entry)
 }
 
+/// Look up a single entry by id, checking L1 before the daily rollups.
+pub async fn find_memory_entry(data_dir: &Path, id: Uuid) -> anyhow::Result<Option<MemoryEntry>> {
+    if let Some((_, mut entries, index)) = find_l1_entry(data_dir, id).await? {
+        return Ok(Some(entries.swap_remove(index)));
+    }
+
+    let query = MemoryQuery {
+        level: MemoryLevel::L2,
+        limit: usize::MAX,
+        since: None,
+        tag: None,
+        text: None,
+    };
+    Ok(read_l2(data_dir, &query)?
+        .into_iter()
+        .find(|entry| entry.id == id))
+}
+
 pub fn read_memory_entries(
     data_dir: &Path,
     query: MemoryQuery,
@@ -144,26 +237,16 @@ fn read_l1(data_dir: &Path, query: &MemoryQuery) -> anyhow::Result<Vec<MemoryEnt
             if line.trim().is_empty() {
                 continue;
             }
-            let parsed: MemoryEntry = serde_json::from_str(line)
+            let parsed: MemoryEntry = parse_record(SchemaKind::MemoryEntry, line)
                 .with_context(|| format!("parsing memory l1 entry in {:?}", entry.path()))?;
-            if let Some(since) = query.since
-                && parsed.created_at < since
-            {
-                continue;
-            }
-            if let Some(tag) = query.tag.as_ref()
-                && !parsed
-                    .tags
-                    .iter()
-                    .any(|candidate| candidate.eq_ignore_ascii_case(tag))
-            {
+            if !query.matches(&parsed) {
                 continue;
             }
             entries.push(parsed);
         }
     }
 
-    entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
     if entries.len() > query.limit {
         entries.truncate(query.limit);
     }
@@ -184,32 +267,37 @@ fn read_l2(data_dir: &Path, query: &MemoryQuery) -> anyhow::Result<Vec<MemoryEnt
         }
         let content = std::fs::read_to_string(entry.path())
             .with_context(|| format!("reading memory l2 file {:?}", entry.path()))?;
-        let parsed: MemoryEntry = serde_json::from_str(&content)
+        let parsed: MemoryEntry = parse_record(SchemaKind::MemoryEntry, &content)
             .with_context(|| format!("parsing memory l2 entry in {:?}", entry.path()))?;
-        if let Some(since) = query.since
-            && parsed.created_at < since
-        {
-            continue;
-        }
-        if let Some(tag) = query.tag.as_ref()
-            && !parsed
-                .tags
-                .iter()
-                .any(|candidate| candidate.eq_ignore_ascii_case(tag))
-        {
+        if !query.matches(&parsed) {
             continue;
         }
         entries.push(parsed);
     }
 
-    entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
     if entries.len() > query.limit {
         entries.truncate(query.limit);
     }
     Ok(entries)
 }
 
+/// One lock per data dir around every L1 append and rewrite, so an entry
+/// appended during a beat is not lost when a tag edit or retention pass
+/// replaces the day file it read earlier.
+fn l1_lock(data_dir: &Path) -> Arc<AsyncMutex<()>> {
+    static LOCKS: LazyLock<parking_lot::Mutex<HashMap<PathBuf, Arc<AsyncMutex<()>>>>> =
+        LazyLock::new(Default::default);
+    LOCKS
+        .lock()
+        .entry(data_dir.to_path_buf())
+        .or_default()
+        .clone()
+}
+
 async fn persist_l1_entry(data_dir: &Path, entry: &MemoryEntry) -> anyhow::Result<()> {
+    let lock = l1_lock(data_dir);
+    let _guard = lock.lock().await;
     let date = entry.created_at.date_naive();
     let dir = data_dir
         .join("memory/l1")
@@ -230,7 +318,11 @@ async fn persist_l1_entry(data_dir: &Path, entry: &MemoryEntry) -> anyhow::Resul
     Ok(())
 }
 
-async fn rebuild_l2_for_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<()> {
+async fn rebuild_l2_for_day(
+    data_dir: &Path,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
     let l1_path = data_dir
         .join("memory/l1")
         .join(format!("{:04}", date.year()))
@@ -250,7 +342,7 @@ async fn rebuild_l2_for_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<
         if line.trim().is_empty() {
             continue;
         }
-        let entry: MemoryEntry = serde_json::from_str(line)
+        let entry: MemoryEntry = parse_record(SchemaKind::MemoryEntry, line)
             .with_context(|| format!("parsing l1 entry during rollup {:?}", l1_path))?;
         entries.push(entry);
     }
@@ -269,14 +361,14 @@ async fn rebuild_l2_for_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<
         let raw = fs::read_to_string(&existing_path)
             .await
             .with_context(|| format!("reading existing l2 {:?}", existing_path))?;
-        let parsed: MemoryEntry = serde_json::from_str(&raw)
+        let parsed: MemoryEntry = parse_record(SchemaKind::MemoryEntry, &raw)
             .with_context(|| format!("parsing existing l2 {:?}", existing_path))?;
         (parsed.id, parsed.created_at)
     } else {
         (Uuid::new_v4(), entries[0].created_at)
     };
 
-    let updated_at = Utc::now();
+    let updated_at = now;
     let summary = format!("{} memories on {}", entries.len(), date.format("%Y-%m-%d"));
 
     let mut details = Vec::new();
@@ -304,6 +396,7 @@ async fn rebuild_l2_for_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<
     }
 
     let rollup = MemoryEntry {
+        schema_version: SCHEMA_VERSION,
         id: previous_id,
         level: MemoryLevel::L2,
         summary,
@@ -321,8 +414,288 @@ async fn rebuild_l2_for_day(data_dir: &Path, date: NaiveDate) -> anyhow::Result<
         .ok_or_else(|| anyhow!("l2 path missing parent"))?;
     fs::create_dir_all(&dir).await?;
     let serialized = serde_json::to_string_pretty(&rollup)?;
-    fs::write(&existing_path, serialized.as_bytes()).await?;
-    Ok(())
+    write_atomic_async(&existing_path, serialized).await
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MemoryTagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Count how many entries of the given level carry each tag.
+pub fn memory_tag_counts(
+    data_dir: &Path,
+    level: MemoryLevel,
+) -> anyhow::Result<Vec<MemoryTagCount>> {
+    let entries = read_memory_entries(
+        data_dir,
+        MemoryQuery {
+            level,
+            limit: usize::MAX,
+            since: None,
+            tag: None,
+            text: None,
+        },
+    )?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        for tag in entry.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut tags: Vec<MemoryTagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| MemoryTagCount { tag, count })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(tags)
+}
+
+/// Add and remove tags on an L1 entry, then rebuild that day's L2 rollup so
+/// tag-filtered timeline queries see the correction.
+///
+/// Returns `Ok(None)` when no L1 entry carries the given id.
+pub async fn update_memory_tags(
+    data_dir: &Path,
+    id: Uuid,
+    add: &[String],
+    remove: &[String],
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<MemoryEntry>> {
+    let lock = l1_lock(data_dir);
+    let guard = lock.lock().await;
+    let Some((path, mut entries, index)) = find_l1_entry(data_dir, id).await? else {
+        return Ok(None);
+    };
+
+    let removals: HashSet<String> = remove.iter().filter_map(|tag| normalize_tag(tag)).collect();
+    let entry = &mut entries[index];
+    entry
+        .tags
+        .retain(|tag| !removals.contains(&tag.to_lowercase()));
+    for tag in add.iter().filter_map(|tag| normalize_tag(tag)) {
+        if !entry
+            .tags
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&tag))
+        {
+            entry.tags.push(tag);
+        }
+    }
+    entry.updated_at = now;
+    let updated = entry.clone();
+
+    let mut serialized = String::new();
+    for entry in &entries {
+        serialized.push_str(&serde_json::to_string(entry)?);
+        serialized.push('\n');
+    }
+    write_atomic_async(&path, serialized)
+        .await
+        .with_context(|| format!("rewriting l1 file {:?}", path))?;
+    drop(guard);
+    rebuild_l2_for_day(data_dir, updated.created_at.date_naive(), now).await?;
+
+    Ok(Some(updated))
+}
+
+async fn find_l1_entry(
+    data_dir: &Path,
+    id: Uuid,
+) -> anyhow::Result<Option<(PathBuf, Vec<MemoryEntry>, usize)>> {
+    let root = data_dir.join("memory/l1");
+    if !root.exists() {
+        return Ok(None);
+    }
+
+    let files: Vec<PathBuf> = WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+
+    for path in files {
+        let content = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("reading memory l1 file {:?}", path))?;
+        let mut entries = Vec::new();
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: MemoryEntry = parse_record(SchemaKind::MemoryEntry, line)
+                .with_context(|| format!("parsing memory l1 entry in {:?}", path))?;
+            entries.push(entry);
+        }
+        if let Some(index) = entries.iter().position(|entry| entry.id == id) {
+            return Ok(Some((path, entries, index)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn normalize_tag(raw: &str) -> Option<String> {
+    let cleaned = raw.trim().to_lowercase();
+    if cleaned.is_empty() {
+        None
+    } else {
+        Some(cleaned)
+    }
+}
+
+/// Compact or delete L1 day files older than the policy allows.
+///
+/// A day is only touched once its L2 rollup covers every L1 entry of that
+/// day, so the information is still reachable through the timeline; days
+/// without a rollup, or with one that misses entries, are reported and left
+/// alone.
+pub async fn apply_memory_retention(
+    data_dir: &Path,
+    policy: &MemoryRetentionPolicy,
+    now: DateTime<Utc>,
+) -> anyhow::Result<MemoryRetentionReport> {
+    let mut report = MemoryRetentionReport::default();
+    let root = data_dir.join("memory/l1");
+    if !root.exists() {
+        return Ok(report);
+    }
+
+    let cutoff = now.date_naive() - chrono::Duration::days(policy.l1_max_age_days as i64);
+    let mut candidates = Vec::new();
+    for entry in WalkDir::new(&root) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Some(date) = l1_file_date(&root, entry.path())
+            && date < cutoff
+        {
+            candidates.push((date, entry.into_path()));
+        }
+    }
+
+    let lock = l1_lock(data_dir);
+    for (date, path) in candidates {
+        let _guard = lock.lock().await;
+        let rollup = data_dir
+            .join("memory/l2")
+            .join(format!("{:04}", date.year()))
+            .join(format!("{:02}", date.month()))
+            .join(format!("{:02}.json", date.day()));
+        if !rollup.exists() {
+            report.days_skipped_without_rollup += 1;
+            continue;
+        }
+        if !rollup_covers_day(&rollup, &path).await? {
+            report.days_skipped_stale_rollup += 1;
+            continue;
+        }
+
+        match policy.mode {
+            MemoryRetentionMode::Delete => {
+                fs::remove_file(&path)
+                    .await
+                    .with_context(|| format!("removing aged l1 file {:?}", path))?;
+                report.days_deleted += 1;
+            }
+            MemoryRetentionMode::Compact => {
+                if compact_l1_file(&path).await? {
+                    report.days_compacted += 1;
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Whether the L2 rollup at `rollup` was built after every entry in the L1
+/// day file at `l1` and carries all of their anchors, tags and intents.
+async fn rollup_covers_day(rollup: &Path, l1: &Path) -> anyhow::Result<bool> {
+    let raw = fs::read_to_string(rollup)
+        .await
+        .with_context(|| format!("reading l2 rollup {:?}", rollup))?;
+    let rollup: MemoryEntry = parse_record(SchemaKind::MemoryEntry, &raw)
+        .with_context(|| format!("parsing l2 rollup {:?}", rollup))?;
+    let content = fs::read_to_string(l1)
+        .await
+        .with_context(|| format!("reading l1 file {:?}", l1))?;
+
+    let anchors: HashSet<_> = rollup.anchors.iter().collect();
+    let tags: HashSet<_> = rollup.tags.iter().collect();
+    let intents: HashSet<_> = rollup.related_intents.iter().collect();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: MemoryEntry = match parse_record(SchemaKind::MemoryEntry, line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!(path = ?l1, error = ?err, "skipping unparseable l1 entry");
+                continue;
+            }
+        };
+        let covered = entry.updated_at <= rollup.updated_at
+            && entry.anchors.iter().all(|anchor| anchors.contains(anchor))
+            && entry.tags.iter().all(|tag| tags.contains(tag))
+            && entry
+                .related_intents
+                .iter()
+                .all(|intent| intents.contains(intent));
+        if !covered {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn compact_l1_file(path: &Path) -> anyhow::Result<bool> {
+    let content = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("reading l1 file for compaction {:?}", path))?;
+
+    let mut changed = false;
+    let mut compacted = String::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Unparseable lines are kept as they are rather than dropped.
+        let mut entry: MemoryEntry = match parse_record(SchemaKind::MemoryEntry, line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!(path = ?path, error = ?err, "keeping unparseable l1 entry as is");
+                compacted.push_str(line);
+                compacted.push('\n');
+                continue;
+            }
+        };
+        if !entry.details.is_empty() {
+            entry.details.clear();
+            changed = true;
+        }
+        compacted.push_str(&serde_json::to_string(&entry)?);
+        compacted.push('\n');
+    }
+
+    if changed {
+        write_atomic_async(path, compacted).await?;
+    }
+    Ok(changed)
+}
+
+fn l1_file_date(root: &Path, path: &Path) -> Option<NaiveDate> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut parts = relative.iter().filter_map(|part| part.to_str());
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.strip_suffix(".jsonl")?.parse().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day)
 }
 
 fn derive_tags(intent: &Intent) -> Vec<String> {
@@ -342,13 +715,205 @@ fn derive_tags(intent: &Intent) -> Vec<String> {
     tags.into_iter().collect()
 }
 
-fn truncate(value: &str, max: usize) -> String {
-    if value.len() <= max {
-        return value.to_string();
+/// An anchor together with the markdown it points at.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedMemoryAnchor {
+    pub label: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Read the markdown behind each anchor of an entry. Anchors that cannot be
+/// read (moved files, paths outside the data dir) carry an error instead of
+/// failing the whole resolution. With `preview`, content is cut to that many
+/// characters.
+pub async fn resolve_memory_anchors(
+    data_dir: &Path,
+    entry: &MemoryEntry,
+    preview: Option<usize>,
+) -> Vec<ResolvedMemoryAnchor> {
+    let mut resolved = Vec::with_capacity(entry.anchors.len());
+    for anchor in &entry.anchors {
+        let content = match super::sanitize_data_relative_path(&anchor.path) {
+            Ok(relative) => super::read_markdown_file(data_dir, &relative).await,
+            Err(err) => Err(err),
+        };
+        let (content, error) = match content {
+            Ok(content) => match preview {
+                Some(max) => (Some(text::truncate(&content, max)), None),
+                None => (Some(content), None),
+            },
+            Err(err) => (None, Some(err.to_string())),
+        };
+        resolved.push(ResolvedMemoryAnchor {
+            label: anchor.label.clone(),
+            path: anchor.path.clone(),
+            content,
+            error,
+        });
+    }
+    resolved
+}
+
+/// Output format of a memory export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryExportFormat {
+    Markdown,
+    Json,
+}
+
+impl MemoryExportFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "markdown" | "md" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
     }
-    let mut slice = value[..max].to_string();
-    slice.push('…');
-    slice
+}
+
+/// Entries selected for an export, oldest first so the bundle reads
+/// chronologically. Anchor markdown is only read while writing.
+#[derive(Debug, Clone)]
+pub struct MemoryExport {
+    pub level: MemoryLevel,
+    pub entries: Vec<MemoryEntry>,
+}
+
+/// One anchored file in an export, written once however many entries
+/// point at it.
+#[derive(Debug, Serialize)]
+struct ExportedAnchor<'a> {
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Select the entries matching the query for an export.
+pub fn export_memory(data_dir: &Path, query: MemoryQuery) -> anyhow::Result<MemoryExport> {
+    let level = query.level;
+    let mut entries = read_memory_entries(data_dir, query)?;
+    entries.reverse();
+    Ok(MemoryExport { level, entries })
+}
+
+impl MemoryExport {
+    /// Write the entries followed by the markdown behind their anchors. Only
+    /// one anchored file is held in memory at a time.
+    pub fn write_to(
+        &self,
+        data_dir: &Path,
+        format: MemoryExportFormat,
+        exported_at: DateTime<Utc>,
+        writer: impl Write,
+    ) -> anyhow::Result<()> {
+        let mut writer = std::io::BufWriter::new(writer);
+        match format {
+            MemoryExportFormat::Markdown => self.write_markdown(data_dir, &mut writer)?,
+            MemoryExportFormat::Json => self.write_json(data_dir, exported_at, &mut writer)?,
+        }
+        writer.flush().context("flushing memory export")?;
+        Ok(())
+    }
+
+    fn anchor_paths(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.entries
+            .iter()
+            .flat_map(|entry| &entry.anchors)
+            .map(|anchor| anchor.path.as_str())
+            .filter(|path| seen.insert(*path))
+            .collect()
+    }
+
+    fn write_markdown(&self, data_dir: &Path, writer: &mut impl Write) -> anyhow::Result<()> {
+        writeln!(writer, "# Memory export")?;
+        for entry in &self.entries {
+            let level = match entry.level {
+                MemoryLevel::L1 => "L1",
+                MemoryLevel::L2 => "L2",
+            };
+            writeln!(writer, "\n## {} [{}]\n", entry.summary, level)?;
+            writeln!(writer, "- id: {}", entry.id)?;
+            writeln!(writer, "- created_at: {}", entry.created_at.to_rfc3339())?;
+            if !entry.tags.is_empty() {
+                writeln!(writer, "- tags: {}", entry.tags.join(", "))?;
+            }
+            for detail in &entry.details {
+                writeln!(writer, "- {}", detail)?;
+            }
+            for anchor in &entry.anchors {
+                writeln!(writer, "- anchor: {} — {}", anchor.label, anchor.path)?;
+            }
+        }
+
+        let paths = self.anchor_paths();
+        if !paths.is_empty() {
+            writeln!(writer, "\n# Anchors")?;
+        }
+        for path in paths {
+            writeln!(writer, "\n## {}\n", path)?;
+            match read_export_anchor(data_dir, path) {
+                Ok(content) => {
+                    for line in content.lines() {
+                        writeln!(writer, "> {}", line)?;
+                    }
+                }
+                Err(err) => writeln!(writer, "_unresolved: {}_", err)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write_json(
+        &self,
+        data_dir: &Path,
+        exported_at: DateTime<Utc>,
+        writer: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        write!(writer, "{{\"level\":")?;
+        serde_json::to_writer(&mut *writer, &self.level)?;
+        write!(writer, ",\"exported_at\":")?;
+        serde_json::to_writer(&mut *writer, &exported_at)?;
+        write!(writer, ",\"entries\":[")?;
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            serde_json::to_writer(&mut *writer, entry)?;
+        }
+        write!(writer, "],\"anchors\":[")?;
+        for (index, path) in self.anchor_paths().into_iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            let (content, error) = match read_export_anchor(data_dir, path) {
+                Ok(content) => (Some(content), None),
+                Err(err) => (None, Some(err.to_string())),
+            };
+            serde_json::to_writer(
+                &mut *writer,
+                &ExportedAnchor {
+                    path,
+                    content,
+                    error,
+                },
+            )?;
+        }
+        write!(writer, "]}}")?;
+        Ok(())
+    }
+}
+
+fn read_export_anchor(data_dir: &Path, path: &str) -> anyhow::Result<String> {
+    let relative = super::sanitize_data_relative_path(path)?;
+    let file = super::resolve_markdown_file(data_dir, &relative)?;
+    std::fs::read_to_string(&file).with_context(|| format!("reading anchor at {:?}", relative))
 }
 
 fn to_anchor(data_dir: &Path, label: &str, path: &Path) -> Option<MemoryAnchor> {
@@ -379,6 +944,8 @@ mod tests {
             summary: "Draft weekly report".to_string(),
             telos_alignment: 0.9,
             created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
             storage_path: None,
         };
         let outcome = AgentOutcome {
@@ -386,6 +953,9 @@ mod tests {
                 thought: "review context".to_string(),
                 action: "summarize".to_string(),
                 observation: "Wrote outline".to_string(),
+                question: None,
+                input: None,
+                sources: Vec::new(),
             }],
             final_answer: "Outlined next steps".to_string(),
         };
@@ -409,6 +979,7 @@ mod tests {
         ingest_memory_snapshot(
             data_dir,
             MemorySnapshotInput {
+                entry_id: Uuid::new_v4(),
                 intent: intent.clone(),
                 outcome: outcome.clone(),
                 journal_path: journal_path.clone(),
@@ -425,6 +996,7 @@ mod tests {
                 limit: 10,
                 since: None,
                 tag: None,
+                text: None,
             },
         )
         .expect("read l1");
@@ -436,6 +1008,22 @@ mod tests {
                 .any(|anchor| anchor.path.contains("intent/history"))
         );
 
+        let search = |text: &str| {
+            read_memory_entries(
+                data_dir,
+                MemoryQuery {
+                    level: MemoryLevel::L1,
+                    text: Some(text.to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("search l1")
+            .len()
+        };
+        assert_eq!(search("WEEKLY report"), 1);
+        assert_eq!(search("wrote outline"), 1);
+        assert_eq!(search("quarterly"), 0);
+
         let l2_entries = read_memory_entries(
             data_dir,
             MemoryQuery {
@@ -443,6 +1031,7 @@ mod tests {
                 limit: 10,
                 since: None,
                 tag: None,
+                text: None,
             },
         )
         .expect("read l2");
@@ -450,4 +1039,362 @@ mod tests {
         assert_eq!(l2_entries[0].level, MemoryLevel::L2);
         assert!(!l2_entries[0].details.is_empty());
     }
+
+    async fn write_l1_day(data_dir: &Path, date: NaiveDate, with_rollup: bool) -> PathBuf {
+        let created_at = date.and_hms_opt(12, 0, 0).unwrap().and_utc();
+        let entry = MemoryEntry {
+            schema_version: SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            level: MemoryLevel::L1,
+            summary: "Aged memory".to_string(),
+            details: vec!["Final: done".to_string()],
+            anchors: Vec::new(),
+            tags: vec!["aged".to_string()],
+            related_intents: Vec::new(),
+            created_at,
+            updated_at: created_at,
+        };
+        persist_l1_entry(data_dir, &entry)
+            .await
+            .expect("persist l1");
+        if with_rollup {
+            rebuild_l2_for_day(data_dir, date, created_at)
+                .await
+                .expect("rollup");
+        }
+        data_dir
+            .join("memory/l1")
+            .join(format!("{:04}", date.year()))
+            .join(format!("{:02}", date.month()))
+            .join(format!("{:02}.jsonl", date.day()))
+    }
+
+    #[tokio::test]
+    async fn export_resolves_anchor_content() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let journal = data_dir.join("journals/2025/01/01.md");
+        fs::create_dir_all(journal.parent().unwrap())
+            .await
+            .expect("journal dir");
+        fs::write(&journal, "## 09:00 — Plan\nFinal answer: ok")
+            .await
+            .expect("journal");
+
+        let created_at = Utc::now();
+        let entry = MemoryEntry {
+            schema_version: SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            level: MemoryLevel::L1,
+            summary: "Plan ⇒ ok".to_string(),
+            details: vec!["Source: cli".to_string()],
+            anchors: vec![
+                MemoryAnchor {
+                    label: "journals".to_string(),
+                    path: "journals/2025/01/01.md".to_string(),
+                },
+                MemoryAnchor {
+                    label: "intent/history".to_string(),
+                    path: "intent/history/missing.md".to_string(),
+                },
+            ],
+            tags: vec!["plan".to_string()],
+            related_intents: Vec::new(),
+            created_at,
+            updated_at: created_at,
+        };
+        persist_l1_entry(data_dir, &entry).await.expect("persist");
+        let follow_up = MemoryEntry {
+            id: Uuid::new_v4(),
+            summary: "Review ⇒ ok".to_string(),
+            anchors: vec![entry.anchors[0].clone()],
+            ..entry.clone()
+        };
+        persist_l1_entry(data_dir, &follow_up)
+            .await
+            .expect("persist follow-up");
+
+        let export = export_memory(
+            data_dir,
+            MemoryQuery {
+                level: MemoryLevel::L1,
+                ..Default::default()
+            },
+        )
+        .expect("export");
+        assert_eq!(export.entries.len(), 2);
+
+        let mut markdown = Vec::new();
+        export
+            .write_to(
+                data_dir,
+                MemoryExportFormat::Markdown,
+                created_at,
+                &mut markdown,
+            )
+            .expect("markdown");
+        let markdown = String::from_utf8(markdown).expect("utf8");
+        assert!(markdown.contains("## Plan ⇒ ok [L1]"));
+        assert!(markdown.contains("- anchor: journals — journals/2025/01/01.md"));
+        assert_eq!(markdown.matches("> Final answer: ok").count(), 1);
+        assert!(markdown.contains("_unresolved:"));
+
+        let mut json = Vec::new();
+        export
+            .write_to(data_dir, MemoryExportFormat::Json, created_at, &mut json)
+            .expect("json");
+        let json: serde_json::Value = serde_json::from_slice(&json).expect("valid json");
+        assert_eq!(json["level"], "L1");
+        assert_eq!(json["entries"].as_array().unwrap().len(), 2);
+        let anchors = json["anchors"].as_array().unwrap();
+        assert_eq!(anchors.len(), 2);
+        assert!(
+            anchors[0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("Final answer: ok")
+        );
+        assert!(anchors[1]["content"].is_null());
+        assert!(anchors[1]["error"].is_string());
+
+        let found = find_memory_entry(data_dir, entry.id)
+            .await
+            .expect("find")
+            .expect("entry");
+        let previews = resolve_memory_anchors(data_dir, &found, Some(5)).await;
+        assert_eq!(previews[0].content.as_deref(), Some("## 0…"));
+    }
+
+    #[tokio::test]
+    async fn tag_updates_rewrite_l1_and_refresh_rollup() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let date = Utc::now().date_naive();
+        write_l1_day(data_dir, date, true).await;
+
+        let entries = read_memory_entries(
+            data_dir,
+            MemoryQuery {
+                level: MemoryLevel::L1,
+                ..Default::default()
+            },
+        )
+        .expect("read l1");
+        let id = entries[0].id;
+
+        let updated = update_memory_tags(
+            data_dir,
+            id,
+            &["Roadmap".to_string()],
+            &["AGED".to_string()],
+            Utc::now(),
+        )
+        .await
+        .expect("update tags")
+        .expect("entry exists");
+        assert_eq!(updated.tags, vec!["roadmap".to_string()]);
+
+        let counts = memory_tag_counts(data_dir, MemoryLevel::L2).expect("l2 counts");
+        assert_eq!(
+            counts,
+            vec![MemoryTagCount {
+                tag: "roadmap".to_string(),
+                count: 1
+            }]
+        );
+
+        let missing = update_memory_tags(data_dir, Uuid::new_v4(), &[], &[], Utc::now())
+            .await
+            .expect("lookup");
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn appends_during_tag_updates_are_kept() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path().to_path_buf();
+        let date = Utc::now().date_naive();
+        let path = write_l1_day(&data_dir, date, false).await;
+        let id = read_memory_entries(
+            &data_dir,
+            MemoryQuery {
+                level: MemoryLevel::L1,
+                ..Default::default()
+            },
+        )
+        .expect("read l1")[0]
+            .id;
+
+        let appends = {
+            let data_dir = data_dir.clone();
+            tokio::spawn(async move {
+                for _ in 0..20 {
+                    write_l1_day(&data_dir, date, false).await;
+                }
+            })
+        };
+        for round in 0..20 {
+            update_memory_tags(&data_dir, id, &[format!("round{round}")], &[], Utc::now())
+                .await
+                .expect("update tags")
+                .expect("entry");
+        }
+        appends.await.expect("appends");
+
+        let content = std::fs::read_to_string(&path).expect("l1 file");
+        assert_eq!(content.lines().count(), 21);
+    }
+
+    #[tokio::test]
+    async fn retention_only_touches_rolled_up_days_past_cutoff() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let now = Utc::now();
+
+        let old_rolled = write_l1_day(
+            data_dir,
+            (now - chrono::Duration::days(40)).date_naive(),
+            true,
+        )
+        .await;
+        let old_pending = write_l1_day(
+            data_dir,
+            (now - chrono::Duration::days(41)).date_naive(),
+            false,
+        )
+        .await;
+        let fresh = write_l1_day(data_dir, now.date_naive(), true).await;
+
+        let policy = MemoryRetentionPolicy {
+            l1_max_age_days: 30,
+            mode: MemoryRetentionMode::Delete,
+        };
+        let report = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention");
+
+        assert_eq!(report.days_deleted, 1);
+        assert_eq!(report.days_skipped_without_rollup, 1);
+        assert!(!old_rolled.exists());
+        assert!(old_pending.exists());
+        assert!(fresh.exists());
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_days_the_rollup_does_not_cover() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let now = Utc::now();
+        let date = (now - chrono::Duration::days(40)).date_naive();
+        let path = write_l1_day(data_dir, date, true).await;
+
+        // An entry added after the rollup was built, e.g. by hand.
+        let later = date.and_hms_opt(18, 0, 0).unwrap().and_utc();
+        let straggler = MemoryEntry {
+            schema_version: SCHEMA_VERSION,
+            id: Uuid::new_v4(),
+            level: MemoryLevel::L1,
+            summary: "Late memory".to_string(),
+            details: Vec::new(),
+            anchors: Vec::new(),
+            tags: vec!["late".to_string()],
+            related_intents: vec![Uuid::new_v4()],
+            created_at: later,
+            updated_at: later,
+        };
+        persist_l1_entry(data_dir, &straggler)
+            .await
+            .expect("persist l1");
+
+        let policy = MemoryRetentionPolicy {
+            l1_max_age_days: 30,
+            mode: MemoryRetentionMode::Delete,
+        };
+        let report = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention");
+        assert_eq!(report.days_deleted, 0);
+        assert_eq!(report.days_skipped_stale_rollup, 1);
+        assert!(path.exists());
+
+        rebuild_l2_for_day(data_dir, date, later)
+            .await
+            .expect("rollup");
+        let report = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention");
+        assert_eq!(report.days_deleted, 1);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn retention_skips_unparseable_l1_lines() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let now = Utc::now();
+        let broken = write_l1_day(
+            data_dir,
+            (now - chrono::Duration::days(40)).date_naive(),
+            true,
+        )
+        .await;
+        let clean = write_l1_day(
+            data_dir,
+            (now - chrono::Duration::days(41)).date_naive(),
+            true,
+        )
+        .await;
+        let mut content = std::fs::read_to_string(&broken).expect("l1 file");
+        content.push_str("{not json\n");
+        std::fs::write(&broken, content).expect("corrupt l1");
+
+        let policy = MemoryRetentionPolicy {
+            l1_max_age_days: 30,
+            mode: MemoryRetentionMode::Compact,
+        };
+        let report = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention");
+        assert_eq!(report.days_compacted, 2);
+        let compacted = std::fs::read_to_string(&broken).expect("l1 file");
+        assert!(compacted.contains("{not json"));
+        assert!(!compacted.contains("Final: done"));
+        assert!(
+            !std::fs::read_to_string(&clean)
+                .unwrap()
+                .contains("Final: done")
+        );
+    }
+
+    #[tokio::test]
+    async fn retention_compact_mode_strips_details() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        let now = Utc::now();
+        let path = write_l1_day(
+            data_dir,
+            (now - chrono::Duration::days(10)).date_naive(),
+            true,
+        )
+        .await;
+
+        let policy = MemoryRetentionPolicy {
+            l1_max_age_days: 7,
+            mode: MemoryRetentionMode::Compact,
+        };
+        let report = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention");
+        assert_eq!(report.days_compacted, 1);
+
+        let raw = fs::read_to_string(&path).await.expect("compacted file");
+        let entry: MemoryEntry = serde_json::from_str(raw.trim()).expect("entry");
+        assert!(entry.details.is_empty());
+        assert_eq!(entry.summary, "Aged memory");
+
+        let again = apply_memory_retention(data_dir, &policy, now)
+            .await
+            .expect("retention rerun");
+        assert_eq!(again.days_compacted, 0);
+    }
 }
@@ -0,0 +1,318 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use walkdir::WalkDir;
+
+use super::write_atomic;
+
+/// Version stamped into every versioned record written by this build.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Records the version the data dir was last migrated to.
+pub const SCHEMA_MARKER_FILE: &str = "schema.json";
+
+/// Persisted JSON formats that carry a `schema_version` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    SpIndex,
+    MemoryEntry,
+    StructuredSnapshot,
+    LlmLog,
+}
+
+/// One format change. `apply` upgrades a record of the previous version in
+/// place; the version stamp is handled by [`upgrade_record`].
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(SchemaKind, &mut Map<String, Value>),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "stamp schema_version; wrap bare structured text snapshots",
+        apply: migrate_v1,
+    },
+    Migration {
+        version: 2,
+        description: "merge the SP top-used and most-recent lists into entries",
+        apply: migrate_v2,
+    },
+];
+
+fn migrate_v1(kind: SchemaKind, record: &mut Map<String, Value>) {
+    // The first previews were saved as the bare content, without a note.
+    if kind == SchemaKind::StructuredSnapshot && !record.contains_key("content") {
+        let content = std::mem::take(record);
+        record.insert("content".to_string(), Value::Object(content));
+    }
+}
+
+fn migrate_v2(kind: SchemaKind, record: &mut Map<String, Value>) {
+    if kind != SchemaKind::SpIndex {
+        return;
+    }
+    // v1 kept two capped lists of `{summary, count, last_seen}`; the
+    // top-used counts are authoritative for summaries in both.
+    let mut entries: Vec<Map<String, Value>> = Vec::new();
+    for list in ["top_used", "most_recent"] {
+        let Some(Value::Array(items)) = record.remove(list) else {
+            continue;
+        };
+        for item in items {
+            let Value::Object(item) = item else {
+                continue;
+            };
+            let Some(summary) = item.get("summary").and_then(Value::as_str) else {
+                continue;
+            };
+            if entries
+                .iter()
+                .any(|entry| entry.get("summary").and_then(Value::as_str) == Some(summary))
+            {
+                continue;
+            }
+            let count = item.get("count").and_then(Value::as_u64).unwrap_or(1);
+            let last_seen = item.get("last_seen").cloned().unwrap_or(Value::Null);
+            let mut entry = Map::new();
+            entry.insert("id".to_string(), Value::from(super::sp_entry_id(summary)));
+            entry.insert("category".to_string(), Value::from("uncategorized"));
+            entry.insert("summary".to_string(), Value::from(summary));
+            entry.insert("count".to_string(), Value::from(count));
+            entry.insert("score".to_string(), Value::from(count as f64));
+            entry.insert("first_seen".to_string(), last_seen.clone());
+            entry.insert("last_seen".to_string(), last_seen);
+            entries.push(entry);
+        }
+    }
+    record
+        .entry("entries")
+        .or_insert_with(|| Value::Array(entries.into_iter().map(Value::Object).collect()));
+}
+
+/// Bring `record` up to [`SCHEMA_VERSION`]. Records without a version
+/// predate versioning and count as version 0. Returns whether anything
+/// changed; fails for records written by a newer build.
+pub fn upgrade_record(kind: SchemaKind, record: &mut Value) -> anyhow::Result<bool> {
+    let Some(object) = record.as_object_mut() else {
+        bail!("{kind:?} record is not a JSON object");
+    };
+    let version = match object.get("schema_version") {
+        Some(value) => value
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .with_context(|| format!("invalid schema_version {value} in {kind:?} record"))?,
+        None => 0,
+    };
+    if version > SCHEMA_VERSION {
+        bail!(
+            "{kind:?} record has schema_version {version}, newer than supported {SCHEMA_VERSION}"
+        );
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|step| step.version > version) {
+        (migration.apply)(kind, object);
+    }
+    object.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    Ok(true)
+}
+
+/// Parse a versioned record, upgrading older formats on the way.
+pub fn parse_record<T: DeserializeOwned>(kind: SchemaKind, raw: &str) -> anyhow::Result<T> {
+    let mut value: Value = serde_json::from_str(raw)?;
+    upgrade_record(kind, &mut value)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaMarker {
+    schema_version: u32,
+    migrated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub files_upgraded: usize,
+}
+
+/// Rewrite every versioned file in the data dir that is older than
+/// [`SCHEMA_VERSION`], then record the new version in `data/schema.json`.
+/// Cheap when the marker is already current. Refuses to touch a data dir
+/// written by a newer build.
+pub fn migrate_data_dir(data_dir: &Path) -> anyhow::Result<MigrationReport> {
+    let marker_path = data_dir.join(SCHEMA_MARKER_FILE);
+    let from = if marker_path.exists() {
+        let raw = fs::read_to_string(&marker_path)
+            .with_context(|| format!("reading schema marker {:?}", marker_path))?;
+        serde_json::from_str::<SchemaMarker>(&raw)
+            .with_context(|| format!("parsing schema marker {:?}", marker_path))?
+            .schema_version
+    } else {
+        0
+    };
+    if from > SCHEMA_VERSION {
+        bail!(
+            "data dir {:?} was written with schema version {from}, but this build only \
+             supports up to {SCHEMA_VERSION}; upgrade hi_telos",
+            data_dir
+        );
+    }
+
+    let mut report = MigrationReport {
+        from,
+        to: SCHEMA_VERSION,
+        files_upgraded: 0,
+    };
+    if from == SCHEMA_VERSION {
+        return Ok(report);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|step| step.version > from) {
+        tracing::info!(
+            version = migration.version,
+            description = migration.description,
+            "migrating data dir"
+        );
+    }
+    for (kind, path, lines) in versioned_files(data_dir) {
+        if upgrade_file(kind, &path, lines)? {
+            report.files_upgraded += 1;
+        }
+    }
+
+    let marker = SchemaMarker {
+        schema_version: SCHEMA_VERSION,
+        migrated_at: Utc::now(),
+    };
+    write_atomic(&marker_path, serde_json::to_vec_pretty(&marker)?)
+        .with_context(|| format!("writing schema marker {:?}", marker_path))?;
+    Ok(report)
+}
+
+/// Every versioned file as `(kind, path, one record per line)`.
+fn versioned_files(data_dir: &Path) -> Vec<(SchemaKind, PathBuf, bool)> {
+    let walk = |dir: &str, extension: &str| -> Vec<PathBuf> {
+        WalkDir::new(data_dir.join(dir))
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(extension))
+            .collect()
+    };
+
+    let mut files = Vec::new();
+    let sp_index = data_dir.join("sp/index.json");
+    if sp_index.exists() {
+        files.push((SchemaKind::SpIndex, sp_index, false));
+    }
+    let preview = data_dir.join("mock/text_structure.json");
+    if preview.exists() {
+        files.push((SchemaKind::StructuredSnapshot, preview, false));
+    }
+    for path in walk("mock/text_structure_history", "json") {
+        files.push((SchemaKind::StructuredSnapshot, path, false));
+    }
+    for path in walk("memory/l1", "jsonl") {
+        files.push((SchemaKind::MemoryEntry, path, true));
+    }
+    for path in walk("memory/l2", "json") {
+        files.push((SchemaKind::MemoryEntry, path, false));
+    }
+    for path in walk("logs/llm", "jsonl") {
+        files.push((SchemaKind::LlmLog, path, true));
+    }
+    files
+}
+
+fn upgrade_file(kind: SchemaKind, path: &Path, lines: bool) -> anyhow::Result<bool> {
+    let raw = fs::read_to_string(path).with_context(|| format!("reading {:?}", path))?;
+    let mut changed = false;
+    let rewritten = if lines {
+        let mut rewritten = String::new();
+        for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+            let mut value: Value =
+                serde_json::from_str(line).with_context(|| format!("parsing {:?}", path))?;
+            changed |= upgrade_record(kind, &mut value)
+                .with_context(|| format!("upgrading {:?}", path))?;
+            rewritten.push_str(&serde_json::to_string(&value)?);
+            rewritten.push('\n');
+        }
+        rewritten
+    } else {
+        let mut value: Value =
+            serde_json::from_str(&raw).with_context(|| format!("parsing {:?}", path))?;
+        changed |=
+            upgrade_record(kind, &mut value).with_context(|| format!("upgrading {:?}", path))?;
+        serde_json::to_string_pretty(&value)?
+    };
+
+    if changed {
+        write_atomic(path, rewritten)?;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn upgrades_legacy_records_and_rejects_newer_ones() {
+        let mut legacy = json!({"title": "T", "summary": "S", "sections": []});
+        assert!(upgrade_record(SchemaKind::StructuredSnapshot, &mut legacy).unwrap());
+        assert_eq!(legacy["content"]["title"], "T");
+        assert_eq!(legacy["schema_version"], SCHEMA_VERSION);
+        assert!(!upgrade_record(SchemaKind::StructuredSnapshot, &mut legacy).unwrap());
+
+        let mut newer = json!({"schema_version": SCHEMA_VERSION + 1});
+        assert!(upgrade_record(SchemaKind::SpIndex, &mut newer).is_err());
+    }
+
+    #[test]
+    fn migrates_data_dir_once() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        let log = data_dir.join("logs/llm/2025/01/01.jsonl");
+        fs::create_dir_all(log.parent().unwrap()).unwrap();
+        fs::write(&log, "{\"phase\":\"a\"}\n\n{\"phase\":\"b\"}\n").unwrap();
+        fs::create_dir_all(data_dir.join("sp")).unwrap();
+        fs::write(data_dir.join("sp/index.json"), "{\"top_used\": []}").unwrap();
+
+        let report = migrate_data_dir(data_dir).unwrap();
+        assert_eq!(report.from, 0);
+        assert_eq!(report.files_upgraded, 2);
+        let migrated = fs::read_to_string(&log).unwrap();
+        assert_eq!(migrated.lines().count(), 2);
+        assert!(
+            migrated
+                .lines()
+                .all(|line| line.contains(&format!("\"schema_version\":{SCHEMA_VERSION}")))
+        );
+
+        let again = migrate_data_dir(data_dir).unwrap();
+        assert_eq!(again.from, SCHEMA_VERSION);
+        assert_eq!(again.files_upgraded, 0);
+
+        fs::write(
+            data_dir.join(SCHEMA_MARKER_FILE),
+            json!({"schema_version": SCHEMA_VERSION + 1, "migrated_at": Utc::now()}).to_string(),
+        )
+        .unwrap();
+        assert!(migrate_data_dir(data_dir).is_err());
+    }
+}
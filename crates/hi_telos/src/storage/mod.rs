@@ -1,9 +1,9 @@
-use std::io::{BufRead, BufReader};
+use std::collections::BTreeMap;
 use std::path::{Component, Path, PathBuf};
 use std::{fmt::Write, fs, str::FromStr};
 
 use anyhow::{Context, anyhow};
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -12,38 +12,218 @@ use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-use crate::{agent::AgentOutcome, llm::LlmLogEntry, tasks::Intent};
+use crate::{
+    agent::AgentOutcome,
+    github,
+    llm::LlmLogEntry,
+    tasks::{
+        DURATION_MS_KEY, ESTIMATED_TOKENS_KEY, EXPERIMENT_KEY, EXPERIMENT_VARIANT_KEY,
+        FAILED_AT_KEY, FAILED_RUNS_KEY, FAILURE_CLASS_KEY, FAILURE_ERROR_KEY, HOLD_REASON_KEY,
+        INTENT_APPROVED_KEY, Intent, IntentState, IntentTransition, LLM_LATENCY_MS_KEY,
+        PROMPT_VERSION_KEY, REPLAY_OF_KEY, SLA_ALERTED_AT_KEY,
+    },
+    text,
+};
 
+mod atomic;
+mod clarification;
+mod disk;
+mod experiments;
+mod failures;
+mod journals;
+mod llm_index;
+mod lock;
+mod md_history;
 mod memory;
+mod migrations;
+mod outbox;
+mod retention;
+mod review;
+mod runs;
+mod seen;
+mod sp;
+mod stats;
 mod structured_text;
+mod tail;
+mod telegram;
+mod walk;
+mod webhooks;
+pub use atomic::{write_atomic, write_atomic_async};
+pub use clarification::{
+    PendingQuestion, answer_chat_question, answer_pending_question, clear_pending_question,
+    list_pending_questions, load_pending_question, park_intent_for_question,
+    requeue_answered_intent,
+};
+pub use disk::{DirUsage, DiskSpace, StorageUsage, disk_space, storage_usage};
+pub use experiments::{ExperimentReport, VariantOutcome, load_experiment_reports};
+pub use failures::{
+    FailedIntent, FailureClass, FailureClassSummary, FailureReport, IntentFailure,
+    classify_failure, load_failure_report,
+};
+pub use journals::{JournalDay, JournalEntry, list_journal_days, load_journal_day};
+pub use lock::{DATA_DIR_LOCK_FILE, DataDirLock};
+pub use md_history::{
+    MD_HISTORY_DIR, MarkdownRevision, diff_markdown, list_markdown_revisions,
+    markdown_relative_path, read_markdown_revision, record_markdown_revision, revert_markdown,
+};
 pub use memory::{
-    MemoryAnchor, MemoryEntry, MemoryLevel, MemoryQuery, MemorySnapshotInput,
-    ingest_memory_snapshot, read_memory_entries,
+    MemoryAnchor, MemoryEntry, MemoryExport, MemoryExportFormat, MemoryLevel, MemoryQuery,
+    MemoryRetentionMode, MemoryRetentionPolicy, MemoryRetentionReport, MemorySnapshotInput,
+    MemoryTagCount, ResolvedMemoryAnchor, apply_memory_retention, export_memory, find_memory_entry,
+    ingest_memory_snapshot, ingest_memory_snapshot_at, memory_tag_counts, read_memory_entries,
+    resolve_memory_anchors, update_memory_tags,
+};
+pub use migrations::{
+    MigrationReport, SCHEMA_MARKER_FILE, SCHEMA_VERSION, SchemaKind, migrate_data_dir,
+    parse_record, upgrade_record,
+};
+pub use outbox::{
+    OutboxMessage, OutboxStatus, OutboxTarget, due_outbox_messages, list_outbox_messages,
+    load_outbox_message, save_outbox_message,
+};
+pub use retention::{
+    CategoryRetentionReport, PruneReason, PrunedFile, RetentionCategory, RetentionPolicy,
+    RetentionReport, RetentionRule, apply_retention,
+};
+pub use review::{
+    WEEKLY_REVIEW_SOURCE, WeeklyStats, collect_weekly_stats, create_weekly_review_intent,
+};
+pub use runs::{
+    RUNS_DIR, RunArtifacts, RunCall, RunManifest, RunStatus, load_run_manifest, run_artifact_path,
+    run_dir, write_run_artifacts,
+};
+pub use seen::{SeenState, load_seen_state, save_seen_state};
+pub use sp::{
+    SP_DECAY_HALF_LIFE_DAYS, SpEntry, SpEntryPage, SpEntryQuery, SpIndex, SpPrecedent,
+    SpRecallPolicy, SpSort, find_sp_precedents, load_sp_entries, load_sp_index, read_sp_entries,
+    sp_entry_id, sp_index_path, summarize_sp_index, update_sp_index,
+};
+pub use stats::{
+    AlignmentBucket, DailyCount, DurationStats, IntentDuration, OutcomeCounts, RunStats,
+    SourceCount, StatsSnapshot, compute_stats, load_stats,
 };
 pub use structured_text::{
-    LoadedStructuredTextPreview, StructuredContent, StructuredSection, StructuredTextHistoryEntry,
-    StructuredTextHistoryFilters, delete_structured_text_preview, list_structured_text_history,
-    load_structured_text_history_entry, load_structured_text_preview,
+    DEFAULT_PREVIEW_NAME, LoadedStructuredTextPreview, STRUCTURED_TEXT_MAX_DEPTH,
+    STRUCTURED_TEXT_MAX_SECTIONS, StructuredContent, StructuredSection, StructuredTextHistoryEntry,
+    StructuredTextHistoryFilters, StructuredTextHistoryPage, StructuredTextPreviewSummary,
+    StructuredTextViolation, delete_structured_text_preview, list_structured_text_history,
+    list_structured_text_history_page, list_structured_text_previews,
+    load_structured_text_history_entry, load_structured_text_preview, normalize_preview_name,
     restore_structured_text_preview_from_history, save_structured_text_preview,
+    structured_content_from_markdown,
 };
+pub use telegram::{TelegramUpdateState, load_telegram_update_state, save_telegram_update_state};
+pub use walk::{WalkPolicy, walk, wildcard_match};
+pub use webhooks::{WebhookDelivery, append_webhook_delivery, read_webhook_deliveries};
 
 const REQUIRED_DIRS: &[&str] = &[
     "intent/inbox",
     "intent/queue",
     "intent/queue/failed",
     "intent/inbox/deferred",
+    "intent/pending_approval",
     "intent/history",
+    "intent/waiting",
     "journals",
     "sp",
     "logs/llm",
     "mock",
     "mock/text_structure_history",
     "messages",
+    "outbox",
     "memory",
     "memory/l1",
     "memory/l2",
 ];
 
+/// Marker a process without an orchestrator (the `server` role) leaves in
+/// the data dir to ask the worker sharing it for a beat.
+pub const BEAT_REQUEST_FILE: &str = ".beat_request";
+
+pub fn request_beat(data_dir: &Path, at: DateTime<Utc>) -> anyhow::Result<()> {
+    let path = data_dir.join(BEAT_REQUEST_FILE);
+    fs::write(&path, format!("{}\n", at.to_rfc3339()))
+        .with_context(|| format!("writing beat request {:?}", path))
+}
+
+/// Clear a pending [`request_beat`], returning whether there was one.
+pub fn take_beat_request(data_dir: &Path) -> anyhow::Result<bool> {
+    let path = data_dir.join(BEAT_REQUEST_FILE);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err).with_context(|| format!("removing beat request {:?}", path)),
+    }
+}
+
+/// Finish time of the worker's latest beat, for `server` role processes
+/// that report on it without running beats themselves.
+pub const LAST_BEAT_FILE: &str = ".last_beat";
+
+pub fn record_last_beat(data_dir: &Path, at: DateTime<Utc>) -> anyhow::Result<()> {
+    write_atomic(
+        &data_dir.join(LAST_BEAT_FILE),
+        format!("{}\n", at.to_rfc3339()),
+    )
+}
+
+/// `None` before the first beat or when the file cannot be read.
+pub fn load_last_beat(data_dir: &Path) -> Option<DateTime<Utc>> {
+    let content = fs::read_to_string(data_dir.join(LAST_BEAT_FILE)).ok()?;
+    DateTime::parse_from_rfc3339(content.trim())
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Ids of the intents a human pinned to the head of the queue, one per
+/// line, so the order survives a restart.
+pub const QUEUE_ORDER_FILE: &str = ".queue_order";
+
+pub fn save_queue_order(data_dir: &Path, ids: &[Uuid]) -> anyhow::Result<()> {
+    let content: String = ids.iter().map(|id| format!("{id}\n")).collect();
+    write_atomic(&data_dir.join(QUEUE_ORDER_FILE), content)
+}
+
+/// Empty when nothing was pinned or the file cannot be read; lines that are
+/// not ids are skipped.
+pub fn load_queue_order(data_dir: &Path) -> Vec<Uuid> {
+    fs::read_to_string(data_dir.join(QUEUE_ORDER_FILE))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Estimated tokens of the intents queued on one UTC day, as `<day>
+/// <tokens>`, for `beat.approval.daily_estimated_tokens`.
+pub const QUEUED_TOKENS_FILE: &str = ".queued_tokens";
+
+/// Tokens queued on `day`; `0` once the file is from another day or
+/// cannot be read.
+pub fn load_queued_tokens(data_dir: &Path, day: NaiveDate) -> u64 {
+    let Ok(content) = fs::read_to_string(data_dir.join(QUEUED_TOKENS_FILE)) else {
+        return 0;
+    };
+    content
+        .split_once(' ')
+        .filter(|(recorded, _)| recorded.trim() == day.to_string())
+        .and_then(|(_, tokens)| tokens.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Add `tokens` to the count for `day`, starting over on a new day.
+pub fn add_queued_tokens(data_dir: &Path, day: NaiveDate, tokens: u64) -> anyhow::Result<u64> {
+    let total = load_queued_tokens(data_dir, day).saturating_add(tokens);
+    write_atomic(
+        &data_dir.join(QUEUED_TOKENS_FILE),
+        format!("{day} {total}\n"),
+    )?;
+    Ok(total)
+}
+
 pub fn ensure_data_layout(data_dir: &Path) -> anyhow::Result<()> {
     for dir in REQUIRED_DIRS {
         let path = data_dir.join(dir);
@@ -52,6 +232,15 @@ pub fn ensure_data_layout(data_dir: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Directories `ensure_data_layout` would create.
+pub fn missing_layout_dirs(data_dir: &Path) -> Vec<&'static str> {
+    REQUIRED_DIRS
+        .iter()
+        .copied()
+        .filter(|dir| !data_dir.join(dir).is_dir())
+        .collect()
+}
+
 pub fn load_yaml<T: DeserializeOwned>(path: PathBuf) -> anyhow::Result<T> {
     let content = fs::read_to_string(&path).with_context(|| format!("reading yaml {:?}", path))?;
     let parsed =
@@ -63,10 +252,7 @@ pub async fn write_markdown(path: &Path, content: &str) -> anyhow::Result<()> {
     if let Some(parent) = path.parent() {
         async_fs::create_dir_all(parent).await?;
     }
-    let mut file = async_fs::File::create(path).await?;
-    file.write_all(content.as_bytes()).await?;
-    file.flush().await?;
-    Ok(())
+    write_atomic_async(path, content).await
 }
 
 pub fn list_markdown_files(root: &Path) -> Vec<PathBuf> {
@@ -84,11 +270,16 @@ pub fn list_markdown_files(root: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
-pub fn list_markdown_tree(data_dir: &Path) -> anyhow::Result<Vec<String>> {
-    let mut files: Vec<String> = list_markdown_files(data_dir)
-        .into_iter()
-        .filter_map(|path| {
-            path.strip_prefix(data_dir)
+/// Data-relative paths of the markdown files under `data_dir`, minus what
+/// `policy` skips.
+pub fn list_markdown_tree(data_dir: &Path, policy: &WalkPolicy) -> anyhow::Result<Vec<String>> {
+    let mut files: Vec<String> = walk(data_dir, data_dir, policy)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(data_dir)
                 .ok()
                 .map(|relative| relative.to_string_lossy().to_string())
         })
@@ -125,6 +316,14 @@ pub fn sanitize_data_relative_path(path: &str) -> anyhow::Result<PathBuf> {
 }
 
 pub async fn read_markdown_file(data_dir: &Path, relative_path: &Path) -> anyhow::Result<String> {
+    let canonical_file = resolve_markdown_file(data_dir, relative_path)?;
+    let content = async_fs::read_to_string(canonical_file).await?;
+    Ok(content)
+}
+
+/// The on-disk path of a markdown file in the data dir, refusing anything
+/// that is not markdown or resolves outside the data dir.
+pub fn resolve_markdown_file(data_dir: &Path, relative_path: &Path) -> anyhow::Result<PathBuf> {
     let canonical_data = fs::canonicalize(data_dir)?;
     let absolute_path = data_dir.join(relative_path);
     if absolute_path.extension().and_then(|ext| ext.to_str()) != Some("md") {
@@ -136,9 +335,7 @@ pub async fn read_markdown_file(data_dir: &Path, relative_path: &Path) -> anyhow
     if !canonical_file.starts_with(&canonical_data) {
         return Err(anyhow!("path escapes data directory"));
     }
-
-    let content = async_fs::read_to_string(canonical_file).await?;
-    Ok(content)
+    Ok(canonical_file)
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +344,10 @@ pub struct LlmLogQuery {
     pub run_id: Option<Uuid>,
     pub phase: Option<String>,
     pub since: Option<DateTime<Utc>>,
+    /// Inclusive upper bound on `timestamp`.
+    pub until: Option<DateTime<Utc>>,
+    /// Continue after the page that produced this cursor.
+    pub cursor: Option<LlmLogCursor>,
     pub limit: usize,
 }
 
@@ -157,18 +358,81 @@ impl Default for LlmLogQuery {
             run_id: None,
             phase: None,
             since: None,
+            until: None,
+            cursor: None,
             limit: 100,
         }
     }
 }
 
+/// Where the previous page of LLM logs (newest first) ended: entries newer
+/// than `before` were returned, and so were the first `skip` entries
+/// stamped exactly `before`. Counting ties keeps calls that share a
+/// timestamp from being skipped or repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlmLogCursor {
+    pub before: DateTime<Utc>,
+    pub skip: usize,
+}
+
+impl LlmLogCursor {
+    /// The cursor following `page`, which was read with `previous`.
+    pub fn after_page(previous: Option<&Self>, page: &[LlmLogEntry]) -> Option<Self> {
+        let last = page.last()?;
+        let mut skip = page
+            .iter()
+            .rev()
+            .take_while(|entry| entry.timestamp == last.timestamp)
+            .count();
+        if let Some(previous) = previous
+            && previous.before == last.timestamp
+        {
+            skip += previous.skip;
+        }
+        Some(Self {
+            before: last.timestamp,
+            skip,
+        })
+    }
+
+    /// Opaque form for query strings: `<unix nanos>.<skip>`.
+    pub fn encode(&self) -> String {
+        format!(
+            "{}.{}",
+            self.before.timestamp_nanos_opt().unwrap_or_default(),
+            self.skip
+        )
+    }
+
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (nanos, skip) = value
+            .split_once('.')
+            .ok_or_else(|| anyhow!("invalid llm log cursor: {value}"))?;
+        let nanos: i64 = nanos
+            .parse()
+            .with_context(|| format!("invalid llm log cursor: {value}"))?;
+        Ok(Self {
+            before: DateTime::from_timestamp_nanos(nanos),
+            skip: skip
+                .parse()
+                .with_context(|| format!("invalid llm log cursor: {value}"))?,
+        })
+    }
+}
+
 pub async fn append_llm_logs(data_dir: &Path, entries: &[LlmLogEntry]) -> anyhow::Result<()> {
     if entries.is_empty() {
         return Ok(());
     }
 
+    let mut days: BTreeMap<NaiveDate, Vec<&LlmLogEntry>> = BTreeMap::new();
     for entry in entries {
-        let date = entry.timestamp.date_naive();
+        days.entry(entry.timestamp.date_naive())
+            .or_default()
+            .push(entry);
+    }
+
+    for (date, day_entries) in days {
         let log_dir =
             data_dir
                 .join("logs/llm")
@@ -180,15 +444,31 @@ pub async fn append_llm_logs(data_dir: &Path, entries: &[LlmLogEntry]) -> anyhow
             .append(true)
             .open(&log_path)
             .await?;
-        let serialized = serde_json::to_string(entry)?;
-        file.write_all(serialized.as_bytes()).await?;
-        file.write_all(b"\n").await?;
+        let previous_bytes = file.metadata().await?.len();
+        for entry in &day_entries {
+            let serialized = serde_json::to_string(entry)?;
+            file.write_all(serialized.as_bytes()).await?;
+            file.write_all(b"\n").await?;
+        }
         file.flush().await?;
+        drop(file);
+
+        // The log is already written; a failed index update only costs the
+        // next reader a rebuild.
+        if llm_index::update_day_index(&log_path, &day_entries, previous_bytes)
+            .await
+            .is_err()
+        {
+            let _ = async_fs::remove_file(llm_index::index_path(&log_path)).await;
+        }
     }
 
     Ok(())
 }
 
+/// Newest entries first. Days before `since` are never listed, days after
+/// `until` (or the cursor) are not read, and when the query names a run or
+/// model, days whose index rules them out are skipped without being read.
 pub async fn read_llm_logs(
     data_dir: &Path,
     mut query: LlmLogQuery,
@@ -202,17 +482,25 @@ pub async fn read_llm_logs(
         return Ok(Vec::new());
     }
 
-    let mut files: Vec<PathBuf> = WalkDir::new(&log_root)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().is_file())
-        .map(|entry| entry.into_path())
-        .collect();
-    files.sort();
-    files.reverse();
-
+    let since = query.since.map(|since| since.date_naive());
+    let newest = match (query.until, query.cursor.map(|cursor| cursor.before)) {
+        (Some(until), Some(before)) => Some(until.min(before)),
+        (until, before) => until.or(before),
+    };
+    let mut tied_to_skip = query.cursor.map(|cursor| cursor.skip).unwrap_or_default();
     let mut results = Vec::new();
-    for file in files {
+    for (date, file) in llm_index::day_logs(&log_root, since)? {
+        if newest.is_some_and(|newest| date > newest.date_naive()) {
+            continue;
+        }
+        let filtered = query.run_id.is_some() || query.model.is_some();
+        if filtered
+            && let Ok(index) = llm_index::load_day_index(&file).await
+            && !index.may_contain(&query)
+        {
+            continue;
+        }
+
         let content = async_fs::read_to_string(&file).await?;
         let mut lines: Vec<&str> = content.lines().collect();
         lines.reverse();
@@ -220,7 +508,7 @@ pub async fn read_llm_logs(
             if line.trim().is_empty() {
                 continue;
             }
-            let entry: LlmLogEntry = serde_json::from_str(line)?;
+            let entry: LlmLogEntry = parse_record(SchemaKind::LlmLog, line)?;
 
             if let Some(ref model) = query.model {
                 let matches_model = entry
@@ -255,6 +543,24 @@ pub async fn read_llm_logs(
                 continue;
             }
 
+            if query
+                .until
+                .as_ref()
+                .is_some_and(|until| &entry.timestamp > until)
+            {
+                continue;
+            }
+
+            if let Some(cursor) = &query.cursor {
+                if entry.timestamp > cursor.before {
+                    continue;
+                }
+                if entry.timestamp == cursor.before && tied_to_skip > 0 {
+                    tied_to_skip -= 1;
+                    continue;
+                }
+            }
+
             results.push(entry);
             if results.len() >= query.limit {
                 return Ok(results);
@@ -277,12 +583,25 @@ struct IntentFrontMatter {
     telos_alignment: Option<f32>,
     #[serde(default)]
     created_at: Option<chrono::DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_at: Option<chrono::DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    /// Shorthand for `metadata.persona`; folded into `metadata` on parse.
+    #[serde(default, skip_serializing)]
+    persona: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state: Option<IntentState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<IntentTransition>,
 }
 
 #[derive(Debug)]
 pub struct IntentRecord {
     pub path: PathBuf,
     pub intent: Intent,
+    /// Markdown below the front matter.
+    pub body: String,
 }
 
 #[derive(Debug)]
@@ -301,11 +620,206 @@ pub fn scan_queue(data_dir: &Path) -> anyhow::Result<Vec<IntentRecord>> {
     scan_intent_dir(&queue_dir)
 }
 
+pub fn scan_pending_approval(data_dir: &Path) -> anyhow::Result<Vec<IntentRecord>> {
+    let pending_dir = data_dir.join("intent/pending_approval");
+    scan_intent_dir(&pending_dir)
+}
+
 pub fn scan_history(data_dir: &Path) -> anyhow::Result<Vec<IntentRecord>> {
     let history_dir = data_dir.join("intent/history");
     scan_intent_dir(&history_dir)
 }
 
+/// An intent still waiting in the inbox, for approval or in the queue, with
+/// its SLA status.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingIntent {
+    #[serde(flatten)]
+    pub intent: Intent,
+    pub stage: &'static str,
+    pub time_in_queue_secs: i64,
+    pub overdue: bool,
+    /// The markdown body, cut to [`INTENT_BODY_PREVIEW_CHARS`] in listings.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub body_truncated: bool,
+}
+
+/// Body characters, the closing `…` included, kept per intent in listings;
+/// `GET /api/intents/:id` returns the whole body.
+pub const INTENT_BODY_PREVIEW_CHARS: usize = 280;
+
+impl PendingIntent {
+    /// Only intents that can still run are ever overdue.
+    pub fn new(stage: &'static str, record: IntentRecord, now: DateTime<Utc>) -> Self {
+        Self {
+            stage,
+            time_in_queue_secs: (now - record.intent.created_at).num_seconds().max(0),
+            overdue: !matches!(stage, "history" | "cancelled") && record.intent.is_overdue(now),
+            intent: record.intent,
+            body: record.body,
+            body_truncated: false,
+        }
+    }
+
+    fn preview(mut self) -> Self {
+        if text::is_truncated(&self.body, INTENT_BODY_PREVIEW_CHARS) {
+            self.body = text::truncate(&self.body, INTENT_BODY_PREVIEW_CHARS);
+            self.body_truncated = true;
+        }
+        self
+    }
+}
+
+pub fn list_pending_intents(
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<PendingIntent>> {
+    let inbox = scan_inbox(data_dir)?
+        .into_iter()
+        .map(|record| ("inbox", record));
+    let pending_approval = scan_pending_approval(data_dir)?
+        .into_iter()
+        .map(|record| ("pending_approval", record));
+    let queue = scan_queue(data_dir)?
+        .into_iter()
+        .map(|record| ("queue", record));
+
+    Ok(inbox
+        .chain(pending_approval)
+        .chain(queue)
+        .map(|(stage, record)| PendingIntent::new(stage, record, now).preview())
+        .collect())
+}
+
+/// Every intent stage and the directory under `data/` it lives in.
+pub const INTENT_STAGES: [(&str, &str); 8] = [
+    ("inbox", "intent/inbox"),
+    ("pending_approval", "intent/pending_approval"),
+    ("queue", "intent/queue"),
+    ("waiting", "intent/waiting"),
+    ("deferred", "intent/inbox/deferred"),
+    ("failed", "intent/queue/failed"),
+    ("history", "intent/history"),
+    ("cancelled", "intent/inbox/discarded"),
+];
+
+/// Intents in one stage, including the parked (`waiting`, `deferred`,
+/// `failed`) and finished (`history`, `cancelled`) ones [`list_pending_intents`] leaves out. `None` for
+/// an unknown stage.
+pub fn list_stage_intents(
+    data_dir: &Path,
+    stage: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<Vec<PendingIntent>>> {
+    let Some(&(stage, dir)) = INTENT_STAGES.iter().find(|(name, _)| *name == stage) else {
+        return Ok(None);
+    };
+    let intents = scan_intent_dir(&data_dir.join(dir))?
+        .into_iter()
+        .map(|record| PendingIntent::new(stage, record, now).preview())
+        .collect();
+    Ok(Some(intents))
+}
+
+/// Look an intent up by id across all stages.
+pub fn find_intent(
+    data_dir: &Path,
+    id: Uuid,
+) -> anyhow::Result<Option<(&'static str, IntentRecord)>> {
+    for (stage, dir) in INTENT_STAGES {
+        if let Some(record) = scan_intent_dir(&data_dir.join(dir))?
+            .into_iter()
+            .find(|record| record.intent.id == id)
+        {
+            return Ok(Some((stage, record)));
+        }
+    }
+    Ok(None)
+}
+
+/// Fields a human may change on an intent that has not run yet. `None`
+/// leaves a field as it is.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IntentEdit {
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub telos_alignment: Option<f32>,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl IntentEdit {
+    /// Apply the front matter fields to an in-memory intent, e.g. one
+    /// already waiting in the queue.
+    pub fn apply_to(&self, intent: &mut Intent) {
+        if let Some(summary) = &self.summary {
+            intent.summary = summary.clone();
+        }
+        if let Some(alignment) = self.telos_alignment {
+            intent.telos_alignment = alignment;
+        }
+        if self.due_at.is_some() {
+            intent.due_at = self.due_at;
+        }
+    }
+}
+
+/// Rewrite an intent file with `edit` applied, keeping fields it leaves out.
+pub fn edit_intent(path: &Path, edit: &IntentEdit) -> anyhow::Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading intent to edit {:?}", path))?;
+    let mut front_matter = parse_intent_front_matter(&content)?;
+    if let Some(summary) = &edit.summary {
+        front_matter.summary = Some(summary.clone());
+    }
+    if let Some(alignment) = edit.telos_alignment {
+        front_matter.telos_alignment = Some(alignment);
+    }
+    if edit.due_at.is_some() {
+        front_matter.due_at = edit.due_at;
+    }
+    let body = edit
+        .body
+        .as_deref()
+        .unwrap_or_else(|| intent_file_body(&content));
+    let rendered = render_intent_file(&front_matter, body)?;
+    write_atomic(path, rendered).with_context(|| format!("writing edited intent {:?}", path))
+}
+
+/// Read one intent file again, e.g. to pick up edits another process made
+/// while it sat in the queue. `None` once the file is gone.
+pub fn load_intent(path: &Path) -> anyhow::Result<Option<Intent>> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_intent(path, &content).map(Some),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("reading intent at {:?}", path)),
+    }
+}
+
+fn parse_intent(path: &Path, content: &str) -> anyhow::Result<Intent> {
+    let front_matter = parse_intent_front_matter(content)?;
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("intent");
+
+    Ok(Intent {
+        id: front_matter.id.unwrap_or_else(Uuid::new_v4),
+        source: front_matter.source.unwrap_or_else(|| "unknown".to_string()),
+        summary: front_matter.summary.unwrap_or_else(|| stem.to_string()),
+        telos_alignment: front_matter.telos_alignment.unwrap_or_default(),
+        created_at: front_matter.created_at.unwrap_or_else(Utc::now),
+        due_at: front_matter.due_at,
+        metadata: front_matter.metadata,
+        storage_path: Some(path.to_path_buf()),
+    })
+}
+
 fn scan_intent_dir(dir: &Path) -> anyhow::Result<Vec<IntentRecord>> {
     let mut records = Vec::new();
 
@@ -320,30 +834,29 @@ fn scan_intent_dir(dir: &Path) -> anyhow::Result<Vec<IntentRecord>> {
             continue;
         }
 
+        // Skip in-flight `write_atomic` temp files.
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
         let path = entry.path();
         let content = fs::read_to_string(&path)
             .with_context(|| format!("reading intent front matter at {:?}", path))?;
-        let front_matter = parse_intent_front_matter(&content)?;
-        let stem = path
-            .file_stem()
-            .and_then(|stem| stem.to_str())
-            .unwrap_or("intent");
-
-        let intent = Intent {
-            id: front_matter.id.unwrap_or_else(Uuid::new_v4),
-            source: front_matter.source.unwrap_or_else(|| "unknown".to_string()),
-            summary: front_matter.summary.unwrap_or_else(|| stem.to_string()),
-            telos_alignment: front_matter.telos_alignment.unwrap_or_default(),
-            created_at: front_matter.created_at.unwrap_or_else(Utc::now),
-            storage_path: Some(path.clone()),
-        };
-
-        records.push(IntentRecord { path, intent });
+        let intent = parse_intent(&path, &content)?;
+        let body = intent_file_body(&content).trim_end().to_string();
+        records.push(IntentRecord { path, intent, body });
     }
     records.sort_by_key(|record| record.intent.created_at);
     Ok(records)
 }
 
+/// Parse one intent file the way the queue scans do and return its id.
+pub fn read_intent_id(path: &Path) -> anyhow::Result<Option<Uuid>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading intent front matter at {:?}", path))?;
+    Ok(parse_intent_front_matter(&content)?.id)
+}
+
 fn parse_intent_front_matter(content: &str) -> anyhow::Result<IntentFrontMatter> {
     let trimmed = content.trim_start();
     let yaml_block = if let Some(rest) = trimmed.strip_prefix("---") {
@@ -361,34 +874,75 @@ fn parse_intent_front_matter(content: &str) -> anyhow::Result<IntentFrontMatter>
         return Ok(IntentFrontMatter::default());
     }
 
-    let parsed = serde_yaml::from_str(yaml_block).with_context(|| "parsing intent front matter")?;
+    let mut parsed: IntentFrontMatter =
+        serde_yaml::from_str(yaml_block).with_context(|| "parsing intent front matter")?;
+    if let Some(persona) = parsed.persona.take() {
+        parsed
+            .metadata
+            .entry(crate::tasks::PERSONA_KEY.to_string())
+            .or_insert(persona);
+    }
     Ok(parsed)
 }
 
+/// Everything needed to drop a new intent into the inbox.
+#[derive(Debug, Clone, Default)]
+pub struct IntentDraft {
+    pub source: String,
+    pub summary: String,
+    pub telos_alignment: f32,
+    pub body: String,
+    pub due_at: Option<DateTime<Utc>>,
+    pub metadata: BTreeMap<String, String>,
+}
+
 pub async fn persist_intent(
     data_dir: &Path,
-    source: &str,
-    summary: &str,
-    telos_alignment: f32,
-    body: &str,
+    draft: &IntentDraft,
 ) -> anyhow::Result<PersistedIntent> {
+    persist_intent_at(data_dir, draft, Utc::now()).await
+}
+
+/// [`persist_intent`] with the intent created at `created_at`.
+pub async fn persist_intent_at(
+    data_dir: &Path,
+    draft: &IntentDraft,
+    created_at: DateTime<Utc>,
+) -> anyhow::Result<PersistedIntent> {
+    let body = draft.body.as_str();
     let inbox_dir = data_dir.join("intent/inbox");
     async_fs::create_dir_all(&inbox_dir).await?;
 
-    let created_at = Utc::now();
     let id = Uuid::new_v4();
     let file_name = format!("{}-{}.md", created_at.format("%Y%m%dT%H%M%S"), id);
     let path = inbox_dir.join(&file_name);
 
     let front_matter = IntentFrontMatter {
         id: Some(id),
-        source: Some(source.to_string()),
-        summary: Some(summary.to_string()),
-        telos_alignment: Some(telos_alignment),
+        source: Some(draft.source.clone()),
+        summary: Some(draft.summary.clone()),
+        telos_alignment: Some(draft.telos_alignment),
         created_at: Some(created_at),
+        due_at: draft.due_at,
+        metadata: draft.metadata.clone(),
+        persona: None,
+        state: Some(IntentState::Received),
+        history: vec![IntentTransition {
+            from: None,
+            to: IntentState::Received,
+            at: created_at,
+            reason: None,
+        }],
     };
 
-    let mut yaml = serde_yaml::to_string(&front_matter)?;
+    let content = render_intent_file(&front_matter, body)?;
+    write_markdown(&path, &content).await?;
+
+    Ok(PersistedIntent { id, path })
+}
+
+fn render_intent_file(front_matter: &IntentFrontMatter, body: &str) -> anyhow::Result<String> {
+    let mut yaml = serde_yaml::to_string(front_matter)?;
     if let Some(stripped) = yaml.strip_prefix("---\n") {
         yaml = stripped.to_string();
     }
@@ -409,13 +963,148 @@ pub async fn persist_intent(
             content.push('\n');
         }
     }
+    Ok(content)
+}
 
-    write_markdown(&path, &content).await?;
+/// Write `intent` with its own id and timestamps to `path`, e.g. to lay out
+/// intents in any stage directory without going through the inbox.
+pub fn write_intent_file(path: &Path, intent: &Intent, body: &str) -> anyhow::Result<()> {
+    let front_matter = IntentFrontMatter {
+        id: Some(intent.id),
+        source: Some(intent.source.clone()),
+        summary: Some(intent.summary.clone()),
+        telos_alignment: Some(intent.telos_alignment),
+        created_at: Some(intent.created_at),
+        due_at: intent.due_at,
+        metadata: intent.metadata.clone(),
+        persona: None,
+        state: None,
+        history: Vec::new(),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("creating intent dir {:?}", parent))?;
+    }
+    let rendered = render_intent_file(&front_matter, body)?;
+    write_atomic(path, rendered).with_context(|| format!("writing intent file {:?}", path))
+}
 
-    Ok(PersistedIntent { id, path })
+/// Body of an intent file, i.e. everything after the front matter block.
+fn intent_file_body(content: &str) -> &str {
+    let Some(rest) = content.trim_start().strip_prefix("---") else {
+        return content;
+    };
+    match rest.find("\n---") {
+        Some(end) => rest[end + 4..].trim_start_matches(['\r', '\n']),
+        None => "",
+    }
+}
+
+/// Set metadata keys in an intent file's front matter, keeping its body.
+pub fn set_intent_metadata(path: &Path, entries: &[(&str, &str)]) -> anyhow::Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading intent for metadata update {:?}", path))?;
+    let mut front_matter = parse_intent_front_matter(&content)?;
+    for (key, value) in entries {
+        front_matter
+            .metadata
+            .insert((*key).to_string(), (*value).to_string());
+    }
+    let rendered = render_intent_file(&front_matter, intent_file_body(&content))?;
+    write_atomic(path, rendered).with_context(|| format!("writing intent metadata {:?}", path))
+}
+
+/// Record that the intent at `path` moved to `state`, appending to the
+/// audit trail in its front matter. Moving to the state it is already in is
+/// a no-op; a move the lifecycle does not allow is an error.
+pub fn record_intent_state(
+    path: &Path,
+    state: IntentState,
+    reason: Option<&str>,
+    at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading intent for state update {:?}", path))?;
+    let mut front_matter = parse_intent_front_matter(&content)?;
+    let from = front_matter.state;
+    if from == Some(state) {
+        return Ok(());
+    }
+    if let Some(from) = from
+        && !from.can_transition_to(state)
+    {
+        anyhow::bail!("intent {:?} cannot move from {from} to {state}", path);
+    }
+    front_matter.state = Some(state);
+    front_matter.history.push(IntentTransition {
+        from,
+        to: state,
+        at,
+        reason: reason.map(str::to_string),
+    });
+    let rendered = render_intent_file(&front_matter, intent_file_body(&content))?;
+    write_atomic(path, rendered).with_context(|| format!("writing intent state {:?}", path))
+}
+
+/// The recorded state of the intent at `path` and how it got there.
+pub fn load_intent_history(
+    path: &Path,
+) -> anyhow::Result<(Option<IntentState>, Vec<IntentTransition>)> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading intent history {:?}", path))?;
+    let front_matter = parse_intent_front_matter(&content)?;
+    Ok((front_matter.state, front_matter.history))
+}
+
+pub fn find_deferred_intent(data_dir: &Path, id: Uuid) -> anyhow::Result<Option<IntentRecord>> {
+    Ok(scan_intent_dir(&data_dir.join("intent/inbox/deferred"))?
+        .into_iter()
+        .find(|record| record.intent.id == id))
 }
 
-pub fn promote_to_queue(path: &Path, data_dir: &Path) -> anyhow::Result<PathBuf> {
+pub fn find_pending_approval_intent(
+    data_dir: &Path,
+    id: Uuid,
+) -> anyhow::Result<Option<IntentRecord>> {
+    Ok(scan_pending_approval(data_dir)?
+        .into_iter()
+        .find(|record| record.intent.id == id))
+}
+
+/// Move a deferred or held intent back into the inbox, flagged with
+/// `metadata.approved = "true"` so the next beat queues it regardless of its
+/// alignment or the approval gate.
+pub fn approve_intent(path: &Path, data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<PathBuf> {
+    set_intent_metadata(path, &[(INTENT_APPROVED_KEY, "true")])?;
+    record_intent_state(path, IntentState::Received, Some("approved"), now)?;
+    let inbox_dir = data_dir.join("intent/inbox");
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
+    let destination = inbox_dir.join(file_name);
+    fs::rename(path, &destination)
+        .with_context(|| format!("moving approved intent to inbox: {:?}", path))?;
+    Ok(destination)
+}
+
+pub fn discard_intent(path: &Path, data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<PathBuf> {
+    let discarded_dir = data_dir.join("intent/inbox/discarded");
+    fs::create_dir_all(&discarded_dir)
+        .with_context(|| format!("ensuring discarded dir {:?}", discarded_dir))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
+    let destination = discarded_dir.join(file_name);
+    record_intent_state(path, IntentState::Cancelled, None, now)?;
+    fs::rename(path, &destination)
+        .with_context(|| format!("moving intent to discarded: {:?}", path))?;
+    Ok(destination)
+}
+
+pub fn promote_to_queue(
+    path: &Path,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
     let queue_dir = data_dir.join("intent/queue");
     fs::create_dir_all(&queue_dir)
         .with_context(|| format!("ensuring queue dir {:?}", queue_dir))?;
@@ -424,12 +1113,33 @@ pub fn promote_to_queue(path: &Path, data_dir: &Path) -> anyhow::Result<PathBuf>
         .file_name()
         .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
     let destination = queue_dir.join(file_name);
+    record_intent_state(path, IntentState::Queued, None, now)?;
     fs::rename(path, &destination)
         .with_context(|| format!("moving intent to queue: {:?}", path))?;
     Ok(destination)
 }
 
-pub fn defer_intent(path: &Path, data_dir: &Path) -> anyhow::Result<PathBuf> {
+/// Hold an intent in `intent/pending_approval` until a human approves or
+/// rejects it.
+pub fn hold_for_approval(
+    path: &Path,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
+    let pending_dir = data_dir.join("intent/pending_approval");
+    fs::create_dir_all(&pending_dir)
+        .with_context(|| format!("ensuring pending approval dir {:?}", pending_dir))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
+    let destination = pending_dir.join(file_name);
+    record_intent_state(path, IntentState::PendingApproval, None, now)?;
+    fs::rename(path, &destination)
+        .with_context(|| format!("moving intent to pending approval: {:?}", path))?;
+    Ok(destination)
+}
+
+pub fn defer_intent(path: &Path, data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<PathBuf> {
     let deferred_dir = data_dir.join("intent/inbox/deferred");
     fs::create_dir_all(&deferred_dir)
         .with_context(|| format!("ensuring deferred dir {:?}", deferred_dir))?;
@@ -437,12 +1147,17 @@ pub fn defer_intent(path: &Path, data_dir: &Path) -> anyhow::Result<PathBuf> {
         .file_name()
         .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
     let destination = deferred_dir.join(file_name);
+    record_intent_state(path, IntentState::Deferred, None, now)?;
     fs::rename(path, &destination)
         .with_context(|| format!("moving intent to deferred: {:?}", path))?;
     Ok(destination)
 }
 
-pub fn quarantine_failed_intent(path: &Path, data_dir: &Path) -> anyhow::Result<PathBuf> {
+pub fn quarantine_failed_intent(
+    path: &Path,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
     let failed_dir = data_dir.join("intent/queue/failed");
     fs::create_dir_all(&failed_dir)
         .with_context(|| format!("ensuring failed dir {:?}", failed_dir))?;
@@ -450,24 +1165,215 @@ pub fn quarantine_failed_intent(path: &Path, data_dir: &Path) -> anyhow::Result<
         .file_name()
         .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
     let destination = failed_dir.join(file_name);
+    // A failing intent leaves the queue even when its file is too broken to
+    // record the state in.
+    if let Err(err) = record_intent_state(path, IntentState::Failed, None, now) {
+        tracing::warn!(error = ?err, ?path, "failed to record failed intent state");
+    }
     fs::rename(path, &destination)
         .with_context(|| format!("moving intent to failed queue: {:?}", path))?;
     Ok(destination)
 }
 
+/// Metadata a replay leaves behind: where the original should answer, so
+/// a debugging run does not reply to the chat or comment on the issue
+/// again, and what the original's triage and runs recorded.
+const NOT_REPLAYED_KEYS: &[&str] = &[
+    INTENT_APPROVED_KEY,
+    crate::telegram::CHAT_ID_KEY,
+    crate::telegram::MESSAGE_ID_KEY,
+    crate::email::FROM_KEY,
+    crate::email::MESSAGE_ID_KEY,
+    github::REPO_KEY,
+    github::ISSUE_NUMBER_KEY,
+    github::ISSUE_URL_KEY,
+    ESTIMATED_TOKENS_KEY,
+    HOLD_REASON_KEY,
+    DURATION_MS_KEY,
+    LLM_LATENCY_MS_KEY,
+    PROMPT_VERSION_KEY,
+    EXPERIMENT_KEY,
+    EXPERIMENT_VARIANT_KEY,
+    FAILURE_CLASS_KEY,
+    FAILURE_ERROR_KEY,
+    FAILED_AT_KEY,
+    FAILED_RUNS_KEY,
+    SLA_ALERTED_AT_KEY,
+];
+
+/// Clone an archived intent into the inbox as a new intent pointing back at
+/// it through [`REPLAY_OF_KEY`]. Metadata, including any LLM pins, carries
+/// over and `pins` override it; approval, the due date, reply targets and
+/// run results do not (see [`NOT_REPLAYED_KEYS`]), so the replay goes
+/// through triage like a fresh intent and answers nowhere but its journal.
+pub async fn replay_intent(
+    data_dir: &Path,
+    record: &IntentRecord,
+    pins: &[(&str, &str)],
+) -> anyhow::Result<PersistedIntent> {
+    let content = async_fs::read_to_string(&record.path)
+        .await
+        .with_context(|| format!("reading intent to replay {:?}", record.path))?;
+
+    let mut metadata = record.intent.metadata.clone();
+    metadata.retain(|key, _| !NOT_REPLAYED_KEYS.contains(&key.as_str()));
+    metadata.insert(REPLAY_OF_KEY.to_string(), record.intent.id.to_string());
+    for (key, value) in pins {
+        metadata.insert((*key).to_string(), (*value).to_string());
+    }
+
+    persist_intent(
+        data_dir,
+        &IntentDraft {
+            source: record.intent.source.clone(),
+            summary: record.intent.summary.clone(),
+            telos_alignment: record.intent.telos_alignment,
+            body: intent_file_body(&content).to_string(),
+            due_at: None,
+            metadata,
+        },
+    )
+    .await
+}
+
+const JOURNAL_LINKS_PREFIX: &str = "<!-- hi:links ";
+
+/// Stable ids embedded in each journal section so clients can jump from a
+/// paragraph to the run trace (`/api/logs/llm?run_id=`) and related memories.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalLinks {
+    pub run_id: Uuid,
+    pub intent_id: Uuid,
+    #[serde(default)]
+    pub memory_ids: Vec<Uuid>,
+}
+
+impl JournalLinks {
+    fn to_comment(&self) -> String {
+        let memory_ids = self
+            .memory_ids
+            .iter()
+            .map(Uuid::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{}run_id={} intent_id={} memory_ids={} -->",
+            JOURNAL_LINKS_PREFIX, self.run_id, self.intent_id, memory_ids
+        )
+    }
+
+    fn from_comment(line: &str) -> Option<Self> {
+        let body = line
+            .trim()
+            .strip_prefix(JOURNAL_LINKS_PREFIX)?
+            .strip_suffix("-->")?;
+
+        let mut run_id = None;
+        let mut intent_id = None;
+        let mut memory_ids = Vec::new();
+        for pair in body.split_whitespace() {
+            let (key, value) = pair.split_once('=')?;
+            match key {
+                "run_id" => run_id = Uuid::parse_str(value).ok(),
+                "intent_id" => intent_id = Uuid::parse_str(value).ok(),
+                "memory_ids" => {
+                    memory_ids = value
+                        .split(',')
+                        .filter(|id| !id.is_empty())
+                        .filter_map(|id| Uuid::parse_str(id).ok())
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            run_id: run_id?,
+            intent_id: intent_id?,
+            memory_ids,
+        })
+    }
+}
+
+const JOURNAL_DURATION_PREFIX: &str = "Duration: ";
+
+/// Time spent processing one intent, from picking it up until its journal
+/// entry is written, and how much of it went to LLM calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunTiming {
+    pub duration_ms: u64,
+    pub llm_latency_ms: u64,
+}
+
+impl RunTiming {
+    /// `elapsed` wall-clock time; LLM latency is summed from the run's logs.
+    pub fn new(elapsed: std::time::Duration, llm_logs: &[LlmLogEntry]) -> Self {
+        Self {
+            duration_ms: elapsed.as_millis() as u64,
+            llm_latency_ms: llm_logs.iter().filter_map(|log| log.latency_ms).sum(),
+        }
+    }
+
+    fn to_line(self) -> String {
+        format!(
+            "{}{} ms (LLM {} ms)",
+            JOURNAL_DURATION_PREFIX, self.duration_ms, self.llm_latency_ms
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix(JOURNAL_DURATION_PREFIX)?;
+        let (duration, llm) = rest.split_once(" ms (LLM ")?;
+        Some(Self {
+            duration_ms: duration.trim().parse().ok()?,
+            llm_latency_ms: llm.strip_suffix(" ms)")?.trim().parse().ok()?,
+        })
+    }
+
+    /// Metadata recorded on the archived intent.
+    pub fn metadata(self) -> [(&'static str, String); 2] {
+        [
+            (DURATION_MS_KEY, self.duration_ms.to_string()),
+            (LLM_LATENCY_MS_KEY, self.llm_latency_ms.to_string()),
+        ]
+    }
+}
+
+/// Extract every link block from a journal document, in file order.
+pub fn parse_journal_links(content: &str) -> Vec<JournalLinks> {
+    content
+        .lines()
+        .filter_map(JournalLinks::from_comment)
+        .collect()
+}
+
 pub async fn append_journal_entry(
     data_dir: &Path,
     intent: &Intent,
     outcome: &AgentOutcome,
+    links: &JournalLinks,
+    timing: Option<RunTiming>,
 ) -> anyhow::Result<PathBuf> {
-    let now = Utc::now();
-    let journal_dir = data_dir
-        .join("journals")
-        .join(format!("{:04}", now.year()))
-        .join(format!("{:02}", now.month()));
-    async_fs::create_dir_all(&journal_dir).await?;
+    append_journal_entry_at(data_dir, Utc::now(), intent, outcome, links, timing, true).await
+}
 
-    let journal_path = journal_dir.join(format!("{:02}.md", now.day()));
+/// [`append_journal_entry`] for a run that finished at `now`, e.g. when
+/// synthesizing fixtures. Without `keep_history` (the disk guard paused it)
+/// no markdown revisions are recorded.
+pub async fn append_journal_entry_at(
+    data_dir: &Path,
+    now: DateTime<Utc>,
+    intent: &Intent,
+    outcome: &AgentOutcome,
+    links: &JournalLinks,
+    timing: Option<RunTiming>,
+    keep_history: bool,
+) -> anyhow::Result<PathBuf> {
+    let day_dir = journal_day_dir(data_dir, now.date_naive());
+    async_fs::create_dir_all(&day_dir).await?;
+
+    let journal_path = day_dir.join(format!("{}.md", intent.id));
+    record_journal_revision(data_dir, &journal_path, keep_history, now).await?;
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
@@ -476,12 +1382,16 @@ pub async fn append_journal_entry(
 
     let mut trace = String::new();
     for (idx, step) in outcome.steps.iter().enumerate() {
+        let action = match step.input.as_deref().map(str::trim) {
+            Some(input) if !input.is_empty() => format!("{} ({input})", step.action),
+            _ => step.action.clone(),
+        };
         let _ = writeln!(
             &mut trace,
             "{}. Thought: {}\n   Action: {}\n   Observation: {}",
             idx + 1,
             step.thought,
-            step.action,
+            action,
             step.observation
         );
     }
@@ -490,21 +1400,145 @@ pub async fn append_journal_entry(
         trace.push_str("(no ReAct steps recorded)\n");
     }
 
+    let timing = timing
+        .map(|timing| format!("{}\n", timing.to_line()))
+        .unwrap_or_default();
+    let artifacts = if run_dir(data_dir, links.run_id).is_dir() {
+        format!("Run artifacts: {RUNS_DIR}/{}/\n", links.run_id)
+    } else {
+        String::new()
+    };
+    let mut sources = String::new();
+    for source in outcome.sources() {
+        if sources.is_empty() {
+            sources.push_str("Sources:\n");
+        }
+        let _ = writeln!(&mut sources, "- <{source}>");
+    }
     let entry = format!(
-        "## {} — {}\n\nIntent processed: {}\nFinal answer: {}\n\n### ReAct trace\n{}\n",
+        "## {} — {}\n{}\n\nIntent processed: {}\nFinal answer: {}\n{}{}{}\n### ReAct trace\n{}\n",
         now.format("%H:%M:%S"),
         intent.summary,
+        links.to_comment(),
         intent.summary,
         outcome.final_answer,
+        timing,
+        artifacts,
+        sources,
         trace.trim_end(),
     );
 
     file.write_all(entry.as_bytes()).await?;
     file.flush().await?;
+    drop(file);
+    record_journal_revision(data_dir, &journal_path, keep_history, now).await?;
+
+    rebuild_journal_index(data_dir, now.date_naive(), keep_history, now).await?;
     Ok(journal_path)
 }
 
-pub async fn archive_intent(intent: &Intent, data_dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+const JOURNAL_INDEX_MARKER: &str = "<!-- hi:journal-index -->";
+const JOURNAL_LEGACY_MARKER: &str = "<!-- hi:journal-legacy -->";
+
+fn journal_day_dir(data_dir: &Path, date: NaiveDate) -> PathBuf {
+    data_dir
+        .join("journals")
+        .join(format!("{:04}", date.year()))
+        .join(format!("{:02}", date.month()))
+        .join(format!("{:02}", date.day()))
+}
+
+/// Regenerate `journals/YYYY/MM/DD.md` as an index over the per-intent files in
+/// `journals/YYYY/MM/DD/`. Entries written before the split are kept verbatim
+/// below the index so the old path stays readable.
+pub async fn rebuild_journal_index(
+    data_dir: &Path,
+    date: NaiveDate,
+    keep_history: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
+    let day_dir = journal_day_dir(data_dir, date);
+    let index_path = day_dir.with_extension("md");
+
+    let legacy = match async_fs::read_to_string(&index_path).await {
+        Ok(existing) if existing.starts_with(JOURNAL_INDEX_MARKER) => existing
+            .split_once(JOURNAL_LEGACY_MARKER)
+            .map(|(_, legacy)| legacy.trim().to_string())
+            .unwrap_or_default(),
+        Ok(existing) => existing.trim().to_string(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("reading journal index {:?}", index_path));
+        }
+    };
+
+    let day_name = day_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut sections = Vec::new();
+    for path in list_markdown_files(&day_dir) {
+        let content = async_fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("reading journal file {:?}", path))?;
+        let Some(heading) = content
+            .lines()
+            .find_map(|line| line.strip_prefix("## "))
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let link = format!("{}/{}", day_name, file_name.to_string_lossy());
+        sections.push((heading, link));
+    }
+    sections.sort();
+
+    let mut index = format!("{}\n# Journal {}\n\n", JOURNAL_INDEX_MARKER, date);
+    if sections.is_empty() {
+        index.push_str("(no entries yet)\n");
+    }
+    for (heading, link) in &sections {
+        let _ = writeln!(&mut index, "- [{}]({})", heading, link);
+    }
+    if !legacy.is_empty() {
+        let _ = write!(
+            &mut index,
+            "\n{}\n## Earlier entries\n\n{}\n",
+            JOURNAL_LEGACY_MARKER, legacy
+        );
+    }
+
+    record_journal_revision(data_dir, &index_path, keep_history, now).await?;
+    write_markdown(&index_path, &index).await?;
+    record_journal_revision(data_dir, &index_path, keep_history, now).await?;
+    Ok(index_path)
+}
+
+/// Journals are versioned around every write while history is kept; see
+/// [`record_markdown_revision`].
+async fn record_journal_revision(
+    data_dir: &Path,
+    path: &Path,
+    keep_history: bool,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    if !keep_history {
+        return Ok(());
+    }
+    if let Ok(relative) = path.strip_prefix(data_dir) {
+        record_markdown_revision(data_dir, relative, now).await?;
+    }
+    Ok(())
+}
+
+pub async fn archive_intent(
+    intent: &Intent,
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<PathBuf>> {
     let Some(path) = intent.storage_path.as_ref() else {
         return Ok(None);
     };
@@ -519,118 +1553,11 @@ pub async fn archive_intent(intent: &Intent, data_dir: &Path) -> anyhow::Result<
         .file_name()
         .ok_or_else(|| anyhow!("intent path missing file name: {:?}", path))?;
     let destination = history_dir.join(file_name);
+    record_intent_state(path, IntentState::Done, None, now)?;
     async_fs::rename(path, &destination).await?;
     Ok(Some(destination))
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SpIndex {
-    #[serde(default)]
-    pub top_used: Vec<String>,
-    #[serde(default)]
-    pub most_recent: Vec<String>,
-}
-
-pub async fn load_sp_index(data_dir: &Path) -> anyhow::Result<SpIndex> {
-    let path = data_dir.join("sp/index.json");
-    let content = async_fs::read_to_string(&path).await?;
-    let persisted: PersistedSpIndex =
-        serde_json::from_str(&content).with_context(|| "parsing sp/index.json")?;
-
-    let top_used = persisted
-        .top_used
-        .iter()
-        .map(|entry| format!("{} ({})", entry.summary, entry.count))
-        .collect();
-    let most_recent = persisted
-        .most_recent
-        .iter()
-        .map(|entry| entry.summary.clone())
-        .collect();
-
-    Ok(SpIndex {
-        top_used,
-        most_recent,
-    })
-}
-
-pub async fn update_sp_index(
-    data_dir: &Path,
-    intent: &Intent,
-    outcome: &AgentOutcome,
-) -> anyhow::Result<()> {
-    let index_path = data_dir.join("sp/index.json");
-    if let Some(parent) = index_path.parent() {
-        async_fs::create_dir_all(parent).await?;
-    }
-
-    let mut index = if async_fs::try_exists(&index_path).await? {
-        let content = async_fs::read_to_string(&index_path).await?;
-        serde_json::from_str::<PersistedSpIndex>(&content)?
-    } else {
-        PersistedSpIndex::default()
-    };
-
-    let now = Utc::now();
-    let summary = format!("{} ⇒ {}", intent.summary, outcome.final_answer);
-    upsert_top_used(&mut index.top_used, &summary, now);
-    upsert_most_recent(&mut index.most_recent, &summary, now);
-
-    let serialized = serde_json::to_string_pretty(&index)?;
-    async_fs::write(&index_path, serialized).await?;
-    Ok(())
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-struct PersistedSpIndex {
-    #[serde(default)]
-    top_used: Vec<SpEntry>,
-    #[serde(default)]
-    most_recent: Vec<SpEntry>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct SpEntry {
-    summary: String,
-    count: u32,
-    last_seen: DateTime<Utc>,
-}
-
-fn upsert_top_used(entries: &mut Vec<SpEntry>, summary: &str, now: DateTime<Utc>) {
-    if let Some(entry) = entries.iter_mut().find(|entry| entry.summary == summary) {
-        entry.count += 1;
-        entry.last_seen = now;
-    } else {
-        entries.push(SpEntry {
-            summary: summary.to_string(),
-            count: 1,
-            last_seen: now,
-        });
-    }
-
-    entries.sort_by(|a, b| {
-        b.count
-            .cmp(&a.count)
-            .then_with(|| b.last_seen.cmp(&a.last_seen))
-    });
-    if entries.len() > 10 {
-        entries.truncate(10);
-    }
-}
-
-fn upsert_most_recent(entries: &mut Vec<SpEntry>, summary: &str, now: DateTime<Utc>) {
-    entries.retain(|entry| entry.summary != summary);
-    entries.push(SpEntry {
-        summary: summary.to_string(),
-        count: 1,
-        last_seen: now,
-    });
-    entries.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
-    if entries.len() > 10 {
-        entries.truncate(10);
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MessageDirection {
@@ -677,6 +1604,7 @@ pub struct MessageLogEntry {
 pub struct MessageLogQuery {
     pub source: Option<String>,
     pub direction: Option<MessageDirection>,
+    pub chat_id: Option<String>,
     pub since: Option<DateTime<Utc>>,
     pub limit: usize,
 }
@@ -686,17 +1614,48 @@ impl Default for MessageLogQuery {
         Self {
             source: None,
             direction: None,
+            chat_id: None,
             since: None,
             limit: 50,
         }
     }
 }
 
+/// Per-chat logs live under `messages/<source>/chats/<chat>/<direction>/`.
+/// Older builds wrote one log per source and direction directly under
+/// `messages/<source>/<direction>/`; those files are still read.
+const MESSAGE_CHATS_DIR: &str = "chats";
+
+/// Directory name for `chat_id`: anything outside a conservative character
+/// set, and a leading dot, becomes `_`. Distinct ids may share a directory,
+/// so reads still match on the entry's own `chat_id`.
+fn chat_dir_name(chat_id: &str) -> String {
+    let name: String = chat_id
+        .chars()
+        .enumerate()
+        .map(|(index, ch)| {
+            let dot_ok = ch == '.' && index > 0;
+            if ch.is_ascii_alphanumeric() || dot_ok || matches!(ch, '-' | '_' | '@' | '+') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name
+    }
+}
+
 pub async fn append_message_entry(data_dir: &Path, entry: &MessageLogEntry) -> anyhow::Result<()> {
     let date = entry.timestamp.date_naive();
     let day_dir = data_dir
         .join("messages")
         .join(&entry.source)
+        .join(MESSAGE_CHATS_DIR)
+        .join(chat_dir_name(&entry.chat_id))
         .join(entry.direction.as_dir())
         .join(format!("{:04}", date.year()))
         .join(format!("{:02}", date.month()));
@@ -744,67 +1703,62 @@ pub fn read_messages(
         }
     }
 
-    let mut files = Vec::new();
-    for source_dir in source_dirs {
-        let target_dirs: Vec<PathBuf> = if let Some(direction) = &query.direction {
-            vec![source_dir.join(direction.as_dir())]
-        } else {
-            let mut dirs = Vec::new();
-            for entry in fs::read_dir(&source_dir)
-                .with_context(|| format!("reading message direction dir {:?}", source_dir))?
-            {
-                let entry = entry?;
-                if entry.file_type()?.is_dir() {
-                    dirs.push(entry.path());
-                }
-            }
-            if dirs.is_empty() {
-                vec![source_dir.join("inbound"), source_dir.join("outbound")]
-            } else {
-                dirs
-            }
-        };
+    let directions = match &query.direction {
+        Some(direction) => vec![direction.as_dir()],
+        None => vec![
+            MessageDirection::Inbound.as_dir(),
+            MessageDirection::Outbound.as_dir(),
+        ],
+    };
 
-        for dir in target_dirs {
-            if !dir.exists() {
-                continue;
-            }
-            for year in
-                fs::read_dir(&dir).with_context(|| format!("reading message year dir {:?}", dir))?
-            {
-                let year = year?;
-                if !year.file_type()?.is_dir() {
-                    continue;
-                }
-                for month in fs::read_dir(year.path())
-                    .with_context(|| format!("reading message month dir {:?}", year.path()))?
+    // Every directory holding `YYYY/MM/DD.jsonl` day logs the query can match.
+    let mut log_dirs = Vec::new();
+    for source_dir in source_dirs {
+        let chats_dir = source_dir.join(MESSAGE_CHATS_DIR);
+        let chat_dirs = match &query.chat_id {
+            Some(chat_id) => vec![chats_dir.join(chat_dir_name(chat_id))],
+            None if chats_dir.is_dir() => {
+                let mut dirs = Vec::new();
+                for entry in fs::read_dir(&chats_dir)
+                    .with_context(|| format!("reading message chats dir {:?}", chats_dir))?
                 {
-                    let month = month?;
-                    if !month.file_type()?.is_dir() {
-                        continue;
-                    }
-                    for file in fs::read_dir(month.path())
-                        .with_context(|| format!("reading message file dir {:?}", month.path()))?
-                    {
-                        let file = file?;
-                        if file.file_type()?.is_file() {
-                            files.push(file.path());
-                        }
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        dirs.push(entry.path());
                     }
                 }
+                dirs
             }
+            None => Vec::new(),
+        };
+        for chat_dir in chat_dirs {
+            log_dirs.extend(directions.iter().map(|direction| chat_dir.join(direction)));
         }
+        log_dirs.extend(
+            directions
+                .iter()
+                .map(|direction| source_dir.join(direction)),
+        );
     }
 
-    files.sort();
+    let since_date = query.since.map(|since| since.date_naive());
+    let mut files = Vec::new();
+    for dir in log_dirs {
+        files.extend(llm_index::day_logs(&dir, since_date)?);
+    }
+    files.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
 
+    // Day logs are appended in order, so reading each from its end yields
+    // its newest entries first and can stop after `limit` of them or at the
+    // first one before `since`.
     let mut entries = Vec::new();
-    for path in files.iter().rev() {
-        let file =
-            fs::File::open(path).with_context(|| format!("opening message log {:?}", path))?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line?;
+    let mut files = files.into_iter().peekable();
+    while let Some((date, path)) = files.next() {
+        let lines = tail::ReverseLines::open(&path)
+            .with_context(|| format!("opening message log {:?}", path))?;
+        let mut matched = 0;
+        for line in lines {
+            let line = line.with_context(|| format!("reading message log {:?}", path))?;
             if line.trim().is_empty() {
                 continue;
             }
@@ -814,17 +1768,31 @@ pub fn read_messages(
                 .since
                 .as_ref()
                 .is_some_and(|since| entry.timestamp < *since)
+            {
+                break;
+            }
+            if query
+                .chat_id
+                .as_ref()
+                .is_some_and(|chat_id| entry.chat_id != *chat_id)
             {
                 continue;
             }
             entries.push(entry);
+            matched += 1;
+            if matched >= query.limit {
+                break;
+            }
         }
-        if entries.len() >= query.limit {
+        // Older days cannot hold newer entries, but other chats' logs for
+        // the same day can.
+        let day_done = files.peek().is_none_or(|(next, _)| *next < date);
+        if day_done && entries.len() >= query.limit {
             break;
         }
     }
 
-    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
     if entries.len() > query.limit {
         entries.truncate(query.limit);
     }
@@ -835,6 +1803,7 @@ pub fn read_messages(
 mod tests {
     use super::*;
     use crate::agent::AgentStep;
+    use chrono::TimeZone;
     use tempfile::tempdir;
 
     #[test]
@@ -847,12 +1816,88 @@ mod tests {
         let intent_path = queue_dir.join("sample.md");
         std::fs::write(&intent_path, "test").unwrap();
 
-        let moved = quarantine_failed_intent(&intent_path, data_dir).unwrap();
+        let moved = quarantine_failed_intent(&intent_path, data_dir, Utc::now()).unwrap();
         assert!(!intent_path.exists());
         assert!(moved.exists());
         assert!(moved.starts_with(data_dir.join("intent/queue/failed")));
     }
 
+    #[tokio::test]
+    async fn intent_moves_are_recorded_as_state_transitions() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).unwrap();
+        let created_at = Utc.with_ymd_and_hms(2025, 1, 1, 9, 0, 0).unwrap();
+        let draft = IntentDraft {
+            source: "user".to_string(),
+            summary: "Tidy notes".to_string(),
+            telos_alignment: 0.9,
+            ..IntentDraft::default()
+        };
+
+        let persisted = persist_intent_at(data_dir, &draft, created_at)
+            .await
+            .unwrap();
+        let at = |minute| created_at + chrono::Duration::minutes(minute);
+        let deferred = defer_intent(&persisted.path, data_dir, at(1)).unwrap();
+        let approved = approve_intent(&deferred, data_dir, at(2)).unwrap();
+        let queued = promote_to_queue(&approved, data_dir, at(3)).unwrap();
+        record_intent_state(&queued, IntentState::Processing, None, at(4)).unwrap();
+        // Recording the current state again adds nothing.
+        record_intent_state(&queued, IntentState::Processing, None, at(5)).unwrap();
+        set_intent_metadata(&queued, &[("note", "kept")]).unwrap();
+        let intent = load_intent(&queued).unwrap().unwrap();
+        let archived = archive_intent(&intent, data_dir, at(6))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let (state, history) = load_intent_history(&archived).unwrap();
+        assert_eq!(state, Some(IntentState::Done));
+        let moves: Vec<_> = history
+            .iter()
+            .map(|transition| (transition.from, transition.to))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                (None, IntentState::Received),
+                (Some(IntentState::Received), IntentState::Deferred),
+                (Some(IntentState::Deferred), IntentState::Received),
+                (Some(IntentState::Received), IntentState::Queued),
+                (Some(IntentState::Queued), IntentState::Processing),
+                (Some(IntentState::Processing), IntentState::Done),
+            ]
+        );
+        let stamps: Vec<_> = history.iter().map(|transition| transition.at).collect();
+        assert_eq!(stamps, [created_at, at(1), at(2), at(3), at(4), at(6)]);
+        assert_eq!(history[2].reason.as_deref(), Some("approved"));
+        assert_eq!(
+            find_intent(data_dir, persisted.id).unwrap().unwrap().0,
+            "history"
+        );
+
+        // Finished intents stay finished.
+        let err = record_intent_state(&archived, IntentState::Queued, None, Utc::now())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cannot move from done to queued"), "{err}");
+
+        // Files from before states were recorded take any first move.
+        let legacy = data_dir.join("intent/queue/legacy.md");
+        std::fs::write(&legacy, "---\nsummary: Old\n---\n\nbody\n").unwrap();
+        let failed = quarantine_failed_intent(&legacy, data_dir, Utc::now()).unwrap();
+        let (state, history) = load_intent_history(&failed).unwrap();
+        assert_eq!(state, Some(IntentState::Failed));
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].from, None);
+        assert!(
+            std::fs::read_to_string(&failed)
+                .unwrap()
+                .ends_with("body\n")
+        );
+    }
+
     fn sample_intent_with_path(path: PathBuf) -> Intent {
         Intent {
             id: Uuid::new_v4(),
@@ -860,6 +1905,8 @@ mod tests {
             summary: "Write summary".to_string(),
             telos_alignment: 0.9,
             created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
             storage_path: Some(path),
         }
     }
@@ -870,6 +1917,9 @@ mod tests {
                 thought: "Collect context".to_string(),
                 action: "summarize_intent".to_string(),
                 observation: "Remaining backlog count: 1".to_string(),
+                question: None,
+                input: None,
+                sources: Vec::new(),
             }],
             final_answer: "Done".to_string(),
         }
@@ -880,12 +1930,17 @@ mod tests {
         let temp = tempdir().unwrap();
         ensure_data_layout(temp.path()).unwrap();
 
+        let due_at = "2025-01-02T03:04:05Z".parse::<DateTime<Utc>>().unwrap();
         let record = persist_intent(
             temp.path(),
-            "cli",
-            "Launch sequence",
-            0.7,
-            "## body\ncontent",
+            &IntentDraft {
+                source: "cli".to_string(),
+                summary: "Launch sequence".to_string(),
+                telos_alignment: 0.7,
+                body: "## body\ncontent".to_string(),
+                due_at: Some(due_at),
+                metadata: BTreeMap::from([("origin".to_string(), "unit-test".to_string())]),
+            },
         )
         .await
         .unwrap();
@@ -894,6 +1949,133 @@ mod tests {
         assert!(record.path.starts_with(temp.path().join("intent/inbox")));
         assert!(content.contains("summary: Launch sequence"));
         assert!(content.contains("## body"));
+
+        let scanned = scan_inbox(temp.path()).unwrap();
+        assert_eq!(scanned[0].intent.due_at, Some(due_at));
+        assert_eq!(
+            scanned[0].intent.metadata.get("origin").map(String::as_str),
+            Some("unit-test")
+        );
+
+        set_intent_metadata(&record.path, &[(INTENT_APPROVED_KEY, "true")]).unwrap();
+        let updated = std::fs::read_to_string(&record.path).unwrap();
+        assert!(updated.ends_with("## body\ncontent\n"));
+        let scanned = scan_inbox(temp.path()).unwrap();
+        assert!(scanned[0].intent.is_approved());
+        assert_eq!(scanned[0].intent.due_at, Some(due_at));
+
+        let before_due = due_at - chrono::Duration::hours(1);
+        let pending = list_pending_intents(temp.path(), before_due).unwrap();
+        assert_eq!(pending[0].stage, "inbox");
+        assert!(!pending[0].overdue);
+
+        let after_due = due_at + chrono::Duration::hours(1);
+        let pending = list_pending_intents(temp.path(), after_due).unwrap();
+        assert!(pending[0].overdue);
+        assert_eq!(pending[0].body, "## body\ncontent");
+        assert!(!pending[0].body_truncated);
+
+        let long_body = "é".repeat(INTENT_BODY_PREVIEW_CHARS + 10);
+        let mut intent = scanned[0].intent.clone();
+        intent.id = Uuid::new_v4();
+        write_intent_file(
+            &temp.path().join("intent/inbox/long.md"),
+            &intent,
+            &long_body,
+        )
+        .unwrap();
+        let pending = list_stage_intents(temp.path(), "inbox", after_due)
+            .unwrap()
+            .unwrap();
+        let long = pending.iter().find(|p| p.intent.id == intent.id).unwrap();
+        assert!(long.body_truncated);
+        assert_eq!(long.body.chars().count(), INTENT_BODY_PREVIEW_CHARS);
+        let (_, record) = find_intent(temp.path(), intent.id).unwrap().unwrap();
+        assert_eq!(record.body, long_body);
+    }
+
+    #[tokio::test]
+    async fn replay_clones_history_intent_into_inbox() {
+        let temp = tempdir().unwrap();
+        ensure_data_layout(temp.path()).unwrap();
+
+        let history_dir = temp.path().join("intent/history");
+        std::fs::create_dir_all(&history_dir).unwrap();
+        let original_id = Uuid::new_v4();
+        std::fs::write(
+            history_dir.join("done.md"),
+            format!(
+                "---\nid: {original_id}\nsource: telegram\nsummary: Draft report\ntelos_alignment: 0.4\ndue_at: 2025-01-02T03:04:05Z\nmetadata:\n  approved: 'true'\n  llm_model: old-model\n  priority: high\n---\n\nOriginal body\n"
+            ),
+        )
+        .unwrap();
+        let record = scan_history(temp.path()).unwrap().remove(0);
+
+        let replayed = replay_intent(
+            temp.path(),
+            &record,
+            &[(crate::tasks::LLM_MODEL_KEY, "gpt-test")],
+        )
+        .await
+        .unwrap();
+
+        assert_ne!(replayed.id, original_id);
+        assert!(history_dir.join("done.md").exists());
+        let inbox = scan_inbox(temp.path()).unwrap();
+        let intent = &inbox[0].intent;
+        assert_eq!(intent.id, replayed.id);
+        assert_eq!(intent.summary, "Draft report");
+        assert_eq!(intent.due_at, None);
+        assert!(!intent.is_approved());
+        assert_eq!(
+            intent.metadata.get(REPLAY_OF_KEY),
+            Some(&original_id.to_string())
+        );
+        assert_eq!(
+            intent.metadata.get("llm_model").map(String::as_str),
+            Some("gpt-test")
+        );
+        assert_eq!(intent.priority(), crate::tasks::IntentPriority::High);
+        let content = std::fs::read_to_string(&replayed.path).unwrap();
+        assert!(content.ends_with("Original body\n"));
+    }
+
+    #[tokio::test]
+    async fn replay_has_no_reply_target() {
+        let temp = tempdir().unwrap();
+        ensure_data_layout(temp.path()).unwrap();
+
+        let history_dir = temp.path().join("intent/history");
+        std::fs::create_dir_all(&history_dir).unwrap();
+        std::fs::write(
+            history_dir.join("done.md"),
+            format!(
+                "---\nid: {}\nsource: github\nsummary: Fix the build\ntelos_alignment: 0.8\nmetadata:\n  telegram_chat_id: '42'\n  telegram_message_id: '7'\n  github_repo: acme/app\n  github_issue_number: '12'\n  github_issue_url: https://github.com/acme/app/issues/12\n  sla_alerted_at: 2025-01-02T03:04:05Z\n  duration_ms: '1200'\n  persona: reviewer\n---\n\nBody\n",
+                Uuid::new_v4()
+            ),
+        )
+        .unwrap();
+        let record = scan_history(temp.path()).unwrap().remove(0);
+
+        replay_intent(temp.path(), &record, &[]).await.unwrap();
+
+        let intent = &scan_inbox(temp.path()).unwrap()[0].intent;
+        assert_eq!(crate::telegram::origin_chat_id(intent), None);
+        for key in [
+            github::REPO_KEY,
+            github::ISSUE_NUMBER_KEY,
+            SLA_ALERTED_AT_KEY,
+            DURATION_MS_KEY,
+        ] {
+            assert!(!intent.metadata.contains_key(key), "{key}");
+        }
+        assert_eq!(
+            intent
+                .metadata
+                .get(crate::tasks::PERSONA_KEY)
+                .map(String::as_str),
+            Some("reviewer")
+        );
     }
 
     #[tokio::test]
@@ -907,55 +2089,72 @@ mod tests {
         std::fs::write(&source_path, "---\nsummary: intent\n---").unwrap();
 
         let intent = sample_intent_with_path(source_path.clone());
-        let outcome = sample_outcome();
+        let mut outcome = sample_outcome();
+        outcome.steps[0].input = Some("launch checklist".to_string());
+        outcome.steps[0].sources = vec!["https://example.com/a".to_string()];
+
+        let links = JournalLinks {
+            run_id: Uuid::new_v4(),
+            intent_id: intent.id,
+            memory_ids: vec![Uuid::new_v4(), Uuid::new_v4()],
+        };
 
-        let journal_path = append_journal_entry(temp.path(), &intent, &outcome)
-            .await
-            .unwrap();
+        let timing = RunTiming {
+            duration_ms: 1500,
+            llm_latency_ms: 1200,
+        };
+        let journal_path =
+            append_journal_entry(temp.path(), &intent, &outcome, &links, Some(timing))
+                .await
+                .unwrap();
 
         let entry = tokio::fs::read_to_string(&journal_path).await.unwrap();
         assert!(entry.contains("Final answer: Done"));
+        assert!(entry.contains("Duration: 1500 ms (LLM 1200 ms)"));
+        assert!(entry.contains("Sources:\n- <https://example.com/a>\n"));
+        assert!(entry.contains("Action: summarize_intent (launch checklist)"));
+        assert_eq!(entry.lines().find_map(RunTiming::from_line), Some(timing));
         assert!(entry.contains("ReAct trace"));
+        assert_eq!(parse_journal_links(&entry), vec![links]);
+        assert_eq!(
+            journal_path.file_name().unwrap().to_string_lossy(),
+            format!("{}.md", intent.id)
+        );
+
+        let index_path = journal_path.parent().unwrap().with_extension("md");
+        let index = tokio::fs::read_to_string(&index_path).await.unwrap();
+        assert!(index.starts_with(JOURNAL_INDEX_MARKER));
+        assert!(index.contains(&format!("/{}.md)", intent.id)));
     }
 
     #[tokio::test]
-    async fn update_sp_index_increments_counts_and_recent() {
+    async fn journal_index_keeps_legacy_daily_entries() {
         let temp = tempdir().unwrap();
-        ensure_data_layout(temp.path()).unwrap();
-
-        let queue_dir = temp.path().join("intent/queue");
-        std::fs::create_dir_all(&queue_dir).unwrap();
-        let source_path = queue_dir.join("intent.md");
-        std::fs::write(&source_path, "---\nsummary: intent\n---").unwrap();
-
-        let intent = sample_intent_with_path(source_path);
-        let outcome = sample_outcome();
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let day_dir = journal_day_dir(temp.path(), date);
+        std::fs::create_dir_all(&day_dir).unwrap();
+        std::fs::write(
+            day_dir.with_extension("md"),
+            "## 08:00:00 — Old\n\nFinal answer: legacy",
+        )
+        .unwrap();
+        std::fs::write(
+            day_dir.join("a.md"),
+            "## 09:00:00 — New\n\nFinal answer: new",
+        )
+        .unwrap();
 
-        update_sp_index(temp.path(), &intent, &outcome)
-            .await
-            .unwrap();
-        update_sp_index(temp.path(), &intent, &outcome)
+        rebuild_journal_index(temp.path(), date, true, Utc::now())
             .await
             .unwrap();
-
-        let content = tokio::fs::read_to_string(temp.path().join("sp/index.json"))
+        let index_path = rebuild_journal_index(temp.path(), date, true, Utc::now())
             .await
             .unwrap();
-        let persisted: PersistedSpIndex = serde_json::from_str(&content).unwrap();
 
-        assert_eq!(persisted.top_used.len(), 1);
-        assert_eq!(persisted.top_used[0].count, 2);
-        assert!(
-            persisted.top_used[0]
-                .summary
-                .contains("Write summary ⇒ Done")
-        );
-        assert_eq!(persisted.most_recent.len(), 1);
-        assert!(
-            persisted.most_recent[0]
-                .summary
-                .contains("Write summary ⇒ Done")
-        );
+        let index = std::fs::read_to_string(index_path).unwrap();
+        assert!(index.contains("- [09:00:00 — New](01/a.md)"));
+        assert_eq!(index.matches("Final answer: legacy").count(), 1);
+        assert_eq!(index.matches(JOURNAL_LEGACY_MARKER).count(), 1);
     }
 
     #[test]
@@ -975,7 +2174,7 @@ mod tests {
         let file_path = intent_dir.join("example.md");
         tokio::fs::write(&file_path, "# Title\nBody").await.unwrap();
 
-        let tree = list_markdown_tree(temp.path()).unwrap();
+        let tree = list_markdown_tree(temp.path(), &WalkPolicy::default()).unwrap();
         assert_eq!(tree, vec!["intent/history/example.md".to_string()]);
 
         let relative = sanitize_data_relative_path("intent/history/example.md").unwrap();
@@ -1032,6 +2231,74 @@ mod tests {
         assert_eq!(inbound[0].text, "hello");
     }
 
+    #[tokio::test]
+    async fn message_logs_are_partitioned_by_chat() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        let timestamp = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+        let entry = |chat_id: &str, text: &str, offset: i64| MessageLogEntry {
+            id: Uuid::new_v4(),
+            direction: MessageDirection::Inbound,
+            source: "email".to_string(),
+            chat_id: chat_id.to_string(),
+            author: None,
+            text: text.to_string(),
+            timestamp: timestamp + chrono::Duration::minutes(offset),
+            metadata: None,
+        };
+
+        append_message_entry(data_dir, &entry("a@example.com", "first", 0))
+            .await
+            .unwrap();
+        append_message_entry(data_dir, &entry("b/../c", "other", 1))
+            .await
+            .unwrap();
+        // Written by an older build: one log for every chat.
+        let legacy = data_dir.join("messages/email/inbound/2025/02/28.jsonl");
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(
+            &legacy,
+            format!(
+                "{}\n{}\n",
+                serde_json::to_string(&entry("a@example.com", "legacy", -60 * 24)).unwrap(),
+                serde_json::to_string(&entry("b/../c", "legacy other", -60 * 24)).unwrap()
+            ),
+        )
+        .unwrap();
+
+        assert!(
+            data_dir
+                .join("messages/email/chats/a@example.com/inbound/2025/03/01.jsonl")
+                .exists()
+        );
+        assert!(data_dir.join("messages/email/chats/b_.._c").is_dir());
+
+        let chat = read_messages(
+            data_dir,
+            MessageLogQuery {
+                chat_id: Some("a@example.com".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let texts: Vec<_> = chat.iter().map(|entry| entry.text.as_str()).collect();
+        assert_eq!(texts, vec!["first", "legacy"]);
+
+        let all = read_messages(data_dir, MessageLogQuery::default()).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].text, "other");
+
+        let recent = read_messages(
+            data_dir,
+            MessageLogQuery {
+                since: Some(timestamp),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recent.len(), 2);
+    }
+
     #[tokio::test]
     async fn append_and_read_llm_logs() {
         let temp = tempdir().unwrap();
@@ -1089,4 +2356,180 @@ mod tests {
         assert_eq!(recent_only.len(), 1);
         assert_eq!(recent_only[0].phase, "FINAL");
     }
+
+    #[tokio::test]
+    async fn llm_log_pages_resume_from_cursor() {
+        let temp = tempdir().unwrap();
+        let identity = crate::llm::LlmIdentity::new("local_stub", None);
+        let run_id = Uuid::new_v4();
+        let tied = Utc.with_ymd_and_hms(2025, 1, 2, 9, 0, 0).unwrap();
+        let earlier = Utc.with_ymd_and_hms(2025, 1, 1, 9, 0, 0).unwrap();
+        let later = Utc.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        let entries: Vec<_> = [earlier, tied, tied, tied, later]
+            .into_iter()
+            .enumerate()
+            .map(|(index, at)| {
+                LlmLogEntry::new(run_id, at, "THINK", format!("p{index}"), "r", &identity)
+            })
+            .collect();
+        append_llm_logs(temp.path(), &entries).await.unwrap();
+
+        let mut cursor = None;
+        let mut prompts = Vec::new();
+        loop {
+            let page = read_llm_logs(
+                temp.path(),
+                LlmLogQuery {
+                    cursor,
+                    limit: 2,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            prompts.extend(page.iter().map(|entry| entry.prompt.clone()));
+            if page.len() < 2 {
+                break;
+            }
+            let next = LlmLogCursor::after_page(cursor.as_ref(), &page).unwrap();
+            assert_eq!(LlmLogCursor::parse(&next.encode()).unwrap(), next);
+            cursor = Some(next);
+        }
+        assert_eq!(prompts, ["p4", "p3", "p2", "p1", "p0"]);
+
+        let bounded = read_llm_logs(
+            temp.path(),
+            LlmLogQuery {
+                until: Some(tied),
+                since: Some(tied),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(bounded.len(), 3);
+        assert!(LlmLogCursor::parse("garbage").is_err());
+    }
+
+    #[tokio::test]
+    async fn llm_log_reads_use_day_indexes_and_since() {
+        let temp = tempdir().unwrap();
+        let identity = crate::llm::LlmIdentity::new("openai", Some("gpt-4o".to_string()));
+        let old_run = Uuid::new_v4();
+        let new_run = Uuid::new_v4();
+        let old_day = Utc.with_ymd_and_hms(2024, 12, 31, 9, 0, 0).unwrap();
+        let new_day = Utc.with_ymd_and_hms(2025, 1, 2, 9, 0, 0).unwrap();
+        append_llm_logs(
+            temp.path(),
+            &[
+                LlmLogEntry::new(old_run, old_day, "THINK", "p", "r", &identity),
+                LlmLogEntry::new(new_run, new_day, "THINK", "p", "r", &identity),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let old_log = temp.path().join("logs/llm/2024/12/31.jsonl");
+        let index: llm_index::LlmDayIndex = serde_json::from_str(
+            &fs::read_to_string(temp.path().join("logs/llm/2024/12/31.index.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(index.entries, 1);
+        assert!(index.run_ids.contains(&old_run));
+        assert!(index.models.contains("gpt-4o"));
+
+        // A day the index rules out is never parsed: swap its log for
+        // garbage of the same size so the index still looks fresh.
+        let new_log = temp.path().join("logs/llm/2025/01/02.jsonl");
+        let mut content = fs::read_to_string(&new_log).unwrap();
+        fs::write(&new_log, "x".repeat(content.len())).unwrap();
+        let by_run = read_llm_logs(
+            temp.path(),
+            LlmLogQuery {
+                run_id: Some(old_run),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(by_run.len(), 1);
+        assert_eq!(by_run[0].run_id, old_run);
+
+        // Older days are pruned by `since` before being opened.
+        fs::write(&new_log, &content).unwrap();
+        fs::write(&old_log, "not json\n").unwrap();
+        let recent = read_llm_logs(
+            temp.path(),
+            LlmLogQuery {
+                since: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].run_id, new_run);
+
+        // Appending to a day whose index went stale rebuilds it.
+        content.push_str(
+            &serde_json::to_string(&LlmLogEntry::new(
+                old_run, new_day, "FINAL", "p", "r", &identity,
+            ))
+            .unwrap(),
+        );
+        content.push('\n');
+        fs::write(&new_log, &content).unwrap();
+        append_llm_logs(
+            temp.path(),
+            &[LlmLogEntry::new(
+                Uuid::new_v4(),
+                new_day,
+                "FINAL",
+                "p",
+                "r",
+                &identity,
+            )],
+        )
+        .await
+        .unwrap();
+        let index: llm_index::LlmDayIndex = serde_json::from_str(
+            &fs::read_to_string(temp.path().join("logs/llm/2025/01/02.index.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(index.entries, 3);
+        assert!(index.run_ids.contains(&old_run));
+    }
+
+    #[test]
+    fn queued_intents_and_beat_markers_are_shared_through_the_data_dir() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).unwrap();
+
+        let path = data_dir.join("intent/queue/shared.md");
+        std::fs::write(&path, "---\nsummary: Before\n---\nbody\n").unwrap();
+        edit_intent(
+            &path,
+            &IntentEdit {
+                summary: Some("After".to_string()),
+                ..IntentEdit::default()
+            },
+        )
+        .unwrap();
+        let intent = load_intent(&path).unwrap().expect("intent");
+        assert_eq!(intent.summary, "After");
+        assert_eq!(intent.storage_path.as_deref(), Some(path.as_path()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(load_intent(&path).unwrap().is_none());
+
+        assert!(!take_beat_request(data_dir).unwrap());
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        request_beat(data_dir, now).unwrap();
+        assert!(take_beat_request(data_dir).unwrap());
+        assert!(!take_beat_request(data_dir).unwrap());
+
+        assert_eq!(load_last_beat(data_dir), None);
+        record_last_beat(data_dir, now).unwrap();
+        assert_eq!(load_last_beat(data_dir), Some(now));
+    }
 }
@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::write_atomic;
+use crate::config::{OutboundWebhookConfig, QuietHours};
+
+const OUTBOX_DIR: &str = "outbox";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl OutboxStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for OutboxStatus {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "failed" => Ok(Self::Failed),
+            _ => Err("unknown outbox status"),
+        }
+    }
+}
+
+/// One outbound message and its delivery state, stored as
+/// `data/outbox/<id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    /// The channel: `telegram`, or the type of `target`.
+    pub source: String,
+    /// Telegram chat; `0` for messages with a `target`.
+    #[serde(default)]
+    pub chat_id: i64,
+    pub text: String,
+    pub status: OutboxStatus,
+    /// Urgent messages are sent even during quiet hours.
+    #[serde(default)]
+    pub urgent: bool,
+    /// Where a notification for a channel other than Telegram goes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<OutboxTarget>,
+    /// Telegram `reply_markup`, such as the inline keyboard of an approval
+    /// prompt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<Value>,
+    /// Quiet hours of the notification channel, in place of `quiet_hours`
+    /// from `telegram.yml`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    pub attempts: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When the next delivery attempt is due while `status` is pending.
+    pub next_attempt_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_message_id: Option<i64>,
+}
+
+impl OutboxMessage {
+    pub fn new(source: &str, chat_id: i64, text: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source: source.to_string(),
+            chat_id,
+            text: text.to_string(),
+            status: OutboxStatus::Pending,
+            urgent: false,
+            target: None,
+            reply_markup: None,
+            quiet_hours: None,
+            attempts: 0,
+            created_at: now,
+            updated_at: now,
+            next_attempt_at: now,
+            last_error: None,
+            provider_message_id: None,
+        }
+    }
+
+    /// A notification for `target`, with `text` as its body.
+    pub fn for_target(target: OutboxTarget, text: &str, now: DateTime<Utc>) -> Self {
+        let mut message = Self::new(target.source(), 0, text, now);
+        message.target = Some(target);
+        message
+    }
+}
+
+/// A notification channel other than Telegram, with everything needed to
+/// send to it again from the outbox. Secrets stay in the environment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutboxTarget {
+    Slack {
+        webhook_url_env: String,
+    },
+    Ntfy {
+        server: String,
+        topic: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_env: Option<String>,
+        priority: u8,
+    },
+    /// `payload` is posted as JSON, signed like every outbound webhook.
+    Webhook {
+        webhook: OutboundWebhookConfig,
+        event_id: Uuid,
+        /// Sent as the event header.
+        header: String,
+        /// Event name in the delivery log.
+        event: String,
+        payload: Value,
+    },
+}
+
+impl OutboxTarget {
+    pub fn source(&self) -> &'static str {
+        match self {
+            Self::Slack { .. } => "slack",
+            Self::Ntfy { .. } => "ntfy",
+            Self::Webhook { .. } => "webhook",
+        }
+    }
+}
+
+pub fn save_outbox_message(data_dir: &Path, message: &OutboxMessage) -> anyhow::Result<()> {
+    let dir = data_dir.join(OUTBOX_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("creating outbox dir {:?}", dir))?;
+    let path = outbox_path(data_dir, message.id);
+    write_atomic(&path, serde_json::to_vec_pretty(message)?)
+        .with_context(|| format!("writing outbox message {:?}", path))
+}
+
+pub fn load_outbox_message(data_dir: &Path, id: Uuid) -> anyhow::Result<Option<OutboxMessage>> {
+    let path = outbox_path(data_dir, id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("reading outbox message {:?}", path))?;
+    let message = serde_json::from_str(&content)
+        .with_context(|| format!("parsing outbox message {:?}", path))?;
+    Ok(Some(message))
+}
+
+/// Outbox messages, newest first, optionally restricted to one status.
+/// Files that do not parse are skipped with a warning.
+pub fn list_outbox_messages(
+    data_dir: &Path,
+    status: Option<OutboxStatus>,
+    limit: usize,
+) -> anyhow::Result<Vec<OutboxMessage>> {
+    let mut messages: Vec<_> = read_outbox_dir(data_dir, None)?
+        .into_iter()
+        .filter(|message| status.is_none_or(|status| message.status == status))
+        .collect();
+    messages.sort_by_key(|message| std::cmp::Reverse(message.created_at));
+    messages.truncate(limit);
+    Ok(messages)
+}
+
+/// Pending messages whose next attempt is due, oldest first. Files that do
+/// not parse are moved to `data/quarantine/<timestamp>/outbox/`, so one bad
+/// file does not hold up every other delivery.
+pub fn due_outbox_messages(
+    data_dir: &Path,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<OutboxMessage>> {
+    let mut due: Vec<_> = read_outbox_dir(data_dir, Some(now))?
+        .into_iter()
+        .filter(|message| message.status == OutboxStatus::Pending && message.next_attempt_at <= now)
+        .collect();
+    due.sort_by_key(|message| message.created_at);
+    Ok(due)
+}
+
+/// Every message in the outbox. Unreadable files are skipped, and
+/// quarantined when `quarantine_at` is set.
+fn read_outbox_dir(
+    data_dir: &Path,
+    quarantine_at: Option<DateTime<Utc>>,
+) -> anyhow::Result<Vec<OutboxMessage>> {
+    let dir = data_dir.join(OUTBOX_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut messages = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("reading outbox dir {:?}", dir))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str::<OutboxMessage>(&content)?));
+        match parsed {
+            Ok(message) => messages.push(message),
+            Err(err) => {
+                tracing::warn!(error = ?err, ?path, "skipping unreadable outbox message");
+                if let Some(now) = quarantine_at
+                    && let Err(err) = quarantine_outbox_file(data_dir, &path, now)
+                {
+                    tracing::warn!(error = ?err, ?path, "failed to quarantine outbox message");
+                }
+            }
+        }
+    }
+    Ok(messages)
+}
+
+fn quarantine_outbox_file(data_dir: &Path, path: &Path, now: DateTime<Utc>) -> anyhow::Result<()> {
+    let file_name = path.file_name().context("outbox file without a name")?;
+    let dir = data_dir
+        .join(crate::doctor::QUARANTINE_DIR)
+        .join(now.format("%Y%m%dT%H%M%SZ").to_string())
+        .join(OUTBOX_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("creating {:?}", dir))?;
+    let destination = dir.join(file_name);
+    fs::rename(path, &destination)
+        .with_context(|| format!("quarantining {:?} to {:?}", path, destination))
+}
+
+fn outbox_path(data_dir: &Path, id: Uuid) -> PathBuf {
+    data_dir.join(OUTBOX_DIR).join(format!("{id}.json"))
+}
@@ -0,0 +1,387 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use super::{OutboxMessage, OutboxStatus};
+
+/// Limits for one category of data. Files older than `max_age_days` go
+/// first; then the oldest remaining files until the category fits in
+/// `max_total_mb`.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct RetentionRule {
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    #[serde(default)]
+    pub max_total_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub llm_logs: Option<RetentionRule>,
+    #[serde(default)]
+    pub messages: Option<RetentionRule>,
+    #[serde(default)]
+    pub journals: Option<RetentionRule>,
+    #[serde(default)]
+    pub intent_history: Option<RetentionRule>,
+    /// Delivered and failed outbox messages; pending ones are never pruned.
+    #[serde(default)]
+    pub outbox: Option<RetentionRule>,
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.rules().next().is_none()
+    }
+
+    fn rules(&self) -> impl Iterator<Item = (RetentionCategory, &RetentionRule)> {
+        [
+            (RetentionCategory::LlmLogs, &self.llm_logs),
+            (RetentionCategory::Messages, &self.messages),
+            (RetentionCategory::Journals, &self.journals),
+            (RetentionCategory::IntentHistory, &self.intent_history),
+            (RetentionCategory::Outbox, &self.outbox),
+        ]
+        .into_iter()
+        .filter_map(|(category, rule)| rule.as_ref().map(|rule| (category, rule)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionCategory {
+    LlmLogs,
+    Messages,
+    Journals,
+    IntentHistory,
+    Outbox,
+}
+
+impl RetentionCategory {
+    fn dir(self) -> &'static str {
+        match self {
+            Self::LlmLogs => "logs/llm",
+            Self::Messages => "messages",
+            Self::Journals => "journals",
+            Self::IntentHistory => "intent/history",
+            Self::Outbox => "outbox",
+        }
+    }
+
+    /// Whether retention may delete `path` at all. Outbox messages still
+    /// waiting for delivery, and files that do not parse as one, are left
+    /// to the flusher.
+    fn is_prunable(self, path: &Path) -> bool {
+        match self {
+            Self::Outbox => fs::read_to_string(path)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<OutboxMessage>(&raw).ok())
+                .is_some_and(|message| message.status != OutboxStatus::Pending),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneReason {
+    Age,
+    Size,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrunedFile {
+    /// Path relative to the data dir.
+    pub path: String,
+    pub date: NaiveDate,
+    pub bytes: u64,
+    pub reason: PruneReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryRetentionReport {
+    pub category: RetentionCategory,
+    /// Files and bytes in the category before pruning.
+    pub files: usize,
+    pub bytes: u64,
+    pub pruned: Vec<PrunedFile>,
+    pub pruned_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub categories: Vec<CategoryRetentionReport>,
+}
+
+impl RetentionReport {
+    pub fn pruned_files(&self) -> usize {
+        self.categories
+            .iter()
+            .map(|category| category.pruned.len())
+            .sum()
+    }
+
+    pub fn pruned_bytes(&self) -> u64 {
+        self.categories
+            .iter()
+            .map(|category| category.pruned_bytes)
+            .sum()
+    }
+}
+
+struct RetainedFile {
+    path: PathBuf,
+    date: NaiveDate,
+    bytes: u64,
+}
+
+/// Work out which files `policy` prunes and, unless `dry_run`, delete them
+/// along with the directories they leave empty.
+///
+/// A file is dated by the `YYYY/MM/DD` in its path (logs, messages and
+/// journals are laid out that way) and by its modification time otherwise,
+/// which for intent history is when the intent was archived.
+pub fn apply_retention(
+    data_dir: &Path,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> anyhow::Result<RetentionReport> {
+    let mut categories = Vec::new();
+    for (category, rule) in policy.rules() {
+        let root = data_dir.join(category.dir());
+        let files = category_files(category, &root)?;
+        let mut report = CategoryRetentionReport {
+            category,
+            files: files.len(),
+            bytes: files.iter().map(|file| file.bytes).sum(),
+            pruned: Vec::new(),
+            pruned_bytes: 0,
+        };
+
+        let cutoff = rule
+            .max_age_days
+            .map(|days| now.date_naive() - chrono::Duration::days(days as i64));
+        let mut remaining = report.bytes;
+        let max_bytes = rule.max_total_mb.map(|mb| mb * 1024 * 1024);
+        for file in files {
+            let reason = if cutoff.is_some_and(|cutoff| file.date < cutoff) {
+                PruneReason::Age
+            } else if max_bytes.is_some_and(|max| remaining > max) {
+                PruneReason::Size
+            } else {
+                continue;
+            };
+            remaining -= file.bytes;
+            report.pruned_bytes += file.bytes;
+            report.pruned.push(PrunedFile {
+                path: relative_path(data_dir, &file.path),
+                date: file.date,
+                bytes: file.bytes,
+                reason,
+            });
+            if !dry_run {
+                fs::remove_file(&file.path).with_context(|| format!("pruning {:?}", file.path))?;
+                remove_empty_parents(&root, &file.path);
+            }
+        }
+        categories.push(report);
+    }
+
+    Ok(RetentionReport {
+        dry_run,
+        categories,
+    })
+}
+
+/// Every prunable file under `root`, oldest first.
+fn category_files(category: RetentionCategory, root: &Path) -> anyhow::Result<Vec<RetainedFile>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root) {
+        let entry = entry.with_context(|| format!("walking {:?}", root))?;
+        if !entry.file_type().is_file() || !category.is_prunable(entry.path()) {
+            continue;
+        }
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("reading metadata of {:?}", entry.path()))?;
+        let date = path_date(root, entry.path()).unwrap_or_else(|| {
+            metadata
+                .modified()
+                .map(|modified| DateTime::<Utc>::from(modified).date_naive())
+                .unwrap_or_else(|_| Utc::now().date_naive())
+        });
+        files.push(RetainedFile {
+            path: entry.into_path(),
+            date,
+            bytes: metadata.len(),
+        });
+    }
+    files.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+    Ok(files)
+}
+
+/// The first `YYYY/MM/DD` run of components below `root`, where the day may
+/// be a file stem (`DD.jsonl`) or a directory (`DD/<file>`).
+fn path_date(root: &Path, path: &Path) -> Option<NaiveDate> {
+    let parts: Vec<&str> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    parts.windows(3).find_map(|window| {
+        let day = window[2].split('.').next()?;
+        let all_digits = |value: &str, len: usize| {
+            value.len() == len && value.chars().all(|ch| ch.is_ascii_digit())
+        };
+        if !(all_digits(window[0], 4) && all_digits(window[1], 2) && all_digits(day, 2)) {
+            return None;
+        }
+        NaiveDate::from_ymd_opt(
+            window[0].parse().ok()?,
+            window[1].parse().ok()?,
+            day.parse().ok()?,
+        )
+    })
+}
+
+fn remove_empty_parents(root: &Path, path: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+fn relative_path(data_dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(data_dir).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::save_outbox_message;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, bytes: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; bytes]).unwrap();
+    }
+
+    #[test]
+    fn prunes_by_age_and_size_with_dry_run() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+
+        write(&data_dir.join("logs/llm/2025/01/05.jsonl"), 10);
+        write(&data_dir.join("logs/llm/2025/03/09.jsonl"), 10);
+        write(&data_dir.join("journals/2025/02/01.md"), 10);
+        write(&data_dir.join("journals/2025/02/01/a.md"), 10);
+        write(&data_dir.join("journals/2025/03/01/b.md"), 10);
+        let big = 1024 * 1024;
+        write(&data_dir.join("messages/api/inbound/2025/03/01.jsonl"), big);
+        write(&data_dir.join("messages/api/inbound/2025/03/02.jsonl"), big);
+
+        let policy = RetentionPolicy {
+            llm_logs: Some(RetentionRule {
+                max_age_days: Some(30),
+                max_total_mb: None,
+            }),
+            messages: Some(RetentionRule {
+                max_age_days: None,
+                max_total_mb: Some(1),
+            }),
+            journals: Some(RetentionRule {
+                max_age_days: Some(14),
+                max_total_mb: None,
+            }),
+            intent_history: None,
+            outbox: None,
+        };
+
+        let preview = apply_retention(data_dir, &policy, now, true).unwrap();
+        assert!(preview.dry_run);
+        assert_eq!(preview.categories.len(), 3);
+        assert_eq!(preview.pruned_files(), 4);
+        assert!(data_dir.join("logs/llm/2025/01/05.jsonl").exists());
+
+        let messages = &preview.categories[1];
+        assert_eq!(messages.category, RetentionCategory::Messages);
+        assert_eq!(messages.pruned.len(), 1);
+        assert_eq!(
+            messages.pruned[0].path,
+            "messages/api/inbound/2025/03/01.jsonl"
+        );
+        assert_eq!(messages.pruned[0].reason, PruneReason::Size);
+
+        let applied = apply_retention(data_dir, &policy, now, false).unwrap();
+        assert_eq!(applied.pruned_bytes(), preview.pruned_bytes());
+        assert!(!data_dir.join("logs/llm/2025/01").exists());
+        assert!(data_dir.join("logs/llm/2025/03/09.jsonl").exists());
+        assert!(!data_dir.join("journals/2025/02").exists());
+        assert!(data_dir.join("journals/2025/03/01/b.md").exists());
+        assert!(
+            data_dir
+                .join("messages/api/inbound/2025/03/02.jsonl")
+                .exists()
+        );
+
+        let again = apply_retention(data_dir, &policy, now, false).unwrap();
+        assert_eq!(again.pruned_files(), 0);
+    }
+
+    #[test]
+    fn outbox_retention_keeps_pending_messages() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path();
+        let now = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+        let pending = OutboxMessage::new("test", 1, "later", now);
+        let mut delivered = OutboxMessage::new("test", 1, "sent", now);
+        delivered.status = OutboxStatus::Delivered;
+        save_outbox_message(data_dir, &pending).unwrap();
+        save_outbox_message(data_dir, &delivered).unwrap();
+        write(&data_dir.join("outbox/broken.json"), 3);
+
+        let policy = RetentionPolicy {
+            outbox: Some(RetentionRule {
+                max_age_days: Some(0),
+                max_total_mb: Some(0),
+            }),
+            ..RetentionPolicy::default()
+        };
+        let report = apply_retention(data_dir, &policy, now, false).unwrap();
+        assert_eq!(report.categories[0].files, 1);
+        assert_eq!(report.pruned_files(), 1);
+        assert!(
+            !data_dir
+                .join(format!("outbox/{}.json", delivered.id))
+                .exists()
+        );
+        assert!(
+            data_dir
+                .join(format!("outbox/{}.json", pending.id))
+                .exists()
+        );
+        assert!(data_dir.join("outbox/broken.json").exists());
+    }
+}
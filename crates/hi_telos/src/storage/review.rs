@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use serde::Serialize;
+
+use super::{
+    INTENT_STAGES, IntentDraft, MemoryLevel, MemoryQuery, MemoryTagCount, PersistedIntent,
+    persist_intent_at, read_memory_entries, scan_intent_dir,
+};
+
+pub const WEEKLY_REVIEW_SOURCE: &str = "weekly_review";
+
+const WEEKLY_REVIEW_TOP_TAGS: usize = 5;
+
+/// Activity for one ISO week, used to pre-fill the weekly review intent.
+#[derive(Debug, Clone, Serialize)]
+pub struct WeeklyStats {
+    pub week: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub completed: usize,
+    pub deferred: usize,
+    pub failed: usize,
+    pub top_tags: Vec<MemoryTagCount>,
+}
+
+/// Gather stats for the ISO week that starts on `week_start` (a Monday).
+/// Completed intents are counted from L1 memory entries, which are written
+/// once per processed intent; deferred and failed intents use their
+/// `created_at` front matter.
+pub fn collect_weekly_stats(data_dir: &Path, week_start: NaiveDate) -> anyhow::Result<WeeklyStats> {
+    let start = week_start.and_time(NaiveTime::MIN).and_utc();
+    let end = start + Duration::days(7);
+    let in_week = |ts: DateTime<Utc>| ts >= start && ts < end;
+
+    let entries = read_memory_entries(
+        data_dir,
+        MemoryQuery {
+            level: MemoryLevel::L1,
+            limit: usize::MAX,
+            since: Some(start),
+            tag: None,
+            text: None,
+        },
+    )?;
+
+    let mut completed = 0;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries.iter().filter(|entry| in_week(entry.created_at)) {
+        completed += 1;
+        for tag in &entry.tags {
+            *counts.entry(tag.clone()).or_default() += 1;
+        }
+    }
+    let mut top_tags: Vec<MemoryTagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| MemoryTagCount { tag, count })
+        .collect();
+    top_tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    top_tags.truncate(WEEKLY_REVIEW_TOP_TAGS);
+
+    let deferred = scan_intent_dir(&data_dir.join("intent/inbox/deferred"))?
+        .iter()
+        .filter(|record| in_week(record.intent.created_at))
+        .count();
+    let failed = scan_intent_dir(&data_dir.join("intent/queue/failed"))?
+        .iter()
+        .filter(|record| in_week(record.intent.created_at))
+        .count();
+
+    Ok(WeeklyStats {
+        week: iso_week_label(week_start),
+        start,
+        end,
+        completed,
+        deferred,
+        failed,
+        top_tags,
+    })
+}
+
+/// Drop a weekly review intent into the inbox covering the previous ISO week,
+/// once `review_day` of the current week has arrived. Returns `None` when it
+/// is too early or the review for that week already exists in any intent
+/// stage, including approval, waiting and discarded.
+pub async fn create_weekly_review_intent(
+    data_dir: &Path,
+    now: DateTime<Utc>,
+    review_day: Weekday,
+    telos_alignment: f32,
+) -> anyhow::Result<Option<PersistedIntent>> {
+    let today = now.date_naive();
+    if today.weekday().num_days_from_monday() < review_day.num_days_from_monday() {
+        return Ok(None);
+    }
+
+    let this_week = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let last_week = this_week - Duration::days(7);
+    let summary = format!("Weekly review {}", iso_week_label(last_week));
+
+    for (_, dir) in INTENT_STAGES {
+        if scan_intent_dir(&data_dir.join(dir))?.iter().any(|record| {
+            record.intent.source == WEEKLY_REVIEW_SOURCE && record.intent.summary == summary
+        }) {
+            return Ok(None);
+        }
+    }
+
+    let stats = collect_weekly_stats(data_dir, last_week)?;
+    let body = render_weekly_review_body(&stats);
+    let persisted = persist_intent_at(
+        data_dir,
+        &IntentDraft {
+            source: WEEKLY_REVIEW_SOURCE.to_string(),
+            summary,
+            telos_alignment,
+            body,
+            due_at: None,
+            metadata: Default::default(),
+        },
+        now,
+    )
+    .await?;
+    Ok(Some(persisted))
+}
+
+fn render_weekly_review_body(stats: &WeeklyStats) -> String {
+    let mut body = format!(
+        "## Weekly review {}\n\nPeriod: {} → {}\n\n",
+        stats.week,
+        stats.start.format("%Y-%m-%d"),
+        (stats.end - Duration::days(1)).format("%Y-%m-%d"),
+    );
+    let _ = writeln!(&mut body, "- Intents completed: {}", stats.completed);
+    let _ = writeln!(&mut body, "- Intents deferred: {}", stats.deferred);
+    let _ = writeln!(&mut body, "- Intents failed: {}", stats.failed);
+    if stats.top_tags.is_empty() {
+        body.push_str("- Top tags: (none)\n");
+    } else {
+        let tags = stats
+            .top_tags
+            .iter()
+            .map(|tag| format!("{} ({})", tag.tag, tag.count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(&mut body, "- Top tags: {}", tags);
+    }
+    body.push_str(
+        "\nWrite a structured retrospective with sections: Wins, Misses, Patterns, Next week focus.\n",
+    );
+    body
+}
+
+fn iso_week_label(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    use crate::storage::{discard_intent, ensure_data_layout};
+
+    #[tokio::test]
+    async fn weekly_review_is_created_once_per_week() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).expect("layout");
+
+        // Wednesday 2025-01-15; the review covers 2025-01-06..2025-01-12.
+        let now = "2025-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let too_early = create_weekly_review_intent(data_dir, now, Weekday::Fri, 1.0)
+            .await
+            .expect("early");
+        assert!(too_early.is_none());
+
+        let created = create_weekly_review_intent(data_dir, now, Weekday::Mon, 1.0)
+            .await
+            .expect("create")
+            .expect("review intent");
+        let content = std::fs::read_to_string(&created.path).expect("intent file");
+        assert!(content.contains("summary: Weekly review 2025-W02"));
+        assert!(content.contains("- Intents completed: 0"));
+        assert!(content.contains("Next week focus"));
+
+        let again = create_weekly_review_intent(data_dir, now, Weekday::Mon, 1.0)
+            .await
+            .expect("again");
+        assert!(again.is_none());
+    }
+
+    #[tokio::test]
+    async fn discarded_weekly_review_is_not_recreated() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).expect("layout");
+
+        let now = "2025-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let created = create_weekly_review_intent(data_dir, now, Weekday::Mon, 1.0)
+            .await
+            .expect("create")
+            .expect("review intent");
+        discard_intent(&created.path, data_dir, now).expect("discard");
+
+        let later = now + Duration::hours(1);
+        let again = create_weekly_review_intent(data_dir, later, Weekday::Mon, 1.0)
+            .await
+            .expect("again");
+        assert!(again.is_none());
+    }
+}
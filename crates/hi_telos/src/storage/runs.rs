@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{sanitize_data_relative_path, write_atomic};
+use crate::{llm::LlmLogEntry, tasks::Intent, tools::ToolFile};
+
+/// One directory per agent run under the data dir, named by run id.
+pub const RUNS_DIR: &str = "runs";
+const RUN_MANIFEST_FILE: &str = "run.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Completed,
+    /// Paused on a question to the user.
+    Waiting,
+    Failed,
+}
+
+/// What a run leaves behind for debugging.
+#[derive(Debug, Clone, Copy)]
+pub struct RunArtifacts<'a> {
+    pub run_id: Uuid,
+    /// `None` for chat runs, which have no intent.
+    pub intent: Option<&'a Intent>,
+    pub status: RunStatus,
+    /// The final answer, question or error, by status.
+    pub detail: Option<&'a str>,
+    pub llm_logs: &'a [LlmLogEntry],
+    pub files: &'a [ToolFile],
+}
+
+/// `runs/<run_id>/run.json`: the run and where its artifacts are, as paths
+/// relative to the run directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub run_id: Uuid,
+    pub intent_id: Option<Uuid>,
+    pub summary: Option<String>,
+    pub status: RunStatus,
+    pub detail: Option<String>,
+    pub written_at: DateTime<Utc>,
+    pub prompt_version: Option<String>,
+    pub calls: Vec<RunCall>,
+    pub files: Vec<String>,
+}
+
+/// One LLM or tool call, with its raw request and response saved as files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCall {
+    pub phase: String,
+    /// The model, or the tool name for `TOOL` calls.
+    pub model: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub latency_ms: Option<u64>,
+    /// The prompt, or the tool name and input.
+    pub request: String,
+    /// The raw response, or the tool output.
+    pub response: String,
+}
+
+pub fn run_dir(data_dir: &Path, run_id: Uuid) -> PathBuf {
+    data_dir.join(RUNS_DIR).join(run_id.to_string())
+}
+
+/// Write `runs/<run_id>/`: each call's raw prompt and response under
+/// `calls/`, tool files under `files/` and a `run.json` manifest.
+pub fn write_run_artifacts(
+    data_dir: &Path,
+    artifacts: &RunArtifacts<'_>,
+    now: DateTime<Utc>,
+) -> anyhow::Result<PathBuf> {
+    let dir = run_dir(data_dir, artifacts.run_id);
+    let write = |relative: &str, content: &[u8]| {
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating run artifact dir {:?}", parent))?;
+        }
+        write_atomic(&path, content).with_context(|| format!("writing run artifact {:?}", path))
+    };
+
+    let mut calls = Vec::new();
+    for (index, entry) in artifacts.llm_logs.iter().enumerate() {
+        let phase = entry.phase.to_ascii_lowercase();
+        let (stem, request_kind, response_kind) = match (phase.as_str(), &entry.model) {
+            ("tool", Some(tool)) => (
+                format!("{:03}-tool-{}", index + 1, flat_file_name(tool)),
+                "input",
+                "output",
+            ),
+            _ => (format!("{:03}-{phase}", index + 1), "prompt", "response"),
+        };
+        let request = format!("calls/{stem}.{request_kind}.txt");
+        let response = format!("calls/{stem}.{response_kind}.txt");
+        write(&request, entry.prompt.as_bytes())?;
+        write(&response, entry.response.as_bytes())?;
+        calls.push(RunCall {
+            phase: entry.phase.clone(),
+            model: entry.model.clone(),
+            timestamp: entry.timestamp,
+            latency_ms: entry.latency_ms,
+            request,
+            response,
+        });
+    }
+
+    let mut files: Vec<String> = Vec::new();
+    for file in artifacts.files {
+        let name = flat_file_name(&file.name);
+        let mut relative = format!("files/{name}");
+        let mut copy = 1;
+        while files.contains(&relative) {
+            copy += 1;
+            relative = format!("files/{copy}-{name}");
+        }
+        write(&relative, &file.content)?;
+        files.push(relative);
+    }
+
+    let manifest = RunManifest {
+        run_id: artifacts.run_id,
+        intent_id: artifacts.intent.map(|intent| intent.id),
+        summary: artifacts.intent.map(|intent| intent.summary.clone()),
+        status: artifacts.status,
+        detail: artifacts.detail.map(str::to_string),
+        written_at: now,
+        prompt_version: artifacts
+            .llm_logs
+            .iter()
+            .find_map(|entry| entry.prompt_version.clone()),
+        calls,
+        files,
+    };
+    write(RUN_MANIFEST_FILE, &serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(dir)
+}
+
+/// The manifest of `run_id`, or `None` when the run left no artifacts.
+pub fn load_run_manifest(data_dir: &Path, run_id: Uuid) -> anyhow::Result<Option<RunManifest>> {
+    let path = run_dir(data_dir, run_id).join(RUN_MANIFEST_FILE);
+    let raw = match fs::read(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("reading {:?}", path)),
+    };
+    serde_json::from_slice(&raw)
+        .map(Some)
+        .with_context(|| format!("parsing {:?}", path))
+}
+
+/// The on-disk path of `relative` inside the run directory, refusing paths
+/// that climb out of it.
+pub fn run_artifact_path(data_dir: &Path, run_id: Uuid, relative: &str) -> anyhow::Result<PathBuf> {
+    Ok(run_dir(data_dir, run_id).join(sanitize_data_relative_path(relative)?))
+}
+
+/// `name` as a single path component: anything but ASCII letters, digits,
+/// `.`, `-` and `_` becomes `_`, and leading dots are dropped.
+fn flat_file_name(name: &str) -> String {
+    let flat: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match flat.trim_start_matches('.') {
+        "" => "file".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmIdentity;
+    use tempfile::tempdir;
+
+    #[test]
+    fn writes_calls_files_and_manifest() {
+        let temp = tempdir().unwrap();
+        let run_id = Uuid::new_v4();
+        let now = Utc::now();
+        let identity = LlmIdentity::new("local_stub", Some("local_stub".to_string()));
+        let mut tool_call = LlmLogEntry::new(
+            run_id,
+            now,
+            "TOOL",
+            "fetch_url https://a.test",
+            "page",
+            &identity,
+        );
+        tool_call.model = Some("fetch_url".to_string());
+        let logs = vec![
+            LlmLogEntry::new(run_id, now, "THINK", "# Phase: THINK", "{}", &identity)
+                .with_prompt("abc123", None),
+            tool_call,
+        ];
+        let files = vec![
+            ToolFile {
+                name: "a.test.html".to_string(),
+                content: b"<html>".to_vec(),
+            },
+            ToolFile {
+                name: "../a.test.html".to_string(),
+                content: b"<html>2".to_vec(),
+            },
+            ToolFile {
+                name: "a.test.html".to_string(),
+                content: b"<html>3".to_vec(),
+            },
+        ];
+
+        let dir = write_run_artifacts(
+            temp.path(),
+            &RunArtifacts {
+                run_id,
+                intent: None,
+                status: RunStatus::Failed,
+                detail: Some("boom"),
+                llm_logs: &logs,
+                files: &files,
+            },
+            now,
+        )
+        .unwrap();
+
+        let manifest = load_run_manifest(temp.path(), run_id).unwrap().unwrap();
+        assert_eq!(manifest.status, RunStatus::Failed);
+        assert_eq!(manifest.prompt_version.as_deref(), Some("abc123"));
+        assert_eq!(manifest.calls[0].request, "calls/001-think.prompt.txt");
+        assert_eq!(
+            manifest.calls[1].response,
+            "calls/002-tool-fetch_url.output.txt"
+        );
+        assert_eq!(
+            manifest.files,
+            vec![
+                "files/a.test.html",
+                "files/_a.test.html",
+                "files/2-a.test.html"
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("calls/001-think.prompt.txt")).unwrap(),
+            "# Phase: THINK"
+        );
+        assert_eq!(
+            fs::read(dir.join("files/2-a.test.html")).unwrap(),
+            b"<html>3"
+        );
+
+        assert_eq!(
+            run_artifact_path(temp.path(), run_id, "files/a.test.html").unwrap(),
+            dir.join("files/a.test.html")
+        );
+        assert!(run_artifact_path(temp.path(), run_id, "../other/run.json").is_err());
+        assert!(
+            load_run_manifest(temp.path(), Uuid::new_v4())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::write_atomic;
+
+/// Enough to cover the window a typical feed or calendar keeps published.
+const SEEN_CAPACITY: usize = 500;
+
+/// Ids already turned into intents (or skipped) by a polling source, keyed by
+/// the source URL. Stored at `data/<namespace>/seen.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeenState {
+    #[serde(default)]
+    sources: BTreeMap<String, VecDeque<String>>,
+}
+
+impl SeenState {
+    pub fn contains(&self, source_url: &str, id: &str) -> bool {
+        self.sources
+            .get(source_url)
+            .is_some_and(|seen| seen.iter().any(|seen_id| seen_id == id))
+    }
+
+    /// Remember `id`, dropping the oldest ids once the per-source capacity is
+    /// reached.
+    pub fn insert(&mut self, source_url: &str, id: &str) {
+        if self.contains(source_url, id) {
+            return;
+        }
+        let seen = self.sources.entry(source_url.to_string()).or_default();
+        seen.push_back(id.to_string());
+        while seen.len() > SEEN_CAPACITY {
+            seen.pop_front();
+        }
+    }
+}
+
+pub fn load_seen_state(data_dir: &Path, namespace: &str) -> anyhow::Result<SeenState> {
+    let path = seen_state_path(data_dir, namespace);
+    if !path.exists() {
+        return Ok(SeenState::default());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("reading seen state {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("parsing seen state {:?}", path))
+}
+
+pub fn save_seen_state(data_dir: &Path, namespace: &str, state: &SeenState) -> anyhow::Result<()> {
+    let path = seen_state_path(data_dir, namespace);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating seen state dir {:?}", parent))?;
+    }
+    write_atomic(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("writing seen state {:?}", path))
+}
+
+fn seen_state_path(data_dir: &Path, namespace: &str) -> PathBuf {
+    data_dir.join(namespace).join("seen.json")
+}
@@ -0,0 +1,634 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs as async_fs;
+use uuid::Uuid;
+
+use super::{SCHEMA_VERSION, SchemaKind, parse_record, write_atomic_async};
+use crate::{
+    agent::AgentOutcome,
+    tasks::{CATEGORY_KEY, Intent},
+};
+
+const SP_INDEX_PATH: &str = "sp/index.json";
+
+/// Entries in each list of [`SpIndex`].
+const SP_SUMMARY_LEN: usize = 10;
+
+/// Entries kept in the index; the lowest decayed scores are dropped first.
+const SP_MAX_ENTRIES: usize = 500;
+
+/// Source intents remembered per entry, oldest first.
+const SP_MAX_INTENTS: usize = 20;
+
+/// Days after which a use counts half as much towards an entry's score.
+pub const SP_DECAY_HALF_LIFE_DAYS: f64 = 14.0;
+
+/// The short lists shown on the dashboard and by `/api/sp`.
+#[derive(Debug, Deserialize)]
+pub struct SpIndex {
+    /// `summary (count)`, highest decayed score first.
+    #[serde(default)]
+    pub top_used: Vec<String>,
+    #[serde(default)]
+    pub most_recent: Vec<String>,
+}
+
+/// One "intent ⇒ final answer" pattern.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpEntry {
+    /// [`sp_entry_id`] of the summary, stable across rebuilds.
+    pub id: String,
+    /// The intent's `category` metadata, or its source.
+    pub category: String,
+    pub summary: String,
+    pub count: u32,
+    /// Uses decayed by [`SP_DECAY_HALF_LIFE_DAYS`], as of `last_seen` on
+    /// disk and as of the request in [`SpEntryPage`].
+    pub score: f64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Intents that produced this pattern.
+    #[serde(default)]
+    pub intents: Vec<Uuid>,
+}
+
+impl SpEntry {
+    pub fn score_at(&self, now: DateTime<Utc>) -> f64 {
+        let days = (now - self.last_seen).num_seconds().max(0) as f64 / 86_400.0;
+        self.score * 0.5_f64.powf(days / SP_DECAY_HALF_LIFE_DAYS)
+    }
+}
+
+/// Stable id of an SP summary: the first 12 hex digits of its SHA-256.
+pub fn sp_entry_id(summary: &str) -> String {
+    hex::encode(&Sha256::digest(summary.as_bytes())[..6])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct PersistedSpIndex {
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(default)]
+    entries: Vec<SpEntry>,
+}
+
+async fn read_sp_index(data_dir: &Path) -> anyhow::Result<PersistedSpIndex> {
+    let path = data_dir.join(SP_INDEX_PATH);
+    if !async_fs::try_exists(&path).await? {
+        return Ok(PersistedSpIndex::default());
+    }
+    let content = async_fs::read_to_string(&path).await?;
+    parse_record(SchemaKind::SpIndex, &content).with_context(|| "parsing sp/index.json")
+}
+
+fn sort_by_score(entries: &mut [SpEntry], now: DateTime<Utc>) {
+    entries.sort_by(|a, b| {
+        b.score_at(now)
+            .total_cmp(&a.score_at(now))
+            .then_with(|| b.last_seen.cmp(&a.last_seen))
+    });
+}
+
+pub async fn load_sp_index(data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<SpIndex> {
+    Ok(summarize_sp_index(read_sp_entries(data_dir).await?, now))
+}
+
+/// Where the SP index lives, for callers caching [`read_sp_entries`].
+pub fn sp_index_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(SP_INDEX_PATH)
+}
+
+/// Every entry of the SP index as stored.
+pub async fn read_sp_entries(data_dir: &Path) -> anyhow::Result<Vec<SpEntry>> {
+    Ok(read_sp_index(data_dir).await?.entries)
+}
+
+/// The [`SpIndex`] lists of `entries` with scores decayed to `now`.
+pub fn summarize_sp_index(mut entries: Vec<SpEntry>, now: DateTime<Utc>) -> SpIndex {
+    sort_by_score(&mut entries, now);
+    let top_used = entries
+        .iter()
+        .take(SP_SUMMARY_LEN)
+        .map(|entry| format!("{} ({})", entry.summary, entry.count))
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_seen));
+    let most_recent = entries
+        .iter()
+        .take(SP_SUMMARY_LEN)
+        .map(|entry| entry.summary.clone())
+        .collect();
+
+    SpIndex {
+        top_used,
+        most_recent,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpSort {
+    /// Highest decayed score first.
+    #[default]
+    Score,
+    /// Most recently used first.
+    Recent,
+    /// Most uses first, ignoring decay.
+    Count,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SpEntryQuery {
+    /// Case-insensitive exact category.
+    pub category: Option<String>,
+    /// Only entries produced by this intent.
+    pub intent: Option<Uuid>,
+    pub sort: SpSort,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpEntryPage {
+    /// Entries matching the filters, before paging.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// Entry count per category across the whole index.
+    pub categories: BTreeMap<String, usize>,
+    pub entries: Vec<SpEntry>,
+}
+
+/// Page through every SP entry with scores decayed to `now`.
+pub async fn load_sp_entries(
+    data_dir: &Path,
+    query: &SpEntryQuery,
+    now: DateTime<Utc>,
+) -> anyhow::Result<SpEntryPage> {
+    let entries = read_sp_index(data_dir).await?.entries;
+
+    let mut categories = BTreeMap::new();
+    for entry in &entries {
+        *categories.entry(entry.category.clone()).or_insert(0) += 1;
+    }
+
+    let mut matching: Vec<SpEntry> = entries
+        .into_iter()
+        .filter(|entry| {
+            query
+                .category
+                .as_ref()
+                .is_none_or(|category| entry.category.eq_ignore_ascii_case(category))
+        })
+        .filter(|entry| query.intent.is_none_or(|id| entry.intents.contains(&id)))
+        .collect();
+    match query.sort {
+        SpSort::Score => sort_by_score(&mut matching, now),
+        SpSort::Recent => matching.sort_by_key(|entry| std::cmp::Reverse(entry.last_seen)),
+        SpSort::Count => matching.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| b.last_seen.cmp(&a.last_seen))
+        }),
+    }
+
+    let total = matching.len();
+    let entries = matching
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit)
+        .map(|mut entry| {
+            entry.score = entry.score_at(now);
+            entry
+        })
+        .collect();
+
+    Ok(SpEntryPage {
+        total,
+        offset: query.offset,
+        limit: query.limit,
+        categories,
+        entries,
+    })
+}
+
+/// When past SP entries are offered to the agent as precedents, from
+/// `sp_recall` in `config/agent.yml`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SpRecallPolicy {
+    #[serde(default = "default_sp_recall_enabled")]
+    pub enabled: bool,
+    /// Entries used fewer times are not trusted as a practice yet.
+    #[serde(default = "default_sp_recall_min_count")]
+    pub min_count: u32,
+    /// Word overlap (Jaccard, 0–1) between the intent summaries.
+    #[serde(default = "default_sp_recall_min_similarity")]
+    pub min_similarity: f64,
+    #[serde(default = "default_sp_recall_limit")]
+    pub limit: usize,
+}
+
+impl Default for SpRecallPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: default_sp_recall_enabled(),
+            min_count: default_sp_recall_min_count(),
+            min_similarity: default_sp_recall_min_similarity(),
+            limit: default_sp_recall_limit(),
+        }
+    }
+}
+
+fn default_sp_recall_enabled() -> bool {
+    true
+}
+
+fn default_sp_recall_min_count() -> u32 {
+    2
+}
+
+fn default_sp_recall_min_similarity() -> f64 {
+    0.5
+}
+
+fn default_sp_recall_limit() -> usize {
+    3
+}
+
+/// A past intent resembling the current one and how it was answered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpPrecedent {
+    pub id: String,
+    pub intent: String,
+    pub final_answer: String,
+    pub count: u32,
+    pub similarity: f64,
+}
+
+/// SP entries whose intent resembles `summary`, most similar first.
+pub async fn find_sp_precedents(
+    data_dir: &Path,
+    summary: &str,
+    policy: &SpRecallPolicy,
+) -> anyhow::Result<Vec<SpPrecedent>> {
+    if !policy.enabled || policy.limit == 0 {
+        return Ok(Vec::new());
+    }
+    let wanted = similarity_tokens(summary);
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut precedents: Vec<SpPrecedent> = read_sp_index(data_dir)
+        .await?
+        .entries
+        .into_iter()
+        .filter(|entry| entry.count >= policy.min_count)
+        .filter_map(|entry| {
+            let (intent, final_answer) = entry.summary.split_once(" ⇒ ")?;
+            let similarity = jaccard(&wanted, &similarity_tokens(intent));
+            (similarity >= policy.min_similarity).then(|| SpPrecedent {
+                id: entry.id.clone(),
+                intent: intent.to_string(),
+                final_answer: final_answer.to_string(),
+                count: entry.count,
+                similarity,
+            })
+        })
+        .collect();
+    precedents.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| b.count.cmp(&a.count))
+    });
+    precedents.truncate(policy.limit);
+    Ok(precedents)
+}
+
+/// Lowercase words, with text in scripts written without spaces (e.g.
+/// Chinese) split into character pairs so it can overlap too.
+fn similarity_tokens(text: &str) -> BTreeSet<String> {
+    let mut tokens = BTreeSet::new();
+    for word in text
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        if word.is_ascii() {
+            tokens.insert(word);
+            continue;
+        }
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() == 1 {
+            tokens.insert(word);
+        }
+        for pair in chars.windows(2) {
+            tokens.insert(pair.iter().collect());
+        }
+    }
+    tokens
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+pub async fn update_sp_index(
+    data_dir: &Path,
+    intent: &Intent,
+    outcome: &AgentOutcome,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let index_path = data_dir.join(SP_INDEX_PATH);
+    if let Some(parent) = index_path.parent() {
+        async_fs::create_dir_all(parent).await?;
+    }
+    let mut index = read_sp_index(data_dir).await?;
+
+    let summary = format!("{} ⇒ {}", intent.summary, outcome.final_answer);
+    let id = sp_entry_id(&summary);
+    match index.entries.iter_mut().find(|entry| entry.id == id) {
+        Some(entry) => {
+            entry.score = entry.score_at(now) + 1.0;
+            entry.count += 1;
+            entry.last_seen = now;
+            entry.intents.retain(|known| *known != intent.id);
+        }
+        None => index.entries.push(SpEntry {
+            id: id.clone(),
+            category: sp_category(intent),
+            summary,
+            count: 1,
+            score: 1.0,
+            first_seen: now,
+            last_seen: now,
+            intents: Vec::new(),
+        }),
+    }
+    if let Some(entry) = index.entries.iter_mut().find(|entry| entry.id == id) {
+        entry.intents.push(intent.id);
+        let excess = entry.intents.len().saturating_sub(SP_MAX_INTENTS);
+        entry.intents.drain(..excess);
+    }
+
+    sort_by_score(&mut index.entries, now);
+    index.entries.truncate(SP_MAX_ENTRIES);
+    index.schema_version = SCHEMA_VERSION;
+
+    let serialized = serde_json::to_string_pretty(&index)?;
+    write_atomic_async(&index_path, serialized).await
+}
+
+fn sp_category(intent: &Intent) -> String {
+    intent
+        .metadata
+        .get(CATEGORY_KEY)
+        .map(|category| category.trim())
+        .filter(|category| !category.is_empty())
+        .unwrap_or(&intent.source)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentStep;
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    fn intent(summary: &str, source: &str) -> Intent {
+        Intent {
+            id: Uuid::new_v4(),
+            source: source.to_string(),
+            summary: summary.to_string(),
+            telos_alignment: 0.9,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
+            storage_path: None,
+        }
+    }
+
+    fn outcome(final_answer: &str) -> AgentOutcome {
+        AgentOutcome {
+            steps: vec![AgentStep {
+                thought: "Collect context".to_string(),
+                action: "summarize_intent".to_string(),
+                observation: "Remaining backlog count: 1".to_string(),
+                question: None,
+                input: None,
+                sources: Vec::new(),
+            }],
+            final_answer: final_answer.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn update_sp_index_increments_counts_and_recent() {
+        let temp = tempdir().unwrap();
+        let intent = intent("Write summary", "unit-test");
+        let now = Utc::now();
+
+        update_sp_index(temp.path(), &intent, &outcome("Done"), now)
+            .await
+            .unwrap();
+        update_sp_index(temp.path(), &intent, &outcome("Done"), now)
+            .await
+            .unwrap();
+
+        let persisted = read_sp_index(temp.path()).await.unwrap();
+        assert_eq!(persisted.schema_version, SCHEMA_VERSION);
+        assert_eq!(persisted.entries.len(), 1);
+        let entry = &persisted.entries[0];
+        assert_eq!(entry.count, 2);
+        assert_eq!(entry.summary, "Write summary ⇒ Done");
+        assert_eq!(entry.id, sp_entry_id("Write summary ⇒ Done"));
+        assert_eq!(entry.category, "unit-test");
+        assert_eq!(entry.intents, vec![intent.id]);
+
+        let index = load_sp_index(temp.path(), now).await.unwrap();
+        assert_eq!(index.top_used, vec!["Write summary ⇒ Done (2)".to_string()]);
+        assert_eq!(index.most_recent, vec!["Write summary ⇒ Done".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn sp_entries_decay_filter_and_page() {
+        let temp = tempdir().unwrap();
+        let start = "2025-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let old = intent("Weekly report", "cli");
+        for _ in 0..3 {
+            update_sp_index(temp.path(), &old, &outcome("Sent"), start)
+                .await
+                .unwrap();
+        }
+        let mut fresh = intent("Triage issue", "github");
+        fresh
+            .metadata
+            .insert(CATEGORY_KEY.to_string(), "support".to_string());
+        let later = start + Duration::days(42);
+        update_sp_index(temp.path(), &fresh, &outcome("Labelled"), later)
+            .await
+            .unwrap();
+
+        // Three uses six weeks ago weigh 3 / 2^3 < one use today.
+        let page = load_sp_entries(
+            temp.path(),
+            &SpEntryQuery {
+                limit: 10,
+                ..Default::default()
+            },
+            later,
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.entries[0].category, "support");
+        assert!((page.entries[1].score - 0.375).abs() < 1e-9);
+        assert_eq!(
+            page.categories,
+            BTreeMap::from([("cli".to_string(), 1), ("support".to_string(), 1)])
+        );
+
+        let by_count = load_sp_entries(
+            temp.path(),
+            &SpEntryQuery {
+                sort: SpSort::Count,
+                limit: 1,
+                ..Default::default()
+            },
+            later,
+        )
+        .await
+        .unwrap();
+        assert_eq!(by_count.total, 2);
+        assert_eq!(by_count.entries.len(), 1);
+        assert_eq!(by_count.entries[0].summary, "Weekly report ⇒ Sent");
+
+        let filtered = load_sp_entries(
+            temp.path(),
+            &SpEntryQuery {
+                category: Some("CLI".to_string()),
+                intent: Some(old.id),
+                limit: 10,
+                ..Default::default()
+            },
+            later,
+        )
+        .await
+        .unwrap();
+        assert_eq!(filtered.total, 1);
+        assert_eq!(filtered.entries[0].count, 3);
+
+        let empty = load_sp_entries(
+            &temp.path().join("missing"),
+            &SpEntryQuery::default(),
+            later,
+        )
+        .await
+        .unwrap();
+        assert_eq!(empty.total, 0);
+    }
+
+    #[tokio::test]
+    async fn legacy_sp_index_migrates_to_entries() {
+        let temp = tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("sp")).unwrap();
+        std::fs::write(
+            temp.path().join(SP_INDEX_PATH),
+            r#"{
+                "schema_version": 1,
+                "top_used": [{"summary": "A ⇒ 1", "count": 4, "last_seen": "2025-01-02T00:00:00Z"}],
+                "most_recent": [
+                    {"summary": "B ⇒ 2", "count": 1, "last_seen": "2025-01-03T00:00:00Z"},
+                    {"summary": "A ⇒ 1", "count": 1, "last_seen": "2025-01-02T00:00:00Z"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let persisted = read_sp_index(temp.path()).await.unwrap();
+        let summaries: Vec<(&str, u32)> = persisted
+            .entries
+            .iter()
+            .map(|entry| (entry.summary.as_str(), entry.count))
+            .collect();
+        assert_eq!(summaries, vec![("A ⇒ 1", 4), ("B ⇒ 2", 1)]);
+        assert_eq!(persisted.entries[0].id, sp_entry_id("A ⇒ 1"));
+        assert_eq!(persisted.entries[0].score, 4.0);
+    }
+
+    #[tokio::test]
+    async fn similar_frequent_entries_become_precedents() {
+        let temp = tempdir().unwrap();
+        let now = Utc::now();
+        for _ in 0..2 {
+            update_sp_index(
+                temp.path(),
+                &intent("Draft the weekly status report", "cli"),
+                &outcome("Sent the report to the team"),
+                now,
+            )
+            .await
+            .unwrap();
+            update_sp_index(
+                temp.path(),
+                &intent("整理本周周报", "telegram"),
+                &outcome("已发送周报"),
+                now,
+            )
+            .await
+            .unwrap();
+        }
+        update_sp_index(
+            temp.path(),
+            &intent("Draft the weekly status email", "cli"),
+            &outcome("Emailed once"),
+            now,
+        )
+        .await
+        .unwrap();
+
+        let policy = SpRecallPolicy::default();
+        let precedents = find_sp_precedents(temp.path(), "draft weekly status report", &policy)
+            .await
+            .unwrap();
+        assert_eq!(precedents.len(), 1);
+        assert_eq!(precedents[0].intent, "Draft the weekly status report");
+        assert_eq!(precedents[0].final_answer, "Sent the report to the team");
+        assert_eq!(precedents[0].count, 2);
+
+        let precedents = find_sp_precedents(temp.path(), "整理本周的周报", &policy)
+            .await
+            .unwrap();
+        assert_eq!(precedents.len(), 1);
+        assert_eq!(precedents[0].final_answer, "已发送周报");
+
+        assert!(
+            find_sp_precedents(temp.path(), "Renew the TLS certificate", &policy)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let disabled = SpRecallPolicy {
+            enabled: false,
+            ..policy
+        };
+        assert!(
+            find_sp_precedents(temp.path(), "draft weekly status report", &disabled)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use super::{
+    JOURNAL_INDEX_MARKER, JOURNAL_LEGACY_MARKER, scan_history, scan_intent_dir, write_atomic,
+};
+use crate::{
+    llm::LlmLogEntry,
+    tasks::{DURATION_MS_KEY, LLM_LATENCY_MS_KEY},
+};
+
+const STATS_CACHE_PATH: &str = "stats/index.json";
+const STATS_SOURCE_DIRS: &[&str] = &[
+    "journals",
+    "logs/llm",
+    "intent/history",
+    "intent/queue/failed",
+    "intent/inbox/deferred",
+];
+const STATS_TOP_SOURCES: usize = 5;
+const ALIGNMENT_BUCKETS: usize = 5;
+const STATS_SLOWEST_INTENTS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub processed_per_day: Vec<DailyCount>,
+    pub outcomes: OutcomeCounts,
+    pub runs: RunStats,
+    #[serde(default)]
+    pub durations: DurationStats,
+    pub top_sources: Vec<SourceCount>,
+    pub alignment: Vec<AlignmentBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OutcomeCounts {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub deferred: usize,
+}
+
+/// Run metrics derived from LLM logs. Durations span the first to the last
+/// logged call of a run and tokens are estimated at four characters each,
+/// since providers' usage numbers are not recorded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunStats {
+    pub count: usize,
+    pub average_duration_ms: Option<u64>,
+    pub estimated_tokens: u64,
+}
+
+/// Processing time of archived intents, from the `duration_ms` and
+/// `llm_latency_ms` metadata written when they finish. Intents archived
+/// before durations were recorded are not counted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DurationStats {
+    pub count: usize,
+    pub total_ms: u64,
+    pub average_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub total_llm_latency_ms: u64,
+    pub average_llm_latency_ms: Option<u64>,
+    /// Longest-running intents first.
+    pub slowest: Vec<IntentDuration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IntentDuration {
+    pub id: Uuid,
+    pub summary: String,
+    pub source: String,
+    pub duration_ms: u64,
+    pub llm_latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceCount {
+    pub source: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlignmentBucket {
+    pub min: f32,
+    pub max: f32,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StatsCache {
+    fingerprint: Vec<DirFingerprint>,
+    snapshot: StatsSnapshot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct DirFingerprint {
+    dir: String,
+    files: usize,
+    bytes: u64,
+    latest_modified_ms: u128,
+}
+
+/// Return aggregate stats, reusing `data/stats/index.json` while none of the
+/// source directories changed since it was written.
+pub fn load_stats(data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<StatsSnapshot> {
+    let fingerprint = STATS_SOURCE_DIRS
+        .iter()
+        .map(|dir| fingerprint_dir(data_dir, dir))
+        .collect::<Vec<_>>();
+
+    let cache_path = data_dir.join(STATS_CACHE_PATH);
+    if let Ok(raw) = fs::read_to_string(&cache_path)
+        && let Ok(cache) = serde_json::from_str::<StatsCache>(&raw)
+        && cache.fingerprint == fingerprint
+    {
+        return Ok(cache.snapshot);
+    }
+
+    let snapshot = compute_stats(data_dir, now)?;
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating stats cache dir {:?}", parent))?;
+    }
+    let cache = StatsCache {
+        fingerprint,
+        snapshot,
+    };
+    write_atomic(&cache_path, serde_json::to_vec_pretty(&cache)?)
+        .with_context(|| format!("writing stats cache {:?}", cache_path))?;
+    Ok(cache.snapshot)
+}
+
+pub fn compute_stats(data_dir: &Path, now: DateTime<Utc>) -> anyhow::Result<StatsSnapshot> {
+    let processed_per_day = count_journal_entries(data_dir)?;
+    let history = scan_history(data_dir)?;
+    let failed = scan_intent_dir(&data_dir.join("intent/queue/failed"))?.len();
+    let deferred = scan_intent_dir(&data_dir.join("intent/inbox/deferred"))?.len();
+
+    let mut durations = Vec::new();
+    let mut sources: HashMap<String, usize> = HashMap::new();
+    let mut alignment = (0..ALIGNMENT_BUCKETS)
+        .map(|idx| AlignmentBucket {
+            min: idx as f32 / ALIGNMENT_BUCKETS as f32,
+            max: (idx + 1) as f32 / ALIGNMENT_BUCKETS as f32,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for record in &history {
+        *sources.entry(record.intent.source.clone()).or_default() += 1;
+        let value = record.intent.telos_alignment.clamp(0.0, 1.0);
+        let idx = ((value * ALIGNMENT_BUCKETS as f32) as usize).min(ALIGNMENT_BUCKETS - 1);
+        alignment[idx].count += 1;
+        let metadata = &record.intent.metadata;
+        if let Some(duration_ms) = metadata
+            .get(DURATION_MS_KEY)
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            durations.push(IntentDuration {
+                id: record.intent.id,
+                summary: record.intent.summary.clone(),
+                source: record.intent.source.clone(),
+                duration_ms,
+                llm_latency_ms: metadata
+                    .get(LLM_LATENCY_MS_KEY)
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or_default(),
+            });
+        }
+    }
+
+    let mut top_sources: Vec<SourceCount> = sources
+        .into_iter()
+        .map(|(source, count)| SourceCount { source, count })
+        .collect();
+    top_sources.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.source.cmp(&b.source)));
+    top_sources.truncate(STATS_TOP_SOURCES);
+
+    Ok(StatsSnapshot {
+        generated_at: now,
+        outcomes: OutcomeCounts {
+            succeeded: processed_per_day.iter().map(|day| day.count).sum(),
+            failed,
+            deferred,
+        },
+        processed_per_day,
+        runs: run_stats(data_dir)?,
+        durations: duration_stats(durations),
+        top_sources,
+        alignment,
+    })
+}
+
+/// Count processed intents per day from `journals/YYYY/MM/DD/<id>.md`, plus
+/// the sections of daily files written before journals were split.
+fn count_journal_entries(data_dir: &Path) -> anyhow::Result<Vec<DailyCount>> {
+    let root = data_dir.join("journals");
+    let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    for entry in WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(&root) else {
+            continue;
+        };
+        let parts: Vec<String> = relative
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        match parts.as_slice() {
+            [year, month, day, _intent] => {
+                if let Some(date) = parse_journal_date(year, month, day) {
+                    *per_day.entry(date).or_default() += 1;
+                }
+            }
+            [year, month, day] => {
+                let Some(date) = parse_journal_date(year, month, day) else {
+                    continue;
+                };
+                let content = fs::read_to_string(entry.path())
+                    .with_context(|| format!("reading journal {:?}", entry.path()))?;
+                let legacy = if content.starts_with(JOURNAL_INDEX_MARKER) {
+                    content
+                        .split_once(JOURNAL_LEGACY_MARKER)
+                        .map(|(_, legacy)| legacy.to_string())
+                        .unwrap_or_default()
+                } else {
+                    content
+                };
+                let sections = legacy
+                    .lines()
+                    .filter(|line| line.starts_with("## ") && line.contains(" — "))
+                    .count();
+                if sections > 0 {
+                    *per_day.entry(date).or_default() += sections;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(per_day
+        .into_iter()
+        .map(|(date, count)| DailyCount { date, count })
+        .collect())
+}
+
+fn duration_stats(mut durations: Vec<IntentDuration>) -> DurationStats {
+    let count = durations.len();
+    if count == 0 {
+        return DurationStats::default();
+    }
+    durations.sort_by(|a, b| {
+        b.duration_ms
+            .cmp(&a.duration_ms)
+            .then_with(|| a.summary.cmp(&b.summary))
+    });
+    let total_ms = durations.iter().map(|entry| entry.duration_ms).sum::<u64>();
+    let total_llm_latency_ms = durations
+        .iter()
+        .map(|entry| entry.llm_latency_ms)
+        .sum::<u64>();
+    // Nearest rank over the descending list.
+    let p95_rank = (count * 95).div_ceil(100);
+    let p95_ms = durations[count - p95_rank].duration_ms;
+
+    DurationStats {
+        count,
+        total_ms,
+        average_ms: Some(total_ms / count as u64),
+        p95_ms: Some(p95_ms),
+        max_ms: Some(durations[0].duration_ms),
+        total_llm_latency_ms,
+        average_llm_latency_ms: Some(total_llm_latency_ms / count as u64),
+        slowest: durations.into_iter().take(STATS_SLOWEST_INTENTS).collect(),
+    }
+}
+
+fn parse_journal_date(year: &str, month: &str, day: &str) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+}
+
+fn run_stats(data_dir: &Path) -> anyhow::Result<RunStats> {
+    let root = data_dir.join("logs/llm");
+    if !root.exists() {
+        return Ok(RunStats::default());
+    }
+
+    let mut spans: HashMap<Uuid, (DateTime<Utc>, DateTime<Utc>)> = HashMap::new();
+    let mut characters: u64 = 0;
+    for entry in WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let is_log = entry.path().extension().and_then(|ext| ext.to_str()) == Some("jsonl");
+        if !entry.file_type().is_file() || !is_log {
+            continue;
+        }
+        let content = fs::read_to_string(entry.path())
+            .with_context(|| format!("reading llm log {:?}", entry.path()))?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let log: LlmLogEntry = super::parse_record(super::SchemaKind::LlmLog, line)
+                .with_context(|| format!("parsing llm log in {:?}", entry.path()))?;
+            characters += (log.prompt.chars().count() + log.response.chars().count()) as u64;
+            let span = spans
+                .entry(log.run_id)
+                .or_insert((log.timestamp, log.timestamp));
+            span.0 = span.0.min(log.timestamp);
+            span.1 = span.1.max(log.timestamp);
+        }
+    }
+
+    let count = spans.len();
+    let average_duration_ms = if count == 0 {
+        None
+    } else {
+        let total: i64 = spans
+            .values()
+            .map(|(start, end)| (*end - *start).num_milliseconds())
+            .sum();
+        Some((total / count as i64).max(0) as u64)
+    };
+
+    Ok(RunStats {
+        count,
+        average_duration_ms,
+        estimated_tokens: characters.div_ceil(4),
+    })
+}
+
+fn fingerprint_dir(data_dir: &Path, dir: &str) -> DirFingerprint {
+    let mut fingerprint = DirFingerprint {
+        dir: dir.to_string(),
+        files: 0,
+        bytes: 0,
+        latest_modified_ms: 0,
+    };
+    for entry in WalkDir::new(data_dir.join(dir))
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if let Some(modified) = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        {
+            fingerprint.latest_modified_ms =
+                fingerprint.latest_modified_ms.max(modified.as_millis());
+        }
+        if metadata.is_file() {
+            fingerprint.files += 1;
+            fingerprint.bytes += metadata.len();
+        }
+    }
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    use crate::storage::ensure_data_layout;
+
+    #[test]
+    fn stats_aggregate_journals_logs_and_history() {
+        let temp = TempDir::new().expect("tempdir");
+        let data_dir = temp.path();
+        ensure_data_layout(data_dir).expect("layout");
+
+        let day_dir = data_dir.join("journals/2025/01/02");
+        fs::create_dir_all(&day_dir).unwrap();
+        fs::write(day_dir.join("a.md"), "## 09:00:00 — A").unwrap();
+        fs::write(day_dir.join("b.md"), "## 10:00:00 — B").unwrap();
+        fs::write(
+            data_dir.join("journals/2025/01/01.md"),
+            "## 08:00:00 — Legacy\n\nFinal answer: ok\n",
+        )
+        .unwrap();
+
+        fs::write(
+            data_dir.join("intent/history/one.md"),
+            "---\nsource: telegram\nsummary: Slow one\ntelos_alignment: 0.9\nmetadata:\n  duration_ms: '3000'\n  llm_latency_ms: '2500'\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            data_dir.join("intent/history/three.md"),
+            "---\nsource: telegram\nsummary: Quick one\nmetadata:\n  duration_ms: '1000'\n  llm_latency_ms: '500'\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            data_dir.join("intent/queue/failed/two.md"),
+            "---\nsource: cli\n---\n",
+        )
+        .unwrap();
+
+        let run_id = Uuid::new_v4();
+        let log_dir = data_dir.join("logs/llm/2025/01");
+        fs::create_dir_all(&log_dir).unwrap();
+        let start = "2025-01-02T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let identity = crate::llm::LlmIdentity::new("local_stub", None);
+        let lines = [
+            LlmLogEntry::new(run_id, start, "THINK", "abcd", "efgh", &identity),
+            LlmLogEntry::new(
+                run_id,
+                start + chrono::Duration::seconds(2),
+                "FINAL",
+                "ijkl",
+                "mnop",
+                &identity,
+            ),
+        ]
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+        fs::write(log_dir.join("02.jsonl"), lines).unwrap();
+
+        let now = "2025-01-03T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let stats = load_stats(data_dir, now).expect("stats");
+        assert_eq!(stats.generated_at, now);
+        assert_eq!(
+            stats.processed_per_day,
+            vec![
+                DailyCount {
+                    date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                    count: 1,
+                },
+                DailyCount {
+                    date: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                    count: 2,
+                },
+            ]
+        );
+        assert_eq!(stats.outcomes.succeeded, 3);
+        assert_eq!(stats.outcomes.failed, 1);
+        assert_eq!(stats.runs.count, 1);
+        assert_eq!(stats.runs.average_duration_ms, Some(2000));
+        assert_eq!(stats.runs.estimated_tokens, 4);
+        assert_eq!(stats.durations.count, 2);
+        assert_eq!(stats.durations.average_ms, Some(2000));
+        assert_eq!(stats.durations.p95_ms, Some(3000));
+        assert_eq!(stats.durations.average_llm_latency_ms, Some(1500));
+        assert_eq!(stats.durations.slowest[0].summary, "Slow one");
+        assert_eq!(stats.durations.slowest[1].duration_ms, 1000);
+        assert_eq!(stats.top_sources[0].source, "telegram");
+        assert_eq!(stats.alignment[4].count, 1);
+
+        let cached = load_stats(data_dir, Utc::now()).expect("cached stats");
+        assert_eq!(cached.generated_at, stats.generated_at);
+    }
+}
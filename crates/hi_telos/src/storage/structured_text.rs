@@ -1,11 +1,24 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 
+use super::{SCHEMA_VERSION, SchemaKind, parse_record, write_atomic_async};
+use crate::text;
+
 const STRUCTURED_TEXT_HISTORY_LIMIT: usize = 20;
+/// Named previews live in `mock/text_structures/<name>/`; the unnamed one
+/// keeps its original place directly under `mock/`.
+const NAMED_PREVIEWS_DIR: &str = "mock/text_structures";
+const PREVIEW_FILE: &str = "text_structure.json";
+const HISTORY_DIR: &str = "text_structure_history";
+/// The unnamed preview is also reachable under this name.
+pub const DEFAULT_PREVIEW_NAME: &str = "default";
+const MAX_PREVIEW_NAME_CHARS: usize = 64;
+/// Path segments the preview routes already use.
+const RESERVED_PREVIEW_NAMES: &[&str] = &["history", "from_markdown", "stream"];
 const HISTORY_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%6fZ";
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -42,6 +55,8 @@ pub struct StructuredContent {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 struct StructuredTextSnapshot {
+    #[serde(default)]
+    pub schema_version: u32,
     pub content: StructuredContent,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
@@ -89,16 +104,368 @@ impl StructuredContent {
     }
 }
 
+/// Deepest allowed section nesting; top-level sections are depth 1.
+pub const STRUCTURED_TEXT_MAX_DEPTH: usize = 6;
+/// Sections allowed across all levels.
+pub const STRUCTURED_TEXT_MAX_SECTIONS: usize = 200;
+const MAX_TITLE_CHARS: usize = 200;
+const MAX_SUMMARY_CHARS: usize = 2_000;
+const MAX_HEADING_CHARS: usize = 200;
+const MAX_BODY_LINE_CHARS: usize = 10_000;
+const MAX_NOTE_CHARS: usize = 500;
+
+/// One rule a submitted [`StructuredContent`] breaks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructuredTextViolation {
+    /// JSON path of the offending field, e.g. `sections[0].children[1].heading`.
+    pub field: String,
+    pub message: String,
+}
+
+impl StructuredContent {
+    /// Every rule the content (and its history `note`) breaks; empty when
+    /// it can be saved.
+    pub fn validate(&self, note: Option<&str>) -> Vec<StructuredTextViolation> {
+        let mut violations = Vec::new();
+        check_text(
+            &mut violations,
+            "title".to_string(),
+            &self.title,
+            MAX_TITLE_CHARS,
+            true,
+        );
+        check_text(
+            &mut violations,
+            "summary".to_string(),
+            &self.summary,
+            MAX_SUMMARY_CHARS,
+            false,
+        );
+        if let Some(note) = note {
+            check_text(
+                &mut violations,
+                "note".to_string(),
+                note,
+                MAX_NOTE_CHARS,
+                false,
+            );
+        }
+
+        let mut sections = 0;
+        let mut stack: Vec<(String, &StructuredSection, usize)> = self
+            .sections
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, section)| (format!("sections[{index}]"), section, 1))
+            .collect();
+        let mut too_deep = false;
+        while let Some((path, section, depth)) = stack.pop() {
+            sections += 1;
+            if depth > STRUCTURED_TEXT_MAX_DEPTH {
+                if !too_deep {
+                    violations.push(StructuredTextViolation {
+                        field: path,
+                        message: format!(
+                            "sections may nest at most {STRUCTURED_TEXT_MAX_DEPTH} levels deep"
+                        ),
+                    });
+                }
+                too_deep = true;
+                continue;
+            }
+            check_text(
+                &mut violations,
+                format!("{path}.heading"),
+                &section.heading,
+                MAX_HEADING_CHARS,
+                true,
+            );
+            for (line, text) in section.body.iter().enumerate() {
+                check_text(
+                    &mut violations,
+                    format!("{path}.body[{line}]"),
+                    text,
+                    MAX_BODY_LINE_CHARS,
+                    false,
+                );
+            }
+            stack.extend(
+                section
+                    .children
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, child)| (format!("{path}.children[{index}]"), child, depth + 1)),
+            );
+        }
+        if sections > STRUCTURED_TEXT_MAX_SECTIONS {
+            violations.push(StructuredTextViolation {
+                field: "sections".to_string(),
+                message: format!(
+                    "at most {STRUCTURED_TEXT_MAX_SECTIONS} sections are allowed, got {sections}"
+                ),
+            });
+        }
+        violations
+    }
+}
+
+fn check_text(
+    violations: &mut Vec<StructuredTextViolation>,
+    field: String,
+    value: &str,
+    max: usize,
+    required: bool,
+) {
+    if required && value.trim().is_empty() {
+        violations.push(StructuredTextViolation {
+            field,
+            message: "must not be empty".to_string(),
+        });
+    } else if value.chars().count() > max {
+        violations.push(StructuredTextViolation {
+            field,
+            message: format!("must be at most {max} characters"),
+        });
+    }
+}
+
+/// Build a preview from markdown headings: the first `#` heading is the
+/// title (`fallback_title` without one), text before the first section is
+/// the summary, and each deeper heading nests a section under the nearest
+/// shallower one. Paragraphs, list items and fenced code blocks become one
+/// body line each; YAML front matter is skipped. Oversized titles and headings are cut to the limits
+/// [`StructuredContent::validate`] enforces.
+pub fn structured_content_from_markdown(markdown: &str, fallback_title: &str) -> StructuredContent {
+    let mut title = None;
+    let mut summary = Vec::new();
+    let mut sections = Vec::new();
+    // Open sections, outermost first, with their heading level.
+    let mut open = OpenSections::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut fence: Option<(&str, Vec<&str>)> = None;
+
+    // YAML front matter (intents, journals) is metadata, not content.
+    let mut lines = markdown.lines().peekable();
+    if lines.peek().is_some_and(|line| line.trim() == "---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+        }
+    }
+
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some((marker, mut block)) = fence.take() {
+            if trimmed.starts_with(marker) {
+                push_text(&mut open, &mut summary, block.join("\n"));
+            } else {
+                block.push(line);
+                fence = Some((marker, block));
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker))
+        {
+            flush(&mut paragraph, &mut open, &mut summary);
+            fence = Some((marker, Vec::new()));
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|ch| *ch == '#').count();
+        let heading = trimmed[level..].trim();
+        let is_heading = (1..=6).contains(&level)
+            && (trimmed.len() == level || trimmed[level..].starts_with(' '));
+        if is_heading {
+            flush(&mut paragraph, &mut open, &mut summary);
+            if heading.is_empty() {
+                continue;
+            }
+            if level == 1 && title.is_none() && sections.is_empty() && open.is_empty() {
+                title = Some(heading.to_string());
+                continue;
+            }
+            while open
+                .last()
+                .is_some_and(|(open_level, _)| *open_level >= level)
+            {
+                close(&mut open, &mut sections);
+            }
+            if open.len() >= STRUCTURED_TEXT_MAX_DEPTH {
+                // Too deep to nest: keep it as text of the deepest section.
+                paragraph.push(trimmed);
+                flush(&mut paragraph, &mut open, &mut summary);
+                continue;
+            }
+            open.push((
+                level,
+                StructuredSection {
+                    heading: text::prefix(heading, MAX_HEADING_CHARS).to_string(),
+                    body: Vec::new(),
+                    children: Vec::new(),
+                },
+            ));
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut open, &mut summary);
+        } else if is_list_item(trimmed) {
+            flush(&mut paragraph, &mut open, &mut summary);
+            paragraph.push(trimmed);
+            flush(&mut paragraph, &mut open, &mut summary);
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    // An unclosed fence runs to the end of the document.
+    if let Some((_, block)) = fence {
+        push_text(&mut open, &mut summary, block.join("\n"));
+    }
+    flush(&mut paragraph, &mut open, &mut summary);
+    while !open.is_empty() {
+        close(&mut open, &mut sections);
+    }
+
+    let title = title
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| fallback_title.to_string());
+    StructuredContent {
+        title: text::prefix(&title, MAX_TITLE_CHARS).to_string(),
+        summary: text::prefix(&summary.join(" "), MAX_SUMMARY_CHARS).to_string(),
+        sections,
+    }
+}
+
+type OpenSections = Vec<(usize, StructuredSection)>;
+
+fn close(open: &mut OpenSections, sections: &mut Vec<StructuredSection>) {
+    if let Some((_, section)) = open.pop() {
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(section),
+            None => sections.push(section),
+        }
+    }
+}
+
+/// Body text of the innermost open section, or the summary before any.
+fn push_text(open: &mut OpenSections, summary: &mut Vec<String>, text: String) {
+    match open.last_mut() {
+        Some((_, section)) => section.body.push(text),
+        None => summary.push(text),
+    }
+}
+
+fn flush(paragraph: &mut Vec<&str>, open: &mut OpenSections, summary: &mut Vec<String>) {
+    if !paragraph.is_empty() {
+        push_text(open, summary, paragraph.join(" "));
+        paragraph.clear();
+    }
+}
+
+fn is_list_item(line: &str) -> bool {
+    if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| line.starts_with(marker))
+    {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && line[digits..].starts_with(". ")
+}
+
+/// Check a preview name: 1-64 lowercase ASCII letters, digits, `-` or `_`.
+/// Returns `None` for the default preview.
+pub fn normalize_preview_name(name: &str) -> Result<Option<&str>> {
+    if name == DEFAULT_PREVIEW_NAME {
+        return Ok(None);
+    }
+    let valid_chars = name
+        .chars()
+        .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_');
+    if name.is_empty() || name.len() > MAX_PREVIEW_NAME_CHARS || !valid_chars {
+        anyhow::bail!(
+            "preview name must be 1-{MAX_PREVIEW_NAME_CHARS} lowercase letters, digits, '-' or '_'"
+        );
+    }
+    if RESERVED_PREVIEW_NAMES.contains(&name) {
+        anyhow::bail!("preview name {name:?} is reserved");
+    }
+    Ok(Some(name))
+}
+
+/// Directory holding the preview file and its history.
+fn preview_dir(data_dir: &Path, name: Option<&str>) -> Result<PathBuf> {
+    match name.map(normalize_preview_name).transpose()?.flatten() {
+        Some(name) => Ok(data_dir.join(NAMED_PREVIEWS_DIR).join(name)),
+        None => Ok(data_dir.join("mock")),
+    }
+}
+
+/// A saved preview as listed by [`list_structured_text_previews`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructuredTextPreviewSummary {
+    pub name: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Every preview saved on disk, the default one first and the named ones
+/// by name. Names whose preview was deleted but still have history are
+/// left out.
+pub async fn list_structured_text_previews(
+    data_dir: &Path,
+) -> Result<Vec<StructuredTextPreviewSummary>> {
+    let mut names = Vec::new();
+    let named_dir = data_dir.join(NAMED_PREVIEWS_DIR);
+    if named_dir.exists() {
+        let mut entries = fs::read_dir(&named_dir)
+            .await
+            .with_context(|| format!("reading named previews at {:?}", named_dir))?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str()
+                && matches!(normalize_preview_name(name), Ok(Some(_)))
+            {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+
+    let mut previews = Vec::new();
+    for name in std::iter::once(None).chain(names.iter().map(|name| Some(name.as_str()))) {
+        if let Some(preview) = load_structured_text_preview(data_dir, name).await? {
+            previews.push(StructuredTextPreviewSummary {
+                name: name.unwrap_or(DEFAULT_PREVIEW_NAME).to_string(),
+                title: preview.content.title,
+                note: preview.note,
+                updated_at: preview.updated_at,
+            });
+        }
+    }
+    Ok(previews)
+}
+
 /// Attempt to load a structured text preview from disk.
 ///
-/// The preview is stored in `<data_dir>/mock/text_structure.json`. Missing files
+/// The default preview is stored in `<data_dir>/mock/text_structure.json`, a
+/// named one in `<data_dir>/mock/text_structures/<name>/`. Missing files
 /// are treated as a soft failure and return `Ok(None)` so the caller can fall
 /// back to the inline payload while still logging the issue. Any other IO or
 /// parsing errors are surfaced to the caller for observability.
 pub async fn load_structured_text_preview(
     data_dir: &Path,
+    name: Option<&str>,
 ) -> Result<Option<LoadedStructuredTextPreview>> {
-    let path = data_dir.join("mock/text_structure.json");
+    let path = preview_dir(data_dir, name)?.join(PREVIEW_FILE);
     match fs::read_to_string(&path).await {
         Ok(raw) => {
             let metadata = fs::metadata(&path).await.ok();
@@ -119,35 +486,43 @@ pub async fn load_structured_text_preview(
 }
 
 /// Persist a structured text preview to disk so subsequent calls to the mock
-/// endpoint return the freshly authored content.
+/// endpoint return the freshly authored content. It also goes into the
+/// history unless `keep_history` is off (the disk guard paused it).
 pub async fn save_structured_text_preview(
     data_dir: &Path,
+    name: Option<&str>,
     payload: &StructuredContent,
     note: Option<&str>,
+    keep_history: bool,
+    now: DateTime<Utc>,
 ) -> Result<()> {
-    let mock_dir = data_dir.join("mock");
+    let mock_dir = preview_dir(data_dir, name)?;
     fs::create_dir_all(&mock_dir)
         .await
         .with_context(|| format!("creating mock directory at {:?}", mock_dir))?;
 
     let snapshot = StructuredTextSnapshot {
+        schema_version: SCHEMA_VERSION,
         content: payload.clone(),
         note: note.map(str::to_string),
     };
     let serialized =
         serde_json::to_vec_pretty(&snapshot).context("serializing structured text preview")?;
-    let path = mock_dir.join("text_structure.json");
-    fs::write(&path, serialized)
+    let path = mock_dir.join(PREVIEW_FILE);
+    write_atomic_async(&path, serialized)
         .await
         .with_context(|| format!("writing structured text preview at {:?}", path))?;
 
-    append_structured_text_history(&mock_dir, payload, note).await?;
+    if keep_history {
+        append_structured_text_history(&mock_dir, payload, note, now).await?;
+    }
 
     Ok(())
 }
 
-pub async fn delete_structured_text_preview(data_dir: &Path) -> Result<()> {
-    let path = data_dir.join("mock/text_structure.json");
+/// Remove the preview file; its history stays so it can be restored.
+pub async fn delete_structured_text_preview(data_dir: &Path, name: Option<&str>) -> Result<()> {
+    let path = preview_dir(data_dir, name)?.join(PREVIEW_FILE);
     match fs::remove_file(&path).await {
         Ok(()) => Ok(()),
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
@@ -164,100 +539,166 @@ pub struct StructuredTextHistoryEntry {
     pub note: Option<String>,
 }
 
+/// One page of [`list_structured_text_history_page`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StructuredTextHistoryPage {
+    pub entries: Vec<StructuredTextHistoryEntry>,
+    /// Pass as `cursor` to continue after the last entry; `None` on the
+    /// last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
 pub async fn list_structured_text_history(
     data_dir: &Path,
+    name: Option<&str>,
     limit: usize,
     filters: Option<&StructuredTextHistoryFilters>,
 ) -> Result<Vec<StructuredTextHistoryEntry>> {
-    let history_dir = data_dir.join("mock/text_structure_history");
+    Ok(
+        list_structured_text_history_page(data_dir, name, limit, filters, None, 0)
+            .await?
+            .entries,
+    )
+}
+
+/// Snapshots newest first, starting after the `cursor` id and skipping
+/// `offset` further matches. Only file names are listed up front; a
+/// snapshot is read once it passes the time filters, and only when the
+/// page or the text query needs its content.
+pub async fn list_structured_text_history_page(
+    data_dir: &Path,
+    name: Option<&str>,
+    limit: usize,
+    filters: Option<&StructuredTextHistoryFilters>,
+    cursor: Option<&str>,
+    offset: usize,
+) -> Result<StructuredTextHistoryPage> {
+    let history_dir = preview_dir(data_dir, name)?.join(HISTORY_DIR);
+    let before = cursor.map(parse_history_id).transpose()?;
     if !history_dir.exists() {
-        return Ok(Vec::new());
+        return Ok(StructuredTextHistoryPage::default());
     }
 
-    let mut dir = fs::read_dir(&history_dir)
+    let limit = if limit == 0 {
+        STRUCTURED_TEXT_HISTORY_LIMIT
+    } else {
+        limit
+    };
+    let since = filters.and_then(|filters| filters.since);
+    let needle = filters
+        .and_then(|filters| filters.note_query.as_deref())
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(str::to_lowercase);
+
+    let candidates = history_index(&history_dir)
+        .await?
+        .into_iter()
+        .filter(|(saved_at, _)| since.is_none_or(|since| *saved_at >= since))
+        .filter(|(saved_at, _)| before.is_none_or(|before| *saved_at < before));
+
+    let mut page = StructuredTextHistoryPage::default();
+    let mut skipped = 0;
+    for (saved_at, path) in candidates {
+        let entry = match needle.as_deref() {
+            Some(needle) => match read_history_entry(&path, saved_at).await? {
+                Some(entry) if entry_contains_query(&entry, needle) => Some(entry),
+                _ => continue,
+            },
+            None => None,
+        };
+        if skipped < offset {
+            skipped += 1;
+            continue;
+        }
+        if page.entries.len() == limit {
+            page.next_cursor = page.entries.last().map(|entry| entry.id.clone());
+            break;
+        }
+        let entry = match entry {
+            Some(entry) => Some(entry),
+            None => read_history_entry(&path, saved_at).await?,
+        };
+        page.entries.extend(entry);
+    }
+
+    Ok(page)
+}
+
+/// History files with the time in their name, newest first.
+async fn history_index(history_dir: &Path) -> Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    let mut entries = fs::read_dir(history_dir)
         .await
         .with_context(|| format!("reading structured text history at {:?}", history_dir))?;
 
-    let mut entries = Vec::new();
-    while let Some(entry) = dir.next_entry().await? {
+    let mut indexed = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if !entry.file_type().await?.is_file() {
             continue;
         }
-
         let Some(stem) = path.file_stem().and_then(|value| value.to_str()) else {
             continue;
         };
-
-        let saved_at = match parse_history_id(stem) {
-            Ok(ts) => ts,
-            Err(_) => continue,
-        };
-
-        let raw = fs::read_to_string(&path)
-            .await
-            .with_context(|| format!("reading structured text history file {:?}", path))?;
-        let snapshot = parse_snapshot(&raw)
-            .with_context(|| format!("parsing structured text history file {:?}", path))?;
-
-        entries.push(StructuredTextHistoryEntry {
-            id: stem.to_string(),
-            saved_at,
-            content: snapshot.content,
-            note: snapshot.note,
-        });
+        if let Ok(ts) = parse_history_id(stem) {
+            indexed.push((ts, path));
+        }
     }
 
-    if let Some(filters) = filters {
-        if let Some(since) = filters.since.as_ref() {
-            entries.retain(|entry| entry.saved_at >= *since);
-        }
+    indexed.sort_by_key(|(ts, _)| std::cmp::Reverse(*ts));
+    Ok(indexed)
+}
 
-        if let Some(needle) = filters.note_query.as_ref().and_then(|query| {
-            let trimmed = query.trim();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed.to_lowercase())
-            }
-        }) {
-            entries.retain(|entry| entry_contains_query(entry, &needle));
+/// `None` when the file was pruned after it was listed.
+async fn read_history_entry(
+    path: &Path,
+    saved_at: DateTime<Utc>,
+) -> Result<Option<StructuredTextHistoryEntry>> {
+    let raw = match fs::read_to_string(path).await {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("reading structured text history file {:?}", path));
         }
-    }
-
-    entries.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
-    let limit = if limit == 0 {
-        STRUCTURED_TEXT_HISTORY_LIMIT
-    } else {
-        limit
     };
-    if entries.len() > limit {
-        entries.truncate(limit);
-    }
-
-    Ok(entries)
+    let snapshot = parse_snapshot(&raw)
+        .with_context(|| format!("parsing structured text history file {:?}", path))?;
+    let id = path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or_default()
+        .to_string();
+    Ok(Some(StructuredTextHistoryEntry {
+        id,
+        saved_at,
+        content: snapshot.content,
+        note: snapshot.note,
+    }))
 }
 
 async fn append_structured_text_history(
     mock_dir: &Path,
     payload: &StructuredContent,
     note: Option<&str>,
+    now: DateTime<Utc>,
 ) -> Result<()> {
-    let history_dir = mock_dir.join("text_structure_history");
+    let history_dir = mock_dir.join(HISTORY_DIR);
     fs::create_dir_all(&history_dir)
         .await
         .with_context(|| format!("creating structured text history dir at {:?}", history_dir))?;
 
-    let now = Utc::now();
     let timestamp = now.format(HISTORY_TIMESTAMP_FORMAT).to_string();
     let history_path = history_dir.join(format!("{}.json", timestamp));
     let snapshot = StructuredTextSnapshot {
+        schema_version: SCHEMA_VERSION,
         content: payload.clone(),
         note: note.map(str::to_string),
     };
     let serialized = serde_json::to_vec_pretty(&snapshot)
         .context("serializing structured text history entry")?;
-    fs::write(&history_path, serialized)
+    write_atomic_async(&history_path, serialized)
         .await
         .with_context(|| {
             format!(
@@ -272,30 +713,7 @@ async fn append_structured_text_history(
 }
 
 async fn prune_structured_text_history(history_dir: &Path, limit: usize) -> Result<()> {
-    let mut entries = fs::read_dir(history_dir)
-        .await
-        .with_context(|| format!("reading structured text history at {:?}", history_dir))?;
-
-    let mut indexed: Vec<(DateTime<Utc>, std::path::PathBuf)> = Vec::new();
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if !entry.file_type().await?.is_file() {
-            continue;
-        }
-        let Some(stem) = path.file_stem().and_then(|value| value.to_str()) else {
-            continue;
-        };
-        if let Ok(ts) = parse_history_id(stem) {
-            indexed.push((ts, path));
-        }
-    }
-
-    indexed.sort_by(|a, b| b.0.cmp(&a.0));
-    if indexed.len() <= limit {
-        return Ok(());
-    }
-
-    for (_, path) in indexed.into_iter().skip(limit) {
+    for (_, path) in history_index(history_dir).await?.into_iter().skip(limit) {
         if let Err(err) = fs::remove_file(&path).await
             && err.kind() != std::io::ErrorKind::NotFound
         {
@@ -317,9 +735,10 @@ fn parse_history_id(id: &str) -> Result<DateTime<Utc>> {
 
 pub async fn load_structured_text_history_entry(
     data_dir: &Path,
+    name: Option<&str>,
     id: &str,
 ) -> Result<Option<StructuredTextHistoryEntry>> {
-    let history_dir = data_dir.join("mock/text_structure_history");
+    let history_dir = preview_dir(data_dir, name)?.join(HISTORY_DIR);
     if !history_dir.exists() {
         return Ok(None);
     }
@@ -345,29 +764,32 @@ pub async fn load_structured_text_history_entry(
 
 pub async fn restore_structured_text_preview_from_history(
     data_dir: &Path,
+    name: Option<&str>,
     id: &str,
+    keep_history: bool,
+    now: DateTime<Utc>,
 ) -> Result<bool> {
-    match load_structured_text_history_entry(data_dir, id).await? {
+    match load_structured_text_history_entry(data_dir, name, id).await? {
         Some(entry) => {
-            save_structured_text_preview(data_dir, &entry.content, entry.note.as_deref()).await?;
+            save_structured_text_preview(
+                data_dir,
+                name,
+                &entry.content,
+                entry.note.as_deref(),
+                keep_history,
+                now,
+            )
+            .await?;
             Ok(true)
         }
         None => Ok(false),
     }
 }
 
+/// Snapshots saved before versioning may be the bare content; the schema
+/// migration wraps them.
 fn parse_snapshot(raw: &str) -> Result<StructuredTextSnapshot> {
-    match serde_json::from_str::<StructuredTextSnapshot>(raw) {
-        Ok(snapshot) => Ok(snapshot),
-        Err(_) => {
-            let content: StructuredContent =
-                serde_json::from_str(raw).context("parsing legacy structured text snapshot")?;
-            Ok(StructuredTextSnapshot {
-                content,
-                note: None,
-            })
-        }
-    }
+    parse_record(SchemaKind::StructuredSnapshot, raw).context("parsing structured text snapshot")
 }
 
 fn entry_contains_query(entry: &StructuredTextHistoryEntry, needle: &str) -> bool {
@@ -417,7 +839,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let data_dir = tmp.path();
 
-        let payload = load_structured_text_preview(data_dir).await.unwrap();
+        let payload = load_structured_text_preview(data_dir, None).await.unwrap();
         assert!(payload.is_none());
     }
 
@@ -445,7 +867,7 @@ mod tests {
         .await
         .unwrap();
 
-        let payload = load_structured_text_preview(data_dir).await.unwrap();
+        let payload = load_structured_text_preview(data_dir, None).await.unwrap();
         let preview = payload.expect("preview");
         assert_eq!(preview.content, expected);
         assert!(preview.updated_at.is_some());
@@ -466,11 +888,11 @@ mod tests {
             }],
         };
 
-        save_structured_text_preview(data_dir, &content, None)
+        save_structured_text_preview(data_dir, None, &content, None, true, Utc::now())
             .await
             .expect("save structured text");
 
-        let persisted = load_structured_text_preview(data_dir)
+        let persisted = load_structured_text_preview(data_dir, None)
             .await
             .expect("load structured text")
             .expect("some");
@@ -490,9 +912,16 @@ mod tests {
             sections: vec![],
         };
 
-        save_structured_text_preview(data_dir, &content, Some("first draft"))
-            .await
-            .expect("save structured text");
+        save_structured_text_preview(
+            data_dir,
+            None,
+            &content,
+            Some("first draft"),
+            true,
+            Utc::now(),
+        )
+        .await
+        .expect("save structured text");
 
         let history_dir = data_dir.join("mock/text_structure_history");
         assert!(history_dir.exists());
@@ -505,7 +934,7 @@ mod tests {
         }
         assert_eq!(count, 1);
 
-        let history_entries = list_structured_text_history(data_dir, 10, None)
+        let history_entries = list_structured_text_history(data_dir, None, 10, None)
             .await
             .expect("list history");
         assert_eq!(history_entries.len(), 1);
@@ -527,15 +956,15 @@ mod tests {
             }],
         };
 
-        save_structured_text_preview(data_dir, &content, None)
+        save_structured_text_preview(data_dir, None, &content, None, true, Utc::now())
             .await
             .expect("save structured text");
 
-        delete_structured_text_preview(data_dir)
+        delete_structured_text_preview(data_dir, None)
             .await
             .expect("delete structured text");
 
-        let payload = load_structured_text_preview(data_dir)
+        let payload = load_structured_text_preview(data_dir, None)
             .await
             .expect("load structured text");
         assert!(payload.is_none());
@@ -575,7 +1004,7 @@ mod tests {
         .await
         .unwrap();
 
-        let entries = list_structured_text_history(data_dir, 10, None)
+        let entries = list_structured_text_history(data_dir, None, 10, None)
             .await
             .expect("list history");
         assert_eq!(entries.len(), 2);
@@ -599,6 +1028,7 @@ mod tests {
             (
                 "20240101T000000000000Z",
                 StructuredTextSnapshot {
+                    schema_version: SCHEMA_VERSION,
                     content: StructuredContent {
                         title: "Alpha Title".to_string(),
                         summary: "Alpha Summary".to_string(),
@@ -614,6 +1044,7 @@ mod tests {
             (
                 "20240201T000000000000Z",
                 StructuredTextSnapshot {
+                    schema_version: SCHEMA_VERSION,
                     content: StructuredContent {
                         title: "Beta Title".to_string(),
                         summary: "Beta Summary".to_string(),
@@ -629,6 +1060,7 @@ mod tests {
             (
                 "20240315T120000000000Z",
                 StructuredTextSnapshot {
+                    schema_version: SCHEMA_VERSION,
                     content: StructuredContent {
                         title: "Gamma Title".to_string(),
                         summary: "Highlights gamma timeline".to_string(),
@@ -657,7 +1089,7 @@ mod tests {
             since: Some(since),
             note_query: None,
         };
-        let filtered = list_structured_text_history(data_dir, 10, Some(&since_filter))
+        let filtered = list_structured_text_history(data_dir, None, 10, Some(&since_filter))
             .await
             .expect("list history since");
         assert_eq!(filtered.len(), 2);
@@ -671,7 +1103,7 @@ mod tests {
             since: None,
             note_query: Some("beta".to_string()),
         };
-        let filtered = list_structured_text_history(data_dir, 10, Some(&note_filter))
+        let filtered = list_structured_text_history(data_dir, None, 10, Some(&note_filter))
             .await
             .expect("list history by note");
         assert_eq!(filtered.len(), 1);
@@ -685,13 +1117,237 @@ mod tests {
             ),
             note_query: Some("milestones".to_string()),
         };
-        let filtered = list_structured_text_history(data_dir, 10, Some(&combined_filter))
+        let filtered = list_structured_text_history(data_dir, None, 10, Some(&combined_filter))
             .await
             .expect("list history combined filters");
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].id, "20240315T120000000000Z");
     }
 
+    #[tokio::test]
+    async fn list_structured_text_history_pages_with_cursor_and_offset() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path();
+        let history_dir = data_dir.join("mock/text_structure_history");
+        tokio::fs::create_dir_all(&history_dir)
+            .await
+            .expect("history dir");
+
+        let ids = [
+            "20240101T000000000000Z",
+            "20240201T000000000000Z",
+            "20240301T000000000000Z",
+            "20240401T000000000000Z",
+        ];
+        for (index, id) in ids.iter().enumerate() {
+            let snapshot = StructuredTextSnapshot {
+                schema_version: SCHEMA_VERSION,
+                content: StructuredContent {
+                    title: format!("Draft {index}"),
+                    summary: if index % 2 == 0 { "even" } else { "odd" }.to_string(),
+                    sections: vec![],
+                },
+                note: None,
+            };
+            tokio::fs::write(
+                history_dir.join(format!("{id}.json")),
+                serde_json::to_vec_pretty(&snapshot).unwrap(),
+            )
+            .await
+            .unwrap();
+        }
+        // Pages never read snapshots they do not return.
+        tokio::fs::write(history_dir.join(format!("{}.json", ids[0])), "not json")
+            .await
+            .unwrap();
+
+        let first = list_structured_text_history_page(data_dir, None, 2, None, None, 0)
+            .await
+            .expect("first page");
+        let page_ids = |page: &StructuredTextHistoryPage| {
+            page.entries
+                .iter()
+                .map(|entry| entry.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(page_ids(&first), vec![ids[3], ids[2]]);
+        assert_eq!(first.next_cursor.as_deref(), Some(ids[2]));
+
+        let second = list_structured_text_history_page(
+            data_dir,
+            None,
+            1,
+            None,
+            first.next_cursor.as_deref(),
+            0,
+        )
+        .await
+        .expect("second page");
+        assert_eq!(page_ids(&second), vec![ids[1]]);
+        assert_eq!(second.next_cursor.as_deref(), Some(ids[1]));
+
+        let skipped = list_structured_text_history_page(data_dir, None, 1, None, None, 2)
+            .await
+            .expect("offset page");
+        assert_eq!(page_ids(&skipped), vec![ids[1]]);
+
+        // A text query has to read snapshots to look for another match.
+        tokio::fs::remove_file(history_dir.join(format!("{}.json", ids[0])))
+            .await
+            .unwrap();
+        let odd = StructuredTextHistoryFilters {
+            since: None,
+            note_query: Some("odd".to_string()),
+        };
+        let filtered = list_structured_text_history_page(data_dir, None, 1, Some(&odd), None, 0)
+            .await
+            .expect("filtered page");
+        assert_eq!(page_ids(&filtered), vec![ids[3]]);
+        assert_eq!(filtered.next_cursor.as_deref(), Some(ids[3]));
+        let filtered = list_structured_text_history_page(
+            data_dir,
+            None,
+            1,
+            Some(&odd),
+            filtered.next_cursor.as_deref(),
+            0,
+        )
+        .await
+        .expect("last filtered page");
+        assert_eq!(page_ids(&filtered), vec![ids[1]]);
+        assert_eq!(filtered.next_cursor, None);
+
+        assert!(
+            list_structured_text_history_page(data_dir, None, 1, None, Some("yesterday"), 0)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_reports_each_broken_rule_with_its_path() {
+        assert!(StructuredContent::mock_payload().validate(None).is_empty());
+
+        let mut nested = StructuredSection {
+            heading: "Leaf".to_string(),
+            body: vec![],
+            children: vec![],
+        };
+        for depth in 0..STRUCTURED_TEXT_MAX_DEPTH {
+            nested = StructuredSection {
+                heading: format!("Level {depth}"),
+                body: vec![],
+                children: vec![nested],
+            };
+        }
+        let content = StructuredContent {
+            title: "  ".to_string(),
+            summary: "ok".to_string(),
+            sections: vec![
+                StructuredSection {
+                    heading: "Fine".to_string(),
+                    body: vec!["short".to_string(), "x".repeat(MAX_BODY_LINE_CHARS + 1)],
+                    children: vec![StructuredSection {
+                        heading: String::new(),
+                        body: vec![],
+                        children: vec![],
+                    }],
+                },
+                nested,
+            ],
+        };
+        let violations = content.validate(Some(&"n".repeat(MAX_NOTE_CHARS + 1)));
+        let fields: Vec<&str> = violations
+            .iter()
+            .map(|violation| violation.field.as_str())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "title",
+                "note",
+                "sections[0].body[1]",
+                "sections[0].children[0].heading",
+                "sections[1].children[0].children[0].children[0].children[0].children[0].children[0]",
+            ]
+        );
+        assert_eq!(violations[0].message, "must not be empty");
+
+        let wide = StructuredContent {
+            title: "Wide".to_string(),
+            summary: String::new(),
+            sections: vec![
+                StructuredSection {
+                    heading: "Section".to_string(),
+                    body: vec![],
+                    children: vec![],
+                };
+                STRUCTURED_TEXT_MAX_SECTIONS + 1
+            ],
+        };
+        let violations = wide.validate(None);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "sections");
+    }
+
+    #[test]
+    fn markdown_headings_become_nested_sections() {
+        let markdown = "---
+id: 1
+---
+# Launch plan
+
+Ship the beta
+by Friday.
+
+## Scope
+- API
+- UI
+
+### Risks
+1. Load
+
+```
+cargo test
+## not a heading
+```
+
+## Owners
+Platform team
+";
+        let content = structured_content_from_markdown(markdown, "fallback");
+        assert_eq!(content.title, "Launch plan");
+        assert_eq!(content.summary, "Ship the beta by Friday.");
+        assert_eq!(
+            content.sections,
+            vec![
+                StructuredSection {
+                    heading: "Scope".to_string(),
+                    body: vec!["- API".to_string(), "- UI".to_string()],
+                    children: vec![StructuredSection {
+                        heading: "Risks".to_string(),
+                        body: vec![
+                            "1. Load".to_string(),
+                            "cargo test\n## not a heading".to_string(),
+                        ],
+                        children: vec![],
+                    }],
+                },
+                StructuredSection {
+                    heading: "Owners".to_string(),
+                    body: vec!["Platform team".to_string()],
+                    children: vec![],
+                },
+            ]
+        );
+        assert!(content.validate(None).is_empty());
+
+        let untitled = structured_content_from_markdown("## Only\ntext", "notes");
+        assert_eq!(untitled.title, "notes");
+        assert_eq!(untitled.summary, "");
+        assert_eq!(untitled.sections[0].heading, "Only");
+    }
+
     #[tokio::test]
     async fn load_structured_text_history_entry_roundtrips() {
         let tmp = TempDir::new().unwrap();
@@ -699,20 +1355,23 @@ mod tests {
 
         save_structured_text_preview(
             data_dir,
+            None,
             &StructuredContent {
                 title: "Snapshot".to_string(),
                 summary: "Snapshot summary".to_string(),
                 sections: vec![],
             },
             Some("snapshot note"),
+            true,
+            Utc::now(),
         )
         .await
         .expect("save structured text");
 
-        let entries = list_structured_text_history(data_dir, 1, None)
+        let entries = list_structured_text_history(data_dir, None, 1, None)
             .await
             .expect("history entries");
-        let entry = load_structured_text_history_entry(data_dir, &entries[0].id)
+        let entry = load_structured_text_history_entry(data_dir, None, &entries[0].id)
             .await
             .expect("load entry")
             .expect("some entry");
@@ -729,39 +1388,51 @@ mod tests {
 
         save_structured_text_preview(
             data_dir,
+            None,
             &StructuredContent {
                 title: "First".to_string(),
                 summary: "First summary".to_string(),
                 sections: vec![],
             },
             Some("first note"),
+            true,
+            Utc::now(),
         )
         .await
         .expect("save first");
 
-        let entries = list_structured_text_history(data_dir, 1, None)
+        let entries = list_structured_text_history(data_dir, None, 1, None)
             .await
             .expect("history entries");
         let first_id = entries[0].id.clone();
 
         save_structured_text_preview(
             data_dir,
+            None,
             &StructuredContent {
                 title: "Second".to_string(),
                 summary: "Second summary".to_string(),
                 sections: vec![],
             },
             None,
+            true,
+            Utc::now(),
         )
         .await
         .expect("save second");
 
-        let restored = restore_structured_text_preview_from_history(data_dir, &first_id)
-            .await
-            .expect("restore");
+        let restored = restore_structured_text_preview_from_history(
+            data_dir,
+            None,
+            &first_id,
+            true,
+            Utc::now(),
+        )
+        .await
+        .expect("restore");
         assert!(restored);
 
-        let preview = load_structured_text_preview(data_dir)
+        let preview = load_structured_text_preview(data_dir, None)
             .await
             .expect("preview")
             .expect("some preview");
@@ -780,20 +1451,138 @@ mod tests {
             sections: vec![],
         };
 
-        save_structured_text_preview(data_dir, &content, Some("author note"))
-            .await
-            .expect("save structured text");
+        save_structured_text_preview(
+            data_dir,
+            None,
+            &content,
+            Some("author note"),
+            true,
+            Utc::now(),
+        )
+        .await
+        .expect("save structured text");
 
-        let preview = load_structured_text_preview(data_dir)
+        let preview = load_structured_text_preview(data_dir, None)
             .await
             .expect("preview")
             .expect("some preview");
         assert_eq!(preview.note.as_deref(), Some("author note"));
 
-        let history = list_structured_text_history(data_dir, 5, None)
+        let history = list_structured_text_history(data_dir, None, 5, None)
             .await
             .expect("history");
         assert_eq!(history.len(), 1);
         assert_eq!(history[0].note.as_deref(), Some("author note"));
     }
+
+    #[tokio::test]
+    async fn named_previews_keep_their_own_preview_and_history() {
+        let tmp = TempDir::new().unwrap();
+        let data_dir = tmp.path();
+
+        let content = |title: &str| StructuredContent {
+            title: title.to_string(),
+            summary: "Summary".to_string(),
+            sections: vec![],
+        };
+        save_structured_text_preview(data_dir, None, &content("Default"), None, true, Utc::now())
+            .await
+            .unwrap();
+        save_structured_text_preview(
+            data_dir,
+            Some("onboarding"),
+            &content("Onboarding"),
+            None,
+            true,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        save_structured_text_preview(
+            data_dir,
+            Some("onboarding"),
+            &content("Onboarding v2"),
+            None,
+            true,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+        let default = load_structured_text_preview(data_dir, Some(DEFAULT_PREVIEW_NAME))
+            .await
+            .unwrap()
+            .expect("default preview");
+        assert_eq!(default.content.title, "Default");
+        let named = load_structured_text_preview(data_dir, Some("onboarding"))
+            .await
+            .unwrap()
+            .expect("named preview");
+        assert_eq!(named.content.title, "Onboarding v2");
+        assert!(
+            data_dir
+                .join("mock/text_structures/onboarding/text_structure.json")
+                .exists()
+        );
+
+        let history = list_structured_text_history(data_dir, Some("onboarding"), 10, None)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        let default_history = list_structured_text_history(data_dir, None, 10, None)
+            .await
+            .unwrap();
+        assert_eq!(default_history.len(), 1);
+        assert!(
+            load_structured_text_history_entry(data_dir, None, &history[0].id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let previews = list_structured_text_previews(data_dir).await.unwrap();
+        let names: Vec<_> = previews
+            .iter()
+            .map(|preview| preview.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["default", "onboarding"]);
+
+        delete_structured_text_preview(data_dir, Some("onboarding"))
+            .await
+            .unwrap();
+        assert!(
+            load_structured_text_preview(data_dir, Some("onboarding"))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            list_structured_text_previews(data_dir).await.unwrap().len(),
+            1
+        );
+
+        for bad in [
+            "",
+            "Upper",
+            "../up",
+            "a/b",
+            "history",
+            "from_markdown",
+            "stream",
+        ] {
+            assert!(normalize_preview_name(bad).is_err(), "{bad:?} accepted");
+        }
+        assert!(
+            save_structured_text_preview(
+                data_dir,
+                Some("../escape"),
+                &content("x"),
+                None,
+                true,
+                Utc::now()
+            )
+            .await
+            .is_err()
+        );
+    }
 }
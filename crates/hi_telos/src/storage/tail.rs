@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read per step from the end of the file.
+const TAIL_CHUNK: usize = 64 * 1024;
+
+/// Lines of a file from the last to the first, read in chunks from the end
+/// so a caller that only wants the newest lines of an append-only log stops
+/// without reading the rest. Lines come without their `\n`; a trailing
+/// newline yields an empty last line.
+pub(super) struct ReverseLines {
+    file: File,
+    /// Start of the part of the file not read yet.
+    pos: u64,
+    chunk: usize,
+    /// Bytes before the first `\n` of what was read, i.e. the tail of a line
+    /// whose start is still unread.
+    carry: Vec<u8>,
+    /// Complete lines of what was read, oldest first.
+    ready: Vec<Vec<u8>>,
+    done: bool,
+}
+
+impl ReverseLines {
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        Self::open_with_chunk(path, TAIL_CHUNK)
+    }
+
+    fn open_with_chunk(path: &Path, chunk: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let pos = file.metadata()?.len();
+        Ok(Self {
+            file,
+            pos,
+            chunk: chunk.max(1),
+            carry: Vec::new(),
+            ready: Vec::new(),
+            done: false,
+        })
+    }
+
+    /// Read the chunk before `pos` and split it into lines.
+    fn fill(&mut self) -> io::Result<()> {
+        let len = (self.chunk as u64).min(self.pos);
+        self.pos -= len;
+        self.file.seek(SeekFrom::Start(self.pos))?;
+        let mut data = vec![0; len as usize];
+        self.file.read_exact(&mut data)?;
+        data.append(&mut self.carry);
+
+        let mut lines = data.split(|byte| *byte == b'\n');
+        self.carry = lines.next().unwrap_or_default().to_vec();
+        self.ready.extend(lines.map(<[u8]>::to_vec));
+        Ok(())
+    }
+}
+
+impl Iterator for ReverseLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.ready.pop() {
+                return Some(decode(line));
+            }
+            if self.done {
+                return None;
+            }
+            if self.pos == 0 {
+                // The first line of the file has no `\n` before it.
+                self.done = true;
+                return Some(decode(std::mem::take(&mut self.carry)));
+            }
+            if let Err(err) = self.fill() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+fn decode(line: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn reversed(content: &str, chunk: usize) -> Vec<String> {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("log.jsonl");
+        std::fs::write(&path, content).unwrap();
+        ReverseLines::open_with_chunk(&path, chunk)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn lines_come_newest_first_across_chunk_boundaries() {
+        let content = "first\nsecond — ünïcode\n\nlast\n";
+        let expected = ["", "last", "", "second — ünïcode", "first"];
+        for chunk in [1, 2, 3, 7, 64, TAIL_CHUNK] {
+            assert_eq!(reversed(content, chunk), expected, "chunk {chunk}");
+        }
+        assert_eq!(reversed("no newline", 4), ["no newline"]);
+        assert_eq!(reversed("", 4), [""]);
+    }
+}
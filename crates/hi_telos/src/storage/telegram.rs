@@ -0,0 +1,106 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::write_atomic;
+
+const TELEGRAM_UPDATE_STATE_PATH: &str = "telegram/updates.json";
+
+/// Processed update ids remembered per bot for dedup.
+const RECENT_UPDATE_IDS: usize = 1_000;
+
+/// Telegram update ids processed per bot (keyed by the numeric bot id from
+/// the token): the last one, whose successor is the next `getUpdates` offset
+/// in polling mode, and a window of recent ones that replays are checked
+/// against. Ids are not guaranteed to grow; after a week without updates
+/// Telegram picks the next one at random, possibly below the last.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelegramUpdateState {
+    #[serde(default)]
+    bots: BTreeMap<String, i64>,
+    #[serde(default)]
+    recent: BTreeMap<String, VecDeque<i64>>,
+}
+
+impl TelegramUpdateState {
+    pub fn last_update_id(&self, bot: &str) -> Option<i64> {
+        self.bots.get(bot).copied()
+    }
+
+    pub fn is_processed(&self, bot: &str, update_id: i64) -> bool {
+        self.recent
+            .get(bot)
+            .is_some_and(|recent| recent.contains(&update_id))
+    }
+
+    /// Remember `update_id`. An id far below the last one means Telegram
+    /// started over, so it becomes the last one.
+    pub fn record(&mut self, bot: &str, update_id: i64) {
+        let last = self.bots.entry(bot.to_string()).or_insert(update_id);
+        if update_id > *last || *last - update_id >= RECENT_UPDATE_IDS as i64 {
+            *last = update_id;
+        }
+
+        let recent = self.recent.entry(bot.to_string()).or_default();
+        if !recent.contains(&update_id) {
+            recent.push_back(update_id);
+            while recent.len() > RECENT_UPDATE_IDS {
+                recent.pop_front();
+            }
+        }
+    }
+}
+
+pub fn load_telegram_update_state(data_dir: &Path) -> anyhow::Result<TelegramUpdateState> {
+    let path = update_state_path(data_dir);
+    if !path.exists() {
+        return Ok(TelegramUpdateState::default());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("reading telegram update state {:?}", path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("parsing telegram update state {:?}", path))
+}
+
+pub fn save_telegram_update_state(
+    data_dir: &Path,
+    state: &TelegramUpdateState,
+) -> anyhow::Result<()> {
+    let path = update_state_path(data_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating telegram state dir {:?}", parent))?;
+    }
+    write_atomic(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("writing telegram update state {:?}", path))
+}
+
+fn update_state_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(TELEGRAM_UPDATE_STATE_PATH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_after_a_random_restart_are_not_replays() {
+        let mut state = TelegramUpdateState::default();
+        for update_id in 900_000..900_010 {
+            state.record("123", update_id);
+        }
+        state.record("123", 900_004);
+        assert_eq!(state.last_update_id("123"), Some(900_009));
+        assert!(state.is_processed("123", 900_004));
+
+        // A week without updates: Telegram picks the next id at random.
+        assert!(!state.is_processed("123", 17));
+        state.record("123", 17);
+        assert_eq!(state.last_update_id("123"), Some(17));
+        assert!(state.is_processed("123", 17));
+        assert!(!state.is_processed("123", 18));
+    }
+}
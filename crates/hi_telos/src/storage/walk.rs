@@ -0,0 +1,223 @@
+use std::path::{Component, Path};
+
+use serde::Deserialize;
+use walkdir::{DirEntry, WalkDir};
+
+/// What the markdown tree listing (`/api/md/tree`, `/ui/md`) skips, from
+/// `config/walk.yml`. Reads of journals, memory, stats and logs, and
+/// backups, exports and retention, still walk everything.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WalkPolicy {
+    /// Globs matched against paths relative to the data dir. A pattern
+    /// without `/` matches any file or directory of that name (`.git`,
+    /// `*.bak`); one with `/` matches from the data dir down, with `*` and
+    /// `?` inside a segment and `**` for any number of segments. An ignored
+    /// directory is not entered.
+    #[serde(default = "default_walk_ignore")]
+    pub ignore: Vec<String>,
+    /// Deepest path below the data dir a walker visits; `journals/a.md` is
+    /// depth 2. `None` walks to the bottom.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+impl Default for WalkPolicy {
+    fn default() -> Self {
+        Self {
+            ignore: default_walk_ignore(),
+            max_depth: None,
+        }
+    }
+}
+
+fn default_walk_ignore() -> Vec<String> {
+    vec![
+        ".git".to_string(),
+        super::MD_HISTORY_DIR.to_string(),
+        "backups".to_string(),
+    ]
+}
+
+impl WalkPolicy {
+    /// Whether `relative`, a path under the data dir, is skipped.
+    pub fn ignores(&self, relative: &Path) -> bool {
+        let segments: Vec<&str> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+        if segments.is_empty() {
+            return false;
+        }
+        self.ignore.iter().any(|pattern| {
+            if pattern.contains('/') {
+                let pattern: Vec<&str> = pattern
+                    .trim_matches('/')
+                    .split('/')
+                    .filter(|part| !part.is_empty())
+                    .collect();
+                path_match(&pattern, &segments)
+            } else {
+                segments.iter().any(|name| wildcard_match(pattern, name))
+            }
+        })
+    }
+}
+
+/// Walk `root`, a directory under `data_dir`, like [`WalkDir`], minus what
+/// `policy` ignores or puts below its `max_depth`.
+pub fn walk<'a>(
+    data_dir: &'a Path,
+    root: &Path,
+    policy: &'a WalkPolicy,
+) -> impl Iterator<Item = walkdir::Result<DirEntry>> + use<'a> {
+    let mut walker = WalkDir::new(root);
+    if let Some(max_depth) = policy.max_depth {
+        let root_depth = root
+            .strip_prefix(data_dir)
+            .map(|relative| relative.components().count())
+            .unwrap_or(0);
+        walker = walker.max_depth(max_depth.saturating_sub(root_depth));
+    }
+    walker.into_iter().filter_entry(move |entry| {
+        entry
+            .path()
+            .strip_prefix(data_dir)
+            .map(|relative| !policy.ignores(relative))
+            .unwrap_or(true)
+    })
+}
+
+/// `pattern` segments, where `**` matches any number of path segments,
+/// against the leading segments of `path`. Matching a prefix is enough:
+/// everything under an ignored directory is ignored with it.
+fn path_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| path_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => wildcard_match(first, name) && path_match(rest, path),
+            None => false,
+        },
+    }
+}
+
+/// `*` matches any run of characters and `?` exactly one; everything else
+/// matches itself. Used for walk ignore patterns and acceptance plan names.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut pattern_chars = pattern.chars();
+    match pattern_chars.next() {
+        None => name.is_empty(),
+        Some('*') => {
+            let rest = pattern_chars.as_str();
+            name.char_indices()
+                .map(|(start, _)| start)
+                .chain([name.len()])
+                .any(|start| wildcard_match(rest, &name[start..]))
+        }
+        Some(expected) => {
+            let mut name_chars = name.chars();
+            match name_chars.next() {
+                Some(actual) if expected == '?' || expected == actual => {
+                    wildcard_match(pattern_chars.as_str(), name_chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn policy(ignore: &[&str], max_depth: Option<usize>) -> WalkPolicy {
+        WalkPolicy {
+            ignore: ignore.iter().map(|pattern| pattern.to_string()).collect(),
+            max_depth,
+        }
+    }
+
+    #[test]
+    fn ignore_patterns_match_names_and_anchored_paths() {
+        let policy = policy(&[".git", "*.bak", "journals/**/drafts", "inbox/a?"], None);
+        for ignored in [
+            ".git",
+            "notes/.git/HEAD",
+            "notes/todo.md.bak",
+            "journals/drafts/x.md",
+            "journals/2025/01/drafts",
+            "inbox/ab/c.md",
+        ] {
+            assert!(policy.ignores(Path::new(ignored)), "{ignored}");
+        }
+        for kept in ["", "notes/git.md", "bak", "notes/drafts/x.md", "inbox/abc"] {
+            assert!(!policy.ignores(Path::new(kept)), "{kept}");
+        }
+    }
+
+    #[test]
+    fn wildcard_match_handles_stars_and_single_chars() {
+        assert!(wildcard_match("*.md", "plan.md"));
+        assert!(wildcard_match("plan-*-v*.md", "plan-ops-v2.md"));
+        assert!(wildcard_match("*", "验收.md"));
+        assert!(wildcard_match("plan-?.md", "plan-验.md"));
+        assert!(!wildcard_match("*.md", "plan.txt"));
+        assert!(!wildcard_match("plan.md", "plan.mdx"));
+        assert!(!wildcard_match("plan-?.md", "plan-10.md"));
+    }
+
+    #[test]
+    fn walks_skip_ignored_dirs_and_stop_at_max_depth() {
+        let temp = tempdir().unwrap();
+        let data_dir = temp.path().join("data");
+        for file in [
+            "journals/2025/01/01/a.md",
+            "journals/top.md",
+            "attachments/big.md",
+            "backups/old.md",
+        ] {
+            let path = data_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        let files = |root: &Path, policy: &WalkPolicy| -> Vec<String> {
+            let mut files: Vec<String> = walk(&data_dir, root, policy)
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| {
+                    let relative = entry.path().strip_prefix(&data_dir).unwrap();
+                    relative.to_string_lossy().replace('\\', "/")
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        let defaults = WalkPolicy::default();
+        assert_eq!(
+            files(&data_dir, &defaults),
+            [
+                "attachments/big.md",
+                "journals/2025/01/01/a.md",
+                "journals/top.md"
+            ]
+        );
+
+        let custom = policy(&["attachments"], Some(3));
+        assert_eq!(
+            files(&data_dir, &custom),
+            ["backups/old.md", "journals/top.md"]
+        );
+        // Depth counts from the data dir, not from where the walk starts.
+        assert!(files(&data_dir.join("journals/2025"), &custom).is_empty());
+        assert_eq!(
+            files(&data_dir.join("journals"), &custom),
+            ["journals/top.md"]
+        );
+    }
+}
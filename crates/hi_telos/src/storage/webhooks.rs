@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// One outbound webhook delivery, successful or not, after all retries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub event: String,
+    pub url: String,
+    pub attempts: u32,
+    pub delivered: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+pub async fn append_webhook_delivery(
+    data_dir: &Path,
+    delivery: &WebhookDelivery,
+) -> anyhow::Result<()> {
+    let date = delivery.timestamp.date_naive();
+    let log_dir =
+        data_dir
+            .join("logs/webhooks")
+            .join(format!("{:04}/{:02}", date.year(), date.month()));
+    fs::create_dir_all(&log_dir).await?;
+    let log_path = log_dir.join(format!("{:02}.jsonl", date.day()));
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .await
+        .with_context(|| format!("opening webhook delivery log {:?}", log_path))?;
+    let mut line = serde_json::to_string(delivery)?;
+    line.push('\n');
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// Most recent deliveries first.
+pub fn read_webhook_deliveries(
+    data_dir: &Path,
+    limit: usize,
+) -> anyhow::Result<Vec<WebhookDelivery>> {
+    let root = data_dir.join("logs/webhooks");
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = WalkDir::new(&root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    files.sort();
+
+    let mut deliveries = Vec::new();
+    for file in files.iter().rev() {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("reading webhook delivery log {:?}", file))?;
+        for line in content.lines().rev().filter(|line| !line.trim().is_empty()) {
+            let delivery: WebhookDelivery = serde_json::from_str(line)
+                .with_context(|| format!("parsing webhook delivery in {:?}", file))?;
+            deliveries.push(delivery);
+            if deliveries.len() >= limit {
+                return Ok(deliveries);
+            }
+        }
+    }
+    Ok(deliveries)
+}
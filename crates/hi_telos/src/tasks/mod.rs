@@ -1,9 +1,171 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Metadata flag set when a deferred intent was approved by a human.
+pub const INTENT_APPROVED_KEY: &str = "approved";
+
+/// Metadata carrying a cost estimate supplied by whoever submitted the
+/// intent (`POST /api/intents`, a source policy), in whatever unit the
+/// operator budgets in, compared against `beat.approval.max_cost_estimate`.
+/// The app never computes it; see [`ESTIMATED_TOKENS_KEY`] for that.
+pub const COST_ESTIMATE_KEY: &str = "cost_estimate";
+
+/// Metadata carrying the tokens a run of the intent is expected to use,
+/// estimated by the app when the beat first sees it in the inbox and
+/// compared against the `beat.approval` token ceilings. Both estimates are
+/// checked by [`ApprovalConfig::hold_reason`](crate::config::ApprovalConfig::hold_reason)
+/// and both are listed by `/api/intents` and `/ui/intents`.
+pub const ESTIMATED_TOKENS_KEY: &str = "estimated_tokens";
+
+/// Metadata naming the `beat.approval` setting that held the intent for
+/// approval, e.g. `max_estimated_tokens`.
+pub const HOLD_REASON_KEY: &str = "hold_reason";
+
+/// Metadata carrying the intent's [`IntentPriority`].
+pub const PRIORITY_KEY: &str = "priority";
+
+/// Metadata naming the archived intent a replayed intent was cloned from.
+pub const REPLAY_OF_KEY: &str = "replay_of";
+
+/// Metadata pinning the LLM provider (`local_stub` or `openai`) an intent
+/// runs against instead of the configured one.
+pub const LLM_PROVIDER_KEY: &str = "llm_provider";
+
+/// Metadata pinning the model an intent runs against.
+pub const LLM_MODEL_KEY: &str = "llm_model";
+
+/// Metadata naming the `agent.personas` entry an intent runs as. A
+/// top-level `persona:` in the front matter is read into this key.
+pub const PERSONA_KEY: &str = "persona";
+
+/// Metadata grouping an intent's SP entry; the intent source otherwise.
+pub const CATEGORY_KEY: &str = "category";
+
+/// Metadata on archived intents: wall-clock milliseconds spent processing.
+pub const DURATION_MS_KEY: &str = "duration_ms";
+
+/// Metadata on archived intents: milliseconds of that spent waiting on the
+/// LLM.
+pub const LLM_LATENCY_MS_KEY: &str = "llm_latency_ms";
+
+/// Metadata on archived intents: version of the prompt templates the run
+/// used.
+pub const PROMPT_VERSION_KEY: &str = "prompt_version";
+
+/// Metadata on archived intents: experiment the run took part in.
+pub const EXPERIMENT_KEY: &str = "experiment";
+
+/// Metadata on archived intents: the experiment variant the run used.
+pub const EXPERIMENT_VARIANT_KEY: &str = "experiment_variant";
+
+/// Metadata on quarantined intents: the [`crate::storage::FailureClass`] of
+/// the last error.
+pub const FAILURE_CLASS_KEY: &str = "failure_class";
+
+/// Metadata on quarantined intents: the last error, on one line.
+pub const FAILURE_ERROR_KEY: &str = "failure_error";
+
+/// Metadata on quarantined intents: RFC 3339 time it was quarantined.
+pub const FAILED_AT_KEY: &str = "failed_at";
+
+/// Metadata on quarantined intents: comma-separated ids of the failed runs.
+pub const FAILED_RUNS_KEY: &str = "failed_runs";
+
+/// Metadata on overdue intents: RFC 3339 time the SLA alert went out, so
+/// it is sent once per deadline, across restarts.
+pub const SLA_ALERTED_AT_KEY: &str = "sla_alerted_at";
+
+/// Where an intent is in its lifecycle, recorded in its front matter next to
+/// the directory it sits in. Files written before states were recorded have
+/// none until their next move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentState {
+    Received,
+    PendingApproval,
+    Deferred,
+    Queued,
+    Processing,
+    /// Paused on a question to the user.
+    Waiting,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl IntentState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IntentState::Received => "received",
+            IntentState::PendingApproval => "pending_approval",
+            IntentState::Deferred => "deferred",
+            IntentState::Queued => "queued",
+            IntentState::Processing => "processing",
+            IntentState::Waiting => "waiting",
+            IntentState::Done => "done",
+            IntentState::Failed => "failed",
+            IntentState::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether an intent in this state may move to `next`. `done` and
+    /// `cancelled` are final; failed, deferred and held intents go back to
+    /// `received` when a human approves or retries them.
+    pub fn can_transition_to(self, next: IntentState) -> bool {
+        use IntentState::*;
+        match self {
+            Received => matches!(next, PendingApproval | Deferred | Queued | Cancelled),
+            PendingApproval | Deferred | Failed => matches!(next, Received | Cancelled),
+            Queued => matches!(next, Processing | Waiting | Cancelled),
+            Processing => matches!(next, Queued | Waiting | Done | Failed),
+            Waiting => matches!(next, Queued | Cancelled),
+            Done | Cancelled => false,
+        }
+    }
+}
+
+impl std::fmt::Display for IntentState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One entry of an intent's audit trail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntentTransition {
+    /// `None` for the first entry, or when the file had no recorded state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<IntentState>,
+    pub to: IntentState,
+    pub at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// How urgently an intent should run once it is queued. Intents without a
+/// priority are `normal`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl IntentPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntentPriority::Low => "low",
+            IntentPriority::Normal => "normal",
+            IntentPriority::High => "high",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Intent {
     pub id: Uuid,
@@ -11,13 +173,68 @@ pub struct Intent {
     pub summary: String,
     pub telos_alignment: f32,
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Free-form key/value pairs carried in the intent front matter, e.g. the
+    /// GitHub issue an intent was created from.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
     #[serde(skip)]
     pub storage_path: Option<PathBuf>,
 }
 
+impl Intent {
+    /// True once the intent has a `due_at` that lies before `now`.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.due_at.is_some_and(|due_at| due_at < now)
+    }
+
+    /// True once an SLA alert went out for the current `due_at`; moving
+    /// the deadline later arms the alert again.
+    pub fn sla_alerted(&self) -> bool {
+        let alerted_at = self
+            .metadata
+            .get(SLA_ALERTED_AT_KEY)
+            .and_then(|value| value.parse::<DateTime<Utc>>().ok());
+        matches!((alerted_at, self.due_at), (Some(at), Some(due_at)) if at >= due_at)
+    }
+
+    pub fn cost_estimate(&self) -> Option<f64> {
+        self.metadata
+            .get(COST_ESTIMATE_KEY)
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    pub fn estimated_tokens(&self) -> Option<u64> {
+        self.metadata
+            .get(ESTIMATED_TOKENS_KEY)
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    pub fn priority(&self) -> IntentPriority {
+        match self.metadata.get(PRIORITY_KEY).map(|value| value.trim()) {
+            Some("low") => IntentPriority::Low,
+            Some("high") => IntentPriority::High,
+            _ => IntentPriority::Normal,
+        }
+    }
+
+    /// True when a human approved the intent despite its low alignment or
+    /// the approval gate.
+    pub fn is_approved(&self) -> bool {
+        self.metadata
+            .get(INTENT_APPROVED_KEY)
+            .is_some_and(|value| value == "true")
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct IntentQueue {
     items: std::collections::VecDeque<Intent>,
+    /// Leading items a human put in order through [`IntentQueue::bump`] or
+    /// [`IntentQueue::reorder`]; newly queued intents line up behind them
+    /// whatever their priority.
+    pinned: usize,
 }
 
 impl IntentQueue {
@@ -25,12 +242,96 @@ impl IntentQueue {
         self.items.push_back(intent);
     }
 
+    /// Queue an intent behind every pinned intent and every waiting intent of
+    /// the same or a higher priority.
+    pub fn enqueue(&mut self, intent: Intent) {
+        let priority = intent.priority();
+        let index = self
+            .items
+            .iter()
+            .skip(self.pinned)
+            .position(|queued| queued.priority() < priority)
+            .map_or(self.items.len(), |index| index + self.pinned);
+        self.items.insert(index, intent);
+    }
+
     pub fn push_front(&mut self, intent: Intent) {
         self.items.push_front(intent);
+        if self.pinned > 0 {
+            self.pinned += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.pinned = 0;
+    }
+
+    /// Take a waiting intent out of the queue, e.g. when it is cancelled.
+    pub fn remove(&mut self, id: Uuid) -> Option<Intent> {
+        let index = self.items.iter().position(|intent| intent.id == id)?;
+        if index < self.pinned {
+            self.pinned -= 1;
+        }
+        self.items.remove(index)
+    }
+
+    pub fn get_mut(&mut self, id: Uuid) -> Option<&mut Intent> {
+        self.items.iter_mut().find(|intent| intent.id == id)
+    }
+
+    /// Move a waiting intent to the head of the queue and pin it there.
+    /// `false` when it is not in the queue.
+    pub fn bump(&mut self, id: Uuid) -> bool {
+        let Some(intent) = self.remove(id) else {
+            return false;
+        };
+        self.items.push_front(intent);
+        self.pinned += 1;
+        true
+    }
+
+    /// Put `ids` at the head of the queue in that order and pin them, ahead
+    /// of the intents pinned before. Returns the first id that is not
+    /// waiting in the queue, or is listed twice, without changing anything.
+    pub fn reorder(&mut self, ids: &[Uuid]) -> Result<(), Uuid> {
+        let mut seen = std::collections::HashSet::new();
+        if let Some(&bad) = ids
+            .iter()
+            .find(|id| !seen.insert(**id) || !self.items.iter().any(|intent| intent.id == **id))
+        {
+            return Err(bad);
+        }
+        self.pin(ids);
+        Ok(())
+    }
+
+    /// [`IntentQueue::reorder`] skipping ids that are not queued, e.g. to
+    /// restore an order saved before a restart.
+    pub fn pin(&mut self, ids: &[Uuid]) {
+        for id in ids.iter().rev() {
+            self.bump(*id);
+        }
+    }
+
+    /// Ids of the pinned intents, head first.
+    pub fn pinned_ids(&self) -> Vec<Uuid> {
+        self.items
+            .iter()
+            .take(self.pinned)
+            .map(|intent| intent.id)
+            .collect()
+    }
+
+    /// Ids of every waiting intent in the order they will run.
+    pub fn ids(&self) -> Vec<Uuid> {
+        self.items.iter().map(|intent| intent.id).collect()
     }
 
     pub fn pop_next(&mut self) -> Option<Intent> {
-        self.items.pop_front()
+        let intent = self.items.pop_front()?;
+        self.pinned = self.pinned.saturating_sub(1);
+        Some(intent)
     }
 
     pub fn len(&self) -> usize {
@@ -41,3 +342,87 @@ impl IntentQueue {
         self.items.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(summary: &str, priority: Option<&str>) -> Intent {
+        Intent {
+            id: Uuid::new_v4(),
+            source: "user".to_string(),
+            summary: summary.to_string(),
+            telos_alignment: 0.9,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: priority
+                .map(|priority| (PRIORITY_KEY.to_string(), priority.to_string()))
+                .into_iter()
+                .collect(),
+            storage_path: None,
+        }
+    }
+
+    #[test]
+    fn enqueue_orders_by_priority_then_arrival() {
+        let mut queue = IntentQueue::default();
+        queue.enqueue(intent("normal-1", None));
+        queue.enqueue(intent("low", Some("low")));
+        queue.enqueue(intent("high-1", Some("high")));
+        queue.enqueue(intent("normal-2", Some("normal")));
+        queue.enqueue(intent("high-2", Some("high")));
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop_next())
+            .map(|intent| intent.summary)
+            .collect();
+        assert_eq!(order, ["high-1", "high-2", "normal-1", "normal-2", "low"]);
+    }
+
+    #[test]
+    fn bumped_and_reordered_intents_stay_ahead_of_new_arrivals() {
+        let mut queue = IntentQueue::default();
+        let first = intent("normal-1", None);
+        let low = intent("low", Some("low"));
+        let second = intent("normal-2", None);
+        let (first_id, low_id, second_id) = (first.id, low.id, second.id);
+        queue.enqueue(first);
+        queue.enqueue(low);
+        queue.enqueue(second);
+
+        assert!(queue.bump(low_id));
+        assert!(!queue.bump(Uuid::new_v4()));
+        queue.enqueue(intent("high", Some("high")));
+        assert_eq!(queue.pinned_ids(), [low_id]);
+
+        assert_eq!(queue.reorder(&[second_id, second_id]), Err(second_id));
+        let unknown = Uuid::new_v4();
+        assert_eq!(queue.reorder(&[second_id, unknown]), Err(unknown));
+        assert_eq!(queue.reorder(&[second_id]), Ok(()));
+        assert_eq!(queue.pinned_ids(), [second_id, low_id]);
+        assert!(queue.remove(second_id).is_some());
+        assert_eq!(queue.pinned_ids(), [low_id]);
+        assert_eq!(queue.ids()[2], first_id);
+
+        let order: Vec<String> = std::iter::from_fn(|| queue.pop_next())
+            .map(|intent| intent.summary)
+            .collect();
+        assert_eq!(order, ["low", "high", "normal-1"]);
+    }
+
+    #[test]
+    fn sla_alert_rearms_when_the_deadline_moves() {
+        let due_at = "2025-03-01T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut overdue = intent("overdue", None);
+        overdue.due_at = Some(due_at);
+        assert!(!overdue.sla_alerted());
+
+        overdue.metadata.insert(
+            SLA_ALERTED_AT_KEY.to_string(),
+            "2025-03-01T09:10:00Z".to_string(),
+        );
+        assert!(overdue.sla_alerted());
+
+        overdue.due_at = Some(due_at + chrono::TimeDelta::days(1));
+        assert!(!overdue.sla_alerted());
+    }
+}
@@ -0,0 +1,1441 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::select;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    clock::Clock,
+    config::{AppConfig, TelegramConfig, TelegramMode},
+    outbox::{self, Channels},
+    server::{self, ServerState},
+    sources::{IngestMode, IngestSource},
+    state::AppContext,
+    storage::{self, IntentDraft, MessageDirection, MessageLogEntry, OutboxMessage},
+    tasks::Intent,
+    text,
+};
+
+pub const CHAT_ID_KEY: &str = "telegram_chat_id";
+pub const MESSAGE_ID_KEY: &str = "telegram_message_id";
+
+/// Telegram rejects messages longer than 4096 characters.
+const TELEGRAM_MESSAGE_MAX_CHARS: usize = 4096;
+
+/// Bound on one Bot API call, so a hung server cannot hold up the outbox.
+const TELEGRAM_API_TIMEOUT_SECS: u64 = 10;
+
+/// Slack on top of `poll_timeout_secs` before a `getUpdates` long-poll is
+/// given up on, so a half-open connection cannot stall the source.
+const TELEGRAM_POLL_MARGIN_SECS: u64 = 10;
+
+pub struct TelegramSendResult {
+    pub message_id: Option<i64>,
+}
+
+pub async fn send_message(
+    config: &TelegramConfig,
+    chat_id: i64,
+    text: &str,
+) -> anyhow::Result<TelegramSendResult> {
+    send_message_with_markup(config, chat_id, text, None).await
+}
+
+/// `sendMessage` with an optional `reply_markup` such as an inline keyboard.
+pub async fn send_message_with_markup(
+    config: &TelegramConfig,
+    chat_id: i64,
+    text: &str,
+    reply_markup: Option<serde_json::Value>,
+) -> anyhow::Result<TelegramSendResult> {
+    let mut request = json!({
+        "chat_id": chat_id,
+        "text": text,
+    });
+    if let Some(reply_markup) = reply_markup {
+        request["reply_markup"] = reply_markup;
+    }
+    let payload = call_api(config, "sendMessage", &request).await?;
+
+    let message_id = payload
+        .get("result")
+        .or_else(|| payload.get("message"))
+        .and_then(|value| value.get("message_id"))
+        .and_then(|value| value.as_i64());
+
+    Ok(TelegramSendResult { message_id })
+}
+
+async fn call_api(
+    config: &TelegramConfig,
+    method: &str,
+    request: &serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(TELEGRAM_API_TIMEOUT_SECS))
+        .build()
+        .context("building telegram client")?;
+    let base = config.api_base.trim_end_matches('/');
+    let url = format!("{}/bot{}/{}", base, config.bot_token, method);
+
+    let response = client
+        .post(url)
+        .json(request)
+        .send()
+        .await
+        // The bot token is part of the URL; keep it out of the error.
+        .map_err(reqwest::Error::without_url)
+        .with_context(|| format!("calling telegram {method}"))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("telegram returned status {}", response.status()));
+    }
+
+    let payload: serde_json::Value = response
+        .json()
+        .await
+        .map_err(reqwest::Error::without_url)
+        .with_context(|| "decoding telegram response")?;
+
+    let ok = payload
+        .get("ok")
+        .and_then(|flag| flag.as_bool())
+        .unwrap_or(false);
+    if !ok {
+        return Err(anyhow!("telegram {method} rejected: {}", payload));
+    }
+    Ok(payload)
+}
+
+/// Point the bot at `url` via `setWebhook`, passing the configured
+/// `webhook_secret` so Telegram echoes it back in
+/// `X-Telegram-Bot-Api-Secret-Token`.
+pub async fn set_webhook(config: &TelegramConfig, url: &str) -> anyhow::Result<serde_json::Value> {
+    let mut request = json!({
+        "url": url,
+        "allowed_updates": ["message", "channel_post", "callback_query"],
+    });
+    if let Some(secret) = &config.webhook_secret {
+        request["secret_token"] = json!(secret);
+    }
+    call_api(config, "setWebhook", &request).await
+}
+
+pub async fn delete_webhook(
+    config: &TelegramConfig,
+    drop_pending_updates: bool,
+) -> anyhow::Result<serde_json::Value> {
+    call_api(
+        config,
+        "deleteWebhook",
+        &json!({ "drop_pending_updates": drop_pending_updates }),
+    )
+    .await
+}
+
+pub async fn get_webhook_info(config: &TelegramConfig) -> anyhow::Result<serde_json::Value> {
+    let payload = call_api(config, "getWebhookInfo", &json!({})).await?;
+    Ok(payload.get("result").cloned().unwrap_or_default())
+}
+
+/// `{public_url}/webhook/telegram`, the route Telegram should call.
+pub fn webhook_url(config: &TelegramConfig) -> Option<String> {
+    config.public_url.as_deref().map(|base| {
+        format!(
+            "{}{}",
+            base.trim_end_matches('/'),
+            server::TELEGRAM_WEBHOOK_PATH
+        )
+    })
+}
+
+/// Send a message and record it in the outbound message log. A failure to
+/// write the log is only warned about since the message already went out.
+pub async fn send_logged_message(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    chat_id: i64,
+    text: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<TelegramSendResult> {
+    send_logged_message_with_markup(data_dir, config, chat_id, text, None, now).await
+}
+
+pub async fn send_logged_message_with_markup(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    chat_id: i64,
+    text: &str,
+    reply_markup: Option<serde_json::Value>,
+    now: DateTime<Utc>,
+) -> anyhow::Result<TelegramSendResult> {
+    let result = send_message_with_markup(config, chat_id, text, reply_markup).await?;
+
+    let entry = MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction: MessageDirection::Outbound,
+        source: "telegram".to_string(),
+        chat_id: chat_id.to_string(),
+        author: Some("telos".to_string()),
+        text: text.to_string(),
+        timestamp: now,
+        metadata: Some(json!({ "message_id": result.message_id })),
+    };
+
+    if let Err(err) = storage::append_message_entry(data_dir, &entry).await {
+        warn!(error = ?err, "failed to persist outbound message log");
+    }
+
+    Ok(result)
+}
+
+/// Decision taken from the inline keyboard sent for a deferred intent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approve,
+    Defer,
+    Discard,
+}
+
+impl ApprovalDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Approve => "approve",
+            Self::Defer => "defer",
+            Self::Discard => "discard",
+        }
+    }
+
+    fn past_tense(self) -> &'static str {
+        match self {
+            Self::Approve => "approved",
+            Self::Defer => "deferred",
+            Self::Discard => "discarded",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "approve" => Some(Self::Approve),
+            "defer" => Some(Self::Defer),
+            "discard" => Some(Self::Discard),
+            _ => None,
+        }
+    }
+
+    /// `callback_data` carried by the keyboard button, e.g.
+    /// `intent:approve:<uuid>` (well under Telegram's 64 byte limit).
+    pub fn callback_data(self, intent_id: Uuid) -> String {
+        format!("intent:{}:{}", self.as_str(), intent_id)
+    }
+
+    fn from_callback_data(data: &str) -> Option<(Self, Uuid)> {
+        let mut parts = data.splitn(3, ':');
+        if parts.next()? != "intent" {
+            return None;
+        }
+        let decision = Self::parse(parts.next()?)?;
+        let intent_id = parts.next()?.parse().ok()?;
+        Some((decision, intent_id))
+    }
+}
+
+/// Ask `chat_id` what to do with an intent that fell under the alignment
+/// threshold. Sent through the outbox like any other outbound message.
+pub fn approval_request(chat_id: i64, intent: &Intent, now: DateTime<Utc>) -> OutboxMessage {
+    let text = format!(
+        "🤔 Low alignment ({:.2}), deferred: {}\nApprove to process it anyway?",
+        intent.telos_alignment, intent.summary
+    );
+    let keyboard = json!({
+        "inline_keyboard": [[
+            {"text": "Approve", "callback_data": ApprovalDecision::Approve.callback_data(intent.id)},
+            {"text": "Defer", "callback_data": ApprovalDecision::Defer.callback_data(intent.id)},
+            {"text": "Discard", "callback_data": ApprovalDecision::Discard.callback_data(intent.id)},
+        ]]
+    });
+    let mut message = OutboxMessage::new("telegram", chat_id, &text, now);
+    message.reply_markup = Some(keyboard);
+    message
+}
+
+/// Ask `chat_id` to approve or reject an intent held in
+/// `intent/pending_approval` by the `beat.approval` gate.
+pub fn hold_request(chat_id: i64, intent: &Intent, now: DateTime<Utc>) -> OutboxMessage {
+    let estimates: Vec<String> = intent
+        .cost_estimate()
+        .map(|cost| format!("estimated cost {cost:.2}"))
+        .into_iter()
+        .chain(
+            intent
+                .estimated_tokens()
+                .map(|tokens| format!("~{tokens} tokens")),
+        )
+        .collect();
+    let cost = if estimates.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", estimates.join(", "))
+    };
+    let text = format!(
+        "🔐 Needs approval from {}{}: {}\nApprove to queue it?",
+        intent.source, cost, intent.summary
+    );
+    let keyboard = json!({
+        "inline_keyboard": [[
+            {"text": "Approve", "callback_data": ApprovalDecision::Approve.callback_data(intent.id)},
+            {"text": "Reject", "callback_data": ApprovalDecision::Discard.callback_data(intent.id)},
+        ]]
+    });
+    let mut message = OutboxMessage::new("telegram", chat_id, &text, now);
+    message.reply_markup = Some(keyboard);
+    message
+}
+
+/// Chat an intent came from, if it was created from a Telegram message.
+pub fn origin_chat_id(intent: &Intent) -> Option<i64> {
+    intent
+        .metadata
+        .get(CHAT_ID_KEY)
+        .and_then(|chat_id| chat_id.parse().ok())
+}
+
+/// Reply text for a processed intent, cut to Telegram's message limit.
+pub fn outcome_message(intent: &Intent, final_answer: &str) -> String {
+    let text = format!("✅ {}\n\n{}", intent.summary, final_answer.trim());
+    if text.chars().count() <= TELEGRAM_MESSAGE_MAX_CHARS {
+        return text;
+    }
+    text::truncate(&text, TELEGRAM_MESSAGE_MAX_CHARS)
+}
+
+/// Telegram messages: `getUpdates` long polling with `mode: polling`, or
+/// updates pushed to `/webhook/telegram` with `mode: webhook`. The last
+/// processed update id is persisted so a restart neither drops nor replays
+/// messages.
+#[derive(Debug, Default)]
+pub struct TelegramSource {
+    client: Client,
+}
+
+#[async_trait]
+impl IngestSource for TelegramSource {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn mode(&self, config: &AppConfig) -> Option<IngestMode> {
+        Some(match config.telegram.as_ref()?.mode {
+            // Each poll waits up to `poll_timeout_secs` for updates itself.
+            TelegramMode::Polling => IngestMode::Poll { interval_secs: 0 },
+            TelegramMode::Webhook => IngestMode::Webhook {
+                path: server::TELEGRAM_WEBHOOK_PATH,
+            },
+        })
+    }
+
+    fn routes(&self) -> Router<ServerState> {
+        server::telegram_webhook_routes()
+    }
+
+    async fn poll(&self, ctx: &AppContext) -> anyhow::Result<usize> {
+        let config = ctx.config();
+        let Some(telegram) = &config.telegram else {
+            return Ok(0);
+        };
+        let clock = ctx.clock();
+        let channels = Channels::of(ctx);
+        select! {
+            polled = poll_updates(
+                &self.client,
+                &config.data_dir,
+                telegram,
+                &channels,
+                ctx.telegram_updates(),
+                clock.as_ref(),
+            ) => polled,
+            _ = ctx.wait_for_shutdown() => Ok(0),
+        }
+    }
+}
+
+/// One `getUpdates` long-poll starting after the last processed update.
+/// Every returned update goes through [`ingest_update`]. Returns the number of
+/// intents created or approved.
+pub async fn poll_updates(
+    client: &Client,
+    data_dir: &Path,
+    config: &TelegramConfig,
+    channels: &Channels,
+    in_flight: &InFlightUpdates,
+    clock: &dyn Clock,
+) -> anyhow::Result<usize> {
+    let state = storage::load_telegram_update_state(data_dir)?;
+    let base = config.api_base.trim_end_matches('/');
+    let url = format!("{}/bot{}/getUpdates", base, config.bot_token);
+
+    let mut request = json!({
+        "timeout": config.poll_timeout_secs,
+        "allowed_updates": ["message", "channel_post", "callback_query"],
+    });
+    if let Some(last) = state.last_update_id(bot_key(config)) {
+        request["offset"] = json!(last + 1);
+    }
+
+    let response = client
+        .post(url)
+        .timeout(Duration::from_secs(
+            config.poll_timeout_secs + TELEGRAM_POLL_MARGIN_SECS,
+        ))
+        .json(&request)
+        .send()
+        .await
+        // The bot token is part of the URL; keep it out of the error.
+        .map_err(reqwest::Error::without_url)
+        .with_context(|| "polling telegram updates")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("telegram returned status {}", response.status()));
+    }
+    let payload: GetUpdatesResponse = response
+        .json()
+        .await
+        .map_err(reqwest::Error::without_url)
+        .with_context(|| "decoding telegram updates")?;
+    if !payload.ok {
+        return Err(anyhow!(
+            "telegram getUpdates rejected: {}",
+            payload.description.unwrap_or_default()
+        ));
+    }
+
+    let mut created = 0;
+    for update in &payload.result {
+        if ingest_update(data_dir, config, channels, in_flight, update, clock.now())
+            .await
+            .needs_beat()
+        {
+            created += 1;
+        }
+    }
+    Ok(created)
+}
+
+#[derive(Debug, Deserialize)]
+struct GetUpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<TelegramUpdate>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// What happened to an inbound update, shared by the webhook and the
+/// long-polling task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelegramIngest {
+    Ignored,
+    /// The `update_id` was already processed (a Telegram retry or replay).
+    Duplicate,
+    /// The sender is not on the allowlist; the message was only logged.
+    Unauthorized,
+    /// The message was logged; `intent_id` is `None` when persisting the
+    /// intent failed.
+    Queued {
+        intent_id: Option<Uuid>,
+    },
+    /// The message answered the open question of a paused run.
+    Answered {
+        intent_id: Uuid,
+    },
+    /// An inline keyboard button on an approval request was pressed.
+    Resolved {
+        intent_id: Uuid,
+        decision: ApprovalDecision,
+    },
+}
+
+impl TelegramIngest {
+    /// Whether the orchestrator has new work: a fresh intent, an answer or an
+    /// approval.
+    pub fn needs_beat(self) -> bool {
+        matches!(
+            self,
+            Self::Queued { intent_id: Some(_) }
+                | Self::Answered { .. }
+                | Self::Resolved {
+                    decision: ApprovalDecision::Approve,
+                    ..
+                }
+        )
+    }
+}
+
+/// Numeric bot id, the part of the token before `:`.
+fn bot_key(config: &TelegramConfig) -> &str {
+    config
+        .bot_token
+        .split_once(':')
+        .map_or(config.bot_token.as_str(), |(id, _)| id)
+}
+
+/// Update ids a workspace is applying right now, per bot. Claimed under the
+/// same lock as the check against the persisted state, so a retry that
+/// arrives while its original is still being applied is a duplicate, but the
+/// lock itself is never held across [`apply_update`].
+#[derive(Debug, Clone, Default)]
+pub struct InFlightUpdates(Arc<Mutex<BTreeSet<(String, i64)>>>);
+
+/// Apply an update once: updates whose `update_id` was already processed for
+/// this bot, or is being processed, are reported as
+/// [`TelegramIngest::Duplicate`] and skipped.
+pub async fn ingest_update(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    channels: &Channels,
+    in_flight: &InFlightUpdates,
+    update: &TelegramUpdate,
+    now: DateTime<Utc>,
+) -> TelegramIngest {
+    let Some(update_id) = update.update_id else {
+        return apply_update(data_dir, config, channels, update, now).await;
+    };
+
+    let bot = bot_key(config);
+    if !claim_update(data_dir, in_flight, bot, update_id) {
+        return TelegramIngest::Duplicate;
+    }
+    let ingested = apply_update(data_dir, config, channels, update, now).await;
+    finish_update(data_dir, in_flight, bot, update_id);
+    ingested
+}
+
+/// Mark `update_id` in flight unless it was already processed or claimed.
+fn claim_update(data_dir: &Path, in_flight: &InFlightUpdates, bot: &str, update_id: i64) -> bool {
+    let mut in_flight = in_flight.0.lock();
+    if in_flight.contains(&(bot.to_string(), update_id)) {
+        return false;
+    }
+    match storage::load_telegram_update_state(data_dir) {
+        Ok(state) if state.is_processed(bot, update_id) => return false,
+        Ok(_) => {}
+        Err(err) => warn!(error = ?err, "failed to load telegram update state, skipping dedup"),
+    }
+    in_flight.insert((bot.to_string(), update_id));
+    true
+}
+
+/// Record `update_id` as processed once it has been applied. A crash before
+/// this leaves it unrecorded, so Telegram's redelivery is applied again.
+fn finish_update(data_dir: &Path, in_flight: &InFlightUpdates, bot: &str, update_id: i64) {
+    let mut in_flight = in_flight.0.lock();
+    in_flight.remove(&(bot.to_string(), update_id));
+    let saved = storage::load_telegram_update_state(data_dir).and_then(|mut state| {
+        state.record(bot, update_id);
+        storage::save_telegram_update_state(data_dir, &state)
+    });
+    if let Err(err) = saved {
+        warn!(error = ?err, update_id, "failed to persist telegram update id");
+    }
+}
+
+/// Log an inbound text message and turn it into an inbox intent, or apply an
+/// approval decision from a `callback_query`. Anything else is ignored.
+async fn apply_update(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    channels: &Channels,
+    update: &TelegramUpdate,
+    now: DateTime<Utc>,
+) -> TelegramIngest {
+    if let Some(query) = &update.callback_query {
+        return handle_callback_query(data_dir, config, query, now).await;
+    }
+
+    let Some(message) = update.primary_message() else {
+        return TelegramIngest::Ignored;
+    };
+
+    let Some(text) = message
+        .text
+        .as_ref()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+    else {
+        return TelegramIngest::Ignored;
+    };
+
+    let timestamp = DateTime::<Utc>::from_timestamp(message.date, 0).unwrap_or(now);
+
+    let author = message.from.as_ref().and_then(|from| {
+        if let Some(username) = from.username.clone() {
+            Some(username)
+        } else {
+            let mut name = String::new();
+            if let Some(first) = &from.first_name {
+                name.push_str(first);
+            }
+            if let Some(last) = &from.last_name {
+                if !name.is_empty() {
+                    name.push(' ');
+                }
+                name.push_str(last);
+            }
+            if name.is_empty() { None } else { Some(name) }
+        }
+    });
+
+    let username = message
+        .from
+        .as_ref()
+        .and_then(|from| from.username.as_deref());
+    let authorized = config.is_authorized(message.chat.id, username);
+
+    let mut metadata = json!({ "message_id": message.message_id });
+    if !authorized {
+        metadata["unauthorized"] = json!(true);
+    }
+    let log_entry = MessageLogEntry {
+        id: Uuid::new_v4(),
+        direction: MessageDirection::Inbound,
+        source: "telegram".to_string(),
+        chat_id: message.chat.id.to_string(),
+        author: author.clone(),
+        text: text.to_string(),
+        timestamp,
+        metadata: Some(metadata),
+    };
+
+    if let Err(err) = storage::append_message_entry(data_dir, &log_entry).await {
+        warn!(error = ?err, "failed to persist inbound telegram message");
+    }
+
+    if !authorized {
+        warn!(
+            chat_id = message.chat.id,
+            username = ?username,
+            "rejected telegram message from chat outside the allowlist"
+        );
+        if let Some(reply) = &config.unauthorized_reply
+            && let Err(err) =
+                outbox::send_or_queue(data_dir, channels, message.chat.id, reply, false, now).await
+        {
+            warn!(error = ?err, "failed to queue telegram unauthorized reply");
+        }
+        return TelegramIngest::Unauthorized;
+    }
+
+    // A chat with an open `ask_user` question is answering it, not asking anew.
+    let chat_id = message.chat.id.to_string();
+    match storage::answer_chat_question(data_dir, "telegram", &chat_id, text, now) {
+        Ok(Some(pending)) => {
+            return TelegramIngest::Answered {
+                intent_id: pending.intent_id,
+            };
+        }
+        Ok(None) => {}
+        Err(err) => warn!(error = ?err, "failed to check pending telegram questions"),
+    }
+
+    let summary = text::truncate(text, 80);
+
+    let body = format!(
+        "Telegram chat: {}
+Author: {}
+Message ID: {}
+
+{}",
+        message.chat.id,
+        author.clone().unwrap_or_else(|| "unknown".to_string()),
+        message.message_id,
+        text
+    );
+
+    let intent_result = storage::persist_intent(
+        data_dir,
+        &IntentDraft {
+            source: "telegram".to_string(),
+            summary,
+            telos_alignment: 1.0,
+            body,
+            due_at: None,
+            metadata: BTreeMap::from([
+                (CHAT_ID_KEY.to_string(), message.chat.id.to_string()),
+                (MESSAGE_ID_KEY.to_string(), message.message_id.to_string()),
+            ]),
+        },
+    )
+    .await;
+
+    let intent_id = match intent_result {
+        Ok(record) => Some(record.id),
+        Err(err) => {
+            warn!(error = ?err, "failed to persist intent from telegram message");
+            None
+        }
+    };
+
+    TelegramIngest::Queued { intent_id }
+}
+
+async fn handle_callback_query(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    query: &TelegramCallbackQuery,
+    now: DateTime<Utc>,
+) -> TelegramIngest {
+    let Some((decision, intent_id)) = query
+        .data
+        .as_deref()
+        .and_then(ApprovalDecision::from_callback_data)
+    else {
+        return TelegramIngest::Ignored;
+    };
+
+    // Deciding is held to the same allowlist as creating intents.
+    let username = query
+        .from
+        .as_ref()
+        .and_then(|from| from.username.as_deref());
+    let chat_id = query.message.as_ref().map(|message| message.chat.id);
+    if !chat_id.is_some_and(|chat_id| config.is_authorized(chat_id, username)) {
+        warn!(
+            chat_id = ?chat_id,
+            username = ?username,
+            %intent_id,
+            "rejected telegram approval decision from sender outside the allowlist"
+        );
+        if let Some(reply) = &config.unauthorized_reply
+            && let Err(err) = call_api(
+                config,
+                "answerCallbackQuery",
+                &json!({"callback_query_id": query.id, "text": reply}),
+            )
+            .await
+        {
+            warn!(error = ?err, "failed to answer telegram callback query");
+        }
+        return TelegramIngest::Unauthorized;
+    }
+
+    let (reply, applied) = match apply_decision(data_dir, config, query, decision, intent_id, now) {
+        Ok(Some(summary)) => (
+            format!("{}: {}", capitalize(decision.past_tense()), summary),
+            true,
+        ),
+        Ok(None) => (
+            "This intent is no longer waiting for approval.".to_string(),
+            false,
+        ),
+        Err(err) => {
+            warn!(error = ?err, %intent_id, "failed to apply telegram approval decision");
+            (
+                "Could not apply the decision, please try again.".to_string(),
+                false,
+            )
+        }
+    };
+
+    if let Err(err) = call_api(
+        config,
+        "answerCallbackQuery",
+        &json!({"callback_query_id": query.id, "text": reply}),
+    )
+    .await
+    {
+        warn!(error = ?err, "failed to answer telegram callback query");
+    }
+    if applied && let Some(message) = &query.message {
+        // Replace the prompt so the buttons cannot be pressed twice.
+        if let Err(err) = call_api(
+            config,
+            "editMessageText",
+            &json!({
+                "chat_id": message.chat.id,
+                "message_id": message.message_id,
+                "text": reply,
+            }),
+        )
+        .await
+        {
+            warn!(error = ?err, "failed to update telegram approval message");
+        }
+    }
+
+    if applied {
+        TelegramIngest::Resolved {
+            intent_id,
+            decision,
+        }
+    } else {
+        TelegramIngest::Ignored
+    }
+}
+
+/// Move the deferred or held intent according to `decision`. Returns its
+/// summary, or `None` when it is no longer waiting or the button was pressed
+/// in a chat other than the one the intent came from (held intents may also
+/// be decided in the default chat).
+fn apply_decision(
+    data_dir: &Path,
+    config: &TelegramConfig,
+    query: &TelegramCallbackQuery,
+    decision: ApprovalDecision,
+    intent_id: Uuid,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<String>> {
+    let Some(chat_id) = query.message.as_ref().map(|message| message.chat.id) else {
+        return Ok(None);
+    };
+    let record = match storage::find_deferred_intent(data_dir, intent_id)? {
+        Some(record) if origin_chat_id(&record.intent) == Some(chat_id) => record,
+        Some(_) => return Ok(None),
+        None => match storage::find_pending_approval_intent(data_dir, intent_id)? {
+            Some(record)
+                if origin_chat_id(&record.intent) == Some(chat_id)
+                    || config.default_chat_id == Some(chat_id) =>
+            {
+                record
+            }
+            _ => return Ok(None),
+        },
+    };
+
+    match decision {
+        ApprovalDecision::Approve => {
+            storage::approve_intent(&record.path, data_dir, now)?;
+        }
+        ApprovalDecision::Defer => {}
+        ApprovalDecision::Discard => {
+            storage::discard_intent(&record.path, data_dir, now)?;
+        }
+    }
+    Ok(Some(record.intent.summary))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramUpdate {
+    #[serde(default)]
+    pub update_id: Option<i64>,
+    #[serde(default)]
+    pub message: Option<TelegramMessage>,
+    #[serde(default)]
+    pub channel_post: Option<TelegramMessage>,
+    #[serde(default)]
+    pub callback_query: Option<TelegramCallbackQuery>,
+}
+
+/// Sent when an inline keyboard button is pressed; `message` is the prompt
+/// that carried the keyboard and `from` who pressed it.
+#[derive(Debug, Deserialize)]
+pub struct TelegramCallbackQuery {
+    pub id: String,
+    #[serde(default)]
+    pub from: Option<TelegramUser>,
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub message: Option<TelegramMessage>,
+}
+
+impl TelegramUpdate {
+    pub fn primary_message(&self) -> Option<&TelegramMessage> {
+        self.message.as_ref().or(self.channel_post.as_ref())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramMessage {
+    pub message_id: i64,
+    pub date: i64,
+    #[serde(default)]
+    pub text: Option<String>,
+    pub chat: TelegramChat,
+    #[serde(default)]
+    pub from: Option<TelegramUser>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+    #[serde(default, rename = "title")]
+    _title: Option<String>,
+    #[serde(default, rename = "username")]
+    _username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramUser {
+    #[serde(default, rename = "id")]
+    _id: i64,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub first_name: Option<String>,
+    #[serde(default)]
+    pub last_name: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use tempfile::TempDir;
+
+    use crate::{
+        agent::{ASK_USER_ACTION, AgentStep},
+        clock::SystemClock,
+    };
+
+    fn config(api_base: String) -> TelegramConfig {
+        TelegramConfig {
+            bot_token: "TEST".to_string(),
+            bot_token_file: None,
+            bot_token_command: None,
+            default_chat_id: None,
+            webhook_secret: None,
+            public_url: None,
+            api_base,
+            mode: TelegramMode::Polling,
+            poll_timeout_secs: 0,
+            allowed_chat_ids: Vec::new(),
+            allowed_usernames: Vec::new(),
+            unauthorized_reply: None,
+            quiet_hours: None,
+        }
+    }
+
+    fn channels(config: &TelegramConfig) -> Channels {
+        Channels {
+            telegram: Some(config.clone()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn request_errors_leave_the_bot_token_out() {
+        let temp = TempDir::new().expect("tempdir");
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("free port")
+            .port();
+        let config = config(format!("http://127.0.0.1:{port}"));
+
+        let err = send_message(&config, 1, "hi").await.err().expect("refused");
+        assert!(!format!("{err:#}").contains("botTEST"));
+
+        let err = poll_updates(
+            &Client::new(),
+            temp.path(),
+            &config,
+            &channels(&config),
+            &InFlightUpdates::default(),
+            &SystemClock,
+        )
+        .await
+        .expect_err("refused");
+        assert!(!format!("{err:#}").contains("botTEST"));
+    }
+
+    #[tokio::test]
+    async fn polling_ingests_updates_and_persists_offset() {
+        let server = MockServer::start_async().await;
+        let first = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/botTEST/getUpdates")
+                    .json_body(json!({
+                        "timeout": 0,
+                        "allowed_updates": ["message", "channel_post", "callback_query"],
+                    }));
+                then.status(200).json_body(json!({
+                    "ok": true,
+                    "result": [
+                        {
+                            "update_id": 41,
+                            "message": {
+                                "message_id": 7,
+                                "date": 1_736_000_000,
+                                "text": "Plan the offsite",
+                                "chat": {"id": 99},
+                                "from": {"id": 1, "username": "alice"}
+                            }
+                        },
+                        {
+                            "update_id": 42,
+                            "message": {
+                                "message_id": 8,
+                                "date": 1_736_000_001,
+                                "chat": {"id": 99}
+                            }
+                        }
+                    ]
+                }));
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = config(server.base_url());
+        let client = Client::new();
+
+        let created = poll_updates(
+            &client,
+            data_dir,
+            &config,
+            &channels(&config),
+            &InFlightUpdates::default(),
+            &SystemClock,
+        )
+        .await
+        .unwrap();
+        first.assert_async().await;
+        assert_eq!(created, 1);
+        assert_eq!(
+            storage::load_telegram_update_state(data_dir)
+                .unwrap()
+                .last_update_id("TEST"),
+            Some(42)
+        );
+        let inbox = storage::scan_inbox(data_dir).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].intent.summary, "Plan the offsite");
+        assert_eq!(origin_chat_id(&inbox[0].intent), Some(99));
+
+        first.delete_async().await;
+        let second = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/botTEST/getUpdates")
+                    .json_body_partial(r#"{"offset": 43}"#);
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": []}));
+            })
+            .await;
+        assert_eq!(
+            poll_updates(
+                &client,
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &SystemClock
+            )
+            .await
+            .unwrap(),
+            0
+        );
+        second.assert_async().await;
+    }
+
+    #[test]
+    fn in_flight_updates_are_claimed_per_workspace() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let in_flight = InFlightUpdates::default();
+
+        assert!(claim_update(first.path(), &in_flight, "1", 10));
+        assert!(!claim_update(first.path(), &in_flight, "1", 10));
+        // Another workspace applying the same update id is not blocked.
+        assert!(claim_update(
+            second.path(),
+            &InFlightUpdates::default(),
+            "1",
+            10
+        ));
+
+        finish_update(first.path(), &in_flight, "1", 10);
+        assert!(!claim_update(first.path(), &in_flight, "1", 10));
+        assert!(claim_update(first.path(), &in_flight, "1", 11));
+    }
+
+    #[tokio::test]
+    async fn approval_callback_moves_deferred_intent() {
+        let server = MockServer::start_async().await;
+        let answer = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/botTEST/answerCallbackQuery");
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": true}));
+            })
+            .await;
+        let edit = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/botTEST/editMessageText")
+                    .json_body_partial(r#"{"chat_id": 99, "message_id": 5}"#);
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": {}}));
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = TelegramConfig {
+            allowed_usernames: vec!["alice".to_string()],
+            ..config(server.base_url())
+        };
+
+        let mut ids = Vec::new();
+        for summary in ["Maybe later", "Not needed"] {
+            let record = storage::persist_intent(
+                data_dir,
+                &IntentDraft {
+                    source: "telegram".to_string(),
+                    summary: summary.to_string(),
+                    telos_alignment: 0.1,
+                    body: "body".to_string(),
+                    due_at: None,
+                    metadata: BTreeMap::from([(CHAT_ID_KEY.to_string(), "99".to_string())]),
+                },
+            )
+            .await
+            .unwrap();
+            storage::defer_intent(&record.path, data_dir, Utc::now()).unwrap();
+            ids.push(record.id);
+        }
+
+        let update_ids = std::sync::atomic::AtomicI64::new(1);
+        let callback_from =
+            |decision: ApprovalDecision, intent_id: Uuid, chat_id: i64, username: &str| {
+                let update_id = update_ids.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                serde_json::from_value::<TelegramUpdate>(json!({
+                    "update_id": update_id,
+                    "callback_query": {
+                        "id": "cb-1",
+                        "from": {"id": 1, "username": username},
+                        "data": decision.callback_data(intent_id),
+                        "message": {"message_id": 5, "date": 0, "chat": {"id": chat_id}}
+                    }
+                }))
+                .unwrap()
+            };
+        let callback =
+            |decision, intent_id, chat_id| callback_from(decision, intent_id, chat_id, "Alice");
+
+        // Senders outside the allowlist cannot decide, even in the right chat.
+        let mallory = callback_from(ApprovalDecision::Approve, ids[0], 99, "mallory");
+        assert_eq!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &mallory,
+                Utc::now()
+            )
+            .await,
+            TelegramIngest::Unauthorized
+        );
+        assert!(
+            storage::find_deferred_intent(data_dir, ids[0])
+                .unwrap()
+                .is_some()
+        );
+
+        // Buttons pressed from another chat are ignored.
+        let foreign = callback(ApprovalDecision::Approve, ids[0], 1);
+        assert_eq!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &foreign,
+                Utc::now()
+            )
+            .await,
+            TelegramIngest::Ignored
+        );
+
+        let approve = callback(ApprovalDecision::Approve, ids[0], 99);
+        let ingested = ingest_update(
+            data_dir,
+            &config,
+            &channels(&config),
+            &InFlightUpdates::default(),
+            &approve,
+            Utc::now(),
+        )
+        .await;
+        assert!(ingested.needs_beat());
+        let inbox = storage::scan_inbox(data_dir).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert!(inbox[0].intent.is_approved());
+        assert!(
+            storage::find_deferred_intent(data_dir, ids[0])
+                .unwrap()
+                .is_none()
+        );
+
+        let discard = callback(ApprovalDecision::Discard, ids[1], 99);
+        assert_eq!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &discard,
+                Utc::now()
+            )
+            .await,
+            TelegramIngest::Resolved {
+                intent_id: ids[1],
+                decision: ApprovalDecision::Discard,
+            }
+        );
+        assert!(
+            storage::find_deferred_intent(data_dir, ids[1])
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            std::fs::read_dir(data_dir.join("intent/inbox/discarded"))
+                .unwrap()
+                .count(),
+            1
+        );
+
+        answer.assert_hits_async(3).await;
+        edit.assert_hits_async(2).await;
+    }
+
+    #[tokio::test]
+    async fn allowlist_rejects_unknown_senders_with_audit_entry() {
+        let server = MockServer::start_async().await;
+        let reply = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/botTEST/sendMessage")
+                    .json_body_partial(r#"{"chat_id": 7, "text": "Not allowed."}"#);
+                then.status(200)
+                    .json_body(json!({"ok": true, "result": {"message_id": 1}}));
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = TelegramConfig {
+            allowed_chat_ids: vec![99],
+            allowed_usernames: vec!["@Alice".to_string()],
+            unauthorized_reply: Some("Not allowed.".to_string()),
+            ..config(server.base_url())
+        };
+        let message = |update_id: i64, chat_id: i64, username: &str| {
+            serde_json::from_value::<TelegramUpdate>(json!({
+                "update_id": update_id,
+                "message": {
+                    "message_id": update_id,
+                    "date": 1_736_000_000,
+                    "text": "Queue this",
+                    "chat": {"id": chat_id},
+                    "from": {"id": 1, "username": username}
+                }
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &message(1, 7, "mallory"),
+                Utc::now()
+            )
+            .await,
+            TelegramIngest::Unauthorized
+        );
+        reply.assert_async().await;
+        // The reply is sent through the outbox, so a failed send is retried.
+        let replies =
+            storage::list_outbox_messages(data_dir, Some(storage::OutboxStatus::Delivered), 5)
+                .unwrap();
+        assert_eq!(replies.len(), 1);
+        assert!(storage::scan_inbox(data_dir).unwrap().is_empty());
+        let logged = storage::read_messages(
+            data_dir,
+            storage::MessageLogQuery {
+                source: Some("telegram".to_string()),
+                direction: Some(MessageDirection::Inbound),
+                limit: 5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(
+            logged[0].metadata.as_ref().unwrap()["unauthorized"],
+            json!(true)
+        );
+
+        assert!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &message(2, 99, "bob"),
+                Utc::now()
+            )
+            .await
+            .needs_beat()
+        );
+        assert!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &message(3, 7, "alice"),
+                Utc::now()
+            )
+            .await
+            .needs_beat()
+        );
+        assert_eq!(storage::scan_inbox(data_dir).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reply_answers_pending_question_instead_of_queueing() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        storage::ensure_data_layout(data_dir).unwrap();
+        let config = config("http://127.0.0.1:9".to_string());
+        let message = |update_id: i64, text: &str| {
+            serde_json::from_value::<TelegramUpdate>(json!({
+                "update_id": update_id,
+                "message": {
+                    "message_id": update_id,
+                    "date": 1_736_000_000,
+                    "text": text,
+                    "chat": {"id": 42}
+                }
+            }))
+            .unwrap()
+        };
+
+        let TelegramIngest::Queued {
+            intent_id: Some(intent_id),
+        } = ingest_update(
+            data_dir,
+            &config,
+            &channels(&config),
+            &InFlightUpdates::default(),
+            &message(1, "Book a server"),
+            Utc::now(),
+        )
+        .await
+        else {
+            panic!("first message should queue an intent");
+        };
+        let record = storage::scan_inbox(data_dir).unwrap().remove(0);
+        let queued = storage::promote_to_queue(&record.path, data_dir, Utc::now()).unwrap();
+        let pending = storage::PendingQuestion {
+            intent_id,
+            run_id: Uuid::new_v4(),
+            summary: record.intent.summary.clone(),
+            question: "Which region?".to_string(),
+            steps: vec![AgentStep {
+                thought: "Region is unclear".to_string(),
+                action: ASK_USER_ACTION.to_string(),
+                observation: String::new(),
+                question: Some("Which region?".to_string()),
+                input: None,
+                sources: Vec::new(),
+            }],
+            source: "telegram".to_string(),
+            chat_id: Some("42".to_string()),
+            asked_at: Utc::now(),
+            answer: None,
+            answered_at: None,
+        };
+        storage::park_intent_for_question(&queued, data_dir, &pending, Utc::now()).unwrap();
+        assert!(storage::scan_queue(data_dir).unwrap().is_empty());
+
+        let answered = ingest_update(
+            data_dir,
+            &config,
+            &channels(&config),
+            &InFlightUpdates::default(),
+            &message(2, "eu-west"),
+            Utc::now(),
+        )
+        .await;
+        assert_eq!(answered, TelegramIngest::Answered { intent_id });
+        assert!(answered.needs_beat());
+        assert!(storage::scan_inbox(data_dir).unwrap().is_empty());
+
+        let resumed = storage::load_pending_question(data_dir, intent_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            resumed.resumed_steps()[0].observation,
+            "User answered: eu-west"
+        );
+        let requeued = storage::requeue_answered_intent(data_dir, intent_id, Utc::now())
+            .unwrap()
+            .expect("waiting intent moves back to the queue");
+        assert_eq!(requeued.intent.id, intent_id);
+        assert_eq!(storage::scan_queue(data_dir).unwrap().len(), 1);
+
+        // Once answered, the next message is a new request again.
+        assert!(matches!(
+            ingest_update(
+                data_dir,
+                &config,
+                &channels(&config),
+                &InFlightUpdates::default(),
+                &message(3, "Thanks"),
+                Utc::now()
+            )
+            .await,
+            TelegramIngest::Queued { intent_id: Some(_) }
+        ));
+    }
+
+    #[test]
+    fn outcome_message_fits_telegram_limit() {
+        let intent = Intent {
+            id: Uuid::new_v4(),
+            source: "telegram".to_string(),
+            summary: "Plan the offsite".to_string(),
+            telos_alignment: 1.0,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: BTreeMap::new(),
+            storage_path: None,
+        };
+        assert_eq!(
+            outcome_message(&intent, "Book the venue\n"),
+            "✅ Plan the offsite\n\nBook the venue"
+        );
+        let long = outcome_message(&intent, &"x".repeat(5_000));
+        assert_eq!(long.chars().count(), TELEGRAM_MESSAGE_MAX_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
@@ -0,0 +1,58 @@
+//! Cutting text for summaries and previews without splitting a character or
+//! a grapheme cluster, such as an emoji with modifiers or a letter with
+//! combining marks. Budgets count `char`s and include the `…` that marks a
+//! cut, so a result never exceeds a limit measured in characters
+//! (Telegram's, a notification channel's).
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The longest prefix of `text` with at most `max` chars that ends between
+/// two grapheme clusters.
+pub fn prefix(text: &str, max: usize) -> &str {
+    let Some((limit, _)) = text.char_indices().nth(max) else {
+        return text;
+    };
+    let end = text
+        .grapheme_indices(true)
+        .map(|(start, grapheme)| start + grapheme.len())
+        .take_while(|&end| end <= limit)
+        .last()
+        .unwrap_or(0);
+    &text[..end]
+}
+
+/// `text` if it has at most `max` chars, else its [`prefix`] of `max - 1`
+/// chars followed by `…`.
+pub fn truncate(text: &str, max: usize) -> String {
+    if !is_truncated(text, max) {
+        return text.to_string();
+    }
+    format!("{}…", prefix(text, max.saturating_sub(1)))
+}
+
+/// Whether [`truncate`] cuts `text` at `max`.
+pub fn is_truncated(text: &str, max: usize) -> bool {
+    text.chars().nth(max).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_on_grapheme_boundaries_within_the_char_budget() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly", 7), "exactly");
+        assert_eq!(truncate("把意图写进日志里", 4), "把意图…");
+        assert!(is_truncated("把意图写进日志里", 4));
+        assert!(!is_truncated("exactly", 7));
+        // "é" as e + U+0301 is one grapheme of two chars.
+        assert_eq!(prefix("cafe\u{301}!", 4), "caf");
+        assert_eq!(prefix("cafe\u{301}!", 5), "cafe\u{301}");
+        // A family emoji is five chars joined by zero-width joiners.
+        let family = "👨\u{200d}👩\u{200d}👧";
+        assert_eq!(truncate(&format!("hi {family}"), 7), "hi …");
+        assert_eq!(truncate(family, 1), "…");
+        assert_eq!(prefix("", 0), "");
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fmt::Write, path::PathBuf, process::Stdio, time::Duration};
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use tokio::{process::Command, time::timeout};
+
+use super::{Tool, ToolOutput};
+use crate::config::RunCommandConfig;
+
+/// Environment variables passed through to commands; everything else,
+/// API keys included, is dropped.
+const COMMAND_ENV_PASSTHROUGH: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "TZ"];
+
+/// `run_command`: run an allowlisted program, without a shell, and return
+/// its exit status and output.
+pub struct RunCommandTool {
+    config: RunCommandConfig,
+    working_dir: PathBuf,
+    description: String,
+}
+
+impl RunCommandTool {
+    /// `working_dir` is the config's, already resolved against the app root.
+    pub fn new(config: RunCommandConfig, working_dir: PathBuf) -> Self {
+        let description = format!(
+            "run a command line (no shell, no pipes); input is the command, one of: {}",
+            config.allowed_commands.join(", ")
+        );
+        Self {
+            config,
+            working_dir,
+            description,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RunCommandTool {
+    fn name(&self) -> &str {
+        "run_command"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    async fn call(&self, input: &str) -> anyhow::Result<ToolOutput> {
+        let words = shlex::split(input.trim())
+            .with_context(|| format!("unbalanced quotes in command {input:?}"))?;
+        let Some((program, args)) = words.split_first() else {
+            bail!("run_command needs a command");
+        };
+        if !self
+            .config
+            .allowed_commands
+            .iter()
+            .any(|allowed| allowed.trim() == program)
+        {
+            bail!(
+                "{program} is not an allowed command (allowed: {})",
+                self.config.allowed_commands.join(", ")
+            );
+        }
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .current_dir(&self.working_dir)
+            .env_clear()
+            .envs(
+                COMMAND_ENV_PASSTHROUGH
+                    .iter()
+                    .filter_map(|key| std::env::var_os(key).map(|value| (key, value))),
+            )
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        let limit = Duration::from_secs(self.config.timeout_secs.max(1));
+        let output = match timeout(limit, command.output()).await {
+            Ok(output) => output.with_context(|| format!("running {program}"))?,
+            Err(_) => bail!("{program} timed out after {}s", limit.as_secs()),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let status = match output.status.code() {
+            Some(code) => format!("exit code {code}"),
+            None => "killed by signal".to_string(),
+        };
+        let render = |max_bytes: Option<usize>| {
+            let mut text = format!("$ {}\n{status}\n", input.trim());
+            for (name, stream) in [("stdout", &stdout), ("stderr", &stderr)] {
+                if stream.trim().is_empty() {
+                    continue;
+                }
+                let shown = match max_bytes {
+                    Some(max) if stream.len() > max => {
+                        let mut end = max;
+                        while !stream.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        format!("{}\n… ({} bytes cut)", &stream[..end], stream.len() - end)
+                    }
+                    _ => stream.trim_end().to_string(),
+                };
+                let _ = writeln!(text, "{name}:\n{shown}");
+            }
+            text.trim_end().to_string()
+        };
+
+        let observation = render(Some(self.config.max_output_bytes));
+        let transcript = render(None);
+        Ok(ToolOutput {
+            transcript: (transcript != observation).then_some(transcript),
+            observation,
+            sources: Vec::new(),
+            files: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn tool(dir: PathBuf, max_output_bytes: usize) -> RunCommandTool {
+        RunCommandTool::new(
+            RunCommandConfig {
+                allowed_commands: vec!["sh".to_string(), "pwd".to_string()],
+                working_dir: dir.clone(),
+                timeout_secs: 1,
+                max_output_bytes,
+            },
+            dir,
+        )
+    }
+
+    #[tokio::test]
+    async fn runs_allowed_commands_in_the_working_dir() {
+        let temp = tempdir().unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+
+        let output = tool(dir.clone(), 1024).call("pwd").await.unwrap();
+        assert_eq!(
+            output.observation,
+            format!("$ pwd\nexit code 0\nstdout:\n{}", dir.display())
+        );
+        assert_eq!(output.transcript, None);
+
+        let failed = tool(dir.clone(), 1024)
+            .call("sh -c 'echo oops >&2; exit 3'")
+            .await
+            .unwrap();
+        assert!(failed.observation.contains("exit code 3\nstderr:\noops"));
+
+        // Set by cargo for this process, but not passed through.
+        let env = tool(dir.clone(), 1024)
+            .call("sh -c 'echo ${CARGO_MANIFEST_DIR:-unset}'")
+            .await;
+        assert!(env.unwrap().observation.ends_with("stdout:\nunset"));
+
+        let long = tool(dir.clone(), 4)
+            .call("sh -c 'echo 0123456789'")
+            .await
+            .unwrap();
+        assert!(long.observation.contains("0123\n… (7 bytes cut)"));
+        assert!(long.transcript.unwrap().contains("0123456789"));
+
+        let denied = tool(dir.clone(), 1024).call("rm -rf /").await.unwrap_err();
+        assert!(denied.to_string().contains("not an allowed command"));
+        let slow = tool(dir, 1024).call("sh -c 'sleep 5'").await.unwrap_err();
+        assert!(slow.to_string().contains("timed out"));
+    }
+}
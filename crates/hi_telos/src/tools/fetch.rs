@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use reqwest::{Client, Url, header, redirect};
+
+use super::{Tool, ToolFile, ToolOutput};
+use crate::{config::FetchUrlConfig, feeds, text};
+
+/// Longest page text put into an observation.
+const FETCH_OBSERVATION_CHARS: usize = 8_000;
+const FETCH_MAX_REDIRECTS: usize = 5;
+
+/// `fetch_url`: GET a page on an allowed domain and return its text.
+pub struct FetchUrlTool {
+    config: FetchUrlConfig,
+    client: Client,
+}
+
+impl FetchUrlTool {
+    pub fn new(config: FetchUrlConfig) -> anyhow::Result<Self> {
+        let allowed = config.allowed_domains.clone();
+        // Redirects must stay on the allowlist too.
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= FETCH_MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if url_allowed(attempt.url(), &allowed) {
+                attempt.follow()
+            } else {
+                let denied = format!("redirect to {} is not allowed", attempt.url());
+                attempt.error(denied)
+            }
+        });
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .redirect(policy)
+            .build()
+            .context("building fetch_url client")?;
+        Ok(Self { config, client })
+    }
+}
+
+#[async_trait]
+impl Tool for FetchUrlTool {
+    fn name(&self) -> &str {
+        "fetch_url"
+    }
+
+    fn description(&self) -> &str {
+        "GET a web page; input is the http(s) URL"
+    }
+
+    async fn call(&self, input: &str) -> anyhow::Result<ToolOutput> {
+        let url = Url::parse(input.trim()).with_context(|| format!("invalid url {input:?}"))?;
+        if !url_allowed(&url, &self.config.allowed_domains) {
+            bail!("{url} is not on the fetch_url allowlist");
+        }
+
+        let mut response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("fetching {url}"))?;
+        let status = response.status();
+        let final_url = response.url().to_string();
+        let is_html = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("html"));
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("reading {url}"))?
+        {
+            let room = self.config.max_bytes.saturating_sub(body.len());
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        if !status.is_success() {
+            bail!("{url} returned status {status}");
+        }
+
+        let raw = String::from_utf8_lossy(&body);
+        let page = if is_html {
+            html_text(&raw)
+        } else {
+            raw.trim().to_string()
+        };
+        truncated |= text::is_truncated(&page, FETCH_OBSERVATION_CHARS);
+        let page = text::truncate(&page, FETCH_OBSERVATION_CHARS);
+        let cut = if truncated { ", truncated" } else { "" };
+        let file = ToolFile {
+            name: format!(
+                "{}.{}",
+                url.host_str().unwrap_or("page"),
+                if is_html { "html" } else { "txt" }
+            ),
+            content: body,
+        };
+        Ok(ToolOutput {
+            observation: format!("Fetched {final_url} ({status}{cut}):\n{page}"),
+            sources: vec![final_url],
+            transcript: None,
+            files: vec![file],
+        })
+    }
+}
+
+/// Only http(s) URLs whose host is an allowed domain or one of its
+/// subdomains.
+fn url_allowed(url: &Url, allowed_domains: &[String]) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    allowed_domains.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches("*.").to_ascii_lowercase();
+        !domain.is_empty()
+            && (host == domain
+                || host
+                    .strip_suffix(&domain)
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    })
+}
+
+/// Page text without markup, scripts or styles.
+fn html_text(html: &str) -> String {
+    let mut kept = String::with_capacity(html.len());
+    let mut rest = html;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        let next = ["<script", "<style"]
+            .iter()
+            .filter_map(|open| lower.find(open).map(|start| (start, &open[1..])))
+            .min_by_key(|(start, _)| *start);
+        let Some((start, tag)) = next else {
+            kept.push_str(rest);
+            break;
+        };
+        kept.push_str(&rest[..start]);
+        let close = format!("</{tag}>");
+        match lower[start..].find(&close) {
+            Some(end) => rest = &rest[start + end + close.len()..],
+            None => break,
+        }
+    }
+    feeds::strip_tags(&kept)
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+
+    fn tool(max_bytes: usize) -> FetchUrlTool {
+        FetchUrlTool::new(FetchUrlConfig {
+            allowed_domains: vec!["127.0.0.1".to_string()],
+            max_bytes,
+            timeout_secs: 5,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn allowlist_covers_subdomains_only() {
+        let allowed = vec!["example.com".to_string()];
+        let check = |url: &str| url_allowed(&Url::parse(url).unwrap(), &allowed);
+        assert!(check("https://example.com/a"));
+        assert!(check("http://docs.Example.com/a"));
+        assert!(!check("https://badexample.com/"));
+        assert!(!check("https://example.com.evil.net/"));
+        assert!(!check("ftp://example.com/file"));
+    }
+
+    #[tokio::test]
+    async fn fetches_page_text_within_limits() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/article");
+                then.status(200)
+                    .header("content-type", "text/html; charset=utf-8")
+                    .body("<html><head><style>p{}</style><script>var x = 1;</script></head><body><h1>Title</h1><p>Fish &amp; chips</p></body></html>");
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/moved");
+                then.status(302)
+                    .header("location", "https://elsewhere.example/article");
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/big");
+                then.status(200).body("x".repeat(100));
+            })
+            .await;
+
+        let output = tool(1024).call(&server.url("/article")).await.unwrap();
+        assert_eq!(output.sources, vec![server.url("/article")]);
+        assert!(output.observation.ends_with(":\nTitle Fish & chips"));
+        assert!(!output.observation.contains("var x"));
+
+        let big = tool(10).call(&server.url("/big")).await.unwrap();
+        assert!(big.observation.contains("truncated"));
+        assert!(big.observation.ends_with(&"x".repeat(10)));
+
+        assert!(tool(1024).call(&server.url("/moved")).await.is_err());
+        let denied = tool(1024).call("https://example.com/").await.unwrap_err();
+        assert!(denied.to_string().contains("allowlist"));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use anyhow::{Context, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::Mutex,
+    time::timeout,
+};
+use tracing::{info, warn};
+
+use super::{Tool, ToolOutput, ToolRegistry};
+use crate::config::{AppConfig, McpServerConfig};
+
+/// Protocol revision sent in `initialize`; servers answer with the one they
+/// speak, and the calls used here are the same in all of them.
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+/// Pages of `tools/list` read before giving up on a server.
+const MCP_MAX_TOOL_PAGES: usize = 20;
+
+/// Connect to every server in `tools.mcp_servers` and register its tools as
+/// `<server>.<tool>`. A server that fails to start is logged and skipped
+/// so one broken integration does not stop the process.
+pub async fn connect_mcp_servers(config: &AppConfig, registry: &mut ToolRegistry) {
+    let root = config.config_dir.parent().unwrap_or(&config.config_dir);
+    for (name, server) in &config.tools.mcp_servers {
+        let dir = server
+            .working_dir
+            .as_ref()
+            .map(|dir| root.join(dir))
+            .unwrap_or_else(|| root.to_path_buf());
+        match McpClient::connect(name, server, &dir).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                match client.list_tools().await {
+                    Ok(tools) => {
+                        info!(server = %name, tools = tools.len(), "connected MCP server");
+                        for tool in tools {
+                            registry.register(Arc::new(tool));
+                        }
+                    }
+                    Err(err) => warn!(server = %name, error = ?err, "listing MCP tools failed"),
+                }
+            }
+            Err(err) => warn!(server = %name, error = ?err, "starting MCP server failed"),
+        }
+    }
+}
+
+/// JSON-RPC over the stdin/stdout of an MCP server process. Requests are
+/// sent one at a time.
+pub struct McpClient {
+    name: String,
+    timeout: Duration,
+    io: Mutex<McpIo>,
+}
+
+struct McpIo {
+    next_id: u64,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    /// Held so the server is killed when the client is dropped.
+    _child: Child,
+}
+
+impl McpClient {
+    /// Start the server and run the `initialize` handshake.
+    pub async fn connect(
+        name: &str,
+        config: &McpServerConfig,
+        working_dir: &std::path::Path,
+    ) -> anyhow::Result<Self> {
+        let (program, args) = config
+            .command
+            .split_first()
+            .with_context(|| format!("MCP server {name} has an empty command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .envs(&config.env)
+            .current_dir(working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("spawning MCP server {name} ({program})"))?;
+        let stdin = child.stdin.take().context("MCP server stdin")?;
+        let stdout = child.stdout.take().context("MCP server stdout")?;
+        let client = Self {
+            name: name.to_string(),
+            timeout: Duration::from_secs(config.timeout_secs.max(1)),
+            io: Mutex::new(McpIo {
+                next_id: 1,
+                stdin,
+                stdout: BufReader::new(stdout).lines(),
+                _child: child,
+            }),
+        };
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "hi_telos", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await
+            .with_context(|| format!("initializing MCP server {name}"))?;
+        client
+            .notify("notifications/initialized")
+            .await
+            .with_context(|| format!("initializing MCP server {name}"))?;
+        Ok(client)
+    }
+
+    /// The server's tools, following `nextCursor` pages.
+    pub async fn list_tools(self: &Arc<Self>) -> anyhow::Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MCP_MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+            for tool in result
+                .get("tools")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let Some(remote_name) = tool.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                tools.push(McpTool::new(
+                    self.clone(),
+                    remote_name,
+                    tool.get("description")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default(),
+                    tool.get("inputSchema").cloned().unwrap_or(Value::Null),
+                ));
+            }
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(tools)
+    }
+
+    /// `tools/call`, returning the text content and whether the server
+    /// flagged it as an error.
+    pub async fn call_tool(&self, tool: &str, arguments: Value) -> anyhow::Result<(String, bool)> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": tool, "arguments": arguments }),
+            )
+            .await?;
+        let text = result
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|part| match part.get("type").and_then(Value::as_str) {
+                Some("text") => part
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                Some(other) => format!("[{other} content]"),
+                None => String::new(),
+            })
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let is_error = result
+            .get("isError")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        Ok((text, is_error))
+    }
+
+    async fn request(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let mut io = self.io.lock().await;
+        let id = io.next_id;
+        io.next_id += 1;
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        io.send(&message).await?;
+
+        let read = async {
+            loop {
+                let line = io
+                    .stdout
+                    .next_line()
+                    .await?
+                    .ok_or_else(|| anyhow!("MCP server {} closed its output", self.name))?;
+                // Skip notifications, server requests and stale replies.
+                let Ok(reply) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                if reply.get("id").and_then(Value::as_u64) != Some(id)
+                    || reply.get("method").is_some()
+                {
+                    continue;
+                }
+                if let Some(error) = reply.get("error") {
+                    bail!(
+                        "MCP server {} rejected {method}: {}",
+                        self.name,
+                        error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown error")
+                    );
+                }
+                return Ok(reply.get("result").cloned().unwrap_or(Value::Null));
+            }
+        };
+        match timeout(self.timeout, read).await {
+            Ok(result) => result,
+            Err(_) => bail!(
+                "MCP server {} did not answer {method} within {}s",
+                self.name,
+                self.timeout.as_secs()
+            ),
+        }
+    }
+
+    async fn notify(&self, method: &str) -> anyhow::Result<()> {
+        let mut io = self.io.lock().await;
+        io.send(&json!({ "jsonrpc": "2.0", "method": method }))
+            .await
+    }
+}
+
+impl McpIo {
+    async fn send(&mut self, message: &Value) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(message)?;
+        line.push(b'\n');
+        self.stdin
+            .write_all(&line)
+            .await
+            .context("writing to MCP server")?;
+        self.stdin.flush().await.context("writing to MCP server")
+    }
+}
+
+/// One tool of an MCP server, offered to the agent as `<server>.<tool>`.
+pub struct McpTool {
+    client: Arc<McpClient>,
+    remote_name: String,
+    name: String,
+    description: String,
+    /// The only required string argument, which a plain-text input fills.
+    text_argument: Option<String>,
+}
+
+impl McpTool {
+    fn new(client: Arc<McpClient>, remote_name: &str, description: &str, schema: Value) -> Self {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        let text_argument = match required.as_slice() {
+            [only]
+                if properties
+                    .and_then(|properties| properties.get(*only))
+                    .and_then(|property| property.get("type"))
+                    .and_then(Value::as_str)
+                    == Some("string") =>
+            {
+                Some(only.to_string())
+            }
+            _ => None,
+        };
+        let arguments = properties
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(key, property)| {
+                        let kind = property
+                            .get("type")
+                            .and_then(Value::as_str)
+                            .unwrap_or("any");
+                        let marker = if required.contains(&key.as_str()) {
+                            ""
+                        } else {
+                            "?"
+                        };
+                        format!("{key}{marker}: {kind}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        let summary = description.lines().next().unwrap_or_default().trim();
+        Self {
+            name: format!("{}.{remote_name}", client.name),
+            remote_name: remote_name.to_string(),
+            description: format!("{summary}; input is a JSON object {{{arguments}}}"),
+            text_argument,
+            client,
+        }
+    }
+
+    /// `input` as tool arguments: a JSON object, or plain text for a tool
+    /// with a single required string argument.
+    fn arguments(&self, input: &str) -> anyhow::Result<Value> {
+        let input = input.trim();
+        if let Ok(Value::Object(arguments)) = serde_json::from_str::<Value>(input) {
+            return Ok(Value::Object(arguments));
+        }
+        match &self.text_argument {
+            Some(key) => Ok(Value::Object(Map::from_iter([(
+                key.clone(),
+                Value::String(input.to_string()),
+            )]))),
+            None if input.is_empty() => Ok(json!({})),
+            None => bail!("{} needs a JSON object as input", self.name),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    async fn call(&self, input: &str) -> anyhow::Result<ToolOutput> {
+        let arguments = self.arguments(input)?;
+        let (text, is_error) = self.client.call_tool(&self.remote_name, arguments).await?;
+        if is_error {
+            bail!("{} reported an error: {text}", self.name);
+        }
+        Ok(ToolOutput {
+            observation: text,
+            ..ToolOutput::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Answers the handshake, lists one tool and echoes a fixed reply to
+    /// the first call, saving that request to `call.json`.
+    const FAKE_SERVER: &str = r#"
+read -r line
+printf '%s\n' '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"fake","version":"0"}}}'
+read -r line
+read -r line
+printf '%s\n' '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"lookup","description":"Look a term up\nMore detail","inputSchema":{"type":"object","properties":{"term":{"type":"string"},"limit":{"type":"integer"}},"required":["term"]}}]}}'
+read -r line
+printf '%s\n' "$line" > call.json
+printf '%s\n' '{"jsonrpc":"2.0","method":"notifications/message","params":{}}'
+printf '%s\n' '{"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"term found"}]}}'
+read -r line
+"#;
+
+    #[tokio::test]
+    async fn mcp_tools_are_listed_and_called_over_stdio() {
+        let temp = tempdir().unwrap();
+        let server = McpServerConfig {
+            command: vec!["sh".to_string(), "-c".to_string(), FAKE_SERVER.to_string()],
+            env: BTreeMap::new(),
+            working_dir: None,
+            timeout_secs: 5,
+        };
+        let client = Arc::new(
+            McpClient::connect("docs", &server, temp.path())
+                .await
+                .unwrap(),
+        );
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools.len(), 1);
+        let tool = &tools[0];
+        assert_eq!(tool.name(), "docs.lookup");
+        assert_eq!(
+            tool.description(),
+            "Look a term up; input is a JSON object {limit?: integer, term: string}"
+        );
+
+        let output = tool.call("rust").await.unwrap();
+        assert_eq!(output.observation, "term found");
+        let request: Value =
+            serde_json::from_str(&std::fs::read_to_string(temp.path().join("call.json")).unwrap())
+                .unwrap();
+        assert_eq!(request["method"], "tools/call");
+        assert_eq!(
+            request["params"],
+            json!({ "name": "lookup", "arguments": { "term": "rust" } })
+        );
+        assert_eq!(
+            tool.arguments(r#"{"term": "x", "limit": 2}"#).unwrap(),
+            json!({ "term": "x", "limit": 2 })
+        );
+    }
+}
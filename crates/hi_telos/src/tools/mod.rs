@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::AppConfig;
+
+mod command;
+mod fetch;
+mod mcp;
+mod search;
+
+pub use command::RunCommandTool;
+pub use fetch::FetchUrlTool;
+pub use mcp::{McpClient, McpTool, connect_mcp_servers};
+pub use search::{SearchResult, WebSearchTool};
+
+/// Something the agent can do instead of imagining an observation: a THINK
+/// step whose `action` names the tool has its `input` passed to
+/// [`Tool::call`], and the result becomes the step's observation.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn name(&self) -> &str;
+    /// One line for the prompt, saying what `input` should be.
+    fn description(&self) -> &str;
+    async fn call(&self, input: &str) -> anyhow::Result<ToolOutput>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolOutput {
+    pub observation: String,
+    /// URLs the observation came from, cited in the final answer.
+    pub sources: Vec<String>,
+    /// The full result for the run's logs, when `observation` is shortened.
+    pub transcript: Option<String>,
+    /// Kept with the run's artifacts under `data/runs/<run_id>/files/`.
+    pub files: Vec<ToolFile>,
+}
+
+/// A file a tool produced, such as a fetched page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolFile {
+    /// File name; anything path-like is flattened when it is saved.
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// The tools offered to the agent, in prompt order.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        if let Some(fetch) = &config.tools.fetch_url {
+            registry.register(Arc::new(FetchUrlTool::new(fetch.clone())?));
+        }
+        if let (Some(command), Some(dir)) = (&config.tools.run_command, config.run_command_dir()) {
+            registry.register(Arc::new(RunCommandTool::new(command.clone(), dir)));
+        }
+        if let Some(search) = &config.tools.web_search {
+            registry.register(Arc::new(WebSearchTool::new(search.clone())?));
+        }
+        Ok(registry)
+    }
+
+    /// Add `tool`, replacing any tool of the same name.
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.retain(|known| known.name() != tool.name());
+        self.tools.push(tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Tool>> {
+        self.tools.iter().find(|tool| tool.name() == name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Tool>> {
+        self.tools.iter()
+    }
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.tools.iter().map(|tool| tool.name()))
+            .finish()
+    }
+}
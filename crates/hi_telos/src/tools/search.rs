@@ -0,0 +1,267 @@
+use std::{env, fmt::Write, time::Duration};
+
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde_json::Value;
+
+use super::{Tool, ToolOutput};
+use crate::{
+    config::{SearchProvider, WebSearchConfig},
+    text,
+};
+
+const BRAVE_SEARCH_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+const BING_SEARCH_URL: &str = "https://api.bing.microsoft.com/v7.0/search";
+/// Longest snippet kept per result.
+const SEARCH_SNIPPET_CHARS: usize = 300;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// `web_search`: look a query up with the configured provider.
+pub struct WebSearchTool {
+    config: WebSearchConfig,
+    client: Client,
+}
+
+impl WebSearchTool {
+    pub fn new(config: WebSearchConfig) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .context("building web_search client")?;
+        Ok(Self { config, client })
+    }
+
+    /// Up to `max_results` results for `query`, in the provider's order.
+    pub async fn search(&self, query: &str) -> anyhow::Result<Vec<SearchResult>> {
+        let count = self.config.max_results.max(1);
+        let (request, results_path, fields) = match &self.config.provider {
+            SearchProvider::Searxng { base_url } => (
+                self.client
+                    .get(format!("{}/search", base_url.trim_end_matches('/')))
+                    .query(&[("q", query), ("format", "json")]),
+                &["results"][..],
+                ["title", "url", "content"],
+            ),
+            SearchProvider::Brave {
+                api_key_env,
+                base_url,
+            } => (
+                self.client
+                    .get(base_url.as_deref().unwrap_or(BRAVE_SEARCH_URL))
+                    .query(&[("q", query), ("count", &count.to_string())])
+                    .header("X-Subscription-Token", api_key(api_key_env)?),
+                &["web", "results"][..],
+                ["title", "url", "description"],
+            ),
+            SearchProvider::Bing {
+                api_key_env,
+                base_url,
+            } => (
+                self.client
+                    .get(base_url.as_deref().unwrap_or(BING_SEARCH_URL))
+                    .query(&[("q", query), ("count", &count.to_string())])
+                    .header("Ocp-Apim-Subscription-Key", api_key(api_key_env)?),
+                &["webPages", "value"][..],
+                ["name", "url", "snippet"],
+            ),
+        };
+
+        let payload = send(request).await?;
+        let items = results_path
+            .iter()
+            .try_fold(&payload, |value, key| value.get(key))
+            .and_then(Value::as_array);
+        let field = |item: &Value, key: &str| {
+            item.get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        Ok(items
+            .into_iter()
+            .flatten()
+            .map(|item| SearchResult {
+                title: field(item, fields[0]),
+                url: field(item, fields[1]),
+                snippet: field(item, fields[2]),
+            })
+            .filter(|result| !result.url.is_empty())
+            .take(count)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn description(&self) -> &str {
+        "search the web; input is the query"
+    }
+
+    async fn call(&self, input: &str) -> anyhow::Result<ToolOutput> {
+        let query = input.trim();
+        if query.is_empty() {
+            bail!("web_search needs a query");
+        }
+        let results = self.search(query).await?;
+        if results.is_empty() {
+            return Ok(ToolOutput {
+                observation: format!("No results for {query:?}"),
+                ..ToolOutput::default()
+            });
+        }
+
+        let mut observation = format!("Results for {query:?}:");
+        for (idx, result) in results.iter().enumerate() {
+            let snippet = text::truncate(&result.snippet, SEARCH_SNIPPET_CHARS);
+            let _ = write!(
+                observation,
+                "\n{}. {}\n   {}\n   {}",
+                idx + 1,
+                result.title,
+                result.url,
+                snippet
+            );
+        }
+        Ok(ToolOutput {
+            observation,
+            sources: results.into_iter().map(|result| result.url).collect(),
+            transcript: None,
+            files: Vec::new(),
+        })
+    }
+}
+
+fn api_key(api_key_env: &str) -> anyhow::Result<String> {
+    env::var(api_key_env).with_context(|| format!("reading {api_key_env}"))
+}
+
+async fn send(request: RequestBuilder) -> anyhow::Result<Value> {
+    let response = request.send().await.context("querying search provider")?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("search provider returned status {status}");
+    }
+    response
+        .json()
+        .await
+        .context("parsing search provider response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use serde_json::json;
+
+    fn tool(provider: SearchProvider) -> WebSearchTool {
+        WebSearchTool::new(WebSearchConfig {
+            provider,
+            max_results: 2,
+            timeout_secs: 5,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn providers_return_capped_results() {
+        let server = MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/search")
+                    .query_param("q", "rust async")
+                    .query_param("format", "json");
+                then.status(200).json_body(json!({ "results": [
+                    { "title": "Async Book", "url": "https://rust-lang.github.io/async-book/", "content": "Asynchronous programming in Rust" },
+                    { "title": "Tokio", "url": "https://tokio.rs", "content": "An async runtime" },
+                    { "title": "Third", "url": "https://example.com", "content": "Cut by max_results" },
+                ]}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/brave")
+                    .query_param("count", "2")
+                    .header("X-Subscription-Token", "brave-key");
+                then.status(200).json_body(json!({ "web": { "results": [
+                    { "title": "Brave hit", "url": "https://brave.example", "description": "From Brave" },
+                ]}}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/bing")
+                    .header("Ocp-Apim-Subscription-Key", "bing-key");
+                then.status(200).json_body(json!({ "webPages": { "value": [
+                    { "name": "Bing hit", "url": "https://bing.example", "snippet": "From Bing" },
+                ]}}));
+            })
+            .await;
+        unsafe {
+            env::set_var("HI_TEST_SEARCH_BRAVE_KEY", "brave-key");
+            env::set_var("HI_TEST_SEARCH_BING_KEY", "bing-key");
+        }
+
+        let searxng = tool(SearchProvider::Searxng {
+            base_url: server.base_url(),
+        });
+        let output = searxng.call("rust async").await.unwrap();
+        assert_eq!(
+            output.observation,
+            "Results for \"rust async\":\n1. Async Book\n   https://rust-lang.github.io/async-book/\n   Asynchronous programming in Rust\n2. Tokio\n   https://tokio.rs\n   An async runtime"
+        );
+        assert_eq!(
+            output.sources,
+            vec![
+                "https://rust-lang.github.io/async-book/",
+                "https://tokio.rs"
+            ]
+        );
+
+        let brave = tool(SearchProvider::Brave {
+            api_key_env: "HI_TEST_SEARCH_BRAVE_KEY".to_string(),
+            base_url: Some(server.url("/brave")),
+        });
+        assert_eq!(
+            brave.search("anything").await.unwrap(),
+            vec![SearchResult {
+                title: "Brave hit".to_string(),
+                url: "https://brave.example".to_string(),
+                snippet: "From Brave".to_string(),
+            }]
+        );
+
+        let bing = tool(SearchProvider::Bing {
+            api_key_env: "HI_TEST_SEARCH_BING_KEY".to_string(),
+            base_url: Some(server.url("/bing")),
+        });
+        let results = bing.search("anything").await.unwrap();
+        assert_eq!(results[0].title, "Bing hit");
+
+        let missing_key = tool(SearchProvider::Bing {
+            api_key_env: "HI_TEST_SEARCH_UNSET_KEY".to_string(),
+            base_url: Some(server.url("/bing")),
+        });
+        assert!(missing_key.call("anything").await.is_err());
+        assert!(searxng.call("  ").await.is_err());
+
+        unsafe {
+            env::remove_var("HI_TEST_SEARCH_BRAVE_KEY");
+            env::remove_var("HI_TEST_SEARCH_BING_KEY");
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{select, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{notifications, outbox::Channels, state::AppContext, storage};
+
+/// How often the watchdog compares the last beat against its threshold.
+const WATCHDOG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Liveness of the beat loop, as served by `/api/status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BeatHealth {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_beat_at: Option<DateTime<Utc>>,
+    pub interval_minutes: u64,
+    /// Gap after which the beat counts as missed.
+    pub threshold_seconds: u64,
+    /// Since the last beat, or since startup before the first one.
+    pub seconds_since_beat: i64,
+    pub overdue: bool,
+}
+
+/// A `server` role process reads the worker's last beat from the data dir.
+pub fn beat_health(ctx: &AppContext) -> BeatHealth {
+    let config = ctx.config();
+    let interval_minutes = config.beat.interval_minutes;
+    let threshold = config.beat.watchdog_threshold();
+    let last_beat_at = if config.server.role.runs_worker() {
+        ctx.last_beat()
+    } else {
+        storage::load_last_beat(&config.data_dir)
+    };
+    drop(config);
+
+    let since = last_beat_at.unwrap_or_else(|| ctx.started_at());
+    let seconds_since_beat = (ctx.now() - since).num_seconds().max(0);
+    let threshold_seconds = threshold.as_secs();
+    BeatHealth {
+        last_beat_at,
+        interval_minutes,
+        threshold_seconds,
+        seconds_since_beat,
+        overdue: seconds_since_beat as u64 > threshold_seconds,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeatAlertKind {
+    Missed,
+    Recovered,
+}
+
+impl BeatAlertKind {
+    /// Event name used for webhook deliveries.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BeatAlertKind::Missed => "beat_missed",
+            BeatAlertKind::Recovered => "beat_recovered",
+        }
+    }
+
+    /// Value of the webhook event header.
+    pub fn header(&self) -> &'static str {
+        match self {
+            BeatAlertKind::Missed => "beat.missed",
+            BeatAlertKind::Recovered => "beat.recovered",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BeatAlert {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub kind: BeatAlertKind,
+    #[serde(flatten)]
+    pub health: BeatHealth,
+}
+
+impl BeatAlert {
+    pub fn new(kind: BeatAlertKind, health: BeatHealth, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp,
+            kind,
+            health,
+        }
+    }
+
+    /// Plain-text body for chat channels.
+    pub fn render(&self) -> String {
+        let last_beat = self
+            .health
+            .last_beat_at
+            .map(|at| at.to_rfc3339())
+            .unwrap_or_else(|| "never".to_string());
+        match self.kind {
+            BeatAlertKind::Missed => format!(
+                "🚨 No beat for {} min (expected every {} min). Last beat: {last_beat}",
+                self.health.seconds_since_beat / 60,
+                self.health.interval_minutes
+            ),
+            BeatAlertKind::Recovered => format!("✅ Beats resumed. Last beat: {last_beat}"),
+        }
+    }
+}
+
+/// Announces each outage once, and its end.
+#[derive(Debug, Default)]
+pub struct BeatWatch {
+    alerted: bool,
+}
+
+impl BeatWatch {
+    pub fn check(&mut self, health: &BeatHealth) -> Option<BeatAlertKind> {
+        match (health.overdue, self.alerted) {
+            (true, false) => {
+                self.alerted = true;
+                Some(BeatAlertKind::Missed)
+            }
+            (false, true) => {
+                self.alerted = false;
+                Some(BeatAlertKind::Recovered)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check the beat loop every minute and alert `beat.watchdog.channels` when
+/// it stops. Channels are read per alert, so config reloads apply.
+pub fn spawn(ctx: AppContext) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut watch = BeatWatch::default();
+        loop {
+            select! {
+                _ = sleep(WATCHDOG_CHECK_INTERVAL) => {}
+                _ = ctx.wait_for_shutdown() => break,
+            }
+
+            let health = beat_health(&ctx);
+            let Some(kind) = watch.check(&health) else {
+                continue;
+            };
+            match kind {
+                BeatAlertKind::Missed => warn!(
+                    last_beat = ?health.last_beat_at,
+                    seconds = health.seconds_since_beat,
+                    "beat loop missed its deadline"
+                ),
+                BeatAlertKind::Recovered => {
+                    info!(last_beat = ?health.last_beat_at, "beats resumed")
+                }
+            }
+
+            let config = ctx.config();
+            let data_dir = config.data_dir.clone();
+            let channels = config.beat.watchdog.channels.clone();
+            drop(config);
+            let outbox = Channels::of(&ctx);
+
+            let alert = BeatAlert::new(kind, health, ctx.now());
+            for channel in &channels {
+                let sent = notifications::notify_beat_alert(
+                    &data_dir,
+                    &outbox,
+                    channel,
+                    &alert,
+                    alert.timestamp,
+                )
+                .await;
+                if let Err(err) = sent {
+                    warn!(error = ?err, "beat watchdog alert failed");
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use tempfile::tempdir;
+
+    use crate::{
+        agent::AgentRuntime, clock::ManualClock, config::AppConfig, fixtures, llm::LocalStubClient,
+    };
+
+    #[test]
+    fn missed_beats_alert_once_until_beats_resume() {
+        let temp = tempdir().unwrap();
+        let root = fixtures::install_core_fixture(temp.path()).unwrap();
+        let config = AppConfig::load_from(&root).unwrap();
+        let interval = config.beat.interval();
+        let missed_beats = config.beat.watchdog.missed_beats;
+        let agent = AgentRuntime::new(config.agent.clone(), Arc::new(LocalStubClient));
+        let start = "2025-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let ctx = AppContext::new(config, Arc::new(agent)).with_clock(clock.clone());
+        let mut watch = BeatWatch::default();
+
+        clock.advance(chrono::Duration::from_std(interval).unwrap());
+        let health = beat_health(&ctx);
+        assert!(!health.overdue);
+        assert_eq!(health.last_beat_at, None);
+        assert_eq!(watch.check(&health), None);
+
+        clock.advance(chrono::Duration::from_std(interval * missed_beats).unwrap());
+        let health = beat_health(&ctx);
+        assert!(health.overdue);
+        assert_eq!(watch.check(&health), Some(BeatAlertKind::Missed));
+        assert_eq!(watch.check(&beat_health(&ctx)), None);
+        let alert = BeatAlert::new(BeatAlertKind::Missed, health, ctx.now());
+        assert!(alert.render().contains("Last beat: never"));
+
+        ctx.record_beat(ctx.now());
+        let health = beat_health(&ctx);
+        assert_eq!(health.seconds_since_beat, 0);
+        assert_eq!(watch.check(&health), Some(BeatAlertKind::Recovered));
+        assert_eq!(watch.check(&health), None);
+    }
+}
@@ -0,0 +1,329 @@
+use std::{env, path::Path, time::Duration};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use tokio::{select, sync::broadcast::error::RecvError, task::JoinHandle};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::OutboundWebhookConfig,
+    events::IntentEvent,
+    notifications,
+    outbox::{self, Channels},
+    state::AppContext,
+    storage::{self, OutboxMessage, OutboxTarget, WebhookDelivery},
+};
+
+pub const SIGNATURE_HEADER: &str = "x-hi-signature";
+pub const EVENT_HEADER: &str = "x-hi-event";
+
+/// Bound on one delivery attempt, so an unresponsive target cannot hold up
+/// the outbox.
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// HTTP client for webhook deliveries.
+pub fn client() -> anyhow::Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .build()
+        .context("building webhook client")
+}
+
+/// Forward intent lifecycle events to every outbound webhook configured in
+/// `config/webhooks.yml`. Each delivery is queued in the outbox and first
+/// attempted in a task of its own, so a slow target neither holds up the
+/// event stream nor loses events; the outbox worker retries failures.
+/// Returns `None` when no targets are configured.
+pub fn spawn_dispatcher(ctx: AppContext) -> Option<JoinHandle<()>> {
+    if ctx.config().webhooks.outbound.is_empty() {
+        return None;
+    }
+
+    let mut events = ctx.events().subscribe();
+    Some(tokio::spawn(async move {
+        loop {
+            let event = select! {
+                received = events.recv() => match received {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "webhook dispatcher lagged behind intent events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ctx.wait_for_shutdown() => break,
+            };
+
+            let config = ctx.config();
+            let now = ctx.now();
+            for target in config
+                .webhooks
+                .outbound
+                .iter()
+                .filter(|target| subscribes_to(target, &event))
+            {
+                let message = match WebhookEvent::for_intent(&event) {
+                    Ok(webhook) => OutboxMessage::for_target(
+                        webhook.into_target(target.clone()),
+                        &notifications::render(&event),
+                        now,
+                    ),
+                    Err(err) => {
+                        warn!(error = ?err, "failed to encode webhook event");
+                        continue;
+                    }
+                };
+                let data_dir = config.data_dir.clone();
+                let channels = Channels::of(&ctx);
+                tokio::spawn(async move {
+                    let queued = outbox::deliver_or_queue(&data_dir, &channels, message, now).await;
+                    if let Err(err) = queued {
+                        warn!(error = ?err, "failed to queue webhook delivery");
+                    }
+                });
+            }
+        }
+    }))
+}
+
+fn subscribes_to(target: &OutboundWebhookConfig, event: &IntentEvent) -> bool {
+    target.events.is_empty()
+        || target
+            .events
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(event.kind.as_str()))
+}
+
+/// What a webhook posts: `header` is sent as [`EVENT_HEADER`] and `name` is
+/// the event recorded in the delivery log.
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    pub header: String,
+    pub name: String,
+    pub payload: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn for_intent(event: &IntentEvent) -> anyhow::Result<Self> {
+        let name = event.kind.as_str();
+        Ok(Self {
+            id: event.id,
+            header: format!("intent.{name}"),
+            name: name.to_string(),
+            payload: serde_json::to_value(event)?,
+        })
+    }
+
+    /// The outbox target that posts this event to `webhook`.
+    pub fn into_target(self, webhook: OutboundWebhookConfig) -> OutboxTarget {
+        OutboxTarget::Webhook {
+            webhook,
+            event_id: self.id,
+            header: self.header,
+            event: self.name,
+            payload: self.payload,
+        }
+    }
+}
+
+/// POST `event` to `target` once and record the outcome, as the outbox's
+/// `attempt`-th try, in the delivery log of `data_dir` when given.
+pub async fn deliver(
+    client: &Client,
+    data_dir: Option<&Path>,
+    target: &OutboundWebhookConfig,
+    event: &WebhookEvent,
+    attempt: u32,
+    now: DateTime<Utc>,
+) -> WebhookDelivery {
+    let (status, error) = match post(client, target, event).await {
+        Ok(status) => (Some(status), None),
+        Err((status, err)) => (status, Some(err)),
+    };
+    if let Some(error) = &error {
+        warn!(url = %target.url, event = %event.name, attempt, %error, "webhook delivery failed");
+    }
+    let delivery = WebhookDelivery {
+        id: Uuid::new_v4(),
+        event_id: event.id,
+        event: event.name.clone(),
+        url: target.url.clone(),
+        attempts: attempt,
+        delivered: error.is_none(),
+        status,
+        error,
+        timestamp: now,
+    };
+    if let Some(data_dir) = data_dir
+        && let Err(err) = storage::append_webhook_delivery(data_dir, &delivery).await
+    {
+        warn!(error = ?err, "failed to persist webhook delivery log");
+    }
+    delivery
+}
+
+/// The response status of a successful post, or the status (if any) and
+/// error of a failed one.
+async fn post(
+    client: &Client,
+    target: &OutboundWebhookConfig,
+    event: &WebhookEvent,
+) -> Result<u16, (Option<u16>, String)> {
+    let body = serde_json::to_vec(&event.payload).map_err(|err| (None, err.to_string()))?;
+    let mut request = client
+        .post(&target.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &event.header);
+    if let Some(secret_env) = &target.secret_env {
+        let secret =
+            env::var(secret_env).map_err(|err| (None, format!("reading webhook secret: {err}")))?;
+        request = request.header(SIGNATURE_HEADER, sign(&secret, &body));
+    }
+
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| (None, err.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((Some(status.as_u16()), format!("target returned {status}")))
+    }
+}
+
+/// `sha256=<hex>` HMAC of the raw body, matching what `/webhook/generic`
+/// accepts.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::prelude::*;
+    use tempfile::TempDir;
+
+    use crate::{events::IntentEventKind, storage::OutboxStatus, tasks::Intent};
+
+    fn sample_event() -> IntentEvent {
+        let intent = Intent {
+            id: Uuid::new_v4(),
+            source: "cli".to_string(),
+            summary: "Ship it".to_string(),
+            telos_alignment: 0.9,
+            created_at: Utc::now(),
+            due_at: None,
+            metadata: Default::default(),
+            storage_path: None,
+        };
+        let mut event = IntentEvent::new(IntentEventKind::Completed, &intent, Utc::now());
+        event.final_answer = Some("Shipped".to_string());
+        event
+    }
+
+    fn target(url: String, secret_env: Option<&str>) -> OutboundWebhookConfig {
+        OutboundWebhookConfig {
+            url,
+            events: vec!["completed".to_string()],
+            secret_env: secret_env.map(str::to_string),
+            max_attempts: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn delivery_is_signed_and_logged() {
+        let server = MockServer::start_async().await;
+        let event = WebhookEvent::for_intent(&sample_event()).unwrap();
+        let body = serde_json::to_vec(&event.payload).unwrap();
+        unsafe {
+            env::set_var("HI_TEST_OUTBOUND_SECRET", "s3cret");
+        }
+        let signature = sign("s3cret", &body);
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/hook")
+                    .header(SIGNATURE_HEADER, signature.as_str())
+                    .header(EVENT_HEADER, "intent.completed");
+                then.status(204);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let delivery = deliver(
+            &client().unwrap(),
+            Some(temp.path()),
+            &target(server.url("/hook"), Some("HI_TEST_OUTBOUND_SECRET")),
+            &event,
+            1,
+            Utc::now(),
+        )
+        .await;
+
+        mock.assert_async().await;
+        assert!(delivery.delivered);
+        assert_eq!(delivery.attempts, 1);
+        let logged = storage::read_webhook_deliveries(temp.path(), 10).unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].event_id, event.id);
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_by_the_outbox() {
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/hook");
+                then.status(500);
+            })
+            .await;
+
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path();
+        let event = sample_event();
+        let channels = Channels {
+            log_deliveries: true,
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let webhook = WebhookEvent::for_intent(&event).unwrap();
+        let message = OutboxMessage::for_target(
+            webhook.into_target(target(server.url("/hook"), None)),
+            "done",
+            now,
+        );
+
+        // One attempt inline; no retry sleeps in the caller.
+        let queued = outbox::deliver_or_queue(data_dir, &channels, message, now)
+            .await
+            .unwrap();
+        mock.assert_hits_async(1).await;
+        assert_eq!(queued.status, OutboxStatus::Pending);
+
+        let later = now + chrono::Duration::hours(1);
+        assert_eq!(
+            outbox::flush_due(data_dir, &channels, later).await.unwrap(),
+            0
+        );
+        mock.assert_hits_async(2).await;
+        let stored = storage::load_outbox_message(data_dir, queued.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, OutboxStatus::Failed);
+
+        let logged = storage::read_webhook_deliveries(data_dir, 10).unwrap();
+        assert_eq!(logged.len(), 2);
+        assert!(logged.iter().all(|delivery| delivery.status == Some(500)));
+        assert!(subscribes_to(&target(String::new(), None), &event));
+    }
+}
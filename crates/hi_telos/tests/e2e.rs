@@ -3,11 +3,13 @@ use std::{fs, sync::Arc, time::Duration};
 use anyhow::Result;
 use hi_telos::{
     agent::AgentRuntime,
+    clock::{Clock, ManualClock},
     config::AppConfig,
     orchestrator,
     server::{self, ServerState},
     state::AppContext,
-    storage::{self, StructuredContent, StructuredSection},
+    storage::{self, IntentDraft, MemoryLevel, MemoryQuery, StructuredContent, StructuredSection},
+    tasks::{IntentPriority, PERSONA_KEY},
 };
 use reqwest::Client;
 use serde::Deserialize;
@@ -75,15 +77,30 @@ async fn beat_ingests_intent_and_writes_journal() -> Result<()> {
 
     let journal_dir = data_dir.join("journals");
     let journal_files = storage::list_markdown_files(&journal_dir);
-    assert_eq!(journal_files.len(), 1, "one journal entry expected");
-    let journal_content = tokio::fs::read_to_string(&journal_files[0]).await?;
+    assert_eq!(
+        journal_files.len(),
+        2,
+        "one per-intent journal plus the daily index expected",
+    );
+    let intent_journal = journal_files
+        .iter()
+        .find(|path| {
+            path.strip_prefix(&journal_dir)
+                .is_ok_and(|relative| relative.components().count() == 4)
+        })
+        .expect("per-intent journal");
+    let journal_content = tokio::fs::read_to_string(intent_journal).await?;
     assert!(
         journal_content
             .contains("Final answer: TelosOps completed the plan for 'Process inbox intent'"),
         "journal should capture agent final answer",
     );
+    assert!(
+        journal_content.contains("\nDuration: "),
+        "journal should record how long the intent took",
+    );
 
-    let sp_index = storage::load_sp_index(&data_dir).await?;
+    let sp_index = storage::load_sp_index(&data_dir, ctx.now()).await?;
     assert!(
         sp_index
             .top_used
@@ -110,6 +127,23 @@ async fn beat_ingests_intent_and_writes_journal() -> Result<()> {
         "LLM logs should capture prompts",
     );
 
+    let run_id = logs[0].run_id;
+    assert!(
+        journal_content.contains(&format!("Run artifacts: runs/{run_id}/")),
+        "journal should point at the run's artifacts",
+    );
+    let manifest = storage::load_run_manifest(&data_dir, run_id)?.expect("run manifest");
+    assert_eq!(manifest.status, storage::RunStatus::Completed);
+    assert_eq!(manifest.calls.len(), logs.len());
+    let final_call = manifest
+        .calls
+        .iter()
+        .find(|call| call.phase == "FINAL")
+        .expect("final call");
+    let final_prompt =
+        fs::read_to_string(storage::run_dir(&data_dir, run_id).join(&final_call.request))?;
+    assert!(final_prompt.starts_with("# Phase: FINAL"));
+
     ctx.request_shutdown();
     let _ = join.await;
 
@@ -121,6 +155,147 @@ async fn beat_ingests_intent_and_writes_journal() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn beat_stamps_outputs_with_context_clock() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let fixture_root = common::install_core_fixture(tmp.path())?;
+
+    unsafe {
+        std::env::set_var("HI_APP_ROOT", &fixture_root);
+        std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+    }
+
+    // Seconds before midnight: the run's outputs must all land on this day.
+    let now = "2025-03-09T23:59:58Z".parse()?;
+    let clock = Arc::new(ManualClock::new(now));
+    let config = AppConfig::load()?;
+    let agent_runtime = AgentRuntime::from_app_config(&config)?.with_clock(clock.clone());
+    let data_dir = config.data_dir.clone();
+    let ctx = AppContext::new(config, Arc::new(agent_runtime)).with_clock(clock.clone());
+    assert_eq!(ctx.now(), now);
+
+    let (handle, join) = orchestrator::spawn(ctx.clone());
+    sleep(Duration::from_millis(50)).await;
+    handle.request_beat().await?;
+
+    timeout(Duration::from_secs(5), async {
+        while storage::scan_history(&data_dir)?.is_empty() {
+            sleep(Duration::from_millis(50)).await;
+        }
+        // Let the memory snapshot that follows archiving land.
+        sleep(Duration::from_millis(200)).await;
+        Ok::<(), anyhow::Error>(())
+    })
+    .await??;
+    ctx.request_shutdown();
+    let _ = join.await;
+    unsafe {
+        std::env::remove_var("HI_APP_ROOT");
+        std::env::remove_var("HI_SERVER_BIND");
+    }
+
+    let journal_files = storage::list_markdown_files(&data_dir.join("journals/2025/03/09"));
+    assert_eq!(
+        journal_files.len(),
+        1,
+        "journal written under the clock's day"
+    );
+
+    let memories = storage::read_memory_entries(
+        &data_dir,
+        MemoryQuery {
+            level: MemoryLevel::L1,
+            limit: usize::MAX,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(memories.len(), 1);
+    assert_eq!(memories[0].created_at, now);
+
+    let logs = storage::read_llm_logs(&data_dir, storage::LlmLogQuery::default()).await?;
+    assert!(!logs.is_empty());
+    assert!(logs.iter().all(|entry| entry.timestamp == now));
+    assert_eq!(clock.now(), now, "nothing advances a manual clock");
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn source_policies_shape_inbox_triage() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let fixture_root = common::install_core_fixture(tmp.path())?;
+    fs::write(
+        fixture_root.join("config/beat.yml"),
+        "interval_minutes: 5\nintent_threshold: 0.4\napproval:\n  sources: [telegram]\n",
+    )?;
+    fs::write(
+        fixture_root.join("config/sources.yml"),
+        "tester:\n  telos_alignment: 0.1\nTelegram:\n  priority: high\n  persona: writer\n  auto_approve: true\n",
+    )?;
+
+    unsafe {
+        std::env::set_var("HI_APP_ROOT", &fixture_root);
+        std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+    }
+
+    let config = AppConfig::load()?;
+    let agent_runtime = AgentRuntime::from_app_config(&config)?;
+    let data_dir = config.data_dir.clone();
+    let telegram = storage::persist_intent(
+        &data_dir,
+        &IntentDraft {
+            source: "telegram".to_string(),
+            summary: "Draft the changelog".to_string(),
+            telos_alignment: 1.0,
+            ..IntentDraft::default()
+        },
+    )
+    .await?;
+    let ctx = AppContext::new(config, Arc::new(agent_runtime));
+
+    let (handle, join) = orchestrator::spawn(ctx.clone());
+    sleep(Duration::from_millis(50)).await;
+    handle.request_beat().await?;
+
+    let archived = timeout(Duration::from_secs(5), async {
+        loop {
+            let history = storage::scan_history(&data_dir)?;
+            if !history.is_empty() {
+                return Ok::<_, anyhow::Error>(history);
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await??;
+    ctx.request_shutdown();
+    let _ = join.await;
+    unsafe {
+        std::env::remove_var("HI_APP_ROOT");
+        std::env::remove_var("HI_SERVER_BIND");
+    }
+
+    // Auto-approved despite `beat.approval.sources`, with the policy's defaults.
+    assert_eq!(archived.len(), 1);
+    let intent = &archived[0].intent;
+    assert_eq!(intent.id, telegram.id);
+    assert_eq!(intent.priority(), IntentPriority::High);
+    assert_eq!(
+        intent.metadata.get(PERSONA_KEY).map(String::as_str),
+        Some("writer")
+    );
+    assert!(storage::scan_pending_approval(&data_dir)?.is_empty());
+
+    // The fixture intent's 0.9 alignment was replaced and now falls below
+    // the threshold.
+    let fixture_id = "00000000-0000-0000-0000-000000000001".parse()?;
+    let deferred = storage::find_deferred_intent(&data_dir, fixture_id)?.expect("deferred intent");
+    assert_eq!(deferred.intent.telos_alignment, 0.1);
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 struct TextStructurePreview {
     title: String,
@@ -0,0 +1,213 @@
+//! Runs every `tests/scenarios/*.yml` end to end: the listed intents go into
+//! the inbox, one beat drives them through the orchestrator against a
+//! [`ScriptedLlmClient`], and the resulting journals, memory and intent
+//! stages are checked against the scenario's `expect` block.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use hi_telos::{
+    agent::AgentRuntime,
+    config::AppConfig,
+    llm::{LlmScript, ScriptedLlmClient},
+    orchestrator,
+    state::AppContext,
+    storage::{self, IntentDraft, MemoryLevel, MemoryQuery},
+};
+use serde::Deserialize;
+use serial_test::serial;
+use tempfile::TempDir;
+use tokio::time::{Instant, sleep};
+
+mod common;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scenario {
+    /// Written verbatim to `config/agent.yml`.
+    #[serde(default)]
+    agent: Option<serde_yaml::Value>,
+    intents: Vec<ScenarioIntent>,
+    llm: LlmScript,
+    expect: Expectations,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScenarioIntent {
+    summary: String,
+    #[serde(default = "default_alignment")]
+    telos_alignment: f32,
+    #[serde(default = "default_source")]
+    source: String,
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Expectations {
+    history: usize,
+    failed: usize,
+    /// Intents paused on an `ask_user` question.
+    waiting: usize,
+    /// Each needle must appear in some per-intent journal.
+    journal_contains: Vec<String>,
+    /// Each needle must appear in some L1 memory summary or detail.
+    memory_contains: Vec<String>,
+    llm_calls: Option<usize>,
+}
+
+fn default_alignment() -> f32 {
+    0.9
+}
+
+fn default_source() -> String {
+    "scenario".to_string()
+}
+
+fn scenario_files() -> Result<Vec<PathBuf>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scenarios");
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .with_context(|| format!("reading {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yml"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+async fn run_scenario(path: &Path) -> Result<()> {
+    let scenario: Scenario = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+    let tmp = TempDir::new()?;
+    let root = common::install_core_fixture(tmp.path())?;
+    // Only the scenario's own intents should run.
+    fs::remove_dir_all(root.join("data/intent/inbox"))?;
+    if let Some(agent) = &scenario.agent {
+        fs::write(root.join("config/agent.yml"), serde_yaml::to_string(agent)?)?;
+    }
+
+    unsafe {
+        std::env::set_var("HI_APP_ROOT", &root);
+        std::env::set_var("HI_SERVER_BIND", "127.0.0.1:0");
+    }
+    let config = AppConfig::load()?;
+    let data_dir = config.data_dir.clone();
+    storage::ensure_data_layout(&data_dir)?;
+    for intent in &scenario.intents {
+        storage::persist_intent(
+            &data_dir,
+            &IntentDraft {
+                source: intent.source.clone(),
+                summary: intent.summary.clone(),
+                telos_alignment: intent.telos_alignment,
+                body: intent.body.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    }
+
+    let llm = Arc::new(ScriptedLlmClient::new(scenario.llm.clone()));
+    let agent = AgentRuntime::new(config.agent.clone(), llm.clone());
+    let ctx = AppContext::new(config, Arc::new(agent));
+    let (handle, join) = orchestrator::spawn(ctx.clone());
+    sleep(Duration::from_millis(50)).await;
+    handle.request_beat().await?;
+
+    let settled = wait_for_beat(&ctx, &data_dir).await;
+    ctx.request_shutdown();
+    let _ = join.await;
+    unsafe {
+        std::env::remove_var("HI_APP_ROOT");
+        std::env::remove_var("HI_SERVER_BIND");
+    }
+    settled?;
+
+    check(&scenario.expect, &data_dir, &llm)
+}
+
+/// The beat is done once the inbox and queue are empty and it has released
+/// the beat gate.
+async fn wait_for_beat(ctx: &AppContext, data_dir: &Path) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(15);
+    loop {
+        let drained =
+            storage::scan_inbox(data_dir)?.is_empty() && storage::scan_queue(data_dir)?.is_empty();
+        if drained && ctx.beat_gate().try_lock().is_ok() {
+            return Ok(());
+        }
+        if Instant::now() > deadline {
+            bail!("beat did not settle in time");
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn check(expect: &Expectations, data_dir: &Path, llm: &ScriptedLlmClient) -> Result<()> {
+    let history = storage::scan_history(data_dir)?.len();
+    ensure!(history == expect.history, "history: {history} intents");
+    let failed = storage::list_stage_intents(data_dir, "failed", chrono::Utc::now())?
+        .unwrap_or_default()
+        .len();
+    ensure!(failed == expect.failed, "failed: {failed} intents");
+    let waiting = storage::list_pending_questions(data_dir)?.len();
+    ensure!(waiting == expect.waiting, "waiting: {waiting} intents");
+
+    let journals: Vec<String> = storage::list_markdown_files(&data_dir.join("journals"))
+        .iter()
+        .map(fs::read_to_string)
+        .collect::<Result<_, _>>()?;
+    for needle in &expect.journal_contains {
+        ensure!(
+            journals.iter().any(|journal| journal.contains(needle)),
+            "no journal contains {needle:?}"
+        );
+    }
+
+    let memories = storage::read_memory_entries(
+        data_dir,
+        MemoryQuery {
+            level: MemoryLevel::L1,
+            limit: usize::MAX,
+            ..Default::default()
+        },
+    )?;
+    for needle in &expect.memory_contains {
+        ensure!(
+            memories.iter().any(|entry| {
+                entry.summary.contains(needle)
+                    || entry.details.iter().any(|detail| detail.contains(needle))
+            }),
+            "no memory contains {needle:?}"
+        );
+    }
+
+    if let Some(calls) = expect.llm_calls {
+        let made = llm.prompts().len();
+        ensure!(made == calls, "llm calls: {made}");
+    }
+    let unused = llm.unused();
+    ensure!(
+        unused.is_empty(),
+        "scripted responses never used: {unused:?}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn scripted_scenarios_pass() -> Result<()> {
+    let files = scenario_files()?;
+    assert!(!files.is_empty(), "no scenarios found");
+    for path in files {
+        run_scenario(&path)
+            .await
+            .with_context(|| format!("scenario {:?}", path.file_name().unwrap_or_default()))?;
+    }
+    Ok(())
+}